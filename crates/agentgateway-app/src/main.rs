@@ -2,11 +2,17 @@
 
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
-use agent_core::{strng, telemetry, version};
+use agent_core::{durfmt, strng, telemetry, version};
+use agentgateway::bench;
+use agentgateway::http::{Method, Uri};
 use agentgateway::types::agent::ListenerTarget;
+use agentgateway::types::local::{
+	NormalizedLocalConfig, ValidateOptions, ValidationError, ValidationErrorKind,
+};
 use agentgateway::{BackendConfig, Config, LoggingFormat, client, serdes};
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 use tracing::info;
 
 #[cfg(feature = "jemalloc")]
@@ -32,6 +38,9 @@ lazy_static::lazy_static! {
 #[command(about, long_about = None)]
 #[command(disable_version_flag = true)]
 struct Args {
+	#[command(subcommand)]
+	command: Option<Command>,
+
 	/// Use config from bytes
 	#[arg(short, long, value_name = "config")]
 	config: Option<String>,
@@ -40,6 +49,7 @@ struct Args {
 	#[arg(short, long, value_name = "file")]
 	file: Option<PathBuf>,
 
+	/// Validate the config and exit. Prefer the `validate` command, which can report errors as JSON.
 	#[arg(long, value_name = "validate-only")]
 	validate_only: bool,
 
@@ -56,9 +66,88 @@ struct Args {
 	copy_self: Option<PathBuf>,
 }
 
+#[derive(Subcommand, Debug)]
+enum Command {
+	/// Validate a config file, reporting every error found. Exits non-zero if it is invalid.
+	Validate {
+		/// Config file to validate
+		#[arg(short, long, value_name = "file")]
+		file: PathBuf,
+
+		/// How to report errors
+		#[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+		output: OutputFormat,
+	},
+	/// Print the JSON Schema of the config file, for editor completion and external validation.
+	#[cfg(feature = "schema")]
+	Schema,
+	/// Send synthetic HTTP or MCP traffic at a target and report latency percentiles. Exits non-zero
+	/// if a threshold is exceeded.
+	Bench(Box<BenchArgs>),
+}
+
+#[derive(clap::Args, Debug)]
+struct BenchArgs {
+	/// URL to send requests to
+	#[arg(long, value_name = "url")]
+	target: Uri,
+
+	/// Config file of a gateway to run in-process for the duration of the benchmark
+	#[arg(short, long, value_name = "file")]
+	file: Option<PathBuf>,
+
+	/// How many requests are in flight at once
+	#[arg(long, default_value_t = 10)]
+	connections: usize,
+
+	/// Total requests per second, across connections. If unset, requests are sent as fast as possible.
+	#[arg(long)]
+	rps: Option<f64>,
+
+	/// How long to send traffic for
+	#[arg(long, default_value = "10s", value_parser = durfmt::parse)]
+	duration: Duration,
+
+	/// HTTP method of plain HTTP requests
+	#[arg(long, default_value = "GET")]
+	method: Method,
+
+	/// Body size of plain HTTP requests, in bytes
+	#[arg(long, default_value_t = 0)]
+	payload_size: usize,
+
+	/// Call an MCP tool, as `name` or `name:weight`, instead of sending plain HTTP requests. Repeat to
+	/// call a mix of tools in proportion to their weights.
+	#[arg(long = "tool", value_name = "name[:weight]")]
+	tools: Vec<bench::Tool>,
+
+	/// JSON arguments of each tool call
+	#[arg(long, default_value = "{}", value_parser = parse_json)]
+	tool_arguments: serde_json::Value,
+
+	/// How to report results
+	#[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+	output: OutputFormat,
+
+	/// Fail if the p99 latency is above this
+	#[arg(long, value_parser = durfmt::parse)]
+	max_p99: Option<Duration>,
+
+	/// Fail if the fraction of failed requests is above this
+	#[arg(long)]
+	max_error_rate: Option<f64>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum OutputFormat {
+	Text,
+	Json,
+}
+
 fn main() -> anyhow::Result<()> {
 	let args = Args::parse();
 	let Args {
+		command,
 		config,
 		file,
 		validate_only,
@@ -78,11 +167,22 @@ fn main() -> anyhow::Result<()> {
 	if let Some(copy_self) = copy_self {
 		return copy_binary(copy_self);
 	}
+	#[cfg(feature = "schema")]
+	if let Some(Command::Schema) = command {
+		return print_schema();
+	}
+	if let Some(Command::Bench(args)) = command {
+		return run_bench(*args);
+	}
 	tokio::runtime::Builder::new_current_thread()
 		.enable_all()
 		.build()
 		.unwrap()
 		.block_on(async move {
+			if let Some(Command::Validate { file, output }) = command {
+				let contents = fs_err::read_to_string(&file)?;
+				return validate(contents, Some(file), output).await;
+			}
 			let (contents, filename) = match (config, file) {
 				(Some(_), Some(_)) => {
 					anyhow::bail!("only one of --config or --file")
@@ -95,7 +195,7 @@ fn main() -> anyhow::Result<()> {
 				(None, None) => ("{}".to_string(), None),
 			};
 			if validate_only {
-				return validate(contents, filename).await;
+				return validate(contents, filename, OutputFormat::Text).await;
 			}
 			let mut config = agentgateway::config::parse_config(contents, filename)?;
 			// Capture the admin/runtime handle to ensure some background tasks (e.g., OTLP exporters created from dataplane
@@ -128,28 +228,195 @@ fn copy_binary(copy_self: PathBuf) -> anyhow::Result<()> {
 	return Ok(());
 }
 
-async fn validate(contents: String, filename: Option<PathBuf>) -> anyhow::Result<()> {
-	let config = agentgateway::config::parse_config(contents, filename)?;
-	let client = client::Client::new(&config.dns, None, BackendConfig::default(), None);
-	if let Some(cfg) = config.xds.local_config {
-		let cs = cfg.read_to_string().await?;
-		agentgateway::types::local::NormalizedLocalConfig::from(
-			client,
-			ListenerTarget {
-				gateway_name: strng::literal!("default"),
-				gateway_namespace: strng::literal!("default"),
-				listener_name: None,
-			},
-			cs.as_str(),
-		)
-		.await?;
-	} else {
-		println!("No local configuration");
+#[cfg(feature = "schema")]
+fn print_schema() -> anyhow::Result<()> {
+	let schema = agentgateway::serdes::json_schema::<agentgateway::types::local::LocalConfig>()?;
+	println!("{schema}");
+	Ok(())
+}
+
+async fn validate(
+	contents: String,
+	filename: Option<PathBuf>,
+	output: OutputFormat,
+) -> anyhow::Result<()> {
+	let errors = match validation_errors(contents, filename).await {
+		Some(errors) => errors,
+		None => {
+			if let OutputFormat::Text = output {
+				println!("No local configuration");
+			}
+			vec![]
+		},
+	};
+	match output {
+		OutputFormat::Text if errors.is_empty() => println!("Configuration is valid!"),
+		OutputFormat::Text => {
+			for e in &errors {
+				eprintln!("error: {e}");
+			}
+			eprintln!("Configuration is invalid: {} errors", errors.len());
+		},
+		OutputFormat::Json => println!(
+			"{}",
+			serde_json::to_string_pretty(&serde_json::json!({
+				"valid": errors.is_empty(),
+				"errors": errors,
+			}))?
+		),
+	}
+	if !errors.is_empty() {
+		std::process::exit(1);
 	}
-	println!("Configuration is valid!");
 	Ok(())
 }
 
+/// Validate the config, returning the errors found, or None if there is no local configuration.
+async fn validation_errors(
+	contents: String,
+	filename: Option<PathBuf>,
+) -> Option<Vec<ValidationError>> {
+	let invalid = |e: anyhow::Error| {
+		Some(vec![ValidationError {
+			kind: ValidationErrorKind::Invalid,
+			location: None,
+			message: format!("{e:#}"),
+		}])
+	};
+	let config = match agentgateway::config::parse_config(contents, filename) {
+		Ok(config) => config,
+		Err(e) => return invalid(e),
+	};
+	let client = client::Client::new(&config.dns, None, BackendConfig::default(), None);
+	let cfg = config.xds.local_config?;
+	let cs = match cfg.read_to_string().await {
+		Ok(cs) => cs,
+		Err(e) => return invalid(e),
+	};
+	let result = NormalizedLocalConfig::validate(
+		client,
+		ListenerTarget {
+			gateway_name: strng::literal!("default"),
+			gateway_namespace: strng::literal!("default"),
+			listener_name: None,
+		},
+		cs.as_str(),
+		ValidateOptions {
+			services_from_xds: config.xds.address.is_some(),
+			interpolate: true,
+		},
+	)
+	.await;
+	Some(result.err().unwrap_or_default())
+}
+
+fn parse_json(s: &str) -> Result<serde_json::Value, serde_json::Error> {
+	serde_json::from_str(s)
+}
+
+fn run_bench(args: BenchArgs) -> anyhow::Result<()> {
+	// Generating load needs more than the single thread the other commands run on.
+	tokio::runtime::Builder::new_multi_thread()
+		.enable_all()
+		.build()
+		.unwrap()
+		.block_on(async move {
+			let _gateway = match &args.file {
+				Some(file) => {
+					let contents = fs_err::read_to_string(file)?;
+					let mut config = agentgateway::config::parse_config(contents, Some(file.clone()))?;
+					config.admin_runtime_handle = Some(tokio::runtime::Handle::current());
+					Some(agentgateway::app::run(Arc::new(config)).await?)
+				},
+				None => None,
+			};
+			let config = agentgateway::config::parse_config("{}".to_string(), None)?;
+			let client = client::Client::new(&config.dns, None, BackendConfig::default(), None);
+			bench::wait_ready(&client, &args.target, Duration::from_secs(10)).await?;
+
+			let workload = if args.tools.is_empty() {
+				bench::Workload::Http {
+					method: args.method,
+					payload_size: args.payload_size,
+				}
+			} else {
+				bench::Workload::Mcp {
+					tools: args.tools,
+					arguments: args.tool_arguments,
+				}
+			};
+			let report = bench::run(
+				client,
+				bench::Options {
+					target: args.target,
+					connections: args.connections,
+					rps: args.rps,
+					duration: args.duration,
+					workload,
+				},
+			)
+			.await?;
+
+			match args.output {
+				OutputFormat::Text => print_bench_report(&report),
+				OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+			}
+			let mut failed = false;
+			if let Some(max) = args.max_p99
+				&& report.total.p99 > max
+			{
+				eprintln!("p99 latency {:?} is above {max:?}", report.total.p99);
+				failed = true;
+			}
+			if let Some(max) = args.max_error_rate
+				&& report.error_rate() > max
+			{
+				eprintln!("error rate {:.4} is above {max}", report.error_rate());
+				failed = true;
+			}
+			if failed {
+				std::process::exit(1);
+			}
+			Ok(())
+		})
+}
+
+fn print_bench_report(report: &bench::Report) {
+	println!(
+		"{} requests in {:.2?}, {:.1} requests/s, {} errors",
+		report.total.requests, report.duration, report.throughput, report.total.errors
+	);
+	println!(
+		"{:<24} {:>10} {:>8} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+		"operation", "requests", "errors", "mean", "p50", "p90", "p99", "p99.9", "max"
+	);
+	let rows = report
+		.operations
+		.iter()
+		.map(|(op, s)| (op.as_str(), s))
+		.chain(std::iter::once(("total", &report.total)));
+	for (op, s) in rows {
+		println!(
+			"{:<24} {:>10} {:>8} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+			op,
+			s.requests,
+			s.errors,
+			durfmt::format(s.mean),
+			durfmt::format(s.p50),
+			durfmt::format(s.p90),
+			durfmt::format(s.p99),
+			durfmt::format(s.p999),
+			durfmt::format(s.max),
+		);
+	}
+	let statuses: Vec<String> = report
+		.statuses
+		.iter()
+		.map(|(status, n)| format!("{status}={n}"))
+		.collect();
+	println!("statuses: {}", statuses.join(" "));
+}
+
 async fn proxy(cfg: Arc<Config>) -> anyhow::Result<()> {
 	info!("version: {}", version::BuildInfo::new());
 	info!(
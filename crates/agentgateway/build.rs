@@ -4,10 +4,12 @@ fn main() -> Result<(), anyhow::Error> {
 	let proto_files = [
 		"proto/ext_proc.proto",
 		"proto/ext_authz.proto",
+		"proto/als.proto",
 		"proto/rls.proto",
 		"proto/resource.proto",
 		"proto/workload.proto",
 		"proto/citadel.proto",
+		"proto/reflection.proto",
 	]
 	.iter()
 	.map(|name| std::env::current_dir().unwrap().join(name))
@@ -8,6 +8,7 @@ use prometheus_client::registry::Registry;
 use tokio::task::JoinSet;
 
 use crate::control::caclient;
+use crate::telemetry::events::{self, EventKind};
 use crate::telemetry::trc;
 use crate::telemetry::trc::Tracer;
 use crate::{Config, ProxyInputs, client, mcp, proxy, state_manager};
@@ -25,6 +26,10 @@ pub async fn run(config: Arc<Config>) -> anyhow::Result<Bound> {
 	})?;
 	// Initialize OpenTelemetry resource defaults from gateway + proxy metadata
 	trc::set_resource_defaults_from_config(config.as_ref());
+	if let Some(otlp) = &config.logging.otlp {
+		crate::telemetry::logexport::init(otlp, &config.logging.level).context("log export")?;
+	}
+	crate::features::init(&config.features);
 	let shutdown = signal::Shutdown::new();
 	// Setup a drain channel. drain_tx is used to trigger a drain, which will complete
 	// once all drain_rx handlers are dropped.
@@ -32,6 +37,10 @@ pub async fn run(config: Arc<Config>) -> anyhow::Result<Bound> {
 	// await drain_rx.signaled(), then cleanup.
 	// Note: there is still a hard timeout if the draining takes too long
 	let (drain_tx, drain_rx) = drain::new();
+	// The management servers are drained separately, once the gateway exits, so they keep
+	// reporting progress and streaming events while the data plane drains.
+	let (management_drain_tx, management_drain_rx) = drain::new();
+	let shutdown_progress = crate::shutdown::Progress::default();
 
 	let ready = readiness::Ready::new();
 	let state_mgr_task = ready.register_task("state manager");
@@ -39,8 +48,9 @@ pub async fn run(config: Arc<Config>) -> anyhow::Result<Bound> {
 
 	let readiness_server = crate::management::readiness_server::Server::new(
 		config.readiness_addr,
-		drain_rx.clone(),
+		management_drain_rx.clone(),
 		ready.clone(),
+		shutdown_progress.clone(),
 	)
 	.await
 	.context("readiness server starts")?;
@@ -74,9 +84,33 @@ pub async fn run(config: Arc<Config>) -> anyhow::Result<Bound> {
 	// Build metrics and then the upstream client with metrics wired in
 	let sub_registry = metrics::sub_registry(&mut registry);
 	let tracer = trc::Tracer::new(&config.tracing)?.map(Arc::new);
+	if let Some(audit) = &config.audit_log {
+		crate::telemetry::audit::init(audit).context("audit log")?;
+	}
+	if let Some(access_log) = &config.access_log {
+		let node = crate::telemetry::als::proto::Node {
+			id: config.proxy_metadata.node_id.clone(),
+			cluster: config.xds.gateway.to_string(),
+		};
+		crate::telemetry::accesslog::init(access_log, node).context("access log")?;
+	}
+	if let Some(slow_request_log) = &config.slow_request_log {
+		crate::telemetry::slowlog::init(slow_request_log).context("slow request log")?;
+	}
+	if let Some(llm_log) = &config.llm_log {
+		crate::telemetry::llmlog::init(llm_log).context("llm log")?;
+	}
+	if let Some(costs) = &config.costs {
+		crate::telemetry::costs::init(costs).context("costs")?;
+	}
+	if let Some(slos) = &config.slos {
+		crate::telemetry::slo::init(slos).context("slos")?;
+		crate::telemetry::slo::SloCollector::register(sub_registry);
+	}
 	let metrics_handle = Arc::new(crate::metrics::Metrics::new(
 		sub_registry,
 		config.logging.excluded_metrics.clone(),
+		config.logging.histogram_buckets.clone(),
 	));
 	let client = client::Client::new(
 		&config.dns,
@@ -84,6 +118,26 @@ pub async fn run(config: Arc<Config>) -> anyhow::Result<Bound> {
 		config.backend.clone(),
 		Some(metrics_handle.clone()),
 	);
+	client::PoolCollector::register(sub_registry, client.clone());
+	crate::http::bufpool::BufferPoolCollector::register(sub_registry);
+	crate::telemetry::events::EventsCollector::register(sub_registry);
+	if let Some(memory) = config.memory.clone() {
+		crate::memory::spawn(memory);
+		crate::memory::MemoryCollector::register(sub_registry);
+	}
+	if let Some(overload) = config.overload.clone() {
+		crate::overload::spawn(overload, &data_plane_handle);
+		crate::overload::OverloadCollector::register(sub_registry);
+	}
+
+	let encryption = match &config.encryption {
+		Some(e) => Some(Arc::new(
+			e.build(&crate::secrets::Resolver::new(control_client.clone()))
+				.await
+				.context("encryption at rest")?,
+		)),
+		None => None,
+	};
 
 	let (xds_tx, xds_rx) = tokio::sync::watch::channel(());
 	let state_mgr =
@@ -100,7 +154,8 @@ pub async fn run(config: Arc<Config>) -> anyhow::Result<Bound> {
 	tokio::spawn(state_mgr.run());
 
 	let ledger_path = std::path::PathBuf::from("/home/toxic/development/tool-mesh/mesh-ledger.log");
-	let mesh_registry = MeshRegistry::new(stores.clone(), ledger_path);
+	let mesh_registry = MeshRegistry::new(stores.clone(), ledger_path, encryption.clone());
+	crate::management::mesh::MeshCollector::register(sub_registry, &mesh_registry);
 
 	// Immortality: Git Watch Sync Loop
 	tokio::spawn(async move {
@@ -120,14 +175,29 @@ pub async fn run(config: Arc<Config>) -> anyhow::Result<Bound> {
 		}
 	});
 
+	let quotas = Arc::new(
+		crate::http::quota::QuotaStore::new(config.quota.clone(), encryption.clone())
+			.context("quota usage loads")?,
+	);
+	tokio::spawn(quotas.clone().run());
+
+	// Shared by the metrics server, the admin metrics stream, and the StatsD exporter.
+	let registry = Arc::new(std::sync::Mutex::new(registry));
+	if let Some(statsd) = config.statsd.clone() {
+		crate::telemetry::statsd::spawn(statsd, registry.clone()).context("statsd")?;
+	}
+
 	#[allow(unused_mut)]
 	let mut admin_server = crate::management::admin::Service::new(
 		config.clone(),
 		stores.clone(),
 		shutdown.trigger(),
-		drain_rx.clone(),
+		management_drain_rx.clone(),
 		data_plane_handle.clone(),
-		mesh_registry,
+		mesh_registry.clone(),
+		quotas.clone(),
+		client.clone(),
+		registry.clone(),
 	)
 	.await
 	.context("admin server starts")?;
@@ -136,6 +206,7 @@ pub async fn run(config: Arc<Config>) -> anyhow::Result<Bound> {
 	#[cfg(feature = "ui")]
 	info!("serving UI at http://{}/ui", config.admin_addr);
 
+	let mcp_state = mcp::App::new(stores.clone(), config.session_encoder.clone());
 	let pi = ProxyInputs {
 		cfg: config.clone(),
 		stores: stores.clone(),
@@ -143,11 +214,19 @@ pub async fn run(config: Arc<Config>) -> anyhow::Result<Bound> {
 		metrics: metrics_handle.clone(),
 		upstream: client.clone(),
 		ca,
-
-		mcp_state: mcp::App::new(stores.clone(), config.session_encoder.clone()),
+		quotas: quotas.clone(),
+		mcp_state: mcp_state.clone(),
 	};
 
-	let gw = proxy::Gateway::new(Arc::new(pi), drain_rx.clone());
+	let pi = Arc::new(pi);
+	if let Some(probes) = config.probes.clone() {
+		proxy::prober::spawn(probes, pi.clone()).context("probes")?;
+	}
+	if let Some(export) = config.costs.as_ref().and_then(|c| c.export.clone()) {
+		crate::telemetry::usage_export::spawn(export, pi.clone()).context("usage export")?;
+	}
+
+	let gw = proxy::Gateway::new(pi, drain_rx.clone());
 
 	// Run the agentgateway in the data plane worker pool.
 	let mut xds_rx_for_proxy = xds_rx.clone();
@@ -168,41 +247,123 @@ pub async fn run(config: Arc<Config>) -> anyhow::Result<Bound> {
 	admin_server.spawn();
 
 	// Create and start the metrics server.
-	let metrics_server =
-		crate::management::metrics_server::Server::new(config.stats_addr, drain_rx.clone(), registry)
-			.await
-			.context("stats server starts")?;
+	let metrics_server = crate::management::metrics_server::Server::new(
+		config.stats_addr,
+		management_drain_rx,
+		registry,
+	)
+	.await
+	.context("stats server starts")?;
 	// Run the metrics sever in the current tokio worker pool.
 	metrics_server.spawn();
 	Ok(Bound {
 		drain_tx,
+		management_drain_tx,
 		shutdown,
+		shutdown_config: config.shutdown,
+		shutdown_progress,
+		ready,
 		tracer,
+		quotas,
+		mesh_registry,
+		mcp_state,
 	})
 }
 
 pub struct Bound {
 	pub shutdown: signal::Shutdown,
 	drain_tx: drain::DrainTrigger,
+	management_drain_tx: drain::DrainTrigger,
+	shutdown_config: crate::shutdown::Config,
+	shutdown_progress: crate::shutdown::Progress,
+	ready: readiness::Ready,
 	tracer: Option<Arc<Tracer>>,
+	quotas: Arc<crate::http::quota::QuotaStore>,
+	mesh_registry: MeshRegistry,
+	mcp_state: mcp::App,
 }
 
 impl Bound {
 	pub async fn wait_termination(self) -> anyhow::Result<()> {
+		use crate::shutdown::Phase;
+
 		// Wait for a signal to shutdown from explicit admin shutdown or signal
 		self.shutdown.wait().await;
+		let cfg = self.shutdown_config;
+		let progress = self.shutdown_progress;
+
+		// Report not ready, so load balancers stop sending new connections, and start a drain; binds
+		// keep accepting for `stop_accepting`, while discouraging connection reuse, and then stop.
+		progress.enter(Phase::StopAccepting);
+		let _not_ready = self.ready.register_task("shutdown");
+		let (drained_tx, drained) = tokio::sync::watch::channel(false);
+		let drain_tx = self.drain_tx;
+		tokio::spawn(async move {
+			drain_tx
+				.start_drain_and_wait(drain::DrainMode::Graceful)
+				.await;
+			let _ = drained_tx.send(true);
+		});
+		let wait_drained = || {
+			let mut drained = drained.clone();
+			async move {
+				let _ = drained.wait_for(|d| *d).await;
+			}
+		};
+		tokio::select! {
+			_ = tokio::time::sleep(cfg.stop_accepting) => {},
+			_ = wait_drained() => {},
+		}
+
+		// Tell mesh nodes and event stream subscribers, whose streams then end.
+		progress
+			.run(Phase::Notify, cfg.notify, async {
+				events::publish(EventKind::ShuttingDown {
+					deadline: cfg.connection_deadline(),
+				});
+				while events::subscriptions() > 0 {
+					tokio::time::sleep(Duration::from_millis(50)).await;
+				}
+			})
+			.await;
+
+		// Wait for in-flight requests. Binds forcefully close connections still open once all
+		// phases serving connections have passed.
+		let complete = progress
+			.run(Phase::DrainHttp, cfg.http, wait_drained())
+			.await;
 
-		if let Some(tracer) = self.tracer {
-			tracer.shutdown()
+		// MCP sessions stream until the client ends them, so close the ones still open.
+		if !complete {
+			let sessions = self.mcp_state.close_sessions();
+			info!(sessions, "closing MCP sessions");
+			progress
+				.run(Phase::DrainMcp, cfg.mcp, wait_drained())
+				.await;
 		}
 
-		// Start a drain; this will attempt to end all connections
-		// or itself be interrupted by a stronger TERM signal, whichever comes first.
-		self
-			.drain_tx
-			.start_drain_and_wait(drain::DrainMode::Graceful)
+		let quotas = self.quotas;
+		let mesh_registry = self.mesh_registry;
+		let tracer = self.tracer;
+		progress
+			.run(Phase::Flush, cfg.flush, async move {
+				if let Err(e) = quotas.flush() {
+					warn!("failed to persist quota usage: {e}");
+				}
+				mesh_registry.flush().await;
+				// Exporters block until their buffers are sent.
+				let _ = tokio::task::spawn_blocking(move || {
+					if let Some(tracer) = tracer {
+						tracer.shutdown()
+					}
+					agent_core::telemetry::logexport::shutdown();
+				})
+				.await;
+			})
 			.await;
 
+		progress.enter(Phase::Exit);
+		drop(self.management_drain_tx);
 		Ok(())
 	}
 }
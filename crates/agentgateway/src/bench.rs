@@ -0,0 +1,400 @@
+//! Synthetic load generation, for `agentgateway bench`.
+//!
+//! Each connection runs its own loop, sending a request as soon as the previous one completes, or
+//! at a fixed rate if one is set. Plain HTTP requests carry a body of a fixed size; MCP workloads
+//! open a session per connection and call tools in proportion to their weight.
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use bytes::Bytes;
+use http_body_util::BodyExt;
+use serde_json::Value;
+use tokio::task::JoinSet;
+use tokio::time::{Instant, MissedTickBehavior};
+
+use crate::client::Client;
+use crate::http::{Body, HeaderValue, Method, Request, StatusCode, Uri, header};
+use crate::*;
+
+#[cfg(test)]
+#[path = "bench_tests.rs"]
+mod tests;
+
+const MCP_SESSION_ID: &str = "mcp-session-id";
+const MCP_PROTOCOL_VERSION: &str = "2025-06-18";
+
+#[derive(Debug, Clone)]
+pub struct Options {
+	/// The URL requests are sent to.
+	pub target: Uri,
+	/// How many requests are in flight at once.
+	pub connections: usize,
+	/// The request rate across all connections. If unset, requests are sent as fast as they complete.
+	pub rps: Option<f64>,
+	pub duration: Duration,
+	pub workload: Workload,
+}
+
+#[derive(Debug, Clone)]
+pub enum Workload {
+	/// Plain HTTP requests, with a body of `payload_size` bytes.
+	Http { method: Method, payload_size: usize },
+	/// MCP tool calls, each with the same arguments.
+	Mcp { tools: Vec<Tool>, arguments: Value },
+}
+
+/// An MCP tool to call, and how often relative to the others.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tool {
+	pub name: String,
+	pub weight: u32,
+}
+
+impl FromStr for Tool {
+	type Err = anyhow::Error;
+
+	/// Parse `name` or `name:weight`.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (name, weight) = match s.rsplit_once(':') {
+			Some((name, weight)) => (name, weight.parse::<u32>()?),
+			None => (s, 1),
+		};
+		if name.is_empty() || weight == 0 {
+			anyhow::bail!("tool must be `name` or `name:weight`, with a positive weight");
+		}
+		Ok(Tool {
+			name: name.to_string(),
+			weight,
+		})
+	}
+}
+
+impl Workload {
+	/// The names of the operations samples are grouped by.
+	fn operations(&self) -> Vec<String> {
+		match self {
+			Workload::Http { method, .. } => vec![method.to_string()],
+			Workload::Mcp { tools, .. } => tools.iter().map(|t| t.name.clone()).collect(),
+		}
+	}
+
+	/// The `n`th request of a connection, and the index of its operation.
+	fn request(&self, target: &Uri, session: Option<&str>, n: usize) -> (usize, Request) {
+		match self {
+			Workload::Http {
+				method,
+				payload_size,
+			} => {
+				let body = match payload_size {
+					0 => Body::empty(),
+					n => Body::from(Bytes::from(vec![b'x'; *n])),
+				};
+				let mut req = Request::new(body);
+				*req.method_mut() = method.clone();
+				*req.uri_mut() = target.clone();
+				(0, req)
+			},
+			Workload::Mcp { tools, arguments } => {
+				let op = pick(tools, n);
+				let body = serde_json::json!({
+					"jsonrpc": "2.0",
+					"id": n,
+					"method": "tools/call",
+					"params": {"name": tools[op].name, "arguments": arguments},
+				});
+				(op, mcp_request(target, session, &body))
+			},
+		}
+	}
+}
+
+/// Pick the tool of the `n`th call, so each tool is called in proportion to its weight.
+fn pick(tools: &[Tool], n: usize) -> usize {
+	let total: usize = tools.iter().map(|t| t.weight as usize).sum();
+	let mut slot = n % total;
+	for (i, t) in tools.iter().enumerate() {
+		if slot < t.weight as usize {
+			return i;
+		}
+		slot -= t.weight as usize;
+	}
+	unreachable!("slot is below the total weight")
+}
+
+fn mcp_request(target: &Uri, session: Option<&str>, body: &Value) -> Request {
+	let mut req = Request::new(Body::from(body.to_string()));
+	*req.method_mut() = Method::POST;
+	*req.uri_mut() = target.clone();
+	let headers = req.headers_mut();
+	headers.insert(
+		header::CONTENT_TYPE,
+		HeaderValue::from_static("application/json"),
+	);
+	headers.insert(
+		header::ACCEPT,
+		HeaderValue::from_static("application/json, text/event-stream"),
+	);
+	if let Some(session) = session.and_then(|s| HeaderValue::from_str(s).ok()) {
+		headers.insert(MCP_SESSION_ID, session);
+	}
+	req
+}
+
+/// Whether a JSON-RPC response body, either JSON or a stream of SSE events, holds an error.
+fn jsonrpc_error(body: &[u8]) -> bool {
+	let is_error =
+		|v: &Value| v.get("error").is_some() || v.pointer("/result/isError") == Some(&true.into());
+	let text = String::from_utf8_lossy(body);
+	let mut events = text
+		.lines()
+		.filter_map(|l| l.strip_prefix("data:"))
+		.map(str::trim)
+		.peekable();
+	if events.peek().is_none() {
+		return match serde_json::from_slice::<Value>(body) {
+			Ok(v) => is_error(&v),
+			Err(_) => true,
+		};
+	}
+	events.any(|e| serde_json::from_str::<Value>(e).is_ok_and(|v| is_error(&v)))
+}
+
+/// Open an MCP session, returning its ID if the server is stateful.
+async fn mcp_initialize(client: &Client, target: &Uri) -> anyhow::Result<Option<String>> {
+	let initialize = serde_json::json!({
+		"jsonrpc": "2.0",
+		"id": 0,
+		"method": "initialize",
+		"params": {
+			"protocolVersion": MCP_PROTOCOL_VERSION,
+			"capabilities": {},
+			"clientInfo": {"name": "agentgateway-bench", "version": agent_core::version::BuildInfo::new().version},
+		},
+	});
+	let resp = client
+		.simple_call(mcp_request(target, None, &initialize))
+		.await?;
+	let status = resp.status();
+	let session = resp
+		.headers()
+		.get(MCP_SESSION_ID)
+		.and_then(|s| s.to_str().ok())
+		.map(str::to_string);
+	let body = resp.into_body().collect().await?.to_bytes();
+	if !status.is_success() || jsonrpc_error(&body) {
+		anyhow::bail!(
+			"initialize failed with {status}: {}",
+			String::from_utf8_lossy(&body)
+		);
+	}
+	let initialized = serde_json::json!({"jsonrpc": "2.0", "method": "notifications/initialized"});
+	let resp = client
+		.simple_call(mcp_request(target, session.as_deref(), &initialized))
+		.await?;
+	resp.into_body().collect().await?;
+	Ok(session)
+}
+
+struct Sample {
+	op: usize,
+	latency: Duration,
+	status: Option<StatusCode>,
+	ok: bool,
+}
+
+async fn worker(
+	client: Client,
+	opts: Arc<Options>,
+	deadline: Instant,
+	interval: Option<Duration>,
+) -> anyhow::Result<Vec<Sample>> {
+	let mcp = matches!(opts.workload, Workload::Mcp { .. });
+	let session = if mcp {
+		mcp_initialize(&client, &opts.target).await?
+	} else {
+		None
+	};
+	let mut ticker = interval.map(|i| {
+		let mut ticker = tokio::time::interval(i);
+		ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+		ticker
+	});
+	let mut samples = vec![];
+	for n in 0.. {
+		if let Some(ticker) = ticker.as_mut() {
+			ticker.tick().await;
+		}
+		if Instant::now() >= deadline {
+			break;
+		}
+		let (op, req) = opts.workload.request(&opts.target, session.as_deref(), n);
+		let start = Instant::now();
+		let (status, ok) = match client.simple_call(req).await {
+			Ok(resp) => {
+				let status = resp.status();
+				let body = resp.into_body().collect().await.map(|b| b.to_bytes());
+				let ok = status.is_success() && body.is_ok_and(|b| !mcp || !jsonrpc_error(&b));
+				(Some(status), ok)
+			},
+			Err(_) => (None, false),
+		};
+		samples.push(Sample {
+			op,
+			latency: start.elapsed(),
+			status,
+			ok,
+		});
+	}
+	Ok(samples)
+}
+
+/// Wait until the target accepts requests, such as a gateway that was just started.
+pub async fn wait_ready(client: &Client, target: &Uri, timeout: Duration) -> anyhow::Result<()> {
+	let deadline = Instant::now() + timeout;
+	loop {
+		let mut req = Request::new(Body::empty());
+		*req.uri_mut() = target.clone();
+		match client.simple_call(req).await {
+			// Any response will do; the target may not accept GET requests.
+			Ok(_) => return Ok(()),
+			Err(e) if Instant::now() >= deadline => {
+				anyhow::bail!("{target} is not ready after {timeout:?}: {e}")
+			},
+			Err(_) => tokio::time::sleep(Duration::from_millis(100)).await,
+		}
+	}
+}
+
+/// Send traffic at the target for the configured duration, and report its latency.
+pub async fn run(client: Client, opts: Options) -> anyhow::Result<Report> {
+	if opts.connections == 0 {
+		anyhow::bail!("connections must be positive");
+	}
+	if let Workload::Mcp { tools, .. } = &opts.workload
+		&& tools.is_empty()
+	{
+		anyhow::bail!("at least one tool is required");
+	}
+	// Spread the rate evenly across connections.
+	let interval = match opts.rps {
+		Some(rps) if rps > 0.0 => Some(Duration::from_secs_f64(opts.connections as f64 / rps)),
+		Some(_) => anyhow::bail!("rps must be positive"),
+		None => None,
+	};
+	let operations = opts.workload.operations();
+	let opts = Arc::new(opts);
+	let start = Instant::now();
+	let deadline = start + opts.duration;
+	let mut workers = JoinSet::new();
+	for _ in 0..opts.connections {
+		workers.spawn(worker(client.clone(), opts.clone(), deadline, interval));
+	}
+	let mut samples = vec![];
+	while let Some(res) = workers.join_next().await {
+		samples.extend(res??);
+	}
+	Ok(Report::new(&operations, samples, start.elapsed()))
+}
+
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Stats {
+	pub requests: u64,
+	pub errors: u64,
+	#[serde(with = "serde_dur")]
+	pub mean: Duration,
+	#[serde(with = "serde_dur")]
+	pub p50: Duration,
+	#[serde(with = "serde_dur")]
+	pub p90: Duration,
+	#[serde(with = "serde_dur")]
+	pub p99: Duration,
+	#[serde(with = "serde_dur")]
+	pub p999: Duration,
+	#[serde(with = "serde_dur")]
+	pub max: Duration,
+}
+
+impl Stats {
+	fn new(mut latencies: Vec<Duration>, errors: u64) -> Stats {
+		if latencies.is_empty() {
+			return Stats::default();
+		}
+		latencies.sort_unstable();
+		let total: Duration = latencies.iter().sum();
+		Stats {
+			requests: latencies.len() as u64,
+			errors,
+			mean: total / latencies.len() as u32,
+			p50: percentile(&latencies, 50.0),
+			p90: percentile(&latencies, 90.0),
+			p99: percentile(&latencies, 99.0),
+			p999: percentile(&latencies, 99.9),
+			max: latencies[latencies.len() - 1],
+		}
+	}
+}
+
+/// The nearest-rank percentile `p` of `sorted`, which must not be empty.
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+	let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+	sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Report {
+	#[serde(with = "serde_dur")]
+	pub duration: Duration,
+	/// Requests completed per second.
+	pub throughput: f64,
+	#[serde(flatten)]
+	pub total: Stats,
+	/// Stats per HTTP method or MCP tool.
+	pub operations: BTreeMap<String, Stats>,
+	/// How many responses had each status code, or `error` if the request failed without one.
+	pub statuses: BTreeMap<String, u64>,
+}
+
+impl Report {
+	fn new(operations: &[String], samples: Vec<Sample>, duration: Duration) -> Report {
+		let mut statuses = BTreeMap::new();
+		let mut by_op = vec![(vec![], 0); operations.len()];
+		let mut all = Vec::with_capacity(samples.len());
+		let mut errors = 0;
+		for s in samples {
+			let status = s
+				.status
+				.map_or_else(|| "error".to_string(), |s| s.as_u16().to_string());
+			*statuses.entry(status).or_default() += 1;
+			let (latencies, op_errors) = &mut by_op[s.op];
+			latencies.push(s.latency);
+			all.push(s.latency);
+			if !s.ok {
+				*op_errors += 1;
+				errors += 1;
+			}
+		}
+		let throughput = all.len() as f64 / duration.as_secs_f64();
+		Report {
+			duration,
+			throughput,
+			total: Stats::new(all, errors),
+			operations: operations
+				.iter()
+				.cloned()
+				.zip(by_op.into_iter().map(|(l, e)| Stats::new(l, e)))
+				.collect(),
+			statuses,
+		}
+	}
+
+	/// The fraction of requests that failed.
+	pub fn error_rate(&self) -> f64 {
+		if self.total.requests == 0 {
+			return 0.0;
+		}
+		self.total.errors as f64 / self.total.requests as f64
+	}
+}
@@ -0,0 +1,161 @@
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use super::*;
+use crate::test_helpers::proxymock::setup_proxy_test;
+
+fn ms(n: u64) -> Duration {
+	Duration::from_millis(n)
+}
+
+fn client() -> Client {
+	let t = setup_proxy_test("{}").expect("setup proxy inputs");
+	t.inputs().upstream.clone()
+}
+
+#[test]
+fn test_parse_tool() {
+	assert_eq!(
+		"search".parse::<Tool>().unwrap(),
+		Tool {
+			name: "search".to_string(),
+			weight: 1
+		}
+	);
+	assert_eq!(
+		"search:3".parse::<Tool>().unwrap(),
+		Tool {
+			name: "search".to_string(),
+			weight: 3
+		}
+	);
+	assert!("search:0".parse::<Tool>().is_err());
+	assert!("search:x".parse::<Tool>().is_err());
+	assert!(":2".parse::<Tool>().is_err());
+}
+
+#[test]
+fn test_pick() {
+	let tools: Vec<Tool> = ["a:3", "b", "c:2"]
+		.iter()
+		.map(|t| t.parse().unwrap())
+		.collect();
+	let picks: Vec<usize> = (0..12).map(|n| pick(&tools, n)).collect();
+	assert_eq!(picks, vec![0, 0, 0, 1, 2, 2, 0, 0, 0, 1, 2, 2]);
+}
+
+#[test]
+fn test_jsonrpc_error() {
+	assert!(!jsonrpc_error(br#"{"jsonrpc":"2.0","id":1,"result":{}}"#));
+	assert!(jsonrpc_error(
+		br#"{"jsonrpc":"2.0","id":1,"error":{"code":-32601,"message":"no"}}"#
+	));
+	assert!(jsonrpc_error(
+		br#"{"jsonrpc":"2.0","id":1,"result":{"isError":true}}"#
+	));
+	assert!(!jsonrpc_error(
+		b"event: message\ndata: {\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{}}\n\n"
+	));
+	assert!(jsonrpc_error(
+		b"event: message\ndata: {\"jsonrpc\":\"2.0\",\"id\":1,\"error\":{}}\n\n"
+	));
+	assert!(jsonrpc_error(b"not json"));
+}
+
+#[test]
+fn test_report() {
+	let sample = |op, latency, status: Option<u16>| Sample {
+		op,
+		latency: ms(latency),
+		status: status.map(|s| StatusCode::from_u16(s).unwrap()),
+		ok: status == Some(200),
+	};
+	let mut samples: Vec<Sample> = (1..=100).map(|n| sample(0, n, Some(200))).collect();
+	samples.push(sample(1, 500, Some(503)));
+	samples.push(sample(1, 1000, None));
+	let operations = vec!["a".to_string(), "b".to_string()];
+	let report = Report::new(&operations, samples, Duration::from_secs(2));
+
+	assert_eq!(report.total.requests, 102);
+	assert_eq!(report.total.errors, 2);
+	assert_eq!(report.throughput, 51.0);
+	assert_eq!(report.total.p50, ms(51));
+	assert_eq!(report.total.p99, ms(500));
+	assert_eq!(report.total.max, ms(1000));
+	assert_eq!(report.operations["a"].p50, ms(50));
+	assert_eq!(report.operations["a"].p90, ms(90));
+	assert_eq!(report.operations["a"].errors, 0);
+	assert_eq!(report.operations["b"].requests, 2);
+	assert_eq!(report.operations["b"].errors, 2);
+	assert_eq!(report.statuses["200"], 100);
+	assert_eq!(report.statuses["503"], 1);
+	assert_eq!(report.statuses["error"], 1);
+	assert_eq!(report.error_rate(), 2.0 / 102.0);
+}
+
+#[tokio::test]
+async fn test_run_http() {
+	let server = MockServer::start().await;
+	Mock::given(method("POST"))
+		.respond_with(ResponseTemplate::new(200))
+		.mount(&server)
+		.await;
+	let report = run(
+		client(),
+		Options {
+			target: server.uri().parse().unwrap(),
+			connections: 2,
+			rps: Some(100.0),
+			duration: ms(200),
+			workload: Workload::Http {
+				method: Method::POST,
+				payload_size: 1024,
+			},
+		},
+	)
+	.await
+	.unwrap();
+	assert!(report.total.requests > 0);
+	assert_eq!(report.total.errors, 0);
+	assert_eq!(report.operations["POST"].requests, report.total.requests);
+	// 100 requests per second for 200ms, so about 20.
+	assert!(report.total.requests <= 30, "{}", report.total.requests);
+}
+
+#[tokio::test]
+async fn test_run_mcp() {
+	let server = MockServer::start().await;
+	Mock::given(method("POST"))
+		.respond_with(
+			ResponseTemplate::new(200)
+				.insert_header(MCP_SESSION_ID, "session")
+				.set_body_json(serde_json::json!({"jsonrpc": "2.0", "id": 1, "result": {}})),
+		)
+		.mount(&server)
+		.await;
+	let report = run(
+		client(),
+		Options {
+			target: format!("{}/mcp", server.uri()).parse().unwrap(),
+			connections: 1,
+			rps: None,
+			duration: ms(100),
+			workload: Workload::Mcp {
+				tools: vec!["a:3".parse().unwrap(), "b".parse().unwrap()],
+				arguments: serde_json::json!({}),
+			},
+		},
+	)
+	.await
+	.unwrap();
+	assert_eq!(report.total.errors, 0);
+	assert!(report.operations["a"].requests >= report.operations["b"].requests * 3);
+
+	// Every request of the session carries its ID.
+	let requests = server.received_requests().await.unwrap();
+	assert!(
+		requests[1..]
+			.iter()
+			.all(|r| r.headers.get(MCP_SESSION_ID).is_some())
+	);
+}
@@ -0,0 +1,126 @@
+//! Bridges HTTP requests onto message brokers, so agent workers that consume from a queue can be
+//! called through the gateway like any other backend, including as MCP servers over stateless
+//! streamable HTTP.
+//!
+//! Each request is published as a message, and the response is built from the worker's reply. The
+//! request body is the message payload, and request headers are copied to message headers, along
+//! with `Http-Method` and `Http-Path`. A reply's payload is the response body and its headers the
+//! response headers, with the status from an `Http-Status` header, or 200 without one.
+
+use ::http::{HeaderName, StatusCode, header};
+use bytes::Bytes;
+
+use crate::http::{HeaderValue, Request, Response};
+use crate::*;
+
+pub mod nats;
+
+#[cfg(test)]
+#[path = "tests.rs"]
+mod tests;
+
+pub const HTTP_METHOD: &str = "Http-Method";
+pub const HTTP_PATH: &str = "Http-Path";
+pub const HTTP_STATUS: &str = "Http-Status";
+
+/// Headers that describe the HTTP connection or framing, rather than the request or response.
+static SKIPPED_HEADERS: [HeaderName; 4] = [
+	header::CONNECTION,
+	header::CONTENT_LENGTH,
+	header::TRANSFER_ENCODING,
+	header::UPGRADE,
+];
+
+#[apply(schema!)]
+pub enum Bridge {
+	Nats(nats::Config),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+	#[error("no reply from the broker within {0:?}")]
+	Timeout(Duration),
+	#[error("no workers are subscribed to the subject")]
+	NoResponders,
+	#[error("request too large for the broker")]
+	PayloadTooLarge,
+	#[error("broker connection failed: {0}")]
+	Connection(anyhow::Error),
+}
+
+/// A message to or from a broker.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Message {
+	pub headers: Vec<(String, String)>,
+	pub payload: Bytes,
+}
+
+impl Message {
+	pub fn header(&self, name: &str) -> Option<&str> {
+		self
+			.headers
+			.iter()
+			.find(|(k, _)| k.eq_ignore_ascii_case(name))
+			.map(|(_, v)| v.as_str())
+	}
+}
+
+/// Publish `req` and wait for its reply.
+pub async fn call(bridge: Bridge, req: Request) -> Result<Response, Error> {
+	match bridge {
+		Bridge::Nats(cfg) => {
+			let timeout = cfg.timeout;
+			tokio::time::timeout(timeout, async move {
+				let conn = nats::connect(&cfg).await?;
+				let msg = to_message(req, conn.max_payload()).await?;
+				conn.request(&cfg.subject, msg).await
+			})
+			.await
+			.map_err(|_| Error::Timeout(timeout))?
+			.map(to_response)
+		},
+	}
+}
+
+async fn to_message(req: Request, limit: usize) -> Result<Message, Error> {
+	let (parts, body) = req.into_parts();
+	let path = parts.uri.path_and_query().map_or("/", |p| p.as_str());
+	let mut headers = vec![
+		(HTTP_METHOD.to_string(), parts.method.to_string()),
+		(HTTP_PATH.to_string(), path.to_string()),
+	];
+	for (k, v) in &parts.headers {
+		if SKIPPED_HEADERS.contains(k) {
+			continue;
+		}
+		if let Ok(v) = v.to_str() {
+			headers.push((k.to_string(), v.to_string()));
+		}
+	}
+	let payload = http::read_body_with_limit(body, limit)
+		.await
+		.map_err(|_| Error::PayloadTooLarge)?;
+	Ok(Message { headers, payload })
+}
+
+fn to_response(reply: Message) -> Response {
+	let mut resp = Response::new(http::Body::from(reply.payload));
+	for (k, v) in reply.headers {
+		if k.eq_ignore_ascii_case(HTTP_STATUS) {
+			if let Ok(status) = StatusCode::from_bytes(v.trim().as_bytes()) {
+				*resp.status_mut() = status;
+			}
+			continue;
+		}
+		if k.eq_ignore_ascii_case(nats::CORRELATION_ID) {
+			continue;
+		}
+		let (Ok(k), Ok(v)) = (HeaderName::try_from(k), HeaderValue::try_from(v)) else {
+			continue;
+		};
+		if !SKIPPED_HEADERS.contains(&k) {
+			resp.headers_mut().append(k, v);
+		}
+	}
+	resp
+}
@@ -0,0 +1,394 @@
+//! Just enough of the NATS client protocol
+//! (https://docs.nats.io/reference/reference-protocols/nats-protocol) to publish requests and
+//! receive their replies.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use bytes::{Bytes, BytesMut};
+use parking_lot::Mutex;
+use rand::Rng;
+use rand::distr::Alphanumeric;
+use secrecy::{ExposeSecret, SecretString};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::sync::oneshot;
+
+use super::{Error, Message};
+use crate::types::agent::Target;
+use crate::*;
+
+/// Matches replies to requests when every reply is sent to the same subject.
+pub const CORRELATION_ID: &str = "Correlation-Id";
+
+/// The longest protocol line accepted from the server, other than message payloads.
+const MAX_LINE: u64 = 64 * 1024;
+/// The server's maximum message size, if it does not send one.
+const DEFAULT_MAX_PAYLOAD: usize = 1024 * 1024;
+/// The status of the reply the server sends when no one is subscribed to a request's subject.
+const NO_RESPONDERS: u16 = 503;
+
+static CONNECTIONS: LazyLock<tokio::sync::Mutex<HashMap<Key, Arc<Connection>>>> =
+	LazyLock::new(Default::default);
+
+#[apply(schema!)]
+pub struct Config {
+	/// The NATS server, such as `nats.default.svc:4222`. TLS is not supported.
+	pub address: Target,
+	/// The subject requests are published to.
+	pub subject: Strng,
+	/// The subject workers publish replies to. By default each request has its own reply inbox, as
+	/// with NATS request/reply. With a reply subject, replies are instead matched to requests by
+	/// their `Correlation-Id` header, which workers must copy from the request.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub reply_subject: Option<Strng>,
+	/// A token to authenticate to the server with.
+	#[serde(
+		default,
+		serialize_with = "ser_redact",
+		skip_serializing_if = "Option::is_none"
+	)]
+	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+	pub token: Option<SecretString>,
+	/// How long to wait for a reply, including connecting to the server. Defaults to 30s.
+	#[serde(default = "default_timeout", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub timeout: Duration,
+}
+
+fn default_timeout() -> Duration {
+	Duration::from_secs(30)
+}
+
+/// Requests share a connection when they use the same server, reply subject, and token.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Key {
+	address: String,
+	reply_subject: Option<Strng>,
+	token: Option<String>,
+}
+
+/// The connection for `cfg`, opening one if there is none or it has closed.
+pub async fn connect(cfg: &Config) -> Result<Arc<Connection>, Error> {
+	let key = Key {
+		address: cfg.address.to_string(),
+		reply_subject: cfg.reply_subject.clone(),
+		token: cfg.token.as_ref().map(|t| t.expose_secret().to_string()),
+	};
+	let mut connections = CONNECTIONS.lock().await;
+	if let Some(conn) = connections.get(&key)
+		&& !conn.closed.load(Ordering::Relaxed)
+	{
+		return Ok(conn.clone());
+	}
+	let conn = Connection::open(cfg).await.map_err(Error::Connection)?;
+	connections.insert(key, conn.clone());
+	Ok(conn)
+}
+
+#[derive(serde::Deserialize)]
+struct ServerInfo {
+	#[serde(default)]
+	headers: bool,
+	#[serde(default)]
+	max_payload: Option<usize>,
+}
+
+enum Reply {
+	/// Each request has its own inbox subject, `<prefix>.<id>`.
+	Inbox(String),
+	/// Replies to every request are sent to this subject, with the request's ID in their
+	/// `Correlation-Id` header.
+	Subject(Strng),
+}
+
+/// A connection to a NATS server. Replies are read on a background task and handed to the request
+/// waiting for them.
+pub struct Connection {
+	writer: tokio::sync::Mutex<OwnedWriteHalf>,
+	reply: Reply,
+	/// Unique to the connection, so gateways sharing a reply subject do not take each other's
+	/// replies.
+	nonce: String,
+	next_id: AtomicU64,
+	/// Requests waiting for a reply, by ID.
+	pending: Mutex<HashMap<String, oneshot::Sender<Result<Message, Error>>>>,
+	max_payload: usize,
+	closed: AtomicBool,
+}
+
+impl Connection {
+	async fn open(cfg: &Config) -> anyhow::Result<Arc<Connection>> {
+		let stream = match &cfg.address {
+			Target::Address(addr) => TcpStream::connect(addr).await?,
+			Target::Hostname(host, port) => TcpStream::connect((host.as_str(), *port)).await?,
+			Target::UnixSocket(_) => anyhow::bail!("unix sockets are not supported"),
+		};
+		stream.set_nodelay(true)?;
+		let (read, mut write) = stream.into_split();
+		let mut read = BufReader::new(read);
+
+		let line = read_line(&mut read).await?;
+		let Some(info) = line.strip_prefix("INFO ") else {
+			anyhow::bail!("expected INFO from server, got {line:?}");
+		};
+		let info: ServerInfo = serde_json::from_str(info)?;
+		if !info.headers {
+			anyhow::bail!("server does not support headers");
+		}
+		let mut connect = serde_json::json!({
+			"verbose": false,
+			"pedantic": false,
+			"tls_required": false,
+			"name": "agentgateway",
+			"lang": "rust",
+			"version": env!("CARGO_PKG_VERSION"),
+			"protocol": 1,
+			"headers": true,
+			"no_responders": true,
+		});
+		if let Some(token) = &cfg.token {
+			connect["auth_token"] = token.expose_secret().into();
+		}
+		write
+			.write_all(format!("CONNECT {connect}\r\nPING\r\n").as_bytes())
+			.await?;
+		// The server answers the PING once it has accepted the connection, or sends an error.
+		loop {
+			let line = read_line(&mut read).await?;
+			match line.split_ascii_whitespace().next() {
+				Some("PONG") => break,
+				Some("PING") => write.write_all(b"PONG\r\n").await?,
+				Some("+OK" | "INFO") => {},
+				_ => anyhow::bail!("server rejected connection: {line}"),
+			}
+		}
+
+		let nonce = nuid();
+		let reply = match &cfg.reply_subject {
+			Some(subject) => Reply::Subject(subject.clone()),
+			None => Reply::Inbox(format!("_INBOX.{nonce}")),
+		};
+		let subscription = match &reply {
+			Reply::Inbox(prefix) => format!("{prefix}.*"),
+			Reply::Subject(subject) => subject.to_string(),
+		};
+		write
+			.write_all(format!("SUB {subscription} 1\r\n").as_bytes())
+			.await?;
+
+		let conn = Arc::new(Connection {
+			writer: tokio::sync::Mutex::new(write),
+			reply,
+			nonce,
+			next_id: AtomicU64::new(0),
+			pending: Default::default(),
+			max_payload: info.max_payload.unwrap_or(DEFAULT_MAX_PAYLOAD),
+			closed: AtomicBool::new(false),
+		});
+		tokio::spawn(conn.clone().read_loop(read));
+		Ok(conn)
+	}
+
+	/// The largest message the server accepts, headers included.
+	pub fn max_payload(&self) -> usize {
+		self.max_payload
+	}
+
+	/// Publish `msg` to `subject` and wait for its reply.
+	pub async fn request(&self, subject: &str, mut msg: Message) -> Result<Message, Error> {
+		let id = format!(
+			"{}-{}",
+			self.nonce,
+			self.next_id.fetch_add(1, Ordering::Relaxed)
+		);
+		let reply_to = match &self.reply {
+			Reply::Inbox(prefix) => format!("{prefix}.{id}"),
+			Reply::Subject(subject) => {
+				msg.headers.push((CORRELATION_ID.to_string(), id.clone()));
+				subject.to_string()
+			},
+		};
+		let (frame, size) = encode_hpub(subject, &reply_to, &msg);
+		if size > self.max_payload {
+			return Err(Error::PayloadTooLarge);
+		}
+
+		let (tx, rx) = oneshot::channel();
+		self.pending.lock().insert(id.clone(), tx);
+		// The request may time out, so it must stop waiting for its reply however it ends.
+		let _pending = PendingGuard { conn: self, id };
+		if let Err(e) = self.writer.lock().await.write_all(&frame).await {
+			self.closed.store(true, Ordering::Relaxed);
+			return Err(Error::Connection(e.into()));
+		}
+		rx.await
+			.map_err(|_| Error::Connection(anyhow::anyhow!("connection closed")))?
+	}
+
+	async fn read_loop(self: Arc<Self>, mut read: BufReader<OwnedReadHalf>) {
+		if let Err(e) = self.read_messages(&mut read).await {
+			debug!("nats connection closed: {e}");
+		}
+		self.closed.store(true, Ordering::Relaxed);
+		// Dropping the senders fails the requests still waiting.
+		self.pending.lock().clear();
+	}
+
+	async fn read_messages(&self, read: &mut BufReader<OwnedReadHalf>) -> anyhow::Result<()> {
+		loop {
+			let line = read_line(read).await?;
+			let mut args = line.split_ascii_whitespace();
+			match args.next() {
+				// MSG <subject> <sid> [reply-to] <#bytes>
+				Some("MSG") => {
+					let args: Vec<&str> = args.collect();
+					let (subject, len) = match args.as_slice() {
+						[subject, _, len] | [subject, _, _, len] => (*subject, len.parse()?),
+						_ => anyhow::bail!("invalid MSG: {line}"),
+					};
+					let payload = self.read_payload(read, len).await?;
+					self.deliver(
+						subject,
+						Ok(Message {
+							headers: vec![],
+							payload,
+						}),
+					);
+				},
+				// HMSG <subject> <sid> [reply-to] <#header bytes> <#total bytes>
+				Some("HMSG") => {
+					let args: Vec<&str> = args.collect();
+					let (subject, header_len, len) = match args.as_slice() {
+						[subject, _, header_len, len] | [subject, _, _, header_len, len] => {
+							(*subject, header_len.parse()?, len.parse()?)
+						},
+						_ => anyhow::bail!("invalid HMSG: {line}"),
+					};
+					if header_len > len {
+						anyhow::bail!("invalid HMSG: {line}");
+					}
+					let mut payload = self.read_payload(read, len).await?;
+					let headers = payload.split_to(header_len);
+					let (status, headers) = parse_headers(&headers)?;
+					let reply = if status == Some(NO_RESPONDERS) && payload.is_empty() {
+						Err(Error::NoResponders)
+					} else {
+						Ok(Message { headers, payload })
+					};
+					self.deliver(subject, reply);
+				},
+				Some("PING") => self.writer.lock().await.write_all(b"PONG\r\n").await?,
+				Some("PONG" | "+OK" | "INFO") => {},
+				Some("-ERR") => warn!("nats server error: {line}"),
+				_ => anyhow::bail!("unexpected line from server: {line}"),
+			}
+		}
+	}
+
+	async fn read_payload(
+		&self,
+		read: &mut BufReader<OwnedReadHalf>,
+		len: usize,
+	) -> anyhow::Result<Bytes> {
+		if len > self.max_payload {
+			anyhow::bail!("message of {len} bytes is larger than the maximum payload");
+		}
+		// The payload is followed by CRLF.
+		let mut payload = BytesMut::zeroed(len + 2);
+		read.read_exact(&mut payload).await?;
+		payload.truncate(len);
+		Ok(payload.freeze())
+	}
+
+	fn deliver(&self, subject: &str, reply: Result<Message, Error>) {
+		let id = match (&self.reply, &reply) {
+			(Reply::Inbox(prefix), _) => subject
+				.strip_prefix(prefix.as_str())
+				.and_then(|s| s.strip_prefix('.')),
+			(Reply::Subject(_), Ok(msg)) => msg.header(CORRELATION_ID),
+			// Without a correlation ID, a reply cannot be matched to its request, which times out.
+			(Reply::Subject(_), Err(_)) => None,
+		};
+		// Replies to requests that already timed out are dropped.
+		let Some(tx) = id.and_then(|id| self.pending.lock().remove(id)) else {
+			return;
+		};
+		let _ = tx.send(reply);
+	}
+}
+
+struct PendingGuard<'a> {
+	conn: &'a Connection,
+	id: String,
+}
+
+impl Drop for PendingGuard<'_> {
+	fn drop(&mut self) {
+		self.conn.pending.lock().remove(&self.id);
+	}
+}
+
+async fn read_line(read: &mut BufReader<OwnedReadHalf>) -> anyhow::Result<String> {
+	let mut line = Vec::new();
+	(&mut *read)
+		.take(MAX_LINE)
+		.read_until(b'\n', &mut line)
+		.await?;
+	if line.is_empty() {
+		anyhow::bail!("connection closed by server");
+	}
+	if !line.ends_with(b"\r\n") {
+		anyhow::bail!("protocol line too long or truncated");
+	}
+	line.truncate(line.len() - 2);
+	Ok(String::from_utf8(line)?)
+}
+
+/// Encode an `HPUB` of `msg`, returning it and the size of the message, headers included.
+fn encode_hpub(subject: &str, reply_to: &str, msg: &Message) -> (BytesMut, usize) {
+	let mut headers = String::from("NATS/1.0\r\n");
+	for (k, v) in &msg.headers {
+		let _ = write!(headers, "{k}: {v}\r\n");
+	}
+	headers.push_str("\r\n");
+	let len = headers.len() + msg.payload.len();
+	let mut frame = BytesMut::with_capacity(len + subject.len() + reply_to.len() + 32);
+	frame
+		.extend_from_slice(format!("HPUB {subject} {reply_to} {} {len}\r\n", headers.len()).as_bytes());
+	frame.extend_from_slice(headers.as_bytes());
+	frame.extend_from_slice(&msg.payload);
+	frame.extend_from_slice(b"\r\n");
+	(frame, len)
+}
+
+/// Parse a header block, `NATS/1.0[ <status>[ <description>]]` followed by `Name: Value` lines.
+fn parse_headers(block: &[u8]) -> anyhow::Result<(Option<u16>, Vec<(String, String)>)> {
+	let block = std::str::from_utf8(block)?;
+	let mut lines = block.split("\r\n");
+	let Some(status) = lines.next().and_then(|l| l.strip_prefix("NATS/1.0")) else {
+		anyhow::bail!("invalid message headers");
+	};
+	let status = status
+		.split_whitespace()
+		.next()
+		.map(str::parse)
+		.transpose()?;
+	let headers = lines
+		.filter_map(|l| l.split_once(':'))
+		.map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+		.collect();
+	Ok((status, headers))
+}
+
+/// A random identifier, unique enough to name a connection's inboxes.
+fn nuid() -> String {
+	rand::rng()
+		.sample_iter(&Alphanumeric)
+		.take(22)
+		.map(char::from)
+		.collect()
+}
@@ -0,0 +1,212 @@
+use std::net::SocketAddr;
+
+use serde_json::json;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+use super::*;
+
+/// A NATS server that accepts one connection, and answers each published message with the frame
+/// `reply` makes from its reply subject and its headers and payload, if any.
+async fn server(reply: impl Fn(&str, &[u8]) -> Option<Vec<u8>> + Send + 'static) -> SocketAddr {
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let addr = listener.local_addr().unwrap();
+	tokio::spawn(async move {
+		let (stream, _) = listener.accept().await.unwrap();
+		let (read, mut write) = stream.into_split();
+		let mut read = BufReader::new(read);
+		write
+			.write_all(b"INFO {\"headers\":true,\"max_payload\":1024}\r\n")
+			.await
+			.unwrap();
+		let mut line = String::new();
+		loop {
+			line.clear();
+			if read.read_line(&mut line).await.unwrap() == 0 {
+				return;
+			}
+			let args: Vec<&str> = line.split_whitespace().collect();
+			match args[0] {
+				"PING" => write.write_all(b"PONG\r\n").await.unwrap(),
+				// HPUB <subject> <reply-to> <#header bytes> <#total bytes>
+				"HPUB" => {
+					let len: usize = args[4].parse().unwrap();
+					let mut msg = vec![0; len + 2];
+					read.read_exact(&mut msg).await.unwrap();
+					msg.truncate(len);
+					if let Some(frame) = reply(args[2], &msg) {
+						write.write_all(&frame).await.unwrap();
+					}
+				},
+				_ => {},
+			}
+		}
+	});
+	addr
+}
+
+fn hmsg(subject: &str, headers: &str, payload: &[u8]) -> Vec<u8> {
+	let headers = format!("NATS/1.0\r\n{headers}\r\n");
+	let mut frame = format!(
+		"HMSG {subject} 1 {} {}\r\n{headers}",
+		headers.len(),
+		headers.len() + payload.len()
+	)
+	.into_bytes();
+	frame.extend_from_slice(payload);
+	frame.extend_from_slice(b"\r\n");
+	frame
+}
+
+fn bridge(cfg: serde_json::Value) -> Bridge {
+	serde_json::from_value(json!({ "nats": cfg })).unwrap()
+}
+
+fn request(body: &'static str) -> Request {
+	::http::Request::builder()
+		.method("POST")
+		.uri("http://example.com/mcp?session=1")
+		.header(header::CONTENT_TYPE, "application/json")
+		.body(http::Body::from(body))
+		.unwrap()
+}
+
+async fn body(resp: Response) -> String {
+	let body = http::read_body_with_limit(resp.into_body(), 4096)
+		.await
+		.unwrap();
+	String::from_utf8(body.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn test_request_reply() {
+	// The worker echoes the whole request message back.
+	let addr = server(|reply_to, msg| {
+		Some(hmsg(
+			reply_to,
+			"Http-Status: 201\r\ncontent-type: text/plain\r\n",
+			msg,
+		))
+	})
+	.await;
+	let resp = call(
+		bridge(json!({"address": addr.to_string(), "subject": "agents.work"})),
+		request("hello"),
+	)
+	.await
+	.unwrap();
+	assert_eq!(resp.status(), StatusCode::CREATED);
+	assert_eq!(resp.headers()[header::CONTENT_TYPE], "text/plain");
+	let body = body(resp).await;
+	assert!(body.starts_with("NATS/1.0\r\n"), "{body}");
+	assert!(body.contains("Http-Method: POST\r\n"), "{body}");
+	assert!(body.contains("Http-Path: /mcp?session=1\r\n"), "{body}");
+	assert!(
+		body.contains("content-type: application/json\r\n"),
+		"{body}"
+	);
+	assert!(body.ends_with("\r\n\r\nhello"), "{body}");
+}
+
+#[tokio::test]
+async fn test_reply_subject() {
+	let addr = server(|reply_to, msg| {
+		assert_eq!(reply_to, "agents.replies");
+		let msg = std::str::from_utf8(msg).unwrap();
+		let id = msg
+			.lines()
+			.find_map(|l| l.strip_prefix("Correlation-Id: "))
+			.unwrap();
+		// A reply to some other request comes first.
+		let mut frames = hmsg(reply_to, "Correlation-Id: other\r\n", b"wrong");
+		frames.extend(hmsg(
+			reply_to,
+			&format!("Correlation-Id: {id}\r\n"),
+			b"right",
+		));
+		Some(frames)
+	})
+	.await;
+	let resp = call(
+		bridge(json!({
+			"address": addr.to_string(),
+			"subject": "agents.work",
+			"replySubject": "agents.replies",
+		})),
+		request("hello"),
+	)
+	.await
+	.unwrap();
+	assert_eq!(resp.status(), StatusCode::OK);
+	assert!(!resp.headers().contains_key("correlation-id"));
+	assert_eq!(body(resp).await, "right");
+}
+
+#[tokio::test]
+async fn test_no_responders() {
+	let addr = server(|reply_to, _| {
+		let headers = "NATS/1.0 503\r\n\r\n";
+		Some(
+			format!(
+				"HMSG {reply_to} 1 {} {}\r\n{headers}\r\n",
+				headers.len(),
+				headers.len()
+			)
+			.into_bytes(),
+		)
+	})
+	.await;
+	let err = call(
+		bridge(json!({"address": addr.to_string(), "subject": "agents.work"})),
+		request("hello"),
+	)
+	.await
+	.unwrap_err();
+	assert!(matches!(err, Error::NoResponders), "{err}");
+}
+
+#[tokio::test]
+async fn test_timeout() {
+	let addr = server(|_, _| None).await;
+	let err = call(
+		bridge(json!({
+			"address": addr.to_string(),
+			"subject": "agents.work",
+			"timeout": "100ms",
+		})),
+		request("hello"),
+	)
+	.await
+	.unwrap_err();
+	assert!(matches!(err, Error::Timeout(_)), "{err}");
+}
+
+#[tokio::test]
+async fn test_payload_too_large() {
+	let addr = server(|_, _| None).await;
+	let err = call(
+		bridge(json!({"address": addr.to_string(), "subject": "agents.work"})),
+		request(include_str!("mod.rs")),
+	)
+	.await
+	.unwrap_err();
+	assert!(matches!(err, Error::PayloadTooLarge), "{err}");
+}
+
+#[test]
+fn test_to_response() {
+	let resp = to_response(Message {
+		headers: vec![
+			("http-status".to_string(), "404".to_string()),
+			("x-worker".to_string(), "a".to_string()),
+			("x-worker".to_string(), "b".to_string()),
+			("content-length".to_string(), "100".to_string()),
+			("bad header".to_string(), "c".to_string()),
+		],
+		payload: Bytes::from_static(b"missing"),
+	});
+	assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+	assert_eq!(resp.headers().get_all("x-worker").iter().count(), 2);
+	assert!(!resp.headers().contains_key(header::CONTENT_LENGTH));
+	assert_eq!(resp.headers().len(), 2);
+}
@@ -619,7 +619,7 @@ pub struct IdentityContext {
 pub struct BackendContext {
 	/// The name of the backend being used. For example, `my-service` or `service/my-namespace/my-service:8080`.
 	pub name: Strng,
-	/// The type of backend. For example, `ai`, `mcp`, `static`, `dynamic`, or `service`.
+	/// The type of backend. For example, `ai`, `mcp`, `static`, `dynamic`, `bridge`, or `service`.
 	#[serde(rename = "type")]
 	pub backend_type: BackendType,
 	/// The protocol of backend. For example, `http`, `tcp`, `a2a`, `mcp`, or `llm`.
@@ -634,6 +634,7 @@ pub enum BackendType {
 	MCP,
 	Static,
 	Dynamic,
+	Bridge,
 	Service,
 	Unknown,
 }
@@ -12,7 +12,66 @@ use hickory_resolver::{ResolveError, TokioResolver};
 
 use crate::*;
 
-const ERROR_BACKOFF: Duration = Duration::from_secs(5);
+/// Resolution of hostname backends. Addresses are cached for the TTL of their records, and
+/// re-resolved in the background while they are in use, so backends can change their addresses
+/// without a config update.
+#[apply(schema!)]
+#[derive(Copy)]
+pub struct Config {
+	/// The shortest time addresses are cached for, even if their records have a shorter TTL.
+	/// Defaults to 5s.
+	#[serde(default = "defaults::min_ttl", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub min_ttl: Duration,
+	/// The longest time addresses are cached for, even if their records have a longer TTL. If unset,
+	/// the TTL of the records is used.
+	#[serde(
+		default,
+		skip_serializing_if = "Option::is_none",
+		with = "serde_dur_option"
+	)]
+	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+	pub max_ttl: Option<Duration>,
+	/// How long a failed resolution is cached before it is retried. If a hostname that previously
+	/// resolved fails with an error other than having no records, its previous addresses are used
+	/// meanwhile. Defaults to 5s.
+	#[serde(default = "defaults::negative_ttl", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub negative_ttl: Duration,
+	/// How long to wait for a connection to a backend address before also trying its next address,
+	/// alternating between IPv6 and IPv4 ("happy eyeballs"). Defaults to 250ms.
+	#[serde(default = "defaults::connection_attempt_delay", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub connection_attempt_delay: Duration,
+}
+
+impl Default for Config {
+	fn default() -> Self {
+		Config {
+			min_ttl: defaults::min_ttl(),
+			max_ttl: None,
+			negative_ttl: defaults::negative_ttl(),
+			connection_attempt_delay: defaults::connection_attempt_delay(),
+		}
+	}
+}
+
+mod defaults {
+	use std::time::Duration;
+
+	pub fn min_ttl() -> Duration {
+		Duration::from_secs(5)
+	}
+
+	pub fn negative_ttl() -> Duration {
+		Duration::from_secs(5)
+	}
+
+	pub fn connection_attempt_delay() -> Duration {
+		// Recommended by RFC 8305.
+		Duration::from_millis(250)
+	}
+}
 
 #[derive(Debug)]
 struct CircularBuffer<T> {
@@ -43,6 +102,7 @@ impl<T: Copy> CircularBuffer<T> {
 pub struct CachedResolver {
 	dns: Arc<Resolver>,
 	entries: Arc<Mutex<HashMap<Strng, Arc<CacheEntry>>>>,
+	config: Config,
 }
 
 #[derive(Debug)]
@@ -60,6 +120,7 @@ impl CacheEntry {
 		name: Strng,
 		resolver: Arc<Resolver>,
 		cache: Arc<Mutex<HashMap<Strng, Arc<CacheEntry>>>>,
+		config: Config,
 	) {
 		self.active.store(true, Ordering::Relaxed);
 
@@ -78,21 +139,26 @@ impl CacheEntry {
 				Ok((ips, expiry)) => {
 					let cb = CircularBuffer::new(ips);
 					self.entries.store(Some(Arc::new(cb)));
-					expiry
+					let now = Instant::now();
+					let ttl = expiry.saturating_duration_since(now).max(config.min_ttl);
+					now + config.max_ttl.map_or(ttl, |max| ttl.min(max))
 				},
 				Err(e) => {
-					let cb = CircularBuffer::new(Default::default());
-					// We got a result, its just empty
-					self.entries.store(Some(Arc::new(cb)));
-					// if we got an error, retain the last state
+					// If the hostname has no records, we cache that it is empty. Otherwise, the failure may be
+					// transient, so retain the last state if there was one.
+					if e.is_no_records_found() || self.entries.load().is_none() {
+						// We got a result, its just empty
+						let cb = CircularBuffer::new(Default::default());
+						self.entries.store(Some(Arc::new(cb)));
+					}
 					debug!("resolution failed: {e:?}");
-					Instant::now() + ERROR_BACKOFF
+					Instant::now() + config.negative_ttl
 				},
 			};
 			// NB: this will run even on error, so the first fetch for a failed response will hit this and
 			// not block
 			self.notify.notify_waiters();
-			sleep_until_expired(next_refresh).await;
+			tokio::time::sleep_until(next_refresh.into()).await;
 		}
 	}
 
@@ -113,6 +179,28 @@ impl CacheEntry {
 			.as_ref()
 			.and_then(|cb| cb.get_and_advance())
 	}
+
+	/// The cached addresses other than `primary`, in the order they should be tried if `primary`
+	/// cannot be reached: alternating address families, starting with the other family.
+	fn alternates(&self, primary: IpAddr) -> Vec<IpAddr> {
+		let Some(entry) = self.entries.load_full() else {
+			return Vec::new();
+		};
+		let (same, other): (Vec<_>, Vec<_>) = entry
+			.data
+			.iter()
+			.filter(|ip| **ip != primary)
+			.partition(|ip| ip.is_ipv4() == primary.is_ipv4());
+		let mut same = same.into_iter();
+		let mut other = other.into_iter();
+		let mut res = Vec::with_capacity(entry.data.len());
+		loop {
+			match (other.next(), same.next()) {
+				(None, None) => return res,
+				(a, b) => res.extend(a.into_iter().chain(b)),
+			}
+		}
+	}
 }
 
 #[derive(Debug)]
@@ -138,17 +226,30 @@ impl Resolver {
 }
 
 impl CachedResolver {
-	pub fn new(config: ResolverConfig, opts: ResolverOpts) -> Self {
-		let mut rb =
-			hickory_resolver::Resolver::builder_with_config(config, TokioConnectionProvider::default());
+	pub fn new(resolver_cfg: ResolverConfig, opts: ResolverOpts, config: Config) -> Self {
+		let mut rb = hickory_resolver::Resolver::builder_with_config(
+			resolver_cfg,
+			TokioConnectionProvider::default(),
+		);
 		*rb.options_mut() = opts;
 		let dns_resolver = rb.build();
 		CachedResolver {
 			entries: Arc::new(Mutex::new(HashMap::new())),
 			dns: Arc::new(Resolver::Real(dns_resolver)),
+			config,
 		}
 	}
 
+	pub fn config(&self) -> &Config {
+		&self.config
+	}
+
+	/// The addresses of `name` to try if `primary`, returned by `resolve`, cannot be reached.
+	pub fn alternates(&self, name: &Strng, primary: IpAddr) -> Vec<IpAddr> {
+		let entry = self.entries.lock().unwrap().get(name).cloned();
+		entry.map(|e| e.alternates(primary)).unwrap_or_default()
+	}
+
 	pub async fn resolve(&self, name: Strng) -> anyhow::Result<IpAddr> {
 		// Check if we already have an entry
 		let entry = {
@@ -171,8 +272,9 @@ impl CachedResolver {
 				let bg_entry = entry.clone();
 				let dns = self.dns.clone();
 				let cache = self.entries.clone();
+				let config = self.config;
 				let handle = tokio::task::spawn(async move {
-					bg_entry.background(name, dns, cache).await;
+					bg_entry.background(name, dns, cache, config).await;
 				});
 				entry.background_task.store(Some(Arc::new(handle)));
 
@@ -185,19 +287,6 @@ impl CachedResolver {
 	}
 }
 
-async fn sleep_until_expired(valid_until: Instant) {
-	const MINIMUM_TTL: Duration = Duration::from_secs(5);
-	let minimum = Instant::now() + MINIMUM_TTL;
-
-	let deadline = if valid_until >= minimum {
-		valid_until
-	} else {
-		minimum
-	};
-
-	tokio::time::sleep_until(deadline.into()).await;
-}
-
 #[cfg(test)]
 #[path = "dns_tests.rs"]
 mod tests;
@@ -1,4 +1,4 @@
-use std::net::Ipv4Addr;
+use std::net::{Ipv4Addr, Ipv6Addr};
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
@@ -25,6 +25,10 @@ impl Mock {
 		responses.insert(host.to_string(), (ips.into_boxed_slice(), expiry));
 	}
 
+	pub fn remove_response(&self, host: &str) {
+		self.responses.lock().unwrap().remove(host);
+	}
+
 	pub async fn resolve(&self, host: &str) -> Result<(Box<[IpAddr]>, Instant), ResolveError> {
 		let responses = self.responses.lock().unwrap();
 		responses
@@ -37,6 +41,16 @@ impl Mock {
 const IP1: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1));
 const IP2: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 2));
 const IP3: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 3));
+const IP6_1: IpAddr = IpAddr::V6(Ipv6Addr::new(0xfd00, 0, 0, 0, 0, 0, 0, 1));
+const IP6_2: IpAddr = IpAddr::V6(Ipv6Addr::new(0xfd00, 0, 0, 0, 0, 0, 0, 2));
+
+fn mock_resolver(mock: Arc<Mock>, config: Config) -> CachedResolver {
+	CachedResolver {
+		dns: Arc::new(Resolver::Mock(mock)),
+		entries: Arc::new(Mutex::new(HashMap::new())),
+		config,
+	}
+}
 
 #[tokio::test]
 async fn test_basic_resolution() {
@@ -46,6 +60,7 @@ async fn test_basic_resolution() {
 	let resolver = CachedResolver {
 		dns: Arc::new(Resolver::Mock(mock)),
 		entries: Arc::new(Mutex::new(HashMap::new())),
+		config: Default::default(),
 	};
 
 	// First resolution should work
@@ -69,6 +84,7 @@ async fn test_ip_change() {
 	let resolver = CachedResolver {
 		dns: Arc::new(Resolver::Mock(mock.clone())),
 		entries: Arc::new(Mutex::new(HashMap::new())),
+		config: Default::default(),
 	};
 
 	// First resolution should work
@@ -96,6 +112,7 @@ async fn test_ip_error() {
 	let resolver = CachedResolver {
 		dns: Arc::new(Resolver::Mock(mock.clone())),
 		entries: Arc::new(Mutex::new(HashMap::new())),
+		config: Default::default(),
 	};
 
 	// We should get an error, no IPs yet
@@ -105,7 +122,7 @@ async fn test_ip_error() {
 	mock.add_response("example.com", vec![IP3], 60);
 	assert_matches!(resolver.resolve("example.com".into()).await, Err(_));
 	// But once the retry occurs we will get it
-	tokio::time::sleep(ERROR_BACKOFF + Duration::from_secs(1)).await;
+	tokio::time::sleep(defaults::negative_ttl() + Duration::from_secs(1)).await;
 	// Now we should get the IP
 	assert_eq!(resolver.resolve("example.com".into()).await.unwrap(), IP3);
 }
@@ -119,6 +136,7 @@ async fn test_multiple_hostnames() {
 	let resolver = CachedResolver {
 		dns: Arc::new(Resolver::Mock(mock)),
 		entries: Arc::new(Mutex::new(HashMap::new())),
+		config: Default::default(),
 	};
 
 	let ip1 = resolver.resolve("host1.com".into()).await.unwrap();
@@ -136,6 +154,7 @@ async fn test_resolution_failure() {
 	let resolver = CachedResolver {
 		dns: Arc::new(Resolver::Mock(mock)),
 		entries: Arc::new(Mutex::new(HashMap::new())),
+		config: Default::default(),
 	};
 
 	let result = resolver.resolve("nonexistent.com".into()).await;
@@ -150,6 +169,7 @@ async fn test_concurrent_resolution() {
 	let resolver = Arc::new(CachedResolver {
 		dns: Arc::new(Resolver::Mock(mock)),
 		entries: Arc::new(Mutex::new(HashMap::new())),
+		config: Default::default(),
 	});
 
 	// Spawn multiple concurrent resolutions
@@ -166,3 +186,73 @@ async fn test_concurrent_resolution() {
 		assert!(result.is_ok());
 	}
 }
+
+#[tokio::test(start_paused = true)]
+async fn test_ttl_bounds() {
+	let mock = Arc::new(Mock::new());
+	mock.add_response("short.com", vec![IP1], 1);
+	mock.add_response("long.com", vec![IP1], 3600);
+	let resolver = mock_resolver(
+		mock.clone(),
+		Config {
+			min_ttl: Duration::from_secs(10),
+			max_ttl: Some(Duration::from_secs(60)),
+			..Default::default()
+		},
+	);
+
+	assert_eq!(resolver.resolve("short.com".into()).await.unwrap(), IP1);
+	assert_eq!(resolver.resolve("long.com".into()).await.unwrap(), IP1);
+	mock.add_response("short.com", vec![IP2], 1);
+	mock.add_response("long.com", vec![IP2], 3600);
+
+	// The short TTL is raised to the minimum
+	tokio::time::sleep(Duration::from_secs(5)).await;
+	assert_eq!(resolver.resolve("short.com".into()).await.unwrap(), IP1);
+	tokio::time::sleep(Duration::from_secs(6)).await;
+	assert_eq!(resolver.resolve("short.com".into()).await.unwrap(), IP2);
+
+	// The long TTL is capped to the maximum
+	tokio::time::sleep(Duration::from_secs(19)).await;
+	assert_eq!(resolver.resolve("long.com".into()).await.unwrap(), IP1);
+	tokio::time::sleep(Duration::from_secs(31)).await;
+	assert_eq!(resolver.resolve("long.com".into()).await.unwrap(), IP2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_stale_on_error() {
+	let mock = Arc::new(Mock::new());
+	mock.add_response("example.com", vec![IP1], 10);
+	let resolver = mock_resolver(mock.clone(), Default::default());
+
+	assert_eq!(resolver.resolve("example.com".into()).await.unwrap(), IP1);
+	// Resolution fails, but the previous address is still used
+	mock.remove_response("example.com");
+	tokio::time::sleep(Duration::from_secs(5)).await;
+	assert_eq!(resolver.resolve("example.com".into()).await.unwrap(), IP1);
+	tokio::time::sleep(Duration::from_secs(6)).await;
+	assert_eq!(resolver.resolve("example.com".into()).await.unwrap(), IP1);
+
+	// And it is retried after the negative TTL
+	mock.add_response("example.com", vec![IP2], 10);
+	tokio::time::sleep(defaults::negative_ttl() + Duration::from_secs(1)).await;
+	assert_eq!(resolver.resolve("example.com".into()).await.unwrap(), IP2);
+}
+
+#[tokio::test]
+async fn test_alternates() {
+	let mock = Arc::new(Mock::new());
+	mock.add_response("example.com", vec![IP6_1, IP6_2, IP1, IP2], 60);
+	let resolver = mock_resolver(mock, Default::default());
+
+	let primary = resolver.resolve("example.com".into()).await.unwrap();
+	assert_eq!(primary, IP6_1);
+	assert_eq!(
+		resolver.alternates(&"example.com".into(), primary),
+		vec![IP1, IP6_2, IP2]
+	);
+	assert_eq!(
+		resolver.alternates(&"other.com".into(), primary),
+		Vec::<IpAddr>::new()
+	);
+}
@@ -1,13 +1,18 @@
 mod azure;
 mod connect_tunnel;
-mod dns;
+pub mod dns;
 mod hbone_tunnel;
+mod poolstats;
 mod tls;
 
+use std::num::NonZeroUsize;
 use std::str::FromStr;
 use std::task;
 
 use ::http::uri::{Authority, Scheme};
+use futures_util::StreamExt;
+use futures_util::stream::FuturesUnordered;
+use hyper_util_fork::client::legacy::{Http2Settings, pool};
 use hyper_util_fork::rt::TokioIo;
 use tracing::event;
 
@@ -20,6 +25,8 @@ use crate::transport::stream::{LoggingMode, Socket};
 use crate::types::agent::Target;
 use crate::*;
 
+pub use poolstats::{PoolCollector, PoolStats};
+
 #[derive(Clone)]
 pub struct Client {
 	client: hyper_util_fork::client::legacy::Client<Connector, http::Body, PoolKey>,
@@ -42,6 +49,12 @@ pub struct TCPCall {
 	pub source: Socket,
 	pub target: Target,
 	pub transport: Transport,
+	/// The backend's TCP settings, if it has a `tcp` policy.
+	pub tcp: Option<types::backend::TCP>,
+	/// Counts the bytes copied, so they are known however the connection ends.
+	pub stats: Arc<agent_core::copy::ConnectionResult>,
+	/// Copy with io_uring, if both connections are plain TCP.
+	pub io_uring: bool,
 }
 
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq)]
@@ -146,7 +159,34 @@ impl From<Option<VersionedBackendTLS>> for Transport {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
-struct PoolKey(Target, SocketAddr, Transport, ::http::Version);
+struct PoolKey(
+	Target,
+	SocketAddr,
+	Transport,
+	::http::Version,
+	Option<types::backend::ConnectionPool>,
+	Option<Http2Settings>,
+	Option<types::backend::TCP>,
+);
+
+impl PoolKey {
+	fn limits(&self) -> pool::Limits {
+		let Some(p) = self.4 else {
+			return pool::Limits::default();
+		};
+		pool::Limits {
+			max_connections: p.max_connections.map(NonZeroUsize::get),
+			max_idle: p.max_idle,
+			idle_timeout: p.idle_timeout,
+			max_requests: p.max_requests_per_connection.map(NonZeroUsize::get),
+			warm_connections: p.warm_connections,
+		}
+	}
+
+	fn http2_settings(&self) -> Http2Settings {
+		self.5.unwrap_or_default()
+	}
+}
 
 #[derive(Debug, Clone, Copy)]
 pub struct ResolvedDestination(pub SocketAddr);
@@ -172,15 +212,73 @@ struct Connector {
 async fn dial(
 	target: &Target,
 	ep: SocketAddr,
-	backend: &crate::BackendConfig,
+	tcp: &types::backend::TCP,
 ) -> Result<Socket, http::Error> {
 	match target {
-		Target::UnixSocket(uds) => Socket::dial_unix(uds, backend)
-			.await
-			.map_err(crate::http::Error::new),
-		_ => Socket::dial(ep, backend)
+		Target::UnixSocket(uds) => Socket::dial_unix(uds, tcp)
 			.await
 			.map_err(crate::http::Error::new),
+		_ => Socket::dial(ep, tcp).await.map_err(crate::http::Error::new),
+	}
+}
+
+/// Connect to the first of `addrs` to accept the connection. Each address is tried in turn, without
+/// waiting for the previous attempt to fail once it has taken longer than `delay` (RFC 8305).
+async fn dial_happy_eyeballs(
+	addrs: Vec<SocketAddr>,
+	delay: Duration,
+	tcp: &types::backend::TCP,
+) -> Result<Socket, http::Error> {
+	let mut addrs = addrs.into_iter();
+	let mut attempts = FuturesUnordered::new();
+	let mut last_err = None;
+	while let Some(addr) = addrs.next() {
+		attempts.push(Socket::dial(addr, tcp));
+		let next_attempt = tokio::time::sleep(delay);
+		tokio::pin!(next_attempt);
+		loop {
+			tokio::select! {
+				Some(res) = attempts.next() => match res {
+					Ok(socket) => return Ok(socket),
+					Err(e) => {
+						last_err = Some(e);
+						// Try the next address right away, rather than waiting for the delay.
+						if attempts.is_empty() || !addrs.as_slice().is_empty() {
+							break;
+						}
+					},
+				},
+				_ = &mut next_attempt, if !addrs.as_slice().is_empty() => break,
+			}
+		}
+	}
+	Err(crate::http::Error::new(last_err.unwrap_or_else(|| {
+		std::io::Error::other("no addresses to connect to")
+	})))
+}
+
+/// Copies with io_uring if enabled, and otherwise with the runtime.
+async fn copy_bidirectional(
+	downstream: Socket,
+	upstream: Socket,
+	stats: &Arc<agent_core::copy::ConnectionResult>,
+	io_uring: bool,
+) -> Result<(), agent_core::copy::CopyError> {
+	if io_uring {
+		#[cfg(all(target_os = "linux", feature = "io-uring"))]
+		return crate::proxy::uring::copy_bidirectional(downstream, upstream, stats.clone()).await;
+	}
+	agent_core::copy::copy_bidirectional(downstream, upstream, stats.as_ref()).await
+}
+
+/// Resolve once no bytes have been copied in either direction for `timeout`.
+async fn wait_for_idle(stats: &agent_core::copy::ConnectionResult, timeout: Duration) {
+	loop {
+		let idle = stats.idle();
+		if idle >= timeout {
+			return;
+		}
+		tokio::time::sleep(timeout - idle).await;
 	}
 }
 
@@ -190,8 +288,10 @@ impl Connector {
 		target: Target,
 		ep: SocketAddr,
 		transport: Transport,
+		tcp: Option<types::backend::TCP>,
 	) -> Result<Socket, http::Error> {
 		let connect_start = std::time::Instant::now();
+		let tcp = tcp.unwrap_or_else(|| self.backend_config.tcp());
 		let transport_name = transport.name();
 		let skip_dns = transport.skip_dns_resolution();
 		let tls = match transport.application() {
@@ -199,7 +299,21 @@ impl Connector {
 			ApplicationTransport::Tls(application) => Some(application.clone()),
 		};
 		let stream = match transport {
-			Transport::Plain(_) => dial(&target, ep, &self.backend_config).await?,
+			Transport::Plain(_) => match &target {
+				Target::Hostname(hostname, port) if !skip_dns => {
+					let alternates = self.resolver.alternates(hostname, ep.ip());
+					let addrs = std::iter::once(ep)
+						.chain(
+							alternates
+								.into_iter()
+								.map(|ip| SocketAddr::from((ip, *port))),
+						)
+						.collect();
+					let delay = self.resolver.config().connection_attempt_delay;
+					dial_happy_eyeballs(addrs, delay, &tcp).await?
+				},
+				_ => dial(&target, ep, &tcp).await?,
+			},
 			Transport::Tunnel(_, tcfg) => {
 				let proxy_dst: SocketAddr = self
 					.resolve_target(skip_dns, &tcfg.proxy)
@@ -207,7 +321,7 @@ impl Connector {
 					.map_err(crate::http::Error::new)?;
 				let dest = target.to_string();
 
-				let mut con = dial(&tcfg.proxy, proxy_dst, &self.backend_config).await?;
+				let mut con = dial(&tcfg.proxy, proxy_dst, &tcp).await?;
 
 				connect_tunnel::handshake(&mut con, &dest)
 					.await
@@ -322,10 +436,12 @@ impl tower::Service<::http::Extensions> for Connector {
 		let mut it = self.clone();
 
 		Box::pin(async move {
-			let PoolKey(target, ep, transport, _) =
+			let PoolKey(target, ep, transport, _, _, _, tcp) =
 				dst.remove::<PoolKey>().expect("pool key must be set");
 
-			it.connect(target, ep, transport).await.map(TokioIo::new)
+			it.connect(target, ep, transport, tcp)
+				.await
+				.map(TokioIo::new)
 		})
 	}
 }
@@ -335,6 +451,7 @@ impl tower::Service<::http::Extensions> for Connector {
 pub struct Config {
 	pub resolver_cfg: ResolverConfig,
 	pub resolver_opts: ResolverOpts,
+	pub resolver: dns::Config,
 }
 
 impl Client {
@@ -344,7 +461,11 @@ impl Client {
 		backend_config: BackendConfig,
 		metrics: Option<Arc<crate::metrics::Metrics>>,
 	) -> Client {
-		let resolver = dns::CachedResolver::new(cfg.resolver_cfg.clone(), cfg.resolver_opts.clone());
+		let resolver = dns::CachedResolver::new(
+			cfg.resolver_cfg.clone(),
+			cfg.resolver_opts.clone(),
+			cfg.resolver,
+		);
 		let mut b =
 			::hyper_util_fork::client::legacy::Client::builder(::hyper_util::rt::TokioExecutor::new());
 		b.pool_timer(hyper_util::rt::tokio::TokioTimer::new());
@@ -361,6 +482,13 @@ impl Client {
 			metrics,
 		};
 		let client = b.build_with_pool_key(connector.clone());
+		client.set_pool_limits(Arc::new(PoolKey::limits));
+		client.set_http2_settings(Arc::new(PoolKey::http2_settings));
+		if let Some(metrics) = &connector.metrics {
+			client.set_pool_observer(Arc::new(poolstats::Observer {
+				metrics: metrics.clone(),
+			}));
+		}
 		Client { client, connector }
 	}
 
@@ -400,7 +528,22 @@ impl Client {
 			source,
 			target,
 			transport,
+			tcp,
+			stats,
+			io_uring,
 		} = call;
+		let idle_timeout = tcp.and_then(|t| t.idle_timeout);
+		let proxy_header = tcp
+			.and_then(|t| t.proxy_protocol)
+			.map(|version| {
+				crate::proxy::proxy_protocol::encode_header(
+					version,
+					source.tcp().peer_addr,
+					source.tcp().local_addr,
+				)
+			})
+			.transpose()
+			.map_err(ProxyError::Processing)?;
 
 		let dest = self
 			.connector
@@ -421,18 +564,31 @@ impl Client {
 
 			"started"
 		);
-		let upstream = self
+		let mut upstream = self
 			.connector
 			.clone()
-			.connect(target, dest, transport)
+			.connect(target, dest, transport, tcp)
 			.await
 			.map_err(ProxyError::UpstreamTCPCallFailed)?;
+		if let Some(header) = proxy_header {
+			use tokio::io::AsyncWriteExt;
+			upstream
+				.write_all(&header)
+				.await
+				.map_err(|e| ProxyError::UpstreamTCPProxy(e.into()))?;
+		}
 
-		agent_core::copy::copy_bidirectional(source, upstream, &agent_core::copy::ConnectionResult {})
-			.await
-			.map_err(ProxyError::UpstreamTCPProxy)?;
+		let copy = copy_bidirectional(source, upstream, &stats, io_uring);
+		let res = match idle_timeout {
+			Some(timeout) => tokio::select! {
+				res = copy => res.map_err(ProxyError::UpstreamTCPProxy),
+				_ = wait_for_idle(&stats, timeout) => Err(ProxyError::UpstreamTCPIdleTimeout(timeout)),
+			},
+			None => copy.await.map_err(ProxyError::UpstreamTCPProxy),
+		};
 
 		let dur = format!("{}ms", start.elapsed().as_millis());
+		let (sent, recv) = stats.load();
 		event!(
 			target: "upstream tcp",
 			parent: None,
@@ -443,10 +599,12 @@ impl Client {
 			transport = %transport_name,
 
 			duration = dur,
+			sent,
+			recv,
 
 			"completed"
 		);
-		Ok(())
+		res
 	}
 
 	pub async fn call(&self, call: Call) -> Result<http::Response, ProxyError> {
@@ -485,7 +643,21 @@ impl Client {
 		let version = req.version();
 		let transport_name = transport.name();
 		let target_name = target.to_string();
-		let key = PoolKey(target, dest, transport, version);
+		let pool_settings = req
+			.extensions()
+			.get::<types::backend::ConnectionPool>()
+			.copied();
+		let http2_settings = req.extensions().get::<Http2Settings>().copied();
+		let tcp_settings = req.extensions().get::<types::backend::TCP>().copied();
+		let key = PoolKey(
+			target,
+			dest,
+			transport,
+			version,
+			pool_settings,
+			http2_settings,
+			tcp_settings,
+		);
 		trace!(?req, ?key, "sending request");
 		req.extensions_mut().insert(key);
 		let method = req.method().clone();
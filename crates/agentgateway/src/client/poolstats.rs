@@ -0,0 +1,164 @@
+use std::collections::BTreeMap;
+
+use agent_core::strng::RichStrng;
+use hyper_util_fork::client::legacy::pool::{self, Event, Snapshot};
+use prometheus_client::collector::Collector;
+use prometheus_client::encoding::{DescriptorEncoder, EncodeMetric};
+use prometheus_client::metrics::MetricType;
+use prometheus_client::metrics::gauge::ConstGauge;
+use prometheus_client::registry::Registry;
+
+use super::{Client, PoolKey};
+use crate::metrics::{Metrics, PoolLabels};
+use crate::*;
+
+#[cfg(test)]
+#[path = "poolstats_tests.rs"]
+mod tests;
+
+/// The state of the upstream connection pool for one backend, as served by `/debug/pools`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolStats {
+	pub backend: String,
+	pub transport: &'static str,
+	/// Open connections, whether idle or in use.
+	pub active: usize,
+	pub idle: usize,
+	/// Requests waiting for a connection, and connections being established.
+	pub pending: usize,
+	pub created: u64,
+	pub reused: u64,
+	pub evicted: u64,
+	pub connect_failures: u64,
+}
+
+impl PoolStats {
+	fn labels(&self) -> PoolLabels {
+		PoolLabels {
+			backend: RichStrng::from(self.backend.as_str()).into(),
+			transport: RichStrng::from(self.transport).into(),
+		}
+	}
+}
+
+fn labels(key: &PoolKey) -> PoolLabels {
+	PoolLabels {
+		backend: RichStrng::from(key.0.to_string()).into(),
+		transport: RichStrng::from(key.2.name()).into(),
+	}
+}
+
+/// Counts connection pool events, per backend.
+pub(super) struct Observer {
+	pub(super) metrics: Arc<Metrics>,
+}
+
+impl pool::Observer<PoolKey> for Observer {
+	fn on_event(&self, key: &PoolKey, event: Event) {
+		let counter = match event {
+			Event::Created => &self.metrics.upstream_connections_created,
+			Event::Reused => &self.metrics.upstream_connections_reused,
+			Event::Evicted => &self.metrics.upstream_connections_evicted,
+			Event::ConnectFailed => &self.metrics.upstream_connect_failures,
+		};
+		counter.get_or_create(&labels(key)).inc();
+	}
+}
+
+/// Combine the pool state of each endpoint and HTTP version into one entry per backend and
+/// transport.
+fn aggregate<'a>(
+	snapshot: impl IntoIterator<Item = (&'a PoolKey, &'a Snapshot)>,
+) -> BTreeMap<(String, &'static str), PoolStats> {
+	let mut stats = BTreeMap::<(String, &'static str), PoolStats>::new();
+	for (key, s) in snapshot {
+		let backend = key.0.to_string();
+		let transport = key.2.name();
+		let e = stats
+			.entry((backend.clone(), transport))
+			.or_insert_with(|| PoolStats {
+				backend,
+				transport,
+				..Default::default()
+			});
+		e.active += s.open;
+		e.idle += s.idle;
+		e.pending += s.pending;
+	}
+	stats
+}
+
+impl Client {
+	/// Returns the state of the upstream connection pool, per backend. Counters are included if
+	/// metrics are enabled.
+	pub fn pool_stats(&self) -> Vec<PoolStats> {
+		let snapshot = self.client.pool_snapshot();
+		let mut stats = aggregate(&snapshot);
+		if let Some(m) = &self.connector.metrics {
+			let counters = [
+				&m.upstream_connections_created,
+				&m.upstream_connections_reused,
+				&m.upstream_connections_evicted,
+				&m.upstream_connect_failures,
+			];
+			for ((backend, transport), s) in stats.iter_mut() {
+				let labels = PoolLabels {
+					backend: RichStrng::from(backend.as_str()).into(),
+					transport: RichStrng::from(*transport).into(),
+				};
+				let [created, reused, evicted, failures] =
+					counters.map(|c| c.get(&labels).map(|c| c.get()).unwrap_or_default());
+				s.created = created;
+				s.reused = reused;
+				s.evicted = evicted;
+				s.connect_failures = failures;
+			}
+		}
+		stats.into_values().collect()
+	}
+}
+
+/// Exposes the pool gauges (active, idle, and pending connections) at scrape time.
+#[derive(Debug)]
+pub struct PoolCollector {
+	client: Client,
+}
+
+impl PoolCollector {
+	pub fn register(registry: &mut Registry, client: Client) {
+		registry.register_collector(Box::new(PoolCollector { client }));
+	}
+}
+
+impl Collector for PoolCollector {
+	fn encode(&self, mut encoder: DescriptorEncoder) -> Result<(), std::fmt::Error> {
+		let stats = self.client.pool_stats();
+		let gauges: [(&str, &str, fn(&PoolStats) -> usize); 3] = [
+			(
+				"upstream_pool_active_connections",
+				"Open upstream connections, whether idle or in use",
+				|s| s.active,
+			),
+			(
+				"upstream_pool_idle_connections",
+				"Idle upstream connections waiting to be reused",
+				|s| s.idle,
+			),
+			(
+				"upstream_pool_pending",
+				"Requests waiting for an upstream connection, and connections being established",
+				|s| s.pending,
+			),
+		];
+		for (name, help, value) in gauges {
+			let mut metric_encoder = encoder.encode_descriptor(name, help, None, MetricType::Gauge)?;
+			for s in &stats {
+				let labels = s.labels();
+				let family_encoder = metric_encoder.encode_family(&labels)?;
+				ConstGauge::new(value(s) as i64).encode(family_encoder)?;
+			}
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,106 @@
+use std::net::SocketAddr;
+
+use hyper_util_fork::client::legacy::pool::Observer as _;
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::family::Family;
+use prometheus_client::registry::Registry;
+
+use super::*;
+use crate::client::{ApplicationTransport, Transport};
+use crate::types::agent::Target;
+
+fn key(target: Target, ep: &str, version: ::http::Version) -> PoolKey {
+	PoolKey(
+		target,
+		ep.parse::<SocketAddr>().unwrap(),
+		Transport::Plain(ApplicationTransport::Plaintext),
+		version,
+		None,
+		None,
+		None,
+	)
+}
+
+fn snapshot(open: usize, idle: usize, pending: usize) -> Snapshot {
+	Snapshot {
+		open,
+		idle,
+		pending,
+	}
+}
+
+#[test]
+fn test_aggregate() {
+	let host = Target::Hostname(strng::new("example.com"), 80);
+	let addr = Target::Address("127.0.0.1:8080".parse().unwrap());
+	let pools = vec![
+		// Two endpoints and two HTTP versions of the same backend are combined.
+		(
+			key(host.clone(), "10.0.0.1:80", ::http::Version::HTTP_11),
+			snapshot(3, 1, 0),
+		),
+		(
+			key(host.clone(), "10.0.0.2:80", ::http::Version::HTTP_11),
+			snapshot(2, 2, 1),
+		),
+		(
+			key(host, "10.0.0.1:80", ::http::Version::HTTP_2),
+			snapshot(1, 0, 0),
+		),
+		(
+			key(addr, "127.0.0.1:8080", ::http::Version::HTTP_11),
+			snapshot(0, 0, 4),
+		),
+	];
+	let stats: Vec<_> = aggregate(pools.iter().map(|(k, s)| (k, s)))
+		.into_values()
+		.collect();
+	assert_eq!(
+		stats,
+		vec![
+			PoolStats {
+				backend: "127.0.0.1:8080".to_string(),
+				transport: "plaintext",
+				pending: 4,
+				..Default::default()
+			},
+			PoolStats {
+				backend: "example.com:80".to_string(),
+				transport: "plaintext",
+				active: 6,
+				idle: 3,
+				pending: 1,
+				..Default::default()
+			},
+		]
+	);
+}
+
+#[test]
+fn test_observer_counts_events() {
+	let mut registry = Registry::default();
+	let metrics = Arc::new(Metrics::new(
+		&mut registry,
+		Default::default(),
+		Default::default(),
+	));
+	let observer = Observer {
+		metrics: metrics.clone(),
+	};
+	let k = key(
+		Target::Hostname(strng::new("example.com"), 443),
+		"10.0.0.1:443",
+		::http::Version::HTTP_11,
+	);
+	observer.on_event(&k, Event::Created);
+	observer.on_event(&k, Event::Reused);
+	observer.on_event(&k, Event::Reused);
+	observer.on_event(&k, Event::ConnectFailed);
+
+	let l = labels(&k);
+	let count = |f: &Family<PoolLabels, Counter>| f.get(&l).map(|c| c.get()).unwrap_or_default();
+	assert_eq!(count(&metrics.upstream_connections_created), 1);
+	assert_eq!(count(&metrics.upstream_connections_reused), 2);
+	assert_eq!(count(&metrics.upstream_connections_evicted), 0);
+	assert_eq!(count(&metrics.upstream_connect_failures), 1);
+}
@@ -18,6 +18,7 @@ use crate::{
 };
 
 pub fn parse_config(contents: String, filename: Option<PathBuf>) -> anyhow::Result<Config> {
+	let contents = interpolate(&contents)?;
 	let nested: NestedRawConfig = serdes::yamlviajson::from_str(&contents)?;
 	let raw = nested.config.unwrap_or_default();
 
@@ -49,6 +50,18 @@ pub fn parse_config(contents: String, filename: Option<PathBuf>) -> anyhow::Resu
 		.unwrap_or("Kubernetes".to_string());
 	let xds = {
 		let address = validate_uri(empty_to_none(parse("XDS_ADDRESS")?).or(raw.xds_address))?;
+		let fallback_addresses = parse::<String>("XDS_FALLBACK_ADDRESSES")?
+			.map(|s| {
+				s.split(',')
+					.map(|a| a.trim().to_string())
+					.filter(|a| !a.is_empty())
+					.collect()
+			})
+			.unwrap_or(raw.xds_fallback_addresses)
+			.into_iter()
+			.map(|a| validate_uri(Some(a)).map(Option::unwrap_or_default))
+			.collect::<anyhow::Result<Vec<_>>>()?;
+		let snapshot_path = parse::<PathBuf>("XDS_SNAPSHOT_PATH")?.or(raw.xds_snapshot_path);
 		// if local_config.is_none() && address.is_none() {
 		// 	anyhow::bail!("file or XDS configuration is required")
 		// }
@@ -100,11 +113,16 @@ pub fn parse_config(contents: String, filename: Option<PathBuf>) -> anyhow::Resu
 		};
 		XDSConfig {
 			address,
+			fallback_addresses,
+			snapshot_path,
 			auth,
 			ca_cert: xds_root_cert,
 			namespace: namespace.into(),
 			gateway: gateway.into(),
 			local_config,
+			kubernetes: raw.kubernetes,
+			remote_config: raw.remote_config,
+			consul: raw.consul,
 		}
 	};
 
@@ -183,6 +201,30 @@ pub fn parse_config(contents: String, filename: Option<PathBuf>) -> anyhow::Resu
 		.unwrap_or_default();
 	let termination_max_deadline =
 		parse_duration("CONNECTION_TERMINATION_DEADLINE")?.or(raw.connection_termination_deadline);
+	let termination_max_deadline = match termination_max_deadline {
+		Some(period) => period,
+		None => match parse::<u64>("TERMINATION_GRACE_PERIOD_SECONDS")? {
+			// We want our drain period to be less than Kubernetes, so we can use the last few seconds
+			// to abruptly terminate anything remaining before Kubernetes SIGKILLs us.
+			// We could just take the SIGKILL, but it is even more abrupt (TCP RST vs RST_STREAM/TLS close, etc)
+			// Note: we do this in code instead of in configuration so that we can use downward API to expose this variable
+			// if it is added to Kubernetes (https://github.com/kubernetes/kubernetes/pull/125746).
+			Some(secs) => Duration::from_secs(cmp::max(
+				if secs > 10 {
+					secs - 5
+				} else {
+					// If the grace period is really low give less buffer
+					secs - 1
+				},
+				1,
+			)),
+			None => Duration::from_secs(5),
+		},
+	};
+	let shutdown = raw
+		.shutdown
+		.unwrap_or_default()
+		.resolve(termination_min_deadline, termination_max_deadline);
 	let otlp = empty_to_none(parse("OTLP_ENDPOINT")?)
 		.or(raw.tracing.as_ref().map(|t| t.otlp_endpoint.clone()));
 
@@ -218,50 +260,89 @@ pub fn parse_config(contents: String, filename: Option<PathBuf>) -> anyhow::Resu
 		.transpose()?
 		.unwrap_or(Address::SocketAddr(SocketAddr::new(bind_wildcard, 15021)));
 
-	let threading_mode = if parse::<String>("THREADING_MODE")?.as_deref() == Some("thread_per_core") {
+	let runtime_shards = parse::<usize>("RUNTIME_SHARDS")?.or(raw.runtime_shards);
+	let threading_mode = if parse::<String>("THREADING_MODE")?.as_deref() == Some("thread_per_core")
+		|| runtime_shards.is_some()
+	{
 		ThreadingMode::ThreadPerCore
 	} else {
 		ThreadingMode::default()
 	};
+	if runtime_shards == Some(0) {
+		anyhow::bail!("runtimeShards must be at least 1");
+	}
+	if threading_mode == ThreadingMode::ThreadPerCore && !cfg!(target_family = "unix") {
+		anyhow::bail!("runtimeShards requires SO_REUSEPORT, which is not supported on this platform");
+	}
+	let runtime_shards = runtime_shards.unwrap_or_else(|| {
+		std::thread::available_parallelism()
+			.map(|n| n.get())
+			.unwrap_or(1)
+	});
+
+	let acceptors = parse::<usize>("ACCEPTORS")?.or(raw.acceptors).unwrap_or(1);
+	if acceptors == 0 {
+		anyhow::bail!("acceptors must be at least 1");
+	}
+	if acceptors > 1 && !cfg!(target_family = "unix") {
+		anyhow::bail!("acceptors requires SO_REUSEPORT, which is not supported on this platform");
+	}
+	let io_uring = parse::<bool>("IO_URING")?.or(raw.io_uring).unwrap_or(false);
+	if io_uring && !cfg!(all(target_os = "linux", feature = "io-uring")) {
+		anyhow::bail!("ioUring requires Linux, and building with the io-uring feature");
+	}
 
 	let session_encoder = match raw.session {
 		None => crate::http::sessionpersistence::Encoder::base64(),
 		Some(s) => crate::http::sessionpersistence::Encoder::aes(s.key.expose_secret())?,
 	};
 
+	let statsd = raw.metrics.as_ref().and_then(|m| m.statsd.clone());
 	Ok(crate::Config {
 		network: network.into(),
 		admin_addr,
+		admin_csrf: raw.admin_csrf.unwrap_or_default(),
+		features: raw.features.unwrap_or_default(),
 		stats_addr,
 		readiness_addr,
 		self_addr,
 		xds,
 		ca,
 		num_worker_threads: parse_worker_threads(raw.worker_threads)?,
-		termination_min_deadline,
+		acceptors,
+		io_uring,
+		termination_min_deadline: shutdown.stop_accepting,
 		threading_mode,
+		runtime_shards,
 		backend: raw.backend,
+		audit_log: raw.audit_log,
+		access_log: raw.access_log,
+		slow_request_log: raw.slow_request_log,
+		llm_log: raw.llm_log,
+		costs: raw.costs,
+		probes: raw.probes,
+		slos: raw.slos,
+		memory: raw
+			.memory
+			.map(|m| m.validate().map(|()| m))
+			.transpose()
+			.context("memory")?,
+		overload: raw
+			.overload
+			.map(|o| o.validate().map(|()| o))
+			.transpose()
+			.context("overload")?,
+		statsd,
+		quota: raw.quota.unwrap_or_default(),
+		encryption: raw.encryption,
+		token_issuer: raw
+			.token_issuer
+			.map(|i| i.try_into().map(Arc::new))
+			.transpose()
+			.context("token issuer")?,
 		admin_runtime_handle: None,
-		termination_max_deadline: match termination_max_deadline {
-			Some(period) => period,
-			None => match parse::<u64>("TERMINATION_GRACE_PERIOD_SECONDS")? {
-				// We want our drain period to be less than Kubernetes, so we can use the last few seconds
-				// to abruptly terminate anything remaining before Kubernetes SIGKILLs us.
-				// We could just take the SIGKILL, but it is even more abrupt (TCP RST vs RST_STREAM/TLS close, etc)
-				// Note: we do this in code instead of in configuration so that we can use downward API to expose this variable
-				// if it is added to Kubernetes (https://github.com/kubernetes/kubernetes/pull/125746).
-				Some(secs) => Duration::from_secs(cmp::max(
-					if secs > 10 {
-						secs - 5
-					} else {
-						// If the grace period is really low give less buffer
-						secs - 1
-					},
-					1,
-				)),
-				None => Duration::from_secs(5),
-			},
-		},
+		termination_max_deadline: shutdown.connection_deadline(),
+		shutdown,
 		tracing: trc::Config {
 			endpoint: otlp,
 			headers: otlp_headers,
@@ -304,6 +385,12 @@ pub fn parse_config(contents: String, filename: Option<PathBuf>) -> anyhow::Resu
 				.as_ref()
 				.and_then(|t| t.path.clone())
 				.unwrap_or_else(|| "/v1/traces".to_string()),
+			propagation: raw
+				.tracing
+				.as_ref()
+				.and_then(|t| t.propagation.clone())
+				.unwrap_or_else(trc::default_propagation),
+			tail_sampling: raw.tracing.as_ref().and_then(|t| t.tail_sampling.clone()),
 		},
 		logging: telemetry::log::Config {
 			filter: raw
@@ -323,6 +410,7 @@ pub fn parse_config(contents: String, filename: Option<PathBuf>) -> anyhow::Resu
 				.as_ref()
 				.and_then(|l| l.format.clone())
 				.unwrap_or_default(),
+			otlp: raw.logging.as_ref().and_then(|l| l.otlp.clone()),
 			fields: raw
 				.logging
 				.and_then(|f| f.fields)
@@ -350,27 +438,52 @@ pub fn parse_config(contents: String, filename: Option<PathBuf>) -> anyhow::Resu
 						.collect::<frozen_collections::FzHashSet<String>>()
 				})
 				.unwrap_or_default(),
-			metric_fields: Arc::new(
-				raw
+			histogram_buckets: raw
+				.metrics
+				.as_ref()
+				.map(|f| {
+					telemetry::metrics::validate_histogram_names(f.histograms.keys())
+						.context("invalid metrics.histograms")?;
+					f.histograms
+						.iter()
+						.map(|(name, b)| {
+							let bounds = b
+								.bounds()
+								.with_context(|| format!("invalid buckets for histogram {name}"))?;
+							Ok::<_, anyhow::Error>((name.clone(), bounds))
+						})
+						.collect::<Result<_, _>>()
+				})
+				.transpose()?
+				.unwrap_or_default(),
+			metric_fields: Arc::new({
+				let (fields, request_labels, cardinality) = raw
 					.metrics
-					.and_then(|f| f.fields)
-					.map(|fields| {
-						Ok::<_, anyhow::Error>(MetricFields {
-							add: fields
+					.map(|m| (m.fields, m.request_labels, m.cardinality))
+					.unwrap_or_default();
+				MetricFields {
+					add: fields
+						.map(|fields| {
+							fields
 								.add
 								.iter()
 								.map(|(k, v)| cel::Expression::new_strict(v).map(|v| (k.clone(), Arc::new(v))))
-								.collect::<Result<_, _>>()?,
+								.collect::<Result<_, _>>()
 						})
-					})
-					.transpose()?
-					.unwrap_or_default(),
-			),
+						.transpose()?
+						.unwrap_or_default(),
+					request_labels,
+					cardinality: Arc::new(telemetry::cardinality::Limiter::new(
+						cardinality.unwrap_or_default(),
+					)),
+				}
+			}),
 		},
 		dns: client::Config {
 			// TODO: read from file
 			resolver_cfg,
 			resolver_opts,
+			resolver: raw.dns.unwrap_or_default(),
 		},
 		proxy_metadata: crate::ProxyMetadata {
 			instance_ip: std::env::var("INSTANCE_IP").unwrap_or_else(|_| "1.1.1.1".to_string()),
@@ -542,6 +655,94 @@ fn parse_otlp_headers(
 	}
 }
 
+/// Interpolate environment variables and files into config text, before it is parsed.
+///
+/// * `$VAR` and `${VAR}` are replaced with the environment variable `VAR`, which must be set.
+/// * `${VAR:-default}` is replaced with `default` if `VAR` is unset or empty, so `${VAR:-}` is
+///   optional.
+/// * `${VAR:?message}` fails with `message` if `VAR` is unset or empty.
+/// * `${file:/path}` is replaced with the contents of the file, without trailing newlines. It
+///   accepts the same `:-` and `:?` modifiers.
+/// * `$$` is a literal `$`.
+/// * `${secret:...}` is kept as is, to be resolved by [crate::secrets] after parsing.
+///
+/// Other uses of `$`, such as in regexes, are kept as is. Values are substituted as text.
+pub fn interpolate(s: &str) -> anyhow::Result<String> {
+	// Avoid expanding the comment for schema.
+	let s = s.replace("# yaml-language-server: $schema", "#");
+	let mut out = String::with_capacity(s.len());
+	let mut rest = s.as_str();
+	while let Some(i) = rest.find('$') {
+		out.push_str(&rest[..i]);
+		let after = &rest[i + 1..];
+		if let Some(after) = after.strip_prefix('$') {
+			out.push('$');
+			rest = after;
+		} else if let Some(inner) = after.strip_prefix('{') {
+			let end = inner
+				.find('}')
+				.ok_or_else(|| anyhow::anyhow!("unterminated ${{ in config"))?;
+			if inner.starts_with("secret:") {
+				out.push_str(&rest[i..i + 2 + end + 1]);
+			} else {
+				out.push_str(&interpolate_expr(&inner[..end])?);
+			}
+			rest = &inner[end + 1..];
+		} else {
+			let len = after.find(|c| !is_name_char(c)).unwrap_or(after.len());
+			if len == 0 || after.starts_with(|c: char| c.is_ascii_digit()) {
+				out.push('$');
+				rest = after;
+				continue;
+			}
+			out.push_str(&interpolate_expr(&after[..len])?);
+			rest = &after[len..];
+		}
+	}
+	out.push_str(rest);
+	Ok(out)
+}
+
+fn is_name_char(c: char) -> bool {
+	c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Resolve the contents of a `${...}` expression.
+fn interpolate_expr(expr: &str) -> anyhow::Result<String> {
+	let (source, modifier) = match [":-", ":?"].iter().filter_map(|op| expr.find(op)).min() {
+		Some(i) => (&expr[..i], Some((&expr[i + 1..i + 2], &expr[i + 2..]))),
+		None => (expr, None),
+	};
+	let value = match source.strip_prefix("file:") {
+		Some(path) => match fs_err::read_to_string(path) {
+			Ok(v) => Some(v.trim_end_matches(['\r', '\n']).to_string()),
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+			Err(e) => return Err(e.into()),
+		},
+		None => {
+			if source.is_empty() || !source.chars().all(is_name_char) {
+				anyhow::bail!("invalid variable name in config: {source:?}");
+			}
+			match env::var(source) {
+				Ok(v) => Some(v),
+				Err(env::VarError::NotPresent) => None,
+				Err(e) => anyhow::bail!("error reading {source}: {e}"),
+			}
+		},
+	};
+	// Like a shell, the modifiers treat empty values as unset.
+	let value = value.filter(|v| modifier.is_none() || !v.is_empty());
+	match (value, modifier) {
+		(Some(v), _) => Ok(v),
+		(None, Some(("-", default))) => Ok(default.to_string()),
+		(None, Some((_, message))) if !message.is_empty() => anyhow::bail!("{source}: {message}"),
+		(None, _) => match source.strip_prefix("file:") {
+			Some(path) => anyhow::bail!("file {path} does not exist"),
+			None => anyhow::bail!("environment variable {source} is not set"),
+		},
+	}
+}
+
 fn get_cpu_count() -> anyhow::Result<usize> {
 	// Allow overriding the count with an env var. This can be used to pass the CPU limit on Kubernetes
 	// from the downward API.
@@ -609,4 +810,68 @@ mod tests {
 		// Test missing env var
 		assert_eq!(parse_otlp_headers("NONEXISTENT_VAR").unwrap(), None);
 	}
+
+	#[test]
+	fn test_interpolate() {
+		use std::io::Write;
+
+		unsafe {
+			env::set_var("TEST_INTERPOLATE_HOST", "example.com");
+			env::set_var("TEST_INTERPOLATE_EMPTY", "");
+		}
+		let mut f = tempfile::NamedTempFile::new().unwrap();
+		writeln!(f, "secret").unwrap();
+		let path = f.path().display();
+		let out = interpolate(&format!(
+			"host: $TEST_INTERPOLATE_HOST
+url: https://${{TEST_INTERPOLATE_HOST}}/v1
+port: ${{TEST_INTERPOLATE_UNSET:-8080}}
+empty: '${{TEST_INTERPOLATE_EMPTY:-default}}'
+key: ${{file:{path}}}
+regex: ^/api/(.*)$
+price: $$5
+token: ${{secret:vault:kv/data/agw#token}}
+"
+		))
+		.unwrap();
+		assert_eq!(
+			out,
+			"host: example.com
+url: https://example.com/v1
+port: 8080
+empty: 'default'
+key: secret
+regex: ^/api/(.*)$
+price: $5
+token: ${secret:vault:kv/data/agw#token}
+"
+		);
+
+		// Set but empty values are only replaced by defaults
+		assert_eq!(interpolate("[${TEST_INTERPOLATE_EMPTY}]").unwrap(), "[]");
+		assert_eq!(
+			interpolate("${file:/missing/key.pem:-none}").unwrap(),
+			"none"
+		);
+
+		let err = |s: &str| interpolate(s).unwrap_err().to_string();
+		assert_eq!(
+			err("${TEST_INTERPOLATE_UNSET}"),
+			"environment variable TEST_INTERPOLATE_UNSET is not set"
+		);
+		assert_eq!(
+			err("${TEST_INTERPOLATE_EMPTY:?must be set}"),
+			"TEST_INTERPOLATE_EMPTY: must be set"
+		);
+		assert_eq!(
+			err("${file:/missing/key.pem}"),
+			"file /missing/key.pem does not exist"
+		);
+		assert!(interpolate("${unterminated").is_err());
+
+		unsafe {
+			env::remove_var("TEST_INTERPOLATE_HOST");
+			env::remove_var("TEST_INTERPOLATE_EMPTY");
+		}
+	}
 }
@@ -14,6 +14,7 @@ use tonic::IntoRequest;
 use tracing::{error, info, warn};
 use x509_parser::certificate::X509Certificate;
 
+use crate::telemetry::events::{self, EventKind};
 use crate::types::discovery::Identity;
 use crate::*;
 
@@ -386,14 +387,14 @@ impl CaClient {
 			interval.tick().await;
 
 			// Check if we need to renew
-			let should_renew = {
+			let (should_renew, expires_at) = {
 				let state = state_tx.borrow();
 				match &*state {
 					CertificateState::Available(cert) => {
 						let refresh_at = cert.refresh_at();
-						SystemTime::now() >= refresh_at
+						(SystemTime::now() >= refresh_at, Some(cert.expiry.not_after))
 					},
-					CertificateState::Error(_) | CertificateState::NotReady => true,
+					CertificateState::Error(_) | CertificateState::NotReady => (true, None),
 				}
 			};
 
@@ -412,6 +413,12 @@ impl CaClient {
 							"Failed to renew certificate for identity {}: {}",
 							config.identity, e
 						);
+						if let Some(expires_at) = expires_at {
+							events::publish(EventKind::CertificateExpiring {
+								identity: config.identity.to_string(),
+								expires_at: expires_at.into(),
+							});
+						}
 						let _ = state_tx.send(CertificateState::Error(e));
 					},
 				}
@@ -0,0 +1,320 @@
+//! Consul discovery mode.
+//!
+//! For environments without Kubernetes, routes and backends can be read from Consul:
+//! * A KV key may hold config in the local config format, such as binds, routes, and backends.
+//! * Healthy instances of Consul services are served as services named
+//!   `<namespace>/<service>.service.consul`, which routes reference with `service` backends. Each
+//!   service is served on `servicePort`, which is forwarded to the port of each instance.
+//!
+//! Changes are watched with Consul blocking queries, and the config is rebuilt whenever the
+//! catalog, the health of an instance, or the KV key changes. The ACL token, if any, is read from
+//! `CONSUL_HTTP_TOKEN`.
+
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+
+use ::http::{HeaderMap, HeaderValue, StatusCode};
+use macro_rules_attribute::apply;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value, json};
+use tokio::sync::Notify;
+
+use crate::client::Client;
+use crate::control::http_get;
+use crate::http::Response;
+use crate::state_manager::PreviousState;
+use crate::store::Stores;
+use crate::telemetry::events;
+use crate::types::agent::ListenerTarget;
+use crate::types::local::{NormalizedLocalConfig, parse_raw};
+use crate::*;
+
+#[cfg(test)]
+#[path = "consul_tests.rs"]
+mod tests;
+
+const CONSUL_TOKEN: &str = "CONSUL_HTTP_TOKEN";
+const CONSUL_INDEX: &str = "x-consul-index";
+/// How long a blocking query waits for a change.
+const WATCH_WAIT: &str = "30s";
+/// How long to wait before retrying a failed watch.
+const WATCH_RETRY: Duration = Duration::from_secs(5);
+
+/// Serve routes and backends read from a Consul KV key, and the healthy instances of Consul
+/// services.
+#[apply(schema!)]
+pub struct Config {
+	/// The address of the Consul HTTP API. Defaults to `http://127.0.0.1:8500`.
+	#[serde(default = "default_address")]
+	pub address: String,
+	/// A KV key holding config in the local config format.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub key: Option<String>,
+	/// The services whose healthy instances are served. If empty, all services are served.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub services: Vec<Strng>,
+	/// The namespace services are served in. Defaults to `consul`.
+	#[serde(default = "default_namespace")]
+	pub namespace: Strng,
+	/// The port services are served on. Defaults to 80.
+	#[serde(default = "default_service_port")]
+	pub service_port: u16,
+}
+
+fn default_address() -> String {
+	"http://127.0.0.1:8500".to_string()
+}
+
+fn default_namespace() -> Strng {
+	strng::literal!("consul")
+}
+
+fn default_service_port() -> u16 {
+	80
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ServiceEntry {
+	node: Node,
+	service: AgentService,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Node {
+	node: Strng,
+	address: String,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AgentService {
+	#[serde(rename = "ID")]
+	id: Strng,
+	#[serde(default)]
+	address: String,
+	port: u16,
+}
+
+pub struct Controller {
+	pub cfg: Config,
+	pub stores: Stores,
+	pub client: Client,
+	pub secrets: secrets::Resolver,
+	pub gateway: ListenerTarget,
+}
+
+impl Controller {
+	/// Start watching Consul in the background.
+	pub fn run(self) {
+		let this = Arc::new(self);
+		let changed = Arc::new(Notify::new());
+		let mut watches = vec![
+			"/v1/catalog/services".to_string(),
+			"/v1/health/state/any".to_string(),
+		];
+		if let Some(key) = &this.cfg.key {
+			watches.push(format!("/v1/kv/{key}"));
+		}
+		for path in watches {
+			tokio::task::spawn(this.clone().watch(path, changed.clone()));
+		}
+		tokio::task::spawn(async move {
+			let mut prev = PreviousState::default();
+			let mut last: Option<Value> = None;
+			loop {
+				changed.notified().await;
+				if let Err(e) = this.sync(&mut prev, &mut last).await {
+					warn!("failed to sync Consul config: {e:#}");
+					events::publish(events::EventKind::ConfigRejected {
+						source: "consul".to_string(),
+						error: format!("{e:#}"),
+					});
+				}
+			}
+		});
+	}
+
+	/// Notify `changed` whenever the result of a blocking query on `path` changes.
+	async fn watch(self: Arc<Self>, path: String, changed: Arc<Notify>) {
+		let mut index: Option<HeaderValue> = None;
+		loop {
+			let query = match &index {
+				Some(i) => format!(
+					"{path}?wait={WATCH_WAIT}&index={}",
+					i.to_str().unwrap_or_default()
+				),
+				None => path.clone(),
+			};
+			let next = match self.get(&query).await {
+				Ok(resp) if resp.status().is_success() || resp.status() == StatusCode::NOT_FOUND => {
+					resp.headers().get(CONSUL_INDEX).cloned()
+				},
+				Ok(resp) => {
+					warn!(path, "failed to watch Consul: status {}", resp.status());
+					None
+				},
+				Err(e) => {
+					warn!(path, "failed to watch Consul: {e:#}");
+					None
+				},
+			};
+			let Some(next) = next else {
+				// Start over once Consul is reachable again, so the config is resynced.
+				index = None;
+				tokio::time::sleep(WATCH_RETRY).await;
+				continue;
+			};
+			if index.as_ref() != Some(&next) {
+				changed.notify_one();
+			}
+			index = Some(next);
+		}
+	}
+
+	async fn sync(&self, prev: &mut PreviousState, last: &mut Option<Value>) -> anyhow::Result<()> {
+		let base = match &self.cfg.key {
+			Some(key) => self.read_key(key).await?,
+			None => None,
+		};
+		let names = if self.cfg.services.is_empty() {
+			self.list_services().await?
+		} else {
+			self.cfg.services.clone()
+		};
+		let mut instances = BTreeMap::new();
+		for name in names {
+			let entries: Vec<ServiceEntry> = self
+				.get_json(&format!("/v1/health/service/{name}?passing=true"))
+				.await?;
+			instances.insert(name, entries);
+		}
+		let config = translate(&self.cfg, base, &instances)?;
+		if last.as_ref() == Some(&config) {
+			return Ok(());
+		}
+		let normalized = NormalizedLocalConfig::from_value(
+			self.client.clone(),
+			&self.secrets,
+			self.gateway.clone(),
+			config.clone(),
+		)
+		.await?;
+		let binds = self.stores.binds.sync_local(
+			normalized.binds,
+			normalized.policies,
+			normalized.backends,
+			prev.binds.clone(),
+		);
+		let discovery = self.stores.discovery.sync_local(
+			normalized.services,
+			normalized.workloads,
+			prev.discovery.clone(),
+		)?;
+		*prev = PreviousState { binds, discovery };
+		*last = Some(config);
+		info!(services = instances.len(), "applied Consul config");
+		Ok(())
+	}
+
+	/// Read the config in a KV key. Returns None if the key does not exist.
+	async fn read_key(&self, key: &str) -> anyhow::Result<Option<Value>> {
+		let resp = self.get(&format!("/v1/kv/{key}?raw")).await?;
+		if resp.status() == StatusCode::NOT_FOUND {
+			return Ok(None);
+		}
+		if !resp.status().is_success() {
+			anyhow::bail!("consul returned status {}", resp.status());
+		}
+		let limit = crate::http::response_buffer_limit(&resp);
+		let body = crate::http::read_body_with_limit(resp.into_body(), limit).await?;
+		let body = std::str::from_utf8(&body).context("config is not valid UTF-8")?;
+		Ok(Some(parse_raw(body).with_context(|| format!("key {key}"))?))
+	}
+
+	/// List the names of all services, other than Consul itself.
+	async fn list_services(&self) -> anyhow::Result<Vec<Strng>> {
+		let services: BTreeMap<Strng, Value> = self.get_json("/v1/catalog/services").await?;
+		Ok(
+			services
+				.into_keys()
+				.filter(|name| name.as_str() != "consul")
+				.collect(),
+		)
+	}
+
+	async fn get_json<T: DeserializeOwned>(&self, path: &str) -> anyhow::Result<T> {
+		let resp = self.get(path).await?;
+		if !resp.status().is_success() {
+			anyhow::bail!("consul returned status {}", resp.status());
+		}
+		crate::json::from_response_body(resp).await
+	}
+
+	async fn get(&self, path: &str) -> anyhow::Result<Response> {
+		let mut headers = HeaderMap::new();
+		if let Ok(token) = std::env::var(CONSUL_TOKEN) {
+			headers.insert("x-consul-token", HeaderValue::try_from(token)?);
+		}
+		let url = format!("{}{path}", self.cfg.address.trim_end_matches('/'));
+		http_get(&self.client, &url, headers).await
+	}
+}
+
+/// Translate the config in the KV key, if any, and the healthy instances of each service into the
+/// local config format.
+fn translate(
+	cfg: &Config,
+	base: Option<Value>,
+	instances: &BTreeMap<Strng, Vec<ServiceEntry>>,
+) -> anyhow::Result<Value> {
+	let mut config = base.unwrap_or_else(|| json!({}));
+	let Some(obj) = config.as_object_mut() else {
+		anyhow::bail!("config in key {:?} is not an object", cfg.key);
+	};
+	let ns = &cfg.namespace;
+	let port = cfg.service_port;
+	let mut services = Vec::new();
+	let mut workloads = Vec::new();
+	for (name, entries) in instances {
+		let hostname = format!("{name}.service.consul");
+		services.push(json!({
+			"name": name,
+			"namespace": ns,
+			"hostname": hostname,
+			"vips": [],
+			"ports": {port.to_string(): port},
+		}));
+		for e in entries {
+			// Services registered without an address use the address of their node.
+			let address = if e.service.address.is_empty() {
+				&e.node.address
+			} else {
+				&e.service.address
+			};
+			let Ok(ip) = address.parse::<IpAddr>() else {
+				warn!(service = %name, instance = %e.service.id, address, "skipping instance without an IP address");
+				continue;
+			};
+			workloads.push(json!({
+				"uid": format!("consul/{}/{}", e.node.node, e.service.id),
+				"name": e.service.id,
+				"namespace": ns,
+				"node": e.node.node,
+				"workloadIps": [ip],
+				"services": {format!("{ns}/{hostname}"): {port.to_string(): e.service.port}},
+			}));
+		}
+	}
+	append(obj, "services", services);
+	append(obj, "workloads", workloads);
+	Ok(config)
+}
+
+fn append(obj: &mut Map<String, Value>, key: &str, values: Vec<Value>) {
+	// Anything other than a list is left for loading to reject.
+	if let Value::Array(existing) = obj.entry(key).or_insert_with(|| json!([])) {
+		existing.extend(values);
+	}
+}
@@ -0,0 +1,107 @@
+use super::*;
+use crate::test_helpers::proxymock::setup_proxy_test;
+
+fn cfg() -> Config {
+	serde_json::from_value(json!({})).unwrap()
+}
+
+fn instances() -> BTreeMap<Strng, Vec<ServiceEntry>> {
+	let entries = serde_json::from_value(json!([
+		{
+			"Node": {"Node": "node-a", "Address": "10.0.0.1"},
+			"Service": {"ID": "web-1", "Service": "web", "Address": "", "Port": 8080},
+		},
+		{
+			"Node": {"Node": "node-b", "Address": "10.0.0.2"},
+			"Service": {"ID": "web-2", "Service": "web", "Address": "10.0.1.2", "Port": 9090},
+		},
+		{
+			"Node": {"Node": "node-c", "Address": "10.0.0.3"},
+			"Service": {"ID": "web-3", "Service": "web", "Address": "web-3.internal", "Port": 8080},
+		},
+	]))
+	.unwrap();
+	BTreeMap::from([(strng::new("web"), entries)])
+}
+
+#[test]
+fn test_translate() {
+	let out = translate(&cfg(), None, &instances()).unwrap();
+	assert_eq!(
+		out["services"],
+		json!([{
+			"name": "web",
+			"namespace": "consul",
+			"hostname": "web.service.consul",
+			"vips": [],
+			"ports": {"80": 80},
+		}])
+	);
+	// Instances without an IP address are skipped, and those without an address use their node's
+	assert_eq!(
+		out["workloads"],
+		json!([
+			{
+				"uid": "consul/node-a/web-1",
+				"name": "web-1",
+				"namespace": "consul",
+				"node": "node-a",
+				"workloadIps": ["10.0.0.1"],
+				"services": {"consul/web.service.consul": {"80": 8080}},
+			},
+			{
+				"uid": "consul/node-b/web-2",
+				"name": "web-2",
+				"namespace": "consul",
+				"node": "node-b",
+				"workloadIps": ["10.0.1.2"],
+				"services": {"consul/web.service.consul": {"80": 9090}},
+			},
+		])
+	);
+}
+
+#[test]
+fn test_translate_merges_key() {
+	let base = json!({
+		"binds": [],
+		"services": [{"name": "db", "namespace": "default", "hostname": "db", "vips": [], "ports": {}}],
+	});
+	let out = translate(&cfg(), Some(base), &instances()).unwrap();
+	assert_eq!(out["binds"], json!([]));
+	let services = out["services"]
+		.as_array()
+		.unwrap()
+		.iter()
+		.map(|s| s["name"].as_str().unwrap())
+		.collect::<Vec<_>>();
+	assert_eq!(services, vec!["db", "web"]);
+
+	assert!(translate(&cfg(), Some(json!([])), &instances()).is_err());
+}
+
+#[tokio::test]
+async fn test_translated_config_loads() {
+	let t = setup_proxy_test("{}").expect("setup proxy inputs");
+	let base = json!({
+		"binds": [{"port": 8080, "listeners": [{"routes": [{
+			"backends": [{"service": {"name": "consul/web.service.consul", "port": 80}}],
+		}]}]}],
+	});
+	let out = translate(&cfg(), Some(base), &instances()).unwrap();
+	let normalized = NormalizedLocalConfig::from_value(
+		t.inputs().upstream.clone(),
+		&secrets::Resolver::new(t.inputs().upstream.clone()),
+		ListenerTarget {
+			gateway_name: "default".into(),
+			gateway_namespace: "default".into(),
+			listener_name: None,
+		},
+		out,
+	)
+	.await
+	.unwrap();
+	assert_eq!(normalized.binds.len(), 1);
+	assert_eq!(normalized.services.len(), 1);
+	assert_eq!(normalized.workloads.len(), 2);
+}
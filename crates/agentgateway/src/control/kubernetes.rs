@@ -0,0 +1,1036 @@
+//! Kubernetes controller mode.
+//!
+//! Instead of receiving configuration from a control plane over XDS, agentgateway can read Gateway
+//! API resources directly from the Kubernetes API. Gateways of the configured class, and the
+//! HTTPRoutes attached to them, are translated into the local configuration format and applied
+//! through the same path as a local config file.
+//!
+//! Two agentgateway resources extend the Gateway API:
+//! * `AgentgatewayBackend` (`agentgateway.dev/v1alpha1`) is a backend, such as an MCP or AI
+//!   backend, that routes reference with `backendRefs`. Its `spec` is a local route backend.
+//! * `AgentgatewayPolicy` (`agentgateway.dev/v1alpha1`) attaches route policies, such as
+//!   `mcpAuthorization`, to HTTPRoutes in its namespace with `targetRefs`. Its `spec.policies`
+//!   holds the policies, in the same format as the `policies` of a local route.
+//!
+//! Resources are listed, then watched for changes from the listed resource version. Routes may
+//! only reference backends in other namespaces that a ReferenceGrant allows. Secret references in
+//! resources may only name Kubernetes secrets in the resource's own namespace.
+//!
+//! Resources that are invalid are rejected on their own, and reported as `configRejected` events,
+//! without holding back changes to the others. Routes that a rejected policy targets are not
+//! served. Only HTTP listeners are served.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+
+use ::http::StatusCode;
+use bytes::BytesMut;
+use http_body_util::BodyExt;
+use macro_rules_attribute::apply;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value, json};
+use tokio::sync::Notify;
+
+use crate::client::{ApplicationTransport, Call, Client};
+use crate::http::backendtls::ResolvedBackendTLS;
+use crate::http::{Body, Response};
+use crate::store::{BindPreviousState, Stores};
+use crate::telemetry::events;
+use crate::types::agent::{ListenerTarget, Target};
+use crate::types::local::NormalizedLocalConfig;
+use crate::*;
+
+#[cfg(test)]
+#[path = "kubernetes_tests.rs"]
+mod tests;
+
+const KUBERNETES_TOKEN: &str = "/var/run/secrets/kubernetes.io/serviceaccount/token";
+const KUBERNETES_CA: &str = "/var/run/secrets/kubernetes.io/serviceaccount/ca.crt";
+
+const GATEWAY_GROUP: &str = "gateway.networking.k8s.io";
+const AGENTGATEWAY_GROUP: &str = "agentgateway.dev";
+
+/// How long a watch runs before it is restarted from the last resource version.
+const WATCH_TIMEOUT: Duration = Duration::from_secs(300);
+/// How long to wait before retrying a failed watch, or checking again for a resource whose CRD is
+/// not installed.
+const WATCH_RETRY: Duration = Duration::from_secs(10);
+
+/// A kind of resource read from the Kubernetes API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Kind {
+	group: &'static str,
+	version: &'static str,
+	resource: &'static str,
+	kind: &'static str,
+}
+
+const GATEWAY: Kind = Kind {
+	group: GATEWAY_GROUP,
+	version: "v1",
+	resource: "gateways",
+	kind: "Gateway",
+};
+const HTTP_ROUTE: Kind = Kind {
+	group: GATEWAY_GROUP,
+	version: "v1",
+	resource: "httproutes",
+	kind: "HTTPRoute",
+};
+const REFERENCE_GRANT: Kind = Kind {
+	group: GATEWAY_GROUP,
+	version: "v1beta1",
+	resource: "referencegrants",
+	kind: "ReferenceGrant",
+};
+const BACKEND: Kind = Kind {
+	group: AGENTGATEWAY_GROUP,
+	version: "v1alpha1",
+	resource: "agentgatewaybackends",
+	kind: "AgentgatewayBackend",
+};
+const POLICY: Kind = Kind {
+	group: AGENTGATEWAY_GROUP,
+	version: "v1alpha1",
+	resource: "agentgatewaypolicies",
+	kind: "AgentgatewayPolicy",
+};
+const KINDS: [Kind; 5] = [GATEWAY, HTTP_ROUTE, REFERENCE_GRANT, BACKEND, POLICY];
+
+/// Serve Gateway API resources read from the Kubernetes API, using the in-cluster service account
+/// credentials.
+#[apply(schema!)]
+pub struct Config {
+	/// Only Gateways of this class are served. Defaults to `agentgateway`.
+	#[serde(default = "default_gateway_class_name")]
+	pub gateway_class_name: Strng,
+	/// Only read resources in this namespace. If unset, resources in all namespaces are read.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub namespace: Option<Strng>,
+}
+
+fn default_gateway_class_name() -> Strng {
+	strng::literal!("agentgateway")
+}
+
+/// GET a path from the Kubernetes API. Returns None if the resource does not exist.
+pub async fn get(client: &Client, path: &str) -> anyhow::Result<Option<Value>> {
+	let resp = request(client, path).await?;
+	if resp.status() == StatusCode::NOT_FOUND {
+		return Ok(None);
+	}
+	if !resp.status().is_success() {
+		anyhow::bail!("kubernetes returned status {}", resp.status());
+	}
+	Ok(Some(crate::json::from_response_body(resp).await?))
+}
+
+/// Send a GET request to the Kubernetes API, returning the response without reading its body.
+async fn request(client: &Client, path: &str) -> anyhow::Result<Response> {
+	let host =
+		std::env::var("KUBERNETES_SERVICE_HOST").context("KUBERNETES_SERVICE_HOST is not set")?;
+	let port: u16 = std::env::var("KUBERNETES_SERVICE_PORT")
+		.ok()
+		.map(|p| p.parse())
+		.transpose()?
+		.unwrap_or(443);
+	// Read the token each time, as it is periodically rotated by the kubelet.
+	let token = fs_err::tokio::read_to_string(KUBERNETES_TOKEN).await?;
+	let tls = ResolvedBackendTLS {
+		root: Some(fs_err::tokio::read(PathBuf::from(KUBERNETES_CA)).await?),
+		..Default::default()
+	}
+	.try_into()?;
+	let req = ::http::Request::builder()
+		.uri(format!("https://kubernetes.default.svc{path}"))
+		.header(
+			::http::header::AUTHORIZATION,
+			format!("Bearer {}", token.trim()),
+		)
+		.body(Body::empty())?;
+	let target = Target::try_from((host.as_str(), port))?;
+	Ok(
+		client
+			.call(Call {
+				req,
+				target,
+				transport: ApplicationTransport::Tls(tls.base_config()).into(),
+			})
+			.await?,
+	)
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ObjectMeta {
+	name: Strng,
+	#[serde(default)]
+	namespace: Strng,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+struct Object<T> {
+	metadata: ObjectMeta,
+	spec: T,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GatewaySpec {
+	gateway_class_name: Strng,
+	#[serde(default)]
+	listeners: Vec<GatewayListener>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GatewayListener {
+	name: Strng,
+	#[serde(default)]
+	hostname: Option<Strng>,
+	port: u16,
+	protocol: Strng,
+	#[serde(default)]
+	allowed_routes: Option<AllowedRoutes>,
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AllowedRoutes {
+	#[serde(default)]
+	namespaces: Option<RouteNamespaces>,
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RouteNamespaces {
+	#[serde(default)]
+	from: Option<Strng>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HTTPRouteSpec {
+	#[serde(default)]
+	parent_refs: Vec<ParentRef>,
+	#[serde(default)]
+	hostnames: Vec<Strng>,
+	#[serde(default)]
+	rules: Vec<HTTPRouteRule>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ParentRef {
+	#[serde(default)]
+	group: Option<Strng>,
+	#[serde(default)]
+	kind: Option<Strng>,
+	name: Strng,
+	#[serde(default)]
+	namespace: Option<Strng>,
+	#[serde(default)]
+	section_name: Option<Strng>,
+	#[serde(default)]
+	port: Option<u16>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HTTPRouteRule {
+	#[serde(default)]
+	name: Option<Strng>,
+	#[serde(default)]
+	matches: Vec<HTTPRouteMatch>,
+	#[serde(default)]
+	filters: Vec<Value>,
+	#[serde(default)]
+	backend_refs: Vec<BackendRef>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HTTPRouteMatch {
+	#[serde(default)]
+	path: Option<TypedMatch>,
+	#[serde(default)]
+	headers: Vec<TypedMatch>,
+	#[serde(default)]
+	query_params: Vec<TypedMatch>,
+	#[serde(default)]
+	method: Option<Strng>,
+}
+
+/// A path, header, or query parameter match. Paths have no name.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TypedMatch {
+	#[serde(default, rename = "type")]
+	match_type: Option<Strng>,
+	#[serde(default)]
+	name: Option<Strng>,
+	#[serde(default)]
+	value: Option<Strng>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BackendRef {
+	#[serde(default)]
+	group: Option<Strng>,
+	#[serde(default)]
+	kind: Option<Strng>,
+	name: Strng,
+	#[serde(default)]
+	namespace: Option<Strng>,
+	#[serde(default)]
+	port: Option<u16>,
+	#[serde(default)]
+	weight: Option<usize>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PolicySpec {
+	#[serde(default)]
+	target_refs: Vec<TargetRef>,
+	policies: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TargetRef {
+	#[serde(default)]
+	group: Option<Strng>,
+	kind: Strng,
+	name: Strng,
+	#[serde(default)]
+	section_name: Option<Strng>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReferenceGrantSpec {
+	#[serde(default)]
+	from: Vec<ReferenceGrantFrom>,
+	#[serde(default)]
+	to: Vec<ReferenceGrantTo>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReferenceGrantFrom {
+	#[serde(default)]
+	group: Strng,
+	kind: Strng,
+	namespace: Strng,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReferenceGrantTo {
+	#[serde(default)]
+	group: Strng,
+	kind: Strng,
+	#[serde(default)]
+	name: Option<Strng>,
+}
+
+/// The resources read from the Kubernetes API.
+#[derive(Debug, Clone, Default)]
+struct Resources {
+	gateways: Vec<Object<GatewaySpec>>,
+	routes: Vec<Object<HTTPRouteSpec>>,
+	reference_grants: Vec<Object<ReferenceGrantSpec>>,
+	backends: Vec<Object<Value>>,
+	policies: Vec<Object<PolicySpec>>,
+	/// Policies that were rejected. The routes they target are not served.
+	rejected_policies: Vec<Object<PolicySpec>>,
+}
+
+/// The objects of each kind, keyed by namespace and name.
+type Objects = HashMap<Kind, BTreeMap<(Strng, Strng), Value>>;
+
+/// The latest objects of each kind, kept up to date by a watch of each kind. A kind is absent
+/// until it is first listed.
+#[derive(Debug, Default)]
+struct Cache {
+	objects: std::sync::Mutex<Objects>,
+	changed: Notify,
+}
+
+impl Cache {
+	/// Replace all objects of a kind, after it was listed.
+	fn replace(&self, kind: Kind, objects: Vec<Value>) {
+		let objects: BTreeMap<_, _> = objects
+			.into_iter()
+			.filter_map(|o| Some((object_key(&o)?, o)))
+			.collect();
+		let prev = self.objects.lock().unwrap().insert(kind, objects.clone());
+		if prev.as_ref() != Some(&objects) {
+			self.changed.notify_one();
+		}
+	}
+
+	/// Apply an `ADDED`, `MODIFIED`, or `DELETED` watch event.
+	fn apply(&self, kind: Kind, event: &str, object: Value) {
+		let Some(key) = object_key(&object) else {
+			return;
+		};
+		let mut objects = self.objects.lock().unwrap();
+		let objects = objects.entry(kind).or_default();
+		if event == "DELETED" {
+			objects.remove(&key);
+		} else {
+			objects.insert(key, object);
+		}
+		self.changed.notify_one();
+	}
+
+	/// The objects of every kind, or None if some kind has not been listed yet.
+	fn snapshot(&self) -> Option<Objects> {
+		let objects = self.objects.lock().unwrap();
+		KINDS
+			.iter()
+			.all(|k| objects.contains_key(k))
+			.then(|| objects.clone())
+	}
+}
+
+fn object_key(o: &Value) -> Option<(Strng, Strng)> {
+	let namespace = o.pointer("/metadata/namespace").and_then(Value::as_str);
+	let name = o.pointer("/metadata/name")?.as_str()?;
+	Some((strng::new(namespace.unwrap_or_default()), strng::new(name)))
+}
+
+fn resource_version(o: &Value) -> Option<&str> {
+	o.pointer("/metadata/resourceVersion")?.as_str()
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct WatchEvent {
+	#[serde(rename = "type")]
+	event_type: Strng,
+	object: Value,
+}
+
+/// Controller reads Gateway API resources and applies them to the stores.
+#[derive(Debug, Clone)]
+pub struct Controller {
+	pub cfg: Config,
+	pub stores: Stores,
+	pub client: Client,
+	pub secrets: secrets::Resolver,
+	pub gateway: ListenerTarget,
+}
+
+/// What was last applied, so unchanged resources are not applied or reported again.
+#[derive(Debug, Default)]
+struct SyncState {
+	prev: BindPreviousState,
+	last: Option<Value>,
+	/// The resources that were rejected, and why.
+	rejected: BTreeMap<String, String>,
+}
+
+impl Controller {
+	/// Start watching resources in the background.
+	pub fn run(self) {
+		let this = Arc::new(self);
+		let cache = Arc::new(Cache::default());
+		for kind in KINDS {
+			tokio::task::spawn(this.clone().watch(kind, cache.clone()));
+		}
+		tokio::task::spawn(async move {
+			let mut state = SyncState::default();
+			loop {
+				cache.changed.notified().await;
+				// Wait until every kind has been listed, so a partial view is never applied.
+				let Some(objects) = cache.snapshot() else {
+					continue;
+				};
+				if let Err(e) = this.sync(&objects, &mut state).await {
+					warn!("failed to sync Kubernetes resources: {e:#}");
+					events::publish(events::EventKind::ConfigRejected {
+						source: "kubernetes".to_string(),
+						error: format!("{e:#}"),
+					});
+				}
+			}
+		});
+	}
+
+	/// Keep the objects of `kind` in `cache` up to date.
+	async fn watch(self: Arc<Self>, kind: Kind, cache: Arc<Cache>) {
+		loop {
+			if let Err(e) = self.reflect(kind, &cache).await {
+				warn!(
+					resource = kind.resource,
+					"failed to watch Kubernetes resources: {e:#}"
+				);
+				tokio::time::sleep(WATCH_RETRY).await;
+			}
+		}
+	}
+
+	/// List the objects of `kind`, then apply changes to them until the watch can no longer be
+	/// resumed, and they must be listed again.
+	async fn reflect(&self, kind: Kind, cache: &Cache) -> anyhow::Result<()> {
+		let path = match &self.cfg.namespace {
+			Some(ns) => format!(
+				"/apis/{}/{}/namespaces/{ns}/{}",
+				kind.group, kind.version, kind.resource
+			),
+			None => format!("/apis/{}/{}/{}", kind.group, kind.version, kind.resource),
+		};
+		let Some(mut list) = get(&self.client, &path).await? else {
+			// The CRD is not installed; check again later, in case it is installed.
+			debug!("{} are not served by the Kubernetes API", kind.resource);
+			cache.replace(kind, vec![]);
+			tokio::time::sleep(WATCH_RETRY).await;
+			return Ok(());
+		};
+		let mut version = resource_version(&list)
+			.context("list has no resource version")?
+			.to_string();
+		let items = match list.get_mut("items").map(Value::take) {
+			Some(Value::Array(items)) => items,
+			_ => vec![],
+		};
+		cache.replace(kind, items);
+		loop {
+			let query = format!(
+				"{path}?watch=true&allowWatchBookmarks=true&resourceVersion={version}\
+				 &timeoutSeconds={}",
+				WATCH_TIMEOUT.as_secs()
+			);
+			let resp = request(&self.client, &query).await?;
+			if resp.status() == StatusCode::GONE {
+				return Ok(());
+			}
+			if !resp.status().is_success() {
+				anyhow::bail!("kubernetes returned status {}", resp.status());
+			}
+			// Events are sent as one JSON object per line.
+			let mut body = resp.into_body();
+			let mut buf = BytesMut::new();
+			while let Some(frame) = body.frame().await {
+				let Ok(data) = frame?.into_data() else {
+					continue;
+				};
+				buf.extend_from_slice(&data);
+				while let Some(i) = buf.iter().position(|b| *b == b'\n') {
+					let line = buf.split_to(i + 1);
+					let event: WatchEvent = serde_json::from_slice(&line)?;
+					if let Some(v) = resource_version(&event.object) {
+						version = v.to_string();
+					}
+					match event.event_type.as_str() {
+						// The resource version is too old to resume from, so list again.
+						"ERROR" if event.object["code"] == 410 => return Ok(()),
+						"ERROR" => anyhow::bail!("watch failed: {}", event.object["message"]),
+						"BOOKMARK" => {},
+						other => cache.apply(kind, other, event.object),
+					}
+				}
+			}
+		}
+	}
+
+	async fn sync(&self, objects: &Objects, state: &mut SyncState) -> anyhow::Result<()> {
+		let mut rejected = BTreeMap::new();
+		let resources = parse_resources(&self.secrets, objects, &mut rejected);
+		let config = translate(&self.cfg, &resources);
+		if state.last.as_ref() == Some(&config) {
+			return Ok(());
+		}
+		let mut applied = config.clone();
+		self
+			.reject_invalid_routes(&mut applied, &mut rejected)
+			.await;
+		for (resource, error) in &rejected {
+			if state.rejected.get(resource) != Some(error) {
+				warn!(resource, "rejected Kubernetes resource: {error}");
+				events::publish(events::EventKind::ConfigRejected {
+					source: "kubernetes".to_string(),
+					error: format!("{resource}: {error}"),
+				});
+			}
+		}
+		state.rejected = rejected;
+		let normalized = NormalizedLocalConfig::from_value(
+			self.client.clone(),
+			&self.secrets,
+			self.gateway.clone(),
+			applied,
+		)
+		.await?;
+		state.prev = self.stores.binds.sync_local(
+			normalized.binds,
+			normalized.policies,
+			normalized.backends,
+			state.prev.clone(),
+		);
+		state.last = Some(config);
+		info!(
+			gateways = resources.gateways.len(),
+			routes = resources.routes.len(),
+			rejected = state.rejected.len(),
+			"applied Kubernetes resources"
+		);
+		Ok(())
+	}
+
+	/// Remove the routes that cannot be loaded on their own, such as those with an invalid match or
+	/// policy, so an invalid route does not prevent the others from being applied.
+	async fn reject_invalid_routes(
+		&self,
+		config: &mut Value,
+		rejected: &mut BTreeMap<String, String>,
+	) {
+		for bind in config["binds"].as_array_mut().into_iter().flatten() {
+			let port = bind["port"].clone();
+			for listener in bind["listeners"].as_array_mut().into_iter().flatten() {
+				let Some(routes) = listener["routes"].as_array_mut() else {
+					continue;
+				};
+				let mut valid = Vec::with_capacity(routes.len());
+				for route in routes.drain(..) {
+					let single = json!({
+						"binds": [{"port": port, "listeners": [{"routes": [&route]}]}],
+					});
+					match NormalizedLocalConfig::from_value(
+						self.client.clone(),
+						&self.secrets,
+						self.gateway.clone(),
+						single,
+					)
+					.await
+					{
+						Ok(_) => valid.push(route),
+						Err(e) => {
+							let resource = format!(
+								"HTTPRoute {}/{} rule {}",
+								route["namespace"].as_str().unwrap_or_default(),
+								route["name"].as_str().unwrap_or_default(),
+								route["ruleName"].as_str().unwrap_or_default(),
+							);
+							rejected.insert(resource, format!("{e:#}"));
+						},
+					}
+				}
+				*routes = valid;
+			}
+		}
+	}
+}
+
+/// Deserialize the objects of each kind, rejecting those that are malformed, or that reference
+/// secrets they may not.
+fn parse_resources(
+	secrets: &secrets::Resolver,
+	objects: &Objects,
+	rejected: &mut BTreeMap<String, String>,
+) -> Resources {
+	let (policies, rejected_policies) = deserialize(secrets, objects, POLICY, rejected);
+	Resources {
+		gateways: deserialize(secrets, objects, GATEWAY, rejected).0,
+		routes: deserialize(secrets, objects, HTTP_ROUTE, rejected).0,
+		reference_grants: deserialize(secrets, objects, REFERENCE_GRANT, rejected).0,
+		backends: deserialize(secrets, objects, BACKEND, rejected).0,
+		policies,
+		rejected_policies,
+	}
+}
+
+/// Deserialize the objects of a kind. Returns the accepted objects, and those that were rejected
+/// for their secret references.
+fn deserialize<T: DeserializeOwned>(
+	secrets: &secrets::Resolver,
+	objects: &Objects,
+	kind: Kind,
+	rejected: &mut BTreeMap<String, String>,
+) -> (Vec<Object<T>>, Vec<Object<T>>) {
+	let mut accepted = vec![];
+	let mut invalid = vec![];
+	for ((ns, name), o) in objects.get(&kind).into_iter().flatten() {
+		let resource = format!("{} {ns}/{name}", kind.kind);
+		let object = match serde_json::from_value::<Object<T>>(o.clone()) {
+			Ok(object) => object,
+			Err(e) => {
+				rejected.insert(resource, e.to_string());
+				continue;
+			},
+		};
+		match check_secrets(secrets, ns, &o["spec"]) {
+			Ok(()) => accepted.push(object),
+			Err(e) => {
+				rejected.insert(resource, format!("{e:#}"));
+				invalid.push(object);
+			},
+		}
+	}
+	(accepted, invalid)
+}
+
+/// Check that the secret references in a resource only name Kubernetes secrets in its own
+/// namespace, so resources cannot read the gateway's environment, or secrets their authors may not.
+fn check_secrets(secrets: &secrets::Resolver, namespace: &str, spec: &Value) -> anyhow::Result<()> {
+	for r in secrets.references(spec)? {
+		let own = r.provider == "k8s"
+			&& r.path.split_once('/').is_some_and(|(ns, name)| {
+				ns == namespace
+					&& name
+						.chars()
+						.all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+			});
+		if !own {
+			anyhow::bail!("secret {r} is not a Kubernetes secret in namespace {namespace}");
+		}
+	}
+	Ok(())
+}
+
+/// Translate Gateway API resources into the local config format.
+fn translate(cfg: &Config, r: &Resources) -> Value {
+	let backends: HashMap<(&str, &str), &Value> = r
+		.backends
+		.iter()
+		.map(|b| {
+			(
+				(b.metadata.namespace.as_str(), b.metadata.name.as_str()),
+				&b.spec,
+			)
+		})
+		.collect();
+	// Binds are keyed by port, so listeners of different Gateways can share a port.
+	let mut binds: BTreeMap<u16, Vec<Value>> = BTreeMap::new();
+	for gw in r
+		.gateways
+		.iter()
+		.filter(|gw| gw.spec.gateway_class_name == cfg.gateway_class_name)
+	{
+		for l in &gw.spec.listeners {
+			if l.protocol != "HTTP" {
+				warn!(
+					gateway = %gw.metadata.name,
+					listener = %l.name,
+					protocol = %l.protocol,
+					"skipping listener with unsupported protocol"
+				);
+				continue;
+			}
+			let routes = r
+				.routes
+				.iter()
+				.filter(|route| attached(gw, l, route))
+				.flat_map(|route| translate_route(route, &backends, r))
+				.collect::<Vec<_>>();
+			let mut listener = json!({
+				"name": format!("{}/{}/{}", gw.metadata.namespace, gw.metadata.name, l.name),
+				"protocol": "HTTP",
+				"routes": routes,
+			});
+			if let Some(h) = &l.hostname {
+				listener["hostname"] = json!(h);
+			}
+			binds.entry(l.port).or_default().push(listener);
+		}
+	}
+	json!({
+		"binds": binds
+			.into_iter()
+			.map(|(port, listeners)| json!({"port": port, "listeners": listeners}))
+			.collect::<Vec<_>>(),
+	})
+}
+
+/// Whether a route is attached to a listener of a Gateway.
+fn attached(gw: &Object<GatewaySpec>, l: &GatewayListener, route: &Object<HTTPRouteSpec>) -> bool {
+	let same_namespace = route.metadata.namespace == gw.metadata.namespace;
+	let from = l
+		.allowed_routes
+		.as_ref()
+		.and_then(|a| a.namespaces.as_ref())
+		.and_then(|n| n.from.as_deref())
+		.unwrap_or("Same");
+	let allowed = match from {
+		"All" => true,
+		"Same" => same_namespace,
+		// Namespace labels are not read, so selectors never match.
+		_ => false,
+	};
+	allowed
+		&& route.spec.parent_refs.iter().any(|p| {
+			p.group.as_deref().unwrap_or(GATEWAY_GROUP) == GATEWAY_GROUP
+				&& p.kind.as_deref().unwrap_or("Gateway") == "Gateway"
+				&& p.name == gw.metadata.name
+				&& p.namespace.as_ref().unwrap_or(&route.metadata.namespace) == &gw.metadata.namespace
+				&& p.section_name.as_ref().is_none_or(|s| s == &l.name)
+				&& p.port.is_none_or(|port| port == l.port)
+		})
+}
+
+/// Translate each rule of an HTTPRoute into a local route. Rules targeted by a rejected policy are
+/// skipped.
+fn translate_route(
+	route: &Object<HTTPRouteSpec>,
+	backends: &HashMap<(&str, &str), &Value>,
+	r: &Resources,
+) -> Vec<Value> {
+	let ns = route.metadata.namespace.as_str();
+	route
+		.spec
+		.rules
+		.iter()
+		.enumerate()
+		.filter_map(|(idx, rule)| {
+			let rule_name = rule
+				.name
+				.clone()
+				.unwrap_or_else(|| strng::format!("rule{idx}"));
+			let targets = |p: &&Object<PolicySpec>| {
+				p.metadata.namespace == ns
+					&& p.spec.target_refs.iter().any(|t| {
+						t.group.as_deref() == Some(GATEWAY_GROUP)
+							&& t.kind == "HTTPRoute"
+							&& t.name == route.metadata.name
+							&& t.section_name.as_ref().is_none_or(|s| s == &rule_name)
+					})
+			};
+			if let Some(p) = r.rejected_policies.iter().find(targets) {
+				warn!(
+					route = %route.metadata.name,
+					namespace = ns,
+					rule = %rule_name,
+					policy = %p.metadata.name,
+					"skipping route targeted by a rejected policy"
+				);
+				return None;
+			}
+			let mut route_policies = Map::new();
+			for f in &rule.filters {
+				translate_filter(f, &mut route_policies);
+			}
+			// Policies are applied in name order; the first to set a policy wins.
+			let mut attached = r.policies.iter().filter(targets).collect::<Vec<_>>();
+			attached.sort_by(|a, b| a.metadata.name.cmp(&b.metadata.name));
+			for p in attached {
+				for (k, v) in &p.spec.policies {
+					route_policies.entry(k.clone()).or_insert_with(|| v.clone());
+				}
+			}
+			let mut out = json!({
+				"name": route.metadata.name,
+				"namespace": ns,
+				"ruleName": rule_name,
+				"hostnames": route.spec.hostnames,
+				"matches": translate_matches(&rule.matches),
+				"backends": rule
+					.backend_refs
+					.iter()
+					.filter_map(|b| translate_backend(ns, b, backends, &r.reference_grants))
+					.collect::<Vec<_>>(),
+			});
+			if !route_policies.is_empty() {
+				out["policies"] = Value::Object(route_policies);
+			}
+			Some(out)
+		})
+		.collect()
+}
+
+fn translate_matches(matches: &[HTTPRouteMatch]) -> Vec<Value> {
+	let default_path = || json!({"pathPrefix": "/"});
+	if matches.is_empty() {
+		return vec![json!({ "path": default_path() })];
+	}
+	matches
+		.iter()
+		.map(|m| {
+			let path = m
+				.path
+				.as_ref()
+				.map(|p| {
+					let value = p.value.as_deref().unwrap_or("/");
+					match p.match_type.as_deref().unwrap_or("PathPrefix") {
+						"Exact" => json!({ "exact": value }),
+						"RegularExpression" => json!({ "regex": [value, value.len()] }),
+						_ => json!({ "pathPrefix": value }),
+					}
+				})
+				.unwrap_or_else(default_path);
+			let mut out = json!({
+				"path": path,
+				"headers": m.headers.iter().map(translate_value_match).collect::<Vec<_>>(),
+				"query": m.query_params.iter().map(translate_value_match).collect::<Vec<_>>(),
+			});
+			if let Some(method) = &m.method {
+				out["method"] = json!(method);
+			}
+			out
+		})
+		.collect()
+}
+
+fn translate_value_match(m: &TypedMatch) -> Value {
+	let value = m.value.as_deref().unwrap_or_default();
+	let value = match m.match_type.as_deref().unwrap_or("Exact") {
+		"RegularExpression" => json!({ "regex": value }),
+		_ => json!({ "exact": value }),
+	};
+	json!({ "name": m.name, "value": value })
+}
+
+/// Translate a route filter into local route policies. Unsupported filters are skipped.
+fn translate_filter(f: &Value, policies: &mut Map<String, Value>) {
+	let header_modifier = |v: &Value| {
+		let pairs = |key: &str| -> Map<String, Value> {
+			v[key]
+				.as_array()
+				.into_iter()
+				.flatten()
+				.filter_map(|h| Some((h["name"].as_str()?.to_string(), h["value"].clone())))
+				.collect()
+		};
+		json!({
+			"add": pairs("add"),
+			"set": pairs("set"),
+			"remove": v.get("remove").cloned().unwrap_or(json!([])),
+		})
+	};
+	match f["type"].as_str().unwrap_or_default() {
+		"RequestHeaderModifier" => {
+			policies.insert(
+				"requestHeaderModifier".to_string(),
+				header_modifier(&f["requestHeaderModifier"]),
+			);
+		},
+		"ResponseHeaderModifier" => {
+			policies.insert(
+				"responseHeaderModifier".to_string(),
+				header_modifier(&f["responseHeaderModifier"]),
+			);
+		},
+		"RequestRedirect" => {
+			let v = &f["requestRedirect"];
+			let mut out = Map::new();
+			if let Some(s) = v["scheme"].as_str() {
+				out.insert("scheme".to_string(), json!(s));
+			}
+			if let Some(a) = translate_authority(v) {
+				out.insert("authority".to_string(), a);
+			}
+			if let Some(p) = translate_path_modifier(&v["path"]) {
+				out.insert("path".to_string(), p);
+			}
+			if let Some(s) = v["statusCode"].as_u64() {
+				out.insert("status".to_string(), json!(s));
+			}
+			policies.insert("requestRedirect".to_string(), Value::Object(out));
+		},
+		"URLRewrite" => {
+			let v = &f["urlRewrite"];
+			let mut out = Map::new();
+			if let Some(a) = translate_authority(v) {
+				out.insert("authority".to_string(), a);
+			}
+			if let Some(p) = translate_path_modifier(&v["path"]) {
+				out.insert("path".to_string(), p);
+			}
+			policies.insert("urlRewrite".to_string(), Value::Object(out));
+		},
+		other => warn!(filter = other, "skipping unsupported route filter"),
+	}
+}
+
+fn translate_authority(v: &Value) -> Option<Value> {
+	match (v["hostname"].as_str(), v["port"].as_u64()) {
+		(Some(h), Some(p)) => Some(json!({ "full": format!("{h}:{p}") })),
+		(Some(h), None) => Some(json!({ "host": h })),
+		(None, Some(p)) => Some(json!({ "port": p })),
+		(None, None) => None,
+	}
+}
+
+fn translate_path_modifier(v: &Value) -> Option<Value> {
+	match v["type"].as_str()? {
+		"ReplaceFullPath" => Some(json!({ "full": v["replaceFullPath"] })),
+		"ReplacePrefixMatch" => Some(json!({ "prefix": v["replacePrefixMatch"] })),
+		_ => None,
+	}
+}
+
+/// Translate a backend reference into a local route backend. References to backends that do not
+/// exist, or that are in another namespace without a ReferenceGrant allowing them, are translated
+/// to an invalid backend, so requests to them fail.
+fn translate_backend(
+	route_namespace: &str,
+	b: &BackendRef,
+	backends: &HashMap<(&str, &str), &Value>,
+	grants: &[Object<ReferenceGrantSpec>],
+) -> Option<Value> {
+	let weight = b.weight.unwrap_or(1);
+	if weight == 0 {
+		return None;
+	}
+	let ns = b.namespace.as_deref().unwrap_or(route_namespace);
+	let group = b.group.as_deref().unwrap_or_default();
+	let kind = b.kind.as_deref().unwrap_or("Service");
+	let invalid = || json!({ "invalid": null });
+	let mut out = match (group, kind, b.port) {
+		_ if !granted(grants, route_namespace, ns, group, kind, &b.name) => {
+			warn!(
+				backend = %b.name,
+				namespace = ns,
+				route_namespace,
+				"backend reference is not allowed by a ReferenceGrant"
+			);
+			invalid()
+		},
+		("", "Service", Some(port)) => {
+			json!({ "host": format!("{}.{ns}.svc.cluster.local:{port}", b.name) })
+		},
+		(AGENTGATEWAY_GROUP, "AgentgatewayBackend", _) => match backends.get(&(ns, b.name.as_str())) {
+			Some(spec) => (*spec).clone(),
+			None => {
+				warn!(backend = %b.name, namespace = ns, "backend not found");
+				invalid()
+			},
+		},
+		_ => {
+			warn!(backend = %b.name, group, kind, "unsupported backend reference");
+			invalid()
+		},
+	};
+	if !out.is_object() {
+		warn!(backend = %b.name, namespace = ns, "backend spec is not an object");
+		out = invalid();
+	}
+	out["weight"] = json!(weight);
+	Some(out)
+}
+
+/// Whether an HTTPRoute in namespace `from` may reference a backend in namespace `to`: either they
+/// are in the same namespace, or a ReferenceGrant in `to` allows it.
+fn granted(
+	grants: &[Object<ReferenceGrantSpec>],
+	from: &str,
+	to: &str,
+	group: &str,
+	kind: &str,
+	name: &str,
+) -> bool {
+	let from_route = |f: &ReferenceGrantFrom| {
+		f.group == GATEWAY_GROUP && f.kind == HTTP_ROUTE.kind && f.namespace == from
+	};
+	let to_backend = |t: &ReferenceGrantTo| {
+		t.group == group && t.kind == kind && t.name.as_ref().is_none_or(|n| n == name)
+	};
+	from == to
+		|| grants.iter().any(|g| {
+			g.metadata.namespace == to
+				&& g.spec.from.iter().any(from_route)
+				&& g.spec.to.iter().any(to_backend)
+		})
+}
@@ -0,0 +1,379 @@
+use super::*;
+use crate::test_helpers::proxymock::setup_proxy_test;
+
+fn object<T: DeserializeOwned>(v: Value) -> Object<T> {
+	serde_json::from_value(v).unwrap()
+}
+
+fn resources() -> Resources {
+	Resources {
+		gateways: vec![
+			object(json!({
+				"metadata": {"name": "gw", "namespace": "apps"},
+				"spec": {
+					"gatewayClassName": "agentgateway",
+					"listeners": [
+						{"name": "http", "port": 8080, "protocol": "HTTP"},
+						{"name": "public", "port": 9090, "protocol": "HTTP", "hostname": "*.example.com",
+							"allowedRoutes": {"namespaces": {"from": "All"}}},
+						{"name": "https", "port": 8443, "protocol": "HTTPS"},
+					],
+				},
+			})),
+			object(json!({
+				"metadata": {"name": "other", "namespace": "apps"},
+				"spec": {
+					"gatewayClassName": "istio",
+					"listeners": [{"name": "http", "port": 80, "protocol": "HTTP"}],
+				},
+			})),
+		],
+		routes: vec![
+			object(json!({
+				"metadata": {"name": "api", "namespace": "apps"},
+				"spec": {
+					"parentRefs": [{"name": "gw", "sectionName": "http"}],
+					"hostnames": ["api.example.com"],
+					"rules": [
+						{
+							"name": "mcp",
+							"matches": [{
+								"path": {"type": "PathPrefix", "value": "/mcp"},
+								"headers": [{"name": "x-team", "value": "a", "type": "Exact"}],
+								"method": "POST",
+							}],
+							"filters": [{
+								"type": "RequestHeaderModifier",
+								"requestHeaderModifier": {
+									"set": [{"name": "x-gateway", "value": "agentgateway"}],
+									"remove": ["x-debug"],
+								},
+							}],
+							"backendRefs": [{
+								"group": "agentgateway.dev",
+								"kind": "AgentgatewayBackend",
+								"name": "tools",
+							}],
+						},
+						{
+							"backendRefs": [
+								{"name": "web", "port": 80, "weight": 3},
+								{"name": "canary", "port": 80, "weight": 0},
+							],
+						},
+					],
+				},
+			})),
+			// Attached to a listener in another namespace that allows all namespaces
+			object(json!({
+				"metadata": {"name": "docs", "namespace": "docs"},
+				"spec": {
+					"parentRefs": [{"name": "gw", "namespace": "apps"}],
+					"rules": [{"backendRefs": [{"name": "missing", "kind": "Secret"}]}],
+				},
+			})),
+		],
+		backends: vec![object(json!({
+			"metadata": {"name": "tools", "namespace": "apps"},
+			"spec": {"mcp": {"targets": [{"name": "everything", "stdio": {"cmd": "npx"}}]}},
+		}))],
+		policies: vec![object(json!({
+			"metadata": {"name": "mcp-authz", "namespace": "apps"},
+			"spec": {
+				"targetRefs": [{
+					"group": "gateway.networking.k8s.io",
+					"kind": "HTTPRoute",
+					"name": "api",
+					"sectionName": "mcp",
+				}],
+				"policies": {"mcpAuthorization": {"rules": ["true"]}},
+			},
+		}))],
+		..Default::default()
+	}
+}
+
+fn cfg() -> Config {
+	serde_json::from_value(json!({})).unwrap()
+}
+
+#[test]
+fn test_translate() {
+	let out = translate(&cfg(), &resources());
+	let binds = out["binds"].as_array().unwrap();
+	// Only HTTP listeners of the agentgateway class are served
+	assert_eq!(
+		binds
+			.iter()
+			.map(|b| b["port"].as_u64().unwrap())
+			.collect::<Vec<_>>(),
+		vec![8080, 9090]
+	);
+
+	let routes = binds[0]["listeners"][0]["routes"].as_array().unwrap();
+	assert_eq!(binds[0]["listeners"][0]["name"], "apps/gw/http");
+	assert_eq!(routes.len(), 2);
+	assert_eq!(
+		routes[0],
+		json!({
+			"name": "api",
+			"namespace": "apps",
+			"ruleName": "mcp",
+			"hostnames": ["api.example.com"],
+			"matches": [{
+				"path": {"pathPrefix": "/mcp"},
+				"headers": [{"name": "x-team", "value": {"exact": "a"}}],
+				"query": [],
+				"method": "POST",
+			}],
+			"policies": {
+				"requestHeaderModifier": {
+					"add": {},
+					"set": {"x-gateway": "agentgateway"},
+					"remove": ["x-debug"],
+				},
+				"mcpAuthorization": {"rules": ["true"]},
+			},
+			"backends": [{
+				"mcp": {"targets": [{"name": "everything", "stdio": {"cmd": "npx"}}]},
+				"weight": 1,
+			}],
+		})
+	);
+	// Unnamed rules are numbered, and backends with no weight are dropped
+	assert_eq!(routes[1]["ruleName"], "rule1");
+	assert_eq!(routes[1]["matches"], json!([{"path": {"pathPrefix": "/"}}]));
+	assert_eq!(
+		routes[1]["backends"],
+		json!([{"host": "web.apps.svc.cluster.local:80", "weight": 3}])
+	);
+
+	// The route in another namespace only attaches to the listener allowing it, and the route bound
+	// to the `http` listener does not attach to the others
+	let public = &binds[1]["listeners"][0];
+	assert_eq!(public["hostname"], "*.example.com");
+	let names = public["routes"]
+		.as_array()
+		.unwrap()
+		.iter()
+		.map(|r| r["name"].as_str().unwrap())
+		.collect::<Vec<_>>();
+	assert_eq!(names, vec!["docs"]);
+	assert_eq!(
+		public["routes"][0]["backends"],
+		json!([{"invalid": null, "weight": 1}])
+	);
+}
+
+#[test]
+fn test_translate_gateway_class() {
+	let r = resources();
+	let cfg: Config = serde_json::from_value(json!({"gatewayClassName": "istio"})).unwrap();
+	let out = translate(&cfg, &r);
+	assert_eq!(out["binds"][0]["port"], 80);
+	assert_eq!(out["binds"][0]["listeners"][0]["routes"], json!([]));
+}
+
+#[tokio::test]
+async fn test_translated_config_loads() {
+	let t = setup_proxy_test("{}").expect("setup proxy inputs");
+	let out = translate(&cfg(), &resources());
+	let normalized = NormalizedLocalConfig::from_value(
+		t.inputs().upstream.clone(),
+		&secrets::Resolver::new(t.inputs().upstream.clone()),
+		ListenerTarget {
+			gateway_name: "default".into(),
+			gateway_namespace: "default".into(),
+			listener_name: None,
+		},
+		out,
+	)
+	.await
+	.unwrap();
+	assert_eq!(normalized.binds.len(), 2);
+	let listener = normalized.binds[0].listeners.iter().next().unwrap();
+	// Each rule is a separate route
+	assert!(
+		listener
+			.routes
+			.contains(&strng::new("default/default/apps/gw/http/apps/api/mcp"))
+	);
+	assert!(
+		listener
+			.routes
+			.contains(&strng::new("default/default/apps/gw/http/apps/api/rule1"))
+	);
+}
+
+#[test]
+fn test_translate_reference_grant() {
+	let mut r = resources();
+	r.routes = vec![object(json!({
+		"metadata": {"name": "api", "namespace": "apps"},
+		"spec": {
+			"parentRefs": [{"name": "gw"}],
+			"rules": [{"backendRefs": [
+				{"name": "web", "namespace": "shared", "port": 80},
+				{"name": "db", "namespace": "shared", "port": 5432},
+			]}],
+		},
+	}))];
+	let backends = |r: &Resources| {
+		translate(&cfg(), r)["binds"][0]["listeners"][0]["routes"][0]["backends"].clone()
+	};
+	// Backends in another namespace may not be referenced without a ReferenceGrant
+	assert_eq!(
+		backends(&r),
+		json!([{"invalid": null, "weight": 1}, {"invalid": null, "weight": 1}])
+	);
+
+	r.reference_grants = vec![object(json!({
+		"metadata": {"name": "apps", "namespace": "shared"},
+		"spec": {
+			"from": [{
+				"group": "gateway.networking.k8s.io",
+				"kind": "HTTPRoute",
+				"namespace": "apps",
+			}],
+			"to": [{"group": "", "kind": "Service", "name": "web"}],
+		},
+	}))];
+	assert_eq!(
+		backends(&r),
+		json!([
+			{"host": "web.shared.svc.cluster.local:80", "weight": 1},
+			{"invalid": null, "weight": 1},
+		])
+	);
+}
+
+#[test]
+fn test_check_secrets() {
+	let t = setup_proxy_test("{}").expect("setup proxy inputs");
+	let secrets = secrets::Resolver::new(t.inputs().upstream.clone());
+	let spec = |s: &str| json!({"ai": {"provider": {"openAI": {}}, "apiKey": s}});
+	check_secrets(&secrets, "apps", &spec("${secret:k8s:apps/openai#key}")).unwrap();
+	for s in [
+		"${secret:k8s:other/openai#key}",
+		"${secret:k8s:apps/openai/../x#key}",
+		"${secret:env:OPENAI_API_KEY}",
+		"${secret:vault:kv/data/openai#key}",
+	] {
+		assert!(check_secrets(&secrets, "apps", &spec(s)).is_err(), "{s}");
+	}
+}
+
+#[test]
+fn test_rejected_resources() {
+	let t = setup_proxy_test("{}").expect("setup proxy inputs");
+	let secrets = secrets::Resolver::new(t.inputs().upstream.clone());
+	let cache = Cache::default();
+	for kind in KINDS {
+		cache.replace(kind, vec![]);
+	}
+	cache.replace(
+		HTTP_ROUTE,
+		vec![
+			json!({
+				"metadata": {"name": "api", "namespace": "apps"},
+				"spec": {
+					"parentRefs": [{"name": "gw"}],
+					"rules": [{"name": "mcp"}, {"name": "web"}],
+				},
+			}),
+			json!({"metadata": {"name": "broken", "namespace": "apps"}, "spec": {"rules": 1}}),
+		],
+	);
+	cache.replace(
+		POLICY,
+		vec![json!({
+			"metadata": {"name": "leak", "namespace": "apps"},
+			"spec": {
+				"targetRefs": [{
+					"group": "gateway.networking.k8s.io",
+					"kind": "HTTPRoute",
+					"name": "api",
+					"sectionName": "mcp",
+				}],
+				"policies": {"requestHeaderModifier": {"set": {"x-key": "${secret:env:API_KEY}"}}},
+			},
+		})],
+	);
+	let mut rejected = BTreeMap::new();
+	let mut r = parse_resources(&secrets, &cache.snapshot().unwrap(), &mut rejected);
+	assert_eq!(
+		rejected.keys().collect::<Vec<_>>(),
+		vec!["AgentgatewayPolicy apps/leak", "HTTPRoute apps/broken"]
+	);
+	assert_eq!(r.routes.len(), 1);
+	assert_eq!(r.rejected_policies.len(), 1);
+
+	// The rule the rejected policy targets is not served, but the others are
+	r.gateways = resources().gateways;
+	let routes = translate(&cfg(), &r)["binds"][0]["listeners"][0]["routes"].clone();
+	assert_eq!(routes.as_array().unwrap().len(), 1);
+	assert_eq!(routes[0]["ruleName"], "web");
+}
+
+#[test]
+fn test_cache() {
+	let cache = Cache::default();
+	let gateway = |name: &str, version: &str| {
+		json!({"metadata": {"name": name, "namespace": "apps", "resourceVersion": version}})
+	};
+	cache.replace(GATEWAY, vec![gateway("a", "1")]);
+	// Nothing is served until every kind has been listed
+	assert!(cache.snapshot().is_none());
+	for kind in KINDS.into_iter().filter(|k| *k != GATEWAY) {
+		cache.replace(kind, vec![]);
+	}
+	cache.apply(GATEWAY, "ADDED", gateway("b", "2"));
+	cache.apply(GATEWAY, "MODIFIED", gateway("a", "3"));
+	cache.apply(GATEWAY, "DELETED", gateway("b", "4"));
+	let objects = cache.snapshot().unwrap();
+	let gateways = objects[&GATEWAY].values().collect::<Vec<_>>();
+	assert_eq!(gateways, vec![&gateway("a", "3")]);
+}
+
+#[tokio::test]
+async fn test_reject_invalid_routes() {
+	let t = setup_proxy_test("{}").expect("setup proxy inputs");
+	let controller = Controller {
+		cfg: cfg(),
+		stores: Stores::new(),
+		client: t.inputs().upstream.clone(),
+		secrets: secrets::Resolver::new(t.inputs().upstream.clone()),
+		gateway: ListenerTarget {
+			gateway_name: "default".into(),
+			gateway_namespace: "default".into(),
+			listener_name: None,
+		},
+	};
+	let mut r = resources();
+	r.policies.push(object(json!({
+		"metadata": {"name": "bad-cel", "namespace": "apps"},
+		"spec": {
+			"targetRefs": [{
+				"group": "gateway.networking.k8s.io",
+				"kind": "HTTPRoute",
+				"name": "api",
+				"sectionName": "rule1",
+			}],
+			"policies": {"mcpAuthorization": {"rules": ["this is not cel"]}},
+		},
+	})));
+	let mut config = translate(&cfg(), &r);
+	let mut rejected = BTreeMap::new();
+	controller
+		.reject_invalid_routes(&mut config, &mut rejected)
+		.await;
+	assert_eq!(
+		rejected.keys().collect::<Vec<_>>(),
+		vec!["HTTPRoute apps/api rule rule1"]
+	);
+	let routes = config["binds"][0]["listeners"][0]["routes"]
+		.as_array()
+		.unwrap();
+	assert_eq!(routes.len(), 1);
+	assert_eq!(routes[0]["ruleName"], "mcp");
+}
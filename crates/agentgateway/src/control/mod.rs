@@ -21,6 +21,9 @@ use crate::types::agent::Target;
 use crate::*;
 
 pub mod caclient;
+pub mod consul;
+pub mod kubernetes;
+pub mod remote;
 
 #[derive(serde::Serialize, Clone, Debug, PartialEq, Eq)]
 pub enum RootCert {
@@ -49,14 +52,21 @@ impl RootCert {
 			},
 			RootCert::Default => return Ok(SYSTEM_TRUST.clone()),
 		};
-		let mut ccb = ClientConfig::builder_with_provider(transport::tls::provider())
+		let provider = transport::tls::provider();
+		let mut ccb = ClientConfig::builder_with_provider(provider.clone())
 			.with_protocol_versions(transport::tls::ALL_TLS_VERSIONS)?
 			.with_root_certificates(roots)
 			.with_no_client_auth();
 		ccb.alpn_protocols = vec![b"h2".to_vec()];
+		let settings = transport::tls::TLSSettings::new(
+			transport::tls::ALL_TLS_VERSIONS,
+			&provider,
+			&ccb.alpn_protocols,
+		);
 		Ok(BackendTLS {
 			hostname_override: None,
 			config: PerAlpnConfig::new(Arc::new(ccb), false),
+			settings: Arc::new(settings),
 		})
 	}
 }
@@ -278,6 +288,44 @@ impl agent_xds::ClientTrait for GrpcChannel {
 	}
 }
 
+/// GET a URL, verifying HTTPS servers with the system trust roots. The port defaults to that of the
+/// scheme.
+pub(crate) async fn http_get(
+	client: &client::Client,
+	url: &str,
+	headers: ::http::HeaderMap,
+) -> anyhow::Result<http::Response> {
+	let uri: Uri = url.parse()?;
+	let tls = match uri.scheme_str() {
+		Some("https") => true,
+		Some("http") => false,
+		_ => anyhow::bail!("url must be http or https: {url}"),
+	};
+	let host = uri
+		.host()
+		.ok_or_else(|| anyhow::anyhow!("url has no host: {url}"))?;
+	let port = uri.port_u16().unwrap_or(if tls { 443 } else { 80 });
+	let target = Target::try_from((host, port))?;
+	let mut req = ::http::Request::builder()
+		.uri(uri)
+		.body(http::Body::empty())?;
+	*req.headers_mut() = headers;
+	let transport = if tls {
+		ApplicationTransport::Tls(SYSTEM_TRUST.base_config())
+	} else {
+		ApplicationTransport::Plaintext
+	};
+	Ok(
+		client
+			.call(client::Call {
+				req,
+				target,
+				transport: transport.into(),
+			})
+			.await?,
+	)
+}
+
 fn get_target(raw: &str, ca: BackendTLS) -> anyhow::Result<(Target, Transport)> {
 	let uri = raw.parse::<Uri>()?;
 
@@ -0,0 +1,221 @@
+//! Remote config mode.
+//!
+//! Instead of a local file, agentgateway can poll its config, in the same format, from an HTTP(S)
+//! server such as an artifact store. This suits fleets that publish config as a static file rather
+//! than running an XDS control plane.
+//!
+//! Every version must be signed with Ed25519. The signature of the config file is served alongside
+//! it, base64 encoded, at `<url>.sig` unless configured otherwise. A config whose signature is
+//! missing or does not verify is rejected, and the previously applied config is kept.
+//!
+//! Every version must also set a `serial` that increases with each version published. A config
+//! whose serial is not newer than the one applied is rejected, so a replayed older config, though
+//! validly signed, cannot roll the gateway back.
+//!
+//! Requests carry the `ETag` of the last applied config in `If-None-Match`, so an unchanged config
+//! is not downloaded again.
+
+use ::http::{HeaderMap, HeaderValue, StatusCode, header};
+use aws_lc_rs::signature::{ED25519, UnparsedPublicKey};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use macro_rules_attribute::apply;
+
+use crate::client::Client;
+use crate::control::http_get;
+use crate::http::Response;
+use crate::state_manager::PreviousState;
+use crate::store::Stores;
+use crate::telemetry::events;
+use crate::types::agent::ListenerTarget;
+use crate::types::local::NormalizedLocalConfig;
+use crate::*;
+
+#[cfg(test)]
+#[path = "remote_tests.rs"]
+mod tests;
+
+/// Serve a config file polled from an HTTP(S) URL, verified with an Ed25519 signature.
+#[apply(schema!)]
+pub struct Config {
+	/// The URL of the config file.
+	pub url: String,
+	/// The URL of the base64 encoded signature of the config file. Defaults to `url` with `.sig`
+	/// appended.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub signature_url: Option<String>,
+	/// The Ed25519 public key that signs the config, base64 encoded.
+	pub public_key: String,
+	/// How often the config is polled. Defaults to 30s.
+	#[serde(default = "default_poll_interval", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub poll_interval: Duration,
+}
+
+fn default_poll_interval() -> Duration {
+	Duration::from_secs(30)
+}
+
+impl Config {
+	fn signature_url(&self) -> String {
+		self
+			.signature_url
+			.clone()
+			.unwrap_or_else(|| format!("{}.sig", self.url))
+	}
+
+	fn public_key(&self) -> anyhow::Result<Vec<u8>> {
+		let key = STANDARD
+			.decode(self.public_key.trim())
+			.context("public key is not valid base64")?;
+		if key.len() != 32 {
+			anyhow::bail!(
+				"public key must be a 32 byte Ed25519 key, got {} bytes",
+				key.len()
+			);
+		}
+		Ok(key)
+	}
+}
+
+/// Verify the base64 encoded Ed25519 `signature` of `config`.
+fn verify(public_key: &[u8], config: &[u8], signature: &[u8]) -> anyhow::Result<()> {
+	let signature = STANDARD
+		.decode(signature.trim_ascii())
+		.context("signature is not valid base64")?;
+	UnparsedPublicKey::new(&ED25519, public_key)
+		.verify(config, &signature)
+		.map_err(|_| anyhow::anyhow!("signature does not match the config"))
+}
+
+/// A fetched config.
+#[derive(Debug)]
+struct Fetched {
+	config: Bytes,
+	etag: Option<HeaderValue>,
+}
+
+/// The config most recently applied.
+#[derive(Debug, Default)]
+struct Applied {
+	prev: PreviousState,
+	etag: Option<HeaderValue>,
+	serial: Option<u64>,
+	config: Bytes,
+}
+
+pub struct Controller {
+	pub cfg: Config,
+	pub stores: Stores,
+	pub client: Client,
+	pub secrets: secrets::Resolver,
+	pub gateway: ListenerTarget,
+}
+
+impl Controller {
+	/// Start polling the config in the background.
+	pub fn run(self) -> anyhow::Result<()> {
+		let public_key = self.cfg.public_key()?;
+		tokio::task::spawn(async move {
+			let mut applied = Applied::default();
+			let mut interval = tokio::time::interval(self.cfg.poll_interval);
+			loop {
+				interval.tick().await;
+				if let Err(e) = self.sync(&public_key, &mut applied).await {
+					warn!(url = self.cfg.url, "failed to sync remote config: {e:#}");
+					events::publish(events::EventKind::ConfigRejected {
+						source: "remote".to_string(),
+						error: format!("{e:#}"),
+					});
+				}
+			}
+		});
+		Ok(())
+	}
+
+	async fn sync(&self, public_key: &[u8], applied: &mut Applied) -> anyhow::Result<()> {
+		let Some(fetched) = self.fetch(public_key, applied.etag.as_ref()).await? else {
+			return Ok(());
+		};
+		if fetched.config == applied.config {
+			// The same config, served under a new ETag.
+			applied.etag = fetched.etag;
+			return Ok(());
+		}
+		let config = std::str::from_utf8(&fetched.config).context("config is not valid UTF-8")?;
+		let normalized = NormalizedLocalConfig::from_with_secrets(
+			self.client.clone(),
+			&self.secrets,
+			self.gateway.clone(),
+			config,
+		)
+		.await?;
+		let serial = normalized.serial.context("config does not set a serial")?;
+		if let Some(last) = applied.serial
+			&& serial <= last
+		{
+			anyhow::bail!("config serial {serial} is not newer than the applied serial {last}");
+		}
+		// Check everything that can fail before applying anything, so a bad config cannot leave the
+		// stores partially updated.
+		normalized.check_services()?;
+		let binds = self.stores.binds.sync_local(
+			normalized.binds,
+			normalized.policies,
+			normalized.backends,
+			applied.prev.binds.clone(),
+		);
+		let discovery = self.stores.discovery.sync_local(
+			normalized.services,
+			normalized.workloads,
+			applied.prev.discovery.clone(),
+		)?;
+		*applied = Applied {
+			prev: PreviousState { binds, discovery },
+			etag: fetched.etag,
+			serial: Some(serial),
+			config: fetched.config,
+		};
+		info!(url = self.cfg.url, serial, "applied remote config");
+		Ok(())
+	}
+
+	/// Fetch the config and verify its signature. Returns None if it has not changed since the
+	/// version with `etag`.
+	async fn fetch(
+		&self,
+		public_key: &[u8],
+		etag: Option<&HeaderValue>,
+	) -> anyhow::Result<Option<Fetched>> {
+		let mut headers = HeaderMap::new();
+		if let Some(etag) = etag {
+			headers.insert(header::IF_NONE_MATCH, etag.clone());
+		}
+		let resp = http_get(&self.client, &self.cfg.url, headers).await?;
+		if resp.status() == StatusCode::NOT_MODIFIED {
+			return Ok(None);
+		}
+		if !resp.status().is_success() {
+			anyhow::bail!("config server returned status {}", resp.status());
+		}
+		let etag = resp.headers().get(header::ETAG).cloned();
+		let config = read_body(resp).await?;
+
+		let signature_url = self.cfg.signature_url();
+		let resp = http_get(&self.client, &signature_url, HeaderMap::new()).await?;
+		if !resp.status().is_success() {
+			anyhow::bail!(
+				"failed to fetch signature from {signature_url}: status {}",
+				resp.status()
+			);
+		}
+		let signature = read_body(resp).await?;
+		verify(public_key, &config, &signature)?;
+		Ok(Some(Fetched { config, etag }))
+	}
+}
+
+async fn read_body(resp: Response) -> anyhow::Result<Bytes> {
+	let limit = crate::http::response_buffer_limit(&resp);
+	Ok(crate::http::read_body_with_limit(resp.into_body(), limit).await?)
+}
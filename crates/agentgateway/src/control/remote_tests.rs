@@ -0,0 +1,174 @@
+use aws_lc_rs::rand::SystemRandom;
+use aws_lc_rs::signature::{Ed25519KeyPair, KeyPair};
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use super::*;
+use crate::test_helpers::proxymock::setup_proxy_test;
+
+const CONFIG: &str = r#"{"serial": 1, "binds": [{"port": 8080, "listeners": [{"routes": [
+	{"backends": [{"host": "127.0.0.1:9000"}]}
+]}]}]}"#;
+
+fn keypair() -> Ed25519KeyPair {
+	let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+	Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap()
+}
+
+fn sign(key: &Ed25519KeyPair, config: &str) -> String {
+	STANDARD.encode(key.sign(config.as_bytes()))
+}
+
+fn controller(server: &MockServer, key: &Ed25519KeyPair) -> Controller {
+	let t = setup_proxy_test("{}").expect("setup proxy inputs");
+	let client = t.inputs().upstream.clone();
+	Controller {
+		cfg: Config {
+			url: format!("{}/config.json", server.uri()),
+			signature_url: None,
+			public_key: STANDARD.encode(key.public_key().as_ref()),
+			poll_interval: default_poll_interval(),
+		},
+		stores: Stores::new(),
+		secrets: secrets::Resolver::new(client.clone()),
+		client,
+		gateway: ListenerTarget {
+			gateway_name: "default".into(),
+			gateway_namespace: "default".into(),
+			listener_name: None,
+		},
+	}
+}
+
+async fn serve(server: &MockServer, config: &str, signature: String) {
+	Mock::given(method("GET"))
+		.and(path("/config.json"))
+		.and(header("if-none-match", "\"v1\""))
+		.respond_with(ResponseTemplate::new(304))
+		.with_priority(1)
+		.mount(server)
+		.await;
+	Mock::given(method("GET"))
+		.and(path("/config.json"))
+		.respond_with(
+			ResponseTemplate::new(200)
+				.insert_header("etag", "\"v1\"")
+				.set_body_string(config),
+		)
+		.mount(server)
+		.await;
+	Mock::given(method("GET"))
+		.and(path("/config.json.sig"))
+		.respond_with(ResponseTemplate::new(200).set_body_string(signature))
+		.mount(server)
+		.await;
+}
+
+#[tokio::test]
+async fn test_sync() {
+	let server = MockServer::start().await;
+	let key = keypair();
+	serve(&server, CONFIG, sign(&key, CONFIG)).await;
+
+	let c = controller(&server, &key);
+	let public_key = c.cfg.public_key().unwrap();
+	let mut applied = Applied::default();
+	c.sync(&public_key, &mut applied).await.unwrap();
+	assert_eq!(applied.etag, Some(HeaderValue::from_static("\"v1\"")));
+	assert_eq!(applied.serial, Some(1));
+	assert_eq!(c.stores.binds.read().all().len(), 1);
+
+	// An unchanged config is not downloaded again
+	let fetched = c.fetch(&public_key, applied.etag.as_ref()).await.unwrap();
+	assert!(fetched.is_none());
+}
+
+#[tokio::test]
+async fn test_sync_rejects_invalid_signature() {
+	let server = MockServer::start().await;
+	let key = keypair();
+	serve(&server, CONFIG, sign(&keypair(), CONFIG)).await;
+
+	let c = controller(&server, &key);
+	let public_key = c.cfg.public_key().unwrap();
+	let mut applied = Applied::default();
+	let err = c.sync(&public_key, &mut applied).await.unwrap_err();
+	assert_eq!(err.to_string(), "signature does not match the config");
+	assert!(applied.etag.is_none());
+	assert!(c.stores.binds.read().all().is_empty());
+}
+
+#[tokio::test]
+async fn test_sync_rejects_rollback() {
+	let server = MockServer::start().await;
+	let key = keypair();
+	let c = controller(&server, &key);
+	let public_key = c.cfg.public_key().unwrap();
+	let mut applied = Applied {
+		serial: Some(2),
+		..Default::default()
+	};
+
+	// A validly signed config with an older serial is rejected
+	serve(&server, CONFIG, sign(&key, CONFIG)).await;
+	let err = c.sync(&public_key, &mut applied).await.unwrap_err();
+	assert_eq!(
+		err.to_string(),
+		"config serial 1 is not newer than the applied serial 2"
+	);
+	assert!(applied.etag.is_none());
+	assert!(c.stores.binds.read().all().is_empty());
+
+	// As is one without a serial
+	let unversioned = r#"{"binds": []}"#;
+	server.reset().await;
+	serve(&server, unversioned, sign(&key, unversioned)).await;
+	let err = c.sync(&public_key, &mut applied).await.unwrap_err();
+	assert_eq!(err.to_string(), "config does not set a serial");
+}
+
+#[tokio::test]
+async fn test_sync_validates_before_applying() {
+	let server = MockServer::start().await;
+	let key = keypair();
+	let config = r#"{"serial": 1,
+"binds": [{"port": 8080, "listeners": [{"routes": [{"backends": [{"host": "127.0.0.1:9000"}]}]}]}],
+"workloads": [{
+	"uid": "w1",
+	"name": "w1",
+	"namespace": "default",
+	"serviceAccount": "default",
+	"workloadIps": ["127.0.0.1"],
+	"services": {"not-a-service": {"8080": 8080}}
+}]}"#;
+	serve(&server, config, sign(&key, config)).await;
+
+	let c = controller(&server, &key);
+	let public_key = c.cfg.public_key().unwrap();
+	let mut applied = Applied::default();
+	let err = c.sync(&public_key, &mut applied).await.unwrap_err();
+	assert!(
+		err.to_string().contains("invalid service not-a-service"),
+		"{err}"
+	);
+	// The binds are not applied either
+	assert!(c.stores.binds.read().all().is_empty());
+	assert!(applied.serial.is_none());
+}
+
+#[test]
+fn test_public_key() {
+	let cfg = |public_key: &str| Config {
+		url: "https://example.com/config.yaml".to_string(),
+		signature_url: None,
+		public_key: public_key.to_string(),
+		poll_interval: default_poll_interval(),
+	};
+	assert_eq!(
+		cfg("").signature_url(),
+		"https://example.com/config.yaml.sig"
+	);
+	assert!(cfg(&STANDARD.encode([0u8; 32])).public_key().is_ok());
+	assert!(cfg(&STANDARD.encode([0u8; 16])).public_key().is_err());
+	assert!(cfg("not base64!").public_key().is_err());
+}
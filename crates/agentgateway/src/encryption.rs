@@ -0,0 +1,264 @@
+//! Encryption at rest for state the gateway persists to disk, such as the recovery ledger and
+//! quota usage.
+//!
+//! Data is encrypted with AES-256-GCM. Each encrypted record is stored as
+//! `agwenc1:<key id>:<base64(nonce || ciphertext)>`, where the key id is derived from the key, so
+//! records written under a previous key can still be read after the key is rotated.
+
+use anyhow::Context;
+use aws_lc_rs::aead::{AES_256_GCM, Aad, Nonce, RandomizedNonceKey};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use secrecy::{ExposeSecret, SecretString};
+
+use crate::*;
+
+const PREFIX: &str = "agwenc1:";
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+	#[error("invalid encryption key: expected 32 bytes (64 hex characters)")]
+	InvalidKey,
+	#[error("data is encrypted, but no encryption key is configured")]
+	MissingKey,
+	#[error("data is encrypted with unknown key {0}; add it to previousKeys to read it")]
+	UnknownKey(String),
+	#[error("encryption failed")]
+	EncryptionFailed,
+	#[error("decryption failed")]
+	DecryptionFailed,
+	#[error("invalid encrypted data format")]
+	InvalidFormat,
+}
+
+#[apply(schema_de!)]
+pub struct Config {
+	/// Key used to encrypt data, as 64 hex characters (for example, from `openssl rand -hex 32`).
+	/// May be a secret reference, such as `${secret:env:AGW_ENCRYPTION_KEY}`.
+	#[serde(deserialize_with = "deser_key")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub key: SecretString,
+	/// Previous keys, still accepted for decryption. Data is re-encrypted with `key` when it is next
+	/// written.
+	#[serde(default, deserialize_with = "deser_keys")]
+	#[cfg_attr(feature = "schema", schemars(with = "Vec<String>"))]
+	pub previous_keys: Vec<SecretString>,
+}
+
+impl serde::Serialize for Config {
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		use serde::ser::SerializeStruct;
+		let mut s = serializer.serialize_struct("Config", 2)?;
+		s.serialize_field("key", "<redacted>")?;
+		s.serialize_field("previousKeys", &self.previous_keys.len())?;
+		s.end()
+	}
+}
+
+impl Config {
+	/// Build a cipher, resolving any secret references. Fails if a key cannot be resolved, rather
+	/// than falling back to writing unencrypted data.
+	pub async fn build(&self, secrets: &secrets::Resolver) -> anyhow::Result<Cipher> {
+		let current = resolve_key(secrets, &self.key)
+			.await
+			.context("encryption key")?;
+		let mut previous = Vec::with_capacity(self.previous_keys.len());
+		for (i, k) in self.previous_keys.iter().enumerate() {
+			previous.push(
+				resolve_key(secrets, k)
+					.await
+					.with_context(|| format!("previous encryption key {i}"))?,
+			);
+		}
+		Ok(Cipher::new(&current, &previous)?)
+	}
+}
+
+async fn resolve_key(secrets: &secrets::Resolver, key: &SecretString) -> anyhow::Result<Vec<u8>> {
+	let raw = match secrets.parse_reference(key.expose_secret())? {
+		Some(r) => secrets.resolve(&r).await?,
+		None => key.clone(),
+	};
+	Ok(hex::decode(raw.expose_secret().trim()).map_err(|_| Error::InvalidKey)?)
+}
+
+fn deser_keys<'de, D>(deserializer: D) -> Result<Vec<SecretString>, D::Error>
+where
+	D: serde::Deserializer<'de>,
+{
+	let keys = <Vec<String> as serde::Deserialize>::deserialize(deserializer)?;
+	Ok(keys.into_iter().map(SecretString::from).collect())
+}
+
+struct Key {
+	id: String,
+	key: RandomizedNonceKey,
+}
+
+impl Key {
+	fn new(raw: &[u8]) -> Result<Self, Error> {
+		if raw.len() != 32 {
+			return Err(Error::InvalidKey);
+		}
+		let key = RandomizedNonceKey::new(&AES_256_GCM, raw).map_err(|_| Error::InvalidKey)?;
+		// Identify keys by a hash prefix, so the key itself is never stored.
+		let id = hex::encode(&aws_lc_rs::digest::digest(&aws_lc_rs::digest::SHA256, raw).as_ref()[..4]);
+		Ok(Key { id, key })
+	}
+}
+
+/// Encrypts records with the current key, and decrypts records written with the current or any
+/// previous key.
+pub struct Cipher {
+	current: Key,
+	previous: Vec<Key>,
+}
+
+impl Debug for Cipher {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Cipher")
+			.field("key_id", &self.current.id)
+			.field("previous_keys", &self.previous.len())
+			.finish()
+	}
+}
+
+impl Cipher {
+	pub fn new(current: &[u8], previous: &[Vec<u8>]) -> Result<Self, Error> {
+		Ok(Cipher {
+			current: Key::new(current)?,
+			previous: previous
+				.iter()
+				.map(|k| Key::new(k))
+				.collect::<Result<_, _>>()?,
+		})
+	}
+
+	pub fn encrypt(&self, plaintext: &[u8]) -> Result<String, Error> {
+		let mut in_out = plaintext.to_vec();
+		let nonce = self
+			.current
+			.key
+			.seal_in_place_append_tag(Aad::from(self.current.id.as_bytes()), &mut in_out)
+			.map_err(|_| Error::EncryptionFailed)?;
+		let mut data = nonce.as_ref().to_vec();
+		data.extend_from_slice(&in_out);
+		Ok(format!(
+			"{PREFIX}{}:{}",
+			self.current.id,
+			STANDARD.encode(data)
+		))
+	}
+
+	pub fn decrypt(&self, record: &str) -> Result<Vec<u8>, Error> {
+		let (id, data) = record
+			.strip_prefix(PREFIX)
+			.and_then(|r| r.split_once(':'))
+			.ok_or(Error::InvalidFormat)?;
+		let key = std::iter::once(&self.current)
+			.chain(&self.previous)
+			.find(|k| k.id == id)
+			.ok_or_else(|| Error::UnknownKey(id.to_string()))?;
+		let data = STANDARD
+			.decode(data.trim_end())
+			.map_err(|_| Error::InvalidFormat)?;
+		if data.len() < NONCE_LEN {
+			return Err(Error::InvalidFormat);
+		}
+		let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+		let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| Error::InvalidFormat)?;
+		let mut in_out = ciphertext.to_vec();
+		let plaintext = key
+			.key
+			.open_in_place(nonce, Aad::from(id.as_bytes()), &mut in_out)
+			.map_err(|_| Error::DecryptionFailed)?;
+		Ok(plaintext.to_vec())
+	}
+
+	/// Returns true if the record was written with a key other than the current one.
+	pub fn needs_rotation(&self, record: &str) -> bool {
+		is_encrypted(record.as_bytes())
+			&& record[PREFIX.len()..]
+				.split_once(':')
+				.is_some_and(|(id, _)| id != self.current.id)
+	}
+}
+
+pub fn is_encrypted(data: &[u8]) -> bool {
+	data.starts_with(PREFIX.as_bytes())
+}
+
+/// Prepare a record for writing, encrypting it if a cipher is configured.
+pub fn seal(cipher: Option<&Cipher>, data: Vec<u8>) -> Result<Vec<u8>, Error> {
+	match cipher {
+		Some(c) => Ok(c.encrypt(&data)?.into_bytes()),
+		None => Ok(data),
+	}
+}
+
+/// Read a record written by `seal`. Plaintext records are accepted even when a cipher is
+/// configured, so enabling encryption does not lose existing data; encrypted records require a
+/// cipher with the right key.
+pub fn open(cipher: Option<&Cipher>, data: &[u8]) -> Result<Vec<u8>, Error> {
+	if !is_encrypted(data) {
+		return Ok(data.to_vec());
+	}
+	let record = std::str::from_utf8(data).map_err(|_| Error::InvalidFormat)?;
+	cipher.ok_or(Error::MissingKey)?.decrypt(record)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn key(b: u8) -> Vec<u8> {
+		vec![b; 32]
+	}
+
+	#[test]
+	fn test_round_trip() {
+		let c = Cipher::new(&key(1), &[]).unwrap();
+		let sealed = seal(Some(&c), b"hello".to_vec()).unwrap();
+		assert!(is_encrypted(&sealed));
+		assert_eq!(open(Some(&c), &sealed).unwrap(), b"hello");
+		// Nonces are random, so the same plaintext encrypts differently
+		assert_ne!(sealed, seal(Some(&c), b"hello".to_vec()).unwrap());
+	}
+
+	#[test]
+	fn test_rotation() {
+		let old = Cipher::new(&key(1), &[]).unwrap();
+		let record = old.encrypt(b"hello").unwrap();
+
+		let rotated = Cipher::new(&key(2), &[key(1)]).unwrap();
+		assert!(rotated.needs_rotation(&record));
+		assert_eq!(rotated.decrypt(&record).unwrap(), b"hello");
+		assert!(!rotated.needs_rotation(&rotated.encrypt(b"hello").unwrap()));
+
+		let dropped = Cipher::new(&key(2), &[]).unwrap();
+		assert!(matches!(
+			dropped.decrypt(&record),
+			Err(Error::UnknownKey(_))
+		));
+	}
+
+	#[test]
+	fn test_missing_key() {
+		let c = Cipher::new(&key(1), &[]).unwrap();
+		let sealed = seal(Some(&c), b"hello".to_vec()).unwrap();
+		assert!(matches!(open(None, &sealed), Err(Error::MissingKey)));
+		// Existing plaintext data is still readable
+		assert_eq!(open(Some(&c), b"{}").unwrap(), b"{}");
+	}
+
+	#[test]
+	fn test_tampered() {
+		let c = Cipher::new(&key(1), &[]).unwrap();
+		let mut record = c.encrypt(b"hello").unwrap();
+		record.pop();
+		record.push('A');
+		assert!(c.decrypt(&record).is_err());
+		assert!(matches!(Cipher::new(&[0; 16], &[]), Err(Error::InvalidKey)));
+	}
+}
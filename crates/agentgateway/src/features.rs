@@ -0,0 +1,96 @@
+//! Feature flags gating experimental subsystems.
+//!
+//! Flags are set by the `features` section of the config, and can be overridden at runtime from
+//! the admin server. Overrides are not persisted, so a restart returns to the configured flags.
+
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use macro_rules_attribute::apply;
+
+use crate::*;
+
+#[cfg(test)]
+#[path = "features_tests.rs"]
+mod tests;
+
+#[apply(schema!)]
+#[derive(Copy, PartialEq, Eq)]
+pub struct Features {
+	/// Accept MCP servers registering themselves with `/mesh/register` on the admin server.
+	/// Defaults to true.
+	#[serde(default = "default_enabled")]
+	pub mesh_discovery: bool,
+	/// Serve responses from the `semanticCache` LLM policy. When disabled, the policy is ignored.
+	/// Defaults to true.
+	#[serde(default = "default_enabled")]
+	pub semantic_cache: bool,
+}
+
+impl Default for Features {
+	fn default() -> Self {
+		Self {
+			mesh_discovery: true,
+			semantic_cache: true,
+		}
+	}
+}
+
+fn default_enabled() -> bool {
+	true
+}
+
+/// A feature that can be switched on or off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+	MeshDiscovery,
+	SemanticCache,
+}
+
+impl Feature {
+	fn flag(self) -> &'static AtomicBool {
+		match self {
+			Feature::MeshDiscovery => &MESH_DISCOVERY,
+			Feature::SemanticCache => &SEMANTIC_CACHE,
+		}
+	}
+}
+
+impl FromStr for Feature {
+	type Err = anyhow::Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"meshDiscovery" => Ok(Feature::MeshDiscovery),
+			"semanticCache" => Ok(Feature::SemanticCache),
+			_ => anyhow::bail!("unknown feature {s}; expected one of meshDiscovery, semanticCache"),
+		}
+	}
+}
+
+static MESH_DISCOVERY: AtomicBool = AtomicBool::new(true);
+static SEMANTIC_CACHE: AtomicBool = AtomicBool::new(true);
+
+/// Apply the configured flags, replacing any runtime overrides.
+pub fn init(features: &Features) {
+	set(Feature::MeshDiscovery, features.mesh_discovery);
+	set(Feature::SemanticCache, features.semantic_cache);
+}
+
+/// Whether a feature is currently enabled.
+pub fn enabled(feature: Feature) -> bool {
+	feature.flag().load(Ordering::Relaxed)
+}
+
+/// Switch a feature on or off at runtime.
+pub fn set(feature: Feature, enabled: bool) {
+	feature.flag().store(enabled, Ordering::Relaxed);
+}
+
+/// The flags in effect, including runtime overrides.
+pub fn effective() -> Features {
+	Features {
+		mesh_discovery: enabled(Feature::MeshDiscovery),
+		semantic_cache: enabled(Feature::SemanticCache),
+	}
+}
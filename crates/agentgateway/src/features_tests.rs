@@ -0,0 +1,29 @@
+use serde_json::json;
+
+use super::*;
+
+#[test]
+fn test_defaults() {
+	let f: Features = serde_json::from_value(json!({})).unwrap();
+	assert_eq!(f, Features::default());
+	assert!(f.mesh_discovery && f.semantic_cache);
+
+	let f: Features = serde_json::from_value(json!({"semanticCache": false})).unwrap();
+	assert!(f.mesh_discovery);
+	assert!(!f.semantic_cache);
+
+	assert!(serde_json::from_value::<Features>(json!({"ebpfBypass": true})).is_err());
+}
+
+#[test]
+fn test_parse_feature() {
+	assert_eq!(
+		"meshDiscovery".parse::<Feature>().unwrap(),
+		Feature::MeshDiscovery
+	);
+	assert_eq!(
+		"semanticCache".parse::<Feature>().unwrap(),
+		Feature::SemanticCache
+	);
+	assert!("mesh_discovery".parse::<Feature>().is_err());
+}
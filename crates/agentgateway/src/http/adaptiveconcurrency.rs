@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+
+use ::http::StatusCode;
+use macro_rules_attribute::apply;
+use parking_lot::Mutex;
+
+use crate::http::Response;
+use crate::proxy::ProxyError;
+use crate::*;
+
+#[cfg(test)]
+#[path = "adaptiveconcurrency_tests.rs"]
+mod tests;
+
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("backend concurrency limit exceeded")]
+pub struct LimitExceeded;
+
+/// Limit the requests in flight to each backend, adjusting the limit to the backend's latency.
+///
+/// The limit grows while responses are as fast as the fastest recent response, within
+/// `latencyTolerance`, and shrinks by `backoffRatio` when they are slower or fail. Requests over
+/// the limit are rejected with 503 Service Unavailable rather than being sent to a backend that is
+/// already overloaded.
+#[apply(schema!)]
+pub struct Policy {
+	/// The limit before any responses have been observed. Defaults to 20.
+	#[serde(default = "default_initial_limit")]
+	pub initial_limit: usize,
+	/// The lowest the limit can go. Defaults to 1.
+	#[serde(default = "default_min_limit")]
+	pub min_limit: usize,
+	/// The highest the limit can go. Defaults to 1000.
+	#[serde(default = "default_max_limit")]
+	pub max_limit: usize,
+	/// How many times slower than the fastest recent response a response can be before the backend
+	/// is considered overloaded. Defaults to 2.
+	#[serde(default = "default_latency_tolerance")]
+	pub latency_tolerance: f64,
+	/// The ratio the limit is multiplied by when the backend is overloaded. Defaults to 0.9.
+	#[serde(default = "default_backoff_ratio")]
+	pub backoff_ratio: f64,
+	/// How long the fastest response is used as the baseline, so the baseline follows changes in the
+	/// backend. Defaults to 30s.
+	#[serde(default = "default_baseline_window", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub baseline_window: Duration,
+	#[serde(skip)]
+	limiters: Arc<Mutex<HashMap<Strng, Arc<Limiter>>>>,
+}
+
+fn default_initial_limit() -> usize {
+	20
+}
+
+fn default_min_limit() -> usize {
+	1
+}
+
+fn default_max_limit() -> usize {
+	1000
+}
+
+fn default_latency_tolerance() -> f64 {
+	2.0
+}
+
+fn default_backoff_ratio() -> f64 {
+	0.9
+}
+
+fn default_baseline_window() -> Duration {
+	Duration::from_secs(30)
+}
+
+impl Policy {
+	pub fn validate(&self) -> anyhow::Result<()> {
+		if self.min_limit == 0 || self.min_limit > self.max_limit {
+			anyhow::bail!("minLimit must be at least 1 and no more than maxLimit");
+		}
+		if !(self.min_limit..=self.max_limit).contains(&self.initial_limit) {
+			anyhow::bail!("initialLimit must be between minLimit and maxLimit");
+		}
+		if self.latency_tolerance.is_nan() || self.latency_tolerance < 1.0 {
+			anyhow::bail!("latencyTolerance must be at least 1");
+		}
+		if !(self.backoff_ratio > 0.0 && self.backoff_ratio < 1.0) {
+			anyhow::bail!("backoffRatio must be between 0 and 1");
+		}
+		Ok(())
+	}
+
+	/// Take a slot for a request to `backend`, if it is under its limit.
+	pub fn acquire(&self, backend: &Strng) -> Result<Permit, LimitExceeded> {
+		let limiter = self
+			.limiters
+			.lock()
+			.entry(backend.clone())
+			.or_insert_with(|| {
+				Arc::new(Limiter {
+					state: Mutex::new(State {
+						limit: self.initial_limit as f64,
+						in_flight: 0,
+						baseline: None,
+					}),
+				})
+			})
+			.clone();
+		{
+			let mut state = limiter.state.lock();
+			if state.in_flight >= state.limit() {
+				return Err(LimitExceeded);
+			}
+			state.in_flight += 1;
+		}
+		Ok(Permit {
+			limiter,
+			policy: self.clone(),
+			start: Instant::now(),
+		})
+	}
+}
+
+/// A slot for a request in flight. The slot is released when it is dropped.
+#[derive(Debug)]
+pub struct Permit {
+	limiter: Arc<Limiter>,
+	policy: Policy,
+	start: Instant,
+}
+
+impl Permit {
+	/// Adjust the limit from the result of the request, and release the slot. Returns the new limit.
+	pub fn complete(self, res: &Result<Response, ProxyError>) -> usize {
+		let overloaded = match res {
+			Ok(resp) => matches!(
+				resp.status(),
+				StatusCode::TOO_MANY_REQUESTS
+					| StatusCode::SERVICE_UNAVAILABLE
+					| StatusCode::GATEWAY_TIMEOUT
+			),
+			// Errors other than failing to reach the backend say nothing about its load.
+			Err(e) => matches!(
+				e,
+				ProxyError::UpstreamCallFailed(_) | ProxyError::UpstreamCallTimeout
+			),
+		};
+		self.record(self.start.elapsed(), overloaded, Instant::now())
+	}
+
+	fn record(&self, latency: Duration, failed: bool, now: Instant) -> usize {
+		let p = &self.policy;
+		let mut state = self.limiter.state.lock();
+		let baseline = match state.baseline {
+			Some((fastest, at)) if latency >= fastest && now.duration_since(at) < p.baseline_window => {
+				fastest
+			},
+			_ => {
+				state.baseline = Some((latency, now));
+				latency
+			},
+		};
+		let overloaded = failed || latency.as_secs_f64() > baseline.as_secs_f64() * p.latency_tolerance;
+		let min = p.min_limit as f64;
+		let max = p.max_limit as f64;
+		if overloaded {
+			state.limit = (state.limit * p.backoff_ratio).max(min);
+		} else if state.in_flight * 2 >= state.limit() {
+			// Only grow the limit while it is being used, by about one per limit's worth of requests.
+			state.limit = (state.limit + 1.0 / state.limit).min(max);
+		}
+		state.limit()
+	}
+}
+
+impl Drop for Permit {
+	fn drop(&mut self) {
+		self.limiter.state.lock().in_flight -= 1;
+	}
+}
+
+/// The requests in flight to a single backend, and its limit.
+#[derive(Debug)]
+struct Limiter {
+	state: Mutex<State>,
+}
+
+#[derive(Debug)]
+struct State {
+	limit: f64,
+	in_flight: usize,
+	/// The fastest recent response, and when it was observed.
+	baseline: Option<(Duration, Instant)>,
+}
+
+impl State {
+	fn limit(&self) -> usize {
+		self.limit as usize
+	}
+}
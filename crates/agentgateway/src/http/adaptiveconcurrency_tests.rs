@@ -0,0 +1,95 @@
+use super::*;
+
+fn policy(cfg: serde_json::Value) -> Policy {
+	serde_json::from_value(cfg).unwrap()
+}
+
+fn ms(n: u64) -> Duration {
+	Duration::from_millis(n)
+}
+
+#[test]
+fn test_config() {
+	let p = policy(serde_json::json!({}));
+	assert_eq!(p.initial_limit, 20);
+	assert_eq!(p.min_limit, 1);
+	assert_eq!(p.max_limit, 1000);
+	assert_eq!(p.baseline_window, Duration::from_secs(30));
+	p.validate().unwrap();
+
+	assert!(
+		policy(serde_json::json!({"minLimit": 0}))
+			.validate()
+			.is_err()
+	);
+	assert!(
+		policy(serde_json::json!({"initialLimit": 5, "minLimit": 10}))
+			.validate()
+			.is_err()
+	);
+	assert!(
+		policy(serde_json::json!({"latencyTolerance": 0.5}))
+			.validate()
+			.is_err()
+	);
+	assert!(
+		policy(serde_json::json!({"backoffRatio": 1.0}))
+			.validate()
+			.is_err()
+	);
+}
+
+#[test]
+fn test_limit() {
+	let p = policy(serde_json::json!({"initialLimit": 2}));
+	let backend = strng::new("a");
+	let first = p.acquire(&backend).unwrap();
+	let _second = p.acquire(&backend).unwrap();
+	assert_eq!(p.acquire(&backend).unwrap_err(), LimitExceeded);
+	// Backends are limited separately
+	assert!(p.acquire(&strng::new("b")).is_ok());
+
+	drop(first);
+	assert!(p.acquire(&backend).is_ok());
+}
+
+#[test]
+fn test_adjust() {
+	let p = policy(serde_json::json!({"initialLimit": 10, "minLimit": 5, "maxLimit": 11}));
+	let backend = strng::new("a");
+	let now = Instant::now();
+	let permits: Vec<_> = (0..5).map(|_| p.acquire(&backend).unwrap()).collect();
+
+	// Fast responses while the limit is in use grow it, up to the max
+	assert_eq!(permits[0].record(ms(100), false, now), 10);
+	for _ in 0..20 {
+		permits[0].record(ms(110), false, now);
+	}
+	assert_eq!(permits[0].record(ms(100), false, now), 11);
+
+	// Slow or failed responses shrink it, down to the min
+	assert_eq!(permits[0].record(ms(300), false, now), 9);
+	assert_eq!(permits[0].record(ms(100), true, now), 8);
+	for _ in 0..20 {
+		permits[0].record(ms(300), false, now);
+	}
+	assert_eq!(permits[0].record(ms(300), false, now), 5);
+
+	// Once the baseline expires, the latest response becomes the baseline
+	let later = now + Duration::from_secs(31);
+	permits[0].record(ms(300), false, later);
+	assert_eq!(permits[0].record(ms(500), false, later), 5);
+	assert_eq!(
+		p.limiters.lock()[&backend].state.lock().baseline,
+		Some((ms(300), later))
+	);
+}
+
+#[test]
+fn test_idle_limit_does_not_grow() {
+	let p = policy(serde_json::json!({"initialLimit": 10}));
+	let permit = p.acquire(&strng::new("a")).unwrap();
+	for _ in 0..100 {
+		assert_eq!(permit.record(ms(100), false, Instant::now()), 10);
+	}
+}
@@ -52,6 +52,15 @@ impl APIKey {
 	pub fn new(s: impl Into<Box<str>>) -> Self {
 		APIKey(SecretString::new(s.into()))
 	}
+
+	/// A stable, non-reversible identifier for the key, safe to log or persist.
+	pub fn fingerprint(&self) -> String {
+		let d = aws_lc_rs::digest::digest(
+			&aws_lc_rs::digest::SHA256,
+			self.0.expose_secret().as_bytes(),
+		);
+		hex::encode(&d.as_ref()[..8])
+	}
 }
 
 type UserMetadata = serde_json::Value;
@@ -122,6 +131,7 @@ impl APIKeyAuthentication {
 		let res = self.verify(req).await?;
 		if let Some(claims) = res {
 			log.cel.ctx().with_api_key(&claims);
+			log.api_key = Some(claims.key.fingerprint());
 			req.headers_mut().remove(http::header::AUTHORIZATION);
 			// Insert the claims into extensions so we can reference it later
 			req.extensions_mut().insert(claims);
@@ -1,11 +1,16 @@
-use crate::http::Request;
 use crate::http::jwt::Claims;
+use crate::http::{Request, keypool};
 use crate::proxy::ProxyError;
 use crate::serdes::deser_key_from_file;
 use crate::types::agent::{BackendTarget, Target};
 use crate::*;
 use secrecy::{ExposeSecret, SecretString};
 
+/// The AWS service a request is signed for, as a request extension. Requests without one are
+/// signed for Bedrock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AwsService(pub &'static str);
+
 #[apply(schema!)]
 #[serde(untagged)]
 pub enum AwsAuth {
@@ -129,6 +134,7 @@ pub enum BackendAuth {
 		)]
 		SecretString,
 	),
+	KeyPool(keypool::KeyPool),
 	#[serde(rename = "gcp")]
 	Gcp(GcpAuth),
 	#[serde(rename = "aws")]
@@ -167,6 +173,7 @@ pub async fn apply_backend_auth(
 				req.headers_mut().insert(http::header::AUTHORIZATION, token);
 			}
 		},
+		BackendAuth::KeyPool(pool) => pool.apply(req),
 		BackendAuth::Gcp(g) => {
 			gcp::insert_token(g, &backend_info.call_target, req.headers_mut())
 				.await
@@ -195,6 +202,7 @@ pub async fn apply_late_backend_auth(
 	match auth {
 		BackendAuth::Passthrough {} => {},
 		BackendAuth::Key(_) => {},
+		BackendAuth::KeyPool(_) => {},
 		BackendAuth::Gcp(_) => {},
 		BackendAuth::Aws(aws_auth) => {
 			aws::sign_request(req, aws_auth)
@@ -390,13 +398,16 @@ mod aws {
 	use aws_config::{BehaviorVersion, SdkConfig};
 	use aws_credential_types::Credentials;
 	use aws_credential_types::provider::ProvideCredentials;
-	use aws_sigv4::http_request::{SignableBody, sign};
+	use aws_sigv4::http_request::{
+		PayloadChecksumKind, PercentEncodingMode, SignableBody, SigningSettings,
+		UriPathNormalizationMode, sign,
+	};
 	use aws_sigv4::sign::v4::SigningParams;
 	use http_body_util::BodyExt;
 	use secrecy::ExposeSecret;
 	use tokio::sync::OnceCell;
 
-	use crate::http::auth::AwsAuth;
+	use crate::http::auth::{AwsAuth, AwsService};
 	use crate::llm::bedrock::AwsRegion;
 	use crate::*;
 
@@ -423,15 +434,28 @@ mod aws {
 			},
 		};
 
-		trace!("AWS signing with region: {}, service: bedrock", region);
+		let service = req
+			.extensions()
+			.get::<AwsService>()
+			.map(|s| s.0)
+			.unwrap_or("bedrock");
+		trace!("AWS signing with region: {}, service: {}", region, service);
+
+		let mut settings = SigningSettings::default();
+		if service == "s3" {
+			// S3 requires the payload hash as a header, and keys are signed as they are.
+			settings.payload_checksum_kind = PayloadChecksumKind::XAmzSha256;
+			settings.percent_encoding_mode = PercentEncodingMode::Single;
+			settings.uri_path_normalization_mode = UriPathNormalizationMode::Disabled;
+		}
 
 		// Sign the request
 		let signing_params = SigningParams::builder()
 			.identity(&creds)
 			.region(region)
-			.name("bedrock")
+			.name(service)
 			.time(std::time::SystemTime::now())
-			.settings(aws_sigv4::http_request::SigningSettings::default())
+			.settings(settings)
 			.build()?
 			.into();
 
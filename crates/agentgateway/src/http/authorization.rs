@@ -33,6 +33,54 @@ pub struct RuleSet {
 	#[serde(serialize_with = "se_policies", deserialize_with = "de_policies")]
 	#[cfg_attr(feature = "schema", schemars(with = "Vec<String>"))]
 	pub rules: PolicySet,
+	/// Whether the rules are enforced, or only evaluated and logged.
+	#[serde(default, skip_serializing_if = "is_default")]
+	pub mode: Mode,
+}
+
+#[apply(schema!)]
+#[derive(Default, Copy, PartialEq, Eq)]
+pub enum Mode {
+	/// Requests that are not allowed by the rules are rejected.
+	#[default]
+	Enforce,
+	/// Rules are evaluated and their decision is logged, but requests are never rejected.
+	/// This is useful to roll out new rules safely.
+	DryRun,
+}
+
+/// The outcome of evaluating a set of rules against a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+	/// No rules were configured, so the request is allowed.
+	NoRules,
+	/// An allow rule matched and no deny rule matched.
+	Allow,
+	/// A deny rule matched.
+	Deny,
+	/// Rules were configured, but no allow rule matched.
+	NoMatch,
+}
+
+impl Decision {
+	pub fn allowed(&self) -> bool {
+		matches!(self, Decision::NoRules | Decision::Allow)
+	}
+
+	fn as_str(&self) -> &'static str {
+		match self {
+			Decision::NoRules => "no_rules",
+			Decision::Allow => "allow",
+			Decision::Deny => "deny",
+			Decision::NoMatch => "no_match",
+		}
+	}
+}
+
+impl Display for Decision {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.as_str())
+	}
 }
 
 impl RuleSet {
@@ -150,16 +198,45 @@ impl RuleSets {
 			return false;
 		};
 		let exec = exec.as_ref();
+		let decision = Self::decide(rule_sets.iter().filter(|r| r.mode == Mode::Enforce), exec);
+		if rule_sets.iter().any(|r| r.mode == Mode::DryRun) {
+			// Evaluate as if the dry run rules were enforced, and report if that would change the outcome.
+			let dry_run = Self::decide(rule_sets.iter(), exec);
+			if decision.allowed() && !dry_run.allowed() {
+				tracing::info!(
+					target: "authorization",
+					decision = %dry_run,
+					dry_run = true,
+					"authorization would deny request"
+				);
+			}
+		}
+		if decision.allowed() {
+			tracing::debug!(target: "authorization", %decision, "authorization allowed request");
+		} else {
+			// Denials are recorded in the audit log; logging each here would let clients flood it.
+			tracing::debug!(target: "authorization", %decision, "authorization denied request");
+		}
+		decision.allowed()
+	}
+
+	fn decide<'a>(
+		rule_sets: impl Iterator<Item = &'a RuleSet> + Clone,
+		exec: &cel::Executor,
+	) -> Decision {
+		if !rule_sets.clone().any(|r| r.has_rules()) {
+			return Decision::NoRules;
+		}
 		// If there are any DENY, deny
-		if rule_sets.iter().any(|r| r.denies(exec)) {
-			return false;
+		if rule_sets.clone().any(|r| r.denies(exec)) {
+			return Decision::Deny;
 		}
 		// If there are any ALLOW, allow
-		if rule_sets.iter().any(|r| r.allows(exec)) {
-			return true;
+		if rule_sets.clone().any(|r| r.allows(exec)) {
+			return Decision::Allow;
 		}
 		// Else deny
-		false
+		Decision::NoMatch
 	}
 
 	pub fn is_empty(&self) -> bool {
@@ -169,7 +246,14 @@ impl RuleSets {
 
 impl RuleSet {
 	pub fn new(rules: PolicySet) -> Self {
-		Self { rules }
+		Self {
+			rules,
+			mode: Mode::default(),
+		}
+	}
+
+	pub fn with_mode(self, mode: Mode) -> Self {
+		Self { mode, ..self }
 	}
 
 	pub fn has_rules(&self) -> bool {
@@ -161,6 +161,79 @@ fn test_rbac_check_array_contains_match() {
 	assert_matches!(rs.validate(|| Ok(OwnedOrBorrowed::Borrowed(&exec))), true);
 }
 
+#[test]
+fn test_rbac_dry_run_does_not_deny() {
+	let policies = vec![r#"mcp.tool.name == "increment" && jwt.user == "admin""#];
+	let rbac = RuleSet::new(create_policy_set(policies)).with_mode(Mode::DryRun);
+	let mut ctx = ContextBuilder::new();
+	let rs = RuleSets::from(vec![rbac.clone()]);
+	rs.register(&mut ctx);
+	ctx.with_jwt(&Claims {
+		inner: Map::from_iter([("sub".to_string(), "1234567890".to_string().into())]),
+		jwt: SecretString::new("".into()),
+	});
+	let exec = ctx
+		.build_with_mcp(Some(&ResourceType::Tool(ResourceId::new(
+			"server".to_string(),
+			"increment".to_string(),
+		))))
+		.unwrap();
+
+	assert_matches!(rs.validate(|| Ok(OwnedOrBorrowed::Borrowed(&exec))), true);
+}
+
+#[test]
+fn test_rbac_dry_run_with_enforced() {
+	let enforced = RuleSet::new(create_policy_set(vec![r#"mcp.tool.name == "increment""#]));
+	let mut deny = PolicySet::default();
+	deny.deny.push(Arc::new(
+		cel::Expression::new_strict(r#"jwt.sub == "1234567890""#).unwrap(),
+	));
+	let dry_run = RuleSet::new(deny).with_mode(Mode::DryRun);
+	let mut ctx = ContextBuilder::new();
+	let rs = RuleSets::from(vec![enforced, dry_run]);
+	rs.register(&mut ctx);
+	ctx.with_jwt(&Claims {
+		inner: Map::from_iter([("sub".to_string(), "1234567890".to_string().into())]),
+		jwt: SecretString::new("".into()),
+	});
+	let exec = ctx
+		.build_with_mcp(Some(&ResourceType::Tool(ResourceId::new(
+			"server".to_string(),
+			"increment".to_string(),
+		))))
+		.unwrap();
+	// The dry run deny would reject the request, but it is only logged
+	assert_matches!(rs.validate(|| Ok(OwnedOrBorrowed::Borrowed(&exec))), true);
+
+	let exec_other_tool = ctx
+		.build_with_mcp(Some(&ResourceType::Tool(ResourceId::new(
+			"server".to_string(),
+			"decrement".to_string(),
+		))))
+		.unwrap();
+	// Enforced rules still apply
+	assert_matches!(
+		rs.validate(|| Ok(OwnedOrBorrowed::Borrowed(&exec_other_tool))),
+		false
+	);
+}
+
+#[test]
+fn test_rbac_mode_deserialize() {
+	let rs: RuleSet = serde_json::from_value(serde_json::json!({
+		"rules": ["true"],
+		"mode": "dryRun",
+	}))
+	.unwrap();
+	assert_eq!(rs.mode, Mode::DryRun);
+	let rs: RuleSet = serde_json::from_value(serde_json::json!({
+		"rules": ["true"],
+	}))
+	.unwrap();
+	assert_eq!(rs.mode, Mode::Enforce);
+}
+
 #[divan::bench]
 fn bench(b: Bencher) {
 	let policies = vec![r#"mcp.tool.name == "increment" && jwt.user.role == "admin""#];
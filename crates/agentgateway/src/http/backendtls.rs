@@ -9,7 +9,8 @@ use serde::Serializer;
 
 use crate::transport;
 use crate::transport::tls;
-use crate::types::agent::{parse_cert, parse_key};
+use crate::types::agent::{TLSVersion, parse_cert, parse_key, tls_versions_for_range};
+use crate::types::frontend;
 
 pub static SYSTEM_TRUST: Lazy<BackendTLS> =
 	Lazy::new(|| LocalBackendTLS::default().try_into().unwrap());
@@ -23,6 +24,7 @@ pub static INSECURE_TRUST: Lazy<BackendTLS> = Lazy::new(|| {
 		insecure_host: false,
 		alpn: None,
 		subject_alt_names: None,
+		..Default::default()
 	}
 	.try_into()
 	.unwrap()
@@ -74,6 +76,8 @@ impl PerAlpnConfig {
 pub struct BackendTLS {
 	pub hostname_override: Option<ServerName<'static>>,
 	pub config: PerAlpnConfig,
+	/// Effective settings, reported in the config dump.
+	pub settings: Arc<tls::TLSSettings>,
 }
 
 impl BackendTLS {
@@ -117,10 +121,10 @@ impl serde::Serialize for BackendTLS {
 		S: Serializer,
 	{
 		// TODO: store raw pem so we can send it back
-		serializer.serialize_none()
+		serde::Serialize::serialize(self.settings.as_ref(), serializer)
 	}
 }
-static SYSTEM_ROOT: Lazy<rustls_native_certs::CertificateResult> =
+pub(crate) static SYSTEM_ROOT: Lazy<rustls_native_certs::CertificateResult> =
 	Lazy::new(rustls_native_certs::load_native_certs);
 
 #[derive(Debug, Clone, Default, serde::Deserialize)]
@@ -140,6 +144,19 @@ pub struct LocalBackendTLS {
 	alpn: Option<Vec<String>>,
 	#[serde(default)]
 	pub subject_alt_names: Option<Vec<String>>,
+	/// Optional cipher suite allowlist (order is preserved).
+	#[cfg_attr(feature = "schema", schemars(with = "Option<Vec<String>>"))]
+	#[serde(default)]
+	cipher_suites: Option<Vec<tls::CipherSuite>>,
+	/// Minimum supported TLS version (only TLS 1.2 and 1.3 are supported).
+	#[serde(default, rename = "minTLSVersion", alias = "minTlsVersion")]
+	min_tls_version: Option<frontend::TLSVersion>,
+	/// Maximum supported TLS version (only TLS 1.2 and 1.3 are supported).
+	#[serde(default, rename = "maxTLSVersion", alias = "maxTlsVersion")]
+	max_tls_version: Option<frontend::TLSVersion>,
+	/// If false, disable TLS session resumption to the backend. Enabled by default.
+	#[serde(default)]
+	session_resumption: Option<bool>,
 }
 
 #[derive(Default, Debug)]
@@ -153,6 +170,10 @@ pub struct ResolvedBackendTLS {
 	pub insecure_host: bool,
 	pub alpn: Option<Vec<String>>,
 	pub subject_alt_names: Option<Vec<String>>,
+	pub cipher_suites: Option<Vec<tls::CipherSuite>>,
+	pub min_version: Option<TLSVersion>,
+	pub max_version: Option<TLSVersion>,
+	pub session_resumption: Option<bool>,
 }
 
 impl ResolvedBackendTLS {
@@ -170,8 +191,14 @@ impl ResolvedBackendTLS {
 		}
 
 		let roots = Arc::new(roots);
-		let ccb = ClientConfig::builder_with_provider(transport::tls::provider())
-			.with_protocol_versions(transport::tls::ALL_TLS_VERSIONS)
+		let provider = match self.cipher_suites.as_deref() {
+			Some(suites) if !suites.is_empty() => transport::tls::provider_with_cipher_suites(suites)?,
+			_ => transport::tls::provider(),
+		};
+		let versions = tls_versions_for_range(self.min_version, self.max_version)?;
+		transport::tls::validate_cipher_suites(&versions, &provider)?;
+		let ccb = ClientConfig::builder_with_provider(provider.clone())
+			.with_protocol_versions(&versions)
 			.expect("server config must be valid")
 			.with_root_certificates(roots.clone());
 
@@ -184,11 +211,9 @@ impl ResolvedBackendTLS {
 			_ => ccb.with_no_client_auth(),
 		};
 		if self.insecure_host {
-			let inner = rustls::client::WebPkiServerVerifier::builder_with_provider(
-				roots,
-				transport::tls::provider(),
-			)
-			.build()?;
+			let inner =
+				rustls::client::WebPkiServerVerifier::builder_with_provider(roots, provider.clone())
+					.build()?;
 			let verifier = Arc::new(tls::insecure::NoServerNameVerification::new(inner));
 			cc.dangerous().set_certificate_verifier(verifier);
 		} else if self.insecure {
@@ -210,9 +235,15 @@ impl ResolvedBackendTLS {
 		} else {
 			cc.alpn_protocols = vec![b"h2".into(), b"http/1.1".into()];
 		}
+		if self.session_resumption == Some(false) {
+			cc.resumption = rustls::client::Resumption::disabled();
+		}
+		let mut settings = tls::TLSSettings::new(&versions, &provider, &cc.alpn_protocols);
+		settings.session_tickets = self.session_resumption;
 		Ok(BackendTLS {
 			hostname_override: self.hostname.map(|s| s.try_into()).transpose()?,
 			config: PerAlpnConfig::new(Arc::new(cc), allow_custom_alpn),
+			settings: Arc::new(settings),
 		})
 	}
 }
@@ -228,6 +259,10 @@ impl LocalBackendTLS {
 			insecure_host: self.insecure_host,
 			alpn: self.alpn,
 			subject_alt_names: self.subject_alt_names,
+			cipher_suites: self.cipher_suites,
+			min_version: self.min_tls_version.map(Into::into),
+			max_version: self.max_tls_version.map(Into::into),
+			session_resumption: self.session_resumption,
 		}
 		.try_into()
 	}
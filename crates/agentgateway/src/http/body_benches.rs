@@ -0,0 +1,92 @@
+//! Benchmarks for forwarding bodies.
+//!
+//! Run with `cargo bench -p agentgateway -F internal_benches -- body_benches`. Each benchmark
+//! forwards a body of `args` frames of 64KiB, so the cost of forwarding can be compared against
+//! the cost of inspecting or accumulating the same body.
+
+use bytes::Bytes;
+use divan::Bencher;
+use http_body_util::BodyExt;
+
+use crate::http::{Body, inspect_body_with_limit, read_body_with_limit};
+
+const FRAME_SIZE: usize = 64 * 1024;
+const FRAMES: &[usize] = &[1, 16, 256];
+
+fn frames(n: usize) -> Vec<Bytes> {
+	(0..n)
+		.map(|i| Bytes::from(vec![i as u8; FRAME_SIZE]))
+		.collect()
+}
+
+fn body(frames: &[Bytes]) -> Body {
+	let frames = frames
+		.to_vec()
+		.into_iter()
+		.map(|f| Ok::<_, std::io::Error>(http_body::Frame::data(f)));
+	Body::new(http_body_util::StreamBody::new(futures_util::stream::iter(
+		frames,
+	)))
+}
+
+async fn forward(mut body: Body) -> usize {
+	let mut n = 0;
+	while let Some(frame) = body.frame().await {
+		n += frame
+			.unwrap()
+			.into_data()
+			.map(|d| d.len())
+			.unwrap_or_default();
+	}
+	n
+}
+
+fn runtime() -> tokio::runtime::Runtime {
+	tokio::runtime::Builder::new_current_thread()
+		.build()
+		.unwrap()
+}
+
+/// Forward a body frame by frame, as the proxy does when no policy inspects it.
+#[divan::bench(args = FRAMES)]
+fn forward_body(b: Bencher, n: usize) {
+	let rt = runtime();
+	let frames = frames(n);
+	b.counter(divan::counter::BytesCount::new(n * FRAME_SIZE))
+		.bench(|| rt.block_on(forward(body(&frames))));
+}
+
+/// Inspect the first frame of a body, as CEL or an external authorization policy does, and then
+/// forward it.
+#[divan::bench(args = FRAMES)]
+fn inspect_and_forward_body(b: Bencher, n: usize) {
+	let rt = runtime();
+	let frames = frames(n);
+	b.counter(divan::counter::BytesCount::new(n * FRAME_SIZE))
+		.bench(|| {
+			rt.block_on(async {
+				let mut body = body(&frames);
+				let inspected = inspect_body_with_limit(&mut body, FRAME_SIZE)
+					.await
+					.unwrap();
+				divan::black_box(inspected);
+				forward(body).await
+			})
+		});
+}
+
+/// Accumulate a whole body into a single buffer, for comparison.
+#[divan::bench(args = FRAMES)]
+fn read_body(b: Bencher, n: usize) {
+	let rt = runtime();
+	let frames = frames(n);
+	b.counter(divan::counter::BytesCount::new(n * FRAME_SIZE))
+		.bench(|| {
+			rt.block_on(async {
+				read_body_with_limit(body(&frames), usize::MAX)
+					.await
+					.unwrap()
+					.len()
+			})
+		});
+}
@@ -0,0 +1,220 @@
+//! A pool of reusable buffers for parsing and serializing bodies.
+//!
+//! Streaming parsers, such as SSE, decode from a scratch buffer that lives as long as the body,
+//! and serialized messages are written into a buffer before being sent. Taking these buffers from
+//! a pool, and returning them once done, avoids allocating and growing a fresh buffer for every
+//! message under sustained streaming load.
+//!
+//! Buffers are pooled in size classes by their capacity. A serialized message is split off its
+//! buffer, so the rest of the buffer's capacity is reused by later messages.
+
+use std::ops::{Deref, DerefMut};
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use agent_core::strng::RichStrng;
+use bytes::{BufMut, Bytes, BytesMut};
+use parking_lot::Mutex;
+use prometheus_client::collector::Collector;
+use prometheus_client::encoding::{DescriptorEncoder, EncodeMetric};
+use prometheus_client::metrics::MetricType;
+use prometheus_client::metrics::counter::ConstCounter;
+use prometheus_client::metrics::gauge::ConstGauge;
+use prometheus_client::registry::Registry;
+use serde::Serialize;
+
+use crate::telemetry::metrics::{BufferPoolLabels, BufferPoolRequestLabels};
+
+#[cfg(test)]
+#[path = "bufpool_tests.rs"]
+mod tests;
+
+/// The capacity of buffers in each size class.
+const SIZE_CLASSES: [usize; 3] = [4 * 1024, 64 * 1024, 1024 * 1024];
+/// The most idle buffers kept in each size class.
+const MAX_IDLE: usize = 256;
+
+static POOL: LazyLock<Pool> = LazyLock::new(Pool::default);
+
+#[derive(Debug, Default)]
+struct Pool {
+	classes: [Class; SIZE_CLASSES.len()],
+}
+
+#[derive(Debug, Default)]
+struct Class {
+	idle: Mutex<Vec<BytesMut>>,
+	hits: AtomicU64,
+	misses: AtomicU64,
+	discarded: AtomicU64,
+}
+
+impl Pool {
+	/// Take an idle buffer with at least `capacity`, or allocate one with `allocate` if there is none.
+	fn get(&self, capacity: usize, allocate: usize) -> BytesMut {
+		let class = SIZE_CLASSES
+			.iter()
+			.position(|&size| size >= capacity)
+			.unwrap_or(SIZE_CLASSES.len() - 1);
+		// Every buffer in a class is at least the class size, so larger classes can serve it too.
+		for c in &self.classes[class..] {
+			if let Some(mut buf) = c.idle.lock().pop() {
+				self.classes[class].hits.fetch_add(1, Ordering::Relaxed);
+				buf.reserve(capacity);
+				return buf;
+			}
+		}
+		self.classes[class].misses.fetch_add(1, Ordering::Relaxed);
+		BytesMut::with_capacity(allocate.max(capacity))
+	}
+
+	fn put(&self, mut buf: BytesMut) {
+		buf.clear();
+		// Buffers too small for any class are dropped, and ones that grew far beyond the largest
+		// class are dropped rather than holding on to the memory.
+		let Some(class) = SIZE_CLASSES
+			.iter()
+			.rposition(|&size| size <= buf.capacity())
+		else {
+			return;
+		};
+		if buf.capacity() > SIZE_CLASSES[SIZE_CLASSES.len() - 1] * 4 {
+			self.classes[class]
+				.discarded
+				.fetch_add(1, Ordering::Relaxed);
+			return;
+		}
+		let mut idle = self.classes[class].idle.lock();
+		if idle.len() >= MAX_IDLE {
+			self.classes[class]
+				.discarded
+				.fetch_add(1, Ordering::Relaxed);
+			return;
+		}
+		idle.push(buf);
+	}
+}
+
+/// A buffer taken from the pool. It is returned to the pool when dropped.
+#[derive(Debug)]
+pub struct Buffer(Option<BytesMut>);
+
+/// Take a buffer with at least `capacity` bytes of capacity from the pool.
+pub fn get(capacity: usize) -> Buffer {
+	let class = SIZE_CLASSES
+		.iter()
+		.find(|&&size| size >= capacity)
+		.copied()
+		.unwrap_or(capacity);
+	Buffer(Some(POOL.get(capacity, class)))
+}
+
+impl Default for Buffer {
+	fn default() -> Self {
+		get(0)
+	}
+}
+
+impl Deref for Buffer {
+	type Target = BytesMut;
+
+	fn deref(&self) -> &BytesMut {
+		self.0.as_ref().expect("buffer is only taken on drop")
+	}
+}
+
+impl DerefMut for Buffer {
+	fn deref_mut(&mut self) -> &mut BytesMut {
+		self.0.as_mut().expect("buffer is only taken on drop")
+	}
+}
+
+impl Drop for Buffer {
+	fn drop(&mut self) {
+		if let Some(buf) = self.0.take() {
+			POOL.put(buf);
+		}
+	}
+}
+
+/// Drop all idle buffers, returning how many were dropped.
+pub fn trim() -> usize {
+	POOL
+		.classes
+		.iter()
+		.map(|c| std::mem::take(&mut *c.idle.lock()).len())
+		.sum()
+}
+
+/// Serialize `value` as JSON into a pooled buffer.
+pub fn to_json<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<Bytes> {
+	// Messages are split off the buffer, so allocate enough for many of them to share it.
+	let mut buf = Buffer(Some(POOL.get(0, SIZE_CLASSES[1])));
+	serde_json::to_writer((&mut *buf).writer(), value)?;
+	Ok(buf.split().freeze())
+}
+
+/// Exposes the idle buffers in the pool, and how often buffers were reused, at scrape time.
+#[derive(Debug)]
+pub struct BufferPoolCollector;
+
+impl BufferPoolCollector {
+	pub fn register(registry: &mut Registry) {
+		registry.register_collector(Box::new(BufferPoolCollector));
+	}
+}
+
+fn size_label(size: usize) -> RichStrng {
+	RichStrng::from(format!("{}KiB", size / 1024))
+}
+
+impl Collector for BufferPoolCollector {
+	fn encode(&self, mut encoder: DescriptorEncoder) -> Result<(), std::fmt::Error> {
+		let classes = SIZE_CLASSES.iter().zip(&POOL.classes);
+		let mut metric_encoder = encoder.encode_descriptor(
+			"buffer_pool_idle_buffers",
+			"Idle body buffers waiting to be reused, by size class",
+			None,
+			MetricType::Gauge,
+		)?;
+		for (&size, class) in classes.clone() {
+			let labels = BufferPoolLabels {
+				size: size_label(size),
+			};
+			let family_encoder = metric_encoder.encode_family(&labels)?;
+			ConstGauge::new(class.idle.lock().len() as i64).encode(family_encoder)?;
+		}
+
+		let mut metric_encoder = encoder.encode_descriptor(
+			"buffer_pool_requests",
+			"Body buffers taken from the pool, by size class and whether an idle buffer was reused (hit) or allocated (miss)",
+			None,
+			MetricType::Counter,
+		)?;
+		for (&size, class) in classes.clone() {
+			for (result, count) in [("hit", &class.hits), ("miss", &class.misses)] {
+				let labels = BufferPoolRequestLabels {
+					size: size_label(size),
+					result: RichStrng::from(result),
+				};
+				let family_encoder = metric_encoder.encode_family(&labels)?;
+				ConstCounter::new(count.load(Ordering::Relaxed)).encode(family_encoder)?;
+			}
+		}
+
+		let mut metric_encoder = encoder.encode_descriptor(
+			"buffer_pool_discarded",
+			"Body buffers freed instead of returned to the pool because it was full or they were too large, by size class",
+			None,
+			MetricType::Counter,
+		)?;
+		for (&size, class) in classes {
+			let labels = BufferPoolLabels {
+				size: size_label(size),
+			};
+			let family_encoder = metric_encoder.encode_family(&labels)?;
+			ConstCounter::new(class.discarded.load(Ordering::Relaxed)).encode(family_encoder)?;
+		}
+		Ok(())
+	}
+}
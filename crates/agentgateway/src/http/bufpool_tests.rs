@@ -0,0 +1,64 @@
+use super::*;
+
+fn idle(pool: &Pool) -> Vec<usize> {
+	pool.classes.iter().map(|c| c.idle.lock().len()).collect()
+}
+
+#[test]
+fn test_reuse() {
+	let pool = Pool::default();
+	let mut buf = pool.get(0, SIZE_CLASSES[0]);
+	assert_eq!(pool.classes[0].misses.load(Ordering::Relaxed), 1);
+	buf.extend_from_slice(b"hello");
+	let ptr = buf.as_ptr();
+	pool.put(buf);
+	assert_eq!(idle(&pool), vec![1, 0, 0]);
+
+	let buf = pool.get(0, SIZE_CLASSES[0]);
+	assert_eq!(pool.classes[0].hits.load(Ordering::Relaxed), 1);
+	assert!(buf.is_empty());
+	assert_eq!(buf.as_ptr(), ptr);
+}
+
+#[test]
+fn test_size_classes() {
+	let pool = Pool::default();
+	// Too small to pool
+	pool.put(BytesMut::with_capacity(100));
+	assert_eq!(idle(&pool), vec![0, 0, 0]);
+	pool.put(BytesMut::with_capacity(100 * 1024));
+	assert_eq!(idle(&pool), vec![0, 1, 0]);
+	// Too large to keep
+	pool.put(BytesMut::with_capacity(SIZE_CLASSES[2] * 8));
+	assert_eq!(idle(&pool), vec![0, 1, 0]);
+	assert_eq!(pool.classes[2].discarded.load(Ordering::Relaxed), 1);
+
+	// Smaller requests are served from larger classes
+	let buf = pool.get(8 * 1024, 8 * 1024);
+	assert!(buf.capacity() >= 100 * 1024);
+	assert_eq!(idle(&pool), vec![0, 0, 0]);
+	// But not the other way around
+	pool.put(buf);
+	let buf = pool.get(SIZE_CLASSES[2], SIZE_CLASSES[2]);
+	assert!(buf.capacity() >= SIZE_CLASSES[2]);
+	assert_eq!(idle(&pool), vec![0, 1, 0]);
+	assert_eq!(pool.classes[2].misses.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn test_max_idle() {
+	let pool = Pool::default();
+	for _ in 0..MAX_IDLE + 1 {
+		pool.put(BytesMut::with_capacity(SIZE_CLASSES[0]));
+	}
+	assert_eq!(idle(&pool), vec![MAX_IDLE, 0, 0]);
+	assert_eq!(pool.classes[0].discarded.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn test_to_json() {
+	let a = to_json(&serde_json::json!({"a": 1})).unwrap();
+	let b = to_json(&serde_json::json!(["b"])).unwrap();
+	assert_eq!(a, Bytes::from_static(br#"{"a":1}"#));
+	assert_eq!(b, Bytes::from_static(br#"["b"]"#));
+}
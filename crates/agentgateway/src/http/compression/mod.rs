@@ -69,10 +69,10 @@ async fn read_to_bytes<R>(mut reader: R, limit: usize) -> Result<Bytes, axum_cor
 where
 	R: AsyncRead + Unpin,
 {
-	let mut buffer = bytes::BytesMut::new();
+	let mut buffer = crate::http::bufpool::get(0);
 	loop {
 		let n = reader
-			.read_buf(&mut buffer)
+			.read_buf(&mut *buffer)
 			.await
 			.map_err(axum_core::Error::new)?;
 		if buffer.len() > limit {
@@ -84,5 +84,5 @@ where
 			break;
 		}
 	}
-	Ok(buffer.freeze())
+	Ok(buffer.split().freeze())
 }
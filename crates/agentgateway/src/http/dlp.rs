@@ -0,0 +1,372 @@
+use std::sync::Arc;
+
+use ::http::{HeaderMap, header};
+use bytes::Bytes;
+use macro_rules_attribute::apply;
+use regex::{Regex, RegexBuilder};
+use tokio_sse_codec::{Event, Frame, SseDecoder, SseEncoder};
+use tower_serve_static::private::mime;
+
+use crate::http::{PolicyResponse, Request, Response};
+use crate::llm::policy::{Action, Builtin, RequestRejection, pii};
+use crate::proxy::ProxyError;
+use crate::telemetry::metrics::{DlpLabels, GuardrailAction, GuardrailPhase, Metrics};
+use crate::*;
+
+#[cfg(test)]
+#[path = "dlp_tests.rs"]
+mod tests;
+
+/// Sensitivity level, selecting which builtin detectors are enabled by default.
+#[apply(schema!)]
+#[derive(Copy, PartialEq, Eq, Default)]
+pub enum Sensitivity {
+	/// Credit card numbers and US social security numbers.
+	Low,
+	/// Everything in `low`, plus email addresses and phone numbers.
+	#[default]
+	Medium,
+	/// Everything in `medium`, plus Canadian social insurance numbers.
+	High,
+}
+
+impl Sensitivity {
+	fn builtins(&self) -> Vec<Builtin> {
+		let mut b = vec![Builtin::CreditCard, Builtin::Ssn];
+		if matches!(self, Sensitivity::Medium | Sensitivity::High) {
+			b.extend([Builtin::Email, Builtin::PhoneNumber]);
+		}
+		if matches!(self, Sensitivity::High) {
+			b.push(Builtin::CaSin);
+		}
+		b
+	}
+}
+
+/// Which payloads are scanned.
+#[apply(schema!)]
+#[derive(Copy, PartialEq, Eq, Default)]
+pub enum Scope {
+	Request,
+	Response,
+	#[default]
+	All,
+}
+
+impl Scope {
+	fn request(&self) -> bool {
+		matches!(self, Scope::Request | Scope::All)
+	}
+	fn response(&self) -> bool {
+		matches!(self, Scope::Response | Scope::All)
+	}
+}
+
+#[apply(schema!)]
+pub struct NamedPattern {
+	/// Name of the pattern, used in the mask (`<NAME>`) and in metrics.
+	pub name: String,
+	#[serde(with = "serde_regex")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub pattern: Regex,
+}
+
+/// Scans request and response bodies for sensitive data, masking or rejecting matches.
+#[apply(schema_de!)]
+pub struct LocalDlp {
+	#[serde(default)]
+	pub sensitivity: Sensitivity,
+	/// Builtin detectors to enable. If unset, the detectors for the `sensitivity` level are used.
+	#[serde(default)]
+	pub builtins: Option<Vec<Builtin>>,
+	/// Custom regex patterns to detect.
+	#[serde(default)]
+	pub patterns: Vec<NamedPattern>,
+	/// Keywords to detect, matched case-insensitively.
+	#[serde(default)]
+	pub keywords: Vec<String>,
+	#[serde(default)]
+	pub action: Action,
+	#[serde(default)]
+	pub scope: Scope,
+	/// Response returned when a match is found and `action` is `reject`.
+	#[serde(default)]
+	pub rejection: RequestRejection,
+}
+
+#[apply(schema_ser!)]
+#[cfg_attr(feature = "schema", schemars(with = "LocalDlp"))]
+pub struct Dlp {
+	pub action: Action,
+	pub scope: Scope,
+	pub rules: Vec<Rule>,
+	pub rejection: RequestRejection,
+}
+
+#[apply(schema_ser!)]
+pub enum Rule {
+	Builtin(Builtin),
+	Pattern(NamedPattern),
+	Keywords(
+		#[serde(serialize_with = "serde_regex::serialize")]
+		#[cfg_attr(feature = "schema", schemars(with = "String"))]
+		Regex,
+	),
+}
+
+impl TryFrom<LocalDlp> for Dlp {
+	type Error = anyhow::Error;
+
+	fn try_from(value: LocalDlp) -> Result<Self, Self::Error> {
+		let LocalDlp {
+			sensitivity,
+			builtins,
+			patterns,
+			keywords,
+			action,
+			scope,
+			rejection,
+		} = value;
+		let mut rules: Vec<Rule> = builtins
+			.unwrap_or_else(|| sensitivity.builtins())
+			.into_iter()
+			.map(Rule::Builtin)
+			.collect();
+		rules.extend(patterns.into_iter().map(Rule::Pattern));
+		if !keywords.is_empty() {
+			if keywords.iter().any(|k| k.is_empty()) {
+				anyhow::bail!("dlp keywords must not be empty");
+			}
+			let alternation = keywords
+				.iter()
+				.map(|k| regex::escape(k))
+				.collect::<Vec<_>>()
+				.join("|");
+			let re = RegexBuilder::new(&alternation)
+				.case_insensitive(true)
+				.build()?;
+			rules.push(Rule::Keywords(re));
+		}
+		if rules.is_empty() {
+			anyhow::bail!("dlp policy must have at least one rule");
+		}
+		Ok(Dlp {
+			action,
+			scope,
+			rules,
+			rejection,
+		})
+	}
+}
+
+/// The result of scanning a payload.
+#[derive(Debug)]
+pub struct Findings {
+	/// The payload with every match replaced by a mask.
+	pub masked: String,
+	/// The entity type of each match, in the order they were found.
+	pub entities: Vec<String>,
+}
+
+impl Dlp {
+	/// Scan the text, returning the masked text and matched entities if anything was found.
+	pub fn scan(&self, text: &str) -> Option<Findings> {
+		let mut current = text.to_string();
+		let mut entities = Vec::new();
+		// Rules are applied sequentially, so later rules see the masks from earlier ones
+		for rule in &self.rules {
+			let mut matches: Vec<(std::ops::Range<usize>, String)> = match rule {
+				Rule::Builtin(b) => pii::recognize_builtin(b, &current)
+					.into_iter()
+					.map(|r| (r.start..r.end, r.entity_type.to_uppercase()))
+					.collect(),
+				Rule::Pattern(p) => p
+					.pattern
+					.find_iter(&current)
+					.map(|m| (m.range(), p.name.to_uppercase()))
+					.collect(),
+				Rule::Keywords(re) => re
+					.find_iter(&current)
+					.map(|m| (m.range(), "KEYWORD".to_string()))
+					.collect(),
+			};
+			// Replace in reverse to avoid index shifting, skipping overlapping matches
+			matches.sort_by(|a, b| b.0.start.cmp(&a.0.start));
+			let mut limit = usize::MAX;
+			for (range, entity) in matches {
+				if range.end > limit {
+					continue;
+				}
+				limit = range.start;
+				let mask = if matches!(rule, Rule::Keywords(_)) {
+					"<REDACTED>".to_string()
+				} else {
+					format!("<{entity}>")
+				};
+				current.replace_range(range, &mask);
+				entities.push(entity);
+			}
+		}
+		if entities.is_empty() {
+			return None;
+		}
+		Some(Findings {
+			masked: current,
+			entities,
+		})
+	}
+
+	pub async fn apply_request(
+		&self,
+		req: &mut Request,
+		metrics: &Metrics,
+	) -> Result<PolicyResponse, ProxyError> {
+		if !self.scope.request() || body_kind(req.headers()) != Some(BodyKind::Buffered) {
+			return Ok(PolicyResponse::default());
+		}
+		let body = crate::http::inspect_body(req)
+			.await
+			.map_err(ProxyError::Processing)?;
+		let Some(findings) = std::str::from_utf8(&body).ok().and_then(|b| self.scan(b)) else {
+			return Ok(PolicyResponse::default());
+		};
+		self.record(metrics, GuardrailPhase::Request, &findings);
+		match self.action {
+			Action::Reject => {
+				debug!(entities=?findings.entities, "request rejected by DLP policy");
+				Ok(PolicyResponse::default().with_response(self.rejection.as_response()))
+			},
+			Action::Mask => {
+				*req.body_mut() = http::Body::from(findings.masked);
+				req.headers_mut().remove(header::CONTENT_LENGTH);
+				Ok(PolicyResponse::default())
+			},
+			Action::Annotate => {
+				debug!(entities=?findings.entities, "request annotated by DLP policy");
+				Ok(PolicyResponse::default())
+			},
+		}
+	}
+
+	pub async fn apply_response(
+		&self,
+		resp: &mut Response,
+		metrics: Arc<Metrics>,
+	) -> Result<(), ProxyError> {
+		if !self.scope.response() {
+			return Ok(());
+		}
+		match body_kind(resp.headers()) {
+			Some(BodyKind::Buffered) => {
+				let body = crate::http::inspect_response_body(resp)
+					.await
+					.map_err(ProxyError::Processing)?;
+				let Some(findings) = std::str::from_utf8(&body).ok().and_then(|b| self.scan(b)) else {
+					return Ok(());
+				};
+				self.record(&metrics, GuardrailPhase::Response, &findings);
+				match self.action {
+					Action::Reject => {
+						debug!(entities=?findings.entities, "response rejected by DLP policy");
+						*resp = self.rejection.as_response();
+					},
+					Action::Mask => {
+						*resp.body_mut() = http::Body::from(findings.masked);
+						resp.headers_mut().remove(header::CONTENT_LENGTH);
+					},
+					Action::Annotate => {
+						debug!(entities=?findings.entities, "response annotated by DLP policy");
+					},
+				}
+			},
+			Some(BodyKind::EventStream) => {
+				// Events that have already been sent cannot be rejected, so streams are masked instead.
+				let action = match self.action {
+					Action::Annotate => Action::Annotate,
+					Action::Mask | Action::Reject => Action::Mask,
+				};
+				let dlp = Dlp {
+					action,
+					..self.clone()
+				};
+				let limit = crate::http::response_buffer_limit(resp);
+				let body = std::mem::take(resp.body_mut());
+				*resp.body_mut() = mask_events(dlp, body, limit, metrics);
+				resp.headers_mut().remove(header::CONTENT_LENGTH);
+			},
+			None => {},
+		}
+		Ok(())
+	}
+
+	fn record(&self, metrics: &Metrics, phase: GuardrailPhase, findings: &Findings) {
+		let action = match self.action {
+			Action::Mask => GuardrailAction::Mask,
+			Action::Reject => GuardrailAction::Reject,
+			Action::Annotate => GuardrailAction::Annotate,
+		};
+		for entity in &findings.entities {
+			metrics
+				.dlp_redactions
+				.get_or_create(&DlpLabels {
+					phase,
+					action,
+					entity: strng::RichStrng::from(entity.as_str()).into(),
+				})
+				.inc();
+		}
+	}
+}
+
+fn mask_events(dlp: Dlp, body: http::Body, limit: usize, metrics: Arc<Metrics>) -> http::Body {
+	let decoder = SseDecoder::<Bytes>::with_max_size(limit);
+	let encoder = SseEncoder::new();
+	crate::parse::transform::parser(body, decoder, encoder, move |frame| {
+		let Frame::Event(Event::<Bytes> { data, name, id }) = frame else {
+			return Some(frame);
+		};
+		let findings = std::str::from_utf8(&data).ok().and_then(|d| dlp.scan(d));
+		let data = match findings {
+			Some(findings) => {
+				dlp.record(&metrics, GuardrailPhase::Response, &findings);
+				match dlp.action {
+					Action::Annotate => data,
+					Action::Mask | Action::Reject => Bytes::from(findings.masked),
+				}
+			},
+			None => data,
+		};
+		Some(Frame::Event(Event::<Bytes> { data, name, id }))
+	})
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BodyKind {
+	Buffered,
+	EventStream,
+}
+
+/// Determine if a body should be scanned. Only uncompressed textual payloads are inspected.
+fn body_kind(headers: &HeaderMap) -> Option<BodyKind> {
+	if headers
+		.get(header::CONTENT_ENCODING)
+		.is_some_and(|e| e.as_bytes() != b"identity")
+	{
+		return None;
+	}
+	let mime = headers
+		.get(header::CONTENT_TYPE)?
+		.to_str()
+		.ok()?
+		.parse::<mime::Mime>()
+		.ok()?;
+	match (mime.type_(), mime.subtype()) {
+		(mime::TEXT, mime::EVENT_STREAM) => Some(BodyKind::EventStream),
+		(mime::TEXT, _)
+		| (mime::APPLICATION, mime::JSON)
+		| (mime::APPLICATION, mime::WWW_FORM_URLENCODED)
+		| (mime::APPLICATION, mime::XML) => Some(BodyKind::Buffered),
+		_ if mime.suffix() == Some(mime::JSON) => Some(BodyKind::Buffered),
+		_ => None,
+	}
+}
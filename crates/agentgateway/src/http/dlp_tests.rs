@@ -0,0 +1,134 @@
+use prometheus_client::registry::Registry;
+
+use super::*;
+
+fn dlp(v: serde_json::Value) -> Dlp {
+	serde_json::from_value::<LocalDlp>(v)
+		.unwrap()
+		.try_into()
+		.unwrap()
+}
+
+fn metrics() -> Arc<Metrics> {
+	let mut registry = Registry::default();
+	Arc::new(Metrics::new(
+		&mut registry,
+		Default::default(),
+		Default::default(),
+	))
+}
+
+fn json_request(body: &'static str) -> Request {
+	::http::Request::builder()
+		.method("POST")
+		.uri("http://example.com/")
+		.header(header::CONTENT_TYPE, "application/json")
+		.body(crate::http::Body::from(body))
+		.unwrap()
+}
+
+#[test]
+fn test_sensitivity_levels() {
+	let low = dlp(serde_json::json!({"sensitivity": "low"}));
+	assert!(low.scan("reach me at test@example.com").is_none());
+	let found = low.scan("card 4111-1111-1111-1111").unwrap();
+	assert_eq!(found.masked, "card <CREDIT_CARD>");
+
+	let medium = dlp(serde_json::json!({}));
+	let found = medium.scan("reach me at test@example.com").unwrap();
+	assert_eq!(found.masked, "reach me at <EMAIL_ADDRESS>");
+}
+
+#[test]
+fn test_patterns_and_keywords() {
+	let d = dlp(serde_json::json!({
+		"builtins": [],
+		"patterns": [{"name": "employeeId", "pattern": "EMP-[0-9]{4}"}],
+		"keywords": ["Project Falcon", "a.b"],
+	}));
+	let found = d
+		.scan("EMP-1234 is on project falcon, see a.b not axb")
+		.unwrap();
+	assert_eq!(
+		found.masked,
+		"<EMPLOYEEID> is on <REDACTED>, see <REDACTED> not axb"
+	);
+	assert_eq!(found.entities, vec!["EMPLOYEEID", "KEYWORD", "KEYWORD"]);
+	assert!(d.scan("nothing to see here").is_none());
+}
+
+#[test]
+fn test_invalid_config() {
+	let res: anyhow::Result<Dlp> =
+		serde_json::from_value::<LocalDlp>(serde_json::json!({"builtins": []}))
+			.unwrap()
+			.try_into();
+	assert!(res.is_err());
+	let res: anyhow::Result<Dlp> =
+		serde_json::from_value::<LocalDlp>(serde_json::json!({"keywords": [""]}))
+			.unwrap()
+			.try_into();
+	assert!(res.is_err());
+}
+
+#[tokio::test]
+async fn test_request_mask() {
+	let d = dlp(serde_json::json!({"builtins": [], "keywords": ["secret"]}));
+	let metrics = metrics();
+	let mut req = json_request(r#"{"msg":"my secret"}"#);
+	let resp = d.apply_request(&mut req, &metrics).await.unwrap();
+	assert!(!resp.should_short_circuit());
+	let body = crate::http::read_body(req).await.unwrap();
+	assert_eq!(body.as_ref(), br#"{"msg":"my <REDACTED>"}"#);
+	let count = metrics
+		.dlp_redactions
+		.get_or_create(&DlpLabels {
+			phase: GuardrailPhase::Request,
+			action: GuardrailAction::Mask,
+			entity: strng::RichStrng::from("KEYWORD").into(),
+		})
+		.get();
+	assert_eq!(count, 1);
+}
+
+#[tokio::test]
+async fn test_request_reject() {
+	let d = dlp(serde_json::json!({
+		"builtins": [],
+		"keywords": ["secret"],
+		"action": "reject",
+	}));
+	let mut req = json_request(r#"{"msg":"my secret"}"#);
+	let resp = d.apply_request(&mut req, &metrics()).await.unwrap();
+	assert!(resp.should_short_circuit());
+
+	// Binary payloads are not scanned
+	let mut req = json_request("secret");
+	req
+		.headers_mut()
+		.insert(header::CONTENT_TYPE, "image/png".parse().unwrap());
+	let resp = d.apply_request(&mut req, &metrics()).await.unwrap();
+	assert!(!resp.should_short_circuit());
+}
+
+#[tokio::test]
+async fn test_response_event_stream() {
+	let d = dlp(serde_json::json!({
+		"builtins": [],
+		"keywords": ["secret"],
+		"action": "reject",
+	}));
+	let mut resp = ::http::Response::builder()
+		.header(header::CONTENT_TYPE, "text/event-stream")
+		.body(crate::http::Body::from("data: a secret\n\ndata: fine\n\n"))
+		.unwrap();
+	d.apply_response(&mut resp, metrics()).await.unwrap();
+	// Streams cannot be rejected once started, so matches are masked instead
+	let body = crate::http::read_body_with_limit(resp.into_body(), 1024)
+		.await
+		.unwrap();
+	let body = std::str::from_utf8(&body).unwrap();
+	assert!(body.contains("a <REDACTED>"), "{body}");
+	assert!(body.contains("fine"), "{body}");
+	assert!(!body.contains("secret"), "{body}");
+}
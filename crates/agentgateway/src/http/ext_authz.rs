@@ -280,13 +280,11 @@ impl ExtAuthz {
 			let original_size = 0;
 			match crate::http::inspect_body_with_limit(req.body_mut(), max_size).await {
 				Ok(body_bytes) => {
-					let bytes = body_bytes.to_vec();
-
 					if body_opts.pack_as_bytes {
-						(String::new(), bytes, original_size)
+						(String::new(), body_bytes.to_vec(), original_size)
 					} else {
 						(
-							String::from_utf8_lossy(&bytes).into_owned(),
+							String::from_utf8_lossy(&body_bytes).into_owned(),
 							Vec::new(),
 							original_size,
 						)
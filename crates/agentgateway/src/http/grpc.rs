@@ -0,0 +1,239 @@
+//! gRPC support.
+//!
+//! gRPC clients expect every response, including the errors the gateway generates itself, to carry
+//! a `grpc-status`, so those errors are sent as trailers-only responses with a status mapped from
+//! their HTTP status. Routes can also match the methods of gRPC services, learned from the backend
+//! with server reflection or from a compiled descriptor set.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use ::http::{HeaderMap, StatusCode};
+use prost::Message;
+
+use crate::client::Client;
+use crate::http::grpc::proto::server_reflection_client::ServerReflectionClient;
+use crate::http::grpc::proto::server_reflection_request::MessageRequest;
+use crate::http::grpc::proto::server_reflection_response::MessageResponse;
+use crate::http::grpc::proto::{ServerReflectionRequest, ServerReflectionResponse};
+use crate::http::{HeaderValue, Response, Uri, header};
+use crate::proxy::ProxyError;
+use crate::*;
+
+#[cfg(test)]
+#[path = "grpc_tests.rs"]
+mod tests;
+
+#[allow(warnings)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+pub mod proto {
+	tonic::include_proto!("grpc.reflection.v1");
+}
+
+pub const GRPC_STATUS: &str = "grpc-status";
+pub const GRPC_MESSAGE: &str = "grpc-message";
+
+/// The gRPC status codes the gateway produces, from
+/// https://github.com/grpc/grpc/blob/master/doc/statuscodes.md.
+pub mod code {
+	pub const OK: u8 = 0;
+	pub const UNKNOWN: u8 = 2;
+	pub const PERMISSION_DENIED: u8 = 7;
+	pub const UNIMPLEMENTED: u8 = 12;
+	pub const INTERNAL: u8 = 13;
+	pub const UNAVAILABLE: u8 = 14;
+	pub const UNAUTHENTICATED: u8 = 16;
+}
+
+/// Whether a request or response is gRPC, from its content type.
+pub fn is_grpc(headers: &HeaderMap) -> bool {
+	headers
+		.get(header::CONTENT_TYPE)
+		.is_some_and(|value| value.as_bytes().starts_with(b"application/grpc"))
+}
+
+/// The gRPC status of a response, if it is in the headers, as in a trailers-only response, or in
+/// the trailers.
+pub fn status(headers: &HeaderMap) -> Option<u8> {
+	std::str::from_utf8(headers.get(GRPC_STATUS)?.as_bytes())
+		.ok()?
+		.parse()
+		.ok()
+}
+
+/// The gRPC status for an HTTP status, as defined in
+/// https://github.com/grpc/grpc/blob/master/doc/http-grpc-status-mapping.md.
+pub fn status_from_http(status: StatusCode) -> u8 {
+	match status.as_u16() {
+		200 => code::OK,
+		400 => code::INTERNAL,
+		401 => code::UNAUTHENTICATED,
+		403 => code::PERMISSION_DENIED,
+		404 => code::UNIMPLEMENTED,
+		429 | 502 | 503 | 504 => code::UNAVAILABLE,
+		_ => code::UNKNOWN,
+	}
+}
+
+/// The response for an error generated by the gateway, for a gRPC client. It is trailers-only, with
+/// the gRPC status mapped from the status the error has for other clients. Other headers, such as
+/// `retry-after`, are kept.
+pub fn error_response(e: ProxyError) -> Response {
+	let message = e.to_string();
+	let mut resp = e.into_response();
+	let code = status_from_http(resp.status());
+	*resp.status_mut() = StatusCode::OK;
+	*resp.body_mut() = http::Body::empty();
+	let headers = resp.headers_mut();
+	headers.remove(header::CONTENT_LENGTH);
+	headers.insert(
+		header::CONTENT_TYPE,
+		HeaderValue::from_static("application/grpc"),
+	);
+	headers.insert(GRPC_STATUS, HeaderValue::from(u16::from(code)));
+	if let Ok(v) = HeaderValue::try_from(encode_message(&message)) {
+		headers.insert(GRPC_MESSAGE, v);
+	}
+	resp
+}
+
+/// Percent-encode a `grpc-message`, as the gRPC protocol requires.
+fn encode_message(message: &str) -> String {
+	let mut encoded = String::with_capacity(message.len());
+	for b in message.bytes() {
+		if (0x20..=0x7e).contains(&b) && b != b'%' {
+			encoded.push(b as char);
+		} else {
+			let _ = write!(encoded, "%{b:02X}");
+		}
+	}
+	encoded
+}
+
+/// The service and method of a gRPC request, from its `/<service>/<method>` path.
+pub fn service_method(path: &str) -> Option<(&str, &str)> {
+	let (service, method) = path.strip_prefix('/')?.split_once('/')?;
+	if service.is_empty() || method.is_empty() || method.contains('/') {
+		return None;
+	}
+	Some((service, method))
+}
+
+/// The methods of each service, by fully-qualified service name.
+pub type Services = BTreeMap<String, Vec<String>>;
+
+/// The services and their methods in a serialized `FileDescriptorSet`, as produced by
+/// `protoc --descriptor_set_out` or `buf build -o`.
+pub fn services_from_descriptors(descriptors: &[u8]) -> anyhow::Result<Services> {
+	let set = prost_types::FileDescriptorSet::decode(descriptors)?;
+	let mut services = Services::new();
+	for file in &set.file {
+		add_services(&mut services, file);
+	}
+	Ok(services)
+}
+
+fn add_services(services: &mut Services, file: &prost_types::FileDescriptorProto) {
+	for svc in &file.service {
+		let name = match file.package() {
+			"" => svc.name().to_string(),
+			package => format!("{package}.{}", svc.name()),
+		};
+		let methods = svc.method.iter().map(|m| m.name().to_string()).collect();
+		services.insert(name, methods);
+	}
+}
+
+/// Learn the services, and their methods, that the gRPC server at `target` serves, with server
+/// reflection. The reflection service itself is left out.
+pub async fn reflect(client: Client, target: &Uri) -> anyhow::Result<Services> {
+	let mut reflection =
+		ServerReflectionClient::with_origin(ClientChannel { client }, target.clone());
+	// The content of a list request is not checked.
+	let list = MessageRequest::ListServices("*".to_string());
+	let names: Vec<String> = match reflection_call(&mut reflection, [list]).await?.pop() {
+		Some(MessageResponse::ListServicesResponse(list)) => list
+			.service
+			.into_iter()
+			.map(|s| s.name)
+			.filter(|s| !s.starts_with("grpc.reflection."))
+			.collect(),
+		other => anyhow::bail!("unexpected reflection response: {other:?}"),
+	};
+
+	let mut services = Services::new();
+	let requests = names
+		.iter()
+		.map(|n| MessageRequest::FileContainingSymbol(n.clone()));
+	for resp in reflection_call(&mut reflection, requests).await? {
+		let MessageResponse::FileDescriptorResponse(files) = resp else {
+			anyhow::bail!("unexpected reflection response: {resp:?}");
+		};
+		for file in files.file_descriptor_proto {
+			add_services(
+				&mut services,
+				&prost_types::FileDescriptorProto::decode(file.as_slice())?,
+			);
+		}
+	}
+	// Files may declare more services than the server serves.
+	services.retain(|name, _| names.contains(name));
+	Ok(services)
+}
+
+/// Send reflection requests on a single stream, returning a response for each.
+async fn reflection_call(
+	reflection: &mut ServerReflectionClient<ClientChannel>,
+	requests: impl IntoIterator<Item = MessageRequest>,
+) -> anyhow::Result<Vec<MessageResponse>> {
+	let requests: Vec<ServerReflectionRequest> = requests
+		.into_iter()
+		.map(|r| ServerReflectionRequest {
+			host: String::new(),
+			message_request: Some(r),
+		})
+		.collect();
+	let mut stream = reflection
+		.server_reflection_info(futures_util::stream::iter(requests))
+		.await?
+		.into_inner();
+	let mut responses = vec![];
+	while let Some(ServerReflectionResponse {
+		message_response, ..
+	}) = stream.message().await?
+	{
+		match message_response {
+			Some(MessageResponse::ErrorResponse(e)) => {
+				anyhow::bail!("reflection failed: {} ({})", e.error_message, e.error_code)
+			},
+			Some(r) => responses.push(r),
+			None => {},
+		}
+	}
+	Ok(responses)
+}
+
+/// Sends gRPC requests with a [`Client`], for calls made while loading config, before policies
+/// and backends are available.
+#[derive(Clone)]
+struct ClientChannel {
+	client: Client,
+}
+
+impl tower::Service<::http::Request<tonic::body::Body>> for ClientChannel {
+	type Response = Response;
+	type Error = anyhow::Error;
+	type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+	fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		Ok(()).into()
+	}
+
+	fn call(&mut self, req: ::http::Request<tonic::body::Body>) -> Self::Future {
+		let client = self.client.clone();
+		let req = req.map(http::Body::new);
+		Box::pin(async move { Ok(client.simple_call(req).await?) })
+	}
+}
@@ -0,0 +1,134 @@
+use prost_types::{
+	FileDescriptorProto, FileDescriptorSet, MethodDescriptorProto, ServiceDescriptorProto,
+};
+
+use super::*;
+
+#[test]
+fn test_status_from_http() {
+	assert_eq!(status_from_http(StatusCode::OK), code::OK);
+	assert_eq!(status_from_http(StatusCode::BAD_REQUEST), code::INTERNAL);
+	assert_eq!(
+		status_from_http(StatusCode::UNAUTHORIZED),
+		code::UNAUTHENTICATED
+	);
+	assert_eq!(
+		status_from_http(StatusCode::FORBIDDEN),
+		code::PERMISSION_DENIED
+	);
+	assert_eq!(status_from_http(StatusCode::NOT_FOUND), code::UNIMPLEMENTED);
+	assert_eq!(
+		status_from_http(StatusCode::TOO_MANY_REQUESTS),
+		code::UNAVAILABLE
+	);
+	assert_eq!(
+		status_from_http(StatusCode::SERVICE_UNAVAILABLE),
+		code::UNAVAILABLE
+	);
+	assert_eq!(
+		status_from_http(StatusCode::INTERNAL_SERVER_ERROR),
+		code::UNKNOWN
+	);
+}
+
+#[test]
+fn test_error_response() {
+	let resp = error_response(ProxyError::NoHealthyEndpoints);
+	assert_eq!(resp.status(), StatusCode::OK);
+	assert!(is_grpc(resp.headers()));
+	assert_eq!(status(resp.headers()), Some(code::UNAVAILABLE));
+	assert_eq!(
+		resp.headers().get(GRPC_MESSAGE).unwrap(),
+		"no healthy backends"
+	);
+
+	let resp = error_response(ProxyError::RateLimitExceeded {
+		limit: 10,
+		remaining: 0,
+		reset_seconds: 5,
+	});
+	assert_eq!(status(resp.headers()), Some(code::UNAVAILABLE));
+	// Headers for other clients are kept.
+	assert_eq!(
+		resp
+			.headers()
+			.get(http::x_headers::X_RATELIMIT_LIMIT)
+			.unwrap(),
+		"10"
+	);
+
+	let resp = error_response(ProxyError::AuthorizationFailed);
+	assert_eq!(status(resp.headers()), Some(code::PERMISSION_DENIED));
+}
+
+#[test]
+fn test_encode_message() {
+	assert_eq!(encode_message("no healthy backends"), "no healthy backends");
+	assert_eq!(encode_message("100% done"), "100%25 done");
+	assert_eq!(encode_message("a\nb"), "a%0Ab");
+	assert_eq!(encode_message("café"), "caf%C3%A9");
+}
+
+#[test]
+fn test_status() {
+	let mut headers = HeaderMap::new();
+	assert_eq!(status(&headers), None);
+	headers.insert(GRPC_STATUS, HeaderValue::from_static("14"));
+	assert_eq!(status(&headers), Some(14));
+	headers.insert(GRPC_STATUS, HeaderValue::from_static("unavailable"));
+	assert_eq!(status(&headers), None);
+}
+
+#[test]
+fn test_service_method() {
+	assert_eq!(
+		service_method("/helloworld.Greeter/SayHello"),
+		Some(("helloworld.Greeter", "SayHello"))
+	);
+	assert_eq!(service_method("/helloworld.Greeter"), None);
+	assert_eq!(service_method("/helloworld.Greeter/"), None);
+	assert_eq!(service_method("//SayHello"), None);
+	assert_eq!(service_method("/a/b/c"), None);
+	assert_eq!(service_method("a/b"), None);
+}
+
+#[test]
+fn test_services_from_descriptors() {
+	let method = |name: &str| MethodDescriptorProto {
+		name: Some(name.to_string()),
+		..Default::default()
+	};
+	let set = FileDescriptorSet {
+		file: vec![
+			FileDescriptorProto {
+				package: Some("helloworld".to_string()),
+				service: vec![ServiceDescriptorProto {
+					name: Some("Greeter".to_string()),
+					method: vec![method("SayHello"), method("SayGoodbye")],
+					..Default::default()
+				}],
+				..Default::default()
+			},
+			FileDescriptorProto {
+				service: vec![ServiceDescriptorProto {
+					name: Some("Echo".to_string()),
+					method: vec![method("Echo")],
+					..Default::default()
+				}],
+				..Default::default()
+			},
+		],
+	};
+	let services = services_from_descriptors(&set.encode_to_vec()).unwrap();
+	assert_eq!(
+		services,
+		Services::from([
+			("Echo".to_string(), vec!["Echo".to_string()]),
+			(
+				"helloworld.Greeter".to_string(),
+				vec!["SayHello".to_string(), "SayGoodbye".to_string()]
+			),
+		])
+	);
+	assert!(services_from_descriptors(b"not a descriptor set").is_err());
+}
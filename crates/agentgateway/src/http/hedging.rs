@@ -0,0 +1,92 @@
+use std::collections::VecDeque;
+
+use ::http::Method;
+use macro_rules_attribute::apply;
+use parking_lot::Mutex;
+
+use crate::*;
+
+#[cfg(test)]
+#[path = "hedging_tests.rs"]
+mod tests;
+
+/// How many recent response latencies the hedging delay is computed from.
+const WINDOW: usize = 256;
+/// How many responses must be observed before the percentile is used instead of `initialDelay`.
+const MIN_SAMPLES: usize = 20;
+
+/// Send a second attempt of idempotent requests that take longer than most, and return whichever
+/// attempt responds first.
+///
+/// The second attempt is sent once the request has taken longer than `percentile` of recent
+/// responses on the route. Endpoints are picked by their load, so the second attempt usually goes
+/// to another endpoint than the first, which is still in flight. The attempt that loses is
+/// cancelled. Hedged requests are not retried.
+#[apply(schema!)]
+pub struct Policy {
+	/// The percentile of recent response latencies after which the second attempt is sent. Defaults
+	/// to 95.
+	#[serde(default = "default_percentile")]
+	pub percentile: f64,
+	/// The delay before the second attempt until enough responses have been observed. Defaults to
+	/// 100ms.
+	#[serde(default = "default_initial_delay", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub initial_delay: Duration,
+	/// The shortest delay before the second attempt, so fast routes are not hedged on noise.
+	/// Defaults to 10ms.
+	#[serde(default = "default_min_delay", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub min_delay: Duration,
+	#[serde(skip)]
+	latencies: Arc<Mutex<VecDeque<Duration>>>,
+}
+
+fn default_percentile() -> f64 {
+	95.0
+}
+
+fn default_initial_delay() -> Duration {
+	Duration::from_millis(100)
+}
+
+fn default_min_delay() -> Duration {
+	Duration::from_millis(10)
+}
+
+impl Policy {
+	pub fn validate(&self) -> anyhow::Result<()> {
+		if !(self.percentile > 0.0 && self.percentile < 100.0) {
+			anyhow::bail!("percentile must be between 0 and 100");
+		}
+		Ok(())
+	}
+
+	/// Whether requests with `method` may be hedged. Only idempotent methods are, as the backend may
+	/// process both attempts.
+	pub fn applies(&self, method: &Method) -> bool {
+		method.is_idempotent()
+	}
+
+	/// How long to wait for the first attempt before sending the second.
+	pub fn delay(&self) -> Duration {
+		let latencies = self.latencies.lock();
+		if latencies.len() < MIN_SAMPLES {
+			return self.initial_delay.max(self.min_delay);
+		}
+		let mut sorted: Vec<Duration> = latencies.iter().copied().collect();
+		drop(latencies);
+		let idx = ((sorted.len() - 1) as f64 * self.percentile / 100.0).round() as usize;
+		let (_, p, _) = sorted.select_nth_unstable(idx);
+		(*p).max(self.min_delay)
+	}
+
+	/// Record the latency of a response, to compute later delays from.
+	pub fn record(&self, latency: Duration) {
+		let mut latencies = self.latencies.lock();
+		if latencies.len() == WINDOW {
+			latencies.pop_front();
+		}
+		latencies.push_back(latency);
+	}
+}
@@ -0,0 +1,68 @@
+use super::*;
+
+fn policy(cfg: serde_json::Value) -> Policy {
+	serde_json::from_value(cfg).unwrap()
+}
+
+fn ms(n: u64) -> Duration {
+	Duration::from_millis(n)
+}
+
+#[test]
+fn test_config() {
+	let p = policy(serde_json::json!({}));
+	assert_eq!(p.percentile, 95.0);
+	assert_eq!(p.initial_delay, ms(100));
+	assert_eq!(p.min_delay, ms(10));
+	p.validate().unwrap();
+
+	assert!(
+		policy(serde_json::json!({"percentile": 0}))
+			.validate()
+			.is_err()
+	);
+	assert!(
+		policy(serde_json::json!({"percentile": 100}))
+			.validate()
+			.is_err()
+	);
+}
+
+#[test]
+fn test_applies() {
+	let p = policy(serde_json::json!({}));
+	assert!(p.applies(&Method::GET));
+	assert!(p.applies(&Method::PUT));
+	assert!(p.applies(&Method::DELETE));
+	assert!(!p.applies(&Method::POST));
+	assert!(!p.applies(&Method::PATCH));
+}
+
+#[test]
+fn test_delay() {
+	let p = policy(serde_json::json!({"percentile": 90, "initialDelay": "50ms"}));
+	// Not enough responses yet
+	for _ in 0..MIN_SAMPLES - 1 {
+		p.record(ms(1));
+	}
+	assert_eq!(p.delay(), ms(50));
+
+	// 1..=100ms, so the 90th percentile is 90ms
+	let p = policy(serde_json::json!({"percentile": 90}));
+	for n in 1..=100 {
+		p.record(ms(n));
+	}
+	assert_eq!(p.delay(), ms(90));
+
+	// Only the most recent responses are used
+	for _ in 0..WINDOW {
+		p.record(ms(20));
+	}
+	assert_eq!(p.delay(), ms(20));
+
+	// But never less than the minimum
+	for _ in 0..WINDOW {
+		p.record(ms(1));
+	}
+	assert_eq!(p.delay(), ms(10));
+}
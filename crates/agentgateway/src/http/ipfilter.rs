@@ -0,0 +1,132 @@
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+
+use ::http::HeaderMap;
+use ipnet::IpNet;
+use macro_rules_attribute::apply;
+use serde::{Deserialize, Deserializer};
+
+use crate::http::Request;
+use crate::http::x_headers::X_FORWARDED_FOR;
+use crate::proxy::ProxyError;
+use crate::transport::stream::TCPConnectionInfo;
+use crate::*;
+
+#[cfg(test)]
+#[path = "ipfilter_tests.rs"]
+mod tests;
+
+/// Allow or deny requests based on the client IP address.
+///
+/// Deny entries take precedence over allow entries. If `allow` is set, only matching clients are
+/// permitted. Entries may be CIDR ranges or single IP addresses.
+#[apply(schema!)]
+pub struct IpFilter {
+	/// Client addresses that are permitted. If empty, all addresses not denied are permitted.
+	#[serde(
+		default,
+		deserialize_with = "de_cidrs",
+		skip_serializing_if = "Vec::is_empty"
+	)]
+	#[cfg_attr(feature = "schema", schemars(with = "Vec<String>"))]
+	pub allow: Vec<IpNet>,
+	/// Client addresses that are rejected.
+	#[serde(
+		default,
+		deserialize_with = "de_cidrs",
+		skip_serializing_if = "Vec::is_empty"
+	)]
+	#[cfg_attr(feature = "schema", schemars(with = "Vec<String>"))]
+	pub deny: Vec<IpNet>,
+	/// Proxies whose `X-Forwarded-For` entries are trusted. When the connecting peer (after PROXY
+	/// protocol, if enabled) is a trusted proxy, the client address is taken from the
+	/// `X-Forwarded-For` header, skipping any further trusted proxies from right to left.
+	#[serde(
+		default,
+		deserialize_with = "de_cidrs",
+		skip_serializing_if = "Vec::is_empty"
+	)]
+	#[cfg_attr(feature = "schema", schemars(with = "Vec<String>"))]
+	pub trusted_proxies: Vec<IpNet>,
+	/// Number of proxy hops in front of agentgateway that are always trusted, regardless of their
+	/// address. A value of 1 uses the rightmost `X-Forwarded-For` entry as the client address.
+	#[serde(default, skip_serializing_if = "is_default")]
+	pub trusted_hops: usize,
+}
+
+impl IpFilter {
+	pub fn apply(&self, req: &Request) -> Result<(), ProxyError> {
+		let Some(tcp) = req.extensions().get::<TCPConnectionInfo>() else {
+			return Err(ProxyError::IpAccessDenied);
+		};
+		let client = self.client_ip(tcp.peer_addr.ip(), req.headers());
+		if self.is_allowed(client) {
+			Ok(())
+		} else {
+			debug!(%client, "request rejected by IP filter");
+			Err(ProxyError::IpAccessDenied)
+		}
+	}
+
+	pub fn is_allowed(&self, ip: IpAddr) -> bool {
+		let ip = ip.to_canonical();
+		if self.deny.iter().any(|n| n.contains(&ip)) {
+			return false;
+		}
+		self.allow.is_empty() || self.allow.iter().any(|n| n.contains(&ip))
+	}
+
+	/// Determine the real client address from the connecting peer and the `X-Forwarded-For` chain.
+	pub fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+		let mut client = peer.to_canonical();
+		if self.trusted_hops == 0 && self.trusted_proxies.is_empty() {
+			return client;
+		}
+		let forwarded: Vec<&str> = headers
+			.get_all(X_FORWARDED_FOR)
+			.iter()
+			.filter_map(|v| v.to_str().ok())
+			.flat_map(|v| v.split(','))
+			.map(str::trim)
+			.collect();
+		for (hop, entry) in forwarded.iter().rev().enumerate() {
+			if !self.is_trusted(client, hop) {
+				break;
+			}
+			let Some(ip) = parse_forwarded(entry) else {
+				break;
+			};
+			client = ip;
+		}
+		client
+	}
+
+	fn is_trusted(&self, ip: IpAddr, hop: usize) -> bool {
+		hop < self.trusted_hops || self.trusted_proxies.iter().any(|n| n.contains(&ip))
+	}
+}
+
+fn parse_forwarded(s: &str) -> Option<IpAddr> {
+	IpAddr::from_str(s)
+		.or_else(|_| SocketAddr::from_str(s).map(|s| s.ip()))
+		.ok()
+		.map(|ip| ip.to_canonical())
+}
+
+fn parse_cidr(s: &str) -> anyhow::Result<IpNet> {
+	if let Ok(net) = IpNet::from_str(s) {
+		return Ok(net);
+	}
+	let ip = IpAddr::from_str(s).map_err(|_| anyhow::anyhow!("invalid CIDR or IP address: {s}"))?;
+	Ok(IpNet::from(ip))
+}
+
+pub(crate) fn de_cidrs<'de, D>(deserializer: D) -> Result<Vec<IpNet>, D::Error>
+where
+	D: Deserializer<'de>,
+{
+	Vec::<String>::deserialize(deserializer)?
+		.iter()
+		.map(|s| parse_cidr(s.trim()).map_err(serde::de::Error::custom))
+		.collect()
+}
@@ -0,0 +1,74 @@
+use super::*;
+
+fn filter(v: serde_json::Value) -> IpFilter {
+	serde_json::from_value(v).unwrap()
+}
+
+fn ip(s: &str) -> IpAddr {
+	s.parse().unwrap()
+}
+
+fn xff(values: &[&str]) -> HeaderMap {
+	let mut hm = HeaderMap::new();
+	for v in values {
+		hm.append(X_FORWARDED_FOR, v.parse().unwrap());
+	}
+	hm
+}
+
+#[test]
+fn test_allow_deny() {
+	let f = filter(serde_json::json!({
+		"allow": ["10.0.0.0/8", "192.168.1.1"],
+		"deny": ["10.1.0.0/16"],
+	}));
+	assert!(f.is_allowed(ip("10.0.0.1")));
+	assert!(f.is_allowed(ip("192.168.1.1")));
+	assert!(!f.is_allowed(ip("192.168.1.2")));
+	assert!(!f.is_allowed(ip("10.1.2.3")));
+	// IPv4-mapped IPv6 peers match IPv4 ranges
+	assert!(f.is_allowed(ip("::ffff:10.0.0.1")));
+
+	let f = filter(serde_json::json!({"deny": ["2001:db8::/32"]}));
+	assert!(!f.is_allowed(ip("2001:db8::1")));
+	assert!(f.is_allowed(ip("127.0.0.1")));
+}
+
+#[test]
+fn test_invalid_cidr() {
+	let res = serde_json::from_value::<IpFilter>(serde_json::json!({"allow": ["not-an-ip"]}));
+	assert!(res.is_err());
+}
+
+#[test]
+fn test_client_ip_untrusted_peer() {
+	let f = filter(serde_json::json!({"trustedProxies": ["10.0.0.0/8"]}));
+	// Peer is not trusted, so X-Forwarded-For is ignored
+	let headers = xff(&["1.1.1.1"]);
+	assert_eq!(f.client_ip(ip("8.8.8.8"), &headers), ip("8.8.8.8"));
+	// No trust configured at all
+	let f = filter(serde_json::json!({}));
+	assert_eq!(f.client_ip(ip("10.0.0.1"), &headers), ip("10.0.0.1"));
+}
+
+#[test]
+fn test_client_ip_trusted_proxies() {
+	let f = filter(serde_json::json!({"trustedProxies": ["10.0.0.0/8"]}));
+	let headers = xff(&["6.6.6.6, 1.1.1.1", "10.0.0.2"]);
+	// Skip trusted proxies from the right; a spoofed leftmost entry is not used
+	assert_eq!(f.client_ip(ip("10.0.0.1"), &headers), ip("1.1.1.1"));
+}
+
+#[test]
+fn test_client_ip_trusted_hops() {
+	let f = filter(serde_json::json!({"trustedHops": 1}));
+	let headers = xff(&["6.6.6.6, 1.1.1.1:1234"]);
+	assert_eq!(f.client_ip(ip("172.16.0.1"), &headers), ip("1.1.1.1"));
+
+	let f = filter(serde_json::json!({"trustedHops": 2}));
+	assert_eq!(f.client_ip(ip("172.16.0.1"), &headers), ip("6.6.6.6"));
+
+	// More hops than entries falls back to the leftmost entry
+	let f = filter(serde_json::json!({"trustedHops": 5}));
+	assert_eq!(f.client_ip(ip("172.16.0.1"), &headers), ip("6.6.6.6"));
+}
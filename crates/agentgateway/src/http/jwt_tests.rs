@@ -369,8 +369,10 @@ fn make_min_req_log() -> crate::telemetry::log::RequestLog {
 		fields: LoggingFields::default(),
 		metric_fields: Arc::new(MetricFields::default()),
 		excluded_metrics: FzHashSet::default(),
+		histogram_buckets: Default::default(),
 		level: "info".to_string(),
 		format: crate::LoggingFormat::Text,
+		otlp: None,
 	};
 	let tracing_cfg = trc::Config {
 		endpoint: None,
@@ -380,10 +382,16 @@ fn make_min_req_log() -> crate::telemetry::log::RequestLog {
 		random_sampling: None,
 		client_sampling: None,
 		path: "/v1/traces".to_string(),
+		propagation: trc::default_propagation(),
+		tail_sampling: None,
 	};
 	let cel = log::CelLogging::new(log_cfg, tracing_cfg);
 	let mut prom = Registry::default();
-	let metrics = Arc::new(Metrics::new(&mut prom, FzHashSet::default()));
+	let metrics = Arc::new(Metrics::new(
+		&mut prom,
+		FzHashSet::default(),
+		Default::default(),
+	));
 	let start = Instant::now();
 	let start_time = agent_core::telemetry::render_current_time();
 	let tcp_info = TCPConnectionInfo {
@@ -0,0 +1,179 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use ::http::{StatusCode, header};
+use parking_lot::Mutex;
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserializer;
+
+use crate::http::{Request, Response};
+use crate::serdes::FileOrInline;
+use crate::*;
+
+#[cfg(test)]
+#[path = "keypool_tests.rs"]
+mod tests;
+
+/// Throttled responses are counted over this window when selecting the least throttled key.
+const THROTTLE_WINDOW: Duration = Duration::from_secs(60);
+
+/// A pool of API keys for a backend, such as several organization keys for one provider. Each
+/// request is sent with one key as a bearer token. Keys rejected with a `401`, `402`, or `403`
+/// are quarantined, and not used again until the quarantine ends; throttled keys (`429`) are
+/// quarantined for the response's `Retry-After`, if it has one. If every key is quarantined, the
+/// one released soonest is used.
+/// Keys may be secret references, so keys rotated in the secret provider are used once the
+/// secret is refreshed.
+#[apply(schema!)]
+pub struct KeyPool {
+	#[cfg_attr(feature = "schema", schemars(with = "Vec<FileOrInline>"))]
+	#[serde(
+		serialize_with = "ser_redact",
+		deserialize_with = "deser_keys_from_file"
+	)]
+	pub keys: Vec<SecretString>,
+	#[serde(default)]
+	pub selection: KeySelection,
+	/// How long a key rejected for authentication or quota is left unused. Defaults to 5m.
+	#[serde(default = "default_quarantine", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub quarantine: Duration,
+	#[serde(skip)]
+	state: Arc<PoolState>,
+}
+
+#[apply(schema!)]
+#[derive(Default, Copy, PartialEq, Eq)]
+pub enum KeySelection {
+	/// Use each key in turn.
+	#[default]
+	RoundRobin,
+	/// Use the key with the fewest throttled responses in the last minute, taking keys in turn
+	/// when tied.
+	LeastThrottled,
+}
+
+fn default_quarantine() -> Duration {
+	Duration::from_secs(300)
+}
+
+fn deser_keys_from_file<'de, D>(deserializer: D) -> Result<Vec<SecretString>, D::Error>
+where
+	D: Deserializer<'de>,
+{
+	let keys = Vec::<FileOrInline>::deserialize(deserializer)?;
+	if keys.is_empty() {
+		return Err(serde::de::Error::custom(
+			"key pool must have at least one key",
+		));
+	}
+	keys
+		.iter()
+		.map(|k| {
+			k.load()
+				.map(|k| SecretString::from(k.trim().to_string()))
+				.map_err(|e| serde::de::Error::custom(e.to_string()))
+		})
+		.collect()
+}
+
+#[derive(Debug, Default)]
+struct PoolState {
+	next: AtomicUsize,
+	keys: Mutex<Vec<KeyState>>,
+}
+
+#[derive(Debug, Default)]
+struct KeyState {
+	quarantined_until: Option<Instant>,
+	throttled: VecDeque<Instant>,
+}
+
+/// The key a request was sent with, carried to its response so the key can be quarantined.
+#[derive(Debug, Clone)]
+pub struct KeyLease {
+	state: Arc<PoolState>,
+	index: usize,
+	quarantine: Duration,
+}
+
+impl KeyPool {
+	/// Set the authorization of a request to the next key, recording the key used as a
+	/// [KeyLease] extension.
+	pub fn apply(&self, req: &mut Request) {
+		let index = self.select(Instant::now());
+		if let Ok(mut token) =
+			::http::HeaderValue::from_str(&format!("Bearer {}", self.keys[index].expose_secret()))
+		{
+			token.set_sensitive(true);
+			req.headers_mut().insert(header::AUTHORIZATION, token);
+		}
+		req.extensions_mut().insert(KeyLease {
+			state: self.state.clone(),
+			index,
+			quarantine: self.quarantine,
+		});
+	}
+
+	fn select(&self, now: Instant) -> usize {
+		let n = self.keys.len();
+		let start = self.state.next.fetch_add(1, Ordering::Relaxed) % n;
+		let mut keys = self.state.keys.lock();
+		keys.resize_with(n, Default::default);
+		for k in keys.iter_mut() {
+			while k
+				.throttled
+				.front()
+				.is_some_and(|t| now.duration_since(*t) > THROTTLE_WINDOW)
+			{
+				k.throttled.pop_front();
+			}
+		}
+		let mut available = (0..n)
+			.map(|i| (start + i) % n)
+			.filter(|i| keys[*i].quarantined_until.is_none_or(|t| t <= now));
+		let selected = match self.selection {
+			KeySelection::RoundRobin => available.next(),
+			KeySelection::LeastThrottled => available.min_by_key(|i| keys[*i].throttled.len()),
+		};
+		selected.unwrap_or_else(|| {
+			(0..n)
+				.min_by_key(|i| keys[*i].quarantined_until)
+				.unwrap_or_default()
+		})
+	}
+}
+
+impl KeyLease {
+	/// Record the response to a request sent with the key.
+	pub fn record(&self, resp: &Response) {
+		let retry_after = resp
+			.headers()
+			.get(header::RETRY_AFTER)
+			.and_then(|v| v.to_str().ok())
+			.and_then(|v| v.parse::<u64>().ok())
+			.map(Duration::from_secs);
+		self.record_status(resp.status(), retry_after, Instant::now());
+	}
+
+	fn record_status(&self, status: StatusCode, retry_after: Option<Duration>, now: Instant) {
+		let quarantine = match status {
+			StatusCode::UNAUTHORIZED | StatusCode::PAYMENT_REQUIRED | StatusCode::FORBIDDEN => {
+				Some(self.quarantine)
+			},
+			StatusCode::TOO_MANY_REQUESTS => retry_after,
+			_ => None,
+		};
+		let mut keys = self.state.keys.lock();
+		let Some(key) = keys.get_mut(self.index) else {
+			return;
+		};
+		if status == StatusCode::TOO_MANY_REQUESTS {
+			key.throttled.push_back(now);
+		}
+		if let Some(quarantine) = quarantine {
+			warn!(key = self.index, %status, ?quarantine, "quarantining backend API key");
+			key.quarantined_until = Some(now + quarantine);
+		}
+	}
+}
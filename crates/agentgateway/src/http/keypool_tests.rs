@@ -0,0 +1,120 @@
+use super::*;
+
+fn pool(selection: KeySelection) -> KeyPool {
+	serde_json::from_value(serde_json::json!({
+		"keys": ["key-a", "key-b", "key-c"],
+		"selection": selection,
+	}))
+	.unwrap()
+}
+
+fn lease(pool: &KeyPool, index: usize) -> KeyLease {
+	KeyLease {
+		state: pool.state.clone(),
+		index,
+		quarantine: pool.quarantine,
+	}
+}
+
+#[test]
+fn test_config() {
+	let p = pool(KeySelection::RoundRobin);
+	assert_eq!(p.keys.len(), 3);
+	assert_eq!(p.keys[1].expose_secret(), "key-b");
+	assert_eq!(p.quarantine, Duration::from_secs(300));
+	// Keys are never serialized
+	let out = serde_json::to_string(&p).unwrap();
+	assert!(!out.contains("key-a"), "{out}");
+
+	let empty = serde_json::from_value::<KeyPool>(serde_json::json!({"keys": []}));
+	assert!(empty.is_err());
+}
+
+#[test]
+fn test_round_robin() {
+	let p = pool(KeySelection::RoundRobin);
+	let now = Instant::now();
+	let picks = (0..4).map(|_| p.select(now)).collect::<Vec<_>>();
+	assert_eq!(picks, vec![0, 1, 2, 0]);
+}
+
+#[test]
+fn test_quarantine() {
+	let p = pool(KeySelection::RoundRobin);
+	let now = Instant::now();
+	p.select(now);
+	lease(&p, 1).record_status(StatusCode::UNAUTHORIZED, None, now);
+	// Key 1 is skipped while quarantined
+	let picks = (0..4).map(|_| p.select(now)).collect::<Vec<_>>();
+	assert_eq!(picks, vec![2, 2, 0, 2]);
+	// and used again once released
+	let later = now + Duration::from_secs(301);
+	let picks = (0..3).map(|_| p.select(later)).collect::<Vec<_>>();
+	assert_eq!(picks, vec![2, 0, 1]);
+
+	// Server errors do not quarantine a key
+	lease(&p, 2).record_status(StatusCode::INTERNAL_SERVER_ERROR, None, later);
+	assert_eq!(p.select(later), 2);
+}
+
+#[test]
+fn test_all_quarantined() {
+	let p = pool(KeySelection::RoundRobin);
+	let now = Instant::now();
+	p.select(now);
+	lease(&p, 0).record_status(StatusCode::FORBIDDEN, None, now);
+	lease(&p, 1).record_status(
+		StatusCode::TOO_MANY_REQUESTS,
+		Some(Duration::from_secs(10)),
+		now,
+	);
+	lease(&p, 2).record_status(StatusCode::PAYMENT_REQUIRED, None, now);
+	// The key released soonest is used
+	assert_eq!(p.select(now), 1);
+	assert_eq!(p.select(now), 1);
+}
+
+#[test]
+fn test_least_throttled() {
+	let p = pool(KeySelection::LeastThrottled);
+	let now = Instant::now();
+	p.select(now);
+	// Throttled without Retry-After, so not quarantined
+	lease(&p, 0).record_status(StatusCode::TOO_MANY_REQUESTS, None, now);
+	lease(&p, 0).record_status(StatusCode::TOO_MANY_REQUESTS, None, now);
+	lease(&p, 1).record_status(StatusCode::TOO_MANY_REQUESTS, None, now);
+	let picks = (0..3).map(|_| p.select(now)).collect::<Vec<_>>();
+	assert_eq!(picks, vec![2, 2, 2]);
+
+	// Throttles are forgotten after the window
+	let later = now + THROTTLE_WINDOW + Duration::from_secs(1);
+	let picks = (0..3).map(|_| p.select(later)).collect::<Vec<_>>();
+	assert_eq!(picks, vec![1, 2, 0]);
+}
+
+#[test]
+fn test_apply() {
+	let p = pool(KeySelection::RoundRobin);
+	let mut req = crate::http::Request::new(crate::http::Body::empty());
+	p.apply(&mut req);
+	let auth = req.headers().get(header::AUTHORIZATION).unwrap();
+	assert_eq!(auth.to_str().unwrap(), "Bearer key-a");
+	assert!(auth.is_sensitive());
+
+	let lease = req.extensions().get::<KeyLease>().unwrap().clone();
+	let resp = ::http::Response::builder()
+		.status(StatusCode::TOO_MANY_REQUESTS)
+		.header(header::RETRY_AFTER, "30")
+		.body(crate::http::Body::empty())
+		.unwrap();
+	lease.record(&resp);
+	let mut req = crate::http::Request::new(crate::http::Body::empty());
+	p.apply(&mut req);
+	p.apply(&mut req);
+	p.apply(&mut req);
+	// Key a is skipped while quarantined for the Retry-After
+	assert_eq!(
+		req.headers().get(header::AUTHORIZATION).unwrap(),
+		"Bearer key-b"
+	);
+}
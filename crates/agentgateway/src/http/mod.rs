@@ -8,19 +8,32 @@ pub mod localratelimit;
 pub mod retry;
 pub mod route;
 
+pub mod adaptiveconcurrency;
 pub mod apikey;
 pub mod auth;
 pub mod authorization;
 pub mod backendtls;
 pub mod basicauth;
+pub mod bufpool;
 pub mod compression;
 pub mod csrf;
+pub mod dlp;
 pub mod ext_authz;
 pub mod ext_proc;
+pub mod grpc;
+pub mod hedging;
+pub mod ipfilter;
+pub mod keypool;
+pub mod opa;
 pub mod outlierdetection;
 mod peekbody;
+pub mod queue;
+pub mod quota;
 pub mod remoteratelimit;
+pub mod requestid;
 pub mod sessionpersistence;
+pub mod sessiontoken;
+pub mod signature;
 #[cfg(any(test, feature = "internal_benches"))]
 pub mod tests_common;
 pub mod transformation_cel;
@@ -274,6 +287,12 @@ pub mod x_headers {
 	pub const X_RATELIMIT_REMAINING: HeaderName = HeaderName::from_static("x-ratelimit-remaining");
 	pub const X_RATELIMIT_RESET: HeaderName = HeaderName::from_static("x-ratelimit-reset");
 	pub const X_AMZN_REQUESTID: HeaderName = HeaderName::from_static("x-amzn-requestid");
+	pub const X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+	pub const X_QUOTA_LIMIT: HeaderName = HeaderName::from_static("x-quota-limit");
+	pub const X_QUOTA_REMAINING: HeaderName = HeaderName::from_static("x-quota-remaining");
+	pub const X_QUOTA_RESET: HeaderName = HeaderName::from_static("x-quota-reset");
+	pub const X_QUOTA_WARNING: HeaderName = HeaderName::from_static("x-quota-warning");
+	pub const X_EXPERIMENT_VARIANT: HeaderName = HeaderName::from_static("x-experiment-variant");
 
 	pub const RETRY_AFTER_MS: HeaderName = HeaderName::from_static("retry-after-ms");
 
@@ -537,3 +556,7 @@ where
 		self.body.size_hint()
 	}
 }
+
+#[cfg(any(test, feature = "internal_benches"))]
+#[path = "body_benches.rs"]
+mod body_benches;
@@ -0,0 +1,592 @@
+use std::path::{Path, PathBuf};
+use std::sync::Weak;
+use std::time::SystemTime;
+
+use ::http::{HeaderName, HeaderValue, Method, StatusCode, header};
+use arc_swap::ArcSwap;
+use macro_rules_attribute::apply;
+use serde_json::{Map, Value, json};
+
+use crate::http::ext_authz::FailureMode;
+use crate::http::filters::BackendRequestTimeout;
+use crate::http::{PolicyResponse, Request, apikey, basicauth, jwt};
+use crate::proxy::ProxyError;
+use crate::proxy::httpproxy::PolicyClient;
+use crate::transport::stream::{TCPConnectionInfo, TLSConnectionInfo};
+use crate::types::agent::SimpleBackendReference;
+use crate::*;
+
+#[cfg(test)]
+#[path = "opa_tests.rs"]
+mod tests;
+
+/// Headers never written to the decision log.
+const REDACTED_HEADERS: &[HeaderName] = &[
+	header::AUTHORIZATION,
+	header::COOKIE,
+	header::PROXY_AUTHORIZATION,
+];
+
+#[apply(schema!)]
+pub struct RemoteOpa {
+	/// Reference to the OPA server
+	#[serde(flatten)]
+	pub target: Arc<SimpleBackendReference>,
+	/// Path of the decision to query, such as `agentgateway/allow`. The request is sent to
+	/// `/v1/data/<path>`.
+	pub path: String,
+}
+
+#[apply(schema!)]
+pub struct EmbeddedOpa {
+	/// A `.rego` file, or a bundle directory containing `.rego` and `data.json` files.
+	pub bundle: PathBuf,
+	/// Rule to evaluate, such as `data.agentgateway.allow`.
+	pub query: String,
+	/// How often to check the bundle for changes. If unset, the bundle is loaded once.
+	#[serde(
+		default,
+		skip_serializing_if = "Option::is_none",
+		with = "serde_dur_option"
+	)]
+	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+	pub reload_interval: Option<Duration>,
+}
+
+/// Authorize requests with an Open Policy Agent decision.
+///
+/// The decision may be a boolean, or an object with an `allow` field and optional `status`, `body`
+/// and `headers` fields. On allow, `headers` are added to the request; on deny, `status`, `body` and
+/// `headers` customize the response.
+#[apply(schema_de!)]
+pub struct LocalOpa {
+	/// Query a remote OPA server over its REST API.
+	#[serde(default)]
+	pub remote: Option<RemoteOpa>,
+	/// Evaluate a Rego policy bundle in process.
+	#[serde(default)]
+	pub embedded: Option<EmbeddedOpa>,
+	/// Behavior when the decision cannot be made
+	#[serde(default)]
+	pub failure_mode: FailureMode,
+	/// Include the request body, up to this many bytes, in the input document.
+	#[serde(default)]
+	pub include_request_body: Option<u32>,
+	/// Log every decision, along with its input, at info level.
+	#[serde(default)]
+	pub decision_log: bool,
+	/// Timeout for remote decisions (default: 200ms)
+	#[serde(default, with = "serde_dur_option")]
+	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+	pub timeout: Option<Duration>,
+}
+
+#[apply(schema_ser!)]
+#[cfg_attr(feature = "schema", schemars(with = "LocalOpa"))]
+pub struct Opa {
+	pub source: Source,
+	pub failure_mode: FailureMode,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub include_request_body: Option<u32>,
+	pub decision_log: bool,
+	#[serde(
+		skip_serializing_if = "Option::is_none",
+		serialize_with = "serde_dur_option::serialize"
+	)]
+	pub timeout: Option<Duration>,
+}
+
+#[apply(schema_ser!)]
+pub enum Source {
+	Remote(RemoteOpa),
+	Embedded(Arc<Bundle>),
+}
+
+impl TryFrom<LocalOpa> for Opa {
+	type Error = anyhow::Error;
+
+	fn try_from(value: LocalOpa) -> Result<Self, Self::Error> {
+		let LocalOpa {
+			remote,
+			embedded,
+			failure_mode,
+			include_request_body,
+			decision_log,
+			timeout,
+		} = value;
+		let source = match (remote, embedded) {
+			(Some(r), None) => Source::Remote(r),
+			(None, Some(e)) => Source::Embedded(Bundle::load(e)?),
+			_ => anyhow::bail!("opa: exactly one of 'remote' or 'embedded' must be set"),
+		};
+		Ok(Opa {
+			source,
+			failure_mode,
+			include_request_body,
+			decision_log,
+			timeout,
+		})
+	}
+}
+
+/// Outcome of evaluating a policy.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Decision {
+	pub allow: bool,
+	pub status: Option<StatusCode>,
+	pub body: Option<String>,
+	pub headers: Vec<(HeaderName, HeaderValue)>,
+}
+
+impl Decision {
+	/// Interpret a policy result. Undefined results deny the request.
+	pub fn from_result(result: Option<&Value>) -> anyhow::Result<Self> {
+		let obj = match result {
+			None => return Ok(Decision::default()),
+			Some(Value::Bool(allow)) => {
+				return Ok(Decision {
+					allow: *allow,
+					..Default::default()
+				});
+			},
+			Some(Value::Object(obj)) => obj,
+			Some(other) => anyhow::bail!("unexpected decision type: {other}"),
+		};
+		let allow = obj
+			.get("allow")
+			.and_then(Value::as_bool)
+			.ok_or_else(|| anyhow::anyhow!("decision is missing a boolean 'allow' field"))?;
+		let status = obj
+			.get("status")
+			.and_then(Value::as_u64)
+			.map(|s| anyhow::Ok(StatusCode::from_u16(u16::try_from(s)?)?))
+			.transpose()?;
+		let body = obj.get("body").map(|b| match b {
+			Value::String(s) => s.clone(),
+			other => other.to_string(),
+		});
+		let headers = match obj.get("headers") {
+			Some(Value::Object(h)) => h
+				.iter()
+				.map(|(k, v)| {
+					let v = v
+						.as_str()
+						.ok_or_else(|| anyhow::anyhow!("header {k} must be a string"))?;
+					Ok((HeaderName::try_from(k)?, HeaderValue::try_from(v)?))
+				})
+				.collect::<anyhow::Result<Vec<_>>>()?,
+			Some(_) => anyhow::bail!("decision 'headers' must be an object"),
+			None => vec![],
+		};
+		Ok(Decision {
+			allow,
+			status,
+			body,
+			headers,
+		})
+	}
+}
+
+impl Opa {
+	pub async fn check(
+		&self,
+		client: PolicyClient,
+		req: &mut Request,
+	) -> Result<PolicyResponse, ProxyError> {
+		let input = self.build_input(req).await;
+		let decision_id = uuid::Uuid::new_v4().to_string();
+		let result = match &self.source {
+			Source::Remote(r) => self.query_remote(r, client, &input, &decision_id).await,
+			Source::Embedded(b) => b.evaluate(&input),
+		};
+		let decision = result.and_then(|r| Decision::from_result(r.as_ref()).map(|d| (r, d)));
+		let (result, decision) = match decision {
+			Ok(d) => d,
+			Err(e) => {
+				warn!(decision_id, "opa decision failed: {e}");
+				return self.handle_failure(&e.to_string());
+			},
+		};
+		if self.decision_log {
+			tracing::info!(
+				target: "opa_decision",
+				decision_id,
+				query = self.query(),
+				allow = decision.allow,
+				input = %redact_input(input),
+				result = %result.unwrap_or(Value::Null),
+			);
+		}
+		if decision.allow {
+			for (k, v) in decision.headers {
+				req.headers_mut().insert(k, v);
+			}
+			return Ok(PolicyResponse::default());
+		}
+		if decision.status.is_none() && decision.body.is_none() && decision.headers.is_empty() {
+			return Err(ProxyError::AuthorizationFailed);
+		}
+		let mut resp = ::http::Response::builder()
+			.status(decision.status.unwrap_or(StatusCode::FORBIDDEN))
+			.body(http::Body::from(decision.body.unwrap_or_default()))
+			.map_err(|e| ProxyError::Processing(e.into()))?;
+		for (k, v) in decision.headers {
+			resp.headers_mut().insert(k, v);
+		}
+		Ok(PolicyResponse {
+			direct_response: Some(resp),
+			response_headers: None,
+		})
+	}
+
+	fn query(&self) -> &str {
+		match &self.source {
+			Source::Remote(r) => &r.path,
+			Source::Embedded(b) => &b.query,
+		}
+	}
+
+	fn handle_failure(&self, error_msg: &str) -> Result<PolicyResponse, ProxyError> {
+		match &self.failure_mode {
+			FailureMode::Allow => {
+				debug!("Allowing request due to FailureMode::Allow configuration");
+				Ok(PolicyResponse::default())
+			},
+			FailureMode::Deny => Err(ProxyError::ExternalAuthorizationFailed(None)),
+			FailureMode::DenyWithStatus(status_code) => {
+				let status = StatusCode::from_u16(*status_code).unwrap_or(StatusCode::FORBIDDEN);
+				let resp = ::http::Response::builder()
+					.status(status)
+					.body(http::Body::from(error_msg.to_string()))
+					.map_err(|e| ProxyError::Processing(e.into()))?;
+				Ok(PolicyResponse {
+					direct_response: Some(resp),
+					response_headers: None,
+				})
+			},
+		}
+	}
+
+	async fn query_remote(
+		&self,
+		remote: &RemoteOpa,
+		client: PolicyClient,
+		input: &Value,
+		decision_id: &str,
+	) -> anyhow::Result<Option<Value>> {
+		let body = serde_json::to_vec(&json!({ "input": input }))?;
+		let mut check_req = ::http::Request::builder()
+			.method(Method::POST)
+			.uri(format!("/v1/data/{}", remote.path.trim_start_matches('/')))
+			.header(header::CONTENT_TYPE, "application/json")
+			.header("x-request-id", decision_id)
+			.body(http::Body::from(body))?;
+		check_req.extensions_mut().insert(BackendRequestTimeout(
+			self.timeout.unwrap_or(Duration::from_millis(200)),
+		));
+		let resp = client
+			.call_reference(check_req, &remote.target)
+			.await
+			.map_err(|e| anyhow::anyhow!("opa request failed: {e}"))?;
+		if !resp.status().is_success() {
+			anyhow::bail!("opa returned status {}", resp.status());
+		}
+		let body = http::read_body_with_limit(resp.into_body(), 2_097_152).await?;
+		let mut doc: Map<String, Value> = serde_json::from_slice(&body)?;
+		Ok(doc.remove("result"))
+	}
+
+	async fn build_input(&self, req: &mut Request) -> Value {
+		let mut headers = Map::new();
+		for (k, v) in req.headers() {
+			let Ok(v) = v.to_str() else { continue };
+			match headers.get_mut(k.as_str()) {
+				Some(Value::String(existing)) => {
+					existing.push_str(", ");
+					existing.push_str(v);
+				},
+				_ => {
+					headers.insert(k.as_str().to_string(), Value::String(v.to_string()));
+				},
+			}
+		}
+		let query: Map<String, Value> = req
+			.uri()
+			.query()
+			.map(|q| {
+				url::form_urlencoded::parse(q.as_bytes())
+					.into_owned()
+					.map(|(k, v)| (k, Value::String(v)))
+					.collect()
+			})
+			.unwrap_or_default();
+		let host = http::get_host(req).ok().map(|h| h.to_string());
+		let mut request = json!({
+			"method": req.method().as_str(),
+			"path": req.uri().path(),
+			"query": query,
+			"host": host,
+			"headers": headers,
+		});
+		if let Some(max) = self.include_request_body {
+			match http::inspect_body_with_limit(req.body_mut(), max as usize).await {
+				Ok(b) => {
+					request["body"] = serde_json::from_slice(&b)
+						.unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&b).into_owned()));
+				},
+				Err(e) => debug!("failed to read request body for opa: {e}"),
+			}
+		}
+
+		let mut identity = Map::new();
+		if let Some(c) = req.extensions().get::<jwt::Claims>() {
+			identity.insert("jwt".to_string(), Value::Object(c.inner.clone()));
+		}
+		if let Some(c) = req.extensions().get::<apikey::Claims>() {
+			identity.insert(
+				"apiKey".to_string(),
+				json!({ "fingerprint": c.key.fingerprint(), "metadata": c.metadata }),
+			);
+		}
+		if let Some(c) = req.extensions().get::<basicauth::Claims>() {
+			identity.insert("basicAuth".to_string(), json!({ "username": c.username }));
+		}
+		if let Some(tls) = req
+			.extensions()
+			.get::<TLSConnectionInfo>()
+			.and_then(|t| t.src_identity.as_ref())
+		{
+			identity.insert(
+				"mtls".to_string(),
+				json!({
+					"identity": tls.identity.as_ref().map(|i| i.to_string()),
+					"subjectAltNames": tls.subject_alt_names,
+				}),
+			);
+		}
+		let source = req
+			.extensions()
+			.get::<TCPConnectionInfo>()
+			.map(|t| json!({ "address": t.peer_addr.ip().to_string(), "port": t.peer_addr.port() }));
+
+		json!({
+			"request": request,
+			"source": source,
+			"identity": identity,
+		})
+	}
+}
+
+fn redact_input(mut input: Value) -> Value {
+	if let Some(Value::Object(h)) = input.pointer_mut("/request/headers") {
+		for name in REDACTED_HEADERS {
+			if let Some(v) = h.get_mut(name.as_str()) {
+				*v = Value::String("<redacted>".to_string());
+			}
+		}
+	}
+	input
+}
+
+/// A Rego policy bundle evaluated in process, reloaded in the background when its files change once
+/// the store starts watching it.
+#[derive(serde::Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct Bundle {
+	bundle: PathBuf,
+	query: String,
+	#[serde(skip_serializing_if = "Option::is_none", with = "serde_dur_option")]
+	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+	reload_interval: Option<Duration>,
+	#[serde(skip)]
+	#[cfg_attr(feature = "schema", schemars(skip))]
+	loaded: ArcSwap<Loaded>,
+	#[serde(skip)]
+	#[cfg_attr(feature = "schema", schemars(skip))]
+	watching: std::sync::Once,
+}
+
+impl std::fmt::Debug for Bundle {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("Bundle")
+			.field("bundle", &self.bundle)
+			.field("query", &self.query)
+			.field("revision", &self.loaded.load().revision)
+			.finish()
+	}
+}
+
+struct Loaded {
+	/// The policy compiled for the query, shared by all requests.
+	policy: regorus::CompiledPolicy,
+	revision: Option<String>,
+	fingerprint: Fingerprint,
+}
+
+/// Cheap summary of the bundle files, used to detect changes without re-reading them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Fingerprint(Vec<(PathBuf, SystemTime, u64)>);
+
+impl Bundle {
+	/// Load the bundle. Changes are not picked up until `start_watching` is called.
+	pub fn load(cfg: EmbeddedOpa) -> anyhow::Result<Arc<Self>> {
+		let loaded = Loaded::read(&cfg.bundle, &cfg.query)?;
+		Ok(Arc::new(Bundle {
+			bundle: cfg.bundle,
+			query: cfg.query,
+			reload_interval: cfg.reload_interval,
+			loaded: ArcSwap::from_pointee(loaded),
+			watching: std::sync::Once::new(),
+		}))
+	}
+
+	/// If the bundle has a reload interval, start checking it for changes in the background. Only the
+	/// first call starts a watcher, which stops once the bundle is dropped.
+	pub fn start_watching(self: &Arc<Self>) {
+		let Some(interval) = self.reload_interval else {
+			return;
+		};
+		self.watching.call_once(|| {
+			tokio::spawn(Self::watch(Arc::downgrade(self), interval));
+		});
+	}
+
+	pub fn evaluate(&self, input: &Value) -> anyhow::Result<Option<Value>> {
+		let input = regorus::Value::from_json_str(&input.to_string())?;
+		let result = self.loaded.load().policy.eval_with_input(input)?;
+		if result == regorus::Value::Undefined {
+			return Ok(None);
+		}
+		Ok(Some(serde_json::to_value(&result)?))
+	}
+
+	/// Check the bundle for changes every `interval`, until the bundle is no longer in use.
+	async fn watch(bundle: Weak<Bundle>, interval: Duration) {
+		let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+		ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+		loop {
+			ticker.tick().await;
+			let Some(bundle) = bundle.upgrade() else {
+				return;
+			};
+			// Reading the bundle is blocking filesystem work, so keep it off the request workers.
+			if tokio::task::spawn_blocking(move || bundle.reload())
+				.await
+				.is_err()
+			{
+				return;
+			}
+		}
+	}
+
+	/// Reload the bundle if its files changed. On failure, the previous policy stays active.
+	pub fn reload(&self) {
+		let current = self.loaded.load();
+		match Fingerprint::read(&self.bundle) {
+			Ok(fp) if fp == current.fingerprint => return,
+			Ok(_) => {},
+			Err(e) => {
+				warn!(bundle = %self.bundle.display(), "failed to check opa bundle: {e}");
+				return;
+			},
+		}
+		match Loaded::read(&self.bundle, &self.query) {
+			Ok(l) => {
+				tracing::info!(
+					bundle = %self.bundle.display(),
+					revision = l.revision.as_deref().unwrap_or_default(),
+					"reloaded opa bundle"
+				);
+				self.loaded.store(Arc::new(l));
+			},
+			Err(e) => warn!(bundle = %self.bundle.display(), "failed to reload opa bundle: {e}"),
+		}
+	}
+}
+
+impl Fingerprint {
+	fn read(root: &Path) -> anyhow::Result<Self> {
+		let mut files = bundle_files(root)?
+			.into_iter()
+			.map(|p| {
+				let md = fs_err::metadata(&p)?;
+				Ok((p, md.modified()?, md.len()))
+			})
+			.collect::<anyhow::Result<Vec<_>>>()?;
+		files.sort();
+		Ok(Fingerprint(files))
+	}
+}
+
+impl Loaded {
+	fn read(root: &Path, query: &str) -> anyhow::Result<Self> {
+		let fingerprint = Fingerprint::read(root)?;
+		let mut engine = regorus::Engine::new();
+		let mut revision = None;
+		let mut policies = 0;
+		for (path, _, _) in &fingerprint.0 {
+			let rel = path.strip_prefix(root).unwrap_or(path);
+			match path.file_name().and_then(|n| n.to_str()) {
+				Some("data.json") => {
+					let data: Value = serde_json::from_slice(&fs_err::read(path)?)?;
+					// As in OPA bundles, data is rooted at the directory containing the file.
+					let data = rel
+						.parent()
+						.into_iter()
+						.flat_map(|p| p.iter().rev())
+						.fold(data, |acc, seg| {
+							Value::Object(Map::from_iter([(seg.to_string_lossy().into_owned(), acc)]))
+						});
+					engine.add_data(regorus::Value::from_json_str(&data.to_string())?)?;
+				},
+				Some(".manifest") => {
+					let manifest: Value = serde_json::from_slice(&fs_err::read(path)?)?;
+					revision = manifest
+						.get("revision")
+						.and_then(Value::as_str)
+						.map(str::to_string);
+				},
+				_ => {
+					engine.add_policy(rel.display().to_string(), fs_err::read_to_string(path)?)?;
+					policies += 1;
+				},
+			}
+		}
+		if policies == 0 {
+			anyhow::bail!("no rego policies found in {}", root.display());
+		}
+		let policy = engine.compile_with_entrypoint(&query.into())?;
+		Ok(Loaded {
+			policy,
+			revision,
+			fingerprint,
+		})
+	}
+}
+
+/// Policy, data and manifest files of a bundle, which may be a single `.rego` file.
+fn bundle_files(root: &Path) -> anyhow::Result<Vec<PathBuf>> {
+	if !root.is_dir() {
+		return Ok(vec![root.to_path_buf()]);
+	}
+	let mut out = vec![];
+	let mut dirs = vec![root.to_path_buf()];
+	while let Some(dir) = dirs.pop() {
+		for entry in fs_err::read_dir(&dir)? {
+			let path = entry?.path();
+			if path.is_dir() {
+				dirs.push(path);
+				continue;
+			}
+			let name = path
+				.file_name()
+				.and_then(|n| n.to_str())
+				.unwrap_or_default();
+			if name.ends_with(".rego") || name == "data.json" || name == ".manifest" {
+				out.push(path);
+			}
+		}
+	}
+	Ok(out)
+}
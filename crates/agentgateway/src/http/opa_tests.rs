@@ -0,0 +1,179 @@
+use super::*;
+
+const POLICY: &str = r#"
+package agentgateway
+
+import rego.v1
+
+default allow := false
+
+allow if {
+	input.request.method == "GET"
+	input.identity.jwt.sub in data.agentgateway.users
+}
+"#;
+
+fn write_bundle(dir: &Path, policy: &str) {
+	fs_err::create_dir_all(dir.join("agentgateway")).unwrap();
+	fs_err::write(dir.join("policy.rego"), policy).unwrap();
+	fs_err::write(
+		dir.join("agentgateway/data.json"),
+		r#"{"users": ["alice"]}"#,
+	)
+	.unwrap();
+	fs_err::write(dir.join(".manifest"), r#"{"revision": "v1"}"#).unwrap();
+}
+
+fn input(method: &str, sub: &str) -> Value {
+	json!({
+		"request": {"method": method, "path": "/", "headers": {}},
+		"identity": {"jwt": {"sub": sub}},
+	})
+}
+
+#[test]
+fn test_decision() {
+	assert!(Decision::from_result(Some(&json!(true))).unwrap().allow);
+	assert!(!Decision::from_result(Some(&json!(false))).unwrap().allow);
+	// Undefined decisions deny
+	assert_eq!(Decision::from_result(None).unwrap(), Decision::default());
+
+	let d = Decision::from_result(Some(&json!({
+		"allow": false,
+		"status": 401,
+		"body": "nope",
+		"headers": {"www-authenticate": "Bearer"},
+	})))
+	.unwrap();
+	assert!(!d.allow);
+	assert_eq!(d.status, Some(StatusCode::UNAUTHORIZED));
+	assert_eq!(d.body.as_deref(), Some("nope"));
+	assert_eq!(d.headers[0].0, header::WWW_AUTHENTICATE);
+
+	assert!(Decision::from_result(Some(&json!("yes"))).is_err());
+	assert!(Decision::from_result(Some(&json!({"status": 200}))).is_err());
+	// Out of range statuses are rejected rather than truncated to 403
+	assert!(Decision::from_result(Some(&json!({"allow": false, "status": 65939}))).is_err());
+}
+
+#[test]
+fn test_redact_input() {
+	let v = redact_input(json!({
+		"request": {"headers": {"authorization": "Bearer secret", "x-user": "alice"}},
+	}));
+	assert_eq!(v["request"]["headers"]["authorization"], "<redacted>");
+	assert_eq!(v["request"]["headers"]["x-user"], "alice");
+}
+
+#[test]
+fn test_embedded() {
+	let dir = tempfile::tempdir().unwrap();
+	write_bundle(dir.path(), POLICY);
+	let bundle = Bundle::load(EmbeddedOpa {
+		bundle: dir.path().to_path_buf(),
+		query: "data.agentgateway.allow".to_string(),
+		reload_interval: None,
+	})
+	.unwrap();
+	assert_eq!(bundle.loaded.load().revision.as_deref(), Some("v1"));
+	assert_eq!(
+		bundle.evaluate(&input("GET", "alice")).unwrap(),
+		Some(json!(true))
+	);
+	assert_eq!(
+		bundle.evaluate(&input("GET", "bob")).unwrap(),
+		Some(json!(false))
+	);
+	assert_eq!(
+		bundle.evaluate(&input("POST", "alice")).unwrap(),
+		Some(json!(false))
+	);
+}
+
+#[test]
+fn test_reload() {
+	let dir = tempfile::tempdir().unwrap();
+	write_bundle(dir.path(), POLICY);
+	let bundle = Bundle::load(EmbeddedOpa {
+		bundle: dir.path().to_path_buf(),
+		query: "data.agentgateway.allow".to_string(),
+		reload_interval: None,
+	})
+	.unwrap();
+
+	// An invalid bundle keeps the previous policy active
+	fs_err::write(
+		dir.path().join("policy.rego"),
+		"package agentgateway\nallow if {",
+	)
+	.unwrap();
+	bundle.reload();
+	assert_eq!(
+		bundle.evaluate(&input("GET", "alice")).unwrap(),
+		Some(json!(true))
+	);
+
+	fs_err::write(
+		dir.path().join("policy.rego"),
+		"package agentgateway\n\nallow := true\n",
+	)
+	.unwrap();
+	bundle.reload();
+	assert_eq!(
+		bundle.evaluate(&input("POST", "bob")).unwrap(),
+		Some(json!(true))
+	);
+}
+
+#[tokio::test]
+async fn test_background_reload() {
+	let dir = tempfile::tempdir().unwrap();
+	write_bundle(dir.path(), POLICY);
+	let bundle = Bundle::load(EmbeddedOpa {
+		bundle: dir.path().to_path_buf(),
+		query: "data.agentgateway.allow".to_string(),
+		reload_interval: Some(Duration::from_millis(10)),
+	})
+	.unwrap();
+	bundle.start_watching();
+	// Starting again does not add a second watcher
+	bundle.start_watching();
+	assert_eq!(
+		bundle.evaluate(&input("POST", "bob")).unwrap(),
+		Some(json!(false))
+	);
+
+	fs_err::write(
+		dir.path().join("policy.rego"),
+		"package agentgateway\n\nallow := true\n",
+	)
+	.unwrap();
+	tokio::time::timeout(Duration::from_secs(5), async {
+		while bundle.evaluate(&input("POST", "bob")).unwrap() != Some(json!(true)) {
+			tokio::time::sleep(Duration::from_millis(10)).await;
+		}
+	})
+	.await
+	.expect("bundle was not reloaded");
+}
+
+#[test]
+fn test_unknown_query() {
+	let dir = tempfile::tempdir().unwrap();
+	write_bundle(dir.path(), POLICY);
+	// The policy is compiled for the query when it is loaded
+	assert!(
+		Bundle::load(EmbeddedOpa {
+			bundle: dir.path().to_path_buf(),
+			query: "data.agentgateway.missing".to_string(),
+			reload_interval: None,
+		})
+		.is_err()
+	);
+}
+
+#[test]
+fn test_exactly_one_source() {
+	let cfg: LocalOpa = serde_json::from_value(json!({})).unwrap();
+	assert!(Opa::try_from(cfg).is_err());
+}
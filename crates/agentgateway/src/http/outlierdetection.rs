@@ -24,6 +24,15 @@ pub fn retry_after(status: StatusCode, h: &HeaderMap) -> Option<std::time::Durat
 	}
 }
 
+/// How long the backend asked clients to wait before retrying a throttled or unavailable response.
+pub fn retry_delay(status: StatusCode, h: &HeaderMap) -> Option<std::time::Duration> {
+	if status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE {
+		process_rate_limit_headers(h, SystemTime::now())
+	} else {
+		None
+	}
+}
+
 /// Some APIs may return rate limit information via response headers.
 /// There is no single standard for this, so we must check a few common implementations.
 fn process_rate_limit_headers(h: &HeaderMap, now: SystemTime) -> Option<std::time::Duration> {
@@ -50,12 +50,10 @@ pub async fn inspect_body(body: &mut Body, limit: usize) -> anyhow::Result<Bytes
 		match orig.frame().await {
 			Some(Ok(frame)) => {
 				if let Some(data) = frame.data_ref() {
+					// The frame is kept as-is to be forwarded later, so inspecting does not copy it.
 					let want_this_read = cmp::min(data.len(), want);
-					if want_this_read == 0 {
-						break;
-					}
 					buffer.push(data.clone());
-					want -= cmp::max(want_this_read, 0);
+					want -= want_this_read;
 					if want == 0 {
 						break;
 					}
@@ -71,7 +69,7 @@ pub async fn inspect_body(body: &mut Body, limit: usize) -> anyhow::Result<Bytes
 	}
 
 	// Despite the name, 'copy_to_bytes' takes the data, not copies it.
-	// So we send a clone.
+	// So we send a clone. It only copies if the inspected bytes span multiple frames.
 	let mut blc = buffer.clone();
 	let ret = blc.copy_to_bytes(cmp::min(buffer.remaining(), limit));
 	let nb = PartiallyBufferedBody {
@@ -184,4 +182,42 @@ mod tests {
 		assert_eq!(Some(&trailers), result.trailers());
 		assert_eq!(result.to_bytes(), payload);
 	}
+
+	#[tokio::test]
+	async fn inspect_zero_limit() {
+		let payload = b"hello world";
+		let mut original = Body::from(payload.as_slice());
+
+		let inspected = inspect_body(&mut original, 0).await.unwrap();
+
+		assert!(inspected.is_empty());
+		assert_eq!(read(original).await, Bytes::from_static(payload));
+	}
+
+	#[tokio::test]
+	async fn inspect_does_not_copy_frames() {
+		use http_body_util::BodyExt;
+		let chunks: Vec<Bytes> = (0..4u8).map(|i| Bytes::from(vec![i; 1024])).collect();
+		let frames = chunks
+			.clone()
+			.into_iter()
+			.map(|c| Ok::<_, std::io::Error>(http_body::Frame::data(c)));
+		let mut original = crate::http::Body::new(http_body_util::StreamBody::new(
+			futures_util::stream::iter(frames),
+		));
+
+		// Bytes within a single frame are shared with the frame
+		let inspected = inspect_body(&mut original, 512).await.unwrap();
+		assert_eq!(inspected.as_ptr(), chunks[0].as_ptr());
+
+		// Frames are forwarded without copying, whether they were buffered or not
+		let mut forwarded = vec![];
+		while let Some(frame) = original.frame().await {
+			forwarded.push(frame.unwrap().into_data().unwrap());
+		}
+		assert_eq!(forwarded.len(), chunks.len());
+		for (f, c) in forwarded.iter().zip(&chunks) {
+			assert_eq!(f.as_ptr(), c.as_ptr());
+		}
+	}
 }
@@ -0,0 +1,261 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
+
+use ::http::{HeaderMap, StatusCode, header};
+use macro_rules_attribute::apply;
+use parking_lot::Mutex;
+use tokio::sync::oneshot;
+
+use crate::http::Response;
+use crate::http::x_headers::RETRY_AFTER_MS;
+use crate::proxy::ProxyResponse;
+use crate::*;
+
+#[cfg(test)]
+#[path = "queue_tests.rs"]
+mod tests;
+
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+	#[error("request queue is full")]
+	Full,
+	#[error("timed out waiting in request queue")]
+	Timeout,
+}
+
+/// Queue requests to a backend while it is at its concurrency limit, or rate limiting the gateway,
+/// admitting them in priority order as capacity frees up.
+#[apply(schema!)]
+pub struct Policy {
+	/// The most requests sent to the backend at once. If unset, requests are only queued while the
+	/// backend is rate limiting.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	#[cfg_attr(feature = "schema", schemars(with = "Option<usize>"))]
+	pub max_concurrency: Option<NonZeroUsize>,
+	/// CEL expression for the priority of a request, such as `int(request.headers["x-priority"])`
+	/// or `jwt.tier == "premium" ? 10 : 0`. Higher priorities are admitted first, and requests of
+	/// equal priority in arrival order. Requests whose priority cannot be evaluated have priority 0.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub priority: Option<Arc<cel::Expression>>,
+	/// The most requests that can wait in the queue. When full, a request of higher priority than the
+	/// lowest queued one takes its place, and is otherwise rejected. Defaults to 100.
+	#[serde(default = "default_max_queue_depth")]
+	pub max_queue_depth: usize,
+	/// How long a request can wait in the queue before it is rejected. Defaults to 30s.
+	#[serde(default = "default_queue_timeout", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub queue_timeout: Duration,
+	/// How long to hold requests after the backend responds with 429 Too Many Requests, when the
+	/// response has no `retry-after` header. Defaults to 1s.
+	#[serde(default = "default_rate_limit_backoff", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub rate_limit_backoff: Duration,
+	#[serde(skip)]
+	queue: Arc<Queue>,
+}
+
+fn default_max_queue_depth() -> usize {
+	100
+}
+
+fn default_queue_timeout() -> Duration {
+	Duration::from_secs(30)
+}
+
+fn default_rate_limit_backoff() -> Duration {
+	Duration::from_secs(1)
+}
+
+impl Policy {
+	/// The priority of a request, from the `priority` expression.
+	pub fn priority(&self, exec: &cel::Executor) -> i64 {
+		let Some(expr) = &self.priority else {
+			return 0;
+		};
+		match exec.eval(expr) {
+			Ok(cel::Value::Int(i)) => i,
+			Ok(cel::Value::UInt(u)) => i64::try_from(u).unwrap_or(i64::MAX),
+			Ok(cel::Value::String(s)) => s.trim().parse().unwrap_or_default(),
+			_ => 0,
+		}
+	}
+
+	/// Wait for a turn to send a request of `priority` to the backend.
+	pub async fn acquire(&self, priority: i64) -> Result<Permit, Error> {
+		self
+			.queue
+			.acquire(
+				self.max_concurrency,
+				self.max_queue_depth,
+				self.queue_timeout,
+				priority,
+			)
+			.await
+	}
+
+	/// Release a permit once the backend has responded, holding back queued requests if it is rate
+	/// limiting.
+	pub fn complete(
+		&self,
+		permit: Permit,
+		res: Result<Response, ProxyResponse>,
+	) -> Result<Response, ProxyResponse> {
+		if let Ok(resp) = &res
+			&& resp.status() == StatusCode::TOO_MANY_REQUESTS
+		{
+			let backoff = retry_after(resp.headers()).unwrap_or(self.rate_limit_backoff);
+			debug!(
+				?backoff,
+				"backend is rate limiting, holding queued requests"
+			);
+			self.queue.pause(backoff, self.max_concurrency);
+		}
+		drop(permit);
+		res
+	}
+}
+
+/// How long a rate limited response asks clients to wait.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+	let header = |name| {
+		headers
+			.get(name)
+			.and_then(|v| v.to_str().ok())
+			.and_then(|v| v.trim().parse::<u64>().ok())
+	};
+	header(&RETRY_AFTER_MS)
+		.map(Duration::from_millis)
+		.or_else(|| header(&header::RETRY_AFTER).map(Duration::from_secs))
+}
+
+/// A turn to send a request to the backend. The next queued request is admitted once it is dropped.
+#[derive(Debug)]
+pub struct Permit {
+	/// Unset for a permit that was never handed out, so dropping it does not release a turn.
+	queue: Option<Arc<Queue>>,
+	max_concurrency: Option<NonZeroUsize>,
+}
+
+impl Drop for Permit {
+	fn drop(&mut self) {
+		let Some(queue) = self.queue.take() else {
+			return;
+		};
+		let mut state = queue.state.lock();
+		state.in_flight -= 1;
+		state.dispatch(&queue, self.max_concurrency, Instant::now());
+	}
+}
+
+/// Requests are ordered by highest priority, then arrival.
+type Key = (Reverse<i64>, u64);
+
+#[derive(Debug, Default)]
+struct State {
+	in_flight: usize,
+	paused_until: Option<Instant>,
+	next_seq: u64,
+	waiting: BTreeMap<Key, oneshot::Sender<Permit>>,
+}
+
+impl State {
+	fn can_admit(&self, max_concurrency: Option<NonZeroUsize>, now: Instant) -> bool {
+		self.paused_until.is_none_or(|t| t <= now)
+			&& max_concurrency.is_none_or(|m| self.in_flight < m.get())
+	}
+
+	/// Admit queued requests, in order, while there is capacity. Each is sent its permit, so a
+	/// request that gives up before receiving it still releases the capacity when it is dropped.
+	fn dispatch(&mut self, queue: &Arc<Queue>, max_concurrency: Option<NonZeroUsize>, now: Instant) {
+		while self.can_admit(max_concurrency, now)
+			&& let Some((_, tx)) = self.waiting.pop_first()
+		{
+			let permit = Permit {
+				queue: Some(queue.clone()),
+				max_concurrency,
+			};
+			match tx.send(permit) {
+				Ok(()) => self.in_flight += 1,
+				// A request that already gave up does not take the capacity.
+				Err(mut permit) => permit.queue = None,
+			}
+		}
+	}
+}
+
+/// The requests sent to, and waiting for, a single backend.
+#[derive(Debug, Default)]
+pub struct Queue {
+	state: Mutex<State>,
+}
+
+impl Queue {
+	async fn acquire(
+		self: &Arc<Self>,
+		max_concurrency: Option<NonZeroUsize>,
+		max_queue_depth: usize,
+		timeout: Duration,
+		priority: i64,
+	) -> Result<Permit, Error> {
+		let (key, mut rx) = {
+			let mut state = self.state.lock();
+			if state.waiting.is_empty() && state.can_admit(max_concurrency, Instant::now()) {
+				state.in_flight += 1;
+				return Ok(Permit {
+					queue: Some(self.clone()),
+					max_concurrency,
+				});
+			}
+			if state.waiting.len() >= max_queue_depth {
+				// Make room by dropping the lowest priority request, if it is lower than this one.
+				match state.waiting.last_key_value() {
+					Some(((Reverse(lowest), _), _)) if *lowest < priority => {
+						state.waiting.pop_last();
+					},
+					_ => return Err(Error::Full),
+				}
+			}
+			let key = (Reverse(priority), state.next_seq);
+			state.next_seq += 1;
+			let (tx, rx) = oneshot::channel();
+			state.waiting.insert(key, tx);
+			(key, rx)
+		};
+		match tokio::time::timeout(timeout, &mut rx).await {
+			Ok(Ok(permit)) => Ok(permit),
+			// Dropped from the queue to make room for a higher priority request
+			Ok(Err(_)) => Err(Error::Full),
+			Err(_) => {
+				if self.state.lock().waiting.remove(&key).is_some() {
+					return Err(Error::Timeout);
+				}
+				// The request was admitted, or dropped, just as it timed out.
+				match rx.try_recv() {
+					Ok(permit) => Ok(permit),
+					Err(_) => Err(Error::Full),
+				}
+			},
+		}
+	}
+
+	/// Hold queued requests for `backoff`, then admit them again.
+	fn pause(self: &Arc<Self>, backoff: Duration, max_concurrency: Option<NonZeroUsize>) {
+		let until = Instant::now() + backoff;
+		let mut state = self.state.lock();
+		if state.paused_until.is_some_and(|t| t >= until) {
+			return;
+		}
+		state.paused_until = Some(until);
+		drop(state);
+		let queue = self.clone();
+		tokio::spawn(async move {
+			tokio::time::sleep_until(until.into()).await;
+			let mut state = queue.state.lock();
+			if state.paused_until == Some(until) {
+				state.paused_until = None;
+			}
+			state.dispatch(&queue, max_concurrency, Instant::now());
+		});
+	}
+}
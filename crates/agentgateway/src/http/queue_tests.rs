@@ -0,0 +1,145 @@
+use super::*;
+
+fn policy(cfg: serde_json::Value) -> Arc<Policy> {
+	Arc::new(serde_json::from_value(cfg).unwrap())
+}
+
+async fn wait_queued(p: &Policy, n: usize) {
+	while p.queue.state.lock().waiting.len() < n {
+		tokio::task::yield_now().await;
+	}
+}
+
+#[test]
+fn test_config() {
+	let p = policy(serde_json::json!({}));
+	assert_eq!(p.max_concurrency, None);
+	assert_eq!(p.max_queue_depth, 100);
+	assert_eq!(p.queue_timeout, Duration::from_secs(30));
+	assert_eq!(p.rate_limit_backoff, Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn test_priority_order() {
+	let p = policy(serde_json::json!({"maxConcurrency": 1}));
+	let first = p.acquire(0).await.unwrap();
+	let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+	for (i, priority) in [0, 10, 5, 10].into_iter().enumerate() {
+		let p = p.clone();
+		let tx = tx.clone();
+		tokio::spawn(async move {
+			let permit = p.acquire(priority).await.unwrap();
+			tx.send((priority, i)).unwrap();
+			drop(permit);
+		});
+		wait_queued(&p, i + 1).await;
+	}
+	drop(first);
+	let mut order = vec![];
+	for _ in 0..4 {
+		order.push(rx.recv().await.unwrap());
+	}
+	// Highest priority first, then in arrival order
+	assert_eq!(order, vec![(10, 1), (10, 3), (5, 2), (0, 0)]);
+}
+
+#[tokio::test]
+async fn test_queue_full() {
+	let p = policy(serde_json::json!({"maxConcurrency": 1, "maxQueueDepth": 1}));
+	let first = p.acquire(0).await.unwrap();
+	let low = tokio::spawn({
+		let p = p.clone();
+		async move { p.acquire(0).await.map(|_| ()) }
+	});
+	wait_queued(&p, 1).await;
+	// Requests of no higher priority than those queued are rejected
+	assert_eq!(p.acquire(0).await.unwrap_err(), Error::Full);
+	// Higher priority requests take the place of the lowest
+	let high = tokio::spawn({
+		let p = p.clone();
+		async move { p.acquire(5).await.map(|_| ()) }
+	});
+	assert_eq!(low.await.unwrap(), Err(Error::Full));
+	wait_queued(&p, 1).await;
+	drop(first);
+	assert_eq!(high.await.unwrap(), Ok(()));
+}
+
+#[tokio::test]
+async fn test_queue_timeout() {
+	let p = policy(serde_json::json!({"maxConcurrency": 1, "queueTimeout": "10ms"}));
+	let first = p.acquire(0).await.unwrap();
+	assert_eq!(p.acquire(0).await.unwrap_err(), Error::Timeout);
+	assert!(p.queue.state.lock().waiting.is_empty());
+	drop(first);
+	assert_eq!(p.queue.state.lock().in_flight, 0);
+}
+
+#[tokio::test]
+async fn test_cancelled_after_dispatch() {
+	let p = policy(serde_json::json!({"maxConcurrency": 1}));
+	let first = p.acquire(0).await.unwrap();
+	let waiter = tokio::spawn({
+		let p = p.clone();
+		async move { p.acquire(0).await.map(|_| ()) }
+	});
+	wait_queued(&p, 1).await;
+	// The waiter is admitted, but gives up before it runs to receive its permit
+	drop(first);
+	waiter.abort();
+	assert!(waiter.await.unwrap_err().is_cancelled());
+	assert_eq!(p.queue.state.lock().in_flight, 0);
+	let _permit = tokio::time::timeout(Duration::from_secs(1), p.acquire(0))
+		.await
+		.unwrap()
+		.unwrap();
+}
+
+#[tokio::test]
+async fn test_rate_limited() {
+	let p = policy(serde_json::json!({}));
+	let permit = p.acquire(0).await.unwrap();
+	let resp = ::http::Response::builder()
+		.status(StatusCode::TOO_MANY_REQUESTS)
+		.header(RETRY_AFTER_MS, "50")
+		.body(crate::http::Body::empty())
+		.unwrap();
+	let started = Instant::now();
+	let res = p.complete(permit, Ok(resp));
+	assert_eq!(res.unwrap().status(), StatusCode::TOO_MANY_REQUESTS);
+	// Requests wait until the backoff has passed
+	let _permit = p.acquire(0).await.unwrap();
+	assert!(started.elapsed() >= Duration::from_millis(50));
+	assert_eq!(p.queue.state.lock().paused_until, None);
+}
+
+#[test]
+fn test_retry_after() {
+	let headers = |h: &[(&str, &str)]| {
+		let mut hm = HeaderMap::new();
+		for (k, v) in h {
+			hm.insert(
+				::http::HeaderName::from_bytes(k.as_bytes()).unwrap(),
+				v.parse().unwrap(),
+			);
+		}
+		hm
+	};
+	assert_eq!(retry_after(&headers(&[])), None);
+	assert_eq!(
+		retry_after(&headers(&[("retry-after", "2")])),
+		Some(Duration::from_secs(2))
+	);
+	assert_eq!(
+		retry_after(&headers(&[("retry-after", "2"), ("retry-after-ms", "250")])),
+		Some(Duration::from_millis(250))
+	);
+	// HTTP dates are not supported
+	assert_eq!(
+		retry_after(&headers(&[(
+			"retry-after",
+			"Wed, 21 Oct 2015 07:28:00 GMT"
+		)])),
+		None
+	);
+}
@@ -0,0 +1,527 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use ::http::{HeaderMap, HeaderValue};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use macro_rules_attribute::apply;
+use parking_lot::Mutex;
+
+use crate::encryption::{self, Cipher};
+use crate::http::x_headers::{X_QUOTA_LIMIT, X_QUOTA_REMAINING, X_QUOTA_RESET, X_QUOTA_WARNING};
+use crate::http::{PolicyResponse, Request, apikey, jwt};
+use crate::proxy::ProxyError;
+use crate::transport::stream::TLSConnectionInfo;
+use crate::*;
+
+#[cfg(test)]
+#[path = "quota_tests.rs"]
+mod tests;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+	#[error("{period:?} {kind} quota exceeded")]
+	Exceeded {
+		period: Period,
+		kind: &'static str,
+		limit: f64,
+		reset_seconds: u64,
+	},
+	#[error("no identity found for quota")]
+	MissingIdentity,
+}
+
+#[apply(schema!)]
+#[derive(Copy, PartialEq, Eq, Hash)]
+pub enum Period {
+	Daily,
+	Monthly,
+}
+
+impl Period {
+	fn id(&self, now: DateTime<Utc>) -> String {
+		match self {
+			Period::Daily => now.format("%Y-%m-%d").to_string(),
+			Period::Monthly => now.format("%Y-%m").to_string(),
+		}
+	}
+
+	/// Seconds until the current period ends.
+	fn reset_seconds(&self, now: DateTime<Utc>) -> u64 {
+		let today = now.date_naive();
+		let next = match self {
+			Period::Daily => today.succ_opt(),
+			Period::Monthly => {
+				let (y, m) = if today.month() == 12 {
+					(today.year() + 1, 1)
+				} else {
+					(today.year(), today.month() + 1)
+				};
+				NaiveDate::from_ymd_opt(y, m, 1)
+			},
+		};
+		next
+			.and_then(|d| d.and_hms_opt(0, 0, 0))
+			.map(|d| (d.and_utc() - now).num_seconds().max(0) as u64)
+			.unwrap_or_default()
+	}
+}
+
+/// Where to read the identity a quota is tracked against.
+#[apply(schema!)]
+#[derive(Copy, PartialEq, Eq)]
+pub enum IdentitySource {
+	/// The `sub` claim of a validated JWT.
+	Jwt,
+	/// The API key used to authenticate.
+	ApiKey,
+	/// The identity, or first subject alt name, of the client certificate.
+	Mtls,
+}
+
+#[apply(schema!)]
+#[derive(PartialEq)]
+pub struct QuotaLimit {
+	pub period: Period,
+	/// Maximum number of requests in the period.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub requests: Option<u64>,
+	/// Maximum number of LLM tokens (input and output) in the period.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub tokens: Option<u64>,
+	/// Maximum LLM spend in the period, in USD. Spend is priced with the models configured for
+	/// `costs`, so requests to models without a price are not counted.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub cost: Option<f64>,
+}
+
+impl QuotaLimit {
+	/// The limits of the period and how much of each is used, as (kind, limit, used).
+	fn usage(&self, c: &Counter) -> [(&'static str, Option<f64>, f64); 3] {
+		[
+			(
+				"request",
+				self.requests.map(|l| l as f64),
+				c.requests as f64,
+			),
+			("token", self.tokens.map(|l| l as f64), c.tokens as f64),
+			("cost", self.cost, c.cost),
+		]
+	}
+}
+
+/// Enforce request and LLM token budgets per authenticated identity.
+#[apply(schema!)]
+pub struct Quota {
+	/// Name of the quota. Policies with the same name share usage.
+	#[serde(default = "default_name")]
+	pub name: String,
+	/// Sources of the identity, in order of preference.
+	#[serde(default = "default_identity")]
+	pub identity: Vec<IdentitySource>,
+	pub limits: Vec<QuotaLimit>,
+	/// Reject requests without an identity. Otherwise, they are not subject to the quota.
+	#[serde(default)]
+	pub require_identity: bool,
+	/// Fraction of a limit, between 0 and 1, past which responses carry `x-quota-warning` headers.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub warn_at: Option<f64>,
+}
+
+fn default_name() -> String {
+	"default".to_string()
+}
+
+fn default_identity() -> Vec<IdentitySource> {
+	vec![
+		IdentitySource::Jwt,
+		IdentitySource::ApiKey,
+		IdentitySource::Mtls,
+	]
+}
+
+impl Quota {
+	pub fn apply(
+		&self,
+		store: &Arc<QuotaStore>,
+		req: &mut Request,
+	) -> Result<PolicyResponse, ProxyError> {
+		let Some(identity) = self.identity(req) else {
+			if self.require_identity {
+				return Err(ProxyError::Quota(Error::MissingIdentity));
+			}
+			return Ok(PolicyResponse::default());
+		};
+		let key = format!("{}/{identity}", self.name);
+		let warning = store
+			.check_and_count(&key, &self.limits, self.warn_at, Utc::now())
+			.map_err(ProxyError::Quota)?;
+		// Allow LLM usage to be recorded once the response is complete
+		req.extensions_mut().insert(Account {
+			store: store.clone(),
+			key,
+		});
+		Ok(PolicyResponse {
+			direct_response: None,
+			response_headers: warning.map(|w| w.headers()),
+		})
+	}
+
+	fn identity(&self, req: &Request) -> Option<String> {
+		self.identity.iter().find_map(|s| match s {
+			IdentitySource::Jwt => req
+				.extensions()
+				.get::<jwt::Claims>()
+				.and_then(|c| c.inner.get("sub"))
+				.and_then(|s| s.as_str())
+				.map(|s| format!("jwt:{s}")),
+			IdentitySource::ApiKey => req
+				.extensions()
+				.get::<apikey::Claims>()
+				.map(|c| format!("apikey:{}", c.key.fingerprint())),
+			IdentitySource::Mtls => {
+				let tls = req
+					.extensions()
+					.get::<TLSConnectionInfo>()?
+					.src_identity
+					.as_ref()?;
+				match &tls.identity {
+					Some(id) => Some(format!("mtls:{id}")),
+					None => tls.subject_alt_names.first().map(|s| format!("mtls:{s}")),
+				}
+			},
+		})
+	}
+}
+
+/// Handle to an identity's usage, used to record LLM tokens after the response.
+#[derive(Debug, Clone)]
+pub struct Account {
+	store: Arc<QuotaStore>,
+	key: String,
+}
+
+impl Account {
+	pub fn add_tokens(&self, tokens: u64) {
+		self.store.add_tokens(&self.key, tokens, Utc::now());
+	}
+
+	pub fn add_cost(&self, cost: f64) {
+		self.store.add_cost(&self.key, cost, Utc::now());
+	}
+}
+
+/// A limit that is close to being exceeded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+	pub period: Period,
+	pub kind: &'static str,
+	pub limit: f64,
+	pub remaining: f64,
+	pub reset_seconds: u64,
+}
+
+impl Warning {
+	fn headers(&self) -> HeaderMap {
+		let mut hm = HeaderMap::new();
+		let period = match self.period {
+			Period::Daily => "daily",
+			Period::Monthly => "monthly",
+		};
+		let values = [
+			(X_QUOTA_WARNING, format!("{period} {} quota", self.kind)),
+			(X_QUOTA_LIMIT, self.limit.to_string()),
+			(X_QUOTA_REMAINING, self.remaining.to_string()),
+			(X_QUOTA_RESET, self.reset_seconds.to_string()),
+		];
+		for (k, v) in values {
+			if let Ok(hv) = HeaderValue::try_from(v) {
+				hm.insert(k, hv);
+			}
+		}
+		hm
+	}
+}
+
+/// Global settings for quota usage storage.
+#[apply(schema!)]
+pub struct StoreConfig {
+	/// File to persist usage counters to. If unset, usage is kept in memory only.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub path: Option<PathBuf>,
+	/// How often usage counters are written to `path`.
+	#[serde(default = "default_flush_interval", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub flush_interval: Duration,
+}
+
+fn default_flush_interval() -> Duration {
+	Duration::from_secs(10)
+}
+
+impl Default for StoreConfig {
+	fn default() -> Self {
+		Self {
+			path: None,
+			flush_interval: default_flush_interval(),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Counter {
+	/// Identifier of the period the counts apply to, such as `2025-01-31` or `2025-01`.
+	pub period: String,
+	pub requests: u64,
+	pub tokens: u64,
+	/// LLM spend in USD.
+	#[serde(default)]
+	pub cost: f64,
+}
+
+impl Counter {
+	fn roll(&mut self, id: String) -> &mut Self {
+		if self.period != id {
+			*self = Counter {
+				period: id,
+				..Default::default()
+			};
+		}
+		self
+	}
+}
+
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Usage {
+	pub daily: Counter,
+	pub monthly: Counter,
+	/// Limits set for this identity through the admin API, replacing the limits of the policy.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub limits: Option<Vec<QuotaLimit>>,
+}
+
+impl Usage {
+	fn counter(&mut self, period: Period, now: DateTime<Utc>) -> &mut Counter {
+		let c = match period {
+			Period::Daily => &mut self.daily,
+			Period::Monthly => &mut self.monthly,
+		};
+		c.roll(period.id(now))
+	}
+
+	/// Whether every counter is from a past period, and no limits are set, so nothing is lost by
+	/// dropping the usage.
+	fn expired(&self, now: DateTime<Utc>) -> bool {
+		self.limits.is_none()
+			&& self.daily.period != Period::Daily.id(now)
+			&& self.monthly.period != Period::Monthly.id(now)
+	}
+}
+
+/// Usage counters for all identities, optionally persisted to disk.
+#[derive(Debug, Default)]
+pub struct QuotaStore {
+	cfg: StoreConfig,
+	cipher: Option<Arc<Cipher>>,
+	usage: Mutex<HashMap<String, Usage>>,
+	dirty: AtomicBool,
+}
+
+impl QuotaStore {
+	pub fn new(cfg: StoreConfig, cipher: Option<Arc<Cipher>>) -> anyhow::Result<Self> {
+		let usage = match &cfg.path {
+			Some(p) if p.exists() => {
+				let data = encryption::open(cipher.as_deref(), &fs_err::read(p)?)?;
+				serde_json::from_slice(&data)?
+			},
+			_ => HashMap::new(),
+		};
+		Ok(Self {
+			cfg,
+			cipher,
+			usage: Mutex::new(usage),
+			dirty: AtomicBool::new(false),
+		})
+	}
+
+	/// Count a request against the limits, or the identity's own limits if it has any. Returns the
+	/// most used limit past `warn_at`, if any.
+	fn check_and_count(
+		&self,
+		key: &str,
+		limits: &[QuotaLimit],
+		warn_at: Option<f64>,
+		now: DateTime<Utc>,
+	) -> Result<Option<Warning>, Error> {
+		let mut usage = self.usage.lock();
+		let u = usage.entry(key.to_string()).or_default();
+		let limits = u.limits.clone().unwrap_or_else(|| limits.to_vec());
+		let mut warning: Option<(f64, Warning)> = None;
+		for l in &limits {
+			let c = u.counter(l.period, now);
+			for (kind, limit, used) in l.usage(c) {
+				let Some(limit) = limit else {
+					continue;
+				};
+				if used >= limit {
+					return Err(Error::Exceeded {
+						period: l.period,
+						kind,
+						limit,
+						reset_seconds: l.period.reset_seconds(now),
+					});
+				}
+				// Requests include this one
+				let used = if kind == "request" { used + 1.0 } else { used };
+				let fraction = used / limit;
+				if warn_at.is_some_and(|w| fraction >= w)
+					&& warning.as_ref().is_none_or(|(f, _)| fraction > *f)
+				{
+					warning = Some((
+						fraction,
+						Warning {
+							period: l.period,
+							kind,
+							limit,
+							remaining: (limit - used).max(0.0),
+							reset_seconds: l.period.reset_seconds(now),
+						},
+					));
+				}
+			}
+		}
+		u.counter(Period::Daily, now).requests += 1;
+		u.counter(Period::Monthly, now).requests += 1;
+		self.dirty.store(true, Ordering::Relaxed);
+		Ok(warning.map(|(_, w)| w))
+	}
+
+	fn add_tokens(&self, key: &str, tokens: u64, now: DateTime<Utc>) {
+		if tokens == 0 {
+			return;
+		}
+		let mut usage = self.usage.lock();
+		let u = usage.entry(key.to_string()).or_default();
+		u.counter(Period::Daily, now).tokens += tokens;
+		u.counter(Period::Monthly, now).tokens += tokens;
+		self.dirty.store(true, Ordering::Relaxed);
+	}
+
+	fn add_cost(&self, key: &str, cost: f64, now: DateTime<Utc>) {
+		if cost <= 0.0 {
+			return;
+		}
+		let mut usage = self.usage.lock();
+		let u = usage.entry(key.to_string()).or_default();
+		u.counter(Period::Daily, now).cost += cost;
+		u.counter(Period::Monthly, now).cost += cost;
+		self.dirty.store(true, Ordering::Relaxed);
+	}
+
+	/// Set the limits of a single identity key, replacing the limits of its policy. `None` restores
+	/// the policy's limits.
+	pub fn set_limits(&self, key: &str, limits: Option<Vec<QuotaLimit>>) {
+		let mut usage = self.usage.lock();
+		match limits {
+			Some(limits) => usage.entry(key.to_string()).or_default().limits = Some(limits),
+			None => {
+				if let Some(u) = usage.get_mut(key) {
+					u.limits = None;
+				}
+			},
+		}
+		self.dirty.store(true, Ordering::Relaxed);
+	}
+
+	/// Current usage for all identities.
+	pub fn snapshot(&self) -> BTreeMap<String, Usage> {
+		let now = Utc::now();
+		let mut usage = self.usage.lock();
+		usage
+			.iter_mut()
+			.map(|(k, u)| {
+				u.counter(Period::Daily, now);
+				u.counter(Period::Monthly, now);
+				(k.clone(), u.clone())
+			})
+			.collect()
+	}
+
+	/// Reset usage for a single identity key, or all identities. Returns the number of entries reset.
+	/// Limits set for an identity are kept.
+	pub fn reset(&self, key: Option<&str>) -> usize {
+		let mut usage = self.usage.lock();
+		let keys: Vec<String> = match key {
+			Some(k) => usage
+				.contains_key(k)
+				.then(|| k.to_string())
+				.into_iter()
+				.collect(),
+			None => usage.keys().cloned().collect(),
+		};
+		for k in &keys {
+			let Some(u) = usage.remove(k) else {
+				continue;
+			};
+			if u.limits.is_some() {
+				usage.insert(
+					k.clone(),
+					Usage {
+						limits: u.limits,
+						..Default::default()
+					},
+				);
+			}
+		}
+		let n = keys.len();
+		self.dirty.store(true, Ordering::Relaxed);
+		n
+	}
+
+	/// Drop usage of identities not seen in the current periods. Returns the number of entries
+	/// dropped.
+	fn evict(&self, now: DateTime<Utc>) -> usize {
+		let mut usage = self.usage.lock();
+		let before = usage.len();
+		usage.retain(|_, u| !u.expired(now));
+		let n = before - usage.len();
+		if n > 0 {
+			self.dirty.store(true, Ordering::Relaxed);
+		}
+		n
+	}
+
+	/// Write usage to disk, if persistence is enabled and anything changed.
+	pub fn flush(&self) -> anyhow::Result<()> {
+		let Some(path) = &self.cfg.path else {
+			return Ok(());
+		};
+		if !self.dirty.swap(false, Ordering::Relaxed) {
+			return Ok(());
+		}
+		let contents = serde_json::to_vec(&*self.usage.lock())?;
+		let contents = encryption::seal(self.cipher.as_deref(), contents)?;
+		// Write to a temporary file first so a crash never leaves a partial file behind
+		let tmp = path.with_extension("tmp");
+		fs_err::write(&tmp, contents)?;
+		fs_err::rename(&tmp, path)?;
+		Ok(())
+	}
+
+	/// Periodically evict expired usage and persist usage until the process exits.
+	pub async fn run(self: Arc<Self>) {
+		let mut interval = tokio::time::interval(self.cfg.flush_interval);
+		loop {
+			interval.tick().await;
+			let evicted = self.evict(Utc::now());
+			if evicted > 0 {
+				debug!(evicted, "evicted expired quota usage");
+			}
+			if let Err(e) = self.flush() {
+				warn!("failed to persist quota usage: {e}");
+			}
+		}
+	}
+}
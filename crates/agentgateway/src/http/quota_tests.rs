@@ -0,0 +1,230 @@
+use chrono::TimeZone;
+
+use super::*;
+
+fn at(y: i32, m: u32, d: u32, h: u32) -> DateTime<Utc> {
+	Utc.with_ymd_and_hms(y, m, d, h, 0, 0).unwrap()
+}
+
+fn limits() -> Vec<QuotaLimit> {
+	vec![
+		QuotaLimit {
+			period: Period::Daily,
+			requests: Some(2),
+			tokens: None,
+			cost: None,
+		},
+		QuotaLimit {
+			period: Period::Monthly,
+			requests: None,
+			tokens: Some(100),
+			cost: None,
+		},
+	]
+}
+
+#[test]
+fn test_request_limit() {
+	let store = QuotaStore::default();
+	let now = at(2025, 1, 31, 12);
+	assert!(store.check_and_count("a", &limits(), None, now).is_ok());
+	assert!(store.check_and_count("a", &limits(), None, now).is_ok());
+	let Err(Error::Exceeded {
+		period,
+		kind,
+		limit,
+		reset_seconds,
+	}) = store.check_and_count("a", &limits(), None, now)
+	else {
+		panic!("expected quota to be exceeded");
+	};
+	assert_eq!(period, Period::Daily);
+	assert_eq!(kind, "request");
+	assert_eq!(limit, 2.0);
+	assert_eq!(reset_seconds, 12 * 3600);
+	// Other identities are tracked separately
+	assert!(store.check_and_count("b", &limits(), None, now).is_ok());
+	// Usage resets once the day rolls over
+	assert!(
+		store
+			.check_and_count("a", &limits(), None, at(2025, 2, 1, 0))
+			.is_ok()
+	);
+}
+
+#[test]
+fn test_token_limit() {
+	let store = QuotaStore::default();
+	let now = at(2025, 1, 10, 0);
+	assert!(store.check_and_count("a", &limits(), None, now).is_ok());
+	store.add_tokens("a", 100, now);
+	let err = store
+		.check_and_count("a", &limits(), None, at(2025, 1, 11, 0))
+		.unwrap_err();
+	assert!(matches!(
+		err,
+		Error::Exceeded {
+			period: Period::Monthly,
+			kind: "token",
+			..
+		}
+	));
+	// The monthly budget is restored in the next month
+	assert!(
+		store
+			.check_and_count("a", &limits(), None, at(2025, 2, 1, 0))
+			.is_ok()
+	);
+}
+
+#[test]
+fn test_cost_limit() {
+	let store = QuotaStore::default();
+	let now = at(2025, 1, 10, 0);
+	let limits = vec![QuotaLimit {
+		period: Period::Daily,
+		requests: None,
+		tokens: None,
+		cost: Some(1.5),
+	}];
+	assert!(store.check_and_count("a", &limits, None, now).is_ok());
+	store.add_cost("a", 1.0, now);
+	assert!(store.check_and_count("a", &limits, None, now).is_ok());
+	store.add_cost("a", 0.5, now);
+	let Err(Error::Exceeded { kind, limit, .. }) = store.check_and_count("a", &limits, None, now)
+	else {
+		panic!("expected quota to be exceeded");
+	};
+	assert_eq!(kind, "cost");
+	assert_eq!(limit, 1.5);
+	assert_eq!(store.snapshot()["a"].monthly.cost, 1.5);
+}
+
+#[test]
+fn test_warning() {
+	let store = QuotaStore::default();
+	let now = at(2025, 1, 31, 12);
+	// The first request uses half of the daily requests
+	assert_eq!(
+		store
+			.check_and_count("a", &limits(), Some(0.8), now)
+			.unwrap(),
+		None
+	);
+	store.add_tokens("a", 85, now);
+	let warning = store
+		.check_and_count("a", &limits(), Some(0.8), now)
+		.unwrap()
+		.unwrap();
+	// Both limits are past 80%, and the daily requests are used up first
+	assert_eq!(
+		warning,
+		Warning {
+			period: Period::Daily,
+			kind: "request",
+			limit: 2.0,
+			remaining: 0.0,
+			reset_seconds: 12 * 3600,
+		}
+	);
+	let headers = warning.headers();
+	assert_eq!(headers[X_QUOTA_WARNING], "daily request quota");
+	assert_eq!(headers[X_QUOTA_LIMIT], "2");
+	assert_eq!(headers[X_QUOTA_REMAINING], "0");
+}
+
+#[test]
+fn test_set_limits() {
+	let store = QuotaStore::default();
+	let now = at(2025, 1, 31, 12);
+	store.set_limits(
+		"a",
+		Some(vec![QuotaLimit {
+			period: Period::Daily,
+			requests: Some(1),
+			tokens: None,
+			cost: None,
+		}]),
+	);
+	assert!(store.check_and_count("a", &limits(), None, now).is_ok());
+	assert!(store.check_and_count("a", &limits(), None, now).is_err());
+	// Other identities keep the policy's limits
+	assert!(store.check_and_count("b", &limits(), None, now).is_ok());
+	assert!(store.check_and_count("b", &limits(), None, now).is_ok());
+	// Clearing the limits restores the policy's limits
+	store.set_limits("a", None);
+	assert!(store.check_and_count("a", &limits(), None, now).is_ok());
+}
+
+#[test]
+fn test_reset_seconds() {
+	assert_eq!(Period::Daily.reset_seconds(at(2025, 1, 1, 23)), 3600);
+	assert_eq!(Period::Monthly.reset_seconds(at(2025, 12, 31, 0)), 86400);
+}
+
+#[test]
+fn test_reset() {
+	let store = QuotaStore::default();
+	let now = Utc::now();
+	store.add_tokens("a", 1, now);
+	store.add_tokens("b", 1, now);
+	assert_eq!(store.reset(Some("a")), 1);
+	assert_eq!(store.reset(Some("a")), 0);
+	assert_eq!(store.snapshot().len(), 1);
+	assert_eq!(store.reset(None), 1);
+	assert!(store.snapshot().is_empty());
+}
+
+#[test]
+fn test_evict() {
+	let store = QuotaStore::default();
+	store.add_tokens("old", 1, at(2025, 1, 31, 12));
+	store.add_tokens("month", 1, at(2025, 2, 1, 12));
+	store.add_tokens("today", 1, at(2025, 2, 2, 12));
+	store.set_limits("limited", Some(limits()));
+
+	// Only usage from a past month, without limits of its own, is dropped
+	assert_eq!(store.evict(at(2025, 2, 2, 13)), 1);
+	let mut keys: Vec<_> = store.usage.lock().keys().cloned().collect();
+	keys.sort();
+	assert_eq!(keys, vec!["limited", "month", "today"]);
+
+	assert_eq!(store.evict(at(2025, 3, 1, 0)), 2);
+	assert_eq!(store.snapshot().keys().collect::<Vec<_>>(), vec!["limited"]);
+}
+
+#[test]
+fn test_persistence() {
+	let dir = tempfile::tempdir().unwrap();
+	let cfg = StoreConfig {
+		path: Some(dir.path().join("quota.json")),
+		..Default::default()
+	};
+	let store = QuotaStore::new(cfg.clone(), None).unwrap();
+	store.add_tokens("a", 42, Utc::now());
+	store.flush().unwrap();
+
+	let restored = QuotaStore::new(cfg, None).unwrap();
+	assert_eq!(restored.snapshot()["a"].daily.tokens, 42);
+	assert_eq!(restored.snapshot()["a"].monthly.tokens, 42);
+}
+
+#[test]
+fn test_encrypted_persistence() {
+	let dir = tempfile::tempdir().unwrap();
+	let cfg = StoreConfig {
+		path: Some(dir.path().join("quota.json")),
+		..Default::default()
+	};
+	let cipher = Arc::new(Cipher::new(&[7; 32], &[]).unwrap());
+	let store = QuotaStore::new(cfg.clone(), Some(cipher.clone())).unwrap();
+	store.add_tokens("a", 42, Utc::now());
+	store.flush().unwrap();
+	let raw = fs_err::read(cfg.path.as_ref().unwrap()).unwrap();
+	assert!(encryption::is_encrypted(&raw));
+
+	let restored = QuotaStore::new(cfg.clone(), Some(cipher)).unwrap();
+	assert_eq!(restored.snapshot()["a"].daily.tokens, 42);
+	// Encrypted usage cannot be loaded without the key
+	assert!(QuotaStore::new(cfg, None).is_err());
+}
@@ -0,0 +1,140 @@
+use std::net::IpAddr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ::http::{HeaderMap, HeaderName, HeaderValue};
+use ipnet::IpNet;
+use macro_rules_attribute::apply;
+use rand::Rng;
+
+use crate::http::ipfilter::de_cidrs;
+use crate::*;
+
+#[cfg(test)]
+#[path = "requestid_tests.rs"]
+mod tests;
+
+/// Incoming request IDs longer than this are replaced.
+const MAX_LENGTH: usize = 128;
+
+const CROCKFORD: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+#[apply(schema!)]
+#[derive(Default, Copy, PartialEq, Eq)]
+pub enum IdFormat {
+	/// A random UUID (version 4).
+	#[default]
+	Uuid,
+	/// A ULID, which sorts by the time the request was received.
+	Ulid,
+}
+
+/// Settings for identifying each request with an ID, which is propagated to backends and included
+/// in access logs, traces, and responses.
+#[apply(schema!)]
+pub struct RequestIdPolicy {
+	/// Header carrying the request ID.
+	#[serde(default = "default_header", with = "http_serde::header_name")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub header: HeaderName,
+	/// Format of generated request IDs.
+	#[serde(default)]
+	pub format: IdFormat,
+	/// Keep request IDs sent by clients. If false, a new ID is always generated.
+	#[serde(default = "default_true")]
+	pub preserve: bool,
+	/// Only keep request IDs from clients connecting from these addresses. If empty, IDs from any
+	/// client are kept.
+	#[serde(
+		default,
+		deserialize_with = "de_cidrs",
+		skip_serializing_if = "Vec::is_empty"
+	)]
+	#[cfg_attr(feature = "schema", schemars(with = "Vec<String>"))]
+	pub trusted_networks: Vec<IpNet>,
+	/// Return the request ID to the client, in the same header.
+	#[serde(default = "default_true")]
+	pub response_header: bool,
+}
+
+fn default_header() -> HeaderName {
+	HeaderName::from_static("x-request-id")
+}
+
+fn default_true() -> bool {
+	true
+}
+
+impl Default for RequestIdPolicy {
+	fn default() -> Self {
+		Self {
+			header: default_header(),
+			format: IdFormat::default(),
+			preserve: true,
+			trusted_networks: vec![],
+			response_header: true,
+		}
+	}
+}
+
+impl RequestIdPolicy {
+	/// Determine the request ID for a request from `peer`, and set it in the request headers.
+	pub fn apply(&self, peer: IpAddr, headers: &mut HeaderMap) -> String {
+		if let Some(id) = self.incoming(peer, headers) {
+			return id;
+		}
+		let id = self.generate();
+		headers.insert(
+			self.header.clone(),
+			HeaderValue::from_str(&id).expect("generated ids are valid header values"),
+		);
+		id
+	}
+
+	/// Set the request ID in the response headers, if enabled.
+	pub fn apply_response(&self, id: &str, headers: &mut HeaderMap) {
+		if !self.response_header {
+			return;
+		}
+		if let Ok(v) = HeaderValue::from_str(id) {
+			headers.insert(self.header.clone(), v);
+		}
+	}
+
+	/// The incoming request ID, if it is present, trusted, and well-formed.
+	fn incoming(&self, peer: IpAddr, headers: &HeaderMap) -> Option<String> {
+		if !self.preserve {
+			return None;
+		}
+		let peer = peer.to_canonical();
+		if !self.trusted_networks.is_empty() && !self.trusted_networks.iter().any(|n| n.contains(&peer))
+		{
+			return None;
+		}
+		let id = headers.get(&self.header)?.to_str().ok()?;
+		let valid =
+			!id.is_empty() && id.len() <= MAX_LENGTH && id.bytes().all(|b| b.is_ascii_graphic());
+		valid.then(|| id.to_string())
+	}
+
+	fn generate(&self) -> String {
+		match self.format {
+			IdFormat::Uuid => uuid::Uuid::new_v4().to_string(),
+			IdFormat::Ulid => {
+				let millis = SystemTime::now()
+					.duration_since(UNIX_EPOCH)
+					.unwrap_or_default()
+					.as_millis() as u64;
+				ulid(millis, rand::rng().random())
+			},
+		}
+	}
+}
+
+/// Encode a ULID from a 48 bit millisecond timestamp and 80 bits of randomness.
+fn ulid(millis: u64, random: u128) -> String {
+	let value = ((millis as u128 & 0xFFFF_FFFF_FFFF) << 80) | (random & ((1 << 80) - 1));
+	(0..26)
+		.rev()
+		.map(|i| CROCKFORD[((value >> (i * 5)) & 0x1F) as usize] as char)
+		.collect()
+}
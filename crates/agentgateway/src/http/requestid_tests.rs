@@ -0,0 +1,96 @@
+use super::*;
+
+fn peer(s: &str) -> IpAddr {
+	s.parse().unwrap()
+}
+
+fn headers(id: &str) -> HeaderMap {
+	let mut h = HeaderMap::new();
+	h.insert("x-request-id", HeaderValue::from_str(id).unwrap());
+	h
+}
+
+#[test]
+fn test_generate() {
+	let p = RequestIdPolicy::default();
+	let mut h = HeaderMap::new();
+	let id = p.apply(peer("10.0.0.1"), &mut h);
+	assert!(uuid::Uuid::parse_str(&id).is_ok());
+	assert_eq!(h.get("x-request-id").unwrap(), id.as_str());
+}
+
+#[test]
+fn test_preserve() {
+	let p = RequestIdPolicy::default();
+	let mut h = headers("abc-123");
+	assert_eq!(p.apply(peer("10.0.0.1"), &mut h), "abc-123");
+	assert_eq!(h.get("x-request-id").unwrap(), "abc-123");
+
+	let p = RequestIdPolicy {
+		preserve: false,
+		..Default::default()
+	};
+	let mut h = headers("abc-123");
+	let id = p.apply(peer("10.0.0.1"), &mut h);
+	assert_ne!(id, "abc-123");
+	assert_eq!(h.get("x-request-id").unwrap(), id.as_str());
+}
+
+#[test]
+fn test_trusted_networks() {
+	let p: RequestIdPolicy = serde_json::from_value(serde_json::json!({
+		"header": "x-correlation-id",
+		"trustedNetworks": ["10.0.0.0/8", "::1"],
+	}))
+	.unwrap();
+	let mut h = HeaderMap::new();
+	h.insert("x-correlation-id", HeaderValue::from_static("abc"));
+
+	assert_eq!(p.apply(peer("10.1.2.3"), &mut h.clone()), "abc");
+	assert_eq!(p.apply(peer("::ffff:10.1.2.3"), &mut h.clone()), "abc");
+	assert_eq!(p.apply(peer("::1"), &mut h.clone()), "abc");
+	let id = p.apply(peer("192.168.0.1"), &mut h);
+	assert_ne!(id, "abc");
+	assert_eq!(h.get("x-correlation-id").unwrap(), id.as_str());
+}
+
+#[test]
+fn test_invalid_incoming() {
+	let p = RequestIdPolicy::default();
+	for invalid in ["", "has space", &"a".repeat(MAX_LENGTH + 1)] {
+		let mut h = headers(invalid);
+		let id = p.apply(peer("10.0.0.1"), &mut h);
+		assert!(uuid::Uuid::parse_str(&id).is_ok(), "{invalid:?} was kept");
+	}
+}
+
+#[test]
+fn test_ulid() {
+	assert_eq!(ulid(0, 0), "00000000000000000000000000");
+	assert_eq!(ulid(u64::MAX, u128::MAX), "7ZZZZZZZZZZZZZZZZZZZZZZZZZ");
+	// The timestamp is encoded first, so IDs sort by time.
+	assert!(ulid(1, u128::MAX) < ulid(2, 0));
+
+	let p = RequestIdPolicy {
+		format: IdFormat::Ulid,
+		..Default::default()
+	};
+	let id = p.apply(peer("10.0.0.1"), &mut HeaderMap::new());
+	assert_eq!(id.len(), 26);
+	assert!(id.bytes().all(|b| CROCKFORD.contains(&b)));
+}
+
+#[test]
+fn test_apply_response() {
+	let mut h = HeaderMap::new();
+	RequestIdPolicy::default().apply_response("abc", &mut h);
+	assert_eq!(h.get("x-request-id").unwrap(), "abc");
+
+	let p = RequestIdPolicy {
+		response_header: false,
+		..Default::default()
+	};
+	let mut h = HeaderMap::new();
+	p.apply_response("abc", &mut h);
+	assert!(h.is_empty());
+}
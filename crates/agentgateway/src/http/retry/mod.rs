@@ -4,9 +4,14 @@ use std::num::NonZeroU8;
 use std::time::Duration;
 
 pub use body::ReplayBody;
+use rand::Rng;
 
 use crate::*;
 
+#[cfg(test)]
+#[path = "tests.rs"]
+mod tests;
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
 #[cfg_attr(feature = "schema", derive(JsonSchema))]
@@ -21,9 +26,81 @@ pub struct Policy {
 	)]
 	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
 	pub backoff: Option<Duration>,
-	#[serde(serialize_with = "ser_display_iter", deserialize_with = "de_codes")]
+	/// Response statuses to retry. If empty, LLM routes retry the statuses each provider uses for
+	/// transient errors, such as `429` and `503`, and other routes only retry connection errors.
+	#[serde(
+		default,
+		serialize_with = "ser_display_iter",
+		deserialize_with = "de_codes"
+	)]
 	#[cfg_attr(feature = "schema", schemars(with = "Vec<std::num::NonZeroU16>"))]
 	pub codes: Box<[http::StatusCode]>,
+	/// gRPC statuses to retry, such as `14` (`UNAVAILABLE`). Only a status in the response headers,
+	/// as servers send when a call fails before responding, can be retried; a status in the
+	/// trailers arrives after the response was already returned.
+	#[serde(default)]
+	pub grpc_codes: Box<[u8]>,
+	/// Before retrying a throttled (`429`) or unavailable (`503`) response, wait as long as the
+	/// backend asks with `Retry-After` or rate limit reset headers, instead of `backoff`.
+	#[serde(default)]
+	pub honor_retry_after: bool,
+	/// Wait a random time between half and all of `backoff`, so clients retrying together spread
+	/// out.
+	#[serde(default)]
+	pub jitter: bool,
+	/// The most time to spend waiting between attempts, in total. Retries that would wait past it
+	/// are not attempted, and the last response is returned.
+	#[serde(
+		default,
+		skip_serializing_if = "Option::is_none",
+		with = "serde_dur_option"
+	)]
+	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+	pub budget: Option<Duration>,
+}
+
+impl Policy {
+	/// Whether a response with `status` should be retried. `provider` is the LLM provider that
+	/// returned it, if any.
+	pub fn retryable_status(&self, status: http::StatusCode, provider: Option<&str>) -> bool {
+		if !self.codes.is_empty() {
+			return self.codes.contains(&status);
+		}
+		provider.is_some_and(|p| crate::llm::retryable_status(p, status))
+	}
+
+	/// Whether a response with a gRPC status in its headers should be retried.
+	pub fn retryable_grpc_status(&self, headers: &http::HeaderMap) -> bool {
+		!self.grpc_codes.is_empty()
+			&& crate::http::grpc::status(headers).is_some_and(|s| self.grpc_codes.contains(&s))
+	}
+
+	/// How long to wait before the next attempt, after `waited` has already been spent waiting, or
+	/// None if the retry budget does not allow it.
+	pub fn delay(
+		&self,
+		resp: Option<(http::StatusCode, &http::HeaderMap)>,
+		waited: Duration,
+	) -> Option<Duration> {
+		let requested = resp
+			.filter(|_| self.honor_retry_after)
+			.and_then(|(status, headers)| crate::http::outlierdetection::retry_delay(status, headers));
+		let delay = match requested {
+			Some(d) => d,
+			None => {
+				let backoff = self.backoff.unwrap_or_default();
+				if self.jitter && !backoff.is_zero() {
+					rand::rng().random_range(backoff / 2..=backoff)
+				} else {
+					backoff
+				}
+			},
+		};
+		match self.budget {
+			Some(budget) if waited + delay > budget => None,
+			_ => Some(delay),
+		}
+	}
 }
 
 pub fn de_codes<'de: 'a, 'a, D>(deserializer: D) -> Result<Box<[http::StatusCode]>, D::Error>
@@ -0,0 +1,81 @@
+use ::http::{HeaderMap, HeaderValue, StatusCode};
+
+use super::*;
+
+fn policy(cfg: serde_json::Value) -> Policy {
+	serde_json::from_value(cfg).unwrap()
+}
+
+#[test]
+fn test_retryable_status() {
+	let p = policy(serde_json::json!({"attempts": 2}));
+	// Without codes, only LLM providers' transient errors are retried
+	assert!(p.retryable_status(StatusCode::TOO_MANY_REQUESTS, Some("openai")));
+	assert!(p.retryable_status(StatusCode::SERVICE_UNAVAILABLE, Some("aws.bedrock")));
+	assert!(!p.retryable_status(StatusCode::BAD_REQUEST, Some("openai")));
+	assert!(!p.retryable_status(StatusCode::UNAUTHORIZED, Some("anthropic")));
+	assert!(!p.retryable_status(StatusCode::TOO_MANY_REQUESTS, None));
+	let overloaded = StatusCode::from_u16(529).unwrap();
+	assert!(p.retryable_status(overloaded, Some("anthropic")));
+	assert!(!p.retryable_status(overloaded, Some("openai")));
+
+	let p = policy(serde_json::json!({"attempts": 2, "codes": [503]}));
+	assert!(p.retryable_status(StatusCode::SERVICE_UNAVAILABLE, None));
+	assert!(!p.retryable_status(StatusCode::TOO_MANY_REQUESTS, Some("openai")));
+}
+
+#[test]
+fn test_delay() {
+	let mut headers = HeaderMap::new();
+	headers.insert(::http::header::RETRY_AFTER, HeaderValue::from_static("3"));
+	let throttled = Some((StatusCode::TOO_MANY_REQUESTS, &headers));
+
+	let p = policy(serde_json::json!({"attempts": 2, "backoff": "100ms"}));
+	assert_eq!(
+		p.delay(throttled, Duration::ZERO),
+		Some(Duration::from_millis(100))
+	);
+
+	let p = policy(serde_json::json!({
+		"attempts": 2,
+		"backoff": "100ms",
+		"honorRetryAfter": true,
+		"budget": "5s",
+	}));
+	assert_eq!(
+		p.delay(throttled, Duration::ZERO),
+		Some(Duration::from_secs(3))
+	);
+	// Only throttled and unavailable responses ask for a delay
+	assert_eq!(
+		p.delay(Some((StatusCode::BAD_GATEWAY, &headers)), Duration::ZERO),
+		Some(Duration::from_millis(100))
+	);
+	assert_eq!(
+		p.delay(None, Duration::ZERO),
+		Some(Duration::from_millis(100))
+	);
+	// Waiting past the budget is not allowed
+	assert_eq!(p.delay(throttled, Duration::from_secs(3)), None);
+
+	let p = policy(serde_json::json!({"attempts": 2, "backoff": "100ms", "jitter": true}));
+	for _ in 0..100 {
+		let d = p.delay(None, Duration::ZERO).unwrap();
+		assert!(d >= Duration::from_millis(50) && d <= Duration::from_millis(100));
+	}
+}
+
+#[test]
+fn test_retryable_grpc_status() {
+	let mut headers = HeaderMap::new();
+	let p = policy(serde_json::json!({"attempts": 2}));
+	headers.insert("grpc-status", HeaderValue::from_static("14"));
+	assert!(!p.retryable_grpc_status(&headers));
+
+	let p = policy(serde_json::json!({"attempts": 2, "grpcCodes": [14]}));
+	assert!(p.retryable_grpc_status(&headers));
+	headers.insert("grpc-status", HeaderValue::from_static("5"));
+	assert!(!p.retryable_grpc_status(&headers));
+	// The status is only in the trailers of a response that started successfully.
+	assert!(!p.retryable_grpc_status(&HeaderMap::new()));
+}
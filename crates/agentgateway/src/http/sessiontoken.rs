@@ -0,0 +1,228 @@
+use ::http::{HeaderName, HeaderValue, header};
+use jsonwebtoken::jwk::{Jwk, JwkSet};
+use jsonwebtoken::{Algorithm, EncodingKey};
+use macro_rules_attribute::apply;
+use serde_json::{Map, Value};
+
+use crate::http::transformation_cel::SerAsStr;
+use crate::http::{Request, apikey, basicauth, jwt};
+use crate::proxy::ProxyError;
+use crate::transport::stream::TLSConnectionInfo;
+use crate::*;
+
+#[cfg(test)]
+#[path = "sessiontoken_tests.rs"]
+mod tests;
+
+#[apply(schema!)]
+#[derive(Copy, PartialEq, Eq, Default)]
+pub enum SigningAlgorithm {
+	#[default]
+	#[serde(rename = "ES256")]
+	Es256,
+	#[serde(rename = "ES384")]
+	Es384,
+	#[serde(rename = "RS256")]
+	Rs256,
+	#[serde(rename = "EdDSA")]
+	EdDsa,
+}
+
+impl SigningAlgorithm {
+	fn algorithm(&self) -> Algorithm {
+		match self {
+			SigningAlgorithm::Es256 => Algorithm::ES256,
+			SigningAlgorithm::Es384 => Algorithm::ES384,
+			SigningAlgorithm::Rs256 => Algorithm::RS256,
+			SigningAlgorithm::EdDsa => Algorithm::EdDSA,
+		}
+	}
+
+	fn encoding_key(&self, pem: &[u8]) -> anyhow::Result<EncodingKey> {
+		Ok(match self {
+			SigningAlgorithm::Es256 | SigningAlgorithm::Es384 => EncodingKey::from_ec_pem(pem)?,
+			SigningAlgorithm::Rs256 => EncodingKey::from_rsa_pem(pem)?,
+			SigningAlgorithm::EdDsa => EncodingKey::from_ed_pem(pem)?,
+		})
+	}
+}
+
+/// Settings for the gateway's token issuer, used to mint session tokens for upstreams.
+#[apply(schema_de!)]
+pub struct RawIssuer {
+	/// Value of the `iss` claim.
+	pub issuer: String,
+	/// PEM encoded private key used to sign tokens.
+	pub key: FileOrInline,
+	/// Key ID, set as the `kid` header and published in the JWKS.
+	#[serde(default)]
+	pub key_id: Option<String>,
+	#[serde(default)]
+	pub algorithm: SigningAlgorithm,
+}
+
+/// Signs session tokens on behalf of the gateway. The public key is published at the admin
+/// `/jwks` endpoint so upstreams can validate tokens.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Issuer {
+	issuer: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	key_id: Option<String>,
+	algorithm: SigningAlgorithm,
+	#[serde(skip)]
+	key: EncodingKey,
+	#[serde(skip)]
+	jwks: JwkSet,
+}
+
+impl std::fmt::Debug for Issuer {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("Issuer")
+			.field("issuer", &self.issuer)
+			.field("key_id", &self.key_id)
+			.field("algorithm", &self.algorithm)
+			.finish()
+	}
+}
+
+impl TryFrom<RawIssuer> for Issuer {
+	type Error = anyhow::Error;
+
+	fn try_from(value: RawIssuer) -> Result<Self, Self::Error> {
+		let pem = value.key.load()?;
+		let key = value.algorithm.encoding_key(pem.as_bytes())?;
+		let mut jwk = Jwk::from_encoding_key(&key, value.algorithm.algorithm())?;
+		jwk.common.key_id = value.key_id.clone();
+		Ok(Issuer {
+			issuer: value.issuer,
+			key_id: value.key_id,
+			algorithm: value.algorithm,
+			key,
+			jwks: JwkSet { keys: vec![jwk] },
+		})
+	}
+}
+
+impl Issuer {
+	/// Public keys for validating issued tokens.
+	pub fn jwks(&self) -> &JwkSet {
+		&self.jwks
+	}
+
+	fn sign(&self, claims: &Map<String, Value>) -> anyhow::Result<String> {
+		let mut header = jsonwebtoken::Header::new(self.algorithm.algorithm());
+		header.kid = self.key_id.clone();
+		Ok(jsonwebtoken::encode(&header, claims, &self.key)?)
+	}
+}
+
+/// Replace the client's credentials toward the upstream with a short-lived token, signed by the
+/// gateway, describing the authenticated identity.
+#[apply(schema!)]
+pub struct SessionToken {
+	/// Value of the `aud` claim.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub audience: Option<String>,
+	/// Lifetime of the token (default: 5m).
+	#[serde(default = "default_ttl", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub ttl: Duration,
+	/// Scopes granted to the upstream, set as the space separated `scope` claim.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub scopes: Vec<String>,
+	/// Claims copied from the client's validated JWT, if present.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub copy_claims: Vec<String>,
+	/// Header to send the token in. Tokens in the `Authorization` header use the `Bearer` scheme.
+	/// Requests without an authenticated identity have the header removed, so clients cannot supply
+	/// their own token.
+	#[serde(default = "default_header")]
+	#[serde_as(as = "SerAsStr")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub header: HeaderName,
+}
+
+fn default_ttl() -> Duration {
+	Duration::from_secs(300)
+}
+
+fn default_header() -> HeaderName {
+	header::AUTHORIZATION
+}
+
+impl SessionToken {
+	pub fn apply(&self, issuer: Option<&Issuer>, req: &mut Request) -> Result<(), ProxyError> {
+		let Some(issuer) = issuer else {
+			return Err(ProxyError::Processing(anyhow::anyhow!(
+				"session token policy requires a token issuer to be configured"
+			)));
+		};
+		let Some(claims) = self.claims(issuer, req, jsonwebtoken::get_current_timestamp()) else {
+			// Unauthenticated requests do not get a token, nor keep one sent by the client.
+			trace!("no identity for session token");
+			req.headers_mut().remove(&self.header);
+			return Ok(());
+		};
+		let token = issuer.sign(&claims).map_err(ProxyError::Processing)?;
+		let value = if self.header == header::AUTHORIZATION {
+			format!("Bearer {token}")
+		} else {
+			token
+		};
+		let mut value = HeaderValue::try_from(value).map_err(|e| ProxyError::Processing(e.into()))?;
+		value.set_sensitive(true);
+		req.headers_mut().insert(self.header.clone(), value);
+		Ok(())
+	}
+
+	fn claims(&self, issuer: &Issuer, req: &Request, now: u64) -> Option<Map<String, Value>> {
+		let jwt = req.extensions().get::<jwt::Claims>();
+		let (sub, method) = identity(req)?;
+		let mut claims = Map::new();
+		if let Some(jwt) = jwt {
+			for c in &self.copy_claims {
+				if let Some(v) = jwt.inner.get(c) {
+					claims.insert(c.clone(), v.clone());
+				}
+			}
+		}
+		claims.insert("iss".to_string(), issuer.issuer.clone().into());
+		claims.insert("sub".to_string(), sub.into());
+		if let Some(aud) = &self.audience {
+			claims.insert("aud".to_string(), aud.clone().into());
+		}
+		claims.insert("iat".to_string(), now.into());
+		claims.insert("exp".to_string(), (now + self.ttl.as_secs()).into());
+		claims.insert("jti".to_string(), uuid::Uuid::new_v4().to_string().into());
+		claims.insert("amr".to_string(), Value::from(vec![method]));
+		if !self.scopes.is_empty() {
+			claims.insert("scope".to_string(), self.scopes.join(" ").into());
+		}
+		Some(claims)
+	}
+}
+
+/// The authenticated subject of a request, and the method used to authenticate it.
+fn identity(req: &Request) -> Option<(String, &'static str)> {
+	let ext = req.extensions();
+	if let Some(sub) = ext
+		.get::<jwt::Claims>()
+		.and_then(|c| c.inner.get("sub"))
+		.and_then(Value::as_str)
+	{
+		return Some((sub.to_string(), "jwt"));
+	}
+	if let Some(c) = ext.get::<apikey::Claims>() {
+		return Some((c.key.fingerprint(), "apikey"));
+	}
+	if let Some(c) = ext.get::<basicauth::Claims>() {
+		return Some((c.username.to_string(), "basic"));
+	}
+	let tls = ext.get::<TLSConnectionInfo>()?.src_identity.as_ref()?;
+	let sub = match &tls.identity {
+		Some(id) => id.to_string(),
+		None => tls.subject_alt_names.first()?.to_string(),
+	};
+	Some((sub, "mtls"))
+}
@@ -0,0 +1,114 @@
+use jsonwebtoken::{DecodingKey, Validation};
+use secrecy::SecretString;
+
+use super::*;
+
+fn issuer() -> Issuer {
+	let kp = rcgen::KeyPair::generate_for(&rcgen::PKCS_ECDSA_P256_SHA256).unwrap();
+	Issuer::try_from(RawIssuer {
+		issuer: "agentgateway".to_string(),
+		key: FileOrInline::Inline(kp.serialize_pem()),
+		key_id: Some("gw-1".to_string()),
+		algorithm: SigningAlgorithm::Es256,
+	})
+	.unwrap()
+}
+
+fn policy() -> SessionToken {
+	serde_json::from_value(serde_json::json!({
+		"audience": "backend",
+		"scopes": ["read", "write"],
+		"copyClaims": ["email"],
+	}))
+	.unwrap()
+}
+
+fn request_with_jwt() -> Request {
+	let mut req = crate::http::Request::new(crate::http::Body::empty());
+	let mut inner = Map::new();
+	inner.insert("sub".to_string(), "alice".into());
+	inner.insert("email".to_string(), "alice@example.com".into());
+	inner.insert("secret".to_string(), "hidden".into());
+	req.extensions_mut().insert(jwt::Claims {
+		inner,
+		jwt: SecretString::new("header.payload.signature".into()),
+	});
+	req
+		.headers_mut()
+		.insert(header::AUTHORIZATION, "Bearer original".parse().unwrap());
+	req
+}
+
+fn decode(issuer: &Issuer, token: &str) -> Map<String, Value> {
+	let jwk = &issuer.jwks().keys[0];
+	assert_eq!(jwk.common.key_id.as_deref(), Some("gw-1"));
+	let mut validation = Validation::new(Algorithm::ES256);
+	validation.set_audience(&["backend"]);
+	validation.set_issuer(&["agentgateway"]);
+	jsonwebtoken::decode::<Map<String, Value>>(
+		token,
+		&DecodingKey::from_jwk(jwk).unwrap(),
+		&validation,
+	)
+	.unwrap()
+	.claims
+}
+
+#[test]
+fn test_mint_token() {
+	let issuer = issuer();
+	let mut req = request_with_jwt();
+	policy().apply(Some(&issuer), &mut req).unwrap();
+
+	let auth = req.headers().get(header::AUTHORIZATION).unwrap();
+	let token = auth.to_str().unwrap().strip_prefix("Bearer ").unwrap();
+	assert_ne!(token, "original");
+
+	let claims = decode(&issuer, token);
+	assert_eq!(claims["sub"], "alice");
+	assert_eq!(claims["scope"], "read write");
+	assert_eq!(claims["email"], "alice@example.com");
+	assert_eq!(claims["amr"], serde_json::json!(["jwt"]));
+	assert!(!claims.contains_key("secret"));
+	assert_eq!(
+		claims["exp"].as_u64().unwrap() - claims["iat"].as_u64().unwrap(),
+		300
+	);
+}
+
+#[test]
+fn test_custom_header() {
+	let issuer = issuer();
+	let mut p = policy();
+	p.header = HeaderName::from_static("x-gateway-token");
+	let mut req = request_with_jwt();
+	p.apply(Some(&issuer), &mut req).unwrap();
+	// The original credentials are left untouched
+	assert_eq!(
+		req.headers().get(header::AUTHORIZATION).unwrap(),
+		"Bearer original"
+	);
+	let token = req.headers().get("x-gateway-token").unwrap();
+	decode(&issuer, token.to_str().unwrap());
+}
+
+#[test]
+fn test_unauthenticated() {
+	let issuer = issuer();
+	let mut req = crate::http::Request::new(crate::http::Body::empty());
+	policy().apply(Some(&issuer), &mut req).unwrap();
+	assert!(req.headers().get(header::AUTHORIZATION).is_none());
+
+	// A token sent by the client is not passed on
+	let mut p = policy();
+	p.header = HeaderName::from_static("x-gateway-token");
+	let mut req = crate::http::Request::new(crate::http::Body::empty());
+	req
+		.headers_mut()
+		.insert("x-gateway-token", "forged".parse().unwrap());
+	p.apply(Some(&issuer), &mut req).unwrap();
+	assert!(req.headers().get("x-gateway-token").is_none());
+
+	// A policy without a configured issuer is a configuration error
+	assert!(policy().apply(None, &mut request_with_jwt()).is_err());
+}
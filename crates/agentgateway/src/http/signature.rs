@@ -0,0 +1,220 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use aws_lc_rs::{digest, hmac};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use macro_rules_attribute::apply;
+use secrecy::{ExposeSecret, SecretString};
+
+use crate::http::Request;
+use crate::proxy::ProxyError;
+use crate::serdes::deser_key_from_file;
+use crate::*;
+
+#[cfg(test)]
+#[path = "signature_tests.rs"]
+mod tests;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+	#[error("no request signature found")]
+	Missing,
+
+	#[error("no request signature timestamp found")]
+	MissingTimestamp,
+
+	#[error("invalid request signature timestamp")]
+	InvalidTimestamp,
+
+	#[error("request signature timestamp outside of allowed clock skew")]
+	Expired,
+
+	#[error("unknown signing key")]
+	UnknownKey,
+
+	#[error("invalid request signature")]
+	InvalidSignature,
+
+	#[error("failed to read request body: {0}")]
+	Body(String),
+}
+
+/// HMAC algorithm used to sign requests
+#[apply(schema!)]
+#[derive(Copy, PartialEq, Eq, Default)]
+pub enum Algorithm {
+	#[default]
+	HmacSha256,
+	HmacSha512,
+}
+
+impl Algorithm {
+	fn hmac(&self) -> hmac::Algorithm {
+		match self {
+			Algorithm::HmacSha256 => hmac::HMAC_SHA256,
+			Algorithm::HmacSha512 => hmac::HMAC_SHA512,
+		}
+	}
+}
+
+/// Encoding of the signature in the signature header
+#[apply(schema!)]
+#[derive(Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+	#[default]
+	Hex,
+	Base64,
+}
+
+#[apply(schema!)]
+pub struct SigningKey {
+	/// Identifier of the key, matched against the `keyIdHeader` if configured.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub id: Option<String>,
+	/// The shared secret used to compute the HMAC.
+	#[cfg_attr(feature = "schema", schemars(with = "FileOrInline"))]
+	#[serde(
+		serialize_with = "ser_redact",
+		deserialize_with = "deser_key_from_file"
+	)]
+	pub key: SecretString,
+}
+
+/// Verifies an HMAC signature over the request timestamp, method, path, selected headers, and body.
+///
+/// The signed payload is built by joining the following with newlines:
+/// the timestamp header value, the request method, the path and query, the value of each
+/// `signedHeaders` entry (in order), and the lowercase hex SHA-256 digest of the body.
+#[apply(schema!)]
+pub struct RequestSignature {
+	/// Signing keys. When no `keyIdHeader` is set, any key may match, allowing keys to be rotated.
+	pub keys: Vec<SigningKey>,
+	#[serde(default)]
+	pub algorithm: Algorithm,
+	#[serde(default)]
+	pub encoding: Encoding,
+	/// Header containing the signature.
+	#[serde(default = "default_signature_header")]
+	pub header: String,
+	/// Optional prefix to strip from the signature header value, such as `sha256=`.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub prefix: Option<String>,
+	/// Header containing the Unix timestamp (in seconds) the request was signed at.
+	#[serde(default = "default_timestamp_header")]
+	pub timestamp_header: String,
+	/// Header selecting which key was used to sign the request.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub key_id_header: Option<String>,
+	/// Additional headers included in the signed payload.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub signed_headers: Vec<String>,
+	/// Maximum allowed difference between the signature timestamp and the current time.
+	#[serde(default = "default_max_clock_skew", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub max_clock_skew: Duration,
+}
+
+fn default_signature_header() -> String {
+	"x-signature".to_string()
+}
+
+fn default_timestamp_header() -> String {
+	"x-signature-timestamp".to_string()
+}
+
+fn default_max_clock_skew() -> Duration {
+	Duration::from_secs(300)
+}
+
+impl RequestSignature {
+	/// Verify the signature of a request, rejecting unsigned, stale, or tampered requests.
+	pub async fn apply(&self, req: &mut Request) -> Result<(), ProxyError> {
+		self
+			.verify(req, SystemTime::now())
+			.await
+			.map_err(ProxyError::SignatureVerificationFailure)
+	}
+
+	async fn verify(&self, req: &mut Request, now: SystemTime) -> Result<(), Error> {
+		let signature = header_str(req, &self.header).ok_or(Error::Missing)?;
+		let signature = match &self.prefix {
+			Some(prefix) => signature.strip_prefix(prefix.as_str()).unwrap_or(signature),
+			None => signature,
+		};
+		let signature = match self.encoding {
+			Encoding::Hex => hex::decode(signature.trim()).ok(),
+			Encoding::Base64 => STANDARD.decode(signature.trim()).ok(),
+		}
+		.ok_or(Error::InvalidSignature)?;
+
+		let timestamp = header_str(req, &self.timestamp_header)
+			.ok_or(Error::MissingTimestamp)?
+			.trim()
+			.to_string();
+		let signed_at = timestamp
+			.parse::<u64>()
+			.map_err(|_| Error::InvalidTimestamp)?;
+		let now = now
+			.duration_since(UNIX_EPOCH)
+			.map_err(|_| Error::InvalidTimestamp)?
+			.as_secs();
+		if now.abs_diff(signed_at) > self.max_clock_skew.as_secs() {
+			return Err(Error::Expired);
+		}
+
+		let keys: Vec<&SigningKey> = match &self.key_id_header {
+			Some(h) => {
+				let id = header_str(req, h).ok_or(Error::UnknownKey)?;
+				let key = self
+					.keys
+					.iter()
+					.find(|k| k.id.as_deref() == Some(id))
+					.ok_or(Error::UnknownKey)?;
+				vec![key]
+			},
+			None => self.keys.iter().collect(),
+		};
+
+		let payload = self.payload(req, &timestamp).await?;
+		let alg = self.algorithm.hmac();
+		let valid = keys.iter().any(|k| {
+			let key = hmac::Key::new(alg, k.key.expose_secret().as_bytes());
+			hmac::verify(&key, &payload, &signature).is_ok()
+		});
+		if !valid {
+			return Err(Error::InvalidSignature);
+		}
+		Ok(())
+	}
+
+	async fn payload(&self, req: &mut Request, timestamp: &str) -> Result<Vec<u8>, Error> {
+		let mut payload = Vec::with_capacity(256);
+		payload.extend_from_slice(timestamp.as_bytes());
+		payload.push(b'\n');
+		payload.extend_from_slice(req.method().as_str().as_bytes());
+		payload.push(b'\n');
+		let path = req
+			.uri()
+			.path_and_query()
+			.map(|pq| pq.as_str())
+			.unwrap_or("/");
+		payload.extend_from_slice(path.as_bytes());
+		payload.push(b'\n');
+		for h in &self.signed_headers {
+			if let Some(v) = req.headers().get(h.as_str()) {
+				payload.extend_from_slice(v.as_bytes());
+			}
+			payload.push(b'\n');
+		}
+		let body = crate::http::inspect_body(req)
+			.await
+			.map_err(|e| Error::Body(e.to_string()))?;
+		let body_digest = digest::digest(&digest::SHA256, &body);
+		payload.extend_from_slice(hex::encode(body_digest.as_ref()).as_bytes());
+		Ok(payload)
+	}
+}
+
+fn header_str<'a>(req: &'a Request, name: &str) -> Option<&'a str> {
+	req.headers().get(name).and_then(|v| v.to_str().ok())
+}
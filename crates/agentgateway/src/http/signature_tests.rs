@@ -0,0 +1,113 @@
+use super::*;
+
+const SECRET: &str = "webhook-secret";
+const NOW: u64 = 1_700_000_000;
+
+fn policy() -> RequestSignature {
+	RequestSignature {
+		keys: vec![SigningKey {
+			id: Some("k1".to_string()),
+			key: SecretString::from(SECRET.to_string()),
+		}],
+		algorithm: Algorithm::HmacSha256,
+		encoding: Encoding::Hex,
+		header: default_signature_header(),
+		prefix: None,
+		timestamp_header: default_timestamp_header(),
+		key_id_header: None,
+		signed_headers: vec![],
+		max_clock_skew: default_max_clock_skew(),
+	}
+}
+
+fn sign(secret: &str, ts: u64, method: &str, path: &str, body: &str) -> String {
+	let digest = hex::encode(digest::digest(&digest::SHA256, body.as_bytes()).as_ref());
+	let payload = format!("{ts}\n{method}\n{path}\n{digest}");
+	let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+	hex::encode(hmac::sign(&key, payload.as_bytes()).as_ref())
+}
+
+fn request(sig: Option<&str>, ts: u64, body: &'static str) -> Request {
+	let mut rb = ::http::Request::builder()
+		.method("POST")
+		.uri("http://example.com/hook?a=b")
+		.header("x-signature-timestamp", ts.to_string());
+	if let Some(sig) = sig {
+		rb = rb.header("x-signature", sig);
+	}
+	rb.body(crate::http::Body::from(body)).unwrap()
+}
+
+fn at(secs: u64) -> SystemTime {
+	UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+#[tokio::test]
+async fn test_valid_signature() {
+	let sig = sign(SECRET, NOW, "POST", "/hook?a=b", "{\"event\":1}");
+	let mut req = request(Some(&sig), NOW, "{\"event\":1}");
+	policy().verify(&mut req, at(NOW + 10)).await.unwrap();
+	// The body must remain readable by later stages
+	let body = crate::http::read_body(req).await.unwrap();
+	assert_eq!(body.as_ref(), b"{\"event\":1}");
+}
+
+#[tokio::test]
+async fn test_missing_signature() {
+	let mut req = request(None, NOW, "{}");
+	let err = policy().verify(&mut req, at(NOW)).await.unwrap_err();
+	assert!(matches!(err, Error::Missing));
+}
+
+#[tokio::test]
+async fn test_tampered_body() {
+	let sig = sign(SECRET, NOW, "POST", "/hook?a=b", "{\"event\":1}");
+	let mut req = request(Some(&sig), NOW, "{\"event\":2}");
+	let err = policy().verify(&mut req, at(NOW)).await.unwrap_err();
+	assert!(matches!(err, Error::InvalidSignature));
+}
+
+#[tokio::test]
+async fn test_clock_skew() {
+	let sig = sign(SECRET, NOW, "POST", "/hook?a=b", "{}");
+	let mut req = request(Some(&sig), NOW, "{}");
+	let err = policy().verify(&mut req, at(NOW + 301)).await.unwrap_err();
+	assert!(matches!(err, Error::Expired));
+}
+
+#[tokio::test]
+async fn test_key_rotation_and_prefix() {
+	let mut pol = policy();
+	pol.prefix = Some("sha256=".to_string());
+	pol.keys.insert(
+		0,
+		SigningKey {
+			id: Some("k0".to_string()),
+			key: SecretString::from("old-secret".to_string()),
+		},
+	);
+	let sig = format!("sha256={}", sign(SECRET, NOW, "POST", "/hook?a=b", "{}"));
+	let mut req = request(Some(&sig), NOW, "{}");
+	pol.verify(&mut req, at(NOW)).await.unwrap();
+
+	pol.key_id_header = Some("x-signature-key".to_string());
+	let mut req = request(Some(&sig), NOW, "{}");
+	req
+		.headers_mut()
+		.insert("x-signature-key", "k0".parse().unwrap());
+	let err = pol.verify(&mut req, at(NOW)).await.unwrap_err();
+	assert!(matches!(err, Error::InvalidSignature));
+}
+
+#[test]
+fn test_deserialize() {
+	let pol: RequestSignature = serde_json::from_value(serde_json::json!({
+		"keys": [{"key": "abc"}],
+		"encoding": "base64",
+		"maxClockSkew": "30s",
+	}))
+	.unwrap();
+	assert_eq!(pol.encoding, Encoding::Base64);
+	assert_eq!(pol.header, "x-signature");
+	assert_eq!(pol.max_clock_skew, Duration::from_secs(30));
+}
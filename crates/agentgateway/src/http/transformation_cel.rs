@@ -273,7 +273,9 @@ impl Transformation {
 		for k in &cfg.remove {
 			r.headers().remove(k);
 		}
-		if let Some(b) = &cfg.body {
+		if let Some(b) = &cfg.body
+			&& crate::overload::body_processing_enabled()
+		{
 			// If it fails, set an empty body
 			let b = eval_body(exec, b).unwrap_or_default();
 			*r.body() = http::Body::from(b);
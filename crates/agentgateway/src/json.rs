@@ -78,7 +78,7 @@ pub async fn inspect_body<T: DeserializeOwned>(req: &mut http::Request) -> anyho
 }
 
 pub fn to_body<T: Serialize>(j: T) -> anyhow::Result<http::Body> {
-	let bytes = serde_json::to_vec(&j)?;
+	let bytes = http::bufpool::to_json(&j)?;
 	Ok(http::Body::from(bytes))
 }
 
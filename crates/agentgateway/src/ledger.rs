@@ -1,11 +1,14 @@
 use std::fs::OpenOptions;
 use std::io::Write;
 use std::path::PathBuf;
+use std::sync::Arc;
 use chrono::Utc;
-use serde::Serialize;
-use tracing::error;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
 
-#[derive(Serialize)]
+use crate::encryption::{self, Cipher};
+
+#[derive(Serialize, Deserialize)]
 pub struct LedgerEntry {
     pub timestamp: String,
     pub service: String,
@@ -15,11 +18,16 @@ pub struct LedgerEntry {
 
 pub struct RecoveryLedger {
     path: PathBuf,
+    cipher: Option<Arc<Cipher>>,
 }
 
 impl RecoveryLedger {
-    pub fn new(path: PathBuf) -> Self {
-        Self { path }
+    pub fn new(path: PathBuf, cipher: Option<Arc<Cipher>>) -> Self {
+        let ledger = Self { path, cipher };
+        if let Err(e) = ledger.rotate() {
+            error!(?e, path=?ledger.path, "RecoveryLedger: Failed to re-encrypt ledger");
+        }
+        ledger
     }
 
     pub fn log(&self, service: &str, event: &str, metadata: serde_json::Value) {
@@ -30,21 +38,106 @@ impl RecoveryLedger {
             metadata,
         };
 
-        if let Ok(json) = serde_json::to_string(&entry) {
-            match OpenOptions::new()
-                .create(true)
-                .append(true)
-                .open(&self.path)
-            {
-                Ok(mut file) => {
-                    if let Err(e) = writeln!(file, "{}", json) {
-                        error!(?e, "RecoveryLedger: Failed to write entry");
-                    }
-                }
-                Err(e) => {
-                    error!(?e, path=?self.path, "RecoveryLedger: Failed to open ledger file");
+        let line = serde_json::to_vec(&entry)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| Ok(encryption::seal(self.cipher.as_deref(), json)?));
+        let line = match line {
+            Ok(l) => l,
+            Err(e) => {
+                error!(?e, "RecoveryLedger: Failed to encode entry");
+                return;
+            }
+        };
+        match OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+        {
+            Ok(mut file) => {
+                if let Err(e) = file.write_all(&line).and_then(|_| file.write_all(b"\n")) {
+                    error!(?e, "RecoveryLedger: Failed to write entry");
                 }
             }
+            Err(e) => {
+                error!(?e, path=?self.path, "RecoveryLedger: Failed to open ledger file");
+            }
+        }
+    }
+
+    /// Read all entries, decrypting them if needed. Fails if an entry is encrypted with a key
+    /// that is not configured.
+    pub fn read(&self) -> anyhow::Result<Vec<LedgerEntry>> {
+        let contents = match fs_err::read_to_string(&self.path) {
+            Ok(c) => c,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+        contents
+            .lines()
+            .filter(|l| !l.is_empty())
+            .map(|l| {
+                let json = encryption::open(self.cipher.as_deref(), l.as_bytes())?;
+                Ok(serde_json::from_slice(&json)?)
+            })
+            .collect()
+    }
+
+    /// Re-encrypt entries written in plaintext or with a previous key, so previous keys can be
+    /// retired.
+    fn rotate(&self) -> anyhow::Result<()> {
+        let Some(cipher) = &self.cipher else {
+            return Ok(());
+        };
+        let contents = match fs_err::read_to_string(&self.path) {
+            Ok(c) => c,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        let stale = |l: &&str| !encryption::is_encrypted(l.as_bytes()) || cipher.needs_rotation(l);
+        if !contents.lines().filter(|l| !l.is_empty()).any(|l| stale(&l)) {
+            return Ok(());
+        }
+        let mut out = String::with_capacity(contents.len());
+        let mut rotated = 0;
+        for line in contents.lines().filter(|l| !l.is_empty()) {
+            if stale(&line) {
+                let json = encryption::open(Some(cipher), line.as_bytes())?;
+                out.push_str(&cipher.encrypt(&json)?);
+                rotated += 1;
+            } else {
+                out.push_str(line);
+            }
+            out.push('\n');
         }
+        let tmp = self.path.with_extension("tmp");
+        fs_err::write(&tmp, out)?;
+        fs_err::rename(&tmp, &self.path)?;
+        info!(path=?self.path, rotated, "RecoveryLedger: Re-encrypted ledger entries");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypted_ledger() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ledger.log");
+        let old = Arc::new(Cipher::new(&[1; 32], &[]).unwrap());
+        RecoveryLedger::new(path.clone(), None).log("a", "register", serde_json::json!({}));
+        RecoveryLedger::new(path.clone(), Some(old.clone())).log("b", "register", serde_json::json!({}));
+        // Existing plaintext entries are encrypted once a key is configured
+        assert!(fs_err::read_to_string(&path).unwrap().lines().all(|l| encryption::is_encrypted(l.as_bytes())));
+
+        let rotated = Arc::new(Cipher::new(&[2; 32], &[vec![1; 32]]).unwrap());
+        let ledger = RecoveryLedger::new(path.clone(), Some(rotated.clone()));
+        let entries = ledger.read().unwrap();
+        assert_eq!(entries.iter().map(|e| e.service.as_str()).collect::<Vec<_>>(), vec!["a", "b"]);
+        assert!(fs_err::read_to_string(&path).unwrap().lines().all(|l| !rotated.needs_rotation(l)));
+
+        // Without the key, entries cannot be read
+        assert!(RecoveryLedger::new(path, None).read().is_err());
     }
 }
@@ -21,19 +21,27 @@ use crate::types::discovery::Identity;
 
 pub mod a2a;
 pub mod app;
+pub mod bench;
+pub mod bridge;
 pub mod cel;
 pub mod client;
 pub mod config;
 pub mod control;
+pub mod encryption;
+pub mod features;
 pub mod http;
 pub mod json;
 pub mod llm;
 pub mod management;
 pub mod ledger;
 pub mod mcp;
+pub mod memory;
+pub mod overload;
 pub mod parse;
 pub mod proxy;
+pub mod secrets;
 pub mod serdes;
+pub mod shutdown;
 pub mod state_manager;
 pub mod store;
 mod telemetry;
@@ -73,6 +81,12 @@ pub struct RawConfig {
 	ca_address: Option<String>,
 	ca_auth_token: Option<String>,
 	xds_address: Option<String>,
+	/// Fallback XDS addresses, tried in order while the control plane at `xdsAddress` is unreachable.
+	#[serde(default)]
+	xds_fallback_addresses: Vec<String>,
+	/// Where to persist the last config received over XDS. If the control plane is unreachable on
+	/// startup, this config is served until it can be reached.
+	xds_snapshot_path: Option<PathBuf>,
 	xds_auth_token: Option<String>,
 	namespace: Option<String>,
 	gateway: Option<String>,
@@ -80,6 +94,13 @@ pub struct RawConfig {
 	service_account: Option<String>,
 	cluster_id: Option<String>,
 	network: Option<String>,
+	/// Serve Gateway API resources read directly from the Kubernetes API, instead of configuration
+	/// from an XDS control plane.
+	kubernetes: Option<control::kubernetes::Config>,
+	/// Serve a signed config file polled from an HTTP(S) URL, instead of a local config file.
+	remote_config: Option<control::remote::Config>,
+	/// Serve routes and backends read from Consul, and the healthy instances of Consul services.
+	consul: Option<control::consul::Config>,
 
 	/// Admin UI address in the format "ip:port"
 	admin_addr: Option<String>,
@@ -87,6 +108,21 @@ pub struct RawConfig {
 	stats_addr: Option<String>,
 	/// Readiness probe server address in the format "ip:port"
 	readiness_addr: Option<String>,
+	/// Origin and CSRF protection for state-changing admin endpoints
+	admin_csrf: Option<management::csrf::AdminCsrf>,
+
+	/// Storage for per-identity quota usage
+	quota: Option<http::quota::StoreConfig>,
+
+	/// Issuer for gateway-minted session tokens
+	token_issuer: Option<http::sessiontoken::RawIssuer>,
+
+	/// Encryption for state persisted to disk, such as the recovery ledger and quota usage
+	encryption: Option<encryption::Config>,
+
+	/// Feature flags for experimental subsystems. These can be overridden at runtime with the
+	/// `/features` admin endpoint.
+	features: Option<features::Features>,
 
 	/// Configuration for stateful session management
 	session: Option<RawSession>,
@@ -97,16 +133,55 @@ pub struct RawConfig {
 	#[serde(default, with = "serde_dur_option")]
 	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
 	connection_min_termination_deadline: Option<Duration>,
+	/// Deadlines of each phase of a graceful shutdown.
+	shutdown: Option<shutdown::RawConfig>,
 
 	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
 	worker_threads: Option<StringOrInt>,
+	/// Number of sockets each bind listens on with SO_REUSEPORT, each with its own accept loop, to
+	/// spread accepting connections across cores. Defaults to 1.
+	acceptors: Option<usize>,
+	/// Accept connections on TCP binds, and copy the connections of TCP routes, with io_uring,
+	/// reducing syscalls under high connection rates. Requires Linux 5.19, and building with the
+	/// `io-uring` feature. Falls back to epoll if io_uring is unavailable at runtime.
+	io_uring: Option<bool>,
+	/// Serve binds from this many independent runtimes, each pinned to a core and with its own
+	/// listener socket and connection pools, instead of a single work-stealing runtime. Defaults to
+	/// the number of cores when `THREADING_MODE` is `thread_per_core`.
+	runtime_shards: Option<usize>,
 
 	tracing: Option<RawTracing>,
 	logging: Option<RawLogging>,
 	metrics: Option<RawMetrics>,
+	/// Security audit log, recording authentication failures, authorization denials, rate limit
+	/// rejections, and policy changes.
+	audit_log: Option<telemetry::audit::Config>,
+	/// Structured access log, recording each request. Replaces the `request` application log line.
+	access_log: Option<telemetry::accesslog::Config>,
+	/// Detailed log of requests exceeding a latency threshold, set per route with a `slowRequestLog`
+	/// policy.
+	slow_request_log: Option<telemetry::slowlog::Config>,
+	/// Log of LLM prompts and completions, set per route with an `llmLog` policy.
+	llm_log: Option<telemetry::llmlog::Config>,
+	/// Token prices used to attribute the cost of LLM requests, reported as metrics and by the
+	/// `/costs` admin endpoint.
+	costs: Option<telemetry::costs::Config>,
+	/// Synthetic requests sent to backends to check their health, reported as metrics and by the
+	/// `/debug/probes` admin endpoint.
+	probes: Option<proxy::prober::Config>,
+	/// Service level objectives per route, reported as error budgets and burn rates by metrics and
+	/// the `/slo` admin endpoint.
+	slos: Option<telemetry::slo::Config>,
 
 	#[serde(default)]
 	backend: BackendConfig,
+	/// Resolution of hostname backends.
+	dns: Option<client::dns::Config>,
+	/// Shed load as memory usage approaches its limit, rather than being OOM-killed.
+	memory: Option<memory::Config>,
+	/// Disable expensive features, and then shed low priority requests, while the data plane is
+	/// short of CPU.
+	overload: Option<overload::Config>,
 
 	#[serde(
 		default,
@@ -151,6 +226,18 @@ pub struct BackendConfig {
 	pool_max_size: Option<usize>,
 }
 
+impl BackendConfig {
+	/// The TCP settings of backends without a `tcp` policy.
+	pub fn tcp(&self) -> types::backend::TCP {
+		types::backend::TCP {
+			keepalives: self.keepalives,
+			connect_timeout: self.connect_timeout,
+			idle_timeout: None,
+			proxy_protocol: None,
+		}
+	}
+}
+
 impl Default for BackendConfig {
 	fn default() -> Self {
 		crate::BackendConfig {
@@ -225,6 +312,12 @@ pub struct RawTracing {
 	client_sampling: Option<StringBoolFloat>,
 	/// OTLP path. Default is /v1/traces
 	path: Option<String>,
+	/// Header formats used to propagate trace context to backends: `traceContext`, `b3`, or
+	/// `b3Multi`. Incoming requests are accepted in any format. Default is `[traceContext]`.
+	propagation: Option<Vec<trc::Propagation>>,
+	/// Only export traces matching these policies, such as errors or slow requests. Spans are
+	/// buffered until each trace completes. If unset, all sampled traces are exported.
+	tail_sampling: Option<telemetry::tailsampling::Config>,
 }
 
 #[apply(schema_de!)]
@@ -233,6 +326,8 @@ pub struct RawLogging {
 	fields: Option<RawLoggingFields>,
 	level: Option<RawLoggingLevel>,
 	format: Option<LoggingFormat>,
+	/// Export logs to an OTLP logs endpoint, in addition to stdout.
+	otlp: Option<telemetry::logexport::Config>,
 }
 
 #[apply(schema_de!)]
@@ -255,6 +350,18 @@ pub struct RawMetrics {
 	#[serde(default)]
 	remove: Vec<String>,
 	fields: Option<RawMetricFields>,
+	/// Override the bucket boundaries of histogram metrics, keyed by metric name (such as
+	/// `request_duration` or `agentgateway_request_duration_seconds`). Names that do not match a
+	/// histogram are rejected.
+	#[serde(default)]
+	histograms: std::collections::HashMap<String, metrics::HistogramBuckets>,
+	/// Additional labels for HTTP request metrics.
+	#[serde(default)]
+	request_labels: Vec<metrics::RequestLabel>,
+	/// Limits on the distinct values of labels derived from requests.
+	cardinality: Option<telemetry::cardinality::Config>,
+	/// Also push metrics to a StatsD or DogStatsD server.
+	statsd: Option<telemetry::statsd::Config>,
 }
 
 #[apply(schema_de!)]
@@ -375,9 +482,15 @@ pub struct Config {
 	pub termination_max_deadline: Duration,
 	#[serde(with = "serde_dur")]
 	pub termination_min_deadline: Duration,
+	pub shutdown: shutdown::Config,
 	/// Specify the number of worker threads the Tokio Runtime will use.
 	pub num_worker_threads: usize,
+	/// Number of sockets, each with its own accept loop, that each bind listens on.
+	pub acceptors: usize,
+	/// Accept connections on TCP binds, and copy the connections of TCP routes, with io_uring.
+	pub io_uring: bool,
 	pub admin_addr: Address,
+	pub admin_csrf: management::csrf::AdminCsrf,
 	pub stats_addr: Address,
 	pub readiness_addr: Address,
 	// For waypoint identification
@@ -388,10 +501,26 @@ pub struct Config {
 	pub ca: Option<caclient::Config>,
 	pub tracing: trc::Config,
 	pub logging: crate::telemetry::log::Config,
+	pub audit_log: Option<crate::telemetry::audit::Config>,
+	pub access_log: Option<crate::telemetry::accesslog::Config>,
+	pub slow_request_log: Option<crate::telemetry::slowlog::Config>,
+	pub llm_log: Option<crate::telemetry::llmlog::Config>,
+	pub costs: Option<crate::telemetry::costs::Config>,
+	pub probes: Option<crate::proxy::prober::Config>,
+	pub slos: Option<crate::telemetry::slo::Config>,
+	pub statsd: Option<crate::telemetry::statsd::Config>,
 	pub dns: client::Config,
+	pub memory: Option<memory::Config>,
+	pub overload: Option<overload::Config>,
 	pub proxy_metadata: ProxyMetadata,
 	pub threading_mode: ThreadingMode,
+	/// Number of runtimes binds are served from in the ThreadPerCore threading mode.
+	pub runtime_shards: usize,
 	pub session_encoder: http::sessionpersistence::Encoder,
+	pub quota: http::quota::StoreConfig,
+	pub token_issuer: Option<Arc<http::sessiontoken::Issuer>>,
+	pub encryption: Option<encryption::Config>,
+	pub features: features::Features,
 	/// Handle for tasks/spans emitted on the admin runtime.
 	#[serde(skip)]
 	pub admin_runtime_handle: Option<tokio::runtime::Handle>,
@@ -430,12 +559,20 @@ pub enum ThreadingMode {
 pub struct XDSConfig {
 	/// XDS address to use. If unset, XDS will not be used.
 	pub address: Option<String>,
+	/// XDS addresses to fail over to when the control plane at `address` is unreachable.
+	pub fallback_addresses: Vec<String>,
+	/// Where to persist the last-known-good XDS config, to serve if no control plane is reachable on
+	/// startup.
+	pub snapshot_path: Option<PathBuf>,
 	pub auth: AuthSource,
 	pub ca_cert: RootCert,
 	pub namespace: Strng,
 	pub gateway: Strng,
 
 	pub local_config: Option<ConfigSource>,
+	pub kubernetes: Option<control::kubernetes::Config>,
+	pub remote_config: Option<control::remote::Config>,
+	pub consul: Option<control::consul::Config>,
 }
 
 #[derive(Clone, Debug)]
@@ -489,6 +626,7 @@ pub struct ProxyInputs {
 
 	mcp_state: mcp::App,
 	ca: Option<Arc<CaClient>>,
+	quotas: Arc<http::quota::QuotaStore>,
 }
 
 #[derive(Debug, Clone, Copy, serde::Serialize)]
@@ -0,0 +1,753 @@
+use std::collections::HashMap;
+
+use ::http::{Method, StatusCode, header};
+use agent_core::strng;
+use bytes::Bytes;
+use itertools::Itertools;
+use macro_rules_attribute::apply;
+use parking_lot::Mutex;
+
+use crate::http::{Body, Request, Response};
+use crate::json;
+use crate::llm::policy::de_from_local_backend_policy;
+use crate::proxy::ProxyError;
+use crate::proxy::httpproxy::PolicyClient;
+use crate::types::agent::{BackendPolicy, ResourceName, SimpleBackend, Target};
+use crate::types::local::LocalBackendPolicies;
+use crate::*;
+
+#[cfg(test)]
+#[path = "batch_tests.rs"]
+mod tests;
+
+/// The largest batch result file that is read.
+const MAX_RESULTS_BYTES: usize = 256 * 1024 * 1024;
+
+/// Collect requests marked as batchable and submit them together to a provider's batch API, which
+/// processes them asynchronously at a lower price. Batched requests are answered straight away with
+/// `202 Accepted` and an ID; their results are served at `statusPath`, and posted to `webhook` if
+/// set, once the provider completes the batch.
+#[apply(schema!)]
+pub struct Policy {
+	pub provider: Provider,
+	/// The header marking a request as batchable, when set to `true`. Other requests are sent as
+	/// usual. Defaults to `x-gateway-batch`.
+	#[serde(default = "default_header")]
+	pub header: Strng,
+	/// Requests are submitted once this many are collected. Defaults to 1000.
+	#[serde(default = "default_max_requests")]
+	pub max_requests: usize,
+	/// Requests are submitted once the oldest has waited this long. Defaults to 1m.
+	#[serde(default = "default_max_wait", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub max_wait: Duration,
+	/// How often the provider is asked for the status of submitted batches. Defaults to 1m.
+	#[serde(default = "default_poll_interval", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub poll_interval: Duration,
+	/// How long results are kept once a batch completes. Defaults to 24h.
+	#[serde(default = "default_retention", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub retention: Duration,
+	/// The path results are served at, followed by the request's ID. Defaults to
+	/// `/v1/gateway/batches/`.
+	#[serde(default = "default_status_path")]
+	pub status_path: Strng,
+	/// Post each result to a URL once its batch completes.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub webhook: Option<Webhook>,
+	#[serde(skip)]
+	batches: Arc<Batches>,
+}
+
+#[apply(schema!)]
+pub enum Provider {
+	/// Submit chat completions requests to the OpenAI Batch API.
+	OpenAI(ProviderConfig),
+	/// Submit messages requests to the Anthropic Message Batches API. The Anthropic API expects the
+	/// key in the `x-api-key` header, which can be set with a `requestHeaderModifier` policy.
+	Anthropic(ProviderConfig),
+}
+
+#[apply(schema!)]
+pub struct ProviderConfig {
+	#[serde(deserialize_with = "de_from_local_backend_policy")]
+	#[cfg_attr(feature = "schema", schemars(with = "LocalBackendPolicies"))]
+	pub policies: Vec<BackendPolicy>,
+}
+
+#[apply(schema!)]
+pub struct Webhook {
+	/// The URL to POST results to. Each result is sent as the JSON served at `statusPath`.
+	pub url: Strng,
+	#[serde(default, deserialize_with = "de_from_local_backend_policy")]
+	#[cfg_attr(feature = "schema", schemars(with = "LocalBackendPolicies"))]
+	pub policies: Vec<BackendPolicy>,
+}
+
+fn default_header() -> Strng {
+	strng::literal!("x-gateway-batch")
+}
+
+fn default_max_requests() -> usize {
+	1000
+}
+
+fn default_max_wait() -> Duration {
+	Duration::from_secs(60)
+}
+
+fn default_poll_interval() -> Duration {
+	Duration::from_secs(60)
+}
+
+fn default_retention() -> Duration {
+	Duration::from_secs(24 * 3600)
+}
+
+fn default_status_path() -> Strng {
+	strng::literal!("/v1/gateway/batches/")
+}
+
+/// The state of a batched request, as served at the status path.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemStatus {
+	pub id: Strng,
+	pub status: State,
+	/// The provider's ID of the batch the request was submitted in.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub batch_id: Option<Strng>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub response: Option<ItemResponse>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum State {
+	/// Waiting to be submitted with the next batch.
+	Queued,
+	/// Submitted to the provider, which has not completed the batch yet.
+	Submitted,
+	/// The provider returned a response, which may itself be an error.
+	Completed,
+	/// The request could not be submitted, or the provider returned no response for it.
+	Failed,
+}
+
+/// The provider's response to a batched request.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemResponse {
+	pub status_code: u16,
+	pub body: serde_json::Value,
+}
+
+/// The outcome of a request in a completed batch.
+#[derive(Debug, Clone, PartialEq)]
+enum Outcome {
+	Response(ItemResponse),
+	Error(String),
+}
+
+#[derive(Debug)]
+struct Queued {
+	id: Strng,
+	body: serde_json::Value,
+}
+
+/// What to do after a request is queued.
+#[derive(Debug)]
+enum Enqueued {
+	/// The queue is full, so these requests should be submitted now.
+	Submit(Vec<Queued>),
+	/// The request started a new queue, which should be submitted once `max_wait` passes.
+	StartTimer(u64),
+	/// The request joined a queue that is already waiting.
+	Wait,
+}
+
+struct Item {
+	status: ItemStatus,
+	expires: Option<Instant>,
+}
+
+/// The queued and submitted requests of a single batch policy.
+#[derive(Default)]
+pub struct Batches {
+	state: Mutex<BatchesState>,
+}
+
+#[derive(Default)]
+struct BatchesState {
+	/// Requests waiting to be submitted, oldest first.
+	queue: Vec<Queued>,
+	/// Incremented each time the queue is submitted, so the timer of an earlier queue does nothing.
+	generation: u64,
+	items: HashMap<Strng, Item>,
+}
+
+impl std::fmt::Debug for Batches {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let state = self.state.lock();
+		f.debug_struct("Batches")
+			.field("queue", &state.queue.len())
+			.field("items", &state.items.len())
+			.finish()
+	}
+}
+
+impl Batches {
+	fn enqueue(&self, id: Strng, body: serde_json::Value, max_requests: usize) -> Enqueued {
+		let mut state = self.state.lock();
+		state.items.insert(
+			id.clone(),
+			Item {
+				status: ItemStatus {
+					id: id.clone(),
+					status: State::Queued,
+					batch_id: None,
+					response: None,
+					error: None,
+				},
+				expires: None,
+			},
+		);
+		state.queue.push(Queued { id, body });
+		if state.queue.len() >= max_requests {
+			state.generation += 1;
+			Enqueued::Submit(std::mem::take(&mut state.queue))
+		} else if state.queue.len() == 1 {
+			Enqueued::StartTimer(state.generation)
+		} else {
+			Enqueued::Wait
+		}
+	}
+
+	/// Take the queue for submission, if it has not been submitted since `generation`.
+	fn take(&self, generation: u64) -> Option<Vec<Queued>> {
+		let mut state = self.state.lock();
+		if state.generation != generation || state.queue.is_empty() {
+			return None;
+		}
+		state.generation += 1;
+		Some(std::mem::take(&mut state.queue))
+	}
+
+	fn get(&self, id: &str) -> Option<ItemStatus> {
+		let mut state = self.state.lock();
+		let now = Instant::now();
+		state.items.retain(|_, i| i.expires.is_none_or(|e| e > now));
+		state.items.get(id).map(|i| i.status.clone())
+	}
+
+	fn submitted(&self, ids: &[Strng], batch_id: &Strng) {
+		let mut state = self.state.lock();
+		for id in ids {
+			if let Some(item) = state.items.get_mut(id) {
+				item.status.status = State::Submitted;
+				item.status.batch_id = Some(batch_id.clone());
+			}
+		}
+	}
+
+	/// Record the outcome of a request, returning its final status.
+	fn finish(&self, id: &Strng, outcome: Outcome, retention: Duration) -> Option<ItemStatus> {
+		let mut state = self.state.lock();
+		let item = state.items.get_mut(id)?;
+		match outcome {
+			Outcome::Response(r) => {
+				item.status.status = State::Completed;
+				item.status.response = Some(r);
+			},
+			Outcome::Error(e) => {
+				item.status.status = State::Failed;
+				item.status.error = Some(e);
+			},
+		}
+		item.expires = Some(Instant::now() + retention);
+		Some(item.status.clone())
+	}
+}
+
+impl Policy {
+	/// Queue a batchable request, or serve the status of a batched one. Returns None for any other
+	/// request, which should be sent as usual.
+	pub async fn apply(
+		&self,
+		client: &PolicyClient,
+		req: &mut Request,
+	) -> Result<Option<Response>, ProxyError> {
+		if req.method() == Method::GET
+			&& let Some(id) = req.uri().path().strip_prefix(self.status_path.as_str())
+		{
+			return Ok(Some(self.status_response(id)));
+		}
+		if !req
+			.headers()
+			.get(self.header.as_str())
+			.is_some_and(|v| v.as_bytes() == b"true")
+		{
+			return Ok(None);
+		}
+		let body = crate::http::inspect_body(req)
+			.await
+			.map_err(ProxyError::Processing)?;
+		let mut body: serde_json::Value =
+			serde_json::from_slice(&body).map_err(|_| ProxyError::InvalidRequest)?;
+		let Some(obj) = body.as_object_mut() else {
+			return Err(ProxyError::InvalidRequest);
+		};
+		// Batch APIs do not stream.
+		obj.remove("stream");
+		obj.remove("stream_options");
+
+		let id = strng::new(uuid::Uuid::new_v4().to_string());
+		match self
+			.batches
+			.enqueue(id.clone(), body, self.max_requests.max(1))
+		{
+			Enqueued::Submit(batch) => self.spawn_submit(client.clone(), batch),
+			Enqueued::StartTimer(generation) => self.spawn_timer(client.clone(), generation),
+			Enqueued::Wait => {},
+		}
+		debug!(%id, "queued batch request");
+		Ok(Some(self.queued_response(&id)))
+	}
+
+	fn queued_response(&self, id: &Strng) -> Response {
+		let body = serde_json::json!({"id": id, "status": State::Queued});
+		::http::Response::builder()
+			.status(StatusCode::ACCEPTED)
+			.header(header::CONTENT_TYPE, "application/json")
+			.header(header::LOCATION, format!("{}{id}", self.status_path))
+			.body(Body::from(body.to_string()))
+			.expect("static response should succeed")
+	}
+
+	fn status_response(&self, id: &str) -> Response {
+		let (status, body) = match self.batches.get(id) {
+			Some(s) => (
+				StatusCode::OK,
+				serde_json::to_vec(&s).expect("status should serialize"),
+			),
+			None => (
+				StatusCode::NOT_FOUND,
+				br#"{"error":"batch request not found"}"#.to_vec(),
+			),
+		};
+		::http::Response::builder()
+			.status(status)
+			.header(header::CONTENT_TYPE, "application/json")
+			.body(Body::from(body))
+			.expect("static response should succeed")
+	}
+
+	fn spawn_submit(&self, client: PolicyClient, batch: Vec<Queued>) {
+		let policy = self.clone();
+		tokio::spawn(async move { policy.submit(&client, batch).await });
+	}
+
+	fn spawn_timer(&self, client: PolicyClient, generation: u64) {
+		let policy = self.clone();
+		tokio::spawn(async move {
+			tokio::time::sleep(policy.max_wait).await;
+			if let Some(batch) = policy.batches.take(generation) {
+				policy.submit(&client, batch).await;
+			}
+		});
+	}
+
+	/// Submit a batch and wait for it to complete, recording the outcome of each request.
+	async fn submit(&self, client: &PolicyClient, batch: Vec<Queued>) {
+		let ids = batch.iter().map(|q| q.id.clone()).collect_vec();
+		let mut outcomes = match self.run(client, &ids, batch).await {
+			Ok(outcomes) => outcomes,
+			Err(e) => {
+				warn!("batch failed: {e}");
+				HashMap::new()
+			},
+		};
+		for id in &ids {
+			let outcome = outcomes
+				.remove(id)
+				.unwrap_or_else(|| Outcome::Error("the provider returned no result".to_string()));
+			let Some(status) = self.batches.finish(id, outcome, self.retention) else {
+				continue;
+			};
+			if let Some(webhook) = &self.webhook
+				&& let Err(e) = webhook.send(client, &status).await
+			{
+				warn!(%id, "failed to send batch result to webhook: {e}");
+			}
+		}
+	}
+
+	async fn run(
+		&self,
+		client: &PolicyClient,
+		ids: &[Strng],
+		batch: Vec<Queued>,
+	) -> anyhow::Result<HashMap<Strng, Outcome>> {
+		let batch_id = self.provider.create(client, batch).await?;
+		debug!(%batch_id, requests=ids.len(), "submitted batch");
+		self.batches.submitted(ids, &batch_id);
+		loop {
+			tokio::time::sleep(self.poll_interval).await;
+			match self.provider.poll(client, &batch_id).await {
+				Ok(Some(outcomes)) => return Ok(outcomes),
+				Ok(None) => {},
+				Err(e) => warn!(%batch_id, "failed to poll batch: {e}"),
+			}
+		}
+	}
+}
+
+impl Provider {
+	fn host(&self) -> &'static str {
+		match self {
+			Provider::OpenAI(_) => "api.openai.com",
+			Provider::Anthropic(_) => "api.anthropic.com",
+		}
+	}
+
+	/// Submit a batch, returning the provider's ID for it.
+	async fn create(&self, client: &PolicyClient, batch: Vec<Queued>) -> anyhow::Result<Strng> {
+		#[derive(serde::Deserialize)]
+		struct Created {
+			id: Strng,
+		}
+
+		match self {
+			Provider::OpenAI(_) => {
+				let boundary = uuid::Uuid::new_v4().simple().to_string();
+				let resp = self
+					.call(
+						client,
+						Method::POST,
+						"/v1/files",
+						Some(format!("multipart/form-data; boundary={boundary}")),
+						multipart(&boundary, &openai_input(batch)),
+					)
+					.await?;
+				let file: Created = json::from_response_body(resp).await?;
+				let resp = self
+					.call(
+						client,
+						Method::POST,
+						"/v1/batches",
+						Some("application/json".to_string()),
+						serde_json::to_vec(&serde_json::json!({
+							"input_file_id": file.id,
+							"endpoint": "/v1/chat/completions",
+							"completion_window": "24h",
+						}))?,
+					)
+					.await?;
+				let created: Created = json::from_response_body(resp).await?;
+				Ok(created.id)
+			},
+			Provider::Anthropic(_) => {
+				let resp = self
+					.call(
+						client,
+						Method::POST,
+						"/v1/messages/batches",
+						Some("application/json".to_string()),
+						serde_json::to_vec(&anthropic_input(batch))?,
+					)
+					.await?;
+				let created: Created = json::from_response_body(resp).await?;
+				Ok(created.id)
+			},
+		}
+	}
+
+	/// Check on a submitted batch, returning the outcome of its requests once it has ended.
+	async fn poll(
+		&self,
+		client: &PolicyClient,
+		batch_id: &str,
+	) -> anyhow::Result<Option<HashMap<Strng, Outcome>>> {
+		match self {
+			Provider::OpenAI(_) => {
+				#[derive(serde::Deserialize)]
+				struct Batch {
+					status: String,
+					output_file_id: Option<String>,
+					error_file_id: Option<String>,
+				}
+				let resp = self
+					.call(
+						client,
+						Method::GET,
+						&format!("/v1/batches/{batch_id}"),
+						None,
+						vec![],
+					)
+					.await?;
+				let batch: Batch = json::from_response_body(resp).await?;
+				if !matches!(
+					batch.status.as_str(),
+					"completed" | "failed" | "expired" | "cancelled"
+				) {
+					return Ok(None);
+				}
+				let mut outcomes = HashMap::new();
+				for file in [batch.output_file_id, batch.error_file_id]
+					.into_iter()
+					.flatten()
+				{
+					let resp = self
+						.call(
+							client,
+							Method::GET,
+							&format!("/v1/files/{file}/content"),
+							None,
+							vec![],
+						)
+						.await?;
+					outcomes.extend(parse_openai_results(&read_results(resp).await?));
+				}
+				Ok(Some(outcomes))
+			},
+			Provider::Anthropic(_) => {
+				#[derive(serde::Deserialize)]
+				struct Batch {
+					processing_status: String,
+				}
+				let resp = self
+					.call(
+						client,
+						Method::GET,
+						&format!("/v1/messages/batches/{batch_id}"),
+						None,
+						vec![],
+					)
+					.await?;
+				let batch: Batch = json::from_response_body(resp).await?;
+				if batch.processing_status != "ended" {
+					return Ok(None);
+				}
+				let resp = self
+					.call(
+						client,
+						Method::GET,
+						&format!("/v1/messages/batches/{batch_id}/results"),
+						None,
+						vec![],
+					)
+					.await?;
+				Ok(Some(parse_anthropic_results(&read_results(resp).await?)))
+			},
+		}
+	}
+
+	async fn call(
+		&self,
+		client: &PolicyClient,
+		method: Method,
+		path: &str,
+		content_type: Option<String>,
+		body: Vec<u8>,
+	) -> anyhow::Result<Response> {
+		let (Provider::OpenAI(config) | Provider::Anthropic(config)) = self;
+		let host = self.host();
+		let mut pols = vec![BackendPolicy::BackendTLS(
+			crate::http::backendtls::SYSTEM_TRUST.clone(),
+		)];
+		pols.extend(config.policies.iter().cloned());
+		let mut rb = ::http::Request::builder()
+			.uri(format!("https://{host}{path}"))
+			.method(method);
+		if let Some(ct) = content_type {
+			rb = rb.header(header::CONTENT_TYPE, ct);
+		}
+		if let Provider::Anthropic(_) = self {
+			// https://docs.anthropic.com/en/api/versioning
+			rb = rb.header("anthropic-version", "2023-06-01");
+		}
+		let req = rb.body(Body::from(body))?;
+		let be = SimpleBackend::Opaque(
+			ResourceName::new(strng::literal!("_llm-batch"), strng::literal!("")),
+			Target::Hostname(strng::new(host), 443),
+		);
+		let resp = client.call_with_explicit_policies(req, be, pols).await?;
+		let status = resp.status();
+		if !status.is_success() {
+			anyhow::bail!("batch request to {path} failed with status {status}");
+		}
+		Ok(resp)
+	}
+}
+
+impl Webhook {
+	async fn send(&self, client: &PolicyClient, status: &ItemStatus) -> anyhow::Result<()> {
+		let uri: ::http::Uri = self.url.parse()?;
+		let host = uri
+			.host()
+			.ok_or_else(|| anyhow::anyhow!("webhook url has no host"))?;
+		let tls = uri.scheme() == Some(&::http::uri::Scheme::HTTPS);
+		let port = uri.port_u16().unwrap_or(if tls { 443 } else { 80 });
+		let mut pols = vec![];
+		if tls {
+			pols.push(BackendPolicy::BackendTLS(
+				crate::http::backendtls::SYSTEM_TRUST.clone(),
+			));
+		}
+		pols.extend(self.policies.iter().cloned());
+		let be = SimpleBackend::Opaque(
+			ResourceName::new(strng::literal!("_llm-batch-webhook"), strng::literal!("")),
+			Target::Hostname(strng::new(host), port),
+		);
+		let req = ::http::Request::builder()
+			.uri(uri.clone())
+			.method(Method::POST)
+			.header(header::CONTENT_TYPE, "application/json")
+			.body(Body::from(serde_json::to_vec(status)?))?;
+		let resp = client.call_with_explicit_policies(req, be, pols).await?;
+		let status = resp.status();
+		if !status.is_success() {
+			anyhow::bail!("webhook failed with status {status}");
+		}
+		Ok(())
+	}
+}
+
+async fn read_results(resp: Response) -> anyhow::Result<Bytes> {
+	Ok(crate::http::read_body_with_limit(resp.into_body(), MAX_RESULTS_BYTES).await?)
+}
+
+/// The JSONL input file of an OpenAI batch.
+fn openai_input(batch: Vec<Queued>) -> Vec<u8> {
+	let mut out = Vec::new();
+	for q in batch {
+		let line = serde_json::json!({
+			"custom_id": q.id,
+			"method": "POST",
+			"url": "/v1/chat/completions",
+			"body": q.body,
+		});
+		out.extend_from_slice(line.to_string().as_bytes());
+		out.push(b'\n');
+	}
+	out
+}
+
+/// A multipart form uploading `file` as an OpenAI batch input file.
+fn multipart(boundary: &str, file: &[u8]) -> Vec<u8> {
+	let mut out = format!(
+		"--{boundary}\r\nContent-Disposition: form-data; name=\"purpose\"\r\n\r\nbatch\r\n\
+		--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"batch.jsonl\"\r\n\
+		Content-Type: application/jsonl\r\n\r\n"
+	)
+	.into_bytes();
+	out.extend_from_slice(file);
+	out.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+	out
+}
+
+/// The body creating an Anthropic message batch.
+fn anthropic_input(batch: Vec<Queued>) -> serde_json::Value {
+	let requests = batch
+		.into_iter()
+		.map(|q| serde_json::json!({"custom_id": q.id, "params": q.body}))
+		.collect_vec();
+	serde_json::json!({ "requests": requests })
+}
+
+/// The outcomes in an OpenAI batch output or error file.
+fn parse_openai_results(file: &[u8]) -> HashMap<Strng, Outcome> {
+	#[derive(serde::Deserialize)]
+	struct Line {
+		custom_id: Strng,
+		response: Option<LineResponse>,
+		error: Option<LineError>,
+	}
+	#[derive(serde::Deserialize)]
+	struct LineResponse {
+		status_code: u16,
+		body: serde_json::Value,
+	}
+	#[derive(serde::Deserialize)]
+	struct LineError {
+		message: String,
+	}
+
+	parse_lines(file, |l: Line| {
+		let outcome = match (l.response, l.error) {
+			(Some(r), _) => Outcome::Response(ItemResponse {
+				status_code: r.status_code,
+				body: r.body,
+			}),
+			(None, Some(e)) => Outcome::Error(e.message),
+			(None, None) => Outcome::Error("the provider returned no result".to_string()),
+		};
+		(l.custom_id, outcome)
+	})
+}
+
+/// The outcomes in an Anthropic message batch results file.
+fn parse_anthropic_results(file: &[u8]) -> HashMap<Strng, Outcome> {
+	#[derive(serde::Deserialize)]
+	struct Line {
+		custom_id: Strng,
+		result: LineResult,
+	}
+	#[derive(serde::Deserialize)]
+	struct LineResult {
+		#[serde(rename = "type")]
+		kind: String,
+		message: Option<serde_json::Value>,
+		error: Option<serde_json::Value>,
+	}
+
+	parse_lines(file, |l: Line| {
+		let outcome = match (l.result.kind.as_str(), l.result.message, l.result.error) {
+			("succeeded", Some(message), _) => Outcome::Response(ItemResponse {
+				status_code: 200,
+				body: message,
+			}),
+			// Errors are returned as they would be for a single request.
+			("errored", _, Some(error)) => Outcome::Response(ItemResponse {
+				status_code: anthropic_error_status(&error),
+				body: error,
+			}),
+			(kind, _, _) => Outcome::Error(format!("request {kind}")),
+		};
+		(l.custom_id, outcome)
+	})
+}
+
+/// The HTTP status Anthropic returns for an error type.
+fn anthropic_error_status(error: &serde_json::Value) -> u16 {
+	match json::traverse(error, &["error", "type"]).and_then(|t| t.as_str()) {
+		Some("invalid_request_error") => 400,
+		Some("authentication_error") => 401,
+		Some("permission_error") => 403,
+		Some("not_found_error") => 404,
+		Some("request_too_large") => 413,
+		Some("rate_limit_error") => 429,
+		Some("overloaded_error") => 529,
+		_ => 500,
+	}
+}
+
+fn parse_lines<L: serde::de::DeserializeOwned>(
+	file: &[u8],
+	f: impl Fn(L) -> (Strng, Outcome),
+) -> HashMap<Strng, Outcome> {
+	file
+		.split(|b| *b == b'\n')
+		.filter(|l| !l.iter().all(u8::is_ascii_whitespace))
+		.filter_map(|l| match serde_json::from_slice::<L>(l) {
+			Ok(l) => Some(f(l)),
+			Err(e) => {
+				warn!("invalid batch result line: {e}");
+				None
+			},
+		})
+		.collect()
+}
@@ -0,0 +1,183 @@
+use super::*;
+
+fn policy() -> Policy {
+	serde_json::from_value(serde_json::json!({
+		"provider": {"openAI": {"policies": {}}},
+	}))
+	.unwrap()
+}
+
+fn queued(id: &str) -> Queued {
+	Queued {
+		id: strng::new(id),
+		body: serde_json::json!({"model": "gpt-4o-mini", "messages": [{"role": "user", "content": id}]}),
+	}
+}
+
+#[test]
+fn test_config() {
+	let p = policy();
+	assert_eq!(p.header, "x-gateway-batch");
+	assert_eq!(p.max_requests, 1000);
+	assert_eq!(p.max_wait, Duration::from_secs(60));
+	assert_eq!(p.poll_interval, Duration::from_secs(60));
+	assert_eq!(p.retention, Duration::from_secs(24 * 3600));
+	assert_eq!(p.status_path, "/v1/gateway/batches/");
+	assert!(p.webhook.is_none());
+}
+
+#[test]
+fn test_enqueue() {
+	let b = Batches::default();
+	let body = serde_json::json!({});
+	// The first request starts the timer, and the queue is submitted once full
+	assert!(matches!(
+		b.enqueue(strng::new("a"), body.clone(), 3),
+		Enqueued::StartTimer(0)
+	));
+	assert!(matches!(
+		b.enqueue(strng::new("b"), body.clone(), 3),
+		Enqueued::Wait
+	));
+	let Enqueued::Submit(batch) = b.enqueue(strng::new("c"), body.clone(), 3) else {
+		panic!("expected a full queue to be submitted");
+	};
+	assert_eq!(
+		batch.iter().map(|q| q.id.as_str()).collect_vec(),
+		["a", "b", "c"]
+	);
+	// The timer of the submitted queue finds nothing to do
+	assert!(b.take(0).is_none());
+
+	assert!(matches!(
+		b.enqueue(strng::new("d"), body.clone(), 3),
+		Enqueued::StartTimer(1)
+	));
+	assert_eq!(b.take(1).unwrap().len(), 1);
+	assert!(b.take(1).is_none());
+	assert_eq!(b.get("d").unwrap().status, State::Queued);
+}
+
+#[test]
+fn test_item_lifecycle() {
+	let b = Batches::default();
+	b.enqueue(strng::new("a"), serde_json::json!({}), 10);
+	b.enqueue(strng::new("b"), serde_json::json!({}), 10);
+	b.submitted(&[strng::new("a"), strng::new("b")], &strng::new("batch_1"));
+	let a = b.get("a").unwrap();
+	assert_eq!(a.status, State::Submitted);
+	assert_eq!(a.batch_id.as_deref(), Some("batch_1"));
+
+	let response = ItemResponse {
+		status_code: 200,
+		body: serde_json::json!({"id": "chatcmpl-1"}),
+	};
+	let done = b
+		.finish(
+			&strng::new("a"),
+			Outcome::Response(response.clone()),
+			Duration::from_secs(60),
+		)
+		.unwrap();
+	assert_eq!(done.status, State::Completed);
+	assert_eq!(done.response, Some(response));
+	assert_eq!(
+		serde_json::to_value(&done).unwrap(),
+		serde_json::json!({
+			"id": "a",
+			"status": "completed",
+			"batchId": "batch_1",
+			"response": {"statusCode": 200, "body": {"id": "chatcmpl-1"}},
+		})
+	);
+
+	// Results are dropped once retention passes
+	b.finish(
+		&strng::new("b"),
+		Outcome::Error("request expired".to_string()),
+		Duration::ZERO,
+	);
+	assert!(b.get("b").is_none());
+	assert!(b.get("missing").is_none());
+}
+
+#[test]
+fn test_openai_input() {
+	let input = openai_input(vec![queued("a"), queued("b")]);
+	let lines = input
+		.split(|b| *b == b'\n')
+		.filter(|l| !l.is_empty())
+		.map(|l| serde_json::from_slice::<serde_json::Value>(l).unwrap())
+		.collect_vec();
+	assert_eq!(lines.len(), 2);
+	assert_eq!(
+		lines[0],
+		serde_json::json!({
+			"custom_id": "a",
+			"method": "POST",
+			"url": "/v1/chat/completions",
+			"body": queued("a").body,
+		})
+	);
+
+	let form = String::from_utf8(multipart("xyz", b"{}\n")).unwrap();
+	assert_eq!(
+		form,
+		"--xyz\r\nContent-Disposition: form-data; name=\"purpose\"\r\n\r\nbatch\r\n\
+		--xyz\r\nContent-Disposition: form-data; name=\"file\"; filename=\"batch.jsonl\"\r\n\
+		Content-Type: application/jsonl\r\n\r\n{}\n\r\n--xyz--\r\n"
+	);
+}
+
+#[test]
+fn test_anthropic_input() {
+	assert_eq!(
+		anthropic_input(vec![queued("a")]),
+		serde_json::json!({"requests": [{"custom_id": "a", "params": queued("a").body}]})
+	);
+}
+
+#[test]
+fn test_parse_openai_results() {
+	let file = br#"{"id":"r1","custom_id":"a","response":{"status_code":200,"request_id":"x","body":{"id":"chatcmpl-1"}},"error":null}
+{"id":"r2","custom_id":"b","response":null,"error":{"code":"batch_expired","message":"This request could not be executed before the completion window expired."}}
+
+not json
+"#;
+	let results = parse_openai_results(file);
+	assert_eq!(results.len(), 2);
+	assert_eq!(
+		results["a"],
+		Outcome::Response(ItemResponse {
+			status_code: 200,
+			body: serde_json::json!({"id": "chatcmpl-1"}),
+		})
+	);
+	assert_eq!(
+		results["b"],
+		Outcome::Error(
+			"This request could not be executed before the completion window expired.".to_string()
+		)
+	);
+}
+
+#[test]
+fn test_parse_anthropic_results() {
+	let file = br#"{"custom_id":"a","result":{"type":"succeeded","message":{"id":"msg_1","type":"message"}}}
+{"custom_id":"b","result":{"type":"errored","error":{"type":"error","error":{"type":"invalid_request_error","message":"bad"}}}}
+{"custom_id":"c","result":{"type":"expired"}}
+"#;
+	let results = parse_anthropic_results(file);
+	assert_eq!(
+		results["a"],
+		Outcome::Response(ItemResponse {
+			status_code: 200,
+			body: serde_json::json!({"id": "msg_1", "type": "message"}),
+		})
+	);
+	let Outcome::Response(b) = &results["b"] else {
+		panic!("expected an error response");
+	};
+	assert_eq!(b.status_code, 400);
+	assert_eq!(results["c"], Outcome::Error("request expired".to_string()));
+}
@@ -10,13 +10,21 @@ use crate::telemetry::log::AsyncLog;
 use crate::{llm, parse};
 
 pub mod from_messages {
+	use std::time::Instant;
+
+	use agent_core::strng;
+	use bytes::Bytes;
 	use itertools::Itertools;
 	use messages::{ContentBlock, ThinkingInput, ToolResultContent, ToolResultContentPart};
+	use tracing::debug;
 	use types::completions::typed as completions;
 	use types::messages::typed as messages;
 
-	use crate::json;
-	use crate::llm::{AIError, types};
+	use crate::http::Body;
+	use crate::llm::types::ResponseType;
+	use crate::llm::{AIError, LLMInfo, types};
+	use crate::telemetry::log::AsyncLog;
+	use crate::{json, parse};
 
 	/// translate an Anthropic messages to an OpenAI completions request
 	pub fn translate(req: &types::messages::Request) -> Result<Vec<u8>, AIError> {
@@ -254,11 +262,332 @@ pub mod from_messages {
 			max_tokens: None,
 			service_tier: None,
 			web_search_options: None,
-			stream_options: None,
+			// Usage is only reported at the end of a stream if requested; we need it for the final
+			// message_delta event.
+			stream_options: if stream {
+				serde_json::from_value(serde_json::json!({"include_usage": true})).ok()
+			} else {
+				None
+			},
 			store: None,
 			reasoning_effort: None,
 		}
 	}
+	/// translate an OpenAI completions response to an Anthropic messages response
+	pub fn translate_response(bytes: &Bytes) -> Result<Box<dyn ResponseType>, AIError> {
+		let resp =
+			serde_json::from_slice::<completions::Response>(bytes).map_err(AIError::ResponseParsing)?;
+		let anthropic = translate_response_internal(resp);
+		let passthrough = json::convert::<_, types::messages::Response>(&anthropic)
+			.map_err(AIError::ResponseParsing)?;
+		Ok(Box::new(passthrough))
+	}
+
+	fn translate_response_internal(resp: completions::Response) -> messages::MessagesResponse {
+		let mut content = Vec::new();
+		let mut stop_reason = None;
+		// Anthropic has no notion of multiple choices, so only the first is used.
+		if let Some(choice) = resp.choices.into_iter().next() {
+			let message = choice.message;
+			if let Some(thinking) = message.reasoning_content {
+				content.push(ContentBlock::Thinking {
+					thinking,
+					signature: String::new(),
+				});
+			}
+			if let Some(text) = message.content {
+				content.push(ContentBlock::Text(messages::ContentTextBlock {
+					text,
+					citations: None,
+					cache_control: None,
+				}));
+			}
+			for call in message.tool_calls.into_iter().flatten() {
+				let completions::MessageToolCalls::Function(call) = call else {
+					// Custom tools have no Anthropic equivalent
+					continue;
+				};
+				content.push(ContentBlock::ToolUse {
+					id: call.id,
+					name: call.function.name,
+					input: serde_json::from_str(&call.function.arguments)
+						.unwrap_or_else(|_| serde_json::json!({})),
+					cache_control: None,
+				});
+			}
+			stop_reason = choice.finish_reason.map(translate_finish_reason);
+		}
+		let usage = resp.usage.map(translate_usage).unwrap_or(messages::Usage {
+			input_tokens: 0,
+			output_tokens: 0,
+			cache_creation_input_tokens: None,
+			cache_read_input_tokens: None,
+		});
+		messages::MessagesResponse {
+			id: resp.id,
+			r#type: "message".to_string(),
+			role: messages::Role::Assistant,
+			content,
+			model: resp.model,
+			stop_reason,
+			stop_sequence: None,
+			usage,
+		}
+	}
+
+	fn translate_finish_reason(reason: completions::FinishReason) -> messages::StopReason {
+		match reason {
+			completions::FinishReason::Stop => messages::StopReason::EndTurn,
+			completions::FinishReason::Length => messages::StopReason::MaxTokens,
+			completions::FinishReason::ToolCalls | completions::FinishReason::FunctionCall => {
+				messages::StopReason::ToolUse
+			},
+			completions::FinishReason::ContentFilter => messages::StopReason::Refusal,
+		}
+	}
+
+	fn translate_usage(usage: completions::Usage) -> messages::Usage {
+		messages::Usage {
+			input_tokens: usage.prompt_tokens as usize,
+			output_tokens: usage.completion_tokens as usize,
+			cache_creation_input_tokens: None,
+			cache_read_input_tokens: usage
+				.prompt_tokens_details
+				.and_then(|d| d.cached_tokens)
+				.map(|t| t as usize),
+		}
+	}
+
+	pub fn translate_error(bytes: &Bytes) -> Result<Bytes, AIError> {
+		let res = serde_json::from_slice::<completions::ChatCompletionErrorResponse>(bytes)
+			.map_err(AIError::ResponseMarshal)?;
+		let m = messages::MessagesErrorResponse {
+			r#type: "error".to_string(),
+			error: messages::MessagesError {
+				r#type: res.error.r#type,
+				message: res.error.message,
+			},
+		};
+		Ok(Bytes::from(
+			serde_json::to_vec(&m).map_err(AIError::ResponseMarshal)?,
+		))
+	}
+
+	pub fn translate_stream(b: Body, buffer_limit: usize, log: AsyncLog<LLMInfo>) -> Body {
+		let mut state = StreamState::default();
+		// https://docs.anthropic.com/en/docs/build-with-claude/streaming
+		parse::sse::json_transform_multi::<completions::StreamResponse, messages::MessagesStreamEvent>(
+			b,
+			buffer_limit,
+			move |f| {
+				let mut out = Vec::new();
+				match f {
+					Some(Ok(chunk)) => state.chunk(chunk, &log, &mut out),
+					Some(Err(e)) => debug!("failed to parse streaming response: {e}"),
+					// OpenAI ends the stream with [DONE], Anthropic with message_stop.
+					None => state.finish(&mut out),
+				}
+				out.into_iter().map(|e| e.into_sse_tuple()).collect()
+			},
+		)
+	}
+
+	#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+	enum BlockKind {
+		Text,
+		Thinking,
+		/// A tool call, by its index in the OpenAI response
+		Tool(u32),
+	}
+
+	/// Tracks an OpenAI stream so it can be re-emitted as Anthropic events. OpenAI streams flat
+	/// deltas, while Anthropic wraps each piece of content in explicit start and stop events.
+	#[derive(Default)]
+	struct StreamState {
+		started: bool,
+		saw_token: bool,
+		finished: bool,
+		/// The content block currently open, and its index
+		block: Option<(BlockKind, usize)>,
+		next_index: usize,
+		stop_reason: Option<messages::StopReason>,
+		usage: Option<messages::Usage>,
+	}
+
+	impl StreamState {
+		fn chunk(
+			&mut self,
+			chunk: completions::StreamResponse,
+			log: &AsyncLog<LLMInfo>,
+			out: &mut Vec<messages::MessagesStreamEvent>,
+		) {
+			if self.finished {
+				return;
+			}
+			if !self.started {
+				self.started = true;
+				log.non_atomic_mutate(|r| r.response.provider_model = Some(strng::new(&chunk.model)));
+				out.push(messages::MessagesStreamEvent::MessageStart {
+					message: messages::MessagesResponse {
+						id: chunk.id.clone(),
+						r#type: "message".to_string(),
+						role: messages::Role::Assistant,
+						content: vec![],
+						model: chunk.model.clone(),
+						stop_reason: None,
+						stop_sequence: None,
+						usage: messages::Usage {
+							input_tokens: 0,
+							output_tokens: 0,
+							cache_creation_input_tokens: None,
+							cache_read_input_tokens: None,
+						},
+					},
+				});
+			}
+			if let Some(choice) = chunk.choices.into_iter().next() {
+				let delta = choice.delta;
+				if delta.reasoning_content.is_some()
+					|| delta.content.is_some()
+					|| delta.tool_calls.is_some()
+				{
+					self.first_token(log);
+				}
+				if let Some(thinking) = delta.reasoning_content {
+					self.open(
+						BlockKind::Thinking,
+						|| ContentBlock::Thinking {
+							thinking: String::new(),
+							signature: String::new(),
+						},
+						out,
+					);
+					self.delta(messages::ContentBlockDelta::ThinkingDelta { thinking }, out);
+				}
+				if let Some(text) = delta.content {
+					self.open(
+						BlockKind::Text,
+						|| {
+							ContentBlock::Text(messages::ContentTextBlock {
+								text: String::new(),
+								citations: None,
+								cache_control: None,
+							})
+						},
+						out,
+					);
+					self.delta(messages::ContentBlockDelta::TextDelta { text }, out);
+				}
+				for call in delta.tool_calls.into_iter().flatten() {
+					let (name, arguments) = call
+						.function
+						.map(|f| (f.name, f.arguments))
+						.unwrap_or_default();
+					// The id and name are only sent in the first chunk of each call
+					self.open(
+						BlockKind::Tool(call.index),
+						|| ContentBlock::ToolUse {
+							id: call.id.unwrap_or_default(),
+							name: name.unwrap_or_default(),
+							input: serde_json::json!({}),
+							cache_control: None,
+						},
+						out,
+					);
+					if let Some(partial_json) = arguments.filter(|a| !a.is_empty()) {
+						self.delta(
+							messages::ContentBlockDelta::InputJsonDelta { partial_json },
+							out,
+						);
+					}
+				}
+				if let Some(reason) = choice.finish_reason {
+					self.close(out);
+					self.stop_reason = Some(translate_finish_reason(reason));
+				}
+			}
+			if let Some(usage) = chunk.usage {
+				log.non_atomic_mutate(|r| {
+					r.response.input_tokens = Some(usage.prompt_tokens as u64);
+					r.response.output_tokens = Some(usage.completion_tokens as u64);
+					r.response.total_tokens = Some(usage.total_tokens as u64);
+				});
+				self.usage = Some(translate_usage(usage));
+			}
+			// Usage is sent in its own chunk after the finish reason, so once we have both the message
+			// is complete.
+			if self.stop_reason.is_some() && self.usage.is_some() {
+				self.finish(out);
+			}
+		}
+
+		fn first_token(&mut self, log: &AsyncLog<LLMInfo>) {
+			if !self.saw_token {
+				self.saw_token = true;
+				log.non_atomic_mutate(|r| {
+					r.response.first_token = Some(Instant::now());
+				});
+			}
+		}
+
+		/// Open a content block of the given kind, closing the current one if it differs.
+		fn open(
+			&mut self,
+			kind: BlockKind,
+			content_block: impl FnOnce() -> ContentBlock,
+			out: &mut Vec<messages::MessagesStreamEvent>,
+		) {
+			if matches!(self.block, Some((k, _)) if k == kind) {
+				return;
+			}
+			self.close(out);
+			let index = self.next_index;
+			self.next_index += 1;
+			self.block = Some((kind, index));
+			out.push(messages::MessagesStreamEvent::ContentBlockStart {
+				index,
+				content_block: content_block(),
+			});
+		}
+
+		fn delta(
+			&self,
+			delta: messages::ContentBlockDelta,
+			out: &mut Vec<messages::MessagesStreamEvent>,
+		) {
+			if let Some((_, index)) = self.block {
+				out.push(messages::MessagesStreamEvent::ContentBlockDelta { index, delta });
+			}
+		}
+
+		fn close(&mut self, out: &mut Vec<messages::MessagesStreamEvent>) {
+			if let Some((_, index)) = self.block.take() {
+				out.push(messages::MessagesStreamEvent::ContentBlockStop { index });
+			}
+		}
+
+		fn finish(&mut self, out: &mut Vec<messages::MessagesStreamEvent>) {
+			if self.finished || !self.started {
+				return;
+			}
+			self.finished = true;
+			self.close(out);
+			let usage = self.usage.take();
+			out.push(messages::MessagesStreamEvent::MessageDelta {
+				delta: messages::MessageDelta {
+					stop_reason: Some(self.stop_reason.unwrap_or(messages::StopReason::EndTurn)),
+					stop_sequence: None,
+				},
+				usage: messages::MessageDeltaUsage {
+					input_tokens: usage.map(|u| u.input_tokens).unwrap_or_default(),
+					output_tokens: usage.map(|u| u.output_tokens).unwrap_or_default(),
+					cache_creation_input_tokens: None,
+					cache_read_input_tokens: usage.and_then(|u| u.cache_read_input_tokens),
+				},
+			});
+			out.push(messages::MessagesStreamEvent::MessageStop);
+		}
+	}
 }
 
 pub fn passthrough_stream(
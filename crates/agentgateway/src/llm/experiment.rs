@@ -0,0 +1,158 @@
+use aws_lc_rs::digest;
+use macro_rules_attribute::apply;
+use rand::Rng;
+
+use crate::http::{Body, Request, StatusCode, header};
+use crate::llm::fallback;
+use crate::proxy::ProxyError;
+use crate::telemetry::metrics::{ExperimentLabels, ExperimentOutcomeLabels, Metrics};
+use crate::*;
+
+#[cfg(test)]
+#[path = "experiment_tests.rs"]
+mod tests;
+
+/// Splits LLM requests between model variants, so they can be compared. Each request is assigned a
+/// variant, its `model` is rewritten to the variant's model, and the variant is reported in the
+/// `x-experiment-variant` response header, logs, and metrics.
+#[apply(schema!)]
+pub struct Policy {
+	/// The name of the experiment, reported with the assigned variant.
+	pub name: Strng,
+	pub variants: Vec<Variant>,
+	/// CEL expression for the key to assign variants by, such as `jwt.sub`. Requests with the same key
+	/// are always assigned the same variant. If unset, or the key cannot be evaluated, variants are
+	/// assigned at random.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub key: Option<Arc<cel::Expression>>,
+	/// Only requests for these models take part in the experiment. If unset, all requests do.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub models: Vec<Strng>,
+}
+
+#[apply(schema!)]
+pub struct Variant {
+	pub name: Strng,
+	/// The model to send requests assigned to this variant to. If unset, requests keep the model they
+	/// asked for, as a control group.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub model: Option<Strng>,
+	/// The share of requests assigned to this variant, relative to the weights of the others. Weights
+	/// adding up to 100 are percentages.
+	pub weight: u32,
+}
+
+/// The variant a request was assigned to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assignment {
+	pub experiment: Strng,
+	pub variant: Strng,
+}
+
+impl Policy {
+	/// The key of a request, from the `key` expression.
+	pub fn key(&self, exec: &cel::Executor) -> Option<String> {
+		match exec.eval(self.key.as_deref()?) {
+			Ok(cel::Value::String(s)) => Some(s.to_string()),
+			Ok(cel::Value::Int(i)) => Some(i.to_string()),
+			Ok(cel::Value::UInt(u)) => Some(u.to_string()),
+			_ => None,
+		}
+	}
+
+	/// Assign a request to a variant, rewriting the model it asks for.
+	pub async fn apply(
+		&self,
+		key: Option<&str>,
+		req: &mut Request,
+	) -> Result<Option<Assignment>, ProxyError> {
+		let body = crate::http::inspect_body(req)
+			.await
+			.map_err(ProxyError::Processing)?;
+		let requested = fallback::requested_model(&body);
+		if !self.models.is_empty()
+			&& !requested
+				.as_deref()
+				.is_some_and(|m| self.models.iter().any(|e| e.as_str() == m))
+		{
+			return Ok(None);
+		}
+		let Some(variant) = self.choose(key) else {
+			return Ok(None);
+		};
+		if let Some(model) = &variant.model
+			&& requested.as_deref() != Some(model.as_str())
+		{
+			let body = fallback::set_model(&body, model).map_err(ProxyError::Processing)?;
+			req.headers_mut().remove(header::CONTENT_LENGTH);
+			*req.body_mut() = Body::from(body);
+		}
+		debug!(experiment=%self.name, variant=%variant.name, "assigned experiment variant");
+		Ok(Some(Assignment {
+			experiment: self.name.clone(),
+			variant: variant.name.clone(),
+		}))
+	}
+
+	/// The variant for a request with `key`, or a random one if it has none.
+	fn choose(&self, key: Option<&str>) -> Option<&Variant> {
+		let total = self
+			.variants
+			.iter()
+			.map(|v| u64::from(v.weight))
+			.sum::<u64>();
+		if total == 0 {
+			return None;
+		}
+		let mut point = match key {
+			Some(key) => bucket(&self.name, key) % total,
+			None => rand::rng().random_range(0..total),
+		};
+		self.variants.iter().find(|v| {
+			let weight = u64::from(v.weight);
+			if point < weight {
+				return true;
+			}
+			point -= weight;
+			false
+		})
+	}
+}
+
+/// A stable hash of a request key, so it is assigned the same variant by every gateway and across
+/// restarts. The experiment name is included so experiments split traffic independently.
+fn bucket(experiment: &str, key: &str) -> u64 {
+	let mut ctx = digest::Context::new(&digest::SHA256);
+	ctx.update(experiment.as_bytes());
+	ctx.update(b"\0");
+	ctx.update(key.as_bytes());
+	let digest = ctx.finish();
+	let mut first = [0u8; 8];
+	first.copy_from_slice(&digest.as_ref()[..8]);
+	u64::from_be_bytes(first)
+}
+
+impl Assignment {
+	/// Record the outcome and duration of a request in the experiment's metrics.
+	pub fn record(&self, metrics: &Metrics, status: Option<StatusCode>, duration: Duration) {
+		let labels = ExperimentLabels {
+			experiment: self.experiment.clone().into(),
+			variant: self.variant.clone().into(),
+		};
+		let outcome = match status {
+			Some(s) if !s.is_client_error() && !s.is_server_error() => "success",
+			_ => "error",
+		};
+		metrics
+			.gen_ai_experiment_requests
+			.get_or_create(&ExperimentOutcomeLabels {
+				experiment: labels.clone(),
+				outcome: outcome.into(),
+			})
+			.inc();
+		metrics
+			.gen_ai_experiment_request_duration
+			.get_or_create(&labels)
+			.observe(duration.as_secs_f64());
+	}
+}
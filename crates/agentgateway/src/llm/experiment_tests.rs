@@ -0,0 +1,90 @@
+use super::*;
+
+fn policy(models: &[&str]) -> Policy {
+	serde_json::from_value(serde_json::json!({
+		"name": "sonnet-vs-4o",
+		"variants": [
+			{"name": "control", "weight": 80},
+			{"name": "sonnet", "model": "claude-sonnet-4-5", "weight": 20},
+		],
+		"models": models,
+	}))
+	.unwrap()
+}
+
+fn request(model: &str) -> Request {
+	let body = serde_json::json!({"model": model, "messages": []});
+	::http::Request::builder()
+		.uri("http://example.com/v1/chat/completions")
+		.header(header::CONTENT_LENGTH, body.to_string().len())
+		.body(Body::from(body.to_string()))
+		.unwrap()
+}
+
+#[test]
+fn test_choose() {
+	let p = policy(&[]);
+	// Keys are always assigned the same variant
+	for key in ["alice", "bob", "carol"] {
+		let first = p.choose(Some(key)).unwrap().name.clone();
+		for _ in 0..10 {
+			assert_eq!(p.choose(Some(key)).unwrap().name, first);
+		}
+	}
+
+	// Keys are split by weight
+	let sonnet = (0..1000)
+		.filter(|i| p.choose(Some(&format!("user-{i}"))).unwrap().name == "sonnet")
+		.count();
+	assert!((150..250).contains(&sonnet), "{sonnet}");
+
+	let p: Policy = serde_json::from_value(serde_json::json!({
+		"name": "empty",
+		"variants": [{"name": "a", "weight": 0}],
+	}))
+	.unwrap();
+	assert!(p.choose(Some("alice")).is_none());
+	assert!(p.choose(None).is_none());
+}
+
+#[test]
+fn test_bucket() {
+	// Experiments split the same keys independently
+	let a = (0..100).map(|i| bucket("a", &i.to_string()) % 2);
+	let b = (0..100).map(|i| bucket("b", &i.to_string()) % 2);
+	assert!(a.zip(b).any(|(a, b)| a != b));
+}
+
+#[tokio::test]
+async fn test_apply() {
+	let p = policy(&["gpt-4o"]);
+	let key = (0..100)
+		.map(|i| format!("user-{i}"))
+		.find(|k| p.choose(Some(k)).unwrap().name == "sonnet")
+		.unwrap();
+
+	let mut req = request("gpt-4o");
+	let assignment = p.apply(Some(&key), &mut req).await.unwrap().unwrap();
+	assert_eq!(
+		assignment,
+		Assignment {
+			experiment: strng::literal!("sonnet-vs-4o"),
+			variant: strng::literal!("sonnet"),
+		}
+	);
+	assert!(req.headers().get(header::CONTENT_LENGTH).is_none());
+	let body = crate::http::inspect_body(&mut req).await.unwrap();
+	assert_eq!(
+		fallback::requested_model(&body).as_deref(),
+		Some("claude-sonnet-4-5")
+	);
+
+	// Requests for other models do not take part
+	let mut req = request("gpt-4o-mini");
+	assert!(p.apply(Some(&key), &mut req).await.unwrap().is_none());
+	let body = crate::http::inspect_body(&mut req).await.unwrap();
+	assert_eq!(
+		fallback::requested_model(&body).as_deref(),
+		Some("gpt-4o-mini")
+	);
+}
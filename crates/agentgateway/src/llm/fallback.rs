@@ -0,0 +1,114 @@
+use std::num::NonZeroU8;
+
+use bytes::Bytes;
+use macro_rules_attribute::apply;
+
+use crate::http::Response;
+use crate::proxy::{ProxyError, ProxyResponse};
+use crate::*;
+
+#[cfg(test)]
+#[path = "fallback_tests.rs"]
+mod tests;
+
+/// Falls back through an ordered list of models when a request to the requested model fails, for
+/// example `gpt-4o`, then `claude-sonnet-4-5`, then a local model. Each fallback resends the request
+/// with its `model` rewritten, so the AI backend routes it to the provider serving that model.
+#[apply(schema!)]
+pub struct Policy {
+	/// The models to try, in order. Requests for a model in the list fall back to the models after it.
+	/// Other requests, including those without a model, are sent as-is, without fallback.
+	pub models: Vec<Link>,
+}
+
+#[apply(schema!)]
+pub struct Link {
+	pub model: Strng,
+	/// The number of attempts to make against this model before falling back to the next.
+	#[serde(default = "default_attempts")]
+	pub attempts: NonZeroU8,
+	/// The time allowed for each attempt against this model before falling back to the next.
+	#[serde(
+		default,
+		skip_serializing_if = "Option::is_none",
+		with = "serde_dur_option"
+	)]
+	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+	pub timeout: Option<Duration>,
+}
+
+fn default_attempts() -> NonZeroU8 {
+	NonZeroU8::new(1).unwrap()
+}
+
+impl Policy {
+	/// The links to try for a request for `model`, starting with the model itself.
+	pub fn chain(&self, model: Option<&str>) -> &[Link] {
+		model
+			.and_then(|m| self.models.iter().position(|l| l.model.as_str() == m))
+			.map(|i| &self.models[i..])
+			.unwrap_or_default()
+	}
+}
+
+/// Why a request fell back to the next model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reason {
+	/// The provider could not be reached, or failed to respond.
+	Error,
+	/// The attempt exceeded its timeout.
+	Timeout,
+	/// The provider responded with 429 Too Many Requests.
+	RateLimited,
+	/// The provider responded with a 5xx status.
+	ServerError,
+}
+
+impl Reason {
+	/// The reason to fall back after an attempt, or None if it should not.
+	pub fn of(res: &Result<Response, ProxyResponse>) -> Option<Reason> {
+		match res {
+			Ok(resp) if resp.status() == ::http::StatusCode::TOO_MANY_REQUESTS => {
+				Some(Reason::RateLimited)
+			},
+			Ok(resp) if resp.status().is_server_error() => Some(Reason::ServerError),
+			Ok(_) => None,
+			Err(ProxyResponse::Error(ProxyError::RequestTimeout | ProxyError::UpstreamCallTimeout)) => {
+				Some(Reason::Timeout)
+			},
+			Err(ProxyResponse::Error(_)) => Some(Reason::Error),
+			// Policies rejected the request; another model would not change that.
+			Err(ProxyResponse::DirectResponse(_)) => None,
+		}
+	}
+
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Reason::Error => "error",
+			Reason::Timeout => "timeout",
+			Reason::RateLimited => "rate_limited",
+			Reason::ServerError => "server_error",
+		}
+	}
+}
+
+/// The model a JSON request body asks for.
+pub fn requested_model(body: &[u8]) -> Option<String> {
+	#[derive(serde::Deserialize)]
+	struct ModelRequest {
+		model: Option<String>,
+	}
+	serde_json::from_slice::<ModelRequest>(body)
+		.ok()
+		.and_then(|r| r.model)
+}
+
+/// Rewrite the model a JSON request body asks for.
+pub fn set_model(body: &[u8], model: &str) -> anyhow::Result<Bytes> {
+	let mut v = serde_json::from_slice::<serde_json::Value>(body)?;
+	let Some(obj) = v.as_object_mut() else {
+		anyhow::bail!("request body is not a JSON object");
+	};
+	obj.insert("model".to_string(), model.into());
+	Ok(Bytes::from(serde_json::to_vec(&v)?))
+}
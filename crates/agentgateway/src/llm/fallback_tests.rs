@@ -0,0 +1,76 @@
+use super::*;
+
+fn policy() -> Policy {
+	serde_json::from_value(serde_json::json!({
+		"models": [
+			{"model": "gpt-4o", "attempts": 2, "timeout": "10s"},
+			{"model": "claude-sonnet-4-5"},
+			{"model": "llama3"},
+		],
+	}))
+	.unwrap()
+}
+
+fn models(links: &[Link]) -> Vec<&str> {
+	links.iter().map(|l| l.model.as_str()).collect()
+}
+
+#[test]
+fn test_config() {
+	let p = policy();
+	assert_eq!(p.models[0].attempts.get(), 2);
+	assert_eq!(p.models[0].timeout, Some(Duration::from_secs(10)));
+	assert_eq!(p.models[1].attempts.get(), 1);
+	assert_eq!(p.models[1].timeout, None);
+}
+
+#[test]
+fn test_chain() {
+	let p = policy();
+	assert_eq!(
+		models(p.chain(Some("gpt-4o"))),
+		vec!["gpt-4o", "claude-sonnet-4-5", "llama3"]
+	);
+	assert_eq!(
+		models(p.chain(Some("claude-sonnet-4-5"))),
+		vec!["claude-sonnet-4-5", "llama3"]
+	);
+	assert!(p.chain(None).is_empty());
+	assert!(p.chain(Some("gpt-4o-mini")).is_empty());
+}
+
+#[test]
+fn test_reason() {
+	let resp = |status: u16| {
+		Ok(
+			::http::Response::builder()
+				.status(status)
+				.body(crate::http::Body::empty())
+				.unwrap(),
+		)
+	};
+	assert_eq!(Reason::of(&resp(200)), None);
+	assert_eq!(Reason::of(&resp(400)), None);
+	assert_eq!(Reason::of(&resp(429)), Some(Reason::RateLimited));
+	assert_eq!(Reason::of(&resp(503)), Some(Reason::ServerError));
+	assert_eq!(
+		Reason::of(&Err(ProxyError::RequestTimeout.into())),
+		Some(Reason::Timeout)
+	);
+	assert_eq!(
+		Reason::of(&Err(ProxyError::NoHealthyEndpoints.into())),
+		Some(Reason::Error)
+	);
+}
+
+#[test]
+fn test_set_model() {
+	let body = br#"{"model":"gpt-4o","messages":[{"role":"user","content":"hi"}]}"#;
+	assert_eq!(requested_model(body).as_deref(), Some("gpt-4o"));
+	let body = set_model(body, "llama3").unwrap();
+	assert_eq!(requested_model(&body).as_deref(), Some("llama3"));
+	let v = serde_json::from_slice::<serde_json::Value>(&body).unwrap();
+	assert_eq!(v["messages"][0]["content"], "hi");
+
+	assert!(set_model(b"[]", "llama3").is_err());
+}
@@ -17,3 +17,10 @@ impl super::Provider for Provider {
 pub const DEFAULT_HOST_STR: &str = "generativelanguage.googleapis.com";
 pub const DEFAULT_HOST: Strng = strng::literal!(DEFAULT_HOST_STR);
 pub const DEFAULT_PATH: &str = "/v1beta/openai/chat/completions";
+
+pub fn path(route: super::RouteType) -> &'static str {
+	match route {
+		super::RouteType::Embeddings => "/v1beta/openai/embeddings",
+		_ => DEFAULT_PATH,
+	}
+}
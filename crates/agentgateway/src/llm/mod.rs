@@ -23,7 +23,9 @@ use crate::proxy::httpproxy::PolicyClient;
 use crate::store::{BackendPolicies, LLMResponsePolicies};
 use crate::telemetry::log::{AsyncLog, RequestLog};
 use crate::types::agent::{BackendPolicy, Target};
-use crate::types::loadbalancer::{ActiveHandle, EndpointWithInfo};
+use crate::types::loadbalancer::{
+	ActiveHandle, DEGRADED_PENALTY, EndpointInfo, EndpointWithInfo, HealthScoring,
+};
 use crate::*;
 
 pub mod anthropic;
@@ -33,8 +35,12 @@ pub mod gemini;
 pub mod openai;
 pub mod vertex;
 
+pub mod batch;
 mod conversion;
+pub mod experiment;
+pub mod fallback;
 pub mod policy;
+pub mod structured_output;
 mod types;
 
 pub use types::SimpleChatCompletionMessage;
@@ -46,33 +52,100 @@ mod tests;
 #[serde(rename_all = "camelCase")]
 pub struct AIBackend {
 	pub providers: crate::types::loadbalancer::EndpointSet<NamedAIProvider>,
+	pub load_balancing: LoadBalancing,
+	pub health_scoring: HealthScoring,
+}
+
+/// How requests are balanced across the providers of a group.
+#[apply(schema!)]
+#[derive(Copy, Default, PartialEq, Eq)]
+pub enum LoadBalancing {
+	/// Prefer providers with fewer pending requests and lower latency.
+	#[default]
+	Requests,
+	/// Prefer providers with the least queued work, estimated from the tokens their pending
+	/// requests use and the rate they process tokens at, as reported in response usage. Suits
+	/// groups of equivalent replicas, such as vLLM servers, serving requests of varying size.
+	Tokens,
+}
+
+impl LoadBalancing {
+	fn score(&self, info: &EndpointInfo) -> f64 {
+		let score = match self {
+			LoadBalancing::Requests => info.score(),
+			LoadBalancing::Tokens => info.token_score(),
+		};
+		if info.is_degraded() {
+			score * DEGRADED_PENALTY
+		} else {
+			score
+		}
+	}
 }
 
 impl AIBackend {
-	pub fn select_provider(&self) -> Option<(Arc<NamedAIProvider>, ActiveHandle)> {
+	/// Whether any provider lists the models it serves, so requests must be routed by model.
+	pub fn routes_by_model(&self) -> bool {
+		self.providers.any(|p| !p.models.is_empty())
+	}
+
+	/// Select a provider for a request. If providers list their models, only providers serving the
+	/// requested model are considered, falling back to providers that do not list any.
+	pub fn select_provider(
+		&self,
+		model: Option<&str>,
+	) -> Option<(Arc<NamedAIProvider>, ActiveHandle)> {
+		if self.routes_by_model() {
+			let mut candidates = model
+				.map(|m| self.providers.select_matching(|p| p.serves_model(m)))
+				.unwrap_or_default();
+			if candidates.is_empty() {
+				candidates = self.providers.select_matching(|p| p.models.is_empty());
+			}
+			let (ep, ep_info) = pick_best(self.load_balancing, candidates.len(), |idx| {
+				let (ep, info) = &candidates[idx];
+				(ep.clone(), info)
+			})?;
+			let handle = self
+				.providers
+				.start_request(ep.name.clone(), ep_info)
+				.with_health_scoring(self.health_scoring);
+			return Some((ep, handle));
+		}
 		let iter = self.providers.iter();
 		let index = iter.index();
-		if index.is_empty() {
-			return None;
-		}
-		// Intentionally allow `rand::seq::index::sample` so we can pick the same element twice
-		// This avoids starvation where the worst endpoint gets 0 traffic
-		let a = rand::rng().random_range(0..index.len());
-		let b = rand::rng().random_range(0..index.len());
-		let best = [a, b]
-			.into_iter()
-			.map(|idx| {
-				let (_, EndpointWithInfo { endpoint, info }) =
-					index.get_index(idx).expect("index already checked");
-				(endpoint.clone(), info)
-			})
-			.max_by(|(_, a), (_, b)| a.score().total_cmp(&b.score()));
-		let (ep, ep_info) = best?;
-		let handle = self.providers.start_request(ep.name.clone(), ep_info);
+		let (ep, ep_info) = pick_best(self.load_balancing, index.len(), |idx| {
+			let (_, EndpointWithInfo { endpoint, info }) =
+				index.get_index(idx).expect("index already checked");
+			(endpoint.clone(), info)
+		})?;
+		let handle = self
+			.providers
+			.start_request(ep.name.clone(), ep_info)
+			.with_health_scoring(self.health_scoring);
 		Some((ep, handle))
 	}
 }
 
+/// Pick the better scoring of two random providers out of `len`.
+fn pick_best<'a>(
+	load_balancing: LoadBalancing,
+	len: usize,
+	get: impl Fn(usize) -> (Arc<NamedAIProvider>, &'a Arc<EndpointInfo>),
+) -> Option<(Arc<NamedAIProvider>, &'a Arc<EndpointInfo>)> {
+	if len == 0 {
+		return None;
+	}
+	// Intentionally allow `rand::seq::index::sample` so we can pick the same element twice
+	// This avoids starvation where the worst endpoint gets 0 traffic
+	let a = rand::rng().random_range(0..len);
+	let b = rand::rng().random_range(0..len);
+	[a, b]
+		.into_iter()
+		.map(get)
+		.max_by(|(_, a), (_, b)| load_balancing.score(a).total_cmp(&load_balancing.score(b)))
+}
+
 #[derive(Debug, Clone, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct NamedAIProvider {
@@ -87,12 +160,23 @@ pub struct NamedAIProvider {
 	pub tokenize: bool,
 	#[serde(default, skip_serializing_if = "Vec::is_empty")]
 	pub inline_policies: Vec<BackendPolicy>,
+	/// Models served by this provider, used to route requests by the model they request.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub models: Vec<Strng>,
 }
 
 impl NamedAIProvider {
 	pub fn use_default_policies(&self) -> bool {
 		self.host_override.is_none()
 	}
+
+	/// Whether `model` matches one of the provider's models. A trailing `*` matches any suffix.
+	pub fn serves_model(&self, model: &str) -> bool {
+		self.models.iter().any(|m| match m.strip_suffix('*') {
+			Some(prefix) => model.starts_with(prefix),
+			None => m.as_str() == model,
+		})
+	}
 }
 
 #[apply(schema!)]
@@ -110,6 +194,8 @@ pub enum RouteType {
 	Responses,
 	/// OpenAI /embeddings
 	Embeddings,
+	/// Cohere-style /v1/rerank
+	Rerank,
 	/// OpenAI /realtime (websockets)
 	Realtime,
 	/// Anthropic /v1/messages/count_tokens
@@ -147,6 +233,7 @@ pub enum InputFormat {
 	Messages,
 	Responses,
 	Embeddings,
+	Rerank,
 	Realtime,
 	CountTokens,
 }
@@ -159,6 +246,7 @@ impl InputFormat {
 			InputFormat::Responses => true,
 			InputFormat::Realtime => false,
 			InputFormat::Embeddings => false,
+			InputFormat::Rerank => false,
 			InputFormat::CountTokens => false,
 		}
 	}
@@ -334,7 +422,7 @@ impl AIProvider {
 			AIProvider::Gemini(_) => http::modify_req(req, |req| {
 				http::modify_uri(req, |uri| {
 					if override_path {
-						Self::set_path_and_query(uri, gemini::DEFAULT_PATH)?;
+						Self::set_path_and_query(uri, gemini::path(route_type))?;
 					}
 					uri.authority = Some(Authority::from_static(gemini::DEFAULT_HOST_STR));
 					Ok(())
@@ -495,6 +583,31 @@ impl AIProvider {
 			.await
 	}
 
+	pub async fn process_rerank_request(
+		&self,
+		backend_info: &crate::http::auth::BackendInfo,
+		policies: Option<&Policy>,
+		req: Request,
+		tokenize: bool,
+		log: &mut Option<&mut RequestLog>,
+	) -> Result<RequestResult, AIError> {
+		let (parts, req) = self
+			.read_body_and_default_model::<types::rerank::Request>(policies, req)
+			.await?;
+
+		self
+			.process_request(
+				backend_info,
+				policies,
+				InputFormat::Rerank,
+				req,
+				parts,
+				tokenize,
+				log,
+			)
+			.await
+	}
+
 	pub async fn process_responses_request(
 		&self,
 		backend_info: &crate::http::auth::BackendInfo,
@@ -565,11 +678,8 @@ impl AIProvider {
 			(InputFormat::Completions, _) => {
 				// All providers support completions input
 			},
-			(
-				InputFormat::Messages,
-				AIProvider::Anthropic(_) | AIProvider::Bedrock(_) | AIProvider::Vertex(_),
-			) => {
-				// Anthropic supports messages input (Bedrock & Vertex support assuming serving Anthropic models)
+			(InputFormat::Messages, _) => {
+				// Anthropic supports messages input. Other providers support it via translation.
 			},
 			(InputFormat::Responses, AIProvider::OpenAI(_)) => {
 				// OpenAI supports responses input
@@ -580,11 +690,22 @@ impl AIProvider {
 			(InputFormat::CountTokens, AIProvider::Bedrock(_)) => {
 				// Bedrock supports count_tokens input via translation
 			},
-			(InputFormat::Embeddings, AIProvider::OpenAI(_) | AIProvider::AzureOpenAI(_)) => {
-				// passthrough
+			(
+				InputFormat::Embeddings,
+				AIProvider::OpenAI(_)
+				| AIProvider::AzureOpenAI(_)
+				| AIProvider::Gemini(_)
+				| AIProvider::Vertex(_),
+			) => {
+				// passthrough, to the provider's OpenAI-compatible embeddings endpoint
+			},
+			(InputFormat::Rerank, AIProvider::OpenAI(_)) => {
+				// passthrough, for OpenAI-compatible servers that serve rerank
+			},
+			(InputFormat::Rerank, AIProvider::Bedrock(_)) => {
+				// Bedrock serves Cohere rerank models, which return Cohere-style responses
 			},
 			(m, p) => {
-				// Messages with OpenAI compatible: currently only supports translating the request
 				return Err(AIError::UnsupportedConversion(strng::format!(
 					"{m:?} from provider {}",
 					p.provider()
@@ -598,7 +719,23 @@ impl AIProvider {
 			{
 				*model = aliased.to_string();
 			}
+			if let Some(access) = &p.model_access {
+				let model = req.model().clone().unwrap_or_default();
+				if let Some(dr) = access.apply(original_format, &model, log.as_deref()) {
+					return Ok(RequestResult::Rejected(dr));
+				}
+			}
+			if original_format.supports_prompt_guard()
+				&& let Some(dr) = p.apply_system_prompt(original_format, &mut req)
+			{
+				return Ok(RequestResult::Rejected(dr));
+			}
 			p.apply_prompt_enrichment(&mut req);
+			if original_format.supports_prompt_guard()
+				&& let Some(redacted) = p.apply_pii_redaction(&mut req)
+			{
+				parts.extensions.insert(redacted);
+			}
 
 			if original_format.supports_prompt_guard() {
 				let http_headers = &parts.headers;
@@ -615,12 +752,102 @@ impl AIProvider {
 			}
 		}
 
-		let llm_info = req.to_llm_request(self.provider(), tokenize)?;
+		let token_limits = policies
+			.and_then(|p| p.token_limits.as_ref())
+			.filter(|_| original_format.supports_prompt_guard());
+		if let Some(max) = token_limits.and_then(|l| l.max_output_tokens) {
+			req.clamp_max_tokens(max);
+		}
+		if let Some(cw) = policies
+			.and_then(|p| p.context_window.as_ref())
+			.filter(|_| original_format.supports_prompt_guard())
+		{
+			let client = PolicyClient {
+				inputs: backend_info.inputs.clone(),
+			};
+			if let Some(dr) = cw.apply(&client, original_format, &mut req).await {
+				return Ok(RequestResult::Rejected(dr));
+			}
+		}
+		let max_input_tokens = token_limits.and_then(|l| l.max_input_tokens);
+		let llm_info = req.to_llm_request(self.provider(), tokenize || max_input_tokens.is_some())?;
+		if let Some(limit) = max_input_tokens
+			&& let Some(tokens) = llm_info.input_tokens
+			&& tokens > limit
+		{
+			debug!(tokens, limit, "rejecting prompt over the input token limit");
+			return Ok(RequestResult::Rejected(
+				policy::token_limits::TokenLimits::input_rejection(original_format, tokens, limit),
+			));
+		}
 		if let Some(log) = log {
 			let needs_prompt = log.cel.cel_context.with_llm_request(&llm_info);
 			if needs_prompt {
 				log.cel.cel_context.with_llm_prompt(req.get_messages())
 			}
+			if let Some(c) = log.llm_capture.as_mut()
+				&& c.wants_prompt()
+				&& original_format.supports_prompt_guard()
+			{
+				c.record_prompt(req.get_messages());
+			}
+		}
+
+		if let Some(cache) = policies.and_then(|p| p.response_cache.as_ref()) {
+			let consumer = log.as_ref().and_then(|l| l.consumer());
+			match cache.lookup(
+				&backend_info.inputs.metrics,
+				original_format,
+				consumer.as_deref(),
+				&req,
+				&llm_info,
+			) {
+				Some(policy::response_cache::Lookup::Hit(body)) => {
+					// The cached response may hold another request's redacted values; restore this one's.
+					let body = match parts.extensions.get::<policy::pii_redaction::Redacted>() {
+						Some(redacted) => redacted.restore_body(body),
+						None => body,
+					};
+					return Ok(RequestResult::Rejected(policy::response_cache::response(
+						body,
+					)));
+				},
+				Some(policy::response_cache::Lookup::Miss(pending)) => {
+					parts.extensions.insert(pending);
+				},
+				None => {},
+			}
+		}
+
+		if let Some(cache) = policies.and_then(|p| p.semantic_cache.as_ref())
+			&& crate::features::enabled(crate::features::Feature::SemanticCache)
+			&& original_format.supports_prompt_guard()
+			&& !llm_info.streaming
+		{
+			let client = PolicyClient {
+				inputs: backend_info.inputs.clone(),
+			};
+			let claims = parts.extensions.get::<Claims>().cloned();
+			let consumer = log.as_ref().and_then(|l| l.consumer());
+			match cache
+				.lookup(
+					&client,
+					original_format,
+					llm_info.request_model.as_str(),
+					consumer.as_deref(),
+					req.get_messages(),
+					claims,
+				)
+				.await
+			{
+				Some(policy::semantic_cache::Lookup::Hit(resp)) => {
+					return Ok(RequestResult::Rejected(resp));
+				},
+				Some(policy::semantic_cache::Lookup::Miss(pending)) => {
+					parts.extensions.insert(pending);
+				},
+				None => {},
+			}
 		}
 
 		let request_model = llm_info.request_model.as_str();
@@ -655,15 +882,31 @@ impl AIProvider {
 		&self,
 		client: PolicyClient,
 		req: LLMRequest,
-		rate_limit: LLMResponsePolicies,
+		mut rate_limit: LLMResponsePolicies,
 		log: AsyncLog<llm::LLMInfo>,
 		include_completion_in_log: bool,
 		resp: Response,
 	) -> Result<Response, AIError> {
 		if req.streaming {
-			return self
-				.process_streaming(req, rate_limit, log, include_completion_in_log, resp)
-				.await;
+			let stream_rewrite = rate_limit.stream_rewrite.take();
+			let pii_restore = rate_limit.pii_restore.take();
+			let resp = self
+				.process_streaming(
+					req,
+					rate_limit,
+					log.clone(),
+					include_completion_in_log,
+					resp,
+				)
+				.await?;
+			let resp = match pii_restore {
+				Some(redacted) => redacted.restore_stream(resp),
+				None => resp,
+			};
+			return Ok(match stream_rewrite {
+				Some(rewrite) => rewrite.apply(resp, log),
+				None => resp,
+			});
 		}
 		// Buffer the body
 		let buffer_limit = http::response_buffer_limit(&resp);
@@ -691,16 +934,18 @@ impl AIProvider {
 			log.store(Some(llm_info));
 			return Ok(resp);
 		}
-		// embeddings has simplified response handling (currently nothing; no translation needed)
-		if req.input_format == InputFormat::Embeddings {
-			let resp = Response::from_parts(parts, bytes.into());
-			let llm_resp = LLMResponse::default();
-			let llm_info = LLMInfo::new(req, llm_resp);
-			log.store(Some(llm_info));
-			return Ok(resp);
-		}
-
-		let (llm_resp, body) = if !parts.status.is_success() {
+		// Embeddings and rerank responses need no translation; only their usage is read.
+		let (llm_resp, body) = if matches!(
+			req.input_format,
+			InputFormat::Embeddings | InputFormat::Rerank
+		) {
+			let llm_resp = if parts.status.is_success() {
+				types::passthrough_usage(&bytes)
+			} else {
+				LLMResponse::default()
+			};
+			(llm_resp, bytes)
+		} else if !parts.status.is_success() {
 			let body = self.process_error(&req, &bytes)?;
 			(LLMResponse::default(), body)
 		} else {
@@ -725,15 +970,30 @@ impl AIProvider {
 			let body = resp.serialize().map_err(AIError::ResponseParsing)?;
 			(llm_resp, Bytes::copy_from_slice(&body))
 		};
+		if let Some(pending) = rate_limit.semantic_cache.take() {
+			pending.complete(parts.status, &mut parts.headers, &body);
+		}
+		if let Some(pending) = rate_limit.response_cache.take() {
+			pending.complete(parts.status, &mut parts.headers, &body);
+		}
+		// Restore after caching, so cached responses never hold another request's values.
+		let body = match rate_limit.pii_restore.take() {
+			Some(redacted) if parts.status.is_success() => redacted.restore_body(body),
+			_ => body,
+		};
 
-		let body = if let Some(encoding) = encoding {
-			Body::from(
+		let body = match encoding {
+			Some(encoding) if crate::overload::body_processing_enabled() => Body::from(
 				http::compression::encode_body(&body, encoding)
 					.await
 					.map_err(AIError::Encoding)?,
-			)
-		} else {
-			Body::from(body)
+			),
+			Some(_) => {
+				// Recompressing is skipped while overloaded, so send the body as is.
+				parts.headers.remove(header::CONTENT_ENCODING);
+				Body::from(body)
+			},
+			None => Body::from(body),
 		};
 		parts.headers.remove(header::CONTENT_LENGTH);
 		let resp = Response::from_parts(parts, body);
@@ -768,6 +1028,12 @@ impl AIProvider {
 				serde_json::from_slice::<types::responses::Response>(bytes)
 					.map_err(AIError::ResponseParsing)?,
 			)),
+			// Messages with a non-Anthropic model on Vertex: translate from its OpenAI compatible API
+			(AIProvider::Vertex(p), InputFormat::Messages)
+				if !p.is_anthropic_model(Some(&req.request_model)) =>
+			{
+				conversion::completions::from_messages::translate_response(bytes)
+			},
 			// Anthropic messages: passthrough
 			(AIProvider::Anthropic(_) | AIProvider::Vertex(_), InputFormat::Messages) => Ok(Box::new(
 				serde_json::from_slice::<types::messages::Response>(bytes)
@@ -783,6 +1049,10 @@ impl AIProvider {
 			(AIProvider::Bedrock(_), InputFormat::Messages) => {
 				conversion::bedrock::from_messages::translate_response(bytes, &req.request_model)
 			},
+			(
+				AIProvider::OpenAI(_) | AIProvider::Gemini(_) | AIProvider::AzureOpenAI(_),
+				InputFormat::Messages,
+			) => conversion::completions::from_messages::translate_response(bytes),
 			(AIProvider::Bedrock(_), InputFormat::Responses) => {
 				conversion::bedrock::from_responses::translate_response(bytes, &req.request_model)
 			},
@@ -796,9 +1066,6 @@ impl AIProvider {
 					))
 				}
 			},
-			(_, InputFormat::Messages) => Err(AIError::UnsupportedConversion(strng::literal!(
-				"this provider does not support Messages"
-			))),
 			(_, InputFormat::Responses) => Err(AIError::UnsupportedConversion(strng::literal!(
 				"this provider does not support Responses"
 			))),
@@ -808,8 +1075,8 @@ impl AIProvider {
 			(_, InputFormat::CountTokens) => {
 				unreachable!("CountTokens should be handled by process_count_tokens_response")
 			},
-			(_, InputFormat::Embeddings) => {
-				unreachable!("Embeddings should be handled by process_embeddings_response")
+			(_, InputFormat::Embeddings | InputFormat::Rerank) => {
+				unreachable!("Embeddings and rerank responses are not translated")
 			},
 		}
 	}
@@ -854,6 +1121,10 @@ impl AIProvider {
 				| AIProvider::Vertex(_),
 				InputFormat::Responses,
 			) => resp.map(|b| conversion::responses::passthrough_stream(b, buffer, log)),
+			// Messages with a non-Anthropic model on Vertex: translate from its OpenAI compatible API
+			(AIProvider::Vertex(p), InputFormat::Messages) if !p.is_anthropic_model(Some(&model)) => {
+				resp.map(|b| conversion::completions::from_messages::translate_stream(b, buffer, log))
+			},
 			// Anthropic messages: passthrough
 			(AIProvider::Anthropic(_) | AIProvider::Vertex(_), InputFormat::Messages) => {
 				resp.map(|b| conversion::messages::passthrough_stream(b, buffer, log))
@@ -880,11 +1151,10 @@ impl AIProvider {
 					conversion::bedrock::from_responses::translate_stream(b, buffer, log, &model, &msg)
 				})
 			},
-			(_, InputFormat::Messages) => {
-				return Err(AIError::UnsupportedConversion(strng::literal!(
-					"this provider does not support Messages for streaming"
-				)));
-			},
+			(
+				AIProvider::OpenAI(_) | AIProvider::Gemini(_) | AIProvider::AzureOpenAI(_),
+				InputFormat::Messages,
+			) => resp.map(|b| conversion::completions::from_messages::translate_stream(b, buffer, log)),
 			(_, InputFormat::Realtime) => {
 				return Err(AIError::UnsupportedConversion(strng::literal!(
 					"realtime does not use streaming codepath"
@@ -898,8 +1168,8 @@ impl AIProvider {
 			(_, InputFormat::CountTokens) => {
 				unreachable!("CountTokens should be handled by process_count_tokens_response")
 			},
-			(_, InputFormat::Embeddings) => {
-				unreachable!("Embeddings should be handled by process_embeddings_response")
+			(_, InputFormat::Embeddings | InputFormat::Rerank) => {
+				unreachable!("Embeddings and rerank responses are not translated")
 			},
 		})
 	}
@@ -940,10 +1210,19 @@ impl AIProvider {
 				// Passthrough; nothing needed
 				Ok(bytes.clone())
 			},
+			(AIProvider::Vertex(p), InputFormat::Messages)
+				if !p.is_anthropic_model(Some(&req.request_model)) =>
+			{
+				conversion::completions::from_messages::translate_error(bytes)
+			},
 			(AIProvider::Anthropic(_) | AIProvider::Vertex(_), InputFormat::Messages) => {
 				// Passthrough; nothing needed
 				Ok(bytes.clone())
 			},
+			(
+				AIProvider::OpenAI(_) | AIProvider::Gemini(_) | AIProvider::AzureOpenAI(_),
+				InputFormat::Messages,
+			) => conversion::completions::from_messages::translate_error(bytes),
 			(AIProvider::Anthropic(_), InputFormat::Completions) => {
 				conversion::messages::from_completions::translate_error(bytes)
 			},
@@ -1023,6 +1302,25 @@ fn num_tokens_from_anthropic_messages(
 	Ok(num_tokens)
 }
 
+/// Whether a status from a provider is a transient error worth retrying. Other errors, such as
+/// invalid requests and authentication failures, fail the same way on every attempt.
+pub fn retryable_status(provider: &str, status: ::http::StatusCode) -> bool {
+	use ::http::StatusCode;
+	match status {
+		StatusCode::REQUEST_TIMEOUT
+		| StatusCode::TOO_MANY_REQUESTS
+		| StatusCode::INTERNAL_SERVER_ERROR
+		| StatusCode::BAD_GATEWAY
+		| StatusCode::SERVICE_UNAVAILABLE
+		| StatusCode::GATEWAY_TIMEOUT => true,
+		// Anthropic reports overload as 529, including for its models on Vertex AI
+		s if s.as_u16() == 529 => {
+			provider == anthropic::Provider::NAME.as_str() || provider == vertex::Provider::NAME.as_str()
+		},
+		_ => false,
+	}
+}
+
 /// Tokenizers take about 200ms to load and are lazy loaded. This loads them on demand, outside the
 /// request path
 pub fn preload_tokenizers() {
@@ -1102,4 +1400,15 @@ fn amend_tokens(rate_limit: store::LLMResponsePolicies, llm_resp: &LLMInfo) {
 	if let Some(rrl) = rate_limit.remote_rate_limit {
 		rrl.amend_tokens(tokens_to_remove)
 	}
+	if let Some(q) = &rate_limit.quota {
+		let input = llm_resp
+			.response
+			.input_tokens
+			.or(llm_resp.request.input_tokens)
+			.unwrap_or_default();
+		q.add_tokens(input + response);
+		if let Some(cost) = crate::telemetry::costs::cost(llm_resp) {
+			q.add_cost(cost);
+		}
+	}
 }
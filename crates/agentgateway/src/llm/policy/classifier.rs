@@ -0,0 +1,69 @@
+use itertools::Itertools;
+use macro_rules_attribute::apply;
+
+use crate::llm::policy::Action;
+use crate::*;
+
+#[cfg(test)]
+#[path = "classifier_tests.rs"]
+mod tests;
+
+/// A local classifier, run in-process without calling out to a model. Each category scores text by
+/// the summed weights of the terms it contains, and matches once the score reaches its threshold.
+#[apply(schema!)]
+pub struct Classifier {
+	/// What to do with messages that match a category. Masking replaces the whole message.
+	#[serde(default)]
+	pub action: Action,
+	pub categories: Vec<Category>,
+}
+
+#[apply(schema!)]
+pub struct Category {
+	pub name: Strng,
+	/// Words or phrases, and the weight each adds to the score when it appears. Terms are matched
+	/// case-insensitively on whole words, and count once however often they appear.
+	pub terms: HashMap<String, f32>,
+	/// The score at which text matches the category. Defaults to 1.
+	#[serde(default = "default_threshold")]
+	pub threshold: f32,
+}
+
+fn default_threshold() -> f32 {
+	1.0
+}
+
+fn words(text: &str) -> Vec<String> {
+	text
+		.split(|c: char| !c.is_alphanumeric())
+		.filter(|w| !w.is_empty())
+		.map(|w| w.to_lowercase())
+		.collect_vec()
+}
+
+impl Category {
+	fn score(&self, text: &[String]) -> f32 {
+		self
+			.terms
+			.iter()
+			.filter(|(term, _)| {
+				let term = words(term);
+				!term.is_empty() && text.windows(term.len()).any(|w| w == term.as_slice())
+			})
+			.map(|(_, weight)| weight)
+			.sum()
+	}
+}
+
+impl Classifier {
+	/// The names of the categories that the text matches.
+	pub fn classify(&self, text: &str) -> Vec<Strng> {
+		let text = words(text);
+		self
+			.categories
+			.iter()
+			.filter(|c| c.score(&text) >= c.threshold)
+			.map(|c| c.name.clone())
+			.collect()
+	}
+}
@@ -0,0 +1,284 @@
+use ::http::header;
+use itertools::Itertools;
+use macro_rules_attribute::apply;
+
+use crate::http::{Body, Response};
+use crate::json;
+use crate::llm::policy::de_from_local_backend_policy;
+use crate::llm::policy::invalid_request_response;
+use crate::llm::policy::model_access::matches;
+use crate::llm::types::{RequestType, is_system_role};
+use crate::llm::{InputFormat, SimpleChatCompletionMessage, num_tokens_from_messages};
+use crate::proxy::httpproxy::PolicyClient;
+use crate::types::agent::{BackendPolicy, ResourceName, SimpleBackend, Target};
+use crate::types::local::LocalBackendPolicies;
+use crate::*;
+
+#[cfg(test)]
+#[path = "context_window_tests.rs"]
+mod tests;
+
+/// Handle requests whose prompt does not fit in the model's context window, instead of sending
+/// them to a provider that will reject them. Prompts are counted with the model's tiktoken
+/// tokenizer, falling back to `cl100k_base` for other models, and must leave room for the
+/// request's `max_tokens`.
+#[apply(schema!)]
+pub struct ContextWindow {
+	/// Context window sizes, in tokens. The first entry whose model matches applies.
+	pub models: Vec<ModelWindow>,
+	/// The context window of models not in `models`. If unset, requests for them are not checked.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub default_tokens: Option<u64>,
+	/// What to do with prompts that do not fit. Defaults to `reject`.
+	#[serde(default)]
+	pub overflow: Overflow,
+}
+
+#[apply(schema!)]
+pub struct ModelWindow {
+	/// The model. `*` matches any characters, so `gpt-4o*` matches every version of `gpt-4o`.
+	pub model: Strng,
+	pub tokens: u64,
+}
+
+#[apply(schema!)]
+#[derive(Default)]
+pub enum Overflow {
+	/// Reject the request with a `context_length_exceeded` error.
+	#[default]
+	Reject,
+	/// Drop the oldest messages until the prompt fits. System messages and the last message are
+	/// always kept.
+	Truncate,
+	/// Replace the oldest messages with a summary written by another model. Prompts that still do
+	/// not fit, or whose history could not be summarized, are truncated.
+	Summarize(Summarizer),
+}
+
+/// A model, served by an OpenAI-compatible chat completions endpoint, that summarizes history.
+#[apply(schema!)]
+pub struct Summarizer {
+	/// The model to summarize with, such as `gpt-4o-mini`.
+	pub model: Strng,
+	/// The chat completions endpoint. Defaults to `https://api.openai.com/v1/chat/completions`.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub url: Option<String>,
+	/// The number of most recent messages kept as is. Defaults to 4.
+	#[serde(default = "default_keep_recent")]
+	pub keep_recent: usize,
+	/// Policies for calling the endpoint, such as `backendAuth`.
+	#[serde(default, deserialize_with = "de_from_local_backend_policy")]
+	#[cfg_attr(feature = "schema", schemars(with = "LocalBackendPolicies"))]
+	pub policies: Vec<BackendPolicy>,
+}
+
+fn default_keep_recent() -> usize {
+	4
+}
+
+const DEFAULT_SUMMARIZER_URL: &str = "https://api.openai.com/v1/chat/completions";
+
+const SUMMARY_INSTRUCTIONS: &str = "Summarize the following conversation, keeping the facts, \
+	decisions, and open questions needed to continue it. Reply with only the summary.";
+
+impl ContextWindow {
+	/// The context window of `model`, if it is known.
+	pub fn window(&self, model: &str) -> Option<u64> {
+		self
+			.models
+			.iter()
+			.find(|w| matches(&w.model, model))
+			.map(|w| w.tokens)
+			.or(self.default_tokens)
+	}
+
+	/// Fit the request's prompt into the model's context window, returning a rejection if it cannot
+	/// be made to fit.
+	pub async fn apply(
+		&self,
+		client: &PolicyClient,
+		format: InputFormat,
+		req: &mut dyn RequestType,
+	) -> Option<Response> {
+		let model = req.model().clone().unwrap_or_default();
+		let window = self.window(&model)?;
+		let max_tokens = req
+			.to_llm_request(strng::literal!(""), false)
+			.ok()
+			.and_then(|r| r.params.max_tokens)
+			.unwrap_or_default();
+		let limit = window.saturating_sub(max_tokens);
+		let messages = req.get_messages();
+		let tokens = num_tokens_from_messages(&model, &messages).ok()?;
+		if tokens <= limit {
+			return None;
+		}
+		debug!(model, tokens, limit, "prompt exceeds the context window");
+		let fitted = match &self.overflow {
+			Overflow::Reject => None,
+			Overflow::Truncate => truncate(&model, messages, limit),
+			Overflow::Summarize(s) => {
+				let summarized = match s.summarize(client, messages.clone()).await {
+					Ok(summarized) => summarized,
+					Err(e) => {
+						warn!("failed to summarize history, truncating instead: {e}");
+						messages
+					},
+				};
+				truncate(&model, summarized, limit)
+			},
+		};
+		let Some(fitted) = fitted else {
+			return Some(rejection(format, &model, tokens, window, max_tokens));
+		};
+		req.set_messages(fitted);
+		None
+	}
+}
+
+fn rejection(
+	format: InputFormat,
+	model: &str,
+	tokens: u64,
+	window: u64,
+	max_tokens: u64,
+) -> Response {
+	let mut message =
+		format!("prompt is {tokens} tokens, exceeding the {window} token context window of {model}");
+	if max_tokens > 0 {
+		message.push_str(&format!(" with {max_tokens} tokens reserved for output"));
+	}
+	invalid_request_response(format, "context_length_exceeded", message)
+}
+
+/// Drop the oldest messages, other than system messages and the last message, until the prompt is
+/// at most `limit` tokens. Returns None if it cannot be made to fit.
+fn truncate(
+	model: &str,
+	mut messages: Vec<SimpleChatCompletionMessage>,
+	limit: u64,
+) -> Option<Vec<SimpleChatCompletionMessage>> {
+	loop {
+		if num_tokens_from_messages(model, &messages).ok()? <= limit {
+			return Some(messages);
+		}
+		let last = messages.len().checked_sub(1)?;
+		let oldest = messages
+			.iter()
+			.position(|m| !is_system_role(&m.role))
+			.filter(|i| *i < last)?;
+		messages.remove(oldest);
+		// Conversations start with the user, so drop replies left at the start.
+		while let Some(i) = messages
+			.iter()
+			.position(|m| !is_system_role(&m.role))
+			.filter(|i| *i < messages.len() - 1 && messages[*i].role == "assistant")
+		{
+			messages.remove(i);
+		}
+	}
+}
+
+impl Summarizer {
+	/// Replace the messages before the most recent ones with a summary. System messages are kept.
+	async fn summarize(
+		&self,
+		client: &PolicyClient,
+		messages: Vec<SimpleChatCompletionMessage>,
+	) -> anyhow::Result<Vec<SimpleChatCompletionMessage>> {
+		let (system, conversation): (Vec<_>, Vec<_>) =
+			messages.into_iter().partition(|m| is_system_role(&m.role));
+		let split = conversation.len().saturating_sub(self.keep_recent.max(1));
+		if split == 0 {
+			return Ok(system.into_iter().chain(conversation).collect());
+		}
+		let (history, recent) = conversation.split_at(split);
+		let summary = self.complete(client, history).await?;
+		Ok(
+			system
+				.into_iter()
+				.chain(std::iter::once(SimpleChatCompletionMessage {
+					role: strng::literal!("user"),
+					content: strng::format!("Summary of the earlier conversation:\n{summary}"),
+				}))
+				.chain(recent.iter().cloned())
+				.collect(),
+		)
+	}
+
+	async fn complete(
+		&self,
+		client: &PolicyClient,
+		history: &[SimpleChatCompletionMessage],
+	) -> anyhow::Result<String> {
+		#[derive(serde::Deserialize)]
+		struct CompletionResponse {
+			choices: Vec<Choice>,
+		}
+		#[derive(serde::Deserialize)]
+		struct Choice {
+			message: Message,
+		}
+		#[derive(serde::Deserialize)]
+		struct Message {
+			content: Option<String>,
+		}
+
+		let url = self.url.as_deref().unwrap_or(DEFAULT_SUMMARIZER_URL);
+		let (target, tls) = target(url)?;
+		let mut pols = Vec::new();
+		if tls {
+			pols.push(BackendPolicy::BackendTLS(
+				crate::http::backendtls::SYSTEM_TRUST.clone(),
+			));
+		}
+		pols.extend(self.policies.iter().cloned());
+		let transcript = history
+			.iter()
+			.map(|m| format!("{}: {}", m.role, m.content))
+			.join("\n\n");
+		let req = ::http::Request::builder()
+			.uri(url)
+			.method(::http::Method::POST)
+			.header(header::CONTENT_TYPE, "application/json")
+			.body(Body::from(serde_json::to_vec(&serde_json::json!({
+				"model": self.model,
+				"messages": [
+					{"role": "system", "content": SUMMARY_INSTRUCTIONS},
+					{"role": "user", "content": transcript},
+				],
+			}))?))?;
+		let be = SimpleBackend::Opaque(
+			ResourceName::new(strng::literal!("_context-summarizer"), strng::literal!("")),
+			target,
+		);
+		let resp = client.call_with_explicit_policies(req, be, pols).await?;
+		let status = resp.status();
+		if !status.is_success() {
+			anyhow::bail!("summarizer returned {status}");
+		}
+		let resp: CompletionResponse = json::from_response_body(resp).await?;
+		resp
+			.choices
+			.into_iter()
+			.next()
+			.and_then(|c| c.message.content)
+			.filter(|c| !c.is_empty())
+			.ok_or_else(|| anyhow::anyhow!("summarizer returned no summary"))
+	}
+}
+
+/// The target of a URL, and whether it uses TLS.
+fn target(url: &str) -> anyhow::Result<(Target, bool)> {
+	let uri: ::http::Uri = url.parse()?;
+	let tls = match uri.scheme_str() {
+		Some("https") => true,
+		Some("http") => false,
+		_ => anyhow::bail!("summarizer url must be http or https: {url}"),
+	};
+	let host = uri
+		.host()
+		.ok_or_else(|| anyhow::anyhow!("summarizer url has no host: {url}"))?;
+	let port = uri.port_u16().unwrap_or(if tls { 443 } else { 80 });
+	Ok(((host, port).try_into()?, tls))
+}
@@ -0,0 +1,142 @@
+use http_body_util::BodyExt;
+
+use super::*;
+use crate::llm::types;
+use crate::test_helpers::proxymock::setup_proxy_test;
+
+fn message(role: &str, content: &str) -> SimpleChatCompletionMessage {
+	SimpleChatCompletionMessage {
+		role: role.into(),
+		content: content.into(),
+	}
+}
+
+fn conversation() -> Vec<SimpleChatCompletionMessage> {
+	let long = "lorem ipsum ".repeat(100);
+	vec![
+		message("system", "Be helpful"),
+		message("user", &long),
+		message("assistant", &long),
+		message("user", &long),
+		message("assistant", "Sure"),
+		message("user", "Thanks, and then?"),
+	]
+}
+
+fn tokens(messages: &[SimpleChatCompletionMessage]) -> u64 {
+	num_tokens_from_messages("gpt-4o", messages).unwrap()
+}
+
+fn request(max_tokens: u64) -> types::completions::Request {
+	serde_json::from_value(serde_json::json!({
+		"model": "gpt-4o",
+		"max_tokens": max_tokens,
+		"messages": conversation(),
+	}))
+	.unwrap()
+}
+
+#[test]
+fn test_config() {
+	let cw: ContextWindow = serde_json::from_value(serde_json::json!({
+		"models": [
+			{"model": "gpt-4o-mini*", "tokens": 64000},
+			{"model": "gpt-4o*", "tokens": 128000},
+		],
+		"overflow": {"summarize": {"model": "gpt-4o-mini"}},
+	}))
+	.unwrap();
+	assert_eq!(cw.window("gpt-4o-mini-2024-07-18"), Some(64000));
+	assert_eq!(cw.window("gpt-4o"), Some(128000));
+	assert_eq!(cw.window("claude-sonnet-4"), None);
+	assert!(matches!(
+		cw.overflow,
+		Overflow::Summarize(Summarizer { keep_recent: 4, .. })
+	));
+
+	let cw: ContextWindow = serde_json::from_value(serde_json::json!({
+		"models": [],
+		"defaultTokens": 8000,
+		"overflow": "truncate",
+	}))
+	.unwrap();
+	assert_eq!(cw.window("claude-sonnet-4"), Some(8000));
+	assert!(matches!(cw.overflow, Overflow::Truncate));
+}
+
+#[test]
+fn test_truncate() {
+	let messages = conversation();
+	let total = tokens(&messages);
+	// Fits already
+	assert_eq!(
+		truncate("gpt-4o", messages.clone(), total).unwrap().len(),
+		6
+	);
+
+	// Dropping the first user message leaves a reply at the start, which is dropped too
+	let out = truncate("gpt-4o", messages.clone(), total - 10).unwrap();
+	let roles = out.iter().map(|m| m.role.as_str()).collect_vec();
+	assert_eq!(roles, vec!["system", "user", "assistant", "user"]);
+
+	// System messages and the last message are always kept
+	let out = truncate(
+		"gpt-4o",
+		messages.clone(),
+		tokens(&[messages[0].clone(), messages[5].clone()]),
+	)
+	.unwrap();
+	let contents = out.iter().map(|m| m.content.as_str()).collect_vec();
+	assert_eq!(contents, vec!["Be helpful", "Thanks, and then?"]);
+	assert_eq!(truncate("gpt-4o", messages, 5), None);
+}
+
+#[tokio::test]
+async fn test_apply() {
+	let t = setup_proxy_test("{}").expect("setup proxy inputs");
+	let client = PolicyClient { inputs: t.inputs() };
+	let total = tokens(&conversation());
+	let policy = |overflow: serde_json::Value| -> ContextWindow {
+		serde_json::from_value(serde_json::json!({
+			"models": [{"model": "gpt-4o", "tokens": total + 100}],
+			"overflow": overflow,
+		}))
+		.unwrap()
+	};
+
+	// Fits with room for the output
+	let mut req = request(100);
+	assert!(
+		policy("reject".into())
+			.apply(&client, InputFormat::Completions, &mut req)
+			.await
+			.is_none()
+	);
+	assert_eq!(req.get_messages().len(), 6);
+
+	let mut req = request(200);
+	let resp = policy("reject".into())
+		.apply(&client, InputFormat::Completions, &mut req)
+		.await
+		.expect("rejected");
+	assert_eq!(resp.status(), ::http::StatusCode::BAD_REQUEST);
+	let b = resp.into_body().collect().await.unwrap().to_bytes();
+	let body: serde_json::Value = serde_json::from_slice(&b).unwrap();
+	assert_eq!(body["error"]["code"], "context_length_exceeded");
+	assert_eq!(
+		body["error"]["message"],
+		format!(
+			"prompt is {total} tokens, exceeding the {} token context window of gpt-4o with 200 tokens reserved for output",
+			total + 100
+		)
+	);
+
+	let mut req = request(200);
+	assert!(
+		policy("truncate".into())
+			.apply(&client, InputFormat::Completions, &mut req)
+			.await
+			.is_none()
+	);
+	assert_eq!(req.get_messages().len(), 4);
+}
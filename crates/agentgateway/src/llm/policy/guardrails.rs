@@ -0,0 +1,270 @@
+use std::future::Future;
+
+use itertools::Itertools;
+use macro_rules_attribute::apply;
+use serde::Deserialize;
+
+use crate::http::auth::{AwsAuth, BackendAuth};
+use crate::json;
+use crate::llm::bedrock::AwsRegion;
+use crate::llm::policy::de_from_local_backend_policy;
+use crate::proxy::httpproxy::PolicyClient;
+use crate::telemetry::metrics::{GuardrailAction, GuardrailFailureLabels, GuardrailPhase};
+use crate::types::agent::{BackendPolicy, ResourceName, SimpleBackend, Target};
+use crate::types::local::LocalBackendPolicies;
+use crate::*;
+
+#[cfg(test)]
+#[path = "guardrails_tests.rs"]
+mod tests;
+
+/// What a guard backed by an external service does when the service fails or times out.
+#[apply(schema!)]
+#[derive(Default, Copy, PartialEq, Eq)]
+pub enum FailureMode {
+	/// Fail the request, so no content passes unchecked.
+	#[default]
+	Closed,
+	/// Allow the content through unchecked.
+	Open,
+}
+
+/// A guardrails service compatible with the Amazon Bedrock `ApplyGuardrail` API. Prompts are
+/// checked as `INPUT` and completions as `OUTPUT`; content the guardrail blocks is rejected, and
+/// content it only anonymizes is masked with the guardrail's output.
+#[apply(schema!)]
+pub struct BedrockGuardrails {
+	/// The ID or ARN of the guardrail.
+	pub guardrail_identifier: Strng,
+	/// The version of the guardrail, such as `1` or `DRAFT`.
+	pub guardrail_version: Strng,
+	/// The AWS region of the guardrail.
+	pub region: Strng,
+	/// The host to send requests to, for compatible services. Defaults to
+	/// `bedrock-runtime.<region>.amazonaws.com`.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub host: Option<Strng>,
+	/// Policies for calling the service. Unless `backendAuth` is set, requests are signed with AWS
+	/// credentials from the environment.
+	#[serde(default, deserialize_with = "de_from_local_backend_policy")]
+	#[cfg_attr(feature = "schema", schemars(with = "LocalBackendPolicies"))]
+	pub policies: Vec<BackendPolicy>,
+	/// How long to wait for the service. Defaults to no limit.
+	#[serde(
+		default,
+		skip_serializing_if = "Option::is_none",
+		with = "serde_dur_option"
+	)]
+	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+	pub timeout: Option<Duration>,
+	/// What to do when the service fails or times out. Defaults to `closed`.
+	#[serde(default)]
+	pub failure_mode: FailureMode,
+}
+
+/// Which side of the conversation content being checked comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+	Input,
+	Output,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplyGuardrailResponse {
+	pub action: String,
+	#[serde(default)]
+	pub outputs: Vec<GuardrailOutput>,
+	#[serde(default)]
+	pub assessments: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GuardrailOutput {
+	#[serde(default)]
+	pub text: String,
+}
+
+/// The verdict of a guardrail on a list of messages.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+	Allow,
+	/// The messages, with sensitive content masked, in the order they were sent.
+	Mask {
+		messages: Vec<String>,
+		categories: Vec<Strng>,
+	},
+	Reject {
+		categories: Vec<Strng>,
+	},
+}
+
+impl BedrockGuardrails {
+	/// Check the text of each message, one content block per message.
+	pub async fn apply(
+		&self,
+		client: &PolicyClient,
+		source: Source,
+		messages: &[Strng],
+	) -> anyhow::Result<Verdict> {
+		let host = self
+			.host
+			.clone()
+			.unwrap_or_else(|| strng::format!("bedrock-runtime.{}.amazonaws.com", self.region));
+		let encode = |s: &str| url::form_urlencoded::byte_serialize(s.as_bytes()).collect::<String>();
+		let uri = format!(
+			"https://{host}/guardrail/{}/version/{}/apply",
+			encode(&self.guardrail_identifier),
+			encode(&self.guardrail_version),
+		);
+		let mut pols = vec![BackendPolicy::BackendTLS(
+			crate::http::backendtls::SYSTEM_TRUST.clone(),
+		)];
+		if !self
+			.policies
+			.iter()
+			.any(|p| matches!(p, BackendPolicy::BackendAuth(_)))
+		{
+			pols.push(BackendPolicy::BackendAuth(BackendAuth::Aws(
+				AwsAuth::Implicit {},
+			)));
+		}
+		pols.extend(self.policies.iter().cloned());
+		let req = ::http::Request::builder()
+			.uri(uri)
+			.method(::http::Method::POST)
+			.header(::http::header::CONTENT_TYPE, "application/json")
+			.extension(AwsRegion {
+				region: self.region.to_string(),
+			})
+			.body(crate::http::Body::from(serde_json::to_vec(&request_body(
+				source, messages,
+			))?))?;
+		let be = SimpleBackend::Opaque(
+			ResourceName::new(strng::literal!("_bedrock-guardrails"), strng::literal!("")),
+			Target::Hostname(host, 443),
+		);
+		let resp = client.call_with_explicit_policies(req, be, pols).await?;
+		if !resp.status().is_success() {
+			anyhow::bail!("guardrails service returned {}", resp.status());
+		}
+		let resp: ApplyGuardrailResponse = json::from_response_body(resp).await?;
+		Ok(verdict(resp, messages.len()))
+	}
+}
+
+fn request_body(source: Source, messages: &[Strng]) -> serde_json::Value {
+	let source = match source {
+		Source::Input => "INPUT",
+		Source::Output => "OUTPUT",
+	};
+	serde_json::json!({
+		"source": source,
+		"content": messages
+			.iter()
+			.map(|m| serde_json::json!({"text": {"text": m}}))
+			.collect_vec(),
+	})
+}
+
+/// Interpret a guardrail's response to `messages` content blocks. If it only anonymized content,
+/// its outputs replace the messages; outputs that cannot be matched to messages are rejected.
+fn verdict(resp: ApplyGuardrailResponse, messages: usize) -> Verdict {
+	if resp.action != "GUARDRAIL_INTERVENED" {
+		return Verdict::Allow;
+	}
+	let mut blocked = false;
+	let mut categories = Vec::new();
+	for a in &resp.assessments {
+		findings(a, &mut blocked, &mut categories);
+	}
+	let categories = categories.into_iter().unique().collect_vec();
+	if blocked || resp.outputs.len() != messages {
+		return Verdict::Reject { categories };
+	}
+	Verdict::Mask {
+		messages: resp.outputs.into_iter().map(|o| o.text).collect(),
+		categories,
+	}
+}
+
+/// Collect the findings of an assessment: every object with an `action`, named by its `type` or
+/// `name`.
+fn findings(v: &serde_json::Value, blocked: &mut bool, categories: &mut Vec<Strng>) {
+	match v {
+		serde_json::Value::Object(obj) => {
+			if let Some(action) = obj.get("action").and_then(|a| a.as_str())
+				&& action != "NONE"
+			{
+				*blocked |= action == "BLOCKED";
+				if let Some(name) = obj
+					.get("type")
+					.or_else(|| obj.get("name"))
+					.and_then(|n| n.as_str())
+				{
+					categories.push(name.to_lowercase().into());
+				}
+			}
+			for v in obj.values() {
+				findings(v, blocked, categories);
+			}
+		},
+		serde_json::Value::Array(items) => {
+			for v in items {
+				findings(v, blocked, categories);
+			}
+		},
+		_ => {},
+	}
+}
+
+/// Call an external guardrails service, giving up after `timeout`. When the call fails and the
+/// guard fails open, returns None so the content is allowed through unchecked.
+pub async fn call<T>(
+	client: &PolicyClient,
+	phase: GuardrailPhase,
+	guard: &'static str,
+	timeout: Option<Duration>,
+	failure_mode: FailureMode,
+	fut: impl Future<Output = anyhow::Result<T>>,
+) -> anyhow::Result<Option<T>> {
+	let err = match bounded(timeout, fut).await {
+		Ok(v) => return Ok(Some(v)),
+		Err(e) => e,
+	};
+	client
+		.inputs
+		.metrics
+		.guardrail_failures
+		.get_or_create(&GuardrailFailureLabels {
+			phase,
+			guard: guard.into(),
+			action: match failure_mode {
+				FailureMode::Open => GuardrailAction::Allow,
+				FailureMode::Closed => GuardrailAction::Reject,
+			},
+		})
+		.inc();
+	match failure_mode {
+		FailureMode::Open => {
+			warn!(
+				guard,
+				"guardrails service failed, allowing content unchecked: {err}"
+			);
+			Ok(None)
+		},
+		FailureMode::Closed => Err(err),
+	}
+}
+
+async fn bounded<T>(
+	timeout: Option<Duration>,
+	fut: impl Future<Output = anyhow::Result<T>>,
+) -> anyhow::Result<T> {
+	let Some(timeout) = timeout else {
+		return fut.await;
+	};
+	tokio::time::timeout(timeout, fut)
+		.await
+		.unwrap_or_else(|_| Err(anyhow::anyhow!("timed out after {timeout:?}")))
+}
@@ -0,0 +1,120 @@
+use super::*;
+use crate::llm::policy::{PromptGuard, RequestGuardKind, ResponseGuardKind};
+
+fn response(v: serde_json::Value) -> ApplyGuardrailResponse {
+	serde_json::from_value(v).unwrap()
+}
+
+#[test]
+fn test_config() {
+	let guard: PromptGuard = serde_json::from_value(serde_json::json!({
+		"request": [{
+			"bedrockGuardrails": {
+				"guardrailIdentifier": "gr-123",
+				"guardrailVersion": "1",
+				"region": "us-west-2",
+				"timeout": "500ms",
+				"failureMode": "open",
+			},
+		}],
+		"response": [{
+			"webhook": {
+				"target": {"host": "guardrails.example.com:8080"},
+				"timeout": "2s",
+			},
+		}],
+	}))
+	.unwrap();
+	let RequestGuardKind::BedrockGuardrails(b) = &guard.request[0].kind else {
+		panic!("expected a bedrock guard");
+	};
+	assert_eq!(b.timeout, Some(Duration::from_millis(500)));
+	assert_eq!(b.failure_mode, FailureMode::Open);
+	assert!(b.policies.is_empty());
+	let ResponseGuardKind::Webhook(w) = &guard.response[0].kind else {
+		panic!("expected a webhook guard");
+	};
+	assert_eq!(w.timeout, Some(Duration::from_secs(2)));
+	assert_eq!(w.failure_mode, FailureMode::Closed);
+}
+
+#[test]
+fn test_request_body() {
+	assert_eq!(
+		request_body(Source::Output, &[strng::new("hello"), strng::new("world")]),
+		serde_json::json!({
+			"source": "OUTPUT",
+			"content": [{"text": {"text": "hello"}}, {"text": {"text": "world"}}],
+		})
+	);
+}
+
+#[test]
+fn test_verdict() {
+	assert_eq!(
+		verdict(
+			response(serde_json::json!({"action": "NONE", "outputs": [], "assessments": [{}]})),
+			1
+		),
+		Verdict::Allow
+	);
+
+	let blocked = response(serde_json::json!({
+		"action": "GUARDRAIL_INTERVENED",
+		"outputs": [{"text": "Sorry, I can't help with that."}],
+		"assessments": [{
+			"contentPolicy": {"filters": [
+				{"type": "VIOLENCE", "confidence": "HIGH", "action": "BLOCKED"},
+				{"type": "INSULTS", "confidence": "NONE", "action": "NONE"},
+			]},
+		}],
+	}));
+	assert_eq!(
+		verdict(blocked, 1),
+		Verdict::Reject {
+			categories: vec![strng::new("violence")]
+		}
+	);
+
+	let anonymized = serde_json::json!({
+		"action": "GUARDRAIL_INTERVENED",
+		"outputs": [{"text": "hi"}, {"text": "my email is {EMAIL}"}],
+		"assessments": [{
+			"sensitiveInformationPolicy": {"piiEntities": [
+				{"match": "jane@example.com", "type": "EMAIL", "action": "ANONYMIZED"},
+			]},
+		}],
+	});
+	assert_eq!(
+		verdict(response(anonymized.clone()), 2),
+		Verdict::Mask {
+			messages: vec!["hi".to_string(), "my email is {EMAIL}".to_string()],
+			categories: vec![strng::new("email")],
+		}
+	);
+	// Outputs that cannot be matched to the messages sent are rejected
+	assert_eq!(
+		verdict(response(anonymized), 3),
+		Verdict::Reject {
+			categories: vec![strng::new("email")]
+		}
+	);
+}
+
+#[tokio::test]
+async fn test_bounded() {
+	assert_eq!(
+		bounded(Some(Duration::from_secs(1)), async { Ok(1) })
+			.await
+			.unwrap(),
+		1
+	);
+	assert_eq!(bounded(None, async { Ok(2) }).await.unwrap(), 2);
+	let err = bounded::<()>(
+		Some(Duration::from_millis(10)),
+		std::future::pending::<anyhow::Result<()>>(),
+	)
+	.await
+	.unwrap_err();
+	assert!(err.to_string().starts_with("timed out"));
+}
@@ -1,25 +1,39 @@
 use ::http::HeaderMap;
 use bytes::Bytes;
+use itertools::Itertools;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::http::filters::HeaderModifier;
 use crate::http::jwt::Claims;
 use crate::http::{Response, StatusCode, auth};
+use crate::llm::policy::classifier::Classifier;
 use crate::llm::policy::webhook::{MaskActionBody, RequestAction, ResponseAction};
-use crate::llm::{AIError, RequestType, ResponseType};
+use crate::llm::{AIError, InputFormat, RequestType, ResponseType};
 use crate::proxy::httpproxy::PolicyClient;
+use crate::telemetry::audit;
+use crate::telemetry::metrics::{GuardrailAction, GuardrailLabels, GuardrailPhase};
 use crate::types::agent::{BackendPolicy, HeaderMatch, HeaderValueMatch, SimpleBackendReference};
 use crate::types::local::LocalBackendPolicies;
 use crate::*;
 
 pub mod webhook;
 
+pub mod classifier;
+pub mod context_window;
+pub mod guardrails;
+pub mod model_access;
 mod moderation;
-mod pii;
+pub(crate) mod pii;
+pub mod pii_redaction;
+pub mod response_cache;
+pub mod semantic_cache;
+pub mod stream_rewrite;
+pub mod system_prompt;
 #[cfg(test)]
 #[path = "tests.rs"]
 mod tests;
+pub mod token_limits;
 
 /// Routes stored in a deterministic order: **longest key to shortest key**, with `"*"` always last.
 ///
@@ -129,6 +143,22 @@ pub struct Policy {
 		schemars(with = "std::collections::HashMap<String, crate::llm::RouteType>")
 	)]
 	pub routes: SortedRoutes,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub semantic_cache: Option<semantic_cache::SemanticCache>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub response_cache: Option<response_cache::ResponseCache>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub token_limits: Option<token_limits::TokenLimits>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub context_window: Option<context_window::ContextWindow>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub stream_rewrite: Option<stream_rewrite::StreamRewrite>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub system_prompt: Option<system_prompt::SystemPrompt>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub pii_redaction: Option<pii_redaction::PiiRedaction>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub model_access: Option<model_access::ModelAccess>,
 }
 
 /// Wildcard pattern converted to regex for model name matching.
@@ -218,6 +248,7 @@ pub struct PromptGuard {
 enum GuardrailOutcome {
 	None,
 	Masked,
+	Annotated,
 	Rejected(Response),
 }
 
@@ -277,6 +308,20 @@ impl Policy {
 		None
 	}
 
+	/// Enforce the system prompt on a request, returning a rejection if it does not conform.
+	pub fn apply_system_prompt(
+		&self,
+		format: InputFormat,
+		chat: &mut dyn RequestType,
+	) -> Option<Response> {
+		self.system_prompt.as_ref()?.apply(format, chat)
+	}
+
+	/// Redact personal data from a request, returning the values to restore in the response.
+	pub fn apply_pii_redaction(&self, chat: &mut dyn RequestType) -> Option<pii_redaction::Redacted> {
+		self.pii_redaction.as_ref()?.apply(chat)
+	}
+
 	pub fn apply_prompt_enrichment(&self, chat: &mut dyn RequestType) {
 		if let Some(prompts) = &self.prompts {
 			if !prompts.prepend.is_empty() {
@@ -342,56 +387,73 @@ impl Policy {
 			.flat_map(|g| g.request.iter())
 		{
 			match &g.kind {
-				RequestGuardKind::Regex(rg) => match Self::apply_regex(req, rg, &g.rejection)? {
-					GuardrailOutcome::Rejected(res) => {
-						Self::record_guardrail_trip(
-							&client,
-							crate::telemetry::metrics::GuardrailPhase::Request,
-							crate::telemetry::metrics::GuardrailAction::Reject,
-						);
+				RequestGuardKind::Regex(rg) => {
+					let outcome = Self::apply_regex(req, rg, &g.rejection)?;
+					if let Some(res) = Self::record_guardrail_outcome(
+						&client,
+						GuardrailPhase::Request,
+						"regex",
+						vec![],
+						outcome,
+					) {
 						return Ok(Some(res));
-					},
-					GuardrailOutcome::Masked => {
-						Self::record_guardrail_trip(
-							&client,
-							crate::telemetry::metrics::GuardrailPhase::Request,
-							crate::telemetry::metrics::GuardrailAction::Mask,
-						);
-					},
-					GuardrailOutcome::None => {
-						Self::record_guardrail_trip(
-							&client,
-							crate::telemetry::metrics::GuardrailPhase::Request,
-							crate::telemetry::metrics::GuardrailAction::Allow,
-						);
-					},
+					}
 				},
 				RequestGuardKind::Webhook(wh) => {
 					if let Some(res) = Self::apply_webhook(req, http_headers, &client, wh).await? {
 						Self::record_guardrail_trip(
 							&client,
-							crate::telemetry::metrics::GuardrailPhase::Request,
-							crate::telemetry::metrics::GuardrailAction::Reject,
+							GuardrailPhase::Request,
+							GuardrailAction::Reject,
+							"webhook",
 						);
 						return Ok(Some(res));
 					}
 				},
 				RequestGuardKind::OpenAIModeration(m) => {
-					if let Some(res) =
-						Self::apply_moderation(req, claims.clone(), &client, &g.rejection, m).await?
-					{
-						Self::record_guardrail_trip(
-							&client,
-							crate::telemetry::metrics::GuardrailPhase::Request,
-							crate::telemetry::metrics::GuardrailAction::Reject,
-						);
+					let content = req
+						.get_messages()
+						.into_iter()
+						.map(|msg| msg.content)
+						.collect_vec();
+					let outcome =
+						Self::apply_moderation(content, claims.clone(), &client, &g.rejection, m).await?;
+					if let Some(res) = Self::record_guardrail_outcome(
+						&client,
+						GuardrailPhase::Request,
+						"openAIModeration",
+						vec![],
+						outcome,
+					) {
+						return Ok(Some(res));
+					}
+				},
+				RequestGuardKind::Classifier(c) => {
+					let (outcome, categories) = Self::apply_classifier(req, c, &g.rejection);
+					if let Some(res) = Self::record_guardrail_outcome(
+						&client,
+						GuardrailPhase::Request,
+						"classifier",
+						categories,
+						outcome,
+					) {
+						return Ok(Some(res));
+					}
+				},
+				RequestGuardKind::BedrockGuardrails(b) => {
+					let Some((outcome, categories)) =
+						Self::apply_bedrock_guardrails(req, &client, b, &g.rejection).await?
+					else {
+						continue;
+					};
+					if let Some(res) = Self::record_guardrail_outcome(
+						&client,
+						GuardrailPhase::Request,
+						"bedrockGuardrails",
+						categories,
+						outcome,
+					) {
 						return Ok(Some(res));
-					} else {
-						Self::record_guardrail_trip(
-							&client,
-							crate::telemetry::metrics::GuardrailPhase::Request,
-							crate::telemetry::metrics::GuardrailAction::Allow,
-						);
 					}
 				},
 			}
@@ -400,20 +462,169 @@ impl Policy {
 	}
 
 	async fn apply_moderation(
-		req: &mut dyn RequestType,
+		content: Vec<Strng>,
 		claims: Option<Claims>,
 		client: &PolicyClient,
 		rej: &RequestRejection,
 		moderation: &Moderation,
-	) -> anyhow::Result<Option<Response>> {
-		let resp = moderation::send_request(req, claims, client, moderation).await?;
+	) -> anyhow::Result<GuardrailOutcome> {
+		let resp = moderation::send_request(content, claims, client, moderation).await?;
 		if resp.results.iter().any(|r| r.flagged) {
-			Ok(Some(rej.as_response()))
+			Ok(GuardrailOutcome::Rejected(rej.as_response()))
 		} else {
-			Ok(None)
+			Ok(GuardrailOutcome::None)
+		}
+	}
+
+	fn apply_classifier(
+		req: &mut dyn RequestType,
+		classifier: &Classifier,
+		rej: &RequestRejection,
+	) -> (GuardrailOutcome, Vec<Strng>) {
+		let mut msgs = req.get_messages();
+		let mut matched = Vec::new();
+		for msg in &mut msgs {
+			let categories = classifier.classify(&msg.content);
+			if categories.is_empty() {
+				continue;
+			}
+			match classifier.action {
+				Action::Reject => return (GuardrailOutcome::Rejected(rej.as_response()), categories),
+				Action::Mask => msg.content = Self::classifier_mask(&categories),
+				Action::Annotate => {},
+			}
+			matched.extend(categories);
+		}
+		let outcome = Self::classifier_outcome(classifier, &matched);
+		if matches!(outcome, GuardrailOutcome::Masked) {
+			req.set_messages(msgs);
+		}
+		(outcome, matched.into_iter().unique().collect())
+	}
+
+	fn apply_classifier_response(
+		resp: &mut dyn ResponseType,
+		classifier: &Classifier,
+		rej: &RequestRejection,
+	) -> anyhow::Result<(GuardrailOutcome, Vec<Strng>)> {
+		let mut msgs = resp.to_webhook_choices();
+		let mut matched = Vec::new();
+		for msg in &mut msgs {
+			let categories = classifier.classify(&msg.message.content);
+			if categories.is_empty() {
+				continue;
+			}
+			match classifier.action {
+				Action::Reject => {
+					return Ok((GuardrailOutcome::Rejected(rej.as_response()), categories));
+				},
+				Action::Mask => msg.message.content = Self::classifier_mask(&categories),
+				Action::Annotate => {},
+			}
+			matched.extend(categories);
+		}
+		let outcome = Self::classifier_outcome(classifier, &matched);
+		if matches!(outcome, GuardrailOutcome::Masked) {
+			resp.set_webhook_choices(msgs)?;
+		}
+		Ok((outcome, matched.into_iter().unique().collect()))
+	}
+
+	/// Messages masked by a classifier are replaced with the categories they matched.
+	fn classifier_mask(categories: &[Strng]) -> Strng {
+		categories
+			.iter()
+			.map(|c| format!("<{}>", c.to_uppercase()))
+			.join(" ")
+			.into()
+	}
+
+	fn classifier_outcome(classifier: &Classifier, matched: &[Strng]) -> GuardrailOutcome {
+		match classifier.action {
+			_ if matched.is_empty() => GuardrailOutcome::None,
+			Action::Mask => GuardrailOutcome::Masked,
+			Action::Annotate | Action::Reject => GuardrailOutcome::Annotated,
 		}
 	}
 
+	/// Check messages with a Bedrock guardrail. Returns None if the service failed and the guard
+	/// fails open.
+	async fn apply_bedrock_guardrails(
+		req: &mut dyn RequestType,
+		client: &PolicyClient,
+		bedrock: &guardrails::BedrockGuardrails,
+		rej: &RequestRejection,
+	) -> anyhow::Result<Option<(GuardrailOutcome, Vec<Strng>)>> {
+		let mut msgs = req.get_messages();
+		let content = msgs.iter().map(|m| m.content.clone()).collect_vec();
+		let Some(verdict) = guardrails::call(
+			client,
+			GuardrailPhase::Request,
+			"bedrockGuardrails",
+			bedrock.timeout,
+			bedrock.failure_mode,
+			bedrock.apply(client, guardrails::Source::Input, &content),
+		)
+		.await?
+		else {
+			return Ok(None);
+		};
+		Ok(Some(match verdict {
+			guardrails::Verdict::Allow => (GuardrailOutcome::None, vec![]),
+			guardrails::Verdict::Mask {
+				messages,
+				categories,
+			} => {
+				for (msg, content) in msgs.iter_mut().zip(messages) {
+					msg.content = content.into();
+				}
+				req.set_messages(msgs);
+				(GuardrailOutcome::Masked, categories)
+			},
+			guardrails::Verdict::Reject { categories } => {
+				(GuardrailOutcome::Rejected(rej.as_response()), categories)
+			},
+		}))
+	}
+
+	async fn apply_bedrock_guardrails_response(
+		resp: &mut dyn ResponseType,
+		client: &PolicyClient,
+		bedrock: &guardrails::BedrockGuardrails,
+		rej: &RequestRejection,
+	) -> anyhow::Result<Option<(GuardrailOutcome, Vec<Strng>)>> {
+		let mut msgs = resp.to_webhook_choices();
+		let content = msgs.iter().map(|m| m.message.content.clone()).collect_vec();
+		let Some(verdict) = guardrails::call(
+			client,
+			GuardrailPhase::Response,
+			"bedrockGuardrails",
+			bedrock.timeout,
+			bedrock.failure_mode,
+			bedrock.apply(client, guardrails::Source::Output, &content),
+		)
+		.await?
+		else {
+			return Ok(None);
+		};
+		Ok(Some(match verdict {
+			guardrails::Verdict::Allow => (GuardrailOutcome::None, vec![]),
+			guardrails::Verdict::Mask {
+				messages,
+				categories,
+			} => {
+				for (msg, content) in msgs.iter_mut().zip(messages) {
+					msg.message.content = content.into();
+				}
+				resp.set_webhook_choices(msgs)?;
+				(GuardrailOutcome::Masked, categories)
+			},
+			guardrails::Verdict::Reject { categories } => {
+				(GuardrailOutcome::Rejected(rej.as_response()), categories)
+			},
+		}))
+	}
+
 	fn apply_regex(
 		req: &mut dyn RequestType,
 		rgx: &RegexRules,
@@ -421,6 +632,7 @@ impl Policy {
 	) -> anyhow::Result<GuardrailOutcome> {
 		let mut msgs = req.get_messages();
 		let mut any_changed = false;
+		let mut any_annotated = false;
 		for msg in &mut msgs {
 			match Self::apply_prompt_guard_regex(&msg.content, rgx) {
 				Some(RegexResult::Reject) => {
@@ -430,6 +642,7 @@ impl Policy {
 					any_changed = true;
 					msg.content = content.into();
 				},
+				Some(RegexResult::Annotate) => any_annotated = true,
 				None => {},
 			}
 		}
@@ -437,6 +650,9 @@ impl Policy {
 			req.set_messages(msgs);
 			return Ok(GuardrailOutcome::Masked);
 		}
+		if any_annotated {
+			return Ok(GuardrailOutcome::Annotated);
+		}
 		Ok(GuardrailOutcome::None)
 	}
 
@@ -447,6 +663,7 @@ impl Policy {
 	) -> anyhow::Result<GuardrailOutcome> {
 		let mut msgs = resp.to_webhook_choices();
 		let mut any_changed = false;
+		let mut any_annotated = false;
 		for msg in &mut msgs {
 			match Self::apply_prompt_guard_regex(&msg.message.content, rgx) {
 				Some(RegexResult::Reject) => {
@@ -456,6 +673,7 @@ impl Policy {
 					any_changed = true;
 					msg.message.content = content.into();
 				},
+				Some(RegexResult::Annotate) => any_annotated = true,
 				None => {},
 			}
 		}
@@ -463,6 +681,9 @@ impl Policy {
 			resp.set_webhook_choices(msgs)?;
 			return Ok(GuardrailOutcome::Masked);
 		}
+		if any_annotated {
+			return Ok(GuardrailOutcome::Annotated);
+		}
 		Ok(GuardrailOutcome::None)
 	}
 
@@ -474,7 +695,18 @@ impl Policy {
 	) -> anyhow::Result<Option<Response>> {
 		let messsages = req.get_messages();
 		let headers = Self::get_webhook_forward_headers(http_headers, &webhook.forward_header_matches);
-		let whr = webhook::send_request(client, &webhook.target, &headers, messsages).await?;
+		let Some(whr) = guardrails::call(
+			client,
+			GuardrailPhase::Request,
+			"webhook",
+			webhook.timeout,
+			webhook.failure_mode,
+			webhook::send_request(client, &webhook.target, &headers, messsages),
+		)
+		.await?
+		else {
+			return Ok(None);
+		};
 		match whr.action {
 			RequestAction::Mask(mask) => {
 				debug!(
@@ -490,8 +722,9 @@ impl Policy {
 				req.set_messages(msgs);
 				Self::record_guardrail_trip(
 					client,
-					crate::telemetry::metrics::GuardrailPhase::Request,
-					crate::telemetry::metrics::GuardrailAction::Mask,
+					GuardrailPhase::Request,
+					GuardrailAction::Mask,
+					"webhook",
 				);
 			},
 			RequestAction::Reject(rej) => {
@@ -516,8 +749,9 @@ impl Policy {
 				);
 				Self::record_guardrail_trip(
 					client,
-					crate::telemetry::metrics::GuardrailPhase::Request,
-					crate::telemetry::metrics::GuardrailAction::Allow,
+					GuardrailPhase::Request,
+					GuardrailAction::Allow,
+					"webhook",
 				);
 			},
 		}
@@ -532,7 +766,18 @@ impl Policy {
 	) -> anyhow::Result<Option<Response>> {
 		let messsages = resp.to_webhook_choices();
 		let headers = Self::get_webhook_forward_headers(http_headers, &webhook.forward_header_matches);
-		let whr = webhook::send_response(client, &webhook.target, &headers, messsages).await?;
+		let Some(whr) = guardrails::call(
+			client,
+			GuardrailPhase::Response,
+			"webhook",
+			webhook.timeout,
+			webhook.failure_mode,
+			webhook::send_response(client, &webhook.target, &headers, messsages),
+		)
+		.await?
+		else {
+			return Ok(None);
+		};
 		match whr.action {
 			ResponseAction::Mask(mask) => {
 				debug!(
@@ -548,8 +793,9 @@ impl Policy {
 				resp.set_webhook_choices(msgs)?;
 				Self::record_guardrail_trip(
 					client,
-					crate::telemetry::metrics::GuardrailPhase::Response,
-					crate::telemetry::metrics::GuardrailAction::Mask,
+					GuardrailPhase::Response,
+					GuardrailAction::Mask,
+					"webhook",
 				);
 			},
 			ResponseAction::Reject(rej) => {
@@ -561,8 +807,9 @@ impl Policy {
 				);
 				Self::record_guardrail_trip(
 					client,
-					crate::telemetry::metrics::GuardrailPhase::Response,
-					crate::telemetry::metrics::GuardrailAction::Reject,
+					GuardrailPhase::Response,
+					GuardrailAction::Reject,
+					"webhook",
 				);
 				return Ok(Some(
 					::http::response::Builder::new()
@@ -579,8 +826,9 @@ impl Policy {
 				);
 				Self::record_guardrail_trip(
 					client,
-					crate::telemetry::metrics::GuardrailPhase::Response,
-					crate::telemetry::metrics::GuardrailAction::Allow,
+					GuardrailPhase::Response,
+					GuardrailAction::Allow,
+					"webhook",
 				);
 			},
 		}
@@ -626,17 +874,54 @@ impl Policy {
 		headers
 	}
 
+	/// Record the outcome of a guard, returning the response to reject the request with, if any.
+	fn record_guardrail_outcome(
+		client: &PolicyClient,
+		phase: GuardrailPhase,
+		guard: &'static str,
+		categories: Vec<Strng>,
+		outcome: GuardrailOutcome,
+	) -> Option<Response> {
+		let (action, res) = match outcome {
+			GuardrailOutcome::None => (GuardrailAction::Allow, None),
+			GuardrailOutcome::Masked => (GuardrailAction::Mask, None),
+			GuardrailOutcome::Annotated => (GuardrailAction::Annotate, None),
+			GuardrailOutcome::Rejected(res) => (GuardrailAction::Reject, Some(res)),
+		};
+		Self::record_guardrail_verdict(client, phase, action, guard, categories);
+		res
+	}
+
 	fn record_guardrail_trip(
 		client: &PolicyClient,
-		phase: crate::telemetry::metrics::GuardrailPhase,
-		action: crate::telemetry::metrics::GuardrailAction,
+		phase: GuardrailPhase,
+		action: GuardrailAction,
+		guard: &'static str,
+	) {
+		Self::record_guardrail_verdict(client, phase, action, guard, vec![])
+	}
+
+	fn record_guardrail_verdict(
+		client: &PolicyClient,
+		phase: GuardrailPhase,
+		action: GuardrailAction,
+		guard: &'static str,
+		categories: Vec<Strng>,
 	) {
 		client
 			.inputs
 			.metrics
 			.guardrail_checks
-			.get_or_create(&crate::telemetry::metrics::GuardrailLabels { phase, action })
+			.get_or_create(&GuardrailLabels { phase, action })
 			.inc();
+		if action != GuardrailAction::Allow {
+			audit::record_guardrail(audit::GuardrailDetails {
+				phase,
+				action,
+				guard,
+				categories,
+			});
+		}
 	}
 
 	// fn convert_message(r: Message) -> ChatCompletionRequestMessage {
@@ -666,20 +951,16 @@ impl Policy {
 		for r in &rgx.rules {
 			match r {
 				RegexRule::Builtin { builtin } => {
-					let rec = match builtin {
-						Builtin::Ssn => &*pii::SSN,
-						Builtin::CreditCard => &*pii::CC,
-						Builtin::PhoneNumber => &*pii::PHONE,
-						Builtin::Email => &*pii::EMAIL,
-						Builtin::CaSin => &*pii::CA_SIN,
-					};
-					let results = pii::recognizer(rec, &current_content);
+					let results = pii::recognize_builtin(builtin, &current_content);
 
 					if !results.is_empty() {
 						match &rgx.action {
 							Action::Reject => {
 								return Some(RegexResult::Reject);
 							},
+							Action::Annotate => {
+								return Some(RegexResult::Annotate);
+							},
 							Action::Mask => {
 								// Sort in reverse to avoid index shifting during replacement
 								let mut sorted_results = results;
@@ -696,7 +977,10 @@ impl Policy {
 						}
 					}
 				},
-				RegexRule::Regex { pattern } => {
+				RegexRule::Regex { pattern }
+				| RegexRule::Keywords {
+					keywords: Keywords { regex: pattern, .. },
+				} => {
 					let ranges: Vec<std::ops::Range<usize>> = pattern
 						.find_iter(&current_content)
 						.map(|m| m.range())
@@ -707,6 +991,9 @@ impl Policy {
 							Action::Reject => {
 								return Some(RegexResult::Reject);
 							},
+							Action::Annotate => {
+								return Some(RegexResult::Annotate);
+							},
 							Action::Mask => {
 								// Process matches in reverse order to avoid index shifting
 								for range in ranges.into_iter().rev() {
@@ -734,37 +1021,66 @@ impl Policy {
 	) -> anyhow::Result<Option<Response>> {
 		for g in guards {
 			match &g.kind {
-				ResponseGuardKind::Regex(rg) => match Self::apply_regex_response(resp, rg, &g.rejection)? {
-					GuardrailOutcome::Rejected(res) => {
-						Self::record_guardrail_trip(
-							client,
-							crate::telemetry::metrics::GuardrailPhase::Response,
-							crate::telemetry::metrics::GuardrailAction::Reject,
-						);
+				ResponseGuardKind::Regex(rg) => {
+					let outcome = Self::apply_regex_response(resp, rg, &g.rejection)?;
+					if let Some(res) = Self::record_guardrail_outcome(
+						client,
+						GuardrailPhase::Response,
+						"regex",
+						vec![],
+						outcome,
+					) {
 						return Ok(Some(res));
-					},
-					GuardrailOutcome::Masked => {
-						Self::record_guardrail_trip(
-							client,
-							crate::telemetry::metrics::GuardrailPhase::Response,
-							crate::telemetry::metrics::GuardrailAction::Mask,
-						);
-					},
-					GuardrailOutcome::None => {
-						Self::record_guardrail_trip(
-							client,
-							crate::telemetry::metrics::GuardrailPhase::Response,
-							crate::telemetry::metrics::GuardrailAction::Allow,
-						);
-					},
+					}
 				},
 				ResponseGuardKind::Webhook(wh) => {
+					// Verdicts are recorded by apply_webhook_response
 					if let Some(res) = Self::apply_webhook_response(resp, http_headers, client, wh).await? {
-						Self::record_guardrail_trip(
-							client,
-							crate::telemetry::metrics::GuardrailPhase::Response,
-							crate::telemetry::metrics::GuardrailAction::Reject,
-						);
+						return Ok(Some(res));
+					}
+				},
+				ResponseGuardKind::OpenAIModeration(m) => {
+					let content = resp
+						.to_webhook_choices()
+						.into_iter()
+						.map(|c| c.message.content)
+						.collect_vec();
+					let outcome = Self::apply_moderation(content, None, client, &g.rejection, m).await?;
+					if let Some(res) = Self::record_guardrail_outcome(
+						client,
+						GuardrailPhase::Response,
+						"openAIModeration",
+						vec![],
+						outcome,
+					) {
+						return Ok(Some(res));
+					}
+				},
+				ResponseGuardKind::Classifier(c) => {
+					let (outcome, categories) = Self::apply_classifier_response(resp, c, &g.rejection)?;
+					if let Some(res) = Self::record_guardrail_outcome(
+						client,
+						GuardrailPhase::Response,
+						"classifier",
+						categories,
+						outcome,
+					) {
+						return Ok(Some(res));
+					}
+				},
+				ResponseGuardKind::BedrockGuardrails(b) => {
+					let Some((outcome, categories)) =
+						Self::apply_bedrock_guardrails_response(resp, client, b, &g.rejection).await?
+					else {
+						continue;
+					};
+					if let Some(res) = Self::record_guardrail_outcome(
+						client,
+						GuardrailPhase::Response,
+						"bedrockGuardrails",
+						categories,
+						outcome,
+					) {
 						return Ok(Some(res));
 					}
 				},
@@ -776,6 +1092,7 @@ impl Policy {
 
 enum RegexResult {
 	Mask(String),
+	Annotate,
 	Reject,
 }
 
@@ -792,6 +1109,8 @@ pub enum RequestGuardKind {
 	Regex(RegexRules),
 	Webhook(Webhook),
 	OpenAIModeration(Moderation),
+	Classifier(Classifier),
+	BedrockGuardrails(guardrails::BedrockGuardrails),
 }
 
 #[apply(schema!)]
@@ -812,6 +1131,37 @@ pub enum RegexRule {
 		#[cfg_attr(feature = "schema", schemars(with = "String"))]
 		pattern: regex::Regex,
 	},
+	Keywords {
+		/// Words or phrases to match, case-insensitively and on word boundaries.
+		#[cfg_attr(feature = "schema", schemars(with = "Vec<String>"))]
+		keywords: Keywords,
+	},
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "Vec<String>", into = "Vec<String>")]
+pub struct Keywords {
+	keywords: Vec<String>,
+	regex: regex::Regex,
+}
+
+impl TryFrom<Vec<String>> for Keywords {
+	type Error = anyhow::Error;
+
+	fn try_from(keywords: Vec<String>) -> Result<Self, Self::Error> {
+		if keywords.is_empty() || keywords.iter().any(|k| k.is_empty()) {
+			anyhow::bail!("keywords must not be empty");
+		}
+		let alternatives = keywords.iter().map(|k| regex::escape(k)).join("|");
+		let regex = regex::Regex::new(&format!(r"(?i)\b(?:{alternatives})\b"))?;
+		Ok(Keywords { keywords, regex })
+	}
+}
+
+impl From<Keywords> for Vec<String> {
+	fn from(k: Keywords) -> Self {
+		k.keywords
+	}
 }
 
 impl RequestRejection {
@@ -861,6 +1211,17 @@ pub struct Webhook {
 	pub target: SimpleBackendReference,
 	#[serde(default, skip_serializing_if = "Vec::is_empty")]
 	pub forward_header_matches: Vec<HeaderMatch>,
+	/// How long to wait for the webhook. Defaults to no limit.
+	#[serde(
+		default,
+		skip_serializing_if = "Option::is_none",
+		with = "serde_dur_option"
+	)]
+	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+	pub timeout: Option<Duration>,
+	/// What to do when the webhook fails or times out. Defaults to `closed`.
+	#[serde(default)]
+	pub failure_mode: guardrails::FailureMode,
 }
 
 #[apply(schema!)]
@@ -889,6 +1250,8 @@ pub enum Action {
 	#[default]
 	Mask,
 	Reject,
+	/// Allow the content unchanged, recording the verdict in metrics and the audit log.
+	Annotate,
 }
 
 #[apply(schema!)]
@@ -925,6 +1288,9 @@ pub struct ResponseGuard {
 pub enum ResponseGuardKind {
 	Regex(RegexRules),
 	Webhook(Webhook),
+	OpenAIModeration(Moderation),
+	Classifier(Classifier),
+	BedrockGuardrails(guardrails::BedrockGuardrails),
 }
 
 #[apply(schema!)]
@@ -937,6 +1303,48 @@ fn default_body() -> Bytes {
 	Bytes::from_static(b"The request was rejected due to inappropriate content")
 }
 
+/// A 400 response rejecting a request, in the error format of the client's API.
+pub fn invalid_request_response(format: InputFormat, code: &str, message: String) -> Response {
+	error_response(
+		format,
+		StatusCode::BAD_REQUEST,
+		"invalid_request_error",
+		code,
+		message,
+	)
+}
+
+/// A response rejecting a request, in the error format of the client's API.
+pub fn error_response(
+	format: InputFormat,
+	status: StatusCode,
+	error_type: &str,
+	code: &str,
+	message: String,
+) -> Response {
+	let body = match format {
+		InputFormat::Messages => serde_json::json!({
+			"type": "error",
+			"error": {
+				"type": error_type,
+				"message": message,
+			},
+		}),
+		_ => serde_json::json!({
+			"error": {
+				"type": error_type,
+				"code": code,
+				"message": message,
+			},
+		}),
+	};
+	::http::Response::builder()
+		.status(status)
+		.header(::http::header::CONTENT_TYPE, "application/json")
+		.body(crate::http::Body::from(body.to_string()))
+		.expect("static response should succeed")
+}
+
 #[test]
 fn test_prompt_caching_policy_deserialization() {
 	use serde_json::json;
@@ -0,0 +1,93 @@
+use macro_rules_attribute::apply;
+
+use crate::http::{Response, StatusCode};
+use crate::llm::InputFormat;
+use crate::llm::policy::error_response;
+use crate::telemetry::audit;
+use crate::telemetry::log::RequestLog;
+use crate::*;
+
+#[cfg(test)]
+#[path = "model_access_tests.rs"]
+mod tests;
+
+/// Restrict which models each identity may request.
+#[apply(schema!)]
+pub struct ModelAccess {
+	/// Rules granting models to identities. The first rule that applies to a request decides which
+	/// models it may use, and requests no rule applies to are rejected.
+	pub rules: Vec<ModelAccessRule>,
+}
+
+#[apply(schema!)]
+pub struct ModelAccessRule {
+	/// CEL expression for the requests the rule applies to, such as
+	/// `jwt.groups.exists(g, g == "interns")`. If unset, the rule applies to all requests.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub when: Option<Arc<cel::Expression>>,
+	/// The models that may be requested. `*` matches any characters, so `gpt-4o-mini*` allows every
+	/// version of `gpt-4o-mini`.
+	pub models: Vec<Strng>,
+}
+
+impl ModelAccess {
+	pub fn expressions(&self) -> impl Iterator<Item = &cel::Expression> {
+		self.rules.iter().filter_map(|r| r.when.as_deref())
+	}
+
+	/// Check that the request may use `model`, returning a rejection if it may not.
+	pub fn apply(
+		&self,
+		format: InputFormat,
+		model: &str,
+		log: Option<&RequestLog>,
+	) -> Option<Response> {
+		let exec = match log {
+			Some(log) => log.cel.ctx_borrow().build(),
+			None => cel::ContextBuilder::new().build(),
+		};
+		if exec.is_ok_and(|exec| self.allows(&exec, model)) {
+			return None;
+		}
+		debug!(model, "rejecting request for a model it may not use");
+		let message = format!("model `{model}` is not allowed");
+		if let Some(log) = log {
+			audit::record_model_denied(log, message.clone());
+		}
+		Some(error_response(
+			format,
+			StatusCode::FORBIDDEN,
+			"permission_error",
+			"model_not_allowed",
+			message,
+		))
+	}
+
+	fn allows(&self, exec: &cel::Executor, model: &str) -> bool {
+		self
+			.rules
+			.iter()
+			.find(|r| r.when.as_ref().is_none_or(|w| exec.eval_bool(w)))
+			.is_some_and(|r| r.models.iter().any(|m| matches(m, model)))
+	}
+}
+
+/// Whether `model` matches `pattern`, where `*` matches any characters.
+pub(super) fn matches(pattern: &str, model: &str) -> bool {
+	let mut parts = pattern.split('*');
+	let Some(mut rest) = parts.next().and_then(|first| model.strip_prefix(first)) else {
+		return false;
+	};
+	let parts = parts.collect::<Vec<_>>();
+	let Some((last, middle)) = parts.split_last() else {
+		// No wildcards, so the model must match exactly.
+		return rest.is_empty();
+	};
+	for part in middle {
+		let Some(i) = rest.find(part) else {
+			return false;
+		};
+		rest = &rest[i + part.len()..];
+	}
+	rest.ends_with(last)
+}
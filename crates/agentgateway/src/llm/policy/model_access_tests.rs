@@ -0,0 +1,74 @@
+use secrecy::SecretString;
+use serde_json::Map;
+
+use super::*;
+use crate::http::jwt::Claims;
+
+fn access() -> ModelAccess {
+	serde_json::from_value(serde_json::json!({
+		"rules": [
+			{"when": r#"jwt.group == "interns""#, "models": ["gpt-4o-mini*", "claude-haiku-*"]},
+			{"when": r#"jwt.group == "engineering""#, "models": ["*"]},
+		],
+	}))
+	.unwrap()
+}
+
+fn exec(access: &ModelAccess, group: Option<&str>) -> cel::Executor<'static> {
+	let mut ctx = cel::ContextBuilder::new();
+	for expr in access.expressions() {
+		ctx.register_expression(expr);
+	}
+	if let Some(group) = group {
+		ctx.with_jwt(&Claims {
+			inner: Map::from_iter([("group".to_string(), group.into())]),
+			jwt: SecretString::new("".into()),
+		});
+	}
+	ctx.build().unwrap()
+}
+
+#[test]
+fn test_matches() {
+	assert!(matches("gpt-4o", "gpt-4o"));
+	assert!(!matches("gpt-4o", "gpt-4o-mini"));
+	assert!(matches("gpt-4o*", "gpt-4o-mini"));
+	assert!(matches("*", "anything"));
+	assert!(matches("claude-*-latest", "claude-haiku-latest"));
+	assert!(!matches("claude-*-latest", "claude-haiku"));
+	assert!(matches("*-mini", "gpt-4o-mini"));
+	// Wildcards do not overlap the literal parts around them
+	assert!(!matches("ab*ba", "aba"));
+}
+
+#[test]
+fn test_allows() {
+	let a = access();
+	let interns = exec(&a, Some("interns"));
+	assert!(a.allows(&interns, "gpt-4o-mini"));
+	assert!(a.allows(&interns, "claude-haiku-4-5"));
+	assert!(!a.allows(&interns, "gpt-4o"));
+
+	let engineering = exec(&a, Some("engineering"));
+	assert!(a.allows(&engineering, "gpt-4o"));
+
+	// Requests no rule applies to are rejected
+	assert!(!a.allows(&exec(&a, Some("sales")), "gpt-4o-mini"));
+	assert!(!a.allows(&exec(&a, None), "gpt-4o-mini"));
+}
+
+#[test]
+fn test_rejection() {
+	let a = access();
+	let resp = a.apply(InputFormat::Completions, "gpt-4o", None).unwrap();
+	assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+	let a: ModelAccess = serde_json::from_value(serde_json::json!({
+		"rules": [{"models": ["gpt-4o-mini"]}],
+	}))
+	.unwrap();
+	assert!(
+		a.apply(InputFormat::Messages, "gpt-4o-mini", None)
+			.is_none()
+	);
+}
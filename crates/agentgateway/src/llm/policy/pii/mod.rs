@@ -2,6 +2,7 @@
 
 use once_cell::sync::Lazy;
 
+use crate::llm::policy::Builtin;
 use crate::llm::policy::pii::email_recognizer::EmailRecognizer;
 use crate::llm::policy::pii::phone_recognizer::PhoneRecognizer;
 use crate::llm::policy::pii::recognizer::Recognizer;
@@ -31,6 +32,21 @@ pub static SSN: Lazy<Box<dyn Recognizer + Sync + Send + 'static>> =
 pub static CA_SIN: Lazy<Box<dyn Recognizer + Sync + Send + 'static>> =
 	Lazy::new(|| Box::new(ca_sin_recognizer::CaSinRecognizer::new()));
 
+/// Run the recognizer for a builtin entity type over the text.
+pub fn recognize_builtin(
+	builtin: &Builtin,
+	text: &str,
+) -> Vec<recognizer_result::RecognizerResult> {
+	let rec = match builtin {
+		Builtin::Ssn => &*SSN,
+		Builtin::CreditCard => &*CC,
+		Builtin::PhoneNumber => &*PHONE,
+		Builtin::Email => &*EMAIL,
+		Builtin::CaSin => &*CA_SIN,
+	};
+	recognizer(rec, text)
+}
+
 #[allow(clippy::borrowed_box)]
 pub fn recognizer(
 	r: &Box<dyn Recognizer + Sync + Send + 'static>,
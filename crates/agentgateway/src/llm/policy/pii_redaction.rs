@@ -0,0 +1,236 @@
+use bytes::Bytes;
+use macro_rules_attribute::apply;
+
+use crate::http::Response;
+use crate::llm::RequestType;
+use crate::llm::policy::{Builtin, pii};
+use crate::parse;
+use crate::*;
+
+#[cfg(test)]
+#[path = "pii_redaction_tests.rs"]
+mod tests;
+
+/// Redact personal data from prompts before they are sent to the provider.
+#[apply(schema!)]
+pub struct PiiRedaction {
+	/// The personal data to redact.
+	pub rules: Vec<RedactionRule>,
+	/// The text each value is replaced with. `{entity}` is replaced with the entity type, such as
+	/// `EMAIL_ADDRESS`, and `{n}` with a number distinguishing the values of each type in the
+	/// request. Defaults to `<{entity}_{n}>`.
+	#[serde(default = "default_replacement")]
+	pub replacement: String,
+	/// Replace the redacted values back into responses, so clients see the original values while the
+	/// provider never does. Only replacements that identify a single value can be restored.
+	#[serde(default)]
+	pub restore: bool,
+}
+
+#[apply(schema!)]
+#[serde(untagged)]
+pub enum RedactionRule {
+	Builtin {
+		builtin: Builtin,
+		/// Replacement for this rule, instead of the policy's.
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		replacement: Option<String>,
+	},
+	Regex {
+		#[serde(with = "serde_regex")]
+		#[cfg_attr(feature = "schema", schemars(with = "String"))]
+		pattern: regex::Regex,
+		/// The entity type of matches, for `{entity}` in replacements.
+		name: Strng,
+		/// Replacement for this rule, instead of the policy's.
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		replacement: Option<String>,
+	},
+}
+
+fn default_replacement() -> String {
+	"<{entity}_{n}>".to_string()
+}
+
+/// A value found in the text, to be replaced.
+struct Match<'a> {
+	start: usize,
+	end: usize,
+	entity: Strng,
+	replacement: &'a str,
+}
+
+impl PiiRedaction {
+	/// Redact the request's messages, returning the values to restore in the response, if enabled.
+	pub fn apply(&self, req: &mut dyn RequestType) -> Option<Redacted> {
+		let mut redactor = Redactor::default();
+		let mut msgs = req.get_messages();
+		let mut any_changed = false;
+		for msg in &mut msgs {
+			if let Some(content) = self.redact(&mut redactor, &msg.content) {
+				msg.content = content.into();
+				any_changed = true;
+			}
+		}
+		if !any_changed {
+			return None;
+		}
+		debug!(
+			values = redactor.seen.len(),
+			"redacted personal data from prompt"
+		);
+		req.set_messages(msgs);
+		self.restore.then(|| redactor.into_redacted())
+	}
+
+	/// Redact texts outside of a request, such as logged prompts and completions, replacing each
+	/// value the same way in every text.
+	pub fn redact_texts<'a>(&self, texts: impl IntoIterator<Item = &'a mut String>) {
+		let mut redactor = Redactor::default();
+		for text in texts {
+			if let Some(redacted) = self.redact(&mut redactor, text) {
+				*text = redacted;
+			}
+		}
+	}
+
+	fn redact(&self, redactor: &mut Redactor, text: &str) -> Option<String> {
+		let mut matches = vec![];
+		for rule in &self.rules {
+			match rule {
+				RedactionRule::Builtin {
+					builtin,
+					replacement,
+				} => {
+					let replacement = replacement.as_deref().unwrap_or(&self.replacement);
+					matches.extend(
+						pii::recognize_builtin(builtin, text)
+							.into_iter()
+							.map(|r| Match {
+								start: r.start,
+								end: r.end,
+								entity: strng::new(&r.entity_type),
+								replacement,
+							}),
+					);
+				},
+				RedactionRule::Regex {
+					pattern,
+					name,
+					replacement,
+				} => {
+					let replacement = replacement.as_deref().unwrap_or(&self.replacement);
+					matches.extend(pattern.find_iter(text).map(|m| Match {
+						start: m.start(),
+						end: m.end(),
+						entity: name.clone(),
+						replacement,
+					}));
+				},
+			}
+		}
+		if matches.is_empty() {
+			return None;
+		}
+		// Where matches overlap, the earliest (and then longest) wins.
+		matches.sort_by_key(|m| (m.start, std::cmp::Reverse(m.end)));
+		let mut out = String::with_capacity(text.len());
+		let mut pos = 0;
+		for m in matches {
+			if m.start < pos {
+				continue;
+			}
+			out.push_str(&text[pos..m.start]);
+			out.push_str(&redactor.placeholder(&m, &text[m.start..m.end]));
+			pos = m.end;
+		}
+		out.push_str(&text[pos..]);
+		Some(out)
+	}
+}
+
+/// Placeholders assigned to the values redacted from a request, so each value is replaced the same
+/// way wherever it appears.
+#[derive(Default)]
+struct Redactor {
+	/// Value to placeholder
+	seen: HashMap<String, String>,
+	/// Distinct values per entity type
+	counts: HashMap<Strng, usize>,
+}
+
+impl Redactor {
+	fn placeholder(&mut self, m: &Match, value: &str) -> String {
+		if let Some(p) = self.seen.get(value) {
+			return p.clone();
+		}
+		let n = self.counts.entry(m.entity.clone()).or_default();
+		*n += 1;
+		let p = m
+			.replacement
+			.replace("{entity}", &m.entity)
+			.replace("{n}", &n.to_string());
+		self.seen.insert(value.to_string(), p.clone());
+		p
+	}
+
+	fn into_redacted(self) -> Redacted {
+		let mut values: HashMap<String, Option<String>> = HashMap::new();
+		for (value, placeholder) in self.seen {
+			values
+				.entry(placeholder)
+				// A placeholder standing for several values cannot be restored.
+				.and_modify(|v| *v = None)
+				.or_insert(Some(value));
+		}
+		let mut values = values
+			.into_iter()
+			.filter_map(|(p, v)| Some((p, v?)))
+			.collect::<Vec<_>>();
+		// Replace longer placeholders first, so `<X_10>` is not taken for `<X_1>`.
+		values.sort_by_key(|(p, _)| std::cmp::Reverse(p.len()));
+		Redacted {
+			values: Arc::new(values),
+		}
+	}
+}
+
+/// The values redacted from a request, carried to the response so they can be restored.
+#[derive(Debug, Clone)]
+pub struct Redacted {
+	/// Placeholder and original value, longest placeholder first
+	values: Arc<Vec<(String, String)>>,
+}
+
+impl Redacted {
+	/// Restore the redacted values in a JSON response body. Bodies that are not JSON are unchanged.
+	pub fn restore_body(&self, body: Bytes) -> Bytes {
+		let Ok(mut v) = serde_json::from_slice::<serde_json::Value>(&body) else {
+			return body;
+		};
+		self.restore_json(&mut v);
+		serde_json::to_vec(&v).map(Bytes::from).unwrap_or(body)
+	}
+
+	/// Restore the redacted values in each event of a streaming response. Values are only restored
+	/// where the placeholder is sent whole in a single event.
+	pub fn restore_stream(self, resp: Response) -> Response {
+		let buffer_limit = http::response_buffer_limit(&resp);
+		resp.map(|b| parse::sse::json_rewrite(b, buffer_limit, move |v| self.restore_json(v), Vec::new))
+	}
+
+	fn restore_json(&self, v: &mut serde_json::Value) {
+		match v {
+			serde_json::Value::String(s) => {
+				for (placeholder, value) in self.values.iter() {
+					if s.contains(placeholder.as_str()) {
+						*s = s.replace(placeholder.as_str(), value);
+					}
+				}
+			},
+			serde_json::Value::Array(a) => a.iter_mut().for_each(|v| self.restore_json(v)),
+			serde_json::Value::Object(m) => m.values_mut().for_each(|v| self.restore_json(v)),
+			_ => {},
+		}
+	}
+}
@@ -0,0 +1,90 @@
+use super::*;
+use crate::llm::types;
+
+fn policy(cfg: serde_json::Value) -> PiiRedaction {
+	serde_json::from_value(cfg).unwrap()
+}
+
+fn request(content: &str) -> types::completions::Request {
+	serde_json::from_value(serde_json::json!({
+		"model": "gpt-4o",
+		"messages": [{"role": "user", "content": content}],
+	}))
+	.unwrap()
+}
+
+fn prompt(req: &dyn RequestType) -> Strng {
+	req.get_messages().remove(0).content
+}
+
+#[test]
+fn test_redact() {
+	let p = policy(serde_json::json!({
+		"rules": [
+			{"builtin": "email"},
+			{"pattern": r"\bEMP-\d+\b", "name": "EMPLOYEE_ID", "replacement": "[employee]"},
+		],
+	}));
+	let mut req = request(
+		"Email alice@example.com and bob@example.com about EMP-123 and EMP-456, cc alice@example.com",
+	);
+	assert!(p.apply(&mut req).is_none());
+	assert_eq!(
+		prompt(&req),
+		"Email <EMAIL_ADDRESS_1> and <EMAIL_ADDRESS_2> about [employee] and [employee], cc <EMAIL_ADDRESS_1>"
+	);
+
+	let mut req = request("nothing to see here");
+	assert!(p.apply(&mut req).is_none());
+	assert_eq!(prompt(&req), "nothing to see here");
+}
+
+#[test]
+fn test_restore() {
+	let p = policy(serde_json::json!({
+		"rules": [
+			{"builtin": "email"},
+			{"pattern": r"\bEMP-\d+\b", "name": "EMPLOYEE_ID", "replacement": "[employee]"},
+		],
+		"restore": true,
+	}));
+	let mut req = request("Write to alice@example.com about EMP-123 and EMP-456");
+	let redacted = p.apply(&mut req).unwrap();
+	assert_eq!(
+		prompt(&req),
+		"Write to <EMAIL_ADDRESS_1> about [employee] and [employee]"
+	);
+
+	let body = serde_json::json!({
+		"choices": [{"message": {"content": "Dear <EMAIL_ADDRESS_1>, re: [employee]"}}],
+	});
+	let restored = redacted.restore_body(Bytes::from(body.to_string()));
+	let restored: serde_json::Value = serde_json::from_slice(&restored).unwrap();
+	// `[employee]` stands for two values, so it is left as is
+	assert_eq!(
+		restored["choices"][0]["message"]["content"],
+		"Dear alice@example.com, re: [employee]"
+	);
+	assert_eq!(
+		redacted.restore_body(Bytes::from_static(b"not json")),
+		Bytes::from_static(b"not json")
+	);
+}
+
+#[test]
+fn test_restore_longest_first() {
+	let p = policy(serde_json::json!({
+		"rules": [{"pattern": r"\bu\d+\b", "name": "USER", "replacement": "USER{n}"}],
+		"restore": true,
+	}));
+	let users = (1..=10)
+		.map(|i| format!("u{i}"))
+		.collect::<Vec<_>>()
+		.join(" ");
+	let mut req = request(&users);
+	let redacted = p.apply(&mut req).unwrap();
+	assert!(prompt(&req).ends_with("USER9 USER10"));
+	let mut v = serde_json::json!("USER10 then USER1");
+	redacted.restore_json(&mut v);
+	assert_eq!(v, "u10 then u1");
+}
@@ -0,0 +1,315 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use ::http::{HeaderValue, header};
+use bytes::Bytes;
+use macro_rules_attribute::apply;
+use parking_lot::Mutex;
+
+use crate::http::{Body, Response};
+use crate::llm::policy::semantic_cache::CACHE_STATUS;
+use crate::llm::{InputFormat, LLMRequest, RequestType};
+use crate::telemetry::metrics::{Metrics, ResponseCacheLabels, ResponseCacheResult};
+use crate::*;
+
+#[cfg(test)]
+#[path = "response_cache_tests.rs"]
+mod tests;
+
+const CACHE_HIT: HeaderValue = HeaderValue::from_static("agentgateway; hit");
+const CACHE_MISS: HeaderValue = HeaderValue::from_static("agentgateway; fwd=miss");
+
+/// Fields that do not change what a request generates, so are left out of its cache key.
+const IGNORED_FIELDS: &[&str] = &[
+	"stream",
+	"stream_options",
+	"user",
+	"metadata",
+	"store",
+	"service_tier",
+];
+
+/// Serve cached responses to requests identical to ones answered before, for deterministic
+/// workloads such as eval suites. Requests match if they are for the same model, with the same
+/// messages, tools, and parameters, regardless of field order, and are from the same consumer. Only
+/// non-streaming requests with a temperature of 0 are cached, along with embeddings and rerank
+/// requests.
+#[apply(schema!)]
+pub struct ResponseCache {
+	/// How long responses are cached for. Defaults to 1h.
+	#[serde(default = "default_ttl", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub ttl: Duration,
+	/// The maximum number of cached responses. Once full, the oldest are evicted first.
+	/// Defaults to 1000.
+	#[serde(default = "default_max_entries")]
+	pub max_entries: usize,
+	/// The maximum size of a response to cache, in bytes. Larger responses are not cached.
+	/// Defaults to 1MiB.
+	#[serde(default = "default_max_response_bytes")]
+	pub max_response_bytes: usize,
+	/// Serve cached responses to any consumer. By default, a response is only served to the consumer
+	/// (API key, JWT subject, or mTLS identity) whose request it answered, so that one consumer is
+	/// never sent a response meant for another. Requests without an identity share one cache.
+	#[serde(default)]
+	pub shared: bool,
+	#[serde(skip, default = "new_store")]
+	store: Arc<Store>,
+}
+
+fn new_store() -> Arc<Store> {
+	crate::memory::register_cache(Store::default())
+}
+
+fn default_ttl() -> Duration {
+	Duration::from_secs(3600)
+}
+
+fn default_max_entries() -> usize {
+	1000
+}
+
+fn default_max_response_bytes() -> usize {
+	1024 * 1024
+}
+
+/// The outcome of looking up a request in the cache.
+pub enum Lookup {
+	/// The cached response body of an identical request.
+	Hit(Bytes),
+	/// No identical request was cached. The response should be stored once it completes.
+	Miss(Pending),
+}
+
+/// A request that missed the cache, carried to the response so it can be stored.
+#[derive(Debug, Clone)]
+pub struct Pending {
+	store: Arc<Store>,
+	key: Key,
+	ttl: Duration,
+	max_entries: usize,
+	max_response_bytes: usize,
+}
+
+impl Pending {
+	/// Mark a response as a cache miss, storing its body if it succeeded.
+	pub fn complete(self, status: ::http::StatusCode, headers: &mut ::http::HeaderMap, body: &Bytes) {
+		headers.insert(CACHE_STATUS, CACHE_MISS);
+		if status.is_success() && body.len() <= self.max_response_bytes {
+			self.store.insert(
+				self.key,
+				body.clone(),
+				Instant::now() + self.ttl,
+				self.max_entries,
+			);
+		}
+	}
+}
+
+impl ResponseCache {
+	/// Look up a request from `consumer` in the cache. Returns None if the request cannot be
+	/// cached.
+	pub fn lookup(
+		&self,
+		metrics: &Metrics,
+		format: InputFormat,
+		consumer: Option<&str>,
+		req: &dyn RequestType,
+		llm: &LLMRequest,
+	) -> Option<Lookup> {
+		let deterministic = matches!(format, InputFormat::Embeddings | InputFormat::Rerank)
+			|| llm.params.temperature == Some(0.0);
+		if llm.streaming || !deterministic {
+			return None;
+		}
+		let consumer = if self.shared {
+			None
+		} else {
+			Some(consumer.unwrap_or_default())
+		};
+		let key = Key::new(format, consumer, req.to_json()?)?;
+		let hit = self.store.get(&key);
+		metrics
+			.gen_ai_response_cache_lookups
+			.get_or_create(&ResponseCacheLabels {
+				model: llm.request_model.clone().into(),
+				result: if hit.is_some() {
+					ResponseCacheResult::Hit
+				} else {
+					ResponseCacheResult::Miss
+				},
+			})
+			.inc();
+		Some(match hit {
+			Some(body) => {
+				debug!(model=%llm.request_model, "serving response from response cache");
+				Lookup::Hit(body)
+			},
+			None => Lookup::Miss(Pending {
+				store: self.store.clone(),
+				key,
+				ttl: self.ttl,
+				max_entries: self.max_entries,
+				max_response_bytes: self.max_response_bytes,
+			}),
+		})
+	}
+}
+
+/// The response to a request served from the cache.
+pub fn response(body: Bytes) -> Response {
+	::http::Response::builder()
+		.status(::http::StatusCode::OK)
+		.header(header::CONTENT_TYPE, "application/json")
+		.header(CACHE_STATUS, CACHE_HIT)
+		.body(Body::from(body))
+		.expect("static response should succeed")
+}
+
+/// The normalized form of a request, which identical requests share.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Key {
+	hash: u64,
+	text: String,
+}
+
+impl Hash for Key {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.hash.hash(state);
+	}
+}
+
+impl Key {
+	/// The key of a request. Requests from different consumers never share a key, unless `consumer`
+	/// is None because the cache is shared.
+	fn new(format: InputFormat, consumer: Option<&str>, mut req: serde_json::Value) -> Option<Key> {
+		let obj = req.as_object_mut()?;
+		for field in IGNORED_FIELDS {
+			obj.remove(*field);
+		}
+		let mut text = format!("{format:?}\n");
+		if let Some(consumer) = consumer {
+			let _ = writeln!(text, "{}", serde_json::Value::from(consumer));
+		}
+		write_canonical(&req, &mut text);
+		let mut h = DefaultHasher::new();
+		text.hash(&mut h);
+		Some(Key {
+			hash: h.finish(),
+			text,
+		})
+	}
+}
+
+/// Write a JSON value with its object keys sorted, so field order does not matter.
+fn write_canonical(v: &serde_json::Value, out: &mut String) {
+	match v {
+		serde_json::Value::Object(obj) => {
+			let mut fields = obj.iter().collect::<Vec<_>>();
+			fields.sort_by_key(|(k, _)| *k);
+			out.push('{');
+			for (i, (k, v)) in fields.into_iter().enumerate() {
+				if i > 0 {
+					out.push(',');
+				}
+				let _ = write!(out, "{}:", serde_json::Value::from(k.as_str()));
+				write_canonical(v, out);
+			}
+			out.push('}');
+		},
+		serde_json::Value::Array(items) => {
+			out.push('[');
+			for (i, v) in items.iter().enumerate() {
+				if i > 0 {
+					out.push(',');
+				}
+				write_canonical(v, out);
+			}
+			out.push(']');
+		},
+		v => {
+			let _ = write!(out, "{v}");
+		},
+	}
+}
+
+#[derive(Debug)]
+struct Entry {
+	body: Bytes,
+	expires: Instant,
+}
+
+/// The cached responses of a single response cache policy.
+#[derive(Default)]
+pub struct Store {
+	inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+	entries: HashMap<Key, Entry>,
+	/// Keys in the order they were stored, along with when they expire. All entries of a policy
+	/// share a TTL, so this is also the order they expire in. A key stored again stays queued under
+	/// its earlier expiry, which is skipped once it no longer matches the entry.
+	expiry: VecDeque<(Instant, Key)>,
+}
+
+impl Inner {
+	/// Remove the oldest entry, returning false if there are none.
+	fn pop_oldest(&mut self) -> bool {
+		let Some((expires, key)) = self.expiry.pop_front() else {
+			return false;
+		};
+		if self.entries.get(&key).is_some_and(|e| e.expires == expires) {
+			self.entries.remove(&key);
+		}
+		true
+	}
+}
+
+impl std::fmt::Debug for Store {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("Store")
+			.field("entries", &self.inner.lock().entries.len())
+			.finish()
+	}
+}
+
+impl Store {
+	fn get(&self, key: &Key) -> Option<Bytes> {
+		let mut inner = self.inner.lock();
+		let now = Instant::now();
+		while inner
+			.expiry
+			.front()
+			.is_some_and(|(expires, _)| *expires <= now)
+		{
+			inner.pop_oldest();
+		}
+		inner.entries.get(key).map(|e| e.body.clone())
+	}
+
+	fn insert(&self, key: Key, body: Bytes, expires: Instant, max_entries: usize) {
+		let mut inner = self.inner.lock();
+		// Concurrent misses for the same request each store their response; keep the latest.
+		inner.expiry.push_back((expires, key.clone()));
+		inner.entries.insert(key, Entry { body, expires });
+		while inner.entries.len() > max_entries && inner.pop_oldest() {}
+		// Drop keys queued under an expiry that was since replaced, so the queue stays bounded.
+		if inner.expiry.len() > 2 * inner.entries.len().max(1) {
+			let Inner { entries, expiry } = &mut *inner;
+			expiry.retain(|(expires, key)| entries.get(key).is_some_and(|e| e.expires == *expires));
+		}
+	}
+}
+
+impl crate::memory::Evict for Store {
+	fn evict(&self) -> usize {
+		let mut inner = self.inner.lock();
+		let evicted = inner.entries.len();
+		inner.entries.clear();
+		inner.expiry.clear();
+		evicted
+	}
+}
@@ -0,0 +1,135 @@
+use super::*;
+
+fn key(req: serde_json::Value) -> Key {
+	Key::new(InputFormat::Completions, None, req).unwrap()
+}
+
+fn insert(store: &Store, req: serde_json::Value, body: &'static str, ttl: Duration, max: usize) {
+	store.insert(
+		key(req),
+		Bytes::from_static(body.as_bytes()),
+		Instant::now() + ttl,
+		max,
+	);
+}
+
+#[test]
+fn test_config() {
+	let c: ResponseCache = serde_json::from_value(serde_json::json!({})).unwrap();
+	assert_eq!(c.ttl, Duration::from_secs(3600));
+	assert_eq!(c.max_entries, 1000);
+	assert_eq!(c.max_response_bytes, 1024 * 1024);
+	assert!(!c.shared);
+}
+
+#[test]
+fn test_key() {
+	let a = key(serde_json::json!({
+		"model": "gpt-4o",
+		"temperature": 0.0,
+		"messages": [{"role": "user", "content": "2+2?"}],
+		"tools": [{"type": "function", "function": {"name": "calc", "parameters": {}}}],
+	}));
+	// Field order and per-request fields do not matter
+	let b = key(serde_json::json!({
+		"tools": [{"function": {"parameters": {}, "name": "calc"}, "type": "function"}],
+		"messages": [{"content": "2+2?", "role": "user"}],
+		"user": "alice",
+		"stream": false,
+		"temperature": 0.0,
+		"model": "gpt-4o",
+	}));
+	assert_eq!(a, b);
+
+	// Anything that changes the output does
+	let c = key(serde_json::json!({
+		"model": "gpt-4o",
+		"temperature": 0.0,
+		"messages": [{"role": "user", "content": "2+2?"}],
+	}));
+	assert_ne!(a, c);
+	let d = key(serde_json::json!({
+		"model": "gpt-4o-mini",
+		"temperature": 0.0,
+		"messages": [{"role": "user", "content": "2+2?"}],
+	}));
+	assert_ne!(c, d);
+	assert_ne!(
+		c,
+		Key::new(
+			InputFormat::Messages,
+			None,
+			serde_json::json!({
+				"model": "gpt-4o",
+				"temperature": 0.0,
+				"messages": [{"role": "user", "content": "2+2?"}],
+			})
+		)
+		.unwrap()
+	);
+}
+
+#[test]
+fn test_store() {
+	let store = Store::default();
+	let req = serde_json::json!({"model": "gpt-4o", "messages": [{"role": "user", "content": "hi"}]});
+	insert(&store, req.clone(), "first", Duration::from_secs(60), 10);
+	assert_eq!(
+		store.get(&key(req.clone())),
+		Some(Bytes::from_static(b"first"))
+	);
+	// Storing the same request again replaces it
+	insert(&store, req.clone(), "second", Duration::from_secs(60), 10);
+	assert_eq!(store.inner.lock().entries.len(), 1);
+	assert_eq!(
+		store.get(&key(req.clone())),
+		Some(Bytes::from_static(b"second"))
+	);
+
+	let other =
+		serde_json::json!({"model": "gpt-4o", "messages": [{"role": "user", "content": "bye"}]});
+	assert_eq!(store.get(&key(other.clone())), None);
+
+	// The oldest entries are evicted once full
+	insert(&store, other.clone(), "bye", Duration::from_secs(60), 1);
+	assert_eq!(store.get(&key(req)), None);
+	assert_eq!(store.get(&key(other)), Some(Bytes::from_static(b"bye")));
+}
+
+#[test]
+fn test_expiry() {
+	let store = Store::default();
+	let req = serde_json::json!({"model": "gpt-4o", "messages": []});
+	insert(&store, req.clone(), "stale", Duration::ZERO, 10);
+	assert_eq!(store.get(&key(req)), None);
+	let inner = store.inner.lock();
+	assert!(inner.entries.is_empty());
+	assert!(inner.expiry.is_empty());
+}
+
+#[test]
+fn test_replaced_expiry() {
+	let store = Store::default();
+	let req = serde_json::json!({"model": "gpt-4o", "messages": []});
+	insert(&store, req.clone(), "first", Duration::ZERO, 10);
+	insert(&store, req.clone(), "second", Duration::from_secs(60), 10);
+	// The first entry's expiry does not remove the entry that replaced it
+	assert_eq!(store.get(&key(req)), Some(Bytes::from_static(b"second")));
+	assert_eq!(store.inner.lock().expiry.len(), 1);
+}
+
+#[test]
+fn test_consumer() {
+	let req = serde_json::json!({"model": "gpt-4o", "messages": []});
+	let alice = Key::new(InputFormat::Completions, Some("alice"), req.clone()).unwrap();
+	let bob = Key::new(InputFormat::Completions, Some("bob"), req.clone()).unwrap();
+	let anonymous = Key::new(InputFormat::Completions, Some(""), req.clone()).unwrap();
+	assert_ne!(alice, bob);
+	assert_ne!(alice, anonymous);
+	assert_eq!(
+		alice,
+		Key::new(InputFormat::Completions, Some("alice"), req.clone()).unwrap()
+	);
+	// A shared cache does not partition by consumer, so is separate from every consumer's partition
+	assert_ne!(alice, key(req));
+}
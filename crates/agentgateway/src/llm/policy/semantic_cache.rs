@@ -0,0 +1,379 @@
+use std::collections::VecDeque;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use ::http::{HeaderValue, header};
+use agent_core::strng;
+use bytes::Bytes;
+use itertools::Itertools;
+use macro_rules_attribute::apply;
+use parking_lot::Mutex;
+
+use crate::http::jwt::Claims;
+use crate::http::{Body, Response};
+use crate::json;
+use crate::llm::policy::de_from_local_backend_policy;
+use crate::llm::{InputFormat, SimpleChatCompletionMessage};
+use crate::proxy::httpproxy::PolicyClient;
+use crate::telemetry::metrics::{SemanticCacheLabels, SemanticCacheResult};
+use crate::types::agent::{BackendPolicy, ResourceName, SimpleBackend, Target};
+use crate::types::local::LocalBackendPolicies;
+use crate::*;
+
+#[cfg(test)]
+#[path = "semantic_cache_tests.rs"]
+mod tests;
+
+/// The `Cache-Status` header (RFC 9211) set on responses from routes with a semantic cache.
+pub const CACHE_STATUS: header::HeaderName = header::HeaderName::from_static("cache-status");
+const CACHE_HIT: HeaderValue = HeaderValue::from_static("agentgateway; hit");
+const CACHE_MISS: HeaderValue = HeaderValue::from_static("agentgateway; fwd=miss");
+
+/// Serve cached responses to prompts that are similar to ones answered before.
+/// Prompts are embedded and compared, by cosine similarity, with prior prompts for the same model
+/// from the same consumer. Only non-streaming requests are cached.
+#[apply(schema!)]
+pub struct SemanticCache {
+	/// How prompts are embedded. Defaults to a local embedding.
+	#[serde(default)]
+	pub embedder: Embedder,
+	/// The minimum similarity, between 0 and 1, for a prior prompt to be served from cache.
+	/// Defaults to 0.95.
+	#[serde(default = "default_similarity_threshold")]
+	pub similarity_threshold: f32,
+	/// How long responses are cached for. Defaults to 1h.
+	#[serde(default = "default_ttl", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub ttl: Duration,
+	/// The maximum number of cached responses. Once full, the oldest are evicted first.
+	/// Defaults to 1000.
+	#[serde(default = "default_max_entries")]
+	pub max_entries: usize,
+	/// Serve cached responses to any consumer. By default, a response is only served to the consumer
+	/// (API key, JWT subject, or mTLS identity) whose prompt it answered, so that one consumer is
+	/// never sent a response meant for another. Requests without an identity share one cache.
+	#[serde(default)]
+	pub shared: bool,
+	#[serde(skip, default = "new_store")]
+	store: Arc<Store>,
+}
+
+#[apply(schema!)]
+pub enum Embedder {
+	/// Embed prompts in-process, by hashing their words and word pairs. This needs no model, but only
+	/// matches prompts that share most of their wording.
+	Local {
+		/// The number of dimensions of the embedding. Defaults to 512.
+		#[serde(default = "default_dimensions")]
+		dimensions: usize,
+	},
+	/// Embed prompts with the OpenAI embeddings API.
+	OpenAI(Embeddings),
+}
+
+impl Default for Embedder {
+	fn default() -> Self {
+		Embedder::Local {
+			dimensions: default_dimensions(),
+		}
+	}
+}
+
+#[apply(schema!)]
+pub struct Embeddings {
+	/// Model to use. Defaults to `text-embedding-3-small`
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub model: Option<Strng>,
+	#[serde(deserialize_with = "de_from_local_backend_policy")]
+	#[cfg_attr(feature = "schema", schemars(with = "LocalBackendPolicies"))]
+	pub policies: Vec<BackendPolicy>,
+}
+
+fn default_similarity_threshold() -> f32 {
+	0.95
+}
+
+fn default_ttl() -> Duration {
+	Duration::from_secs(3600)
+}
+
+fn new_store() -> Arc<Store> {
+	crate::memory::register_cache(Store::default())
+}
+
+fn default_max_entries() -> usize {
+	1000
+}
+
+fn default_dimensions() -> usize {
+	512
+}
+
+/// The outcome of looking up a request in the cache.
+pub enum Lookup {
+	/// A cached response to a similar prompt.
+	Hit(Response),
+	/// No similar prompt was cached. The response should be stored once it completes.
+	Miss(Pending),
+}
+
+/// A request that missed the cache, carried to the response so it can be stored.
+#[derive(Debug, Clone)]
+pub struct Pending {
+	store: Arc<Store>,
+	namespace: Strng,
+	embedding: Vec<f32>,
+	ttl: Duration,
+	max_entries: usize,
+}
+
+impl Pending {
+	/// Mark a response as a cache miss, storing its body if it succeeded.
+	pub fn complete(self, status: ::http::StatusCode, headers: &mut ::http::HeaderMap, body: &Bytes) {
+		headers.insert(CACHE_STATUS, CACHE_MISS);
+		if status.is_success() {
+			self.store.insert(
+				Entry {
+					namespace: self.namespace,
+					embedding: self.embedding,
+					body: body.clone(),
+					expires: Instant::now() + self.ttl,
+				},
+				self.max_entries,
+			);
+		}
+	}
+}
+
+impl SemanticCache {
+	/// Look up a prompt from `consumer` in the cache. Returns None if the prompt could not be
+	/// embedded, in which case the request is neither served from nor stored in the cache.
+	pub async fn lookup(
+		&self,
+		client: &PolicyClient,
+		format: InputFormat,
+		model: &str,
+		consumer: Option<&str>,
+		messages: Vec<SimpleChatCompletionMessage>,
+		claims: Option<Claims>,
+	) -> Option<Lookup> {
+		let prompt = prompt_text(&messages);
+		if prompt.is_empty() {
+			return None;
+		}
+		let embedding = match &self.embedder {
+			Embedder::Local { dimensions } => embed_local(&prompt, *dimensions),
+			Embedder::OpenAI(e) => match embed_openai(client, e, &prompt, claims).await {
+				Ok(embedding) => normalize(embedding),
+				Err(e) => {
+					warn!("failed to embed prompt for semantic cache: {e}");
+					return None;
+				},
+			},
+		};
+		let namespace = self.namespace(format, model, consumer);
+		let hit = self
+			.store
+			.get(&namespace, &embedding, self.similarity_threshold);
+		client
+			.inputs
+			.metrics
+			.gen_ai_semantic_cache_lookups
+			.get_or_create(&SemanticCacheLabels {
+				model: model.into(),
+				result: if hit.is_some() {
+					SemanticCacheResult::Hit
+				} else {
+					SemanticCacheResult::Miss
+				},
+			})
+			.inc();
+		Some(match hit {
+			Some((body, similarity)) => {
+				debug!(%model, similarity, "serving response from semantic cache");
+				Lookup::Hit(
+					::http::Response::builder()
+						.status(::http::StatusCode::OK)
+						.header(header::CONTENT_TYPE, "application/json")
+						.header(CACHE_STATUS, CACHE_HIT)
+						.body(Body::from(body))
+						.expect("static response should succeed"),
+				)
+			},
+			None => Lookup::Miss(Pending {
+				store: self.store.clone(),
+				namespace,
+				embedding,
+				ttl: self.ttl,
+				max_entries: self.max_entries,
+			}),
+		})
+	}
+
+	/// The partition of the cache a request is compared with.
+	fn namespace(&self, format: InputFormat, model: &str, consumer: Option<&str>) -> Strng {
+		if self.shared {
+			return strng::format!("{format:?}/{model}");
+		}
+		strng::format!("{format:?}/{model}/{}", consumer.unwrap_or_default())
+	}
+}
+
+/// The text of a conversation that is embedded, one `role: content` line per message.
+fn prompt_text(messages: &[SimpleChatCompletionMessage]) -> String {
+	messages
+		.iter()
+		.filter(|m| !m.content.is_empty())
+		.map(|m| format!("{}: {}", m.role, m.content))
+		.join("\n")
+}
+
+/// Embed text by hashing its words and adjacent word pairs into a fixed number of dimensions.
+fn embed_local(text: &str, dimensions: usize) -> Vec<f32> {
+	let mut v = vec![0f32; dimensions.max(1)];
+	let words = text
+		.split(|c: char| !c.is_alphanumeric())
+		.filter(|w| !w.is_empty())
+		.map(|w| w.to_lowercase())
+		.collect_vec();
+	let mut add = |feature: &[&str]| {
+		let mut h = DefaultHasher::new();
+		feature.hash(&mut h);
+		let h = h.finish();
+		let sign = if h & 1 == 0 { 1.0 } else { -1.0 };
+		v[((h >> 1) % v.len() as u64) as usize] += sign;
+	};
+	for w in &words {
+		add(&[w.as_str()]);
+	}
+	for pair in words.windows(2) {
+		add(&[pair[0].as_str(), pair[1].as_str()]);
+	}
+	normalize(v)
+}
+
+async fn embed_openai(
+	client: &PolicyClient,
+	embeddings: &Embeddings,
+	prompt: &str,
+	claims: Option<Claims>,
+) -> anyhow::Result<Vec<f32>> {
+	#[derive(serde::Deserialize)]
+	struct EmbeddingResponse {
+		data: Vec<EmbeddingData>,
+	}
+	#[derive(serde::Deserialize)]
+	struct EmbeddingData {
+		embedding: Vec<f32>,
+	}
+
+	let model = embeddings
+		.model
+		.clone()
+		.unwrap_or(strng::literal!("text-embedding-3-small"));
+	let mut pols = vec![BackendPolicy::BackendTLS(
+		crate::http::backendtls::SYSTEM_TRUST.clone(),
+	)];
+	pols.extend(embeddings.policies.iter().cloned());
+	let mut rb = ::http::Request::builder()
+		.uri("https://api.openai.com/v1/embeddings")
+		.method(::http::Method::POST)
+		.header(header::CONTENT_TYPE, "application/json");
+	if let Some(claims) = claims {
+		rb = rb.extension(claims);
+	}
+	let req = rb.body(Body::from(serde_json::to_vec(&serde_json::json!({
+		"input": prompt,
+		"model": model,
+	}))?))?;
+	let mock_be = SimpleBackend::Opaque(
+		ResourceName::new(strng::literal!("_openai-embeddings"), strng::literal!("")),
+		Target::Hostname(strng::literal!("api.openai.com"), 443),
+	);
+	let resp = client
+		.call_with_explicit_policies(req, mock_be, pols)
+		.await?;
+	let status = resp.status();
+	if !status.is_success() {
+		anyhow::bail!("embeddings request failed with status {status}");
+	}
+	let resp: EmbeddingResponse = json::from_response_body(resp).await?;
+	resp
+		.data
+		.into_iter()
+		.next()
+		.map(|d| d.embedding)
+		.ok_or_else(|| anyhow::anyhow!("embeddings response contained no embedding"))
+}
+
+fn normalize(mut v: Vec<f32>) -> Vec<f32> {
+	let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+	if norm > 0.0 {
+		v.iter_mut().for_each(|x| *x /= norm);
+	}
+	v
+}
+
+/// Cosine similarity of two normalized embeddings.
+fn similarity(a: &[f32], b: &[f32]) -> f32 {
+	if a.len() != b.len() {
+		return 0.0;
+	}
+	a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+struct Entry {
+	namespace: Strng,
+	embedding: Vec<f32>,
+	body: Bytes,
+	expires: Instant,
+}
+
+/// The cached responses of a single semantic cache policy, oldest first.
+#[derive(Default)]
+pub struct Store {
+	entries: Mutex<VecDeque<Entry>>,
+}
+
+impl std::fmt::Debug for Store {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("Store")
+			.field("entries", &self.entries.lock().len())
+			.finish()
+	}
+}
+
+impl Store {
+	/// The most similar cached response at or above the threshold, and its similarity.
+	fn get(&self, namespace: &str, embedding: &[f32], threshold: f32) -> Option<(Bytes, f32)> {
+		let mut entries = self.entries.lock();
+		let now = Instant::now();
+		// All entries of a policy share a TTL, so expired entries are always at the front.
+		while entries.front().is_some_and(|e| e.expires <= now) {
+			entries.pop_front();
+		}
+		entries
+			.iter()
+			.filter(|e| e.namespace.as_str() == namespace)
+			.map(|e| (e, similarity(&e.embedding, embedding)))
+			.filter(|(_, s)| *s >= threshold)
+			.max_by(|(_, a), (_, b)| a.total_cmp(b))
+			.map(|(e, s)| (e.body.clone(), s))
+	}
+
+	fn insert(&self, entry: Entry, max_entries: usize) {
+		let mut entries = self.entries.lock();
+		entries.push_back(entry);
+		while entries.len() > max_entries {
+			entries.pop_front();
+		}
+	}
+}
+
+impl crate::memory::Evict for Store {
+	fn evict(&self) -> usize {
+		let mut entries = self.entries.lock();
+		let evicted = entries.len();
+		entries.clear();
+		evicted
+	}
+}
@@ -0,0 +1,188 @@
+use super::*;
+
+fn msg(role: &str, content: &str) -> SimpleChatCompletionMessage {
+	SimpleChatCompletionMessage {
+		role: role.into(),
+		content: content.into(),
+	}
+}
+
+fn entry(namespace: &str, text: &str, ttl: Duration) -> Entry {
+	Entry {
+		namespace: namespace.into(),
+		embedding: embed_local(text, 512),
+		body: Bytes::copy_from_slice(text.as_bytes()),
+		expires: Instant::now() + ttl,
+	}
+}
+
+#[test]
+fn test_config() {
+	let c: SemanticCache = serde_json::from_value(serde_json::json!({})).unwrap();
+	assert!(matches!(c.embedder, Embedder::Local { dimensions: 512 }));
+	assert_eq!(c.similarity_threshold, 0.95);
+	assert_eq!(c.ttl, Duration::from_secs(3600));
+	assert_eq!(c.max_entries, 1000);
+
+	let c: SemanticCache = serde_json::from_value(serde_json::json!({
+		"embedder": {"openAI": {"model": "text-embedding-3-large", "policies": {}}},
+		"similarityThreshold": 0.9,
+		"ttl": "10m",
+		"maxEntries": 10,
+	}))
+	.unwrap();
+	let Embedder::OpenAI(e) = &c.embedder else {
+		panic!("expected openAI embedder");
+	};
+	assert_eq!(e.model.as_deref(), Some("text-embedding-3-large"));
+	assert_eq!(c.ttl, Duration::from_secs(600));
+	assert_eq!(c.max_entries, 10);
+	assert!(!c.shared);
+}
+
+#[tokio::test]
+async fn test_consumers() {
+	let client = PolicyClient {
+		inputs: crate::test_helpers::proxymock::setup_proxy_test("{}")
+			.unwrap()
+			.inputs(),
+	};
+	let prompt = || vec![msg("user", "What is the capital of France?")];
+	let lookup = |cache: &SemanticCache, consumer: &'static str| {
+		let cache = cache.clone();
+		let client = client.clone();
+		async move {
+			cache
+				.lookup(
+					&client,
+					InputFormat::Completions,
+					"gpt-4o",
+					Some(consumer),
+					prompt(),
+					None,
+				)
+				.await
+				.unwrap()
+		}
+	};
+	let store = |lookup: Lookup| {
+		let Lookup::Miss(pending) = lookup else {
+			panic!("expected a cache miss");
+		};
+		pending.complete(
+			::http::StatusCode::OK,
+			&mut ::http::HeaderMap::new(),
+			&Bytes::from_static(b"{}"),
+		);
+	};
+
+	// Each consumer only gets responses to its own prompts
+	let cache: SemanticCache = serde_json::from_value(serde_json::json!({})).unwrap();
+	store(lookup(&cache, "apikey:a").await);
+	assert!(matches!(lookup(&cache, "apikey:a").await, Lookup::Hit(_)));
+	assert!(matches!(lookup(&cache, "apikey:b").await, Lookup::Miss(_)));
+
+	let cache: SemanticCache = serde_json::from_value(serde_json::json!({"shared": true})).unwrap();
+	store(lookup(&cache, "apikey:a").await);
+	assert!(matches!(lookup(&cache, "apikey:b").await, Lookup::Hit(_)));
+}
+
+#[test]
+fn test_prompt_text() {
+	assert_eq!(
+		prompt_text(&[
+			msg("system", "You are a helpful assistant"),
+			msg("assistant", ""),
+			msg("user", "What is the capital of France?"),
+		]),
+		"system: You are a helpful assistant\nuser: What is the capital of France?"
+	);
+}
+
+#[test]
+fn test_embed_local() {
+	let a = embed_local("user: What is the capital of France?", 512);
+	let b = embed_local("user: what is the capital of France", 512);
+	let c = embed_local("user: Write a haiku about the ocean", 512);
+	assert_eq!(a.len(), 512);
+	assert!((similarity(&a, &a) - 1.0).abs() < 1e-5);
+	assert!(similarity(&a, &b) > 0.99);
+	assert!(similarity(&a, &c) < 0.5);
+}
+
+#[test]
+fn test_store() {
+	let store = Store::default();
+	store.insert(
+		entry(
+			"Completions/gpt-4o",
+			"What is the capital of France?",
+			Duration::from_secs(60),
+		),
+		10,
+	);
+	let query = embed_local("what is the capital of france", 512);
+	let (body, similarity) = store.get("Completions/gpt-4o", &query, 0.95).unwrap();
+	assert_eq!(body.as_ref(), b"What is the capital of France?");
+	assert!(similarity >= 0.95);
+
+	// Other models, and dissimilar prompts, do not match
+	assert!(store.get("Completions/gpt-4o-mini", &query, 0.95).is_none());
+	let other = embed_local("Write a haiku about the ocean", 512);
+	assert!(store.get("Completions/gpt-4o", &other, 0.95).is_none());
+}
+
+#[test]
+fn test_store_eviction() {
+	let store = Store::default();
+	store.insert(entry("m", "expired prompt", Duration::ZERO), 10);
+	assert!(
+		store
+			.get("m", &embed_local("expired prompt", 512), 0.95)
+			.is_none()
+	);
+	assert_eq!(store.entries.lock().len(), 0);
+
+	for text in ["first prompt", "second prompt", "third prompt"] {
+		store.insert(entry("m", text, Duration::from_secs(60)), 2);
+	}
+	assert_eq!(store.entries.lock().len(), 2);
+	assert!(
+		store
+			.get("m", &embed_local("first prompt", 512), 0.99)
+			.is_none()
+	);
+	assert!(
+		store
+			.get("m", &embed_local("third prompt", 512), 0.99)
+			.is_some()
+	);
+}
+
+#[test]
+fn test_complete() {
+	let store = Arc::new(Store::default());
+	let pending = |text: &str| Pending {
+		store: store.clone(),
+		namespace: "m".into(),
+		embedding: embed_local(text, 512),
+		ttl: Duration::from_secs(60),
+		max_entries: 10,
+	};
+
+	let mut headers = ::http::HeaderMap::new();
+	pending("failed prompt").complete(
+		::http::StatusCode::INTERNAL_SERVER_ERROR,
+		&mut headers,
+		&Bytes::from_static(b"{}"),
+	);
+	assert_eq!(headers.get(CACHE_STATUS).unwrap(), "agentgateway; fwd=miss");
+	assert_eq!(store.entries.lock().len(), 0);
+
+	pending("good prompt").complete(
+		::http::StatusCode::OK,
+		&mut headers,
+		&Bytes::from_static(b"{}"),
+	);
+	assert_eq!(store.entries.lock().len(), 1);
+}
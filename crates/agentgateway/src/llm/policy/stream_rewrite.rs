@@ -0,0 +1,100 @@
+use macro_rules_attribute::apply;
+
+use crate::http::Response;
+use crate::llm::LLMInfo;
+use crate::parse;
+use crate::telemetry::log::AsyncLog;
+use crate::*;
+
+#[cfg(test)]
+#[path = "stream_rewrite_tests.rs"]
+mod tests;
+
+/// The name of the event carrying the usage summary.
+pub const USAGE_EVENT: &str = "usage";
+
+/// Rewrite streaming responses as they are sent to the client, one event at a time.
+#[apply(schema!)]
+pub struct StreamRewrite {
+	/// Fields to remove from each JSON event, as dotted paths. `*` matches every element of an array,
+	/// so `choices.*.logprobs` removes `logprobs` from each choice.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub remove_fields: Vec<String>,
+	/// Send a `usage` event at the end of the stream, before `[DONE]` if the format has one, with the
+	/// model and token counts of the response.
+	#[serde(default)]
+	pub usage_summary: bool,
+}
+
+impl StreamRewrite {
+	pub fn apply(&self, resp: Response, log: AsyncLog<LLMInfo>) -> Response {
+		if self.remove_fields.is_empty() && !self.usage_summary {
+			return resp;
+		}
+		let buffer_limit = http::response_buffer_limit(&resp);
+		let paths = self
+			.remove_fields
+			.iter()
+			.map(|f| f.split('.').map(str::to_string).collect::<Vec<_>>())
+			.collect::<Vec<_>>();
+		let usage_summary = self.usage_summary;
+		resp.map(|b| {
+			parse::sse::json_rewrite(
+				b,
+				buffer_limit,
+				move |event| {
+					for path in &paths {
+						remove_path(event, path);
+					}
+				},
+				move || {
+					if !usage_summary {
+						return vec![];
+					}
+					let mut summary = None;
+					log.non_atomic_mutate(|info| summary = Some(usage(info)));
+					summary.map(|s| (USAGE_EVENT, s)).into_iter().collect()
+				},
+			)
+		})
+	}
+}
+
+fn usage(info: &LLMInfo) -> serde_json::Value {
+	let input_tokens = info.input_tokens();
+	let output_tokens = info.response.output_tokens;
+	serde_json::json!({
+		"model": info.response.provider_model.as_ref().unwrap_or(&info.request.request_model),
+		"input_tokens": input_tokens,
+		"output_tokens": output_tokens,
+		"total_tokens": info.response.total_tokens.or_else(|| Some(input_tokens? + output_tokens?)),
+	})
+}
+
+/// Remove the field at `path`, descending through objects by key and arrays by index or `*`.
+fn remove_path(v: &mut serde_json::Value, path: &[String]) {
+	let Some((first, rest)) = path.split_first() else {
+		return;
+	};
+	match v {
+		serde_json::Value::Object(m) if rest.is_empty() => {
+			m.remove(first);
+		},
+		serde_json::Value::Object(m) => {
+			if let Some(v) = m.get_mut(first) {
+				remove_path(v, rest);
+			}
+		},
+		serde_json::Value::Array(a) if first == "*" => {
+			for v in a {
+				remove_path(v, rest);
+			}
+		},
+		serde_json::Value::Array(a) if !rest.is_empty() => {
+			if let Some(v) = first.parse::<usize>().ok().and_then(|i| a.get_mut(i)) {
+				remove_path(v, rest);
+			}
+		},
+		_ => {},
+	}
+}
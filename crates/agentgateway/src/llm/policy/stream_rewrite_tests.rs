@@ -0,0 +1,98 @@
+use super::*;
+
+fn remove(mut v: serde_json::Value, path: &str) -> serde_json::Value {
+	let path = path.split('.').map(str::to_string).collect::<Vec<_>>();
+	remove_path(&mut v, &path);
+	v
+}
+
+#[test]
+fn test_config() {
+	let r: StreamRewrite = serde_json::from_value(serde_json::json!({})).unwrap();
+	assert!(r.remove_fields.is_empty());
+	assert!(!r.usage_summary);
+
+	let r: StreamRewrite = serde_json::from_value(serde_json::json!({
+		"removeFields": ["system_fingerprint"],
+		"usageSummary": true,
+	}))
+	.unwrap();
+	assert_eq!(r.remove_fields, vec!["system_fingerprint"]);
+	assert!(r.usage_summary);
+}
+
+#[test]
+fn test_remove_path() {
+	let chunk = serde_json::json!({
+		"id": "chatcmpl-1",
+		"system_fingerprint": "fp_1",
+		"choices": [
+			{"index": 0, "delta": {"content": "Hi"}, "logprobs": null},
+			{"index": 1, "delta": {"content": "Hello"}, "logprobs": null},
+		],
+	});
+	assert_eq!(
+		remove(chunk.clone(), "system_fingerprint"),
+		serde_json::json!({
+			"id": "chatcmpl-1",
+			"choices": [
+				{"index": 0, "delta": {"content": "Hi"}, "logprobs": null},
+				{"index": 1, "delta": {"content": "Hello"}, "logprobs": null},
+			],
+		})
+	);
+	assert_eq!(
+		remove(chunk.clone(), "choices.*.logprobs")["choices"],
+		serde_json::json!([
+			{"index": 0, "delta": {"content": "Hi"}},
+			{"index": 1, "delta": {"content": "Hello"}},
+		])
+	);
+	assert_eq!(
+		remove(chunk.clone(), "choices.1.delta")["choices"],
+		serde_json::json!([
+			{"index": 0, "delta": {"content": "Hi"}, "logprobs": null},
+			{"index": 1, "logprobs": null},
+		])
+	);
+	// Missing fields, and paths through non-objects, are left alone
+	assert_eq!(remove(chunk.clone(), "usage.prompt_tokens"), chunk);
+	assert_eq!(remove(chunk.clone(), "id.value"), chunk);
+	assert_eq!(remove(chunk.clone(), "choices.5.delta"), chunk);
+}
+
+#[test]
+fn test_usage() {
+	let mut info = LLMInfo {
+		request: crate::llm::LLMRequest {
+			input_tokens: Some(10),
+			input_format: crate::llm::InputFormat::Completions,
+			request_model: strng::literal!("gpt-4o"),
+			provider: strng::literal!("openai"),
+			streaming: true,
+			params: Default::default(),
+		},
+		response: Default::default(),
+	};
+	assert_eq!(
+		usage(&info),
+		serde_json::json!({
+			"model": "gpt-4o",
+			"input_tokens": 10,
+			"output_tokens": null,
+			"total_tokens": null,
+		})
+	);
+	info.response.provider_model = Some(strng::literal!("gpt-4o-2024-08-06"));
+	info.response.input_tokens = Some(12);
+	info.response.output_tokens = Some(5);
+	assert_eq!(
+		usage(&info),
+		serde_json::json!({
+			"model": "gpt-4o-2024-08-06",
+			"input_tokens": 12,
+			"output_tokens": 5,
+			"total_tokens": 17,
+		})
+	);
+}
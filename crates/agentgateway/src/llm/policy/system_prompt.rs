@@ -0,0 +1,73 @@
+use macro_rules_attribute::apply;
+
+use crate::http::Response;
+use crate::llm::policy::invalid_request_response;
+use crate::llm::{InputFormat, RequestType, SimpleChatCompletionMessage};
+use crate::*;
+
+#[cfg(test)]
+#[path = "system_prompt_tests.rs"]
+mod tests;
+
+/// A system prompt required on every request, so mandated instructions cannot be left out or
+/// overridden by clients.
+#[apply(schema!)]
+pub struct SystemPrompt {
+	/// The required system prompt.
+	pub prompt: Strng,
+	/// How requests that do not begin with the prompt are handled. Defaults to `prepend`.
+	#[serde(default)]
+	pub mode: Mode,
+}
+
+#[apply(schema!)]
+#[derive(Default, Copy, PartialEq, Eq)]
+pub enum Mode {
+	/// Add the prompt before the request's own system instructions, unless they already begin with
+	/// it.
+	#[default]
+	Prepend,
+	/// Replace the request's system instructions with the prompt.
+	Replace,
+	/// Reject requests whose system instructions do not begin with the prompt.
+	Reject,
+}
+
+impl SystemPrompt {
+	/// Enforce the prompt on a request, returning a rejection if it does not conform.
+	pub fn apply(&self, format: InputFormat, req: &mut dyn RequestType) -> Option<Response> {
+		match self.mode {
+			Mode::Prepend if !self.conforms(req) => self.prepend(req),
+			Mode::Prepend => {},
+			Mode::Replace => {
+				req.remove_system_prompts();
+				self.prepend(req);
+			},
+			Mode::Reject if !self.conforms(req) => {
+				debug!("rejecting request without the required system prompt");
+				return Some(invalid_request_response(
+					format,
+					"invalid_system_prompt",
+					"request must begin with the required system prompt".to_string(),
+				));
+			},
+			Mode::Reject => {},
+		}
+		None
+	}
+
+	/// Whether the request's first system instruction begins with the prompt.
+	fn conforms(&self, req: &dyn RequestType) -> bool {
+		req
+			.system_prompts()
+			.first()
+			.is_some_and(|p| p.trim_start().starts_with(self.prompt.trim()))
+	}
+
+	fn prepend(&self, req: &mut dyn RequestType) {
+		req.prepend_prompts(vec![SimpleChatCompletionMessage {
+			role: strng::literal!("system"),
+			content: self.prompt.clone(),
+		}]);
+	}
+}
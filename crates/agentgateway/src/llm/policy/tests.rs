@@ -317,3 +317,106 @@ fn test_model_alias_pattern_validation() {
 	assert!(pattern.matches("test.v1"));
 	assert!(!pattern.matches("testXv1")); // X doesn't match literal dot
 }
+
+fn regex_rules(v: serde_json::Value) -> RegexRules {
+	serde_json::from_value(v).unwrap()
+}
+
+#[test]
+fn test_prompt_guard_keywords() {
+	let rules = regex_rules(serde_json::json!({
+		"rules": [{"keywords": ["secret project", "confidential"]}],
+	}));
+	let Some(RegexResult::Mask(masked)) =
+		Policy::apply_prompt_guard_regex("This is CONFIDENTIAL, about the Secret Project.", &rules)
+	else {
+		panic!("expected mask");
+	};
+	assert_eq!(masked, "This is <masked>, about the <masked>.");
+	// Keywords match whole words only
+	assert!(Policy::apply_prompt_guard_regex("nonconfidential", &rules).is_none());
+
+	assert!(
+		serde_json::from_value::<RegexRules>(serde_json::json!({"rules": [{"keywords": []}]})).is_err()
+	);
+}
+
+#[test]
+fn test_prompt_guard_annotate() {
+	let rules = regex_rules(serde_json::json!({
+		"action": "annotate",
+		"rules": [{"builtin": "email"}],
+	}));
+	assert!(matches!(
+		Policy::apply_prompt_guard_regex("Contact me at test@example.com", &rules),
+		Some(RegexResult::Annotate)
+	));
+	assert!(Policy::apply_prompt_guard_regex("Nothing to see here", &rules).is_none());
+}
+
+#[test]
+fn test_guard_kinds() {
+	let guard: PromptGuard = serde_json::from_value(serde_json::json!({
+		"request": [{
+			"classifier": {
+				"action": "annotate",
+				"categories": [{"name": "violence", "terms": {"kill": 1.0}}],
+			},
+		}],
+		"response": [
+			{"openAIModeration": {"policies": {}}},
+			{"classifier": {"categories": [{"name": "violence", "terms": {"kill": 1.0}}]}},
+		],
+	}))
+	.unwrap();
+	assert!(matches!(
+		guard.request[0].kind,
+		RequestGuardKind::Classifier(Classifier {
+			action: Action::Annotate,
+			..
+		})
+	));
+	assert!(matches!(
+		guard.response[0].kind,
+		ResponseGuardKind::OpenAIModeration(_)
+	));
+	assert!(matches!(
+		guard.response[1].kind,
+		ResponseGuardKind::Classifier(_)
+	));
+}
+
+#[tokio::test]
+async fn test_token_limits_rejection() {
+	use http_body_util::BodyExt;
+
+	let limits: token_limits::TokenLimits =
+		serde_json::from_value(serde_json::json!({"maxInputTokens": 1000})).unwrap();
+	assert_eq!(limits.max_input_tokens, Some(1000));
+	assert_eq!(limits.max_output_tokens, None);
+
+	let body = |resp: Response| async move {
+		assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+		let b = resp.into_body().collect().await.unwrap().to_bytes();
+		serde_json::from_slice::<serde_json::Value>(&b).unwrap()
+	};
+	let openai = body(token_limits::TokenLimits::input_rejection(
+		crate::llm::InputFormat::Completions,
+		1500,
+		1000,
+	))
+	.await;
+	assert_eq!(openai["error"]["code"], "context_length_exceeded");
+	assert_eq!(
+		openai["error"]["message"],
+		"prompt is 1500 tokens, exceeding the limit of 1000 tokens"
+	);
+	let anthropic = body(token_limits::TokenLimits::input_rejection(
+		crate::llm::InputFormat::Messages,
+		1500,
+		1000,
+	))
+	.await;
+	assert_eq!(anthropic["type"], "error");
+	assert_eq!(anthropic["error"]["type"], "invalid_request_error");
+}
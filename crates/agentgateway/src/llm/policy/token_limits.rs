@@ -0,0 +1,30 @@
+use macro_rules_attribute::apply;
+
+use crate::http::Response;
+use crate::llm::InputFormat;
+use crate::llm::policy::invalid_request_response;
+use crate::*;
+
+/// Token limits enforced by the gateway before requests reach the provider.
+#[apply(schema!)]
+pub struct TokenLimits {
+	/// Reject requests whose prompt is more than this many tokens. Prompts are counted with the
+	/// model's tiktoken tokenizer, falling back to `cl100k_base` for other models.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub max_input_tokens: Option<u64>,
+	/// The most tokens a request may generate. Requests asking for more are clamped to this, and
+	/// requests without a limit have it set.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub max_output_tokens: Option<u64>,
+}
+
+impl TokenLimits {
+	/// The response for a prompt over `max_input_tokens`, in the error format of the client's API.
+	pub fn input_rejection(format: InputFormat, tokens: u64, limit: u64) -> Response {
+		invalid_request_response(
+			format,
+			"context_length_exceeded",
+			format!("prompt is {tokens} tokens, exceeding the limit of {limit} tokens"),
+		)
+	}
+}
@@ -0,0 +1,323 @@
+use bytes::Bytes;
+use macro_rules_attribute::apply;
+use serde_json::Value;
+
+use crate::http::{Response, StatusCode};
+use crate::llm::InputFormat;
+use crate::*;
+
+#[cfg(test)]
+#[path = "structured_output_tests.rs"]
+mod tests;
+
+/// Validates that LLM responses are JSON matching a schema, so downstream agents can always parse
+/// them. Invalid responses can be retried with a repair prompt; once retries are exhausted, a
+/// `502` is returned in the client's API error format. Only non-streaming chat completions,
+/// messages, and responses requests are validated.
+#[apply(schema!)]
+pub struct Policy {
+	/// The JSON schema the text of responses must match. The `type`, `enum`, `const`, `properties`,
+	/// `required`, `additionalProperties`, `items`, `minItems`, `maxItems`, `minLength`,
+	/// `maxLength`, `pattern`, `minimum`, `maximum`, `exclusiveMinimum`, `exclusiveMaximum`,
+	/// `allOf`, `anyOf`, `oneOf`, and local `$ref` keywords are checked; others are ignored.
+	pub schema: Value,
+	/// How many times an invalid response is retried with a repair prompt. Defaults to 0.
+	#[serde(default)]
+	pub max_retries: u8,
+	/// The prompt sent, after the invalid response, to ask the model to correct it. `{error}` is
+	/// replaced with the validation error, and `{schema}` with the schema.
+	#[serde(default = "default_repair_prompt")]
+	pub repair_prompt: Strng,
+}
+
+fn default_repair_prompt() -> Strng {
+	strng::literal!(
+		"Your response was not valid JSON matching the required schema: {error}. Respond with only the corrected JSON, with no other text. The schema is: {schema}"
+	)
+}
+
+/// The result of validating a response.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Check {
+	/// The response matches the schema.
+	Valid,
+	/// The response is not one that is validated, such as a tool call.
+	Skipped,
+	/// The response text does not match the schema.
+	Invalid {
+		format: InputFormat,
+		output: String,
+		error: String,
+	},
+}
+
+impl Policy {
+	/// Validate the body of a successful response.
+	pub fn check(&self, body: &[u8]) -> Check {
+		let Ok(resp) = serde_json::from_slice::<Value>(body) else {
+			return Check::Skipped;
+		};
+		let Some((format, output)) = response_text(&resp) else {
+			return Check::Skipped;
+		};
+		let error = match serde_json::from_str::<Value>(output.trim()) {
+			Ok(v) => match validate(&self.schema, &self.schema, &v, "$") {
+				Ok(()) => return Check::Valid,
+				Err(e) => e,
+			},
+			Err(e) => format!("not valid JSON: {e}"),
+		};
+		Check::Invalid {
+			format,
+			output,
+			error,
+		}
+	}
+
+	/// The request to send to repair an invalid response: the original request, followed by the
+	/// invalid response and the repair prompt. Returns None if the request has no messages to add to.
+	pub fn repair(&self, req: &[u8], output: &str, error: &str) -> Option<Bytes> {
+		let mut req = serde_json::from_slice::<Value>(req).ok()?;
+		let prompt = self
+			.repair_prompt
+			.replace("{error}", error)
+			.replace("{schema}", &self.schema.to_string());
+		let messages = req.get_mut("messages")?.as_array_mut()?;
+		messages.push(serde_json::json!({"role": "assistant", "content": output}));
+		messages.push(serde_json::json!({"role": "user", "content": prompt}));
+		serde_json::to_vec(&req).ok().map(Bytes::from)
+	}
+
+	/// The response returned once retries are exhausted.
+	pub fn error_response(&self, format: InputFormat, error: &str) -> Response {
+		crate::llm::policy::error_response(
+			format,
+			StatusCode::BAD_GATEWAY,
+			"api_error",
+			"invalid_structured_output",
+			format!("model response did not match the required schema: {error}"),
+		)
+	}
+}
+
+/// Whether a request asks for a streaming response, which is not validated.
+pub fn is_streaming(req: &[u8]) -> bool {
+	#[derive(serde::Deserialize)]
+	struct StreamRequest {
+		#[serde(default)]
+		stream: bool,
+	}
+	serde_json::from_slice::<StreamRequest>(req).is_ok_and(|r| r.stream)
+}
+
+/// The text of a chat completions, messages, or responses response, along with its format.
+fn response_text(resp: &Value) -> Option<(InputFormat, String)> {
+	if let Some(choices) = resp.get("choices") {
+		let content = choices.get(0)?.get("message")?.get("content")?.as_str()?;
+		return Some((InputFormat::Completions, content.to_string()));
+	}
+	let (format, blocks) = match resp.get("type").and_then(Value::as_str) {
+		Some("message") => (
+			InputFormat::Messages,
+			resp.get("content")?.as_array()?.clone(),
+		),
+		_ if resp.get("object").and_then(Value::as_str) == Some("response") => (
+			InputFormat::Responses,
+			resp
+				.get("output")?
+				.as_array()?
+				.iter()
+				.filter(|o| o.get("type").and_then(Value::as_str) == Some("message"))
+				.filter_map(|o| o.get("content")?.as_array())
+				.flatten()
+				.cloned()
+				.collect(),
+		),
+		_ => return None,
+	};
+	let text = blocks
+		.iter()
+		.filter(|b| {
+			matches!(
+				b.get("type").and_then(Value::as_str),
+				Some("text" | "output_text")
+			)
+		})
+		.filter_map(|b| b.get("text")?.as_str())
+		.collect::<String>();
+	if text.is_empty() {
+		return None;
+	}
+	Some((format, text))
+}
+
+/// Validate `v` against `schema`, returning the first mismatch. `path` is the location of `v` in
+/// the response, for error messages, and `root` the schema `$ref`s are resolved against.
+fn validate(root: &Value, schema: &Value, v: &Value, path: &str) -> Result<(), String> {
+	let schema = match schema {
+		Value::Bool(true) => return Ok(()),
+		Value::Bool(false) => return Err(format!("{path}: no value is allowed")),
+		Value::Object(s) => s,
+		_ => return Ok(()),
+	};
+	if let Some(r) = schema.get("$ref").and_then(Value::as_str) {
+		let target = r
+			.strip_prefix('#')
+			.and_then(|p| root.pointer(p))
+			.ok_or_else(|| format!("{path}: unsupported $ref {r}"))?;
+		validate(root, target, v, path)?;
+	}
+	if let Some(t) = schema.get("type") {
+		let types = match t {
+			Value::String(s) => vec![s.as_str()],
+			Value::Array(a) => a.iter().filter_map(Value::as_str).collect(),
+			_ => vec![],
+		};
+		if !types.is_empty() && !types.iter().any(|t| is_type(v, t)) {
+			return Err(format!("{path}: expected {}", types.join(" or ")));
+		}
+	}
+	if let Some(e) = schema.get("enum").and_then(Value::as_array)
+		&& !e.contains(v)
+	{
+		return Err(format!("{path}: must be one of {}", Value::from(e.clone())));
+	}
+	if let Some(c) = schema.get("const")
+		&& c != v
+	{
+		return Err(format!("{path}: must be {c}"));
+	}
+
+	match v {
+		Value::Object(obj) => {
+			let properties = schema.get("properties").and_then(Value::as_object);
+			for required in schema
+				.get("required")
+				.and_then(Value::as_array)
+				.into_iter()
+				.flatten()
+				.filter_map(Value::as_str)
+			{
+				if !obj.contains_key(required) {
+					return Err(format!("{path}: missing required property {required}"));
+				}
+			}
+			for (k, fv) in obj {
+				let child = format!("{path}.{k}");
+				match (
+					properties.and_then(|p| p.get(k)),
+					schema.get("additionalProperties"),
+				) {
+					(Some(s), _) => validate(root, s, fv, &child)?,
+					(None, Some(Value::Bool(false))) => {
+						return Err(format!("{path}: unexpected property {k}"));
+					},
+					(None, Some(s)) => validate(root, s, fv, &child)?,
+					(None, None) => {},
+				}
+			}
+		},
+		Value::Array(items) => {
+			if let Some(min) = schema.get("minItems").and_then(Value::as_u64)
+				&& (items.len() as u64) < min
+			{
+				return Err(format!("{path}: expected at least {min} items"));
+			}
+			if let Some(max) = schema.get("maxItems").and_then(Value::as_u64)
+				&& (items.len() as u64) > max
+			{
+				return Err(format!("{path}: expected at most {max} items"));
+			}
+			if let Some(s) = schema.get("items") {
+				for (i, item) in items.iter().enumerate() {
+					validate(root, s, item, &format!("{path}[{i}]"))?;
+				}
+			}
+		},
+		Value::String(s) => {
+			let len = s.chars().count() as u64;
+			if let Some(min) = schema.get("minLength").and_then(Value::as_u64)
+				&& len < min
+			{
+				return Err(format!("{path}: expected at least {min} characters"));
+			}
+			if let Some(max) = schema.get("maxLength").and_then(Value::as_u64)
+				&& len > max
+			{
+				return Err(format!("{path}: expected at most {max} characters"));
+			}
+			if let Some(p) = schema.get("pattern").and_then(Value::as_str) {
+				let re = regex::Regex::new(p).map_err(|_| format!("{path}: invalid pattern {p}"))?;
+				if !re.is_match(s) {
+					return Err(format!("{path}: must match {p}"));
+				}
+			}
+		},
+		Value::Number(n) => {
+			let n = n.as_f64().unwrap_or_default();
+			let bound = |k: &str| schema.get(k).and_then(Value::as_f64);
+			if let Some(min) = bound("minimum")
+				&& n < min
+			{
+				return Err(format!("{path}: must be at least {min}"));
+			}
+			if let Some(max) = bound("maximum")
+				&& n > max
+			{
+				return Err(format!("{path}: must be at most {max}"));
+			}
+			if let Some(min) = bound("exclusiveMinimum")
+				&& n <= min
+			{
+				return Err(format!("{path}: must be greater than {min}"));
+			}
+			if let Some(max) = bound("exclusiveMaximum")
+				&& n >= max
+			{
+				return Err(format!("{path}: must be less than {max}"));
+			}
+		},
+		_ => {},
+	}
+
+	let subschemas = |k: &str| {
+		schema
+			.get(k)
+			.and_then(Value::as_array)
+			.map(|a| a.as_slice())
+			.unwrap_or_default()
+	};
+	for s in subschemas("allOf") {
+		validate(root, s, v, path)?;
+	}
+	let any_of = subschemas("anyOf");
+	if !any_of.is_empty() && !any_of.iter().any(|s| validate(root, s, v, path).is_ok()) {
+		return Err(format!("{path}: does not match any allowed schema"));
+	}
+	let one_of = subschemas("oneOf");
+	if !one_of.is_empty() {
+		let matches = one_of
+			.iter()
+			.filter(|s| validate(root, s, v, path).is_ok())
+			.count();
+		if matches != 1 {
+			return Err(format!(
+				"{path}: must match exactly one allowed schema, but matches {matches}"
+			));
+		}
+	}
+	Ok(())
+}
+
+fn is_type(v: &Value, t: &str) -> bool {
+	match t {
+		"object" => v.is_object(),
+		"array" => v.is_array(),
+		"string" => v.is_string(),
+		"number" => v.is_number(),
+		"integer" => v.is_i64() || v.is_u64() || v.as_f64().is_some_and(|f| f.fract() == 0.0),
+		"boolean" => v.is_boolean(),
+		"null" => v.is_null(),
+		_ => true,
+	}
+}
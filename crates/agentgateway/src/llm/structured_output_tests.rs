@@ -0,0 +1,160 @@
+use super::*;
+
+fn policy() -> Policy {
+	serde_json::from_value(serde_json::json!({
+		"schema": {
+			"type": "object",
+			"properties": {
+				"name": {"type": "string", "minLength": 1},
+				"tags": {"type": "array", "items": {"$ref": "#/$defs/tag"}, "maxItems": 2},
+				"rating": {"type": "integer", "minimum": 1, "maximum": 5},
+			},
+			"required": ["name", "rating"],
+			"additionalProperties": false,
+			"$defs": {"tag": {"enum": ["new", "sale"]}},
+		},
+		"maxRetries": 1,
+	}))
+	.unwrap()
+}
+
+fn completion(content: &str) -> Vec<u8> {
+	serde_json::to_vec(&serde_json::json!({
+		"object": "chat.completion",
+		"choices": [{"index": 0, "message": {"role": "assistant", "content": content}}],
+	}))
+	.unwrap()
+}
+
+fn error(p: &Policy, body: &[u8]) -> String {
+	match p.check(body) {
+		Check::Invalid { error, .. } => error,
+		c => panic!("expected an invalid response, got {c:?}"),
+	}
+}
+
+#[test]
+fn test_validate() {
+	let p = policy();
+	assert_eq!(p.max_retries, 1);
+	assert_eq!(
+		p.check(&completion(
+			r#"{"name": "mug", "tags": ["sale"], "rating": 4}"#
+		)),
+		Check::Valid
+	);
+	assert_eq!(
+		error(&p, &completion(r#"{"name": "mug"}"#)),
+		"$: missing required property rating"
+	);
+	assert_eq!(
+		error(&p, &completion(r#"{"name": "mug", "rating": 4.5}"#)),
+		"$.rating: expected integer"
+	);
+	assert_eq!(
+		error(&p, &completion(r#"{"name": "mug", "rating": 9}"#)),
+		"$.rating: must be at most 5"
+	);
+	assert_eq!(
+		error(
+			&p,
+			&completion(r#"{"name": "mug", "rating": 4, "tags": ["old"]}"#)
+		),
+		r#"$.tags[0]: must be one of ["new","sale"]"#
+	);
+	assert_eq!(
+		error(&p, &completion(r#"{"name": "", "rating": 4}"#)),
+		"$.name: expected at least 1 characters"
+	);
+	assert_eq!(
+		error(
+			&p,
+			&completion(r#"{"name": "mug", "rating": 4, "price": 3}"#)
+		),
+		"$: unexpected property price"
+	);
+	assert!(error(&p, &completion("Sure! Here is the JSON")).starts_with("not valid JSON"));
+}
+
+#[test]
+fn test_subschemas() {
+	let p: Policy = serde_json::from_value(serde_json::json!({
+		"schema": {"oneOf": [{"type": "string"}, {"type": "number"}, {"const": 1}]},
+	}))
+	.unwrap();
+	assert_eq!(p.check(&completion(r#""a""#)), Check::Valid);
+	assert_eq!(
+		error(&p, &completion("1")),
+		"$: must match exactly one allowed schema, but matches 2"
+	);
+	assert_eq!(
+		error(&p, &completion("true")),
+		"$: must match exactly one allowed schema, but matches 0"
+	);
+}
+
+#[test]
+fn test_response_formats() {
+	let p = policy();
+	let valid = r#"{"name": "mug", "rating": 4}"#;
+	let messages = serde_json::json!({
+		"type": "message",
+		"role": "assistant",
+		"content": [{"type": "text", "text": valid}],
+	});
+	assert_eq!(p.check(messages.to_string().as_bytes()), Check::Valid);
+	let responses = serde_json::json!({
+		"object": "response",
+		"output": [{"type": "message", "content": [{"type": "output_text", "text": "{}"}]}],
+	});
+	let Check::Invalid { format, .. } = p.check(responses.to_string().as_bytes()) else {
+		panic!("expected an invalid response");
+	};
+	assert_eq!(format, InputFormat::Responses);
+
+	// Tool calls and other responses are not validated
+	let tool_call = serde_json::json!({
+		"choices": [{"message": {"role": "assistant", "content": null, "tool_calls": []}}],
+	});
+	assert_eq!(p.check(tool_call.to_string().as_bytes()), Check::Skipped);
+	assert_eq!(p.check(b"not json"), Check::Skipped);
+}
+
+#[test]
+fn test_repair() {
+	let p = policy();
+	let req = serde_json::json!({
+		"model": "gpt-4o",
+		"messages": [{"role": "user", "content": "Describe the mug"}],
+	});
+	let repaired = p
+		.repair(
+			req.to_string().as_bytes(),
+			"a mug",
+			"not valid JSON: expected value",
+		)
+		.unwrap();
+	let repaired: Value = serde_json::from_slice(&repaired).unwrap();
+	let messages = repaired["messages"].as_array().unwrap();
+	assert_eq!(messages.len(), 3);
+	assert_eq!(
+		messages[1],
+		serde_json::json!({"role": "assistant", "content": "a mug"})
+	);
+	let prompt = messages[2]["content"].as_str().unwrap();
+	assert!(prompt.contains("not valid JSON: expected value"));
+	assert!(prompt.contains(r#""required":["name","rating"]"#));
+
+	// Requests without messages cannot be repaired
+	let responses = serde_json::json!({"model": "gpt-4o", "input": "Describe the mug"});
+	assert!(
+		p.repair(responses.to_string().as_bytes(), "a mug", "error")
+			.is_none()
+	);
+}
+
+#[test]
+fn test_is_streaming() {
+	assert!(is_streaming(br#"{"stream": true, "messages": []}"#));
+	assert!(!is_streaming(br#"{"messages": []}"#));
+}
@@ -291,6 +291,77 @@ async fn test_messages_to_completions() {
 	test_request("anthropic", "request_anthropic_tools", request);
 }
 
+#[tokio::test]
+async fn test_messages_to_completions_response() {
+	let raw = fs::read("src/llm/tests/response_openai_tool.json").unwrap();
+	let resp = conversion::completions::from_messages::translate_response(&Bytes::from(raw))
+		.expect("failed to translate response");
+	let resp = serde_json::from_slice::<Value>(&resp.serialize().unwrap()).unwrap();
+	assert_eq!(resp["type"], "message");
+	assert_eq!(resp["stop_reason"], "tool_use");
+	assert_eq!(
+		resp["content"],
+		serde_json::json!([
+			{"type": "text", "text": "Let me check the weather."},
+			{"type": "tool_use", "id": "call_abc123", "name": "get_weather", "input": {"location": "Paris"}},
+		])
+	);
+	assert_eq!(
+		resp["usage"],
+		serde_json::json!({"input_tokens": 42, "output_tokens": 18, "cache_read_input_tokens": 10})
+	);
+
+	let err = br#"{"error": {"type": "invalid_request_error", "message": "bad model"}}"#;
+	let err = conversion::completions::from_messages::translate_error(&Bytes::from_static(err))
+		.expect("failed to translate error");
+	assert_eq!(
+		serde_json::from_slice::<Value>(&err).unwrap(),
+		serde_json::json!({"type": "error", "error": {"type": "invalid_request_error", "message": "bad model"}})
+	);
+}
+
+#[tokio::test]
+async fn test_messages_to_completions_stream() {
+	let body = Body::from(fs::read("src/llm/tests/response_stream-openai_tool.json").unwrap());
+	let resp =
+		conversion::completions::from_messages::translate_stream(body, 1024, AsyncLog::default());
+	let resp_bytes = resp.collect().await.unwrap().to_bytes();
+	let events = std::str::from_utf8(&resp_bytes)
+		.unwrap()
+		.split("\n\n")
+		.filter(|e| !e.is_empty())
+		.map(|e| {
+			let (name, data) = e.split_once('\n').unwrap();
+			let data = serde_json::from_str::<Value>(data.trim_start_matches("data: ")).unwrap();
+			(name.trim_start_matches("event: ").to_string(), data)
+		})
+		.collect::<Vec<_>>();
+	let names = events.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>();
+	assert_eq!(
+		names,
+		vec![
+			"message_start",
+			"content_block_start",
+			"content_block_delta",
+			"content_block_delta",
+			"content_block_stop",
+			"content_block_start",
+			"content_block_delta",
+			"content_block_delta",
+			"content_block_stop",
+			"message_delta",
+			"message_stop",
+		]
+	);
+	assert_eq!(events[0].1["message"]["model"], "gpt-4o-2024-08-06");
+	assert_eq!(events[2].1["delta"]["text"], "Let me");
+	assert_eq!(events[5].1["index"], 1);
+	assert_eq!(events[5].1["content_block"]["name"], "get_weather");
+	assert_eq!(events[7].1["delta"]["partial_json"], "\"Paris\"}");
+	assert_eq!(events[9].1["delta"]["stop_reason"], "tool_use");
+	assert_eq!(events[9].1["usage"]["output_tokens"], 18);
+}
+
 #[tokio::test]
 async fn test_completions_to_messages() {
 	let response = |i| conversion::messages::from_completions::translate_response(&i);
@@ -372,3 +443,277 @@ fn test_prompt_enrichment() {
 		apply_test_prompts,
 	);
 }
+
+fn ai_backend(groups: Value) -> AIBackend {
+	let be: crate::types::local::LocalAIBackend =
+		serde_json::from_value(serde_json::json!({ "groups": groups })).unwrap();
+	be.translate().unwrap()
+}
+
+fn selected(be: &AIBackend, model: Option<&str>) -> Option<String> {
+	be.select_provider(model).map(|(p, _)| p.name.to_string())
+}
+
+#[tokio::test]
+async fn test_select_provider_by_model() {
+	let be = ai_backend(serde_json::json!([{"providers": [
+		{"name": "openai", "provider": {"openAI": {}}, "models": ["gpt-4o", "o3"]},
+		{"name": "anthropic", "provider": {"anthropic": {}}, "models": ["claude-*"]},
+		{"name": "ollama", "provider": {"openAI": {}}, "hostOverride": "localhost:11434"},
+	]}]));
+	assert!(be.routes_by_model());
+	assert_eq!(selected(&be, Some("gpt-4o")).as_deref(), Some("openai"));
+	assert_eq!(
+		selected(&be, Some("claude-sonnet-4")).as_deref(),
+		Some("anthropic")
+	);
+	// Models no provider lists, and requests without a model, go to the providers without models.
+	assert_eq!(selected(&be, Some("llama3")).as_deref(), Some("ollama"));
+	assert_eq!(selected(&be, None).as_deref(), Some("ollama"));
+}
+
+#[tokio::test]
+async fn test_select_provider_by_model_groups() {
+	// A model served only by a lower priority group is still routed there.
+	let be = ai_backend(serde_json::json!([
+		{"providers": [{"name": "primary", "provider": {"openAI": {}}, "models": ["gpt-4o"]}]},
+		{"providers": [{"name": "secondary", "provider": {"anthropic": {}}, "models": ["claude-*"]}]},
+	]));
+	assert_eq!(
+		selected(&be, Some("claude-opus-4")).as_deref(),
+		Some("secondary")
+	);
+	// Without a catch-all provider, unknown models have no provider.
+	assert_eq!(selected(&be, Some("llama3")), None);
+
+	let be = ai_backend(serde_json::json!([{"providers": [
+		{"name": "openai", "provider": {"openAI": {}}},
+	]}]));
+	assert!(!be.routes_by_model());
+	assert_eq!(selected(&be, Some("anything")).as_deref(), Some("openai"));
+}
+
+#[test]
+fn test_clamp_max_tokens() {
+	let mut r: types::completions::Request = serde_json::from_value(serde_json::json!({
+		"model": "gpt-4o",
+		"messages": [{"role": "user", "content": "hi"}],
+	}))
+	.unwrap();
+	r.clamp_max_tokens(100);
+	assert_eq!(r.max_completion_tokens, Some(100));
+	assert_eq!(r.max_tokens, None);
+
+	let mut r: types::completions::Request = serde_json::from_value(serde_json::json!({
+		"model": "gpt-4o",
+		"messages": [{"role": "user", "content": "hi"}],
+		"max_tokens": 500,
+	}))
+	.unwrap();
+	r.clamp_max_tokens(100);
+	assert_eq!(r.max_completion_tokens, None);
+	assert_eq!(r.max_tokens, Some(100));
+	r.clamp_max_tokens(200);
+	assert_eq!(r.max_tokens, Some(100));
+
+	let mut r: types::messages::Request = serde_json::from_value(serde_json::json!({
+		"model": "claude-sonnet-4-5",
+		"messages": [{"role": "user", "content": "hi"}],
+		"max_tokens": 50,
+	}))
+	.unwrap();
+	r.clamp_max_tokens(100);
+	assert_eq!(r.max_tokens, Some(50));
+
+	let mut r: types::responses::Request = serde_json::from_value(serde_json::json!({
+		"model": "gpt-4o",
+		"input": "hi",
+	}))
+	.unwrap();
+	r.clamp_max_tokens(100);
+	assert_eq!(r.max_output_tokens, Some(100));
+}
+
+#[tokio::test]
+async fn test_token_load_balancing() {
+	let be: crate::types::local::LocalAIBackend = serde_json::from_value(serde_json::json!({
+		"groups": [{"providers": [
+			{"name": "a", "provider": {"openAI": {}}, "hostOverride": "10.0.0.1:8000"},
+			{"name": "b", "provider": {"openAI": {}}, "hostOverride": "10.0.0.2:8000"},
+		]}],
+		"loadBalancing": "tokens",
+	}))
+	.unwrap();
+	let be = be.translate().unwrap();
+	assert_eq!(be.load_balancing, LoadBalancing::Tokens);
+	let info = |name: &str| {
+		be.providers
+			.iter()
+			.iter()
+			.find(|(p, _)| p.name.as_str() == name)
+			.map(|(_, info)| info.clone())
+			.unwrap()
+	};
+	let (a, b) = (info("a"), info("b"));
+	// Without usage, providers are scored by pending requests and latency
+	assert_eq!(a.token_score(), a.score());
+
+	// Both providers process tokens at the same rate, but "a" has larger requests in flight
+	let a_handle = be.providers.start_request(strng::literal!("a"), &a);
+	a_handle.record_tokens(4000, Duration::from_secs(4));
+	let b_handle = be.providers.start_request(strng::literal!("b"), &b);
+	b_handle.record_tokens(500, Duration::from_millis(500));
+	assert!(a.token_score() < b.token_score());
+
+	// Requests queued behind small ones still outweigh a single large one
+	let pending = (0..20)
+		.map(|_| be.providers.start_request(strng::literal!("b"), &b))
+		.collect::<Vec<_>>();
+	assert!(b.token_score() < a.token_score());
+	drop(pending);
+}
+
+#[test]
+fn test_passthrough_usage() {
+	let openai = serde_json::json!({
+		"object": "list",
+		"data": [{"object": "embedding", "index": 0, "embedding": [0.1, 0.2]}],
+		"model": "text-embedding-3-small",
+		"usage": {"prompt_tokens": 8, "total_tokens": 8},
+	});
+	let usage = types::passthrough_usage(openai.to_string().as_bytes());
+	assert_eq!(usage.input_tokens, Some(8));
+	assert_eq!(usage.total_tokens, Some(8));
+	assert_eq!(
+		usage.provider_model.as_deref(),
+		Some("text-embedding-3-small")
+	);
+
+	let cohere = serde_json::json!({
+		"results": [{"index": 1, "relevance_score": 0.9}, {"index": 0, "relevance_score": 0.1}],
+		"meta": {"billed_units": {"search_units": 1, "input_tokens": 42}},
+	});
+	let usage = types::passthrough_usage(cohere.to_string().as_bytes());
+	assert_eq!(usage.input_tokens, Some(42));
+	assert_eq!(usage.output_tokens, None);
+
+	assert_eq!(types::passthrough_usage(b"not json").input_tokens, None);
+}
+
+#[test]
+fn test_rerank_request() {
+	let mut r: types::rerank::Request = serde_json::from_value(serde_json::json!({
+		"query": "capital of France",
+		"documents": ["Paris is the capital of France.", {"text": "Berlin is in Germany."}],
+		"top_n": 1,
+		"return_documents": true,
+	}))
+	.unwrap();
+	*r.model() = Some("bge-reranker-v2-m3".to_string());
+	let llm = r.to_llm_request(strng::literal!("openai"), false).unwrap();
+	assert_eq!(llm.input_format, InputFormat::Rerank);
+	assert_eq!(llm.request_model.as_str(), "bge-reranker-v2-m3");
+	assert!(!llm.streaming);
+	// Unknown fields are passed through
+	let body: Value = serde_json::from_slice(&r.to_openai().unwrap()).unwrap();
+	assert_eq!(body["return_documents"], true);
+	assert_eq!(body["documents"][1]["text"], "Berlin is in Germany.");
+	assert_eq!(openai::path(RouteType::Rerank), "/v1/rerank");
+}
+
+#[test]
+fn test_rerank_bedrock() {
+	let r: types::rerank::Request = serde_json::from_value(serde_json::json!({
+		"model": "cohere.rerank-v3-5:0",
+		"query": "capital of France",
+		"documents": ["Paris is the capital of France.", "Berlin is in Germany."],
+		"top_n": 1,
+	}))
+	.unwrap();
+	let provider = bedrock::Provider {
+		model: None,
+		region: strng::new("us-west-2"),
+		guardrail_identifier: None,
+		guardrail_version: None,
+	};
+	let body: Value = serde_json::from_slice(&r.to_bedrock(&provider, None, None).unwrap()).unwrap();
+	assert_eq!(
+		body,
+		serde_json::json!({
+			"query": "capital of France",
+			"documents": ["Paris is the capital of France.", "Berlin is in Germany."],
+			"top_n": 1,
+			"api_version": 2,
+		})
+	);
+	assert_eq!(
+		provider
+			.get_path_for_route(RouteType::Rerank, false, "cohere.rerank-v3-5:0")
+			.as_str(),
+		"/model/cohere.rerank-v3-5:0/invoke"
+	);
+}
+
+#[tokio::test]
+async fn test_health_scoring() {
+	let be: crate::types::local::LocalAIBackend = serde_json::from_value(serde_json::json!({
+		"groups": [{"providers": [
+			{"name": "a", "provider": {"openAI": {}}, "hostOverride": "10.0.0.1:8000"},
+			{"name": "b", "provider": {"openAI": {}}, "hostOverride": "10.0.0.2:8000"},
+		]}],
+		"healthScoring": {"degradedBelow": 0.4, "recoveredAbove": 0.9},
+	}))
+	.unwrap();
+	let be = be.translate().unwrap();
+	assert_eq!(be.health_scoring.degraded_below, 0.4);
+	assert_eq!(be.health_scoring.latency_threshold, Duration::from_secs(60));
+	let a_info = be
+		.providers
+		.iter()
+		.iter()
+		.find(|(p, _)| p.name.as_str() == "a")
+		.map(|(_, info)| info.clone())
+		.unwrap();
+	let record = |status: ::http::StatusCode| {
+		be.providers
+			.start_request(strng::literal!("a"), &a_info)
+			.with_health_scoring(be.health_scoring)
+			.record_health(status)
+			.unwrap()
+	};
+
+	// Throttling and errors lower the score until the provider is degraded
+	let (score, degraded) = record(::http::StatusCode::TOO_MANY_REQUESTS);
+	assert!(score < 1.0);
+	assert!(!degraded);
+	record(::http::StatusCode::SERVICE_UNAVAILABLE);
+	record(::http::StatusCode::SERVICE_UNAVAILABLE);
+	let (score, degraded) = record(::http::StatusCode::SERVICE_UNAVAILABLE);
+	assert!(score < 0.4, "{score}");
+	assert!(degraded);
+	assert_eq!(
+		LoadBalancing::Requests.score(&a_info),
+		a_info.score() * DEGRADED_PENALTY
+	);
+
+	// Degraded providers lose almost every choice against healthy ones
+	let picked_a = (0..100)
+		.filter(|_| be.select_provider(None).unwrap().0.name == "a")
+		.count();
+	assert!(picked_a < 50, "{picked_a}");
+
+	// Recovery needs a score above the higher threshold
+	let (score, degraded) = record(::http::StatusCode::OK);
+	assert!((0.4..0.9).contains(&score), "{score}");
+	assert!(degraded);
+	for _ in 0..10 {
+		record(::http::StatusCode::OK);
+	}
+	let (score, degraded) = record(::http::StatusCode::OK);
+	assert!(score >= 0.9, "{score}");
+	assert!(!degraded);
+
+	// Client errors are not the provider's fault
+	let (score, _) = record(::http::StatusCode::BAD_REQUEST);
+	assert!(score >= 0.9, "{score}");
+}
@@ -144,6 +144,9 @@ impl super::RequestType for Request {
 	fn model(&mut self) -> &mut Option<String> {
 		&mut self.model
 	}
+	fn to_json(&self) -> Option<serde_json::Value> {
+		serde_json::to_value(self).ok()
+	}
 	fn prepend_prompts(&mut self, prompts: Vec<llm::types::SimpleChatCompletionMessage>) {
 		self
 			.messages
@@ -156,6 +159,12 @@ impl super::RequestType for Request {
 			.extend(prompts.into_iter().map(convert_message));
 	}
 
+	fn remove_system_prompts(&mut self) {
+		self
+			.messages
+			.retain(|m| !llm::types::is_system_role(&m.role));
+	}
+
 	fn to_anthropic(&self) -> Result<Vec<u8>, AIError> {
 		conversion::messages::from_completions::translate(self)
 	}
@@ -173,6 +182,20 @@ impl super::RequestType for Request {
 		serde_json::to_vec(&self).map_err(AIError::RequestMarshal)
 	}
 
+	fn clamp_max_tokens(&mut self, max: u64) {
+		let max = u32::try_from(max).unwrap_or(u32::MAX);
+		if self.max_completion_tokens.is_none() && self.max_tokens.is_none() {
+			self.max_completion_tokens = Some(max);
+			return;
+		}
+		for t in [&mut self.max_completion_tokens, &mut self.max_tokens]
+			.into_iter()
+			.flatten()
+		{
+			*t = (*t).min(max);
+		}
+	}
+
 	fn to_llm_request(&self, provider: Strng, tokenize: bool) -> Result<LLMRequest, AIError> {
 		let model = strng::new(self.model.as_deref().unwrap_or_default());
 		let input_tokens = if tokenize {
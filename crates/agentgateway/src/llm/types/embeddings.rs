@@ -31,6 +31,10 @@ impl RequestType for Request {
 		&mut self.model
 	}
 
+	fn to_json(&self) -> Option<serde_json::Value> {
+		serde_json::to_value(self).ok()
+	}
+
 	fn prepend_prompts(&mut self, _prompts: Vec<SimpleChatCompletionMessage>) {
 		// Ignored
 	}
@@ -65,12 +69,11 @@ impl RequestType for Request {
 	}
 
 	fn get_messages(&self) -> Vec<SimpleChatCompletionMessage> {
-		unimplemented!("get_messages is used for prompt guard; prompt guard is disable for embeddings.")
+		// Embeddings requests have input to embed, not chat messages.
+		vec![]
 	}
 
-	fn set_messages(&mut self, _messages: Vec<SimpleChatCompletionMessage>) {
-		unimplemented!("set_messages is used for prompt guard; prompt guard is disable for embeddings.")
-	}
+	fn set_messages(&mut self, _messages: Vec<SimpleChatCompletionMessage>) {}
 
 	fn to_openai(&self) -> Result<Vec<u8>, AIError> {
 		serde_json::to_vec(&self).map_err(AIError::RequestMarshal)
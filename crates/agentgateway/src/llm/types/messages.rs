@@ -97,6 +97,10 @@ impl RequestType for Request {
 		&mut self.model
 	}
 
+	fn to_json(&self) -> Option<serde_json::Value> {
+		serde_json::to_value(self).ok()
+	}
+
 	fn prepend_prompts(&mut self, prompts: Vec<SimpleChatCompletionMessage>) {
 		let (system_prompts, message_prompts): (Vec<_>, Vec<_>) = prompts
 			.into_iter()
@@ -164,6 +168,26 @@ impl RequestType for Request {
 		}
 	}
 
+	fn clamp_max_tokens(&mut self, max: u64) {
+		self.max_tokens = Some(self.max_tokens.map_or(max, |t| t.min(max)));
+	}
+
+	fn system_prompts(&self) -> Vec<Strng> {
+		match &self.system {
+			Some(RequestContent::Text(text)) => vec![strng::new(text)],
+			Some(RequestContent::Array(parts)) => parts
+				.iter()
+				.filter_map(|p| p.text.as_deref())
+				.map(strng::new)
+				.collect(),
+			None => vec![],
+		}
+	}
+
+	fn remove_system_prompts(&mut self) {
+		self.system = None;
+	}
+
 	fn to_llm_request(&self, provider: Strng, tokenize: bool) -> Result<LLMRequest, AIError> {
 		let model = strng::new(self.model.as_deref().unwrap_or_default());
 		let input_tokens = if tokenize {
@@ -3,6 +3,7 @@ pub mod completions;
 pub mod count_tokens;
 pub mod embeddings;
 pub mod messages;
+pub mod rerank;
 pub mod responses;
 
 use agent_core::prelude::Strng;
@@ -34,6 +35,27 @@ pub trait RequestType: Send + Sync {
 	fn get_messages(&self) -> Vec<SimpleChatCompletionMessage>;
 	fn set_messages(&mut self, messages: Vec<SimpleChatCompletionMessage>);
 
+	/// Cap the number of tokens the request may generate at `max`, setting a limit if it has none.
+	fn clamp_max_tokens(&mut self, _max: u64) {}
+
+	/// The text of the request's system instructions, in order.
+	fn system_prompts(&self) -> Vec<Strng> {
+		self
+			.get_messages()
+			.into_iter()
+			.filter(|m| is_system_role(&m.role))
+			.map(|m| m.content)
+			.collect()
+	}
+
+	/// Remove all of the request's system instructions.
+	fn remove_system_prompts(&mut self) {}
+
+	/// The request as JSON, in its own format.
+	fn to_json(&self) -> Option<serde_json::Value> {
+		None
+	}
+
 	fn to_openai(&self) -> Result<Vec<u8>, AIError> {
 		Err(AIError::UnsupportedConversion(strng::literal!("openai")))
 	}
@@ -58,6 +80,48 @@ pub trait RequestType: Send + Sync {
 	}
 }
 
+/// The usage reported by an embeddings or rerank response, which are passed through untranslated.
+/// Both OpenAI-style `usage` and Cohere-style `meta.billed_units` are read.
+pub fn passthrough_usage(bytes: &[u8]) -> LLMResponse {
+	#[derive(serde::Deserialize)]
+	struct Body {
+		model: Option<Strng>,
+		usage: Option<Usage>,
+		meta: Option<Meta>,
+	}
+	#[derive(serde::Deserialize)]
+	struct Usage {
+		prompt_tokens: Option<u64>,
+		total_tokens: Option<u64>,
+	}
+	#[derive(serde::Deserialize)]
+	struct Meta {
+		billed_units: Option<BilledUnits>,
+	}
+	#[derive(serde::Deserialize)]
+	struct BilledUnits {
+		input_tokens: Option<u64>,
+	}
+	let Ok(body) = serde_json::from_slice::<Body>(bytes) else {
+		return LLMResponse::default();
+	};
+	let input_tokens = body
+		.usage
+		.and_then(|u| u.prompt_tokens.or(u.total_tokens))
+		.or_else(|| body.meta?.billed_units?.input_tokens);
+	LLMResponse {
+		input_tokens,
+		total_tokens: input_tokens,
+		provider_model: body.model,
+		..Default::default()
+	}
+}
+
+/// Whether a message role carries system instructions.
+pub fn is_system_role(role: &str) -> bool {
+	matches!(role, "system" | "developer")
+}
+
 /// SimpleChatCompletionMessage is a simplified chat message
 #[apply(schema!)]
 pub struct SimpleChatCompletionMessage {
@@ -0,0 +1,87 @@
+use agent_core::prelude::Strng;
+use agent_core::strng;
+use serde::{Deserialize, Serialize};
+
+use crate::llm::types::RequestType;
+use crate::llm::{AIError, InputFormat, LLMRequest, SimpleChatCompletionMessage};
+
+/// The Cohere API version Bedrock requires for rerank models.
+const BEDROCK_COHERE_API_VERSION: u64 = 2;
+
+/// A Cohere-style rerank request, as served by Cohere, Bedrock, and OpenAI-compatible servers such
+/// as vLLM.
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct Request {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub model: Option<String>,
+	pub query: String,
+	/// The documents to rank, either strings or objects.
+	pub documents: Vec<serde_json::Value>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub top_n: Option<u64>,
+
+	// Everything else - passthrough
+	#[serde(flatten, default)]
+	pub rest: serde_json::Value,
+}
+
+impl RequestType for Request {
+	fn model(&mut self) -> &mut Option<String> {
+		&mut self.model
+	}
+
+	fn to_json(&self) -> Option<serde_json::Value> {
+		serde_json::to_value(self).ok()
+	}
+
+	fn prepend_prompts(&mut self, _prompts: Vec<SimpleChatCompletionMessage>) {
+		// Ignored
+	}
+
+	fn append_prompts(&mut self, _prompts: Vec<SimpleChatCompletionMessage>) {
+		// Ignored
+	}
+
+	fn to_llm_request(&self, provider: Strng, _tokenize: bool) -> Result<LLMRequest, AIError> {
+		let model = strng::new(self.model.as_deref().unwrap_or_default());
+		Ok(LLMRequest {
+			// We never tokenize these, so always empty
+			input_tokens: None,
+			input_format: InputFormat::Rerank,
+			request_model: model,
+			provider,
+			streaming: false,
+			params: Default::default(),
+		})
+	}
+
+	fn get_messages(&self) -> Vec<SimpleChatCompletionMessage> {
+		// Rerank requests have a query and documents, not chat messages.
+		vec![]
+	}
+
+	fn set_messages(&mut self, _messages: Vec<SimpleChatCompletionMessage>) {}
+
+	fn to_openai(&self) -> Result<Vec<u8>, AIError> {
+		serde_json::to_vec(&self).map_err(AIError::RequestMarshal)
+	}
+
+	fn to_bedrock(
+		&self,
+		_provider: &crate::llm::bedrock::Provider,
+		_headers: Option<&::http::HeaderMap>,
+		_prompt_caching: Option<&crate::llm::policy::PromptCachingConfig>,
+	) -> Result<Vec<u8>, AIError> {
+		// Bedrock serves Cohere rerank models through InvokeModel, which takes the model in the path
+		// and requires the Cohere API version in the body.
+		let mut body = serde_json::to_value(Request {
+			model: None,
+			..self.clone()
+		})
+		.map_err(AIError::RequestMarshal)?;
+		if let Some(body) = body.as_object_mut() {
+			body.insert("api_version".to_string(), BEDROCK_COHERE_API_VERSION.into());
+		}
+		serde_json::to_vec(&body).map_err(AIError::RequestMarshal)
+	}
+}
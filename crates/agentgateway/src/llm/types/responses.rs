@@ -214,6 +214,10 @@ impl RequestType for Request {
 		&mut self.model
 	}
 
+	fn to_json(&self) -> Option<serde_json::Value> {
+		serde_json::to_value(self).ok()
+	}
+
 	fn prepend_prompts(&mut self, prompts: Vec<SimpleChatCompletionMessage>) {
 		let mut items = self.take_input_as_items();
 		let prepend_items: Vec<InputItem> = prompts.into_iter().map(Into::into).collect();
@@ -227,6 +231,44 @@ impl RequestType for Request {
 		self.input = Input::Items(items);
 	}
 
+	fn clamp_max_tokens(&mut self, max: u64) {
+		let max = u32::try_from(max).unwrap_or(u32::MAX);
+		self.max_output_tokens = Some(self.max_output_tokens.map_or(max, |t| t.min(max)));
+	}
+
+	fn system_prompts(&self) -> Vec<Strng> {
+		let instructions = self
+			.rest
+			.get("instructions")
+			.and_then(|i| i.as_str())
+			.map(strng::new);
+		instructions
+			.into_iter()
+			.chain(
+				self
+					.get_messages()
+					.into_iter()
+					.filter(|m| is_system_role(&m.role))
+					.map(|m| m.content),
+			)
+			.collect()
+	}
+
+	fn remove_system_prompts(&mut self) {
+		if let Some(rest) = self.rest.as_object_mut() {
+			rest.remove("instructions");
+		}
+		if let Input::Items(items) = &mut self.input {
+			items.retain(|item| match item {
+				InputItem::EasyMessage(msg) => !matches!(msg.role, Role::System | Role::Developer),
+				InputItem::Item(Item::Message(MessageItem::Input(msg))) => {
+					!matches!(msg.role, InputRole::System | InputRole::Developer)
+				},
+				_ => true,
+			});
+		}
+	}
+
 	fn to_llm_request(&self, provider: Strng, tokenize: bool) -> Result<LLMRequest, AIError> {
 		let model = strng::new(self.model.as_deref().unwrap_or_default());
 		let input_tokens = if tokenize {
@@ -3,7 +3,7 @@
 use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use agent_core::drain::DrainWatcher;
@@ -11,16 +11,30 @@ use agent_core::version::BuildInfo;
 use agent_core::{signal, telemetry};
 use hyper::Request;
 use hyper::body::Incoming;
-use hyper::header::{CONTENT_TYPE, HeaderValue};
+use hyper::header::{CONTENT_TYPE, HeaderValue, SET_COOKIE};
+use itertools::Itertools;
+use prometheus_client::registry::Registry;
 use tokio::runtime::Handle;
 use tokio::time;
 use tracing::{info, warn};
 use tracing_subscriber::filter;
 
+use super::csrf::EndpointGroup;
 use super::hyper_helpers::{Server, empty_response, plaintext_response};
 use super::mesh::{MeshHeartbeat, MeshRegistry};
+use super::metrics_stream;
 use crate::Config;
+use crate::client::Client;
 use crate::http::Response;
+use crate::http::quota::QuotaStore;
+use crate::state_manager::ConfigScope;
+use crate::telemetry::events::{self, EventKind};
+use crate::types::agent::ListenerTarget;
+use crate::types::local::{NormalizedLocalConfig, ValidateOptions, ValidationError};
+
+#[cfg(test)]
+#[path = "admin_tests.rs"]
+mod tests;
 
 pub trait ConfigDumpHandler: Sync + Send {
 	fn key(&self) -> &'static str;
@@ -47,6 +61,9 @@ struct State {
 	admin_fallback: Option<Arc<dyn AdminFallback>>,
 	dataplane_handle: Handle,
 	mesh_registry: MeshRegistry,
+	quotas: Arc<QuotaStore>,
+	client: Client,
+	registry: Arc<Mutex<Registry>>,
 }
 
 pub struct Service {
@@ -60,6 +77,11 @@ pub struct ConfigDump {
 	stores: crate::store::Stores,
 	version: BuildInfo,
 	config: Arc<Config>,
+	/// Set while the config is served from the XDS snapshot, before any control plane is reached.
+	#[serde(skip_serializing_if = "std::ops::Not::not")]
+	stale: bool,
+	/// The feature flags in effect, including runtime overrides.
+	features: crate::features::Features,
 }
 
 #[derive(serde::Serialize, Debug, Clone, Default)]
@@ -89,6 +111,9 @@ impl Service {
 		drain_rx: DrainWatcher,
 		dataplane_handle: Handle,
 		mesh_registry: MeshRegistry,
+		quotas: Arc<QuotaStore>,
+		client: Client,
+		registry: Arc<Mutex<Registry>>,
 	) -> anyhow::Result<Self> {
 		Server::<State>::bind(
 			"admin",
@@ -102,6 +127,9 @@ impl Service {
 				admin_fallback: None,
 				dataplane_handle,
 				mesh_registry,
+				quotas,
+				client,
+				registry,
 			},
 		)
 		.await
@@ -122,7 +150,18 @@ impl Service {
 
 	pub fn spawn(self) {
 		self.s.spawn(|state, req| async move {
-			match req.uri().path() {
+			let csrf = state
+				.config
+				.admin_csrf
+				.policy(EndpointGroup::for_path(req.uri().path()));
+			if let Err(e) = csrf.check(req.method(), req.headers()) {
+				return Ok(plaintext_response(
+					hyper::StatusCode::FORBIDDEN,
+					format!("{e}\n"),
+				));
+			}
+			let set_cookie = csrf.issue_token(req.method(), req.headers());
+			let resp = match req.uri().path() {
 				#[cfg(target_os = "linux")]
 				"/debug/pprof/profile" => handle_pprof(req).await,
 				#[cfg(target_os = "linux")]
@@ -136,22 +175,47 @@ impl Service {
 					.await,
 				),
 				"/debug/tasks" => handle_tokio_tasks(req, &state.dataplane_handle).await,
-				"/config_dump" => {
+				"/config_dump" => Ok(
 					handle_config_dump(
 						&state.config_dump_handlers,
 						ConfigDump {
 							stores: state.stores.clone(),
 							version: BuildInfo::new(),
 							config: state.config.clone(),
+							stale: crate::state_manager::xds_config_stale(),
+							features: crate::features::effective(),
 						},
 					)
-					.await
+					.await,
+				),
+				"/config_status" => Ok(handle_config_status(req).await),
+				"/config/dry-run" => Ok(handle_config_dry_run(&state, req).await),
+				"/config/schema" => Ok(handle_config_schema(req).await),
+				"/config/history" => Ok(handle_config_history(req).await),
+				"/features" => Ok(handle_features(req).await),
+				"/config/rollback" => Ok(handle_config_rollback(req).await),
+				p if p == "/config/reload" || p.starts_with("/config/reload/") => {
+					Ok(handle_config_reload(req).await)
 				},
+				"/xds_status" => Ok(handle_xds_status(req).await),
+				"/debug/xds" => Ok(handle_debug_xds(req).await),
 				"/logging" => Ok(handle_logging(req).await),
 				"/mesh/register" => Ok(handle_mesh_register(&state.mesh_registry, req).await),
 				"/mesh/nodes" => Ok(handle_mesh_nodes(&state.mesh_registry, req).await),
-				"/mesh/events" => Ok(handle_mesh_events(&state.mesh_registry, req).await),
+				"/mesh/events" => Ok(handle_mesh_events(req).await),
+				"/events" => Ok(handle_events(req).await),
 				"/mesh/logs" => Ok(handle_mesh_logs(&state.mesh_registry, req).await),
+				"/quotas" => Ok(handle_quotas(&state.quotas, req).await),
+				"/quotas/reset" => Ok(handle_quotas_reset(&state.quotas, req).await),
+				"/quotas/limits" => Ok(handle_quotas_limits(&state.quotas, req).await),
+				"/costs" => Ok(handle_costs(req).await),
+				"/slo" => Ok(handle_slo(req).await),
+				"/jwks" => Ok(handle_jwks(&state.config, req).await),
+				"/debug/pools" => Ok(handle_pools(&state.client, req).await),
+				"/debug/probes" => Ok(handle_probes(req).await),
+				"/debug/overload" => Ok(handle_overload(req).await),
+				"/debug/events" => Ok(handle_debug_events(req).await),
+				"/debug/metrics/stream" => Ok(handle_metrics_stream(&state.registry, req).await),
 				_ => {
 					if let Some(h) = &state.admin_fallback {
 						Ok(h.handle(req).await)
@@ -161,7 +225,13 @@ impl Service {
 						Ok(empty_response(hyper::StatusCode::NOT_FOUND))
 					}
 				},
-			}
+			};
+			resp.map(|mut r| {
+				if let Some(c) = set_cookie {
+					r.headers_mut().append(SET_COOKIE, c);
+				}
+				r
+			})
 		})
 	}
 }
@@ -178,7 +248,62 @@ async fn handle_dashboard(_req: Request<Incoming>) -> Response {
 		),
 		("quitquitquit", "shut down the server"),
 		("config_dump", "dump the current agentgateway configuration"),
+		("config/schema", "JSON Schema of the configuration file"),
+		(
+			"config/dry-run",
+			"POST a local config to preview the resources it resolves to, and any errors, without applying it",
+		),
+		(
+			"config/reload",
+			"POST to reload the local config; POST to config/reload/{listeners,backends,policies,telemetry,services} to reload only that part",
+		),
+		(
+			"debug/xds",
+			"XDS control plane status and the resources most recently rejected",
+		),
+		(
+			"config/history",
+			"recently applied generations of the local config",
+		),
+		(
+			"config/rollback",
+			"POST with ?to=<generation> to re-apply a previous generation of the local config",
+		),
+		(
+			"features",
+			"feature flags in effect; POST with ?name=<flag>&enabled=<bool> to override one at runtime",
+		),
 		("logging", "query/changing logging levels"),
+		("quotas", "dump per-identity quota usage"),
+		("quotas/reset", "reset quota usage for one identity (?key=) or all identities"),
+		(
+			"quotas/limits",
+			"set the quota limits of one identity (?key=), or restore its policy's limits with an empty body",
+		),
+		(
+			"costs",
+			"report LLM spend over a time window (?window= or ?start=&end=, ?groupBy=)",
+		),
+		("slo", "report error budgets and burn rates per service level objective"),
+		("jwks", "public keys for validating gateway-minted session tokens"),
+		("debug/pools", "dump upstream connection pool state per backend"),
+		("debug/probes", "dump the latest synthetic probe result per backend"),
+		(
+			"debug/overload",
+			"current overload level, event loop lag and CPU usage, and recent level transitions",
+		),
+		(
+			"debug/events",
+			"recent upstream failures, retries, evictions, and policy denials (?since=, ?kind=)",
+		),
+		(
+			"debug/metrics/stream",
+			"stream metric deltas as SSE or NDJSON (?interval=, ?format=, ?metric=)",
+		),
+		(
+			"events",
+			"stream gateway health events as SSE (?kind= to filter by event kind)",
+		),
 	];
 
 	let mut api_rows = String::new();
@@ -310,26 +435,94 @@ async fn handle_tokio_tasks(
 	)
 }
 
-async fn handle_config_dump(
-	handlers: &[Arc<dyn ConfigDumpHandler>],
+/// The config dump along with the sections from each ConfigDumpHandler.
+#[derive(serde::Serialize)]
+struct FullConfigDump {
+	#[serde(flatten)]
 	dump: ConfigDump,
-) -> anyhow::Result<Response> {
-	let serde_json::Value::Object(mut kv) = serde_json::to_value(&dump)? else {
-		anyhow::bail!("config dump is not a key-value pair")
+	#[serde(flatten)]
+	handlers: HandlerSections,
+}
+
+/// The sections from each ConfigDumpHandler. Each is produced as it is written, so only one is held
+/// in memory at a time.
+struct HandlerSections(Vec<Arc<dyn ConfigDumpHandler>>);
+
+impl serde::Serialize for HandlerSections {
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		use serde::ser::{Error, SerializeMap};
+		let mut map = serializer.serialize_map(Some(self.0.len()))?;
+		for h in &self.0 {
+			let section = h.handle().map_err(S::Error::custom)?;
+			map.serialize_entry(h.key(), &section)?;
+		}
+		map.end()
+	}
+}
+
+async fn handle_config_dump(handlers: &[Arc<dyn ConfigDumpHandler>], dump: ConfigDump) -> Response {
+	let dump = FullConfigDump {
+		dump,
+		handlers: HandlerSections(handlers.to_vec()),
 	};
 
-	for h in handlers {
-		let x = h.handle()?;
-		kv.insert(h.key().to_string(), x);
+	// The dump can be tens of megabytes, so rather than building it in memory it is serialized on
+	// a blocking thread, handler sections included, and sent in chunks as the client reads them.
+	let (tx, rx) = tokio::sync::mpsc::channel(4);
+	tokio::task::spawn_blocking(move || {
+		let mut w = ChunkWriter {
+			buf: bytes::BytesMut::with_capacity(CHUNK_SIZE),
+			tx,
+		};
+		let res = serde_json::to_writer_pretty(&mut w, &dump)
+			.map_err(std::io::Error::from)
+			.and_then(|_| std::io::Write::flush(&mut w));
+		if let Err(e) = res {
+			if e.kind() == std::io::ErrorKind::BrokenPipe {
+				return;
+			}
+			warn!("failed to write config dump: {e}");
+			// Abort the response, so the client does not mistake a truncated dump for a whole one.
+			let _ = w.tx.blocking_send(Err(e));
+		}
+	});
+	let stream = tokio_stream::wrappers::ReceiverStream::new(rx);
+	let body = crate::http::Body::new(http_body_util::StreamBody::new(stream));
+	::http::Response::builder()
+		.status(hyper::StatusCode::OK)
+		.header(hyper::header::CONTENT_TYPE, "application/json")
+		.body(body)
+		.expect("builder with known status code should not fail")
+}
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Writes to a response body in chunks of CHUNK_SIZE, waiting for the body to be read when the
+/// channel is full.
+struct ChunkWriter {
+	buf: bytes::BytesMut,
+	tx: tokio::sync::mpsc::Sender<std::io::Result<hyper::body::Frame<bytes::Bytes>>>,
+}
+
+impl std::io::Write for ChunkWriter {
+	fn write(&mut self, b: &[u8]) -> std::io::Result<usize> {
+		self.buf.extend_from_slice(b);
+		if self.buf.len() >= CHUNK_SIZE {
+			self.flush()?;
+		}
+		Ok(b.len())
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		if self.buf.is_empty() {
+			return Ok(());
+		}
+		let chunk = self.buf.split().freeze();
+		self
+			.tx
+			.blocking_send(Ok(hyper::body::Frame::data(chunk)))
+			.map_err(|_| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "client went away"))
 	}
-	let body = serde_json::to_string_pretty(&kv)?;
-	Ok(
-		::http::Response::builder()
-			.status(hyper::StatusCode::OK)
-			.header(hyper::header::CONTENT_TYPE, "application/json")
-			.body(body.into())
-			.expect("builder with known status code should not fail"),
-	)
 }
 
 // mirror envoy's behavior: https://www.envoyproxy.io/docs/envoy/latest/operations/admin#post--logging
@@ -461,6 +654,12 @@ async fn handle_jemalloc_pprof_heapgen(_req: Request<Incoming>) -> anyhow::Resul
 }
 async fn handle_mesh_register(registry: &MeshRegistry, req: Request<Incoming>) -> Response {
 	use http_body_util::BodyExt;
+	if !crate::features::enabled(crate::features::Feature::MeshDiscovery) {
+		return plaintext_response(
+			hyper::StatusCode::FORBIDDEN,
+			"mesh discovery is disabled\n".to_string(),
+		);
+	}
 	match *req.method() {
 		hyper::Method::POST => {
 			let token = req.headers()
@@ -574,19 +773,741 @@ async fn handle_mesh_nodes(registry: &MeshRegistry, _req: Request<Incoming>) ->
 	response
 }
 
-async fn handle_mesh_events(registry: &MeshRegistry, _req: Request<Incoming>) -> Response {
+async fn handle_quotas(quotas: &QuotaStore, _req: Request<Incoming>) -> Response {
+	let json_body = match serde_json::to_string_pretty(&quotas.snapshot()) {
+		Ok(j) => j,
+		Err(e) => {
+			return plaintext_response(
+				hyper::StatusCode::INTERNAL_SERVER_ERROR,
+				format!("failed to serialize quota usage: {e}\n"),
+			);
+		},
+	};
+
+	let mut response = plaintext_response(hyper::StatusCode::OK, json_body);
+	response.headers_mut().insert(
+		hyper::header::CONTENT_TYPE,
+		hyper::header::HeaderValue::from_static("application/json"),
+	);
+	response
+}
+
+async fn handle_costs(req: Request<Incoming>) -> Response {
+	use crate::telemetry::costs;
+
+	let query = match costs::Query::parse(req.uri().query(), chrono::Utc::now()) {
+		Ok(q) => q,
+		Err(e) => return plaintext_response(hyper::StatusCode::BAD_REQUEST, format!("{e}\n")),
+	};
+	let Some(report) = costs::report(&query) else {
+		return plaintext_response(
+			hyper::StatusCode::NOT_FOUND,
+			"costs are not configured\n".to_string(),
+		);
+	};
+	let json_body = match serde_json::to_string_pretty(&report) {
+		Ok(j) => j,
+		Err(e) => {
+			return plaintext_response(
+				hyper::StatusCode::INTERNAL_SERVER_ERROR,
+				format!("failed to serialize cost report: {e}\n"),
+			);
+		},
+	};
+
+	let mut response = plaintext_response(hyper::StatusCode::OK, json_body);
+	response.headers_mut().insert(
+		hyper::header::CONTENT_TYPE,
+		hyper::header::HeaderValue::from_static("application/json"),
+	);
+	response
+}
+
+async fn handle_pools(client: &Client, _req: Request<Incoming>) -> Response {
+	let json_body = match serde_json::to_string_pretty(&client.pool_stats()) {
+		Ok(j) => j,
+		Err(e) => {
+			return plaintext_response(
+				hyper::StatusCode::INTERNAL_SERVER_ERROR,
+				format!("failed to serialize pool stats: {e}\n"),
+			);
+		},
+	};
+
+	let mut response = plaintext_response(hyper::StatusCode::OK, json_body);
+	response.headers_mut().insert(
+		hyper::header::CONTENT_TYPE,
+		hyper::header::HeaderValue::from_static("application/json"),
+	);
+	response
+}
+
+async fn handle_slo(_req: Request<Incoming>) -> Response {
+	let Some(report) = crate::telemetry::slo::report() else {
+		return plaintext_response(
+			hyper::StatusCode::NOT_FOUND,
+			"slos are not configured\n".to_string(),
+		);
+	};
+	let json_body = match serde_json::to_string_pretty(&report) {
+		Ok(j) => j,
+		Err(e) => {
+			return plaintext_response(
+				hyper::StatusCode::INTERNAL_SERVER_ERROR,
+				format!("failed to serialize slo report: {e}\n"),
+			);
+		},
+	};
+
+	let mut response = plaintext_response(hyper::StatusCode::OK, json_body);
+	response.headers_mut().insert(
+		hyper::header::CONTENT_TYPE,
+		hyper::header::HeaderValue::from_static("application/json"),
+	);
+	response
+}
+
+async fn handle_config_status(_req: Request<Incoming>) -> Response {
+	let Some(status) = crate::state_manager::config_status() else {
+		return plaintext_response(
+			hyper::StatusCode::NOT_FOUND,
+			"local config is not configured\n".to_string(),
+		);
+	};
+	let json_body = match serde_json::to_string_pretty(&status) {
+		Ok(j) => j,
+		Err(e) => {
+			return plaintext_response(
+				hyper::StatusCode::INTERNAL_SERVER_ERROR,
+				format!("failed to serialize config status: {e}\n"),
+			);
+		},
+	};
+
+	let mut response = plaintext_response(hyper::StatusCode::OK, json_body);
+	response.headers_mut().insert(
+		hyper::header::CONTENT_TYPE,
+		hyper::header::HeaderValue::from_static("application/json"),
+	);
+	response
+}
+
+#[cfg(feature = "schema")]
+async fn handle_config_schema(_req: Request<Incoming>) -> Response {
+	let json_body = match crate::serdes::json_schema::<crate::types::local::LocalConfig>() {
+		Ok(j) => j,
+		Err(e) => {
+			return plaintext_response(
+				hyper::StatusCode::INTERNAL_SERVER_ERROR,
+				format!("failed to generate config schema: {e}\n"),
+			);
+		},
+	};
+
+	let mut response = plaintext_response(hyper::StatusCode::OK, json_body);
+	response.headers_mut().insert(
+		hyper::header::CONTENT_TYPE,
+		hyper::header::HeaderValue::from_static("application/json"),
+	);
+	response
+}
+
+#[cfg(not(feature = "schema"))]
+async fn handle_config_schema(_req: Request<Incoming>) -> Response {
+	plaintext_response(
+		hyper::StatusCode::NOT_FOUND,
+		"the config schema is not supported in this build\n".to_string(),
+	)
+}
+
+/// Reload the local config now. `/config/reload/<scope>` only applies one part of it, such as
+/// `backends`, leaving the rest of the running config untouched.
+async fn handle_config_reload(req: Request<Incoming>) -> Response {
+	if req.method() != hyper::Method::POST {
+		return plaintext_response(
+			hyper::StatusCode::METHOD_NOT_ALLOWED,
+			"Invalid HTTP method\n".to_string(),
+		);
+	}
+	let scopes = match req.uri().path().strip_prefix("/config/reload/") {
+		None | Some("") => ConfigScope::ALL.to_vec(),
+		Some(scope) => match scope.parse::<ConfigScope>() {
+			Ok(scope) => vec![scope],
+			Err(e) => {
+				return plaintext_response(hyper::StatusCode::NOT_FOUND, format!("{e}\n"));
+			},
+		},
+	};
+	match crate::state_manager::reload_local_config(scopes.clone()).await {
+		None => plaintext_response(
+			hyper::StatusCode::NOT_FOUND,
+			"local config is not configured\n".to_string(),
+		),
+		Some(Ok(())) => plaintext_response(
+			hyper::StatusCode::OK,
+			format!("reloaded {}\n", scopes.iter().join(", ")),
+		),
+		Some(Err(e)) => plaintext_response(
+			hyper::StatusCode::BAD_REQUEST,
+			format!("config rejected: {e:#}\n"),
+		),
+	}
+}
+
+async fn handle_config_history(_req: Request<Incoming>) -> Response {
+	if crate::state_manager::config_status().is_none() {
+		return plaintext_response(
+			hyper::StatusCode::NOT_FOUND,
+			"local config is not configured\n".to_string(),
+		);
+	}
+	let json_body = match serde_json::to_string_pretty(&crate::state_manager::config_history()) {
+		Ok(j) => j,
+		Err(e) => {
+			return plaintext_response(
+				hyper::StatusCode::INTERNAL_SERVER_ERROR,
+				format!("failed to serialize config history: {e}\n"),
+			);
+		},
+	};
+
+	let mut response = plaintext_response(hyper::StatusCode::OK, json_body);
+	response.headers_mut().insert(
+		hyper::header::CONTENT_TYPE,
+		hyper::header::HeaderValue::from_static("application/json"),
+	);
+	response
+}
+
+/// Re-apply a previous generation of the local config, as listed by `/config/history`.
+async fn handle_config_rollback(req: Request<Incoming>) -> Response {
+	if req.method() != hyper::Method::POST {
+		return plaintext_response(
+			hyper::StatusCode::METHOD_NOT_ALLOWED,
+			"Invalid HTTP method\n".to_string(),
+		);
+	}
+	let Some(to) = req.uri().query().and_then(|v| {
+		url::form_urlencoded::parse(v.as_bytes())
+			.find(|(k, _)| k == "to")
+			.map(|(_, v)| v.into_owned())
+	}) else {
+		return plaintext_response(
+			hyper::StatusCode::BAD_REQUEST,
+			"missing to query parameter\n".to_string(),
+		);
+	};
+	let Ok(generation) = to.parse::<u64>() else {
+		return plaintext_response(
+			hyper::StatusCode::BAD_REQUEST,
+			format!("invalid generation {to}\n"),
+		);
+	};
+	if !crate::state_manager::config_history()
+		.iter()
+		.any(|g| g.generation == generation)
+	{
+		return plaintext_response(
+			hyper::StatusCode::NOT_FOUND,
+			format!("config generation {generation} is not in the history\n"),
+		);
+	}
+	match crate::state_manager::rollback_local_config(generation).await {
+		None => plaintext_response(
+			hyper::StatusCode::NOT_FOUND,
+			"local config is not configured\n".to_string(),
+		),
+		Some(Ok(())) => plaintext_response(
+			hyper::StatusCode::OK,
+			format!("rolled back to generation {generation}\n"),
+		),
+		Some(Err(e)) => plaintext_response(
+			hyper::StatusCode::BAD_REQUEST,
+			format!("config rejected: {e:#}\n"),
+		),
+	}
+}
+
+/// Report the feature flags in effect, or override one with POST `?name=<flag>&enabled=<bool>`.
+async fn handle_features(req: Request<Incoming>) -> Response {
+	match *req.method() {
+		hyper::Method::GET => {},
+		hyper::Method::POST => {
+			let params: HashMap<String, String> = req
+				.uri()
+				.query()
+				.map(|v| {
+					url::form_urlencoded::parse(v.as_bytes())
+						.into_owned()
+						.collect()
+				})
+				.unwrap_or_default();
+			let (Some(name), Some(enabled)) = (params.get("name"), params.get("enabled")) else {
+				return plaintext_response(
+					hyper::StatusCode::BAD_REQUEST,
+					"missing name or enabled query parameter\n".to_string(),
+				);
+			};
+			let feature = match name.parse::<crate::features::Feature>() {
+				Ok(f) => f,
+				Err(e) => {
+					return plaintext_response(hyper::StatusCode::BAD_REQUEST, format!("{e}\n"));
+				},
+			};
+			let Ok(enabled) = enabled.parse::<bool>() else {
+				return plaintext_response(
+					hyper::StatusCode::BAD_REQUEST,
+					format!("invalid enabled value {enabled}\n"),
+				);
+			};
+			info!(feature = %name, enabled, "feature flag overridden");
+			crate::features::set(feature, enabled);
+		},
+		_ => {
+			return plaintext_response(
+				hyper::StatusCode::METHOD_NOT_ALLOWED,
+				"Invalid HTTP method\n".to_string(),
+			);
+		},
+	}
+	let json_body = match serde_json::to_string_pretty(&crate::features::effective()) {
+		Ok(j) => j,
+		Err(e) => {
+			return plaintext_response(
+				hyper::StatusCode::INTERNAL_SERVER_ERROR,
+				format!("failed to serialize features: {e}\n"),
+			);
+		},
+	};
+
+	let mut response = plaintext_response(hyper::StatusCode::OK, json_body);
+	response.headers_mut().insert(
+		hyper::header::CONTENT_TYPE,
+		hyper::header::HeaderValue::from_static("application/json"),
+	);
+	response
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DryRun {
+	valid: bool,
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	errors: Vec<ValidationError>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	resources: Option<NormalizedLocalConfig>,
+}
+
+/// The largest config accepted by `/config/dry-run`.
+const DRY_RUN_BODY_LIMIT: usize = 4 * 1024 * 1024;
+
+/// Translate and validate a local config as if it were applied, without changing the stores.
+/// The config is not interpolated and its secrets are not resolved, so the response cannot reveal
+/// the gateway's environment, files, or secrets.
+async fn handle_config_dry_run(state: &State, req: Request<Incoming>) -> Response {
+	if req.method() != hyper::Method::POST {
+		return plaintext_response(
+			hyper::StatusCode::METHOD_NOT_ALLOWED,
+			"Invalid HTTP method\n".to_string(),
+		);
+	}
+	let body = crate::http::Body::new(req.into_body());
+	let body = match crate::http::read_body_with_limit(body, DRY_RUN_BODY_LIMIT).await {
+		Ok(b) => b,
+		Err(e) => {
+			return plaintext_response(
+				hyper::StatusCode::BAD_REQUEST,
+				format!("failed to read body: {e}\n"),
+			);
+		},
+	};
+	let Ok(body) = std::str::from_utf8(&body) else {
+		return plaintext_response(
+			hyper::StatusCode::BAD_REQUEST,
+			"config is not valid UTF-8\n".to_string(),
+		);
+	};
+	let gateway = ListenerTarget {
+		gateway_name: state.config.xds.gateway.clone(),
+		gateway_namespace: state.config.xds.namespace.clone(),
+		listener_name: None,
+	};
+	let opts = ValidateOptions {
+		services_from_xds: state.config.xds.address.is_some(),
+		interpolate: false,
+	};
+	let result = NormalizedLocalConfig::validate(state.client.clone(), gateway, body, opts).await;
+	let (status, result) = match result {
+		Ok(resources) => (
+			hyper::StatusCode::OK,
+			DryRun {
+				valid: true,
+				errors: vec![],
+				resources: Some(resources),
+			},
+		),
+		Err(errors) => (
+			hyper::StatusCode::BAD_REQUEST,
+			DryRun {
+				valid: false,
+				errors,
+				resources: None,
+			},
+		),
+	};
+	let json_body = match serde_json::to_string_pretty(&result) {
+		Ok(j) => j,
+		Err(e) => {
+			return plaintext_response(
+				hyper::StatusCode::INTERNAL_SERVER_ERROR,
+				format!("failed to serialize dry run result: {e}\n"),
+			);
+		},
+	};
+
+	let mut response = plaintext_response(status, json_body);
+	response.headers_mut().insert(
+		hyper::header::CONTENT_TYPE,
+		hyper::header::HeaderValue::from_static("application/json"),
+	);
+	response
+}
+
+async fn handle_xds_status(_req: Request<Incoming>) -> Response {
+	let Some(status) = crate::state_manager::control_plane_status() else {
+		return plaintext_response(
+			hyper::StatusCode::NOT_FOUND,
+			"xds is not configured\n".to_string(),
+		);
+	};
+	let json_body = match serde_json::to_string_pretty(&status) {
+		Ok(j) => j,
+		Err(e) => {
+			return plaintext_response(
+				hyper::StatusCode::INTERNAL_SERVER_ERROR,
+				format!("failed to serialize xds status: {e}\n"),
+			);
+		},
+	};
+
+	let mut response = plaintext_response(hyper::StatusCode::OK, json_body);
+	response.headers_mut().insert(
+		hyper::header::CONTENT_TYPE,
+		hyper::header::HeaderValue::from_static("application/json"),
+	);
+	response
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct XdsDebug {
+	control_planes: Vec<agent_xds::ControlPlaneStatus>,
+	stale: bool,
+	rejections: Vec<agent_xds::Rejection>,
+}
+
+async fn handle_debug_xds(_req: Request<Incoming>) -> Response {
+	let (Some(control_planes), Some(rejections)) = (
+		crate::state_manager::control_plane_status(),
+		crate::state_manager::xds_rejections(),
+	) else {
+		return plaintext_response(
+			hyper::StatusCode::NOT_FOUND,
+			"xds is not configured\n".to_string(),
+		);
+	};
+	let debug = XdsDebug {
+		control_planes,
+		stale: crate::state_manager::xds_config_stale(),
+		rejections,
+	};
+	let json_body = match serde_json::to_string_pretty(&debug) {
+		Ok(j) => j,
+		Err(e) => {
+			return plaintext_response(
+				hyper::StatusCode::INTERNAL_SERVER_ERROR,
+				format!("failed to serialize xds debug info: {e}\n"),
+			);
+		},
+	};
+
+	let mut response = plaintext_response(hyper::StatusCode::OK, json_body);
+	response.headers_mut().insert(
+		hyper::header::CONTENT_TYPE,
+		hyper::header::HeaderValue::from_static("application/json"),
+	);
+	response
+}
+
+async fn handle_probes(_req: Request<Incoming>) -> Response {
+	let Some(results) = crate::proxy::prober::results() else {
+		return plaintext_response(
+			hyper::StatusCode::NOT_FOUND,
+			"probes are not configured\n".to_string(),
+		);
+	};
+	let json_body = match serde_json::to_string_pretty(&results) {
+		Ok(j) => j,
+		Err(e) => {
+			return plaintext_response(
+				hyper::StatusCode::INTERNAL_SERVER_ERROR,
+				format!("failed to serialize probe results: {e}\n"),
+			);
+		},
+	};
+
+	let mut response = plaintext_response(hyper::StatusCode::OK, json_body);
+	response.headers_mut().insert(
+		hyper::header::CONTENT_TYPE,
+		hyper::header::HeaderValue::from_static("application/json"),
+	);
+	response
+}
+
+async fn handle_overload(_req: Request<Incoming>) -> Response {
+	let Some(report) = crate::overload::report() else {
+		return plaintext_response(
+			hyper::StatusCode::NOT_FOUND,
+			"overload manager is not configured\n".to_string(),
+		);
+	};
+	let json_body = match serde_json::to_string_pretty(&report) {
+		Ok(j) => j,
+		Err(e) => {
+			return plaintext_response(
+				hyper::StatusCode::INTERNAL_SERVER_ERROR,
+				format!("failed to serialize overload report: {e}\n"),
+			);
+		},
+	};
+
+	let mut response = plaintext_response(hyper::StatusCode::OK, json_body);
+	response.headers_mut().insert(
+		hyper::header::CONTENT_TYPE,
+		hyper::header::HeaderValue::from_static("application/json"),
+	);
+	response
+}
+
+async fn handle_metrics_stream(
+	registry: &Arc<Mutex<Registry>>,
+	req: Request<Incoming>,
+) -> Response {
 	use futures::StreamExt;
-	use tokio_stream::wrappers::BroadcastStream;
 
-	let rx = registry.subscribe();
-	let stream = BroadcastStream::new(rx).map(|event| {
-		match event {
-			Ok(e) => {
-				let json = serde_json::to_string(&e).unwrap_or_default();
-				Ok::<_, std::convert::Infallible>(format!("data: {}\n\n", json))
+	let opts = match metrics_stream::Options::parse(req.uri().query()) {
+		Ok(opts) => opts,
+		Err(e) => return plaintext_response(hyper::StatusCode::BAD_REQUEST, format!("{e}\n")),
+	};
+	let content_type = opts.format.content_type();
+	let stream = metrics_stream::stream(registry.clone(), opts).map(|s| {
+		Ok::<_, std::convert::Infallible>(hyper::body::Frame::data(bytes::Bytes::from(s)))
+	});
+	::http::Response::builder()
+		.status(hyper::StatusCode::OK)
+		.header(hyper::header::CONTENT_TYPE, content_type)
+		.header(hyper::header::CACHE_CONTROL, "no-cache")
+		.body(crate::http::Body::new(http_body_util::StreamBody::new(stream)))
+		.expect("builder with known status code should not fail")
+}
+
+async fn handle_quotas_reset(quotas: &QuotaStore, req: Request<Incoming>) -> Response {
+	if req.method() != hyper::Method::POST {
+		return plaintext_response(
+			hyper::StatusCode::METHOD_NOT_ALLOWED,
+			"Invalid HTTP method\n".to_string(),
+		);
+	}
+	let qp: HashMap<String, String> = req
+		.uri()
+		.query()
+		.map(|v| {
+			url::form_urlencoded::parse(v.as_bytes())
+				.into_owned()
+				.collect()
+		})
+		.unwrap_or_default();
+	let reset = quotas.reset(qp.get("key").map(String::as_str));
+	plaintext_response(
+		hyper::StatusCode::OK,
+		format!("reset quota usage for {reset} identities\n"),
+	)
+}
+
+async fn handle_quotas_limits(quotas: &QuotaStore, req: Request<Incoming>) -> Response {
+	use http_body_util::BodyExt;
+	if req.method() != hyper::Method::POST {
+		return plaintext_response(
+			hyper::StatusCode::METHOD_NOT_ALLOWED,
+			"Invalid HTTP method\n".to_string(),
+		);
+	}
+	let Some(key) = req.uri().query().and_then(|v| {
+		url::form_urlencoded::parse(v.as_bytes())
+			.find(|(k, _)| k == "key")
+			.map(|(_, v)| v.into_owned())
+	}) else {
+		return plaintext_response(
+			hyper::StatusCode::BAD_REQUEST,
+			"missing key query parameter\n".to_string(),
+		);
+	};
+	let body = match req.into_body().collect().await {
+		Ok(b) => b.to_bytes(),
+		Err(e) => {
+			return plaintext_response(
+				hyper::StatusCode::BAD_REQUEST,
+				format!("failed to read body: {e}\n"),
+			);
+		},
+	};
+	let limits = if body.iter().all(u8::is_ascii_whitespace) {
+		None
+	} else {
+		match serde_json::from_slice::<Vec<crate::http::quota::QuotaLimit>>(&body) {
+			Ok(l) => Some(l),
+			Err(e) => {
+				return plaintext_response(
+					hyper::StatusCode::BAD_REQUEST,
+					format!("failed to parse quota limits: {e}\n"),
+				);
 			},
-			Err(_) => Ok::<_, std::convert::Infallible>(": keep-alive\n\n".to_string()),
 		}
+	};
+	let msg = if limits.is_some() {
+		format!("set quota limits for {key}\n")
+	} else {
+		format!("restored policy quota limits for {key}\n")
+	};
+	quotas.set_limits(&key, limits);
+	plaintext_response(hyper::StatusCode::OK, msg)
+}
+
+async fn handle_jwks(config: &Config, _req: Request<Incoming>) -> Response {
+	let jwks = config
+		.token_issuer
+		.as_ref()
+		.map(|i| i.jwks().clone())
+		.unwrap_or(jsonwebtoken::jwk::JwkSet { keys: vec![] });
+	let json_body = match serde_json::to_string_pretty(&jwks) {
+		Ok(j) => j,
+		Err(e) => {
+			return plaintext_response(
+				hyper::StatusCode::INTERNAL_SERVER_ERROR,
+				format!("failed to serialize jwks: {e}\n"),
+			);
+		},
+	};
+
+	let mut response = plaintext_response(hyper::StatusCode::OK, json_body);
+	response.headers_mut().insert(
+		hyper::header::CONTENT_TYPE,
+		hyper::header::HeaderValue::from_static("application/json"),
+	);
+	response
+}
+
+async fn handle_mesh_events(_req: Request<Incoming>) -> Response {
+	// Mesh clients receive the mesh events alone, as they were before the event bus.
+	event_stream(|e| match e.kind {
+		EventKind::Mesh(m) => Some(m),
+		_ => None,
+	})
+}
+
+/// The event kinds selected by `kind` query parameters. Empty selects every kind.
+fn event_kinds(query: Option<&str>) -> Vec<String> {
+	url::form_urlencoded::parse(query.unwrap_or_default().as_bytes())
+		.filter(|(k, _)| k == "kind")
+		.flat_map(|(_, v)| {
+			v.split(',')
+				.map(str::trim)
+				.filter(|k| !k.is_empty())
+				.map(str::to_string)
+				.collect::<Vec<_>>()
+		})
+		.collect()
+}
+
+async fn handle_events(req: Request<Incoming>) -> Response {
+	let kinds = event_kinds(req.uri().query());
+	event_stream(move |e| {
+		(kinds.is_empty() || kinds.iter().any(|k| k == e.kind.name())).then_some(e)
+	})
+}
+
+async fn handle_debug_events(req: Request<Incoming>) -> Response {
+	let query = req.uri().query();
+	let since = url::form_urlencoded::parse(query.unwrap_or_default().as_bytes())
+		.find(|(k, _)| k == "since")
+		.map(|(_, v)| events::parse_since(&v, chrono::Utc::now()))
+		.transpose();
+	let since = match since {
+		Ok(s) => s,
+		Err(e) => return plaintext_response(hyper::StatusCode::BAD_REQUEST, format!("{e}\n")),
+	};
+	let kinds = event_kinds(query);
+	let recent: Vec<events::Event> = events::recent(since)
+		.into_iter()
+		.filter(|e| kinds.is_empty() || kinds.iter().any(|k| k == e.kind.name()))
+		.collect();
+	let json_body = match serde_json::to_string_pretty(&recent) {
+		Ok(j) => j,
+		Err(e) => {
+			return plaintext_response(
+				hyper::StatusCode::INTERNAL_SERVER_ERROR,
+				format!("failed to serialize events: {e}\n"),
+			);
+		},
+	};
+
+	let mut response = plaintext_response(hyper::StatusCode::OK, json_body);
+	response.headers_mut().insert(
+		hyper::header::CONTENT_TYPE,
+		hyper::header::HeaderValue::from_static("application/json"),
+	);
+	response
+}
+
+/// Stream events from the event bus as SSE. Events `f` returns `None` for are skipped.
+///
+/// If the client falls too far behind, it is sent a `resync` event and the stream ends, so it
+/// knows to fetch the current state rather than carry on with missing events. When the gateway
+/// shuts down, every client is sent a `shutdown` event and the stream ends.
+fn event_stream<T: serde::Serialize>(
+	f: impl Fn(events::Event) -> Option<T> + Send + 'static,
+) -> Response {
+	use futures::StreamExt;
+
+	let events = futures::stream::unfold(Some(events::subscribe()), |sub| async move {
+		let mut sub = sub?;
+		match sub.recv().await {
+			// The gateway is shutting down, so end the stream after this.
+			Ok(e) if matches!(e.kind, EventKind::ShuttingDown { .. }) => Some((Ok(e), None)),
+			Ok(e) => Some((Ok(e), Some(sub))),
+			// The subscription is closed, so end the stream after this.
+			Err(e) => Some((Err(e), None)),
+		}
+	});
+	let stream = events.filter_map(move |event| {
+		let frame = match event {
+			// Sent regardless of `f`, so every client knows why the stream ended.
+			Ok(e) if matches!(e.kind, EventKind::ShuttingDown { .. }) => Some(format!(
+				"event: shutdown\ndata: {}\n\n",
+				serde_json::to_string(&e).unwrap_or_default()
+			)),
+			Ok(e) => f(e).map(|e| {
+				let json = serde_json::to_string(&e).unwrap_or_default();
+				format!("data: {}\n\n", json)
+			}),
+			Err(e) => Some(format!(
+				"event: resync\ndata: {}\n\n",
+				serde_json::json!({ "reason": e.to_string() })
+			)),
+		};
+		futures::future::ready(frame.map(|s| {
+			Ok::<_, std::convert::Infallible>(hyper::body::Frame::data(bytes::Bytes::from(s)))
+		}))
 	});
 
 	::http::Response::builder()
@@ -594,8 +1515,6 @@ async fn handle_mesh_events(registry: &MeshRegistry, _req: Request<Incoming>) ->
 		.header(hyper::header::CONTENT_TYPE, "text/event-stream")
 		.header(hyper::header::CACHE_CONTROL, "no-cache")
 		.header(hyper::header::CONNECTION, "keep-alive")
-		.body(crate::http::Body::new(http_body_util::StreamBody::new(
-			stream.map(|s| Ok::<_, std::convert::Infallible>(hyper::body::Frame::data(bytes::Bytes::from(s.unwrap()))))
-		)))
+		.body(crate::http::Body::new(http_body_util::StreamBody::new(stream)))
 		.expect("builder with known status code should not fail")
 }
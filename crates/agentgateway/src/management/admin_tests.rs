@@ -0,0 +1,70 @@
+use http_body_util::BodyExt;
+
+use super::*;
+
+struct Section(&'static str, usize);
+
+impl ConfigDumpHandler for Section {
+	fn key(&self) -> &'static str {
+		self.0
+	}
+
+	fn handle(&self) -> anyhow::Result<serde_json::Value> {
+		Ok(serde_json::Value::String("x".repeat(self.1)))
+	}
+}
+
+struct Failing;
+
+impl ConfigDumpHandler for Failing {
+	fn key(&self) -> &'static str {
+		"failing"
+	}
+
+	fn handle(&self) -> anyhow::Result<serde_json::Value> {
+		anyhow::bail!("section unavailable")
+	}
+}
+
+fn dump() -> ConfigDump {
+	ConfigDump {
+		stores: crate::store::Stores::new(),
+		version: BuildInfo::new(),
+		config: Arc::new(crate::config::parse_config("{}".to_string(), None).unwrap()),
+		stale: false,
+		features: crate::features::effective(),
+	}
+}
+
+#[tokio::test]
+async fn test_config_dump_chunked() {
+	let handlers: Vec<Arc<dyn ConfigDumpHandler>> = vec![
+		Arc::new(Section("small", 10)),
+		Arc::new(Section("large", 3 * CHUNK_SIZE)),
+	];
+	let mut body = handle_config_dump(&handlers, dump()).await.into_body();
+	let mut frames = 0;
+	let mut buf = Vec::new();
+	while let Some(frame) = body.frame().await {
+		buf.extend_from_slice(&frame.unwrap().into_data().unwrap());
+		frames += 1;
+	}
+	assert!(
+		frames > 1,
+		"expected the dump in several chunks, got {frames}"
+	);
+
+	let v: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+	assert!(v.get("config").is_some());
+	assert_eq!(v["small"], "x".repeat(10));
+	assert_eq!(v["large"].as_str().unwrap().len(), 3 * CHUNK_SIZE);
+}
+
+#[tokio::test]
+async fn test_config_dump_handler_error() {
+	let handlers: Vec<Arc<dyn ConfigDumpHandler>> =
+		vec![Arc::new(Section("small", 10)), Arc::new(Failing)];
+	let body = handle_config_dump(&handlers, dump()).await.into_body();
+	// The response is aborted, rather than ending with a truncated dump
+	assert!(body.collect().await.is_err());
+}
@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+use ::http::{HeaderMap, HeaderValue, Method, header};
+use macro_rules_attribute::apply;
+
+use crate::*;
+
+#[cfg(test)]
+#[path = "csrf_tests.rs"]
+mod tests;
+
+const DEFAULT_COOKIE: &str = "agw-csrf";
+const DEFAULT_HEADER: &str = "x-csrf-token";
+
+/// Groups of admin endpoints that accept state-changing requests.
+#[apply(schema!)]
+#[derive(Copy, Hash, PartialEq, Eq)]
+pub enum EndpointGroup {
+	/// `/quitquitquit`
+	Lifecycle,
+	/// `/logging`
+	Logging,
+	/// `/mesh/*`
+	Mesh,
+	/// `/quotas/*`
+	Quotas,
+	/// Any other endpoint, such as the UI.
+	Other,
+}
+
+impl EndpointGroup {
+	pub fn for_path(path: &str) -> Self {
+		match path {
+			"/quitquitquit" => EndpointGroup::Lifecycle,
+			"/logging" => EndpointGroup::Logging,
+			p if p.starts_with("/mesh/") => EndpointGroup::Mesh,
+			p if p.starts_with("/quotas/") => EndpointGroup::Quotas,
+			_ => EndpointGroup::Other,
+		}
+	}
+}
+
+/// Origin and CSRF protection for state-changing (non GET/HEAD/OPTIONS) admin requests.
+///
+/// Requests from browsers (those sending `Origin` or `Sec-Fetch-Site`) must target an allowed host
+/// and come from the same origin or an allowed origin. Requests from non-browser clients, such as
+/// `curl`, are not affected unless a CSRF token is required.
+#[apply(schema!)]
+#[derive(Default)]
+pub struct AdminCsrf {
+	/// Settings applied to all endpoint groups without an override.
+	#[serde(default)]
+	pub default: OriginPolicy,
+	/// Per endpoint group overrides.
+	#[serde(default, skip_serializing_if = "HashMap::is_empty")]
+	pub groups: HashMap<EndpointGroup, OriginPolicy>,
+}
+
+#[apply(schema!)]
+#[derive(Default)]
+pub struct OriginPolicy {
+	/// Disable origin and CSRF checks.
+	#[serde(default, skip_serializing_if = "is_default")]
+	pub disabled: bool,
+	/// Additional origins, such as `https://admin.example.com`, permitted to make requests.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub allowed_origins: Vec<String>,
+	/// Hostnames accepted in the `Host` header of browser requests. IP addresses and `localhost` are
+	/// always accepted; other names must be listed to guard against DNS rebinding.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub allowed_hosts: Vec<String>,
+	/// Require a double-submit token: the value of the token cookie must be sent in the token header.
+	/// The cookie is issued on safe requests that do not already carry it.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub token: Option<DoubleSubmitToken>,
+}
+
+#[apply(schema!)]
+pub struct DoubleSubmitToken {
+	#[serde(default = "default_cookie")]
+	pub cookie: String,
+	#[serde(default = "default_header")]
+	pub header: String,
+}
+
+fn default_cookie() -> String {
+	DEFAULT_COOKIE.to_string()
+}
+
+fn default_header() -> String {
+	DEFAULT_HEADER.to_string()
+}
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum Error {
+	#[error("host not allowed")]
+	HostNotAllowed,
+	#[error("cross-origin request not allowed")]
+	CrossOrigin,
+	#[error("missing or invalid CSRF token")]
+	InvalidToken,
+}
+
+impl AdminCsrf {
+	pub fn policy(&self, group: EndpointGroup) -> &OriginPolicy {
+		self.groups.get(&group).unwrap_or(&self.default)
+	}
+}
+
+impl OriginPolicy {
+	/// Validate a request, returning an error if it should be rejected.
+	pub fn check(&self, method: &Method, headers: &HeaderMap) -> Result<(), Error> {
+		if self.disabled || is_safe_method(method) {
+			return Ok(());
+		}
+		let origin = header_str(headers, header::ORIGIN).filter(|o| *o != "null");
+		let fetch_site = header_str(headers, "sec-fetch-site");
+		if origin.is_some() || fetch_site.is_some() {
+			self.check_browser(origin, fetch_site, headers)?;
+		}
+		if let Some(token) = &self.token {
+			token.check(headers)?;
+		}
+		Ok(())
+	}
+
+	fn check_browser(
+		&self,
+		origin: Option<&str>,
+		fetch_site: Option<&str>,
+		headers: &HeaderMap,
+	) -> Result<(), Error> {
+		let host = header_str(headers, header::HOST).ok_or(Error::HostNotAllowed)?;
+		if !self.host_allowed(host) {
+			warn!(host, "admin request rejected: host not allowed");
+			return Err(Error::HostNotAllowed);
+		}
+		if let Some(origin) = origin {
+			if self.allowed_origins.iter().any(|o| o == origin) {
+				return Ok(());
+			}
+			let same_origin = origin
+				.split_once("://")
+				.is_some_and(|(_, authority)| authority.eq_ignore_ascii_case(host));
+			if !same_origin {
+				warn!(origin, host, "admin request rejected: cross-origin request");
+				return Err(Error::CrossOrigin);
+			}
+			return Ok(());
+		}
+		match fetch_site {
+			Some("same-origin") | Some("none") | None => Ok(()),
+			Some(site) => {
+				warn!(site, "admin request rejected: cross-site request");
+				Err(Error::CrossOrigin)
+			},
+		}
+	}
+
+	fn host_allowed(&self, host: &str) -> bool {
+		let name = strip_port(host);
+		if name.eq_ignore_ascii_case("localhost") || name.parse::<IpAddr>().is_ok() {
+			return true;
+		}
+		self
+			.allowed_hosts
+			.iter()
+			.any(|h| h.eq_ignore_ascii_case(name) || h.eq_ignore_ascii_case(host))
+	}
+
+	/// Returns a `Set-Cookie` header issuing a new token, if this request needs one.
+	pub fn issue_token(&self, method: &Method, headers: &HeaderMap) -> Option<HeaderValue> {
+		let token = self.token.as_ref()?;
+		if self.disabled || !is_safe_method(method) || token.cookie_value(headers).is_some() {
+			return None;
+		}
+		let value = hex::encode(rand::random::<[u8; 32]>());
+		// The cookie is intentionally readable from JavaScript, so the UI can echo it in the header.
+		HeaderValue::from_str(&format!(
+			"{}={value}; Path=/; SameSite=Strict",
+			token.cookie
+		))
+		.ok()
+	}
+}
+
+impl DoubleSubmitToken {
+	fn check(&self, headers: &HeaderMap) -> Result<(), Error> {
+		let cookie = self.cookie_value(headers).ok_or(Error::InvalidToken)?;
+		let submitted = header_str(headers, self.header.as_str()).ok_or(Error::InvalidToken)?;
+		if cookie.is_empty()
+			|| aws_lc_rs::constant_time::verify_slices_are_equal(cookie.as_bytes(), submitted.as_bytes())
+				.is_err()
+		{
+			return Err(Error::InvalidToken);
+		}
+		Ok(())
+	}
+
+	fn cookie_value<'a>(&self, headers: &'a HeaderMap) -> Option<&'a str> {
+		headers
+			.get_all(header::COOKIE)
+			.iter()
+			.filter_map(|v| v.to_str().ok())
+			.flat_map(|v| v.split(';'))
+			.filter_map(|c| c.trim().split_once('='))
+			.find(|(k, _)| *k == self.cookie)
+			.map(|(_, v)| v)
+	}
+}
+
+fn is_safe_method(method: &Method) -> bool {
+	matches!(method, &Method::GET | &Method::HEAD | &Method::OPTIONS)
+}
+
+fn header_str<K: header::AsHeaderName>(headers: &HeaderMap, k: K) -> Option<&str> {
+	headers.get(k).and_then(|v| v.to_str().ok())
+}
+
+fn strip_port(host: &str) -> &str {
+	if let Some(rest) = host.strip_prefix('[') {
+		// IPv6 literal, such as [::1]:15000
+		return rest.split_once(']').map(|(h, _)| h).unwrap_or(rest);
+	}
+	match host.rsplit_once(':') {
+		Some((h, port)) if port.chars().all(|c| c.is_ascii_digit()) => h,
+		_ => host,
+	}
+}
@@ -0,0 +1,123 @@
+use super::*;
+
+fn headers(h: &[(&'static str, &'static str)]) -> HeaderMap {
+	let mut hm = HeaderMap::new();
+	for (k, v) in h {
+		hm.append(*k, v.parse().unwrap());
+	}
+	hm
+}
+
+#[test]
+fn test_endpoint_groups() {
+	assert_eq!(
+		EndpointGroup::for_path("/quitquitquit"),
+		EndpointGroup::Lifecycle
+	);
+	assert_eq!(
+		EndpointGroup::for_path("/mesh/register"),
+		EndpointGroup::Mesh
+	);
+	assert_eq!(
+		EndpointGroup::for_path("/quotas/reset"),
+		EndpointGroup::Quotas
+	);
+	assert_eq!(
+		EndpointGroup::for_path("/config_dump"),
+		EndpointGroup::Other
+	);
+}
+
+#[test]
+fn test_non_browser_allowed() {
+	let p = OriginPolicy::default();
+	assert_eq!(
+		p.check(&Method::POST, &headers(&[("host", "evil.example.com")])),
+		Ok(())
+	);
+}
+
+#[test]
+fn test_origin_checks() {
+	let p = OriginPolicy::default();
+	let same = headers(&[
+		("host", "localhost:15000"),
+		("origin", "http://localhost:15000"),
+	]);
+	assert_eq!(p.check(&Method::POST, &same), Ok(()));
+
+	let cross = headers(&[
+		("host", "localhost:15000"),
+		("origin", "https://evil.example.com"),
+	]);
+	assert_eq!(p.check(&Method::POST, &cross), Err(Error::CrossOrigin));
+	// Safe methods are never rejected
+	assert_eq!(p.check(&Method::GET, &cross), Ok(()));
+
+	let p = OriginPolicy {
+		allowed_origins: vec!["https://evil.example.com".to_string()],
+		..Default::default()
+	};
+	assert_eq!(p.check(&Method::POST, &cross), Ok(()));
+
+	let cross_site = headers(&[
+		("host", "127.0.0.1:15000"),
+		("sec-fetch-site", "cross-site"),
+	]);
+	assert_eq!(
+		OriginPolicy::default().check(&Method::POST, &cross_site),
+		Err(Error::CrossOrigin)
+	);
+}
+
+#[test]
+fn test_dns_rebinding() {
+	// Same origin, but the host is not a known name for the admin server
+	let rebound = headers(&[
+		("host", "rebind.example.com:15000"),
+		("origin", "http://rebind.example.com:15000"),
+	]);
+	assert_eq!(
+		OriginPolicy::default().check(&Method::POST, &rebound),
+		Err(Error::HostNotAllowed)
+	);
+	let p = OriginPolicy {
+		allowed_hosts: vec!["rebind.example.com".to_string()],
+		..Default::default()
+	};
+	assert_eq!(p.check(&Method::POST, &rebound), Ok(()));
+	let ipv6 = headers(&[("host", "[::1]:15000"), ("origin", "http://[::1]:15000")]);
+	assert_eq!(OriginPolicy::default().check(&Method::POST, &ipv6), Ok(()));
+}
+
+#[test]
+fn test_double_submit_token() {
+	let p = OriginPolicy {
+		token: Some(DoubleSubmitToken {
+			cookie: default_cookie(),
+			header: default_header(),
+		}),
+		..Default::default()
+	};
+	let set_cookie = p.issue_token(&Method::GET, &HeaderMap::new()).unwrap();
+	assert!(set_cookie.to_str().unwrap().starts_with("agw-csrf="));
+
+	let ok = headers(&[("cookie", "a=b; agw-csrf=abc"), ("x-csrf-token", "abc")]);
+	assert_eq!(p.check(&Method::POST, &ok), Ok(()));
+	assert!(p.issue_token(&Method::GET, &ok).is_none());
+
+	let mismatch = headers(&[("cookie", "agw-csrf=abc"), ("x-csrf-token", "abd")]);
+	assert_eq!(p.check(&Method::POST, &mismatch), Err(Error::InvalidToken));
+	let missing = headers(&[("x-csrf-token", "abc")]);
+	assert_eq!(p.check(&Method::POST, &missing), Err(Error::InvalidToken));
+}
+
+#[test]
+fn test_group_overrides() {
+	let cfg: AdminCsrf = serde_json::from_value(serde_json::json!({
+		"groups": {"lifecycle": {"disabled": true}},
+	}))
+	.unwrap();
+	assert!(cfg.policy(EndpointGroup::Lifecycle).disabled);
+	assert!(!cfg.policy(EndpointGroup::Mesh).disabled);
+}
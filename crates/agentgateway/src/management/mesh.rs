@@ -1,13 +1,22 @@
 use std::collections::HashMap;
 use std::net::SocketAddr;
-use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, TrySendError};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
+use arc_swap::ArcSwap;
+use chrono::Utc;
+use prometheus_client::collector::Collector;
+use prometheus_client::encoding::{DescriptorEncoder, EncodeMetric};
+use prometheus_client::metrics::MetricType;
+use prometheus_client::metrics::counter::ConstCounter;
+use prometheus_client::registry::Registry;
+use tokio::sync::oneshot;
 use tracing::{debug, info, warn};
-use tokio::time;
-use tokio::sync::broadcast;
 
 use crate::store::Stores;
 use crate::ledger::RecoveryLedger;
+use crate::telemetry::events::{self, EventKind};
 use crate::types::proto::agent::Resource as ADPResource;
 use crate::types::proto::agent::resource::Kind as XdsKind;
 use crate::types::proto::agent::{
@@ -48,6 +57,125 @@ pub struct MeshHeartbeat {
     /// Matrix Guardian: Cryptographically blessed status
     #[serde(default)]
     pub is_blessed: bool,
+    /// Time the heartbeat was sent, in seconds since the Unix epoch. Must be within
+    /// `MAX_CLOCK_SKEW` of the gateway's clock.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
+    /// Random value, unique per heartbeat, used to reject replayed heartbeats.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+}
+
+/// Maximum difference between a heartbeat's timestamp and the gateway's clock.
+const MAX_CLOCK_SKEW: Duration = Duration::from_secs(60);
+/// Upper bound on remembered nonces, so a flood of heartbeats cannot exhaust memory.
+const MAX_NONCES: usize = 100_000;
+/// How often nodes that stopped sending heartbeats are looked for.
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(30);
+/// How long a node may go without a heartbeat before it is evicted.
+const ZOMBIE_TIMEOUT: Duration = Duration::from_secs(90);
+/// Upper bound on registrations waiting for the writer. Once full, further registrations are
+/// dropped rather than queued; nodes heartbeat periodically, so a later heartbeat applies them.
+const MAX_PENDING_WRITES: usize = 1024;
+
+/// Failures of the mesh registry's background writer.
+#[derive(Debug, Default)]
+struct MeshStats {
+    /// Registrations and evictions that could not be applied to the config store.
+    store_failures: AtomicU64,
+    /// Registrations dropped because too many were waiting for the writer.
+    dropped_writes: AtomicU64,
+}
+
+/// Exposes the mesh registry's failures to update the config store at scrape time.
+#[derive(Debug)]
+pub struct MeshCollector {
+    stats: Arc<MeshStats>,
+}
+
+impl MeshCollector {
+    pub fn register(registry: &mut Registry, mesh: &MeshRegistry) {
+        registry.register_collector(Box::new(MeshCollector {
+            stats: mesh.stats.clone(),
+        }));
+    }
+}
+
+impl Collector for MeshCollector {
+    fn encode(&self, mut encoder: DescriptorEncoder) -> Result<(), std::fmt::Error> {
+        let counters = [
+            (
+                "mesh_store_failures",
+                "Mesh node registrations and evictions that could not be applied to the config store",
+                &self.stats.store_failures,
+            ),
+            (
+                "mesh_dropped_writes",
+                "Mesh node registrations dropped because too many were waiting to be applied",
+                &self.stats.dropped_writes,
+            ),
+        ];
+        for (name, help, value) in counters {
+            let metric_encoder = encoder.encode_descriptor(name, help, None, MetricType::Counter)?;
+            ConstCounter::new(value.load(Ordering::Relaxed)).encode(metric_encoder)?;
+        }
+        Ok(())
+    }
+}
+
+/// Rejects heartbeats that are stale, or that reuse a nonce seen within the skew window.
+pub struct ReplayGuard {
+    max_skew: Duration,
+    /// Nonces seen, keyed by service and nonce, with the time (in seconds) they can be forgotten.
+    seen: Mutex<HashMap<(String, String), i64>>,
+}
+
+impl ReplayGuard {
+    pub fn new(max_skew: Duration) -> Self {
+        Self {
+            max_skew,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Validates the heartbeat's timestamp, without recording its nonce.
+    pub fn check_fresh(&self, hb: &MeshHeartbeat, now: i64) -> anyhow::Result<()> {
+        let Some(ts) = hb.timestamp else {
+            anyhow::bail!("heartbeat timestamp is required");
+        };
+        if hb.nonce.as_deref().is_none_or(str::is_empty) {
+            anyhow::bail!("heartbeat nonce is required");
+        }
+        if now.abs_diff(ts) > self.max_skew.as_secs() {
+            anyhow::bail!("heartbeat timestamp is outside the allowed clock skew");
+        }
+        Ok(())
+    }
+
+    /// Records the heartbeat's nonce, failing if it was already used.
+    pub fn record(&self, hb: &MeshHeartbeat, now: i64) -> anyhow::Result<()> {
+        let (Some(ts), Some(nonce)) = (hb.timestamp, hb.nonce.as_ref()) else {
+            anyhow::bail!("heartbeat timestamp and nonce are required");
+        };
+        let mut seen = self.seen.lock().unwrap();
+        let key = (hb.service_name.clone(), nonce.clone());
+        if seen.contains_key(&key) {
+            anyhow::bail!("replayed heartbeat");
+        }
+        if seen.len() >= MAX_NONCES {
+            seen.retain(|_, expiry| *expiry > now);
+            if seen.len() >= MAX_NONCES {
+                anyhow::bail!("too many heartbeats");
+            }
+        }
+        // A nonce only needs to be remembered until its timestamp is no longer fresh.
+        seen.insert(key, ts + self.max_skew.as_secs() as i64);
+        Ok(())
+    }
+
+    fn prune(&self, now: i64) {
+        self.seen.lock().unwrap().retain(|_, expiry| *expiry > now);
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -63,42 +191,70 @@ pub struct MeshNode {
     pub token: String,
 }
 
+type Nodes = HashMap<String, Arc<MeshNode>>;
+
+/// Changes applied to the config store and ledger in the background, so the file I/O and store
+/// updates they need do not block the request that caused them.
+enum Write {
+    Register(MeshHeartbeat),
+    /// Signals once every write queued before it has been applied.
+    Flush(oneshot::Sender<()>),
+}
+
 #[derive(Clone)]
 pub struct MeshRegistry {
-    stores: Stores,
-    nodes: Arc<RwLock<HashMap<String, MeshNode>>>,
-    events: broadcast::Sender<MeshEvent>,
-    ledger: Arc<RecoveryLedger>,
+    /// A snapshot of the nodes. Reads, such as token validation, load it without locking; writes
+    /// replace it while holding `write_lock`.
+    nodes: Arc<ArcSwap<Nodes>>,
+    write_lock: Arc<Mutex<()>>,
+    writes: mpsc::SyncSender<Write>,
+    replay: Arc<ReplayGuard>,
+    stats: Arc<MeshStats>,
 }
 
 impl MeshRegistry {
-    pub fn new(stores: Stores, ledger_path: std::path::PathBuf) -> Self {
-        let (events, _) = broadcast::channel(100);
-        let ledger = Arc::new(RecoveryLedger::new(ledger_path));
+    pub fn new(
+        stores: Stores,
+        ledger_path: std::path::PathBuf,
+        cipher: Option<Arc<crate::encryption::Cipher>>,
+    ) -> Self {
+        let (writes, pending) = mpsc::sync_channel(MAX_PENDING_WRITES);
         let registry = Self {
+            nodes: Arc::new(ArcSwap::from_pointee(HashMap::new())),
+            write_lock: Arc::new(Mutex::new(())),
+            writes,
+            replay: Arc::new(ReplayGuard::new(MAX_CLOCK_SKEW)),
+            stats: Default::default(),
+        };
+        let writer = MeshWriter {
             stores,
-            nodes: Arc::new(RwLock::new(HashMap::new())),
-            events,
-            ledger,
+            ledger: RecoveryLedger::new(ledger_path, cipher),
+            nodes: registry.nodes.clone(),
+            write_lock: registry.write_lock.clone(),
+            replay: registry.replay.clone(),
+            stats: registry.stats.clone(),
         };
-
-        // Start Self-Healing Loop (Zombie Cleanup)
-        let registry_clone = registry.clone();
-        tokio::spawn(async move {
-            let mut interval = time::interval(Duration::from_secs(30));
-            loop {
-                interval.tick().await;
-                registry_clone.cleanup_zombies();
-            }
-        });
-
+        // Apply writes in order, and evict zombies, on a dedicated thread since the ledger writes to
+        // a file. The thread exits once every handle to the registry is dropped.
+        std::thread::Builder::new()
+            .name("mesh-writer".to_string())
+            .spawn(move || writer.run(pending))
+            .expect("failed to spawn mesh writer thread");
         registry
     }
 
+    /// Registers a heartbeat. The node is visible as soon as this returns, while projecting it
+    /// into the config store and recording it in the ledger happen in the background.
     pub fn register(&self, heartbeat: MeshHeartbeat, provided_token: Option<String>) -> anyhow::Result<String> {
-        let mut nodes = self.nodes.write().unwrap();
+        let now = Utc::now().timestamp();
         let name = heartbeat.service_name.clone();
-        
+        if let Err(e) = self.replay.check_fresh(&heartbeat, now) {
+            warn!(service=%name, "Matrix Guardian: Stale heartbeat rejected: {e}");
+            return Err(e);
+        }
+        let _write = self.write_lock.lock().unwrap();
+        let nodes = self.nodes.load();
+
         // Matrix Guardian: Strict Policy Enforcement
         let mut is_blessed = false;
         if let Some(existing) = nodes.get(&name) {
@@ -115,6 +271,12 @@ impl MeshRegistry {
         } else if provided_token.is_none() {
             info!(service=%name, "Matrix Guardian: Blessing new ephemeral node");
         }
+        // Only record nonces once the heartbeat is otherwise accepted, so rejected requests
+        // cannot fill the nonce cache.
+        if let Err(e) = self.replay.record(&heartbeat, now) {
+            warn!(service=%name, "Matrix Guardian: Replayed heartbeat rejected: {e}");
+            return Err(e);
+        }
 
         let token = provided_token.unwrap_or_else(|| {
             use rand::{distr::Alphanumeric, Rng};
@@ -127,50 +289,127 @@ impl MeshRegistry {
 
         debug!(service=%name, transport=?heartbeat.transport, port=%heartbeat.port, "processing mesh heartbeat");
 
-        nodes.insert(name.clone(), MeshNode {
+        let mut updated = HashMap::clone(&nodes);
+        updated.insert(name.clone(), Arc::new(MeshNode {
             metadata: MeshHeartbeat {
                 is_blessed,
                 ..heartbeat.clone()
             },
             last_seen: Instant::now(),
             token: token.clone(),
-        });
-
-        // Project into ADP
-        self.project_to_adp(heartbeat.clone())?;
+        }));
+        self.nodes.store(Arc::new(updated));
 
-        self.ledger.log(&name, "register", serde_json::to_value(&heartbeat).unwrap_or_default());
+        match self.writes.try_send(Write::Register(heartbeat.clone())) {
+            Ok(()) | Err(TrySendError::Disconnected(_)) => {}
+            Err(TrySendError::Full(_)) => {
+                self.stats.dropped_writes.fetch_add(1, Ordering::Relaxed);
+                warn!(service=%name, "mesh writer is behind, dropping registration until the next heartbeat");
+            }
+        }
 
-        let _ = self.events.send(MeshEvent::NodeUpdated(MeshHeartbeat {
+        events::publish(EventKind::Mesh(MeshEvent::NodeUpdated(MeshHeartbeat {
             is_blessed,
             ..heartbeat
-        }));
+        })));
 
         Ok(token)
     }
 
-    fn cleanup_zombies(&self) {
-        let now = Instant::now();
-        let mut to_remove = Vec::new();
+    pub fn get_nodes(&self) -> Vec<MeshHeartbeat> {
+        self.nodes.load().values().map(|n| n.metadata.clone()).collect()
+    }
 
-        {
-            let nodes = self.nodes.read().unwrap();
-            for (name, node) in nodes.iter() {
-                if now.duration_since(node.last_seen) > Duration::from_secs(90) {
-                    to_remove.push(name.clone());
+    pub fn validate_token(&self, service_name: &str, token: &str) -> bool {
+        self.nodes.load().get(service_name).map(|n| n.token == token).unwrap_or(false)
+    }
+
+    /// Waits until every registration and eviction so far is recorded in the ledger.
+    pub async fn flush(&self) {
+        let (tx, rx) = oneshot::channel();
+        // Unlike registrations, a flush waits for room in the queue, off the runtime's workers.
+        let writes = self.writes.clone();
+        let sent = tokio::task::spawn_blocking(move || writes.send(Write::Flush(tx)).is_ok())
+            .await
+            .unwrap_or(false);
+        if sent {
+            let _ = rx.await;
+        }
+    }
+}
+
+/// Applies registrations and evictions to the config store, and records them in the ledger.
+struct MeshWriter {
+    stores: Stores,
+    ledger: RecoveryLedger,
+    nodes: Arc<ArcSwap<Nodes>>,
+    write_lock: Arc<Mutex<()>>,
+    replay: Arc<ReplayGuard>,
+    stats: Arc<MeshStats>,
+}
+
+impl MeshWriter {
+    fn run(&self, pending: mpsc::Receiver<Write>) {
+        let mut next_cleanup = Instant::now() + CLEANUP_INTERVAL;
+        loop {
+            match pending.recv_timeout(next_cleanup.saturating_duration_since(Instant::now())) {
+                Ok(write) => self.apply(write),
+                Err(RecvTimeoutError::Timeout) => {
+                    self.cleanup_zombies(Instant::now());
+                    next_cleanup = Instant::now() + CLEANUP_INTERVAL;
+                }
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+    }
+
+    fn apply(&self, write: Write) {
+        match write {
+            Write::Register(hb) => {
+                let name = hb.service_name.clone();
+                let metadata = serde_json::to_value(&hb).unwrap_or_default();
+                if let Err(e) = self.project_to_adp(hb) {
+                    self.stats.store_failures.fetch_add(1, Ordering::Relaxed);
+                    warn!(service=%name, "failed to project mesh node: {e}");
                 }
+                self.ledger.log(&name, "register", metadata);
             }
+            Write::Flush(done) => {
+                let _ = done.send(());
+            }
+        }
+    }
+
+    /// Evicts nodes that have not sent a heartbeat within `ZOMBIE_TIMEOUT`.
+    fn cleanup_zombies(&self, now: Instant) {
+        self.replay.prune(Utc::now().timestamp());
+        let is_zombie = |node: &MeshNode| now.duration_since(node.last_seen) > ZOMBIE_TIMEOUT;
+        if !self.nodes.load().values().any(|n| is_zombie(n)) {
+            return;
         }
 
-        if !to_remove.is_empty() {
-            let mut nodes = self.nodes.write().unwrap();
-            for name in to_remove {
-                warn!(service=%name, "mesh node heartbeat timed out, evicting zombie from ADP");
-                nodes.remove(&name);
-                let _ = self.evict_from_adp(&name);
-                self.ledger.log(&name, "evict", serde_json::json!({"reason": "timeout"}));
-                let _ = self.events.send(MeshEvent::NodeRemoved(name));
+        let to_remove = {
+            let _write = self.write_lock.lock().unwrap();
+            let mut nodes = HashMap::clone(&self.nodes.load());
+            let mut to_remove = Vec::new();
+            nodes.retain(|name, node| {
+                if is_zombie(node) {
+                    to_remove.push(name.clone());
+                    return false;
+                }
+                true
+            });
+            self.nodes.store(Arc::new(nodes));
+            to_remove
+        };
+        for name in to_remove {
+            warn!(service=%name, "mesh node heartbeat timed out, evicting zombie from ADP");
+            if let Err(e) = self.evict_from_adp(&name) {
+                self.stats.store_failures.fetch_add(1, Ordering::Relaxed);
+                warn!(service=%name, "failed to evict mesh node: {e}");
             }
+            self.ledger.log(&name, "evict", serde_json::json!({"reason": "timeout"}));
+            events::publish(EventKind::Mesh(MeshEvent::NodeRemoved(name)));
         }
     }
 
@@ -247,17 +486,116 @@ impl MeshRegistry {
 
         Ok(())
     }
+}
 
-    pub fn get_nodes(&self) -> Vec<MeshHeartbeat> {
-        self.nodes.read().unwrap().values().map(|n| n.metadata.clone()).collect()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn heartbeat(nonce: Option<&str>, timestamp: Option<i64>) -> MeshHeartbeat {
+        MeshHeartbeat {
+            service_name: "svc".to_string(),
+            transport: TransportType::Streamable,
+            port: 8080,
+            active_sessions: 0,
+            pid: None,
+            addr: None,
+            sampling_supported: false,
+            is_blessed: false,
+            timestamp,
+            nonce: nonce.map(str::to_string),
+        }
     }
 
-    pub fn validate_token(&self, service_name: &str, token: &str) -> bool {
-        let nodes = self.nodes.read().unwrap();
-        nodes.get(service_name).map(|n| n.token == token).unwrap_or(false)
+    #[test]
+    fn test_replay_guard() {
+        let guard = ReplayGuard::new(Duration::from_secs(60));
+        let now = 1_700_000_000;
+        let hb = heartbeat(Some("abc"), Some(now));
+        guard.check_fresh(&hb, now).unwrap();
+        guard.record(&hb, now).unwrap();
+        // The same heartbeat cannot be accepted twice
+        assert!(guard.record(&hb, now + 1).is_err());
+        // Other nonces are accepted
+        guard.record(&heartbeat(Some("def"), Some(now)), now).unwrap();
+
+        // Once the nonce expires, the heartbeat is rejected as stale instead
+        guard.prune(now + 61);
+        assert!(guard.check_fresh(&hb, now + 61).is_err());
+    }
+
+    #[test]
+    fn test_register() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = MeshRegistry::new(Stores::new(), dir.path().join("ledger"), None);
+        let now = Utc::now().timestamp();
+        let token = registry.register(heartbeat(Some("a"), Some(now)), None).unwrap();
+        assert!(registry.validate_token("svc", &token));
+        assert!(!registry.validate_token("svc", "wrong"));
+        assert!(!registry.validate_token("other", &token));
+        assert_eq!(registry.get_nodes().len(), 1);
+
+        // Existing services must present their token
+        assert!(registry.register(heartbeat(Some("b"), Some(now)), None).is_err());
+        assert!(registry.register(heartbeat(Some("c"), Some(now)), Some("wrong".to_string())).is_err());
+        registry.register(heartbeat(Some("d"), Some(now)), Some(token.clone())).unwrap();
+        let nodes = registry.get_nodes();
+        assert_eq!(nodes.len(), 1);
+        assert!(nodes[0].is_blessed);
+    }
+
+    #[test]
+    fn test_cleanup_zombies() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = MeshRegistry::new(Stores::new(), dir.path().join("ledger"), None);
+        let now = Utc::now().timestamp();
+        registry.register(heartbeat(Some("a"), Some(now)), None).unwrap();
+        let writer = MeshWriter {
+            stores: Stores::new(),
+            ledger: RecoveryLedger::new(dir.path().join("zombies"), None),
+            nodes: registry.nodes.clone(),
+            write_lock: registry.write_lock.clone(),
+            replay: registry.replay.clone(),
+            stats: registry.stats.clone(),
+        };
+        writer.cleanup_zombies(Instant::now() + Duration::from_secs(60));
+        assert_eq!(registry.get_nodes().len(), 1);
+        writer.cleanup_zombies(Instant::now() + ZOMBIE_TIMEOUT + Duration::from_secs(1));
+        assert!(registry.get_nodes().is_empty());
+    }
+
+    #[test]
+    fn test_full_write_queue() {
+        // Nothing drains the queue, so it fills after one registration
+        let (writes, _pending) = mpsc::sync_channel(1);
+        let registry = MeshRegistry {
+            nodes: Arc::new(ArcSwap::from_pointee(HashMap::new())),
+            write_lock: Arc::new(Mutex::new(())),
+            writes,
+            replay: Arc::new(ReplayGuard::new(MAX_CLOCK_SKEW)),
+            stats: Default::default(),
+        };
+        let now = Utc::now().timestamp();
+        for (service, nonce) in [("a", "1"), ("b", "2")] {
+            let hb = MeshHeartbeat {
+                service_name: service.to_string(),
+                ..heartbeat(Some(nonce), Some(now))
+            };
+            registry.register(hb, None).unwrap();
+        }
+        // Dropped registrations are counted, but the nodes are still visible
+        assert_eq!(registry.stats.dropped_writes.load(Ordering::Relaxed), 1);
+        assert_eq!(registry.get_nodes().len(), 2);
     }
 
-    pub fn subscribe(&self) -> broadcast::Receiver<MeshEvent> {
-        self.events.subscribe()
+    #[test]
+    fn test_stale_or_missing() {
+        let guard = ReplayGuard::new(Duration::from_secs(60));
+        let now = 1_700_000_000;
+        assert!(guard.check_fresh(&heartbeat(Some("a"), Some(now - 120)), now).is_err());
+        assert!(guard.check_fresh(&heartbeat(Some("a"), Some(now + 120)), now).is_err());
+        assert!(guard.check_fresh(&heartbeat(None, Some(now)), now).is_err());
+        assert!(guard.check_fresh(&heartbeat(Some(""), Some(now)), now).is_err());
+        assert!(guard.check_fresh(&heartbeat(Some("a"), None), now).is_err());
     }
 }
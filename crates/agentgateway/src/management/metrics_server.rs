@@ -14,16 +14,16 @@ use crate::Address;
 use crate::http::Response;
 
 pub struct Server {
-	s: hyper_helpers::Server<Mutex<Registry>>,
+	s: hyper_helpers::Server<Arc<Mutex<Registry>>>,
 }
 
 impl Server {
 	pub async fn new(
 		addr: Address,
 		drain_rx: DrainWatcher,
-		registry: Registry,
+		registry: Arc<Mutex<Registry>>,
 	) -> anyhow::Result<Self> {
-		hyper_helpers::Server::<Mutex<Registry>>::bind("stats", addr, drain_rx, Mutex::new(registry))
+		hyper_helpers::Server::<Arc<Mutex<Registry>>>::bind("stats", addr, drain_rx, registry)
 			.await
 			.map(|s| Server { s })
 	}
@@ -35,14 +35,14 @@ impl Server {
 	pub fn spawn(self) {
 		self.s.spawn(|registry, req| async move {
 			match req.uri().path() {
-				"/metrics" | "/stats/prometheus" => Ok(handle_metrics(registry, req).await),
+				"/metrics" | "/stats/prometheus" => Ok(handle_metrics(&registry, req).await),
 				_ => Ok(hyper_helpers::empty_response(hyper::StatusCode::NOT_FOUND)),
 			}
 		})
 	}
 }
 
-async fn handle_metrics(reg: Arc<Mutex<Registry>>, req: Request<Incoming>) -> Response {
+async fn handle_metrics(reg: &Mutex<Registry>, req: Request<Incoming>) -> Response {
 	let mut buf = String::new();
 	let reg = reg.lock().expect("mutex");
 	if let Err(err) = encode(&mut buf, &reg) {
@@ -53,6 +53,10 @@ async fn handle_metrics(reg: Arc<Mutex<Registry>>, req: Request<Incoming>) -> Re
 	}
 
 	let response_content_type = content_type(&req);
+	// Exemplars are only part of the OpenMetrics format; the Prometheus text format cannot parse them.
+	if response_content_type == <&str>::from(ContentType::PlainText) {
+		buf = strip_exemplars(&buf);
+	}
 
 	::http::Response::builder()
 		.status(hyper::StatusCode::OK)
@@ -77,6 +81,19 @@ impl From<ContentType> for &str {
 	}
 }
 
+fn strip_exemplars(metrics: &str) -> String {
+	let mut out = String::with_capacity(metrics.len());
+	for line in metrics.lines() {
+		let line = match line.find(" # {") {
+			Some(i) if !line.starts_with('#') => &line[..i],
+			_ => line,
+		};
+		out.push_str(line);
+		out.push('\n');
+	}
+	out
+}
+
 #[inline(always)]
 fn content_type<T>(req: &Request<T>) -> &str {
 	req
@@ -139,4 +156,17 @@ mod test {
 			"text/plain; charset=utf-8"
 		)
 	}
+
+	#[test]
+	fn test_strip_exemplars() {
+		let metrics = "# HELP request_duration_seconds Duration\n\
+			request_duration_seconds_bucket{le=\"0.5\"} 2 # {trace_id=\"4bf92f3577b34da6a3ce929d0e0e4736\"} 0.3\n\
+			request_duration_seconds_count 2\n";
+		assert_eq!(
+			super::strip_exemplars(metrics),
+			"# HELP request_duration_seconds Duration\n\
+			request_duration_seconds_bucket{le=\"0.5\"} 2\n\
+			request_duration_seconds_count 2\n"
+		);
+	}
 }
@@ -0,0 +1,200 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use futures_util::Stream;
+use prometheus_client::encoding::text::encode;
+use prometheus_client::registry::Registry;
+
+use crate::*;
+
+#[cfg(test)]
+#[path = "metrics_stream_tests.rs"]
+mod tests;
+
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(5);
+const MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Format {
+	Sse,
+	Ndjson,
+}
+
+impl Format {
+	pub(super) fn content_type(&self) -> &'static str {
+		match self {
+			Format::Sse => "text/event-stream",
+			Format::Ndjson => "application/x-ndjson",
+		}
+	}
+}
+
+/// Options for a metrics stream, from the query parameters `interval` (seconds), `format` (`sse`
+/// or `ndjson`), and `metric` (only include metric families whose name contains one of the
+/// comma separated values).
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Options {
+	pub(super) interval: Duration,
+	pub(super) format: Format,
+	pub(super) metrics: Vec<String>,
+}
+
+impl Options {
+	pub(super) fn parse(query: Option<&str>) -> Result<Options, String> {
+		let mut opts = Options {
+			interval: DEFAULT_INTERVAL,
+			format: Format::Sse,
+			metrics: vec![],
+		};
+		let Some(query) = query else {
+			return Ok(opts);
+		};
+		for (k, v) in url::form_urlencoded::parse(query.as_bytes()) {
+			match k.as_ref() {
+				"interval" => {
+					let secs: f64 = v
+						.parse()
+						.map_err(|_| format!("invalid interval {v:?}, expected seconds"))?;
+					if !secs.is_finite() || secs < MIN_INTERVAL.as_secs_f64() {
+						return Err(format!(
+							"interval must be at least {}s",
+							MIN_INTERVAL.as_secs()
+						));
+					}
+					opts.interval = Duration::from_secs_f64(secs);
+				},
+				"format" => {
+					opts.format = match v.as_ref() {
+						"sse" => Format::Sse,
+						"ndjson" => Format::Ndjson,
+						_ => return Err(format!("invalid format {v:?}, expected sse or ndjson")),
+					}
+				},
+				"metric" => opts.metrics.extend(
+					v.split(',')
+						.map(str::trim)
+						.filter(|m| !m.is_empty())
+						.map(str::to_string),
+				),
+				_ => {},
+			}
+		}
+		Ok(opts)
+	}
+}
+
+/// The value of each series at a point in time, split by whether they only ever increase.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Sample {
+	pub(crate) cumulative: BTreeMap<String, f64>,
+	pub(crate) gauges: BTreeMap<String, f64>,
+}
+
+impl Sample {
+	/// Parse the text exposition of a registry. Histogram buckets are skipped, only their sum and
+	/// count are kept.
+	pub(crate) fn parse(text: &str, filter: &[String]) -> Sample {
+		let mut sample = Sample::default();
+		let mut family: Option<(&str, &str)> = None;
+		for line in text.lines() {
+			if let Some(t) = line.strip_prefix("# TYPE ") {
+				family = t.split_once(' ');
+				continue;
+			}
+			if line.starts_with('#') || line.is_empty() {
+				continue;
+			}
+			let Some((name, kind)) = family else {
+				continue;
+			};
+			if !filter.is_empty() && !filter.iter().any(|f| name.contains(f.as_str())) {
+				continue;
+			}
+			// Drop the exemplar, if any.
+			let line = line.split_once(" # {").map(|(l, _)| l).unwrap_or(line);
+			let Some((series, value)) = line.rsplit_once(' ') else {
+				continue;
+			};
+			let Ok(value) = value.parse::<f64>() else {
+				continue;
+			};
+			let metric = series.split_once('{').map(|(m, _)| m).unwrap_or(series);
+			if metric.ends_with("_bucket") || metric.ends_with("_created") {
+				continue;
+			}
+			match kind {
+				"counter" | "histogram" | "summary" => {
+					sample.cumulative.insert(series.to_string(), value);
+				},
+				"gauge" | "unknown" => {
+					sample.gauges.insert(series.to_string(), value);
+				},
+				_ => {},
+			}
+		}
+		sample
+	}
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Update {
+	timestamp: String,
+	interval_seconds: f64,
+	/// Increase of each counter and histogram sum/count over the interval. Series that did not
+	/// change are omitted.
+	deltas: BTreeMap<String, f64>,
+	/// Current value of each gauge.
+	gauges: BTreeMap<String, f64>,
+}
+
+pub(crate) fn deltas(prev: &Sample, cur: &Sample) -> BTreeMap<String, f64> {
+	cur
+		.cumulative
+		.iter()
+		.filter_map(|(k, v)| {
+			let d = match prev.cumulative.get(k) {
+				// A decrease means the series was reset.
+				Some(p) if p <= v => v - p,
+				_ => *v,
+			};
+			(d != 0.0).then(|| (k.clone(), d))
+		})
+		.collect()
+}
+
+pub(crate) fn sample(registry: &Mutex<Registry>, filter: &[String]) -> Sample {
+	let mut buf = String::new();
+	let reg = registry.lock().expect("mutex");
+	if let Err(e) = encode(&mut buf, &reg) {
+		warn!("failed to encode metrics: {e}");
+	}
+	Sample::parse(&buf, filter)
+}
+
+/// Stream metric deltas from `registry` every `opts.interval`, each update framed as `format`.
+pub(super) fn stream(registry: Arc<Mutex<Registry>>, opts: Options) -> impl Stream<Item = String> {
+	let prev = sample(&registry, &opts.metrics);
+	let interval =
+		tokio::time::interval_at(tokio::time::Instant::now() + opts.interval, opts.interval);
+	futures_util::stream::unfold((interval, prev), move |(mut interval, prev)| {
+		let registry = registry.clone();
+		let opts = opts.clone();
+		async move {
+			interval.tick().await;
+			let cur = sample(&registry, &opts.metrics);
+			let update = Update {
+				timestamp: agent_core::telemetry::render_current_time(),
+				interval_seconds: opts.interval.as_secs_f64(),
+				deltas: deltas(&prev, &cur),
+				gauges: cur.gauges.clone(),
+			};
+			let json = serde_json::to_string(&update).unwrap_or_default();
+			let frame = match opts.format {
+				Format::Sse => format!("data: {json}\n\n"),
+				Format::Ndjson => format!("{json}\n"),
+			};
+			Some((frame, (interval, cur)))
+		}
+	})
+}
@@ -0,0 +1,113 @@
+use super::*;
+
+const METRICS: &str = r#"# HELP agentgateway_requests The total number of HTTP requests sent.
+# TYPE agentgateway_requests counter
+agentgateway_requests_total{backend="a",status="200"} 10
+agentgateway_requests_total{backend="a",status="500"} 2
+# HELP agentgateway_request_duration_seconds Duration of HTTP requests.
+# TYPE agentgateway_request_duration_seconds histogram
+# UNIT agentgateway_request_duration_seconds seconds
+agentgateway_request_duration_seconds_sum{backend="a"} 1.5
+agentgateway_request_duration_seconds_count{backend="a"} 12
+agentgateway_request_duration_seconds_bucket{le="0.5",backend="a"} 11 # {trace_id="4bf92f3577b34da6a3ce929d0e0e4736"} 0.3
+agentgateway_request_duration_seconds_bucket{le="+Inf",backend="a"} 12
+# HELP agentgateway_upstream_pool_idle_connections Idle upstream connections.
+# TYPE agentgateway_upstream_pool_idle_connections gauge
+agentgateway_upstream_pool_idle_connections{backend="a"} 3
+# HELP agentgateway_build Agentgateway build information.
+# TYPE agentgateway_build info
+agentgateway_build_info{tag="dev"} 1
+# EOF
+"#;
+
+#[test]
+fn test_parse_sample() {
+	let sample = Sample::parse(METRICS, &[]);
+	assert_eq!(
+		sample.cumulative,
+		BTreeMap::from([
+			(
+				r#"agentgateway_request_duration_seconds_count{backend="a"}"#.to_string(),
+				12.0
+			),
+			(
+				r#"agentgateway_request_duration_seconds_sum{backend="a"}"#.to_string(),
+				1.5
+			),
+			(
+				r#"agentgateway_requests_total{backend="a",status="200"}"#.to_string(),
+				10.0
+			),
+			(
+				r#"agentgateway_requests_total{backend="a",status="500"}"#.to_string(),
+				2.0
+			),
+		])
+	);
+	assert_eq!(
+		sample.gauges,
+		BTreeMap::from([(
+			r#"agentgateway_upstream_pool_idle_connections{backend="a"}"#.to_string(),
+			3.0
+		)])
+	);
+
+	let filtered = Sample::parse(METRICS, &["request_duration".to_string()]);
+	assert_eq!(filtered.cumulative.len(), 2);
+	assert!(filtered.gauges.is_empty());
+}
+
+#[test]
+fn test_deltas() {
+	let prev = Sample::parse(METRICS, &[]);
+	let cur = Sample::parse(
+		&METRICS
+			.replace(r#"status="200"} 10"#, r#"status="200"} 15"#)
+			.replace(r#"_count{backend="a"} 12"#, r#"_count{backend="a"} 4"#),
+		&[],
+	);
+	assert_eq!(
+		deltas(&prev, &cur),
+		BTreeMap::from([
+			// The count was reset, so its current value is the increase.
+			(
+				r#"agentgateway_request_duration_seconds_count{backend="a"}"#.to_string(),
+				4.0
+			),
+			(
+				r#"agentgateway_requests_total{backend="a",status="200"}"#.to_string(),
+				5.0
+			),
+		])
+	);
+}
+
+#[test]
+fn test_parse_options() {
+	assert_eq!(
+		Options::parse(None).unwrap(),
+		Options {
+			interval: Duration::from_secs(5),
+			format: Format::Sse,
+			metrics: vec![],
+		}
+	);
+	assert_eq!(
+		Options::parse(Some(
+			"interval=2&format=ndjson&metric=requests,gen_ai&metric=pool"
+		))
+		.unwrap(),
+		Options {
+			interval: Duration::from_secs(2),
+			format: Format::Ndjson,
+			metrics: vec![
+				"requests".to_string(),
+				"gen_ai".to_string(),
+				"pool".to_string()
+			],
+		}
+	);
+	assert!(Options::parse(Some("interval=0.1")).is_err());
+	assert!(Options::parse(Some("interval=abc")).is_err());
+	assert!(Options::parse(Some("format=xml")).is_err());
+}
@@ -1,6 +1,8 @@
 pub mod admin;
+pub mod csrf;
 pub mod mesh;
 pub mod metrics_server;
 pub mod readiness_server;
 
 mod hyper_helpers;
+pub(crate) mod metrics_stream;
@@ -11,9 +11,15 @@ use itertools::Itertools;
 use super::hyper_helpers;
 use crate::Address;
 use crate::http::Response;
+use crate::shutdown;
+
+struct State {
+	ready: readiness::Ready,
+	shutdown: shutdown::Progress,
+}
 
 pub struct Server {
-	s: hyper_helpers::Server<readiness::Ready>,
+	s: hyper_helpers::Server<State>,
 	ready: readiness::Ready,
 }
 
@@ -22,8 +28,13 @@ impl Server {
 		address: Address,
 		drain_rx: DrainWatcher,
 		ready: readiness::Ready,
+		shutdown: shutdown::Progress,
 	) -> anyhow::Result<Self> {
-		hyper_helpers::Server::<readiness::Ready>::bind("readiness", address, drain_rx, ready.clone())
+		let state = State {
+			ready: ready.clone(),
+			shutdown,
+		};
+		hyper_helpers::Server::<State>::bind("readiness", address, drain_rx, state)
 			.await
 			.map(|s| Server { s, ready })
 	}
@@ -37,9 +48,10 @@ impl Server {
 	}
 
 	pub fn spawn(self) {
-		self.s.spawn(|ready, req| async move {
+		self.s.spawn(|state, req| async move {
 			match req.uri().path() {
-				"/healthz/ready" => Ok(handle_ready(&ready, req).await),
+				"/healthz/ready" => Ok(handle_ready(&state.ready, req).await),
+				"/healthz" => Ok(handle_health(&state.shutdown, req).await),
 				_ => Ok(hyper_helpers::empty_response(hyper::StatusCode::NOT_FOUND)),
 			}
 		})
@@ -64,3 +76,34 @@ async fn handle_ready(ready: &readiness::Ready, req: Request<Incoming>) -> Respo
 		_ => hyper_helpers::empty_response(hyper::StatusCode::METHOD_NOT_ALLOWED),
 	}
 }
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Health {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	shutdown_phase: Option<shutdown::Phase>,
+	shutdown_phases: Vec<shutdown::PhaseProgress>,
+}
+
+/// Reports the progress of a graceful shutdown, if one has started. Unlike the data plane, this
+/// server is not drained, so it keeps reporting while connections drain.
+async fn handle_health(progress: &shutdown::Progress, req: Request<Incoming>) -> Response {
+	match *req.method() {
+		hyper::Method::GET => {
+			let health = Health {
+				shutdown_phase: progress.phase(),
+				shutdown_phases: progress.phases(),
+			};
+			let mut resp = hyper_helpers::plaintext_response(
+				hyper::StatusCode::OK,
+				serde_json::to_string_pretty(&health).unwrap_or_default(),
+			);
+			resp.headers_mut().insert(
+				hyper::header::CONTENT_TYPE,
+				hyper::header::HeaderValue::from_static("application/json"),
+			);
+			resp
+		},
+		_ => hyper_helpers::empty_response(hyper::StatusCode::METHOD_NOT_ALLOWED),
+	}
+}
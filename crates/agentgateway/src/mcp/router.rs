@@ -46,6 +46,11 @@ impl App {
 		Self { state, session }
 	}
 
+	/// Close all open MCP sessions, so their streams end. Returns how many were open.
+	pub fn close_sessions(&self) -> usize {
+		self.session.close_all()
+	}
+
 	pub fn should_passthrough(
 		&self,
 		backend_policies: &BackendPolicies,
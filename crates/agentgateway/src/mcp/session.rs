@@ -16,6 +16,7 @@ use rmcp::model::{
 use rmcp::transport::common::http_header::{EVENT_STREAM_MIME_TYPE, JSON_MIME_TYPE};
 use sse_stream::{KeepAlive, Sse, SseBody, SseStream};
 use tokio::sync::mpsc::{Receiver, Sender};
+use tokio_util::sync::{CancellationToken, WaitForCancellationFutureOwned};
 
 use crate::http::Response;
 use crate::mcp::handler::Relay;
@@ -425,6 +426,8 @@ impl Session {
 pub struct SessionManager {
 	encoder: http::sessionpersistence::Encoder,
 	sessions: RwLock<HashMap<String, Session>>,
+	/// Cancelled when the gateway shuts down, to end the streams of open sessions.
+	closing: CancellationToken,
 }
 
 fn session_id() -> Arc<str> {
@@ -436,9 +439,25 @@ impl SessionManager {
 		Self {
 			encoder,
 			sessions: Default::default(),
+			closing: CancellationToken::new(),
 		}
 	}
 
+	/// Completes once the gateway starts closing sessions. Session streams end when this does.
+	pub fn closing(&self) -> WaitForCancellationFutureOwned {
+		self.closing.clone().cancelled_owned()
+	}
+
+	/// Close all open sessions, ending their streams. Clients must start a new session, on another
+	/// replica.
+	pub fn close_all(&self) -> usize {
+		self.closing.cancel();
+		let mut sm = self.sessions.write().expect("write lock");
+		let n = sm.len();
+		sm.clear();
+		n
+	}
+
 	pub fn get_session(&self, id: &str) -> Option<Session> {
 		self.sessions.read().ok()?.get(id).cloned()
 	}
@@ -140,7 +140,8 @@ impl LegacySSEService {
 				Ok(bytes) => Ok(Event::default().event("message").data(&bytes)),
 				Err(e) => Err(io::Error::new(io::ErrorKind::InvalidData, e)),
 			}),
-		);
+		)
+		.take_until(self.session_manager.closing());
 		let (parts, _) = request.into_parts();
 		Sse::new(stream).into_response().map(|b| {
 			http::Body::new(DropBody::new(
@@ -5,6 +5,7 @@ use crate::mcp::handler::Relay;
 use crate::mcp::session::SessionManager;
 use crate::*;
 use ::http::StatusCode;
+use futures_util::StreamExt;
 use rmcp::model::{ClientJsonRpcMessage, ClientRequest, ServerJsonRpcMessage};
 use rmcp::transport::common::http_header::{
 	EVENT_STREAM_MIME_TYPE, HEADER_SESSION_ID, JSON_MIME_TYPE,
@@ -217,7 +218,12 @@ impl StreamableHttpService {
 		};
 
 		let (parts, _) = request.into_parts();
-		session.get_stream(parts).await
+		let closing = self.session_manager.closing();
+		session.get_stream(parts).await.map(|b| {
+			http::Body::new(http_body_util::StreamBody::new(
+				http_body_util::BodyStream::new(b).take_until(closing),
+			))
+		})
 	}
 
 	pub async fn handle_delete(&self, request: Request) -> Response {
@@ -495,6 +495,21 @@ fn normalize_url_path(prefix: &str, path: &str) -> String {
 	}
 }
 
+/// Map the response of an API call to a tool result. Objects are also returned as structured
+/// content, which must be an object.
+fn tool_result(body: Value) -> rmcp::model::CallToolResult {
+	let text = match &body {
+		Value::String(s) => s.clone(),
+		v => v.to_string(),
+	};
+	rmcp::model::CallToolResult {
+		content: vec![rmcp::model::Content::text(text)],
+		structured_content: body.is_object().then_some(body),
+		is_error: None,
+		meta: None,
+	}
+}
+
 #[derive(Debug)]
 pub struct Handler {
 	pub prefix: String,
@@ -574,18 +589,23 @@ impl Handler {
 				return Err(UpstreamError::InvalidMethod(method.to_string()));
 			},
 			ClientRequest::CallToolRequest(ctr) => {
-				let res = self
-					.call_tool(ctr.params.name.as_ref(), ctr.params.arguments, ctx)
-					.await?;
-				Messages::from_result(
-					id,
-					CallToolResult {
-						content: vec![],
-						structured_content: Some(res),
-						is_error: None,
+				let name = ctr.params.name.as_ref();
+				if !self.tools.iter().any(|(t, _)| t.name == name) {
+					return Err(UpstreamError::OpenAPIError(anyhow::anyhow!(
+						"tool {name} not found"
+					)));
+				}
+				// A failed API call is a result the model can act on, not a protocol error.
+				let res = match self.call_tool(name, ctr.params.arguments, ctx).await {
+					Ok(res) => tool_result(res),
+					Err(e) => CallToolResult {
+						content: vec![Content::text(e.to_string())],
+						structured_content: None,
+						is_error: Some(true),
 						meta: None,
 					},
-				)
+				};
+				Messages::from_result(id, res)
 			},
 			ClientRequest::ListToolsRequest(_) => Messages::from_result(
 				id,
@@ -772,15 +792,14 @@ impl Handler {
 			.await?
 			.1;
 
-			let body = serde_json::from_slice::<serde_json::Value>(&body_bytes)?;
+			// Not every API responds with JSON; anything else is returned as text.
+			let body = serde_json::from_slice::<serde_json::Value>(&body_bytes)
+				.unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&body_bytes).into_owned()));
 			Ok(body)
 		} else {
 			let lim = crate::http::response_buffer_limit(&response);
-			let body = String::from_utf8(
-				crate::http::read_body_with_limit(response.into_body(), lim)
-					.await?
-					.to_vec(),
-			)?;
+			let body = crate::http::read_body_with_limit(response.into_body(), lim).await?;
+			let body = std::str::from_utf8(&body)?;
 			Err(anyhow::anyhow!(
 				"Upstream API call for tool '{}' failed with status {}: {}",
 				name,
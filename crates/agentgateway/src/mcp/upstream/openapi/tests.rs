@@ -28,6 +28,7 @@ async fn setup() -> (MockServer, Handler) {
 		&client::Config {
 			resolver_cfg: ResolverConfig::default(),
 			resolver_opts: ResolverOpts::default(),
+			resolver: Default::default(),
 		},
 		None,
 		BackendConfig::default(),
@@ -40,9 +41,11 @@ async fn setup() -> (MockServer, Handler) {
 		metrics: Arc::new(crate::metrics::Metrics::new(
 			metrics::sub_registry(&mut Registry::default()),
 			Default::default(),
+			Default::default(),
 		)),
 		upstream: client.clone(),
 		ca: None,
+		quotas: Default::default(),
 
 		mcp_state: mcp::router::App::new(stores.clone(), encoder),
 	});
@@ -500,6 +503,48 @@ async fn test_call_tool_with_compressed_response() {
 	assert_eq!(result.unwrap(), expected_response);
 }
 
+#[tokio::test]
+async fn test_call_tool_text_response() {
+	let (server, handler) = setup().await;
+
+	Mock::given(method("GET"))
+		.and(path("/users/text-user"))
+		.respond_with(ResponseTemplate::new(200).set_body_string("plain text"))
+		.mount(&server)
+		.await;
+
+	let args = json!({ "path": { "user_id": "text-user" } });
+	let result = handler
+		.call_tool(
+			"get_user",
+			Some(args.as_object().unwrap().clone()),
+			&IncomingRequestContext::empty(),
+		)
+		.await;
+
+	assert_eq!(result.unwrap(), json!("plain text"));
+}
+
+#[test]
+fn test_tool_result() {
+	let object = json!({ "id": "123" });
+	let result = super::tool_result(object.clone());
+	assert_eq!(result.structured_content, Some(object.clone()));
+	assert_eq!(
+		result.content[0].as_text().unwrap().text,
+		object.to_string()
+	);
+	assert_eq!(result.is_error, None);
+
+	// Structured content must be an object, so anything else is only text.
+	let result = super::tool_result(json!([1, 2]));
+	assert_eq!(result.structured_content, None);
+	assert_eq!(result.content[0].as_text().unwrap().text, "[1,2]");
+
+	let result = super::tool_result(json!("plain text"));
+	assert_eq!(result.content[0].as_text().unwrap().text, "plain text");
+}
+
 #[tokio::test]
 async fn test_normalize_url_path_empty_prefix() {
 	// Test the fix for double slash issue when prefix is empty (host/port config)
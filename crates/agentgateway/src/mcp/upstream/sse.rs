@@ -80,7 +80,7 @@ impl ClientCore {
 		message: ClientJsonRpcMessage,
 		ctx: &IncomingRequestContext,
 	) -> Result<(), ClientError> {
-		let body = serde_json::to_vec(&message).map_err(ClientError::new)?;
+		let body = crate::http::bufpool::to_json(&message).map_err(ClientError::new)?;
 
 		let mut req = ::http::Request::builder()
 			.uri(&self.uri)
@@ -0,0 +1,347 @@
+//! Memory watchdog.
+//!
+//! Past its memory limit, the gateway is OOM-killed without warning, dropping every connection at
+//! once. The watchdog instead compares the memory in use to the limit, and sheds load as it gets
+//! close so memory can recover:
+//!
+//! * Past `evictAt`, caches and idle pooled buffers are dropped.
+//! * Past `shedAt`, new connections are closed as they are accepted, and requests with large
+//!   bodies are rejected with 503.
+//!
+//! Memory in use is the resident set size of the process, which includes memory the allocator
+//! keeps after it is freed, so is what the limit is enforced against.
+
+use std::sync::atomic::{AtomicU8, AtomicU64, Ordering};
+use std::sync::{LazyLock, Weak};
+
+use agent_core::strng::RichStrng;
+use parking_lot::Mutex;
+use prometheus_client::collector::Collector;
+use prometheus_client::encoding::{DescriptorEncoder, EncodeMetric};
+use prometheus_client::metrics::MetricType;
+use prometheus_client::metrics::counter::ConstCounter;
+use prometheus_client::metrics::gauge::ConstGauge;
+use prometheus_client::registry::Registry;
+
+use crate::telemetry::events::{self, EventKind};
+use crate::telemetry::metrics::MemoryShedLabels;
+use crate::*;
+
+#[cfg(test)]
+#[path = "memory_tests.rs"]
+mod tests;
+
+/// How far below a threshold, as a fraction of the limit, memory must fall to leave its pressure
+/// level, so the level does not flap around the threshold.
+const HYSTERESIS: f64 = 0.05;
+
+static STATE: LazyLock<State> = LazyLock::new(State::default);
+
+#[apply(schema!)]
+pub struct Config {
+	/// The memory limit, in bytes. Defaults to the cgroup memory limit. If neither is set, the
+	/// watchdog is disabled.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub limit: Option<u64>,
+	/// The fraction of the limit past which caches are evicted. Defaults to 0.8.
+	#[serde(default = "default_evict_at")]
+	pub evict_at: f64,
+	/// The fraction of the limit past which new connections, and requests with large bodies, are
+	/// rejected. Defaults to 0.9.
+	#[serde(default = "default_shed_at")]
+	pub shed_at: f64,
+	/// While shedding, requests with a body larger than this many bytes, or of unknown length, are
+	/// rejected. Defaults to 64KiB.
+	#[serde(default = "default_large_body")]
+	pub large_body: u64,
+	/// How often memory usage is checked. Defaults to 1s.
+	#[serde(default = "default_interval", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub interval: Duration,
+}
+
+fn default_evict_at() -> f64 {
+	0.8
+}
+
+fn default_shed_at() -> f64 {
+	0.9
+}
+
+fn default_large_body() -> u64 {
+	64 * 1024
+}
+
+fn default_interval() -> Duration {
+	Duration::from_secs(1)
+}
+
+impl Config {
+	pub fn validate(&self) -> anyhow::Result<()> {
+		if !(self.evict_at > 0.0 && self.evict_at <= self.shed_at && self.shed_at <= 1.0) {
+			anyhow::bail!("thresholds must satisfy 0 < evictAt <= shedAt <= 1");
+		}
+		if self.interval.is_zero() {
+			anyhow::bail!("interval must be positive");
+		}
+		Ok(())
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Pressure {
+	Normal,
+	/// Caches are evicted.
+	Evict,
+	/// New connections, and requests with large bodies, are rejected.
+	Shed,
+}
+
+impl Pressure {
+	fn from_u8(v: u8) -> Pressure {
+		match v {
+			0 => Pressure::Normal,
+			1 => Pressure::Evict,
+			_ => Pressure::Shed,
+		}
+	}
+
+	/// The pressure level at `used`, the fraction of the limit in use, coming from `self`.
+	fn next(self, used: f64, cfg: &Config) -> Pressure {
+		// Levels are entered at their threshold, but only left once memory falls further below it.
+		let below = |threshold: f64, level: Pressure| {
+			if self >= level {
+				used < threshold - HYSTERESIS
+			} else {
+				used < threshold
+			}
+		};
+		if !below(cfg.shed_at, Pressure::Shed) {
+			Pressure::Shed
+		} else if !below(cfg.evict_at, Pressure::Evict) {
+			Pressure::Evict
+		} else {
+			Pressure::Normal
+		}
+	}
+}
+
+/// A cache that can drop its entries to free memory.
+pub trait Evict: Send + Sync {
+	/// Drop all entries, returning how many were dropped.
+	fn evict(&self) -> usize;
+}
+
+#[derive(Default)]
+struct State {
+	pressure: AtomicU8,
+	usage: AtomicU64,
+	limit: AtomicU64,
+	large_body: AtomicU64,
+	shed_connections: AtomicU64,
+	shed_requests: AtomicU64,
+	evicted: AtomicU64,
+	caches: Mutex<Vec<Weak<dyn Evict>>>,
+}
+
+impl State {
+	fn pressure(&self) -> Pressure {
+		Pressure::from_u8(self.pressure.load(Ordering::Relaxed))
+	}
+
+	/// Update the pressure level from the memory in use, and evict caches if needed.
+	fn check(&self, usage: u64, limit: u64, cfg: &Config) -> Pressure {
+		self.usage.store(usage, Ordering::Relaxed);
+		let prev = self.pressure();
+		let next = prev.next(usage as f64 / limit as f64, cfg);
+		self.pressure.store(next as u8, Ordering::Relaxed);
+		if next != prev {
+			if next > prev {
+				warn!(?next, usage, limit, "memory pressure increased");
+			} else {
+				info!(?next, usage, limit, "memory pressure decreased");
+			}
+			events::publish(EventKind::MemoryPressure {
+				pressure: next,
+				usage,
+				limit,
+			});
+		}
+		if next >= Pressure::Evict {
+			let evicted = self.evict();
+			debug!(evicted, "evicted caches under memory pressure");
+		}
+		next
+	}
+
+	fn evict(&self) -> usize {
+		let caches: Vec<_> = {
+			let mut caches = self.caches.lock();
+			caches.retain(|c| c.strong_count() > 0);
+			caches.iter().filter_map(Weak::upgrade).collect()
+		};
+		let evicted = caches.iter().map(|c| c.evict()).sum::<usize>() + http::bufpool::trim();
+		self.evicted.fetch_add(evicted as u64, Ordering::Relaxed);
+		evicted
+	}
+
+	fn shed_request(&self, body: &impl http_body::Body) -> bool {
+		if self.pressure() < Pressure::Shed || body.is_end_stream() {
+			return false;
+		}
+		let large_body = self.large_body.load(Ordering::Relaxed);
+		let shed = body.size_hint().upper().is_none_or(|n| n > large_body);
+		if shed {
+			self.shed_requests.fetch_add(1, Ordering::Relaxed);
+		}
+		shed
+	}
+}
+
+/// Register `cache` to be evicted under memory pressure, for as long as it is alive.
+pub fn register_cache<C: Evict + 'static>(cache: C) -> Arc<C> {
+	let cache = Arc::new(cache);
+	let weak: Weak<dyn Evict> = Arc::downgrade(&cache);
+	STATE.caches.lock().push(weak);
+	cache
+}
+
+/// The current memory pressure level.
+pub fn pressure() -> Pressure {
+	STATE.pressure()
+}
+
+/// Whether a newly accepted connection should be closed, to shed load.
+pub fn shed_connection() -> bool {
+	let shed = STATE.pressure() >= Pressure::Shed;
+	if shed {
+		STATE.shed_connections.fetch_add(1, Ordering::Relaxed);
+	}
+	shed
+}
+
+/// Whether a request with `body` should be rejected, to shed load.
+pub fn shed_request(body: &impl http_body::Body) -> bool {
+	STATE.shed_request(body)
+}
+
+/// Start the watchdog, checking memory usage every `interval`.
+pub fn spawn(cfg: Config) {
+	let Some(limit) = cfg.limit.or_else(cgroup_limit) else {
+		warn!("no memory limit is configured or set on the cgroup, the memory watchdog is disabled");
+		return;
+	};
+	info!(limit, "starting memory watchdog");
+	STATE.limit.store(limit, Ordering::Relaxed);
+	STATE.large_body.store(cfg.large_body, Ordering::Relaxed);
+	tokio::spawn(async move {
+		let mut interval = tokio::time::interval(cfg.interval);
+		interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+		loop {
+			interval.tick().await;
+			match resident_memory() {
+				Some(usage) => {
+					STATE.check(usage, limit, &cfg);
+				},
+				None => {
+					warn!("failed to read memory usage, the memory watchdog is disabled");
+					return;
+				},
+			}
+		}
+	});
+}
+
+fn resident_memory() -> Option<u64> {
+	parse_vm_rss(&fs_err::read_to_string("/proc/self/status").ok()?)
+}
+
+/// The resident set size in bytes, from the contents of `/proc/self/status`.
+fn parse_vm_rss(status: &str) -> Option<u64> {
+	let line = status.lines().find_map(|l| l.strip_prefix("VmRSS:"))?;
+	let kb = line.trim().strip_suffix("kB")?.trim().parse::<u64>().ok()?;
+	Some(kb * 1024)
+}
+
+fn cgroup_limit() -> Option<u64> {
+	// cgroup v2, then v1.
+	[
+		"/sys/fs/cgroup/memory.max",
+		"/sys/fs/cgroup/memory/memory.limit_in_bytes",
+	]
+	.into_iter()
+	.find_map(|path| fs_err::read_to_string(path).ok())
+	.and_then(|s| parse_cgroup_limit(&s))
+}
+
+fn parse_cgroup_limit(s: &str) -> Option<u64> {
+	let limit = s.trim().parse::<u64>().ok()?;
+	// cgroup v2 reports no limit as `max`, and v1 as a value close to the largest page-aligned i64.
+	(limit < 1 << 60).then_some(limit)
+}
+
+/// Exposes memory usage, the pressure level, and how much load was shed, at scrape time.
+#[derive(Debug)]
+pub struct MemoryCollector;
+
+impl MemoryCollector {
+	pub fn register(registry: &mut Registry) {
+		registry.register_collector(Box::new(MemoryCollector));
+	}
+}
+
+impl Collector for MemoryCollector {
+	fn encode(&self, mut encoder: DescriptorEncoder) -> Result<(), std::fmt::Error> {
+		let gauges = [
+			(
+				"memory_usage_bytes",
+				"Resident memory of the process, as last checked by the memory watchdog",
+				&STATE.usage,
+			),
+			(
+				"memory_limit_bytes",
+				"The memory limit enforced by the memory watchdog",
+				&STATE.limit,
+			),
+		];
+		for (name, help, v) in gauges {
+			let metric_encoder = encoder.encode_descriptor(name, help, None, MetricType::Gauge)?;
+			ConstGauge::new(v.load(Ordering::Relaxed) as i64).encode(metric_encoder)?;
+		}
+
+		let metric_encoder = encoder.encode_descriptor(
+			"memory_pressure",
+			"The memory pressure level: 0 (normal), 1 (evicting caches), or 2 (shedding load)",
+			None,
+			MetricType::Gauge,
+		)?;
+		ConstGauge::new(STATE.pressure() as i64).encode(metric_encoder)?;
+
+		let mut metric_encoder = encoder.encode_descriptor(
+			"memory_shed",
+			"Connections closed and requests rejected to shed load under memory pressure, by kind",
+			None,
+			MetricType::Counter,
+		)?;
+		for (kind, count) in [
+			("connection", &STATE.shed_connections),
+			("request", &STATE.shed_requests),
+		] {
+			let labels = MemoryShedLabels {
+				kind: RichStrng::from(kind),
+			};
+			let family_encoder = metric_encoder.encode_family(&labels)?;
+			ConstCounter::new(count.load(Ordering::Relaxed)).encode(family_encoder)?;
+		}
+
+		let metric_encoder = encoder.encode_descriptor(
+			"memory_evicted",
+			"Cache entries and idle buffers dropped under memory pressure",
+			None,
+			MetricType::Counter,
+		)?;
+		ConstCounter::new(STATE.evicted.load(Ordering::Relaxed)).encode(metric_encoder)?;
+		Ok(())
+	}
+}
@@ -0,0 +1,116 @@
+use std::convert::Infallible;
+use std::sync::atomic::AtomicUsize;
+
+use bytes::Bytes;
+use http_body::Frame;
+use http_body_util::{Empty, Full, StreamBody};
+
+use super::*;
+
+fn config(cfg: serde_json::Value) -> Config {
+	serde_json::from_value(cfg).unwrap()
+}
+
+#[derive(Default)]
+struct TestCache(AtomicUsize);
+
+impl Evict for TestCache {
+	fn evict(&self) -> usize {
+		self.0.swap(0, Ordering::Relaxed)
+	}
+}
+
+#[test]
+fn test_config() {
+	let cfg = config(serde_json::json!({}));
+	assert_eq!(cfg.limit, None);
+	assert_eq!(cfg.evict_at, 0.8);
+	assert_eq!(cfg.shed_at, 0.9);
+	assert_eq!(cfg.large_body, 64 * 1024);
+	assert_eq!(cfg.interval, Duration::from_secs(1));
+	cfg.validate().unwrap();
+
+	for invalid in [
+		serde_json::json!({"evictAt": 0}),
+		serde_json::json!({"evictAt": 0.95}),
+		serde_json::json!({"shedAt": 1.5}),
+		serde_json::json!({"interval": "0s"}),
+	] {
+		assert!(config(invalid).validate().is_err());
+	}
+}
+
+#[test]
+fn test_pressure() {
+	let cfg = config(serde_json::json!({}));
+	use Pressure::*;
+	assert_eq!(Normal.next(0.5, &cfg), Normal);
+	assert_eq!(Normal.next(0.8, &cfg), Evict);
+	assert_eq!(Normal.next(0.95, &cfg), Shed);
+	// Levels are only left once memory falls well below their threshold.
+	assert_eq!(Evict.next(0.78, &cfg), Evict);
+	assert_eq!(Evict.next(0.74, &cfg), Normal);
+	assert_eq!(Shed.next(0.88, &cfg), Shed);
+	assert_eq!(Shed.next(0.84, &cfg), Evict);
+	assert_eq!(Shed.next(0.5, &cfg), Normal);
+}
+
+#[test]
+fn test_check() {
+	let cfg = config(serde_json::json!({}));
+	let state = State::default();
+	let cache = Arc::new(TestCache::default());
+	let weak: Weak<dyn Evict> = Arc::downgrade(&cache);
+	state.caches.lock().push(weak);
+
+	cache.0.store(10, Ordering::Relaxed);
+	assert_eq!(state.check(50, 100, &cfg), Pressure::Normal);
+	assert_eq!(cache.0.load(Ordering::Relaxed), 10);
+
+	assert_eq!(state.check(85, 100, &cfg), Pressure::Evict);
+	assert_eq!(cache.0.load(Ordering::Relaxed), 0);
+	assert!(state.evicted.load(Ordering::Relaxed) >= 10);
+	assert_eq!(state.usage.load(Ordering::Relaxed), 85);
+
+	assert_eq!(state.check(95, 100, &cfg), Pressure::Shed);
+	assert_eq!(state.pressure(), Pressure::Shed);
+
+	// Dropped caches are no longer evicted.
+	drop(cache);
+	state.evict();
+	assert!(state.caches.lock().is_empty());
+}
+
+#[test]
+fn test_shed_request() {
+	let state = State::default();
+	state.large_body.store(10, Ordering::Relaxed);
+	let small = || Full::new(Bytes::from_static(b"small"));
+	let large = || Full::new(Bytes::from_static(b"a much larger body"));
+	let unknown = || StreamBody::new(futures::stream::empty::<Result<Frame<Bytes>, Infallible>>());
+
+	assert!(!state.shed_request(&large()));
+
+	state
+		.pressure
+		.store(Pressure::Shed as u8, Ordering::Relaxed);
+	assert!(!state.shed_request(&Empty::<Bytes>::new()));
+	assert!(!state.shed_request(&small()));
+	assert!(state.shed_request(&large()));
+	assert!(state.shed_request(&unknown()));
+	assert_eq!(state.shed_requests.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn test_parse_vm_rss() {
+	let status = "Name:\tagentgateway\nVmPeak:\t  204800 kB\nVmRSS:\t   10240 kB\nThreads:\t8\n";
+	assert_eq!(parse_vm_rss(status), Some(10 * 1024 * 1024));
+	assert_eq!(parse_vm_rss("Name:\tagentgateway\n"), None);
+}
+
+#[test]
+fn test_parse_cgroup_limit() {
+	assert_eq!(parse_cgroup_limit("536870912\n"), Some(512 * 1024 * 1024));
+	assert_eq!(parse_cgroup_limit("max\n"), None);
+	assert_eq!(parse_cgroup_limit("9223372036854771712\n"), None);
+}
@@ -0,0 +1,352 @@
+//! CPU overload manager.
+//!
+//! When the data plane runs out of CPU, every request slows down at once, and work the gateway does
+//! beyond proxying competes with proxying itself. The overload manager measures how late the data
+//! plane runtime wakes up timers (event loop lag) and how much of the available CPU the process
+//! uses. While either is past its threshold, the overload [`Level`] rises by one step each check,
+//! progressively disabling expensive features and finally shedding low priority requests. Once both
+//! have been below their thresholds for `cooldown`, it falls back by one step at a time.
+//!
+//! The current level and recent transitions are served at `/debug/overload`.
+
+use std::collections::VecDeque;
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicI64, AtomicU8, AtomicU64, Ordering};
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use prometheus_client::collector::Collector;
+use prometheus_client::encoding::{DescriptorEncoder, EncodeMetric};
+use prometheus_client::metrics::MetricType;
+use prometheus_client::metrics::counter::ConstCounter;
+use prometheus_client::metrics::gauge::ConstGauge;
+use prometheus_client::registry::Registry;
+
+use crate::*;
+
+#[cfg(test)]
+#[path = "overload_tests.rs"]
+mod tests;
+
+/// How many recent level transitions are kept for `/debug/overload`.
+const HISTORY: usize = 32;
+
+/// Process CPU time in `/proc/self/stat` is in clock ticks, which Linux always reports at 100Hz.
+const CLOCK_TICKS_PER_SEC: f64 = 100.0;
+
+static STATE: LazyLock<State> = LazyLock::new(State::default);
+
+#[apply(schema!)]
+pub struct Config {
+	/// How late the data plane may wake up a timer before it is overloaded. Defaults to 100ms.
+	#[serde(default = "default_max_lag", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub max_lag: Duration,
+	/// The fraction of the available CPU the process may use before it is overloaded. Defaults to
+	/// 0.9.
+	#[serde(default = "default_max_cpu")]
+	pub max_cpu: f64,
+	/// While shedding, requests with a priority below this are rejected. The priority of a request is
+	/// from its route's `requestQueue` priority expression, or 0 if the route has none. Defaults to
+	/// 1.
+	#[serde(default = "default_min_priority")]
+	pub min_priority: i64,
+	/// How often lag and CPU usage are checked, and so how quickly the level rises. Defaults to 1s.
+	#[serde(default = "default_interval", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub interval: Duration,
+	/// How long lag and CPU usage must stay below their thresholds before the level falls by a step.
+	/// Defaults to 10s.
+	#[serde(default = "default_cooldown", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub cooldown: Duration,
+}
+
+fn default_max_lag() -> Duration {
+	Duration::from_millis(100)
+}
+
+fn default_max_cpu() -> f64 {
+	0.9
+}
+
+fn default_min_priority() -> i64 {
+	1
+}
+
+fn default_interval() -> Duration {
+	Duration::from_secs(1)
+}
+
+fn default_cooldown() -> Duration {
+	Duration::from_secs(10)
+}
+
+impl Config {
+	pub fn validate(&self) -> anyhow::Result<()> {
+		if !(self.max_cpu > 0.0 && self.max_cpu <= 1.0) {
+			anyhow::bail!("maxCpu must be between 0 and 1");
+		}
+		if self.interval.is_zero() {
+			anyhow::bail!("interval must be positive");
+		}
+		Ok(())
+	}
+}
+
+/// Each level disables everything the levels below it do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Level {
+	Normal,
+	/// New requests are not traced.
+	NoTracing,
+	/// Request and response body transformations are skipped, and LLM responses are not
+	/// recompressed.
+	NoBodyProcessing,
+	/// Requests below the minimum priority are rejected.
+	Shed,
+}
+
+impl Level {
+	fn from_u8(v: u8) -> Level {
+		match v {
+			0 => Level::Normal,
+			1 => Level::NoTracing,
+			2 => Level::NoBodyProcessing,
+			_ => Level::Shed,
+		}
+	}
+
+	fn up(self) -> Level {
+		Level::from_u8((self as u8).saturating_add(1))
+	}
+
+	fn down(self) -> Level {
+		Level::from_u8((self as u8).saturating_sub(1))
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sample {
+	/// How late the data plane woke up from its last check.
+	#[serde(with = "serde_dur")]
+	pub lag: Duration,
+	/// The fraction of the available CPU used since the last check.
+	pub cpu: f64,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Transition {
+	pub at: DateTime<Utc>,
+	pub from: Level,
+	pub to: Level,
+	#[serde(flatten)]
+	pub sample: Sample,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Report {
+	pub level: Level,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub sample: Option<Sample>,
+	pub shed_requests: u64,
+	pub config: Config,
+	/// The most recent level transitions, oldest first.
+	pub transitions: Vec<Transition>,
+}
+
+#[derive(Default)]
+struct State {
+	level: AtomicU8,
+	min_priority: AtomicI64,
+	shed_requests: AtomicU64,
+	inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+	config: Option<Config>,
+	sample: Option<Sample>,
+	/// Since when lag and CPU usage have been below their thresholds.
+	calm_since: Option<Instant>,
+	transitions: VecDeque<Transition>,
+}
+
+impl State {
+	fn level(&self) -> Level {
+		Level::from_u8(self.level.load(Ordering::Relaxed))
+	}
+
+	fn configure(&self, cfg: &Config) {
+		self.min_priority.store(cfg.min_priority, Ordering::Relaxed);
+		self.inner.lock().config = Some(cfg.clone());
+	}
+
+	/// Update the level from a sample taken at `now`.
+	fn check(&self, sample: Sample, cfg: &Config, now: Instant) -> Level {
+		let mut inner = self.inner.lock();
+		inner.sample = Some(sample);
+		let prev = self.level();
+		let next = if sample.lag > cfg.max_lag || sample.cpu > cfg.max_cpu {
+			inner.calm_since = None;
+			prev.up()
+		} else {
+			let calm_since = *inner.calm_since.get_or_insert(now);
+			if prev > Level::Normal && now.duration_since(calm_since) >= cfg.cooldown {
+				// Wait out another cooldown before the next step down.
+				inner.calm_since = Some(now);
+				prev.down()
+			} else {
+				prev
+			}
+		};
+		if next != prev {
+			if next > prev {
+				warn!(from=?prev, to=?next, lag=?sample.lag, cpu=sample.cpu, "overload level increased");
+			} else {
+				info!(from=?prev, to=?next, lag=?sample.lag, cpu=sample.cpu, "overload level decreased");
+			}
+			if inner.transitions.len() == HISTORY {
+				inner.transitions.pop_front();
+			}
+			inner.transitions.push_back(Transition {
+				at: Utc::now(),
+				from: prev,
+				to: next,
+				sample,
+			});
+			self.level.store(next as u8, Ordering::Relaxed);
+		}
+		next
+	}
+
+	fn shed_request(&self, priority: i64) -> bool {
+		let shed = self.level() >= Level::Shed && priority < self.min_priority.load(Ordering::Relaxed);
+		if shed {
+			self.shed_requests.fetch_add(1, Ordering::Relaxed);
+		}
+		shed
+	}
+
+	fn report(&self) -> Option<Report> {
+		let inner = self.inner.lock();
+		Some(Report {
+			level: self.level(),
+			sample: inner.sample,
+			shed_requests: self.shed_requests.load(Ordering::Relaxed),
+			config: inner.config.clone()?,
+			transitions: inner.transitions.iter().cloned().collect(),
+		})
+	}
+}
+
+/// The current overload level.
+pub fn level() -> Level {
+	STATE.level()
+}
+
+/// Whether new requests may be traced.
+pub fn tracing_enabled() -> bool {
+	STATE.level() < Level::NoTracing
+}
+
+/// Whether bodies may be transformed or recompressed.
+pub fn body_processing_enabled() -> bool {
+	STATE.level() < Level::NoBodyProcessing
+}
+
+/// Whether a request of `priority` should be rejected, to shed load.
+pub fn shed_request(priority: i64) -> bool {
+	STATE.shed_request(priority)
+}
+
+/// Report the current level and recent transitions, if the overload manager is configured.
+pub fn report() -> Option<Report> {
+	STATE.report()
+}
+
+/// Start checking for overload on the data plane runtime, every `interval`.
+pub fn spawn(cfg: Config, data_plane: &tokio::runtime::Handle) {
+	STATE.configure(&cfg);
+	let cores = std::thread::available_parallelism().map_or(1, |n| n.get()) as f64;
+	data_plane.spawn(async move {
+		let mut cpu = CpuClock::default();
+		loop {
+			let start = Instant::now();
+			tokio::time::sleep(cfg.interval).await;
+			let now = Instant::now();
+			let sample = Sample {
+				lag: now.duration_since(start).saturating_sub(cfg.interval),
+				cpu: cpu.utilization(now, cores).unwrap_or_default(),
+			};
+			STATE.check(sample, &cfg, now);
+		}
+	});
+}
+
+/// Tracks process CPU time between checks.
+#[derive(Default)]
+struct CpuClock {
+	last: Option<(Instant, Duration)>,
+}
+
+impl CpuClock {
+	/// The fraction of `cores` used since the last call.
+	fn utilization(&mut self, now: Instant, cores: f64) -> Option<f64> {
+		let used = parse_cpu_time(&fs_err::read_to_string("/proc/self/stat").ok()?)?;
+		let (last_at, last_used) = self.last.replace((now, used))?;
+		let wall = now.duration_since(last_at).as_secs_f64();
+		if wall == 0.0 {
+			return None;
+		}
+		Some((used.saturating_sub(last_used).as_secs_f64() / wall / cores).min(1.0))
+	}
+}
+
+/// The user and system CPU time of the process, from the contents of `/proc/self/stat`.
+fn parse_cpu_time(stat: &str) -> Option<Duration> {
+	// The command name may contain spaces, so fields are counted from after it. `utime` and `stime`
+	// are the 14th and 15th fields, and the state, the first after the command name, is the 3rd.
+	let (_, fields) = stat.rsplit_once(')')?;
+	let mut fields = fields.split_whitespace().skip(11);
+	let utime = fields.next()?.parse::<u64>().ok()?;
+	let stime = fields.next()?.parse::<u64>().ok()?;
+	Some(Duration::from_secs_f64(
+		(utime + stime) as f64 / CLOCK_TICKS_PER_SEC,
+	))
+}
+
+/// Exposes the overload level and how many requests were shed, at scrape time.
+#[derive(Debug)]
+pub struct OverloadCollector;
+
+impl OverloadCollector {
+	pub fn register(registry: &mut Registry) {
+		registry.register_collector(Box::new(OverloadCollector));
+	}
+}
+
+impl Collector for OverloadCollector {
+	fn encode(&self, mut encoder: DescriptorEncoder) -> Result<(), std::fmt::Error> {
+		let metric_encoder = encoder.encode_descriptor(
+			"overload_level",
+			"The overload level: 0 (normal), 1 (no tracing), 2 (no body processing), or 3 (shedding load)",
+			None,
+			MetricType::Gauge,
+		)?;
+		ConstGauge::new(STATE.level() as i64).encode(metric_encoder)?;
+
+		let metric_encoder = encoder.encode_descriptor(
+			"overload_shed_requests",
+			"Requests rejected to shed load while overloaded",
+			None,
+			MetricType::Counter,
+		)?;
+		ConstCounter::new(STATE.shed_requests.load(Ordering::Relaxed)).encode(metric_encoder)?;
+		Ok(())
+	}
+}
@@ -0,0 +1,119 @@
+use super::*;
+
+fn config(cfg: serde_json::Value) -> Config {
+	serde_json::from_value(cfg).unwrap()
+}
+
+fn sample(lag_ms: u64, cpu: f64) -> Sample {
+	Sample {
+		lag: Duration::from_millis(lag_ms),
+		cpu,
+	}
+}
+
+#[test]
+fn test_config() {
+	let cfg = config(serde_json::json!({}));
+	assert_eq!(cfg.max_lag, Duration::from_millis(100));
+	assert_eq!(cfg.max_cpu, 0.9);
+	assert_eq!(cfg.min_priority, 1);
+	assert_eq!(cfg.interval, Duration::from_secs(1));
+	assert_eq!(cfg.cooldown, Duration::from_secs(10));
+	cfg.validate().unwrap();
+
+	assert!(config(serde_json::json!({"maxCpu": 0})).validate().is_err());
+	assert!(
+		config(serde_json::json!({"interval": "0s"}))
+			.validate()
+			.is_err()
+	);
+}
+
+#[test]
+fn test_check() {
+	let cfg = config(serde_json::json!({"cooldown": "10s"}));
+	let state = State::default();
+	state.configure(&cfg);
+	let start = Instant::now();
+	let at = |secs: u64| start + Duration::from_secs(secs);
+
+	assert_eq!(state.check(sample(5, 0.2), &cfg, at(0)), Level::Normal);
+	// Each overloaded check rises a level, from either lag or CPU.
+	assert_eq!(state.check(sample(500, 0.2), &cfg, at(1)), Level::NoTracing);
+	assert_eq!(
+		state.check(sample(5, 0.95), &cfg, at(2)),
+		Level::NoBodyProcessing
+	);
+	assert_eq!(state.check(sample(500, 0.95), &cfg, at(3)), Level::Shed);
+	assert_eq!(state.check(sample(500, 0.95), &cfg, at(4)), Level::Shed);
+
+	// Levels fall one step per cooldown once calm.
+	assert_eq!(state.check(sample(5, 0.2), &cfg, at(5)), Level::Shed);
+	assert_eq!(state.check(sample(5, 0.2), &cfg, at(14)), Level::Shed);
+	assert_eq!(
+		state.check(sample(5, 0.2), &cfg, at(15)),
+		Level::NoBodyProcessing
+	);
+	assert_eq!(
+		state.check(sample(5, 0.2), &cfg, at(20)),
+		Level::NoBodyProcessing
+	);
+	// Overload resets the cooldown.
+	assert_eq!(state.check(sample(500, 0.2), &cfg, at(21)), Level::Shed);
+	assert_eq!(state.check(sample(5, 0.2), &cfg, at(22)), Level::Shed);
+	assert_eq!(
+		state.check(sample(5, 0.2), &cfg, at(32)),
+		Level::NoBodyProcessing
+	);
+
+	let report = state.report().unwrap();
+	assert_eq!(report.level, Level::NoBodyProcessing);
+	assert_eq!(report.sample, Some(sample(5, 0.2)));
+	let transitions: Vec<_> = report.transitions.iter().map(|t| (t.from, t.to)).collect();
+	assert_eq!(
+		transitions,
+		vec![
+			(Level::Normal, Level::NoTracing),
+			(Level::NoTracing, Level::NoBodyProcessing),
+			(Level::NoBodyProcessing, Level::Shed),
+			(Level::Shed, Level::NoBodyProcessing),
+			(Level::NoBodyProcessing, Level::Shed),
+			(Level::Shed, Level::NoBodyProcessing),
+		]
+	);
+}
+
+#[test]
+fn test_history() {
+	let cfg = config(serde_json::json!({"cooldown": "0s"}));
+	let state = State::default();
+	state.configure(&cfg);
+	let now = Instant::now();
+	for _ in 0..HISTORY {
+		state.check(sample(500, 0.2), &cfg, now);
+		state.check(sample(5, 0.2), &cfg, now);
+	}
+	assert_eq!(state.report().unwrap().transitions.len(), HISTORY);
+}
+
+#[test]
+fn test_shed_request() {
+	let cfg = config(serde_json::json!({"minPriority": 5}));
+	let state = State::default();
+	state.configure(&cfg);
+	assert!(!state.shed_request(0));
+
+	state.level.store(Level::Shed as u8, Ordering::Relaxed);
+	assert!(state.shed_request(0));
+	assert!(state.shed_request(4));
+	assert!(!state.shed_request(5));
+	assert_eq!(state.report().unwrap().shed_requests, 2);
+}
+
+#[test]
+fn test_parse_cpu_time() {
+	let stat = "1234 (agent gateway) S 1 1234 1234 0 -1 4194560 5000 0 0 0 250 150 0 0 20 0 8 0 100 \
+	            1000000 2500 18446744073709551615";
+	assert_eq!(parse_cpu_time(stat), Some(Duration::from_secs(4)));
+	assert_eq!(parse_cpu_time("1234 (agentgateway"), None);
+}
@@ -154,3 +154,66 @@ data: [DONE]
 "#
 	);
 }
+
+fn sse_body(msgs: &[&str]) -> http::Body {
+	http::Body::from_stream(futures_util::stream::iter(
+		msgs
+			.iter()
+			.map(|m| Ok::<_, std::io::Error>(Bytes::copy_from_slice(m.as_bytes())))
+			.collect::<Vec<_>>(),
+	))
+}
+
+#[tokio::test]
+async fn test_sse_json_rewrite() {
+	let body = sse_body(&[
+		"data: {\"msg\": 1, \"drop\": true}\n\n",
+		"data: not json\n\n",
+		"data: [DONE]\n\n",
+	]);
+	let rewritten = sse::json_rewrite(
+		body,
+		1024,
+		|v| {
+			v.as_object_mut().unwrap().remove("drop");
+		},
+		|| vec![("end", serde_json::json!({"done": true}))],
+	);
+	let result = rewritten.collect().await.unwrap().to_bytes();
+	assert_eq!(
+		String::from_utf8_lossy(&result),
+		r#"event: 
+data: {"msg":1}
+
+event: 
+data: not json
+
+event: end
+data: {"done":true}
+
+event: 
+data: [DONE]
+
+"#
+	);
+
+	// Without [DONE], the end events are sent once the stream ends
+	let body = sse_body(&["event: delta\ndata: {\"msg\": 2}\n\n"]);
+	let rewritten = sse::json_rewrite(
+		body,
+		1024,
+		|_| {},
+		|| vec![("end", serde_json::json!({"done": true}))],
+	);
+	let result = rewritten.collect().await.unwrap().to_bytes();
+	assert_eq!(
+		String::from_utf8_lossy(&result),
+		r#"event: delta
+data: {"msg":2}
+
+event: end
+data: {"done":true}
+
+"#
+	);
+}
@@ -6,6 +6,7 @@ use http_body::Body;
 use pin_project_lite::pin_project;
 use tokio_util::codec::Decoder;
 
+use crate::http::bufpool;
 use crate::*;
 
 pin_project! {
@@ -13,7 +14,7 @@ pin_project! {
 		#[pin]
 		body: http::Body,
 		decoder: D,
-		decode_buffer: BytesMut,
+		decode_buffer: bufpool::Buffer,
 		handler: F,
 		finished: bool,
 	}
@@ -29,7 +30,7 @@ where
 		body,
 		decoder,
 		handler,
-		decode_buffer: BytesMut::new(),
+		decode_buffer: bufpool::Buffer::default(),
 		finished: false,
 	})
 }
@@ -1,10 +1,11 @@
 use bytes::Bytes;
+use parking_lot::Mutex;
 use serde::Serialize;
 use serde::de::DeserializeOwned;
 use tokio_sse_codec::{Event, Frame, SseDecoder, SseEncoder};
 
 use super::passthrough::parser as passthrough_parser;
-use super::transform::parser as transform_parser;
+use super::transform::{parser as transform_parser, parser_with_eof as transform_parser_with_eof};
 use crate::*;
 
 pub fn json_passthrough<F: DeserializeOwned>(
@@ -56,6 +57,102 @@ pub fn json_transform<I: DeserializeOwned, O: Serialize>(
 	})
 }
 
+/// Like json_transform, but each input event may produce any number of named output events.
+/// The terminal `[DONE]` event is passed to the handler as `None` rather than forwarded, as not all
+/// formats use it.
+pub fn json_transform_multi<I: DeserializeOwned, O: Serialize>(
+	b: http::Body,
+	buffer_limit: usize,
+	mut f: impl FnMut(Option<anyhow::Result<I>>) -> Vec<(&'static str, O)> + Send + 'static,
+) -> http::Body {
+	let decoder = SseDecoder::<Bytes>::with_max_size(buffer_limit);
+	let encoder = SseEncoder::new();
+
+	transform_parser(b, decoder, encoder, move |o| {
+		let Some(data) = unwrap_sse_data(o) else {
+			return vec![];
+		};
+		let obj = if data.as_ref() == b"[DONE]" {
+			None
+		} else {
+			Some(serde_json::from_slice::<I>(&data).map_err(anyhow::Error::from))
+		};
+		f(obj)
+			.into_iter()
+			.filter_map(|(event_name, event)| {
+				serde_json::to_vec(&event).ok().map(|json_bytes| {
+					Frame::Event(Event::<Bytes> {
+						data: Bytes::from(json_bytes),
+						name: std::borrow::Cow::Borrowed(event_name),
+						id: None,
+					})
+				})
+			})
+			.collect::<Vec<_>>()
+	})
+}
+
+/// Rewrite the JSON data of each event in place, keeping event names. Events that are not JSON
+/// are forwarded unchanged. `end` is called once, before the terminal `[DONE]` event or at the end
+/// of the stream if there is none, and the named events it returns are sent there.
+pub fn json_rewrite(
+	b: http::Body,
+	buffer_limit: usize,
+	mut f: impl FnMut(&mut serde_json::Value) + Send + 'static,
+	end: impl FnOnce() -> Vec<(&'static str, serde_json::Value)> + Send + 'static,
+) -> http::Body {
+	let decoder = SseDecoder::<Bytes>::with_max_size(buffer_limit);
+	let encoder = SseEncoder::new();
+	let end = Arc::new(Mutex::new(Some(end)));
+	let eof_end = end.clone();
+
+	transform_parser_with_eof(
+		b,
+		decoder,
+		encoder,
+		move |o| match o {
+			Frame::Event(Event::<Bytes> { data, name, id }) => {
+				if data.as_ref() == b"[DONE]" {
+					let mut events = end_frames(&end);
+					events.push(Frame::Event(Event::<Bytes> { data, name, id }));
+					return events;
+				}
+				let data = match serde_json::from_slice::<serde_json::Value>(&data) {
+					Ok(mut obj) => {
+						f(&mut obj);
+						serde_json::to_vec(&obj).map(Bytes::from).unwrap_or(data)
+					},
+					Err(_) => data,
+				};
+				vec![Frame::Event(Event::<Bytes> { data, name, id })]
+			},
+			other => vec![other],
+		},
+		move || end_frames(&eof_end),
+	)
+}
+
+fn end_frames<F>(end: &Mutex<Option<F>>) -> Vec<Frame<Bytes>>
+where
+	F: FnOnce() -> Vec<(&'static str, serde_json::Value)>,
+{
+	let Some(end) = end.lock().take() else {
+		return vec![];
+	};
+	end()
+		.into_iter()
+		.filter_map(|(event_name, event)| {
+			serde_json::to_vec(&event).ok().map(|json_bytes| {
+				Frame::Event(Event::<Bytes> {
+					data: Bytes::from(json_bytes),
+					name: std::borrow::Cow::Borrowed(event_name),
+					id: None,
+				})
+			})
+		})
+		.collect()
+}
+
 fn unwrap_sse_data(frame: Frame<Bytes>) -> Option<Bytes> {
 	let Frame::Event(Event::<Bytes> { data, .. }) = frame else {
 		return None;
@@ -7,6 +7,7 @@ use http_body::Body;
 use pin_project_lite::pin_project;
 use tokio_util::codec::{Decoder, Encoder};
 
+use crate::http::bufpool;
 use crate::*;
 
 pin_project! {
@@ -14,16 +15,60 @@ pin_project! {
 		#[pin]
 		body: http::Body,
 		decoder: D,
-		decode_buffer: BytesMut,
+		decode_buffer: bufpool::Buffer,
+		encode_buffer: bufpool::Buffer,
 		buffered_trailers: Option<HeaderMap>,
 		encoder: E,
 		handler: F,
+		on_eof: Option<EofHandler<T>>,
 		finished: bool,
 		_phantom: std::marker::PhantomData<T>,
 	}
 }
 
+type EofHandler<T> = Box<dyn FnOnce() -> Vec<T> + Send>;
+
 pub fn parser<D, E, F, I, T>(body: http::Body, decoder: D, encoder: E, handler: F) -> http::Body
+where
+	D: Decoder + Send + 'static,
+	D::Error: Send + Into<axum_core::BoxError> + 'static,
+	F: FnMut(D::Item) -> I + Send + 'static,
+	I: IntoIterator<Item = T>,
+	E: Encoder<T> + Send + 'static,
+	E::Error: Send + Into<axum_core::BoxError> + 'static,
+	T: Send + 'static,
+{
+	new_body(body, decoder, encoder, handler, None)
+}
+
+/// Like parser, but `on_eof` is called once the body has been fully decoded, and the items it
+/// returns are encoded at the end of the body.
+pub fn parser_with_eof<D, E, F, I, T>(
+	body: http::Body,
+	decoder: D,
+	encoder: E,
+	handler: F,
+	on_eof: impl FnOnce() -> Vec<T> + Send + 'static,
+) -> http::Body
+where
+	D: Decoder + Send + 'static,
+	D::Error: Send + Into<axum_core::BoxError> + 'static,
+	F: FnMut(D::Item) -> I + Send + 'static,
+	I: IntoIterator<Item = T>,
+	E: Encoder<T> + Send + 'static,
+	E::Error: Send + Into<axum_core::BoxError> + 'static,
+	T: Send + 'static,
+{
+	new_body(body, decoder, encoder, handler, Some(Box::new(on_eof)))
+}
+
+fn new_body<D, E, F, I, T>(
+	body: http::Body,
+	decoder: D,
+	encoder: E,
+	handler: F,
+	on_eof: Option<EofHandler<T>>,
+) -> http::Body
 where
 	D: Decoder + Send + 'static,
 	D::Error: Send + Into<axum_core::BoxError> + 'static,
@@ -37,7 +82,9 @@ where
 		body,
 		decoder,
 		handler,
-		decode_buffer: BytesMut::new(),
+		on_eof,
+		decode_buffer: bufpool::Buffer::default(),
+		encode_buffer: bufpool::Buffer::default(),
 		buffered_trailers: None,
 		encoder,
 		finished: false,
@@ -71,8 +118,6 @@ where
 			return Poll::Ready(None);
 		}
 
-		let mut encode_buffer = BytesMut::new();
-
 		let try_decode = |finished: bool,
 		                  buf: &mut BytesMut,
 		                  decoder: &mut D,
@@ -111,14 +156,15 @@ where
 			&mut *this.decoder,
 			this.handler,
 			&mut *this.encoder,
-			&mut encode_buffer,
+			this.encode_buffer,
 		) {
 			return Poll::Ready(Some(Err(e)));
 		}
 
 		// If we have encoded data to send, send it
-		if !encode_buffer.is_empty() {
-			let data = encode_buffer.split_to(encode_buffer.len());
+		if !this.encode_buffer.is_empty() {
+			// Frames are split off the buffer, so the rest of its capacity is reused.
+			let data = this.encode_buffer.split();
 			return Poll::Ready(Some(Ok(http_body::Frame::data(data.freeze()))));
 		}
 
@@ -146,12 +192,21 @@ where
 					&mut *this.decoder,
 					this.handler,
 					&mut *this.encoder,
-					&mut encode_buffer,
-				) {
+					this.encode_buffer,
+				)
+				.and_then(|_| {
+					for item in this.on_eof.take().map(|f| f()).unwrap_or_default() {
+						this
+							.encoder
+							.encode(item, this.encode_buffer)
+							.map_err(http::Error::new)?;
+					}
+					Ok(())
+				}) {
 					Ok(_) => {
-						if !encode_buffer.is_empty() {
+						if !this.encode_buffer.is_empty() {
 							// If there is more data to encode, send it
-							let data = encode_buffer.split_to(encode_buffer.len());
+							let data = this.encode_buffer.split();
 							Poll::Ready(Some(Ok(http_body::Frame::data(data.freeze()))))
 						} else if let Some(trailer) = std::mem::take(this.buffered_trailers) {
 							// If there is no more data, send any trailers
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::convert::Infallible;
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
@@ -13,22 +14,26 @@ use futures_util::FutureExt;
 use http::StatusCode;
 use hyper_util::rt::TokioIo;
 use hyper_util::server::conn::auto;
-use tokio::net::{TcpListener, TcpStream};
+use tokio::net::TcpListener;
+#[cfg(unix)]
+use tokio::net::UnixListener;
 use tokio::sync::watch;
 use tokio::task::{AbortHandle, JoinSet};
 use tokio_stream::StreamExt;
 use tracing::{Instrument, debug, error, event, info, info_span, warn};
 
+use crate::ProxyInputs;
 use crate::proxy::ProxyError;
+use crate::proxy::shard;
 use crate::store::{Event, FrontendPolices};
 use crate::telemetry::metrics::TCPLabels;
 use crate::transport::BufferLimit;
 use crate::transport::stream::{Extension, LoggingMode, Socket, TLSConnectionInfo};
 use crate::types::agent::{
 	Bind, BindKey, BindProtocol, Listener, ListenerProtocol, TransportProtocol, TunnelProtocol,
+	UnixSocket,
 };
 use crate::types::frontend;
-use crate::{ProxyInputs, client};
 
 #[cfg(test)]
 #[path = "gateway_test.rs"]
@@ -48,57 +53,73 @@ impl Gateway {
 		let drain = self.drain.clone();
 		let subdrain = self.drain.clone();
 		let mut js = JoinSet::new();
+		// In the thread per core mode, each bind is served by every shard instead of by acceptors on
+		// the dataplane runtime.
+		let shards = if self.pi.cfg.threading_mode == crate::ThreadingMode::ThreadPerCore {
+			match shard::start(&self.pi, self.pi.cfg.runtime_shards) {
+				Ok(shards) => shards,
+				Err(e) => {
+					error!("failed to start runtime shards: {e}");
+					return;
+				},
+			}
+		} else {
+			Vec::new()
+		};
 		let (initial_binds, mut binds) = {
 			let binds = self.pi.stores.read_binds();
 			(binds.all(), binds.subscribe())
 		};
-		let mut active: HashMap<SocketAddr, AbortHandle> = HashMap::new();
+		// Each bind has one task per acceptor, or per shard. Only one can listen on a Unix socket.
+		let mut active: HashMap<ListenAddress, Vec<AbortHandle>> = HashMap::new();
 		let mut handle_bind = |js: &mut JoinSet<anyhow::Result<()>>, b: Event<Arc<Bind>>| {
 			let b = match b {
 				Event::Add(b) => b,
 				Event::Remove(to_remove) => {
-					if let Some(h) = active.remove(&to_remove.address) {
+					for h in active
+						.remove(&ListenAddress::of(&to_remove))
+						.unwrap_or_default()
+					{
 						h.abort();
 					}
 					return;
 				},
 			};
-			if active.contains_key(&b.address) {
+			let address = ListenAddress::of(&b);
+			if active.contains_key(&address) {
 				debug!("bind already exists");
 				return;
 			}
 
-			debug!("add bind {}", b.address);
-			if self.pi.cfg.threading_mode == crate::ThreadingMode::ThreadPerCore {
-				let core_ids = core_affinity::get_core_ids().unwrap();
-				let _ = core_ids
-					.into_iter()
-					.map(|id| {
-						let subdrain = subdrain.clone();
-						let pi = self.pi.clone();
-						let b = b.clone();
-						std::thread::spawn(move || {
-							let res = core_affinity::set_for_current(id);
-							if !res {
-								panic!("failed to set current CPU")
-							}
-							tokio::runtime::Builder::new_current_thread()
-								.enable_all()
-								.build()
-								.unwrap()
-								.block_on(async {
-									let _ = Self::run_bind(pi.clone(), subdrain.clone(), b.clone())
-										.in_current_span()
-										.await;
-								})
-						})
+			debug!("add bind {address:?}");
+			let tasks = if shards.is_empty() {
+				let acceptors = if b.unix_socket.is_some() {
+					1
+				} else {
+					self.pi.cfg.acceptors
+				};
+				(0..acceptors)
+					.map(|_| {
+						js.spawn(Self::run_bind(self.pi.clone(), subdrain.clone(), b.clone()).in_current_span())
 					})
-					.collect::<Vec<_>>();
+					.collect()
 			} else {
-				let task =
-					js.spawn(Self::run_bind(self.pi.clone(), subdrain.clone(), b.clone()).in_current_span());
-				active.insert(b.address, task);
-			}
+				let shards = if b.unix_socket.is_some() {
+					&shards[..1]
+				} else {
+					&shards[..]
+				};
+				shards
+					.iter()
+					.map(|s| {
+						js.spawn_on(
+							Self::run_bind(s.pi.clone(), subdrain.clone(), b.clone()).in_current_span(),
+							&s.handle,
+						)
+					})
+					.collect()
+			};
+			active.insert(address, tasks);
 		};
 		for bind in initial_binds {
 			handle_bind(&mut js, Event::Add(bind))
@@ -141,31 +162,18 @@ impl Gateway {
 		let name = b.key.clone();
 		let bind_protocol = b.protocol;
 		let tunnel_protocol = b.tunnel_protocol;
-		let (pi, listener) = if pi.cfg.threading_mode == crate::ThreadingMode::ThreadPerCore {
-			let mut pi = Arc::unwrap_or_clone(pi);
-			let client = client::Client::new(
-				&pi.cfg.dns,
-				None,
-				pi.cfg.backend.clone(),
-				Some(pi.metrics.clone()),
-			);
-			pi.upstream = client;
-			let pi = Arc::new(pi);
-			let builder = if b.address.is_ipv4() {
-				socket2::Socket::new(socket2::Domain::IPV4, socket2::Type::STREAM, None)?
-			} else {
-				socket2::Socket::new(socket2::Domain::IPV6, socket2::Type::STREAM, None)?
-			};
-			#[cfg(target_family = "unix")]
-			builder.set_reuse_port(true)?;
-			builder.bind(&b.address.into())?;
-			builder.listen(1024)?;
-			let listener: std::net::TcpListener = builder.into();
-			listener.set_nonblocking(true)?;
-			let listener = tokio::net::TcpListener::from_std(listener)?;
-			(pi, listener)
+		// Each acceptor or shard binds its own socket, and the kernel spreads new connections across
+		// them.
+		let reuse_port =
+			pi.cfg.threading_mode == crate::ThreadingMode::ThreadPerCore || pi.cfg.acceptors > 1;
+		let listener = if let Some(unix) = &b.unix_socket {
+			BindListener::unix(unix)?
+		} else if pi.cfg.io_uring {
+			BindListener::uring(b.address, reuse_port)?
+		} else if reuse_port {
+			BindListener::Tcp(Self::reuse_port_listener(b.address)?)
 		} else {
-			(pi, TcpListener::bind(b.address).await?)
+			BindListener::Tcp(TcpListener::bind(b.address).await?)
 		};
 		info!(bind = name.as_str(), "started bind");
 		let component = format!("bind {name}");
@@ -192,11 +200,11 @@ impl Gateway {
 			let (mut upgrader, weak) = drain.into_weak();
 			let (inner_trigger, inner_drain) = drain::new();
 			drop(inner_drain);
-			let handle_stream = |stream: TcpStream, upgrader: &DrainUpgrader| {
-				let Ok(mut stream) = Socket::from_tcp(stream) else {
-					// Can fail if they immediately disconnected; not much we can do.
+			let handle_stream = |mut stream: Socket, upgrader: &DrainUpgrader| {
+				if crate::memory::shed_connection() {
+					debug!(bind=?name, "connection closed under memory pressure");
 					return;
-				};
+				}
 				stream.with_logging(LoggingMode::Downstream);
 				let pi = pi.clone();
 				// We got the connection; make a strong drain blocker.
@@ -221,7 +229,7 @@ impl Gateway {
 			// First, accept new connections until a drain is triggered
 			let drain_mode = loop {
 				tokio::select! {
-					Ok((stream, _peer)) = listener.accept() => handle_stream(stream, &upgrader),
+					Ok(stream) = listener.accept() => handle_stream(stream, &upgrader),
 					res = &mut wait => {
 						break res;
 					}
@@ -242,7 +250,7 @@ impl Gateway {
 			// We still need to accept new connections during this time though, so race them
 			loop {
 				tokio::select! {
-					Ok((stream, _peer)) = listener.accept() => handle_stream(stream, &upgrader),
+					Ok(stream) = listener.accept() => handle_stream(stream, &upgrader),
 					_ = &mut drained_for_minimum => {
 						// We are done! exit.
 						// This will stop accepting new connections
@@ -256,6 +264,27 @@ impl Gateway {
 		Ok(())
 	}
 
+	/// Bind a listener with SO_REUSEPORT, so several listeners can share the address.
+	fn reuse_port_listener(address: SocketAddr) -> anyhow::Result<TcpListener> {
+		let listener = Self::reuse_port_std_listener(address)?;
+		Ok(TcpListener::from_std(listener)?)
+	}
+
+	fn reuse_port_std_listener(address: SocketAddr) -> anyhow::Result<std::net::TcpListener> {
+		let builder = if address.is_ipv4() {
+			socket2::Socket::new(socket2::Domain::IPV4, socket2::Type::STREAM, None)?
+		} else {
+			socket2::Socket::new(socket2::Domain::IPV6, socket2::Type::STREAM, None)?
+		};
+		#[cfg(target_family = "unix")]
+		builder.set_reuse_port(true)?;
+		builder.bind(&address.into())?;
+		builder.listen(1024)?;
+		let listener: std::net::TcpListener = builder.into();
+		listener.set_nonblocking(true)?;
+		Ok(listener)
+	}
+
 	pub async fn proxy_bind(
 		bind_name: BindKey,
 		bind_protocol: BindProtocol,
@@ -585,11 +614,11 @@ impl Gateway {
 					Ok((best, Socket::from_tls(ext, counter, tls.into())?))
 				},
 				None => {
-					let sni = sni.to_string();
-					// Passthrough
+					// Passthrough. Without SNI, only routes without hostnames match.
+					let sni = (!sni.is_empty()).then(|| sni.to_string());
 					start.io.rewind();
 					ext.insert(TLSConnectionInfo {
-						server_name: Some(sni),
+						server_name: sni,
 						..Default::default()
 					});
 					Ok((best, Socket::from_rewind(ext, counter, start.io)))
@@ -599,11 +628,12 @@ impl Gateway {
 		tokio::time::timeout(to, handshake).await?
 	}
 
-	/// Handle incoming connection with PROXY protocol v2 header.
+	/// Handle incoming connection with a PROXY protocol v1 or v2 header.
 	///
-	/// Used for Istio sandwich waypoint mode where ztunnel handles mTLS termination
-	/// and forwards traffic to agentgateway using PROXY protocol. The PROXY header
-	/// contains the original client addresses and peer identity (TLV 0xD0).
+	/// Used behind load balancers that forward the original client address, and for Istio
+	/// sandwich waypoint mode where ztunnel handles mTLS termination and forwards traffic to
+	/// agentgateway using PROXY protocol. The PROXY header contains the original client
+	/// addresses and, from ztunnel, the peer identity (TLV 0xD0).
 	async fn terminate_proxy_protocol(
 		bind_name: BindKey,
 		bind_protocol: BindProtocol,
@@ -631,13 +661,16 @@ impl Gateway {
 		let raw_peer_addr = raw_stream.tcp().peer_addr;
 
 		// Update TCPConnectionInfo with real source/dest from PROXY header
-		// This overwrites ztunnel's loopback address with the actual client address
-		raw_stream.ext_mut().insert(TCPConnectionInfo {
-			peer_addr: pp_info.src_addr,
-			local_addr: pp_info.dst_addr,
-			start: Instant::now(),
-			raw_peer_addr: Some(raw_peer_addr),
-		});
+		// This overwrites ztunnel's loopback address with the actual client address.
+		// LOCAL and UNKNOWN headers carry no addresses, so the connection's own are kept.
+		if let Some((src, dst)) = pp_info.addresses {
+			raw_stream.ext_mut().insert(TCPConnectionInfo {
+				peer_addr: src,
+				local_addr: dst,
+				start: Instant::now(),
+				raw_peer_addr: Some(raw_peer_addr),
+			});
+		}
 
 		// Insert TLSConnectionInfo with identity from TLV 0xD0
 		// Even though there's no TLS on this connection, we use this struct
@@ -836,6 +869,83 @@ impl Gateway {
 	}
 }
 
+/// What a bind listens on, which identifies it across updates.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ListenAddress {
+	Tcp(SocketAddr),
+	Unix(PathBuf),
+}
+
+impl ListenAddress {
+	fn of(b: &Bind) -> ListenAddress {
+		match &b.unix_socket {
+			Some(unix) => ListenAddress::Unix(unix.path.clone()),
+			None => ListenAddress::Tcp(b.address),
+		}
+	}
+}
+
+enum BindListener {
+	Tcp(TcpListener),
+	#[cfg(unix)]
+	Unix(UnixListener),
+	#[cfg(all(target_os = "linux", feature = "io-uring"))]
+	Uring(super::uring::Acceptor),
+}
+
+impl BindListener {
+	#[cfg(unix)]
+	fn unix(cfg: &UnixSocket) -> anyhow::Result<BindListener> {
+		use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+		// A socket left behind by a previous run would fail the bind, but nothing else is replaced.
+		match std::fs::symlink_metadata(&cfg.path) {
+			Ok(m) if m.file_type().is_socket() => fs_err::remove_file(&cfg.path)?,
+			Ok(_) => anyhow::bail!("{} exists and is not a socket", cfg.path.display()),
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => {},
+			Err(e) => return Err(e.into()),
+		}
+		let listener = UnixListener::bind(&cfg.path)?;
+		if let Some(mode) = cfg.mode {
+			fs_err::set_permissions(&cfg.path, std::fs::Permissions::from_mode(mode))?;
+		}
+		Ok(BindListener::Unix(listener))
+	}
+
+	#[cfg(not(unix))]
+	fn unix(_cfg: &UnixSocket) -> anyhow::Result<BindListener> {
+		anyhow::bail!("Unix sockets are not supported on this platform")
+	}
+
+	#[cfg(all(target_os = "linux", feature = "io-uring"))]
+	fn uring(address: SocketAddr, reuse_port: bool) -> anyhow::Result<BindListener> {
+		let listener = if reuse_port {
+			Gateway::reuse_port_std_listener(address)?
+		} else {
+			let listener = std::net::TcpListener::bind(address)?;
+			listener.set_nonblocking(true)?;
+			listener
+		};
+		Ok(BindListener::Uring(super::uring::Acceptor::new(listener)?))
+	}
+
+	#[cfg(not(all(target_os = "linux", feature = "io-uring")))]
+	fn uring(_address: SocketAddr, _reuse_port: bool) -> anyhow::Result<BindListener> {
+		anyhow::bail!("io_uring requires Linux, and building with the io-uring feature")
+	}
+
+	async fn accept(&self) -> std::io::Result<Socket> {
+		match self {
+			// Can fail if they immediately disconnected; not much we can do.
+			BindListener::Tcp(l) => Socket::from_tcp(l.accept().await?.0),
+			#[cfg(unix)]
+			BindListener::Unix(l) => Ok(Socket::from_unix_downstream(l.accept().await?.0)),
+			#[cfg(all(target_os = "linux", feature = "io-uring"))]
+			BindListener::Uring(a) => a.accept().await,
+		}
+	}
+}
+
 fn tls_looks_like_http(d: Bytes) -> bool {
 	d.starts_with(b"GET /")
 		|| d.starts_with(b"POST /")
@@ -858,6 +968,7 @@ pub fn auto_server(c: Option<&frontend::HTTP>) -> auto::Builder<::hyper_util::rt
 		http2_window_size,
 		http2_connection_window_size,
 		http2_frame_size,
+		http2_max_concurrent_streams,
 		http2_keepalive_interval,
 		http2_keepalive_timeout,
 	} = c.unwrap_or(&def);
@@ -885,6 +996,9 @@ pub fn auto_server(c: Option<&frontend::HTTP>) -> auto::Builder<::hyper_util::rt
 	if let Some(m) = http2_frame_size {
 		b.http2().max_frame_size(*m);
 	}
+	if let Some(m) = http2_max_concurrent_streams {
+		b.http2().max_concurrent_streams(*m);
+	}
 
 	b
 }
@@ -18,7 +18,8 @@ use crate::test_helpers::proxymock::*;
 use crate::types::agent::{
 	Backend, BackendPolicy, BackendReference, BackendWithPolicies, Bind, BindProtocol, Listener,
 	ListenerProtocol, ListenerSet, PathMatch, PolicyTarget, ResourceName, Route,
-	RouteBackendReference, RouteMatch, RouteName, RouteSet, Target, TargetedPolicy, TrafficPolicy,
+	RouteBackendReference, RouteMatch, RouteName, RouteSet, TCPRouteSet, Target, TargetedPolicy,
+	TrafficPolicy,
 };
 use crate::types::backend;
 use crate::*;
@@ -171,6 +172,65 @@ async fn llm_log_body() {
 	assert_llm(io, include_bytes!("../llm/tests/request_basic.json"), want).await;
 }
 
+#[tokio::test]
+async fn llm_log_embeddings() {
+	use crate::telemetry::{audit, llmlog};
+
+	// Other tests may have initialized it already
+	let _ = llmlog::init(&llmlog::Config {
+		sinks: vec![audit::Sink::Stderr],
+	});
+	let mock = body_mock(
+		json!({
+			"object": "list",
+			"data": [{"object": "embedding", "index": 0, "embedding": [0.1, 0.2]}],
+			"model": "text-embedding-3-small",
+			"usage": {"prompt_tokens": 2, "total_tokens": 2},
+		})
+		.to_string()
+		.as_bytes(),
+	)
+	.await;
+	let (_mock, bind, io) = setup_llm_mock(
+		mock,
+		AIProvider::OpenAI(openai::Provider { model: None }),
+		false,
+		"{}",
+	);
+	let route = RouteName {
+		name: "route".into(),
+		namespace: "".into(),
+		rule_name: None,
+		kind: None,
+	};
+	let _bind = bind
+		.with_policy(TargetedPolicy {
+			key: strng::new("ai"),
+			name: None,
+			target: PolicyTarget::Route(route.clone()),
+			policy: TrafficPolicy::AI(Arc::new(deser(json!({
+				"routes": {"/v1/embeddings": "embeddings"},
+			}))))
+			.into(),
+		})
+		.with_policy(TargetedPolicy {
+			key: strng::new("llmlog"),
+			name: None,
+			target: PolicyTarget::Route(route),
+			policy: TrafficPolicy::LlmLog(deser(json!({}))).into(),
+		});
+
+	// Embeddings have no chat messages, so only the request details are recorded
+	let res = send_request_body(
+		io,
+		Method::POST,
+		"http://lo/v1/embeddings",
+		br#"{"model": "text-embedding-3-small", "input": "hello"}"#,
+	)
+	.await;
+	assert_eq!(res.status(), 200);
+}
+
 #[tokio::test]
 async fn basic_tcp() {
 	let mock = simple_mock().await;
@@ -181,6 +241,192 @@ async fn basic_tcp() {
 	assert_eq!(body.method, Method::POST);
 }
 
+#[tokio::test]
+async fn tcp_idle_timeout() {
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	// An echo server
+	let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let addr = listener.local_addr().unwrap();
+	tokio::spawn(async move {
+		let (mut stream, _) = listener.accept().await.unwrap();
+		let (mut r, mut w) = stream.split();
+		let _ = tokio::io::copy(&mut r, &mut w).await;
+	});
+	let mut route = basic_named_tcp_route(strng::format!("/{addr}"));
+	route.backends[0].inline_policies = vec![BackendPolicy::TCP(backend::TCP {
+		idle_timeout: Some(Duration::from_millis(100)),
+		..Default::default()
+	})];
+	let t = setup_proxy_test("{}")
+		.unwrap()
+		.with_backend(addr)
+		.with_bind(simple_tcp_bind(route));
+	let mut io = t.serve(BIND_KEY);
+
+	io.write_all(b"ping").await.unwrap();
+	let mut buf = [0; 4];
+	io.read_exact(&mut buf).await.unwrap();
+	assert_eq!(&buf, b"ping");
+	// Once nothing is sent for the idle timeout, the connection is closed
+	let n = tokio::time::timeout(Duration::from_secs(5), io.read(&mut buf))
+		.await
+		.unwrap()
+		.unwrap();
+	assert_eq!(n, 0);
+}
+
+#[tokio::test]
+async fn tcp_proxy_protocol_upstream() {
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	use crate::proxy::proxy_protocol::parse_proxy_protocol;
+	use crate::types::backend::ProxyProtocolVersion;
+
+	for version in [ProxyProtocolVersion::V1, ProxyProtocolVersion::V2] {
+		// A backend that reports the PROXY header and the data after it
+		let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let addr = listener.local_addr().unwrap();
+		let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+		tokio::spawn(async move {
+			let (mut stream, _) = listener.accept().await.unwrap();
+			let info = parse_proxy_protocol(&mut stream).await.unwrap();
+			let mut buf = [0; 4];
+			stream.read_exact(&mut buf).await.unwrap();
+			tx.send((info.addresses, buf)).await.unwrap();
+		});
+		let mut route = basic_named_tcp_route(strng::format!("/{addr}"));
+		route.backends[0].inline_policies = vec![BackendPolicy::TCP(backend::TCP {
+			proxy_protocol: Some(version),
+			..Default::default()
+		})];
+		let t = setup_proxy_test("{}")
+			.unwrap()
+			.with_backend(addr)
+			.with_bind(simple_tcp_bind(route));
+		let mut io = t.serve(BIND_KEY);
+
+		io.write_all(b"ping").await.unwrap();
+		let (addresses, data) = rx.recv().await.unwrap();
+		assert_eq!(
+			addresses,
+			Some((
+				"127.0.0.1:12345".parse().unwrap(),
+				"127.0.0.1:80".parse().unwrap()
+			)),
+			"{version:?}"
+		);
+		assert_eq!(&data, b"ping");
+	}
+}
+
+#[cfg(target_family = "unix")]
+#[tokio::test]
+async fn reuse_port_acceptors() {
+	// Each acceptor binds its own socket on the same address
+	let first = super::Gateway::reuse_port_listener("127.0.0.1:0".parse().unwrap()).unwrap();
+	let addr = first.local_addr().unwrap();
+	let second = super::Gateway::reuse_port_listener(addr).unwrap();
+	assert_eq!(second.local_addr().unwrap(), addr);
+}
+
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+#[tokio::test]
+async fn uring_acceptor() {
+	let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+	listener.set_nonblocking(true).unwrap();
+	let addr = listener.local_addr().unwrap();
+	// Where io_uring is unavailable, this accepts with epoll instead.
+	let acceptor = crate::proxy::uring::Acceptor::new(listener).unwrap();
+	for _ in 0..3 {
+		let client = tokio::net::TcpStream::connect(addr).await.unwrap();
+		let socket = acceptor.accept().await.unwrap();
+		assert_eq!(socket.tcp().peer_addr, client.local_addr().unwrap());
+	}
+}
+
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+#[tokio::test]
+async fn uring_copy() {
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	async fn pair() -> (tokio::net::TcpStream, tokio::net::TcpStream) {
+		let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let client = tokio::net::TcpStream::connect(listener.local_addr().unwrap())
+			.await
+			.unwrap();
+		(client, listener.accept().await.unwrap().0)
+	}
+	let (mut client, downstream) = pair().await;
+	let (upstream, mut server) = pair().await;
+	let stats = Arc::new(agent_core::copy::ConnectionResult::default());
+	// Where io_uring is unavailable, this copies with the runtime instead.
+	let copy = tokio::spawn(crate::proxy::uring::copy_bidirectional(
+		crate::transport::stream::Socket::from_tcp(downstream).unwrap(),
+		crate::transport::stream::Socket::from_tcp(upstream).unwrap(),
+		stats.clone(),
+	));
+	let echo = tokio::spawn(async move {
+		let mut buf = vec![0; 64 * 1024];
+		loop {
+			let n = server.read(&mut buf).await.unwrap();
+			if n == 0 {
+				break;
+			}
+			server.write_all(&buf[..n]).await.unwrap();
+		}
+	});
+
+	// Larger than the copy buffers, so each direction takes several receives and sends
+	let data: Vec<u8> = (0..300_000u32).map(|i| i as u8).collect();
+	let (mut rx, mut tx) = client.split();
+	let mut echoed = Vec::new();
+	let (_, read) = tokio::join!(
+		async {
+			tx.write_all(&data).await.unwrap();
+			tx.shutdown().await.unwrap();
+		},
+		rx.read_to_end(&mut echoed),
+	);
+	read.unwrap();
+	assert_eq!(echoed, data);
+	// Closing the downstream closes the upstream, and ends the copy
+	copy.await.unwrap().unwrap();
+	echo.await.unwrap();
+	assert_eq!(stats.load(), (300_000, 300_000));
+}
+
+#[cfg(target_family = "unix")]
+#[tokio::test]
+async fn unix_socket_listener() {
+	use std::os::unix::fs::PermissionsExt;
+
+	let dir = tempfile::tempdir().unwrap();
+	let path = dir.path().join("gateway.sock");
+	// A socket left behind by a previous run is replaced
+	drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+	let listener = super::BindListener::unix(&types::agent::UnixSocket {
+		path: path.clone(),
+		mode: Some(0o600),
+	})
+	.unwrap();
+	let mode = fs_err::metadata(&path).unwrap().permissions().mode();
+	assert_eq!(mode & 0o777, 0o600);
+
+	let _client = tokio::net::UnixStream::connect(&path).await.unwrap();
+	let socket = listener.accept().await.unwrap();
+	assert!(socket.tcp().peer_addr.ip().is_loopback());
+
+	// Anything else is not
+	let file = dir.path().join("file");
+	fs_err::write(&file, "").unwrap();
+	let res = super::BindListener::unix(&types::agent::UnixSocket {
+		path: file,
+		mode: None,
+	});
+	assert!(res.is_err());
+}
+
 #[tokio::test]
 async fn direct_response() {
 	let mock = simple_mock().await;
@@ -257,6 +503,9 @@ async fn tls_termination() {
 					cipher_suites: None,
 					min_tls_version: None,
 					max_tls_version: None,
+					alpn: None,
+					session_tickets: None,
+					session_ticket_keys: None,
 				}
 				.try_into()
 				.unwrap(),
@@ -266,6 +515,7 @@ async fn tls_termination() {
 		}]),
 		protocol: BindProtocol::tls,
 		tunnel_protocol: Default::default(),
+		unix_socket: None,
 	};
 
 	let t = setup_proxy_test("{}")
@@ -286,6 +536,90 @@ async fn tls_termination() {
 	assert_matches!(res, Err(_));
 }
 
+#[tokio::test]
+async fn tls_passthrough() {
+	use tokio::io::AsyncReadExt;
+
+	// Each backend reports the first byte it receives, which is the start of the client's TLS
+	// handshake if the stream was passed through untouched.
+	let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+	let backend = |name: &'static str| {
+		let tx = tx.clone();
+		async move {
+			let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+			let addr = listener.local_addr().unwrap();
+			tokio::spawn(async move {
+				loop {
+					let (mut stream, _) = listener.accept().await.unwrap();
+					let first = stream.read_u8().await.unwrap();
+					tx.send((name, first)).unwrap();
+				}
+			});
+			addr
+		}
+	};
+	let route = |name: &str, hostnames: &[&str], addr: SocketAddr| {
+		let mut route = basic_named_tcp_route(strng::format!("/{addr}"));
+		route.key = strng::new(name);
+		route.name.name = strng::new(name);
+		route.hostnames = hostnames.iter().map(strng::new).collect();
+		route
+	};
+	let exact = backend("exact").await;
+	let wildcard = backend("wildcard").await;
+	let fallback = backend("fallback").await;
+	let bind = Bind {
+		key: BIND_KEY,
+		// not really used
+		address: "127.0.0.1:0".parse().unwrap(),
+		listeners: ListenerSet::from_list([Listener {
+			key: LISTENER_KEY,
+			name: Default::default(),
+			hostname: Default::default(),
+			protocol: ListenerProtocol::TLS(None),
+			tcp_routes: TCPRouteSet::from_list(vec![
+				route("exact", &["a.example.com"], exact),
+				route("wildcard", &["*.example.com"], wildcard),
+				route("fallback", &[], fallback),
+			]),
+			routes: Default::default(),
+		}]),
+		protocol: BindProtocol::tls,
+		tunnel_protocol: Default::default(),
+		unix_socket: None,
+	};
+	let t = setup_proxy_test("{}")
+		.unwrap()
+		.with_backend(exact)
+		.with_backend(wildcard)
+		.with_backend(fallback)
+		.with_bind(bind);
+
+	let tls: http::backendtls::BackendTLS = http::backendtls::ResolvedBackendTLS {
+		insecure: true,
+		..Default::default()
+	}
+	.try_into()
+	.unwrap();
+	// An IP address is not a valid SNI, so none is sent.
+	for (server_name, want) in [
+		("a.example.com", "exact"),
+		("b.example.com", "wildcard"),
+		("127.0.0.1", "fallback"),
+	] {
+		let connect = tokio_rustls::TlsConnector::from(tls.base_config().config).connect(
+			rustls_pki_types::ServerName::try_from(server_name).unwrap(),
+			t.serve(BIND_KEY),
+		);
+		// The backends never answer the handshake, so it does not complete.
+		tokio::spawn(connect);
+		let (got, first) = rx.recv().await.unwrap();
+		assert_eq!(got, want, "{server_name}");
+		// A TLS handshake record
+		assert_eq!(first, 0x16, "{server_name}");
+	}
+}
+
 #[tokio::test]
 async fn tls_backend_connection() {
 	let (mock, certs) = tls_mock().await;
@@ -397,6 +731,47 @@ async fn tls_backend_http2_version() {
 	assert_eq!(read_body(res.into_body()).await.version, Version::HTTP_2);
 }
 
+#[tokio::test]
+async fn tls_backend_http2_settings() {
+	let (mock, certs) = tls_mock().await;
+	let backend_tls = http::backendtls::ResolvedBackendTLS {
+		root: Some(certs.root_cert.pem().into_bytes()),
+		hostname: Some("localhost".to_string()),
+		..Default::default()
+	}
+	.try_into()
+	.unwrap();
+	let backend_http = backend::HTTP {
+		version: Some(Version::HTTP_2),
+		http2_window_size: Some(1024 * 1024),
+		http2_connection_window_size: Some(4 * 1024 * 1024),
+		http2_frame_size: Some(32 * 1024),
+		http2_keepalive_interval: Some(Duration::from_secs(10)),
+		http2_keepalive_timeout: Some(Duration::from_secs(5)),
+		..Default::default()
+	};
+
+	let t = setup_proxy_test("{}")
+		.unwrap()
+		.with_raw_backend(BackendWithPolicies {
+			backend: Backend::Opaque(
+				ResourceName::new(strng::format!("{}", mock.address()), "".into()),
+				Target::Address(*mock.address()),
+			),
+			inline_policies: vec![
+				BackendPolicy::BackendTLS(backend_tls),
+				BackendPolicy::HTTP(backend_http),
+			],
+		})
+		.with_bind(simple_bind(basic_route(*mock.address())));
+
+	for _ in 0..2 {
+		let res = send_http_version(&t, Version::HTTP_2).await;
+		assert_eq!(res.status(), 200);
+		assert_eq!(read_body(res.into_body()).await.version, Version::HTTP_2);
+	}
+}
+
 #[tokio::test]
 async fn tls_backend_http1_version() {
 	let (mock, certs) = tls_mock().await;
@@ -6,6 +6,7 @@ use ::http::uri::PathAndQuery;
 use ::http::{HeaderMap, header};
 use anyhow::anyhow;
 use futures_util::FutureExt;
+use futures_util::future::Either;
 use headers::HeaderMapExt;
 use hyper::body::Incoming;
 use hyper::upgrade::OnUpgrade;
@@ -23,7 +24,7 @@ use crate::http::filters::{AutoHostname, BackendRequestTimeout};
 use crate::http::transformation_cel::Transformation;
 use crate::http::{
 	Authority, HeaderName, HeaderValue, PolicyResponse, Request, Response, Scheme, StatusCode, Uri,
-	auth, filters, merge_in_headers, retry,
+	auth, filters, keypool, merge_in_headers, retry,
 };
 use crate::llm::{InputFormat, LLMRequest, RequestResult, RouteType};
 use crate::proxy::{ProxyError, ProxyResponse, ProxyResponseReason, resolve_simple_backend};
@@ -31,9 +32,15 @@ use crate::store::{
 	BackendPolicies, FrontendPolices, GatewayPolicies, LLMRequestPolicies, LLMResponsePolicies,
 	RoutePath,
 };
+use crate::telemetry::events::{self, EventKind};
 use crate::telemetry::log;
 use crate::telemetry::log::{AsyncLog, DropOnLog, LogBody, RequestLog};
+use crate::telemetry::metrics::{
+	BackendConcurrencyLabels, FallbackLabels, HedgeLabels, QueueRejectionLabels,
+	StructuredOutputLabels,
+};
 use crate::telemetry::trc::TraceParent;
+use crate::telemetry::{llmlog, slowlog};
 use crate::transport::stream::{Extension, TCPConnectionInfo, TLSConnectionInfo};
 use crate::types::{backend, frontend};
 use crate::{ProxyInputs, store, *};
@@ -83,6 +90,12 @@ async fn apply_request_policies(
 	req: &mut Request,
 	response_policies: &mut ResponsePolicies,
 ) -> Result<(), ProxyResponse> {
+	log.access_log = policies.access_log.clone();
+	log.slow_request = slowlog::capture(policies.slow_request_log.as_ref(), req.headers());
+	log.llm_capture = llmlog::capture(policies.llm_log.as_ref());
+	if let Some(f) = &policies.ip_filter {
+		f.apply(req)?;
+	}
 	if let Some(j) = &policies.jwt {
 		j.apply(log, req)
 			.await
@@ -94,6 +107,9 @@ async fn apply_request_policies(
 	if let Some(b) = &policies.api_key {
 		b.apply(log, req).await?;
 	}
+	if let Some(s) = &policies.request_signature {
+		s.apply(req).await?;
+	}
 
 	let mut exec = once_cell::sync::OnceCell::new();
 
@@ -120,12 +136,25 @@ async fn apply_request_policies(
 			.map_err(|_| ProxyResponse::from(ProxyError::AuthorizationFailed))?;
 	}
 
+	if let Some(o) = &policies.opa {
+		o.check(client.clone(), req)
+			.await?
+			.apply(response_policies.headers())?;
+	}
+
 	for lrl in &policies.local_rate_limit {
 		lrl.check_request()?;
 	}
 
+	if let Some(q) = &policies.quota {
+		q.apply(&client.inputs.quotas, req)?
+			.apply(response_policies.headers())?;
+	}
+
 	if let Some(rrl) = &policies.remote_rate_limit {
-		rrl.check(client, req, build_ctx(&exec, log)?).await?
+		rrl
+			.check(client.clone(), req, build_ctx(&exec, log)?)
+			.await?
 	} else {
 		http::PolicyResponse::default()
 	}
@@ -150,6 +179,15 @@ async fn apply_request_policies(
 			.map_err(|_| ProxyError::CsrfValidationFailed)?
 			.apply(response_policies.headers())?;
 	}
+	if let Some(dlp) = &policies.dlp {
+		dlp
+			.apply_request(req, &log.metrics)
+			.await?
+			.apply(response_policies.headers())?;
+	}
+	if let Some(st) = &policies.session_token {
+		st.apply(client.inputs.cfg.token_issuer.as_deref(), req)?;
+	}
 	if let Some(rhm) = &policies.request_header_modifier {
 		rhm.apply(req.headers_mut()).map_err(ProxyError::from)?;
 	}
@@ -181,6 +219,13 @@ async fn apply_request_policies(
 			.with_response(dr.apply().map_err(ProxyError::from)?)
 			.apply(response_policies.headers())?;
 	}
+	if let Some(b) = &policies.llm_batch
+		&& let Some(resp) = b.apply(&client, req).await?
+	{
+		PolicyResponse::default()
+			.with_response(resp)
+			.apply(response_policies.headers())?;
+	}
 
 	// Mirror, timeout, and retry are handled separately.
 
@@ -199,7 +244,7 @@ async fn apply_backend_policies(
 		backend_auth,
 		a2a,
 		http,
-		// Doesn't currently have any options to set, todo
+		// Applied elsewhere
 		tcp: _,
 		// Applied elsewhere
 		llm_provider: _,
@@ -270,6 +315,9 @@ async fn apply_gateway_policies(
 	ext_proc: Option<&mut ExtProcRequest>,
 	response_headers: &mut HeaderMap,
 ) -> Result<(), ProxyResponse> {
+	if let Some(f) = &policies.ip_filter {
+		f.apply(req)?;
+	}
 	if let Some(j) = &policies.jwt {
 		j.apply(log, req)
 			.await
@@ -349,12 +397,26 @@ async fn apply_llm_request_policies(
 	Ok(store::LLMResponsePolicies {
 		local_rate_limit: policies.local_rate_limit.clone(),
 		remote_rate_limit: response,
+		quota: req.extensions().get::<http::quota::Account>().cloned(),
 		prompt_guard: policies
 			.llm
 			.as_deref()
 			.and_then(|llm| llm.prompt_guard.as_ref())
 			.map(|g| g.response.clone())
 			.unwrap_or_default(),
+		semantic_cache: req
+			.extensions_mut()
+			.remove::<llm::policy::semantic_cache::Pending>(),
+		response_cache: req
+			.extensions_mut()
+			.remove::<llm::policy::response_cache::Pending>(),
+		stream_rewrite: policies
+			.llm
+			.as_deref()
+			.and_then(|llm| llm.stream_rewrite.clone()),
+		pii_restore: req
+			.extensions_mut()
+			.remove::<llm::policy::pii_redaction::Redacted>(),
 	})
 }
 
@@ -397,7 +459,23 @@ impl HTTPProxy {
 		if let Some(lp) = &policies.access_log {
 			apply_logging_policy_to_log(&mut log, lp);
 		}
+		// Assign the request ID before anything else sees the request, so it is visible to policies,
+		// backends, and logs.
+		let request_id = policies.request_id.as_ref().map(|p| {
+			let id = p.apply(tcp.peer_addr.ip(), req.headers_mut());
+			log.request_id = Some(id.clone());
+			(p, id)
+		});
 		let mut log: DropOnLog = log.into();
+		// gRPC clients need a gRPC status on the errors we generate, too.
+		let grpc = http::grpc::is_grpc(req.headers());
+		let error_response = |e: ProxyError| {
+			if grpc {
+				http::grpc::error_response(e)
+			} else {
+				e.into_response()
+			}
+		};
 
 		// Setup ResponsePolicies outside of proxy_internal, so we have can unconditionally run them even on errors
 		// or direct responses
@@ -425,7 +503,7 @@ impl HTTPProxy {
 			Err(e) => e.as_reason(),
 		};
 		let mut resp = ret.unwrap_or_else(|err| match err {
-			ProxyResponse::Error(e) => e.into_response(),
+			ProxyResponse::Error(e) => error_response(e),
 			ProxyResponse::DirectResponse(dr) => *dr,
 		});
 
@@ -446,17 +524,26 @@ impl HTTPProxy {
 		{
 			Ok(_) => resp,
 			Err(e) => match e {
-				ProxyResponse::Error(e) => e.into_response(),
+				ProxyResponse::Error(e) => error_response(e),
 				ProxyResponse::DirectResponse(dr) => *dr,
 			},
 		};
 
+		// Added last so it is present on error and direct responses as well.
+		if let Some((p, id)) = &request_id {
+			p.apply_response(id, resp.headers_mut());
+		}
+
 		// Pass the log into the body so it finishes once the stream is entirely complete.
 		// We will also record trailer info there.
 		log.with(|l| {
 			l.status = Some(resp.status());
 			l.reason = Some(reason);
+			maybe_set_grpc_status(&l.grpc_status, resp.headers());
 			l.retry_after = http::outlierdetection::retry_after(resp.status(), resp.headers());
+			if let Some(s) = l.slow_request.as_mut() {
+				s.record_response(l.start.elapsed(), resp.headers());
+			}
 		});
 
 		if resp.status() == StatusCode::SWITCHING_PROTOCOLS {
@@ -506,6 +593,11 @@ impl HTTPProxy {
 		);
 		log.version = Some(req.version());
 
+		// Buffering a large body could push the gateway past its memory limit, so reject it upfront.
+		if crate::memory::shed_request(req.body()) {
+			return Err(ProxyError::MemoryPressure.into());
+		}
+
 		// Record request now. We may do it later as well, after we have more expressions registered.
 		Self::apply_request_to_cel(log, &mut req).await;
 
@@ -532,7 +624,7 @@ impl HTTPProxy {
 		let trace_sampled = log.trace_sampled(trace_parent.as_ref());
 
 		// Use dynamic tracer from frontend policy if available, otherwise use static tracer
-		if trace_sampled {
+		if trace_sampled && crate::overload::tracing_enabled() {
 			log.tracer = if let Some(tp) = frontend_policies.tracing.as_deref() {
 				debug!(
 					resources_count=%tp.config.resources.len(),
@@ -570,7 +662,7 @@ impl HTTPProxy {
 					ns
 				},
 			};
-			ns.insert_header(&mut req);
+			ns.insert_headers(&mut req, &self.inputs.cfg.tracing.propagation);
 			req.extensions_mut().insert(ns.clone());
 			log.outgoing_span = Some(ns);
 		}
@@ -658,6 +750,7 @@ impl HTTPProxy {
 		// backend_response_header is set much later
 		response_policies.timeout = route_policies.timeout.clone();
 		response_policies.transformation = route_policies.transformation.clone();
+		response_policies.dlp = route_policies.dlp.clone();
 		response_policies.gateway_transformation = gateway_policies.transformation.clone();
 		response_policies.ext_proc = maybe_ext_proc;
 		response_policies.gateway_ext_proc = maybe_gateway_ext_proc;
@@ -685,7 +778,24 @@ impl HTTPProxy {
 			log.backend_protocol = Some(bp)
 		}
 
-		let (head, body) = req.into_parts();
+		if let Some(experiment) = &route_policies.model_experiment {
+			let key = log
+				.cel
+				.ctx_borrow()
+				.build()
+				.ok()
+				.and_then(|exec| experiment.key(&exec));
+			if let Some(assignment) = experiment.apply(key.as_deref(), &mut req).await? {
+				if let Ok(v) = HeaderValue::from_str(assignment.variant.as_str()) {
+					response_policies
+						.headers()
+						.insert(http::x_headers::X_EXPERIMENT_VARIANT, v);
+				}
+				log.experiment = Some(assignment);
+			}
+		}
+
+		let (head, mut body) = req.into_parts();
 		for mirror in route_policies
 			.request_mirror
 			.iter()
@@ -712,10 +822,122 @@ impl HTTPProxy {
 
 		const MAX_BUFFERED_BYTES: usize = 64 * 1024;
 		let retries = route_policies.retry.clone();
+		let hedging = route_policies.hedging.clone();
+		let model_fallback = route_policies.model_fallback.clone();
+		let structured_output = route_policies.structured_output.clone();
+		let priority = route_policies
+			.request_queue
+			.as_ref()
+			.and_then(|q| {
+				let exec = log.cel.ctx_borrow().build().ok()?;
+				Some(q.priority(&exec))
+			})
+			.unwrap_or_default();
+		if crate::overload::shed_request(priority) {
+			return Err(ProxyError::Overloaded.into());
+		}
+		let queued = match route_policies.request_queue.clone() {
+			Some(q) => {
+				let permit = match q.acquire(priority).await {
+					Ok(permit) => permit,
+					Err(e) => {
+						self
+							.inputs
+							.metrics
+							.request_queue_rejections
+							.get_or_create(&QueueRejectionLabels {
+								route: log.route_name.as_ref().map(|r| r.as_route_name()).into(),
+								reason: match e {
+									http::queue::Error::Full => "full",
+									http::queue::Error::Timeout => "timeout",
+								}
+								.into(),
+							})
+							.inc();
+						return Err(ProxyError::Queue(e).into());
+					},
+				};
+				Some((q, permit))
+			},
+			None => None,
+		};
+		// Release the queue permit, if any, once the backend responds.
+		let complete = move |res: Result<Response, ProxyResponse>| match queued {
+			Some((q, permit)) => q.complete(permit, res),
+			None => res,
+		};
 		let late_route_policies: Arc<LLMRequestPolicies> = Arc::new(route_policies.into());
+		if let Some(structured_output) = structured_output {
+			// Repairs resend the request themselves, so take the place of retries.
+			let req = Request::from_parts(head, body);
+			let res = self
+				.attempt_structured_output(
+					log,
+					&mut req_upgrade,
+					late_route_policies,
+					&selected_backend,
+					backend_policies,
+					response_policies,
+					&structured_output,
+					model_fallback.as_ref(),
+					req,
+				)
+				.await;
+			return complete(res);
+		}
+		if let Some(fallback) = model_fallback {
+			// Fallback chains resend the request themselves, so take the place of retries.
+			let req = Request::from_parts(head, body);
+			let res = self
+				.attempt_model_fallback(
+					log,
+					&mut req_upgrade,
+					late_route_policies,
+					&selected_backend,
+					backend_policies,
+					response_policies,
+					&fallback,
+					req,
+				)
+				.await;
+			return complete(res);
+		}
+		if let Some(hedging) = hedging.filter(|h| {
+			h.applies(&head.method)
+				&& req_upgrade.is_none()
+				// MCP and LLM backends log each request as it is processed, so cannot send it twice.
+				&& matches!(
+					selected_backend.backend.backend,
+					Backend::Service(..) | Backend::Opaque(..)
+				)
+		}) {
+			// Both attempts send the body, so it must be buffered.
+			match http::retry::ReplayBody::try_new(body, MAX_BUFFERED_BYTES) {
+				Ok(body) => {
+					let started = Instant::now();
+					let res = self
+						.attempt_hedged(
+							log,
+							late_route_policies,
+							&selected_backend,
+							backend_policies,
+							response_policies,
+							&hedging,
+							head,
+							body,
+						)
+						.await;
+					record_attempt(log, started, &res);
+					return complete(res);
+				},
+				Err(b) => {
+					debug!("initial body is too large to hedge, disabling hedging");
+					body = b;
+				},
+			}
+		}
 		// attempts is the total number of attempts, not the retries
 		let attempts = retries.as_ref().map(|r| r.attempts.get() + 1).unwrap_or(1);
-		let retry_backoff = retries.as_ref().and_then(|r| r.backoff);
 		let request_timeout = response_policies
 			.timeout
 			.as_ref()
@@ -736,7 +958,8 @@ impl HTTPProxy {
 				trace!("no retries");
 				// no retries at all, just send the request as normal
 				let req = Request::from_parts(head, http::Body::new(body));
-				return self
+				let started = Instant::now();
+				let res = self
 					.attempt_upstream(
 						log,
 						&mut req_upgrade,
@@ -747,9 +970,13 @@ impl HTTPProxy {
 						req,
 					)
 					.await;
+				record_attempt(log, started, &res);
+				return complete(res);
 			},
 		};
 		let mut last_res: Option<Result<Response, ProxyResponse>> = None;
+		// Time spent waiting between attempts, counted against the retry budget
+		let mut waited = Duration::ZERO;
 		for n in 0..attempts {
 			let last = n == attempts - 1;
 			let this = next.take().expect("next should be set");
@@ -757,7 +984,7 @@ impl HTTPProxy {
 			if matches!(this.is_capped(), None | Some(true)) {
 				// This could be either too much buffered, or it could mean we got a response before we read the request body.
 				debug!("buffered too much to attempt a retry");
-				return last_res.expect("should only be capped if we had a previous attempt");
+				return complete(last_res.expect("should only be capped if we had a previous attempt"));
 			}
 			if !last {
 				// Stop cloning on our last
@@ -773,6 +1000,7 @@ impl HTTPProxy {
 				);
 			}
 			let req = Request::from_parts(head, http::Body::new(this));
+			let started = Instant::now();
 			let res = self
 				.attempt_upstream(
 					log,
@@ -784,25 +1012,45 @@ impl HTTPProxy {
 					req,
 				)
 				.await;
-			if last || !should_retry(&res, retries.as_ref().unwrap()) {
+			record_attempt(log, started, &res);
+			let pol = retries.as_ref().unwrap();
+			let provider = log.llm_request.as_ref().map(|r| r.provider.clone());
+			if last || !should_retry(&res, pol, provider.as_deref()) {
 				if !last {
 					debug!("response not retry-able");
 				}
-				return res;
+				return complete(res);
 			}
+			let Some(delay) = pol.delay(res.as_ref().ok().map(|r| (r.status(), r.headers())), waited)
+			else {
+				debug!(?waited, "retry budget exhausted");
+				return complete(res);
+			};
 			debug!(
-				backoff=?retry_backoff,
+				?delay,
 				"attempting another retry, last result was {} {:?}",
 				res.is_err(),
 				res.as_ref().map(|r| r.status())
 			);
+			events::publish(EventKind::RequestRetried {
+				route: log
+					.route_name
+					.as_ref()
+					.map(|r| r.as_route_name().to_string()),
+				backend: log
+					.backend_info
+					.as_ref()
+					.map(|b| b.backend_name.to_string()),
+				attempt: n + 1,
+			});
 			last_res = Some(res);
-			if let Some(bo) = retry_backoff {
+			if !delay.is_zero() {
+				waited += delay;
 				let fut = if let Some(request_timeout) = request_timeout {
 					let deadline = tokio::time::Instant::from_std(log.start + request_timeout);
-					tokio::time::timeout_at(deadline, tokio::time::sleep(bo)).await
+					tokio::time::timeout_at(deadline, tokio::time::sleep(delay)).await
 				} else {
-					tokio::time::sleep(bo).await;
+					tokio::time::sleep(delay).await;
 					Ok(())
 				};
 				fut.map_err(|_| ProxyError::RequestTimeout)?
@@ -811,6 +1059,199 @@ impl HTTPProxy {
 		unreachable!()
 	}
 
+	/// Send the request, validating the response against the structured output schema and resending
+	/// it with a repair prompt while it does not match.
+	#[allow(clippy::too_many_arguments)]
+	async fn attempt_structured_output(
+		&self,
+		log: &mut RequestLog,
+		req_upgrade: &mut Option<RequestUpgrade>,
+		route_policies: Arc<store::LLMRequestPolicies>,
+		selected_backend: &RouteBackend,
+		backend_policies: BackendPolicies,
+		response_policies: &mut ResponsePolicies,
+		structured_output: &llm::structured_output::Policy,
+		fallback: Option<&llm::fallback::Policy>,
+		mut req: Request,
+	) -> Result<Response, ProxyResponse> {
+		let mut body = http::inspect_body(&mut req)
+			.await
+			.map_err(ProxyError::Processing)?;
+		let streaming = llm::structured_output::is_streaming(&body);
+		let (mut head, _) = req.into_parts();
+		head.headers.remove(header::CONTENT_LENGTH);
+		let mut attempt = 0;
+		loop {
+			let req = Request::from_parts(head.clone(), http::Body::from(body.clone()));
+			let res = match fallback {
+				Some(fallback) => {
+					self
+						.attempt_model_fallback(
+							log,
+							req_upgrade,
+							route_policies.clone(),
+							selected_backend,
+							backend_policies.clone(),
+							response_policies,
+							fallback,
+							req,
+						)
+						.await
+				},
+				None => {
+					let started = Instant::now();
+					let res = self
+						.attempt_upstream(
+							log,
+							req_upgrade,
+							route_policies.clone(),
+							selected_backend,
+							backend_policies.clone(),
+							response_policies,
+							req,
+						)
+						.await;
+					record_attempt(log, started, &res);
+					res
+				},
+			};
+			let mut resp = match res {
+				Ok(resp) if !streaming && resp.status().is_success() => resp,
+				res => return res,
+			};
+			let Ok(resp_body) = http::inspect_response_body(&mut resp).await else {
+				return Ok(resp);
+			};
+			let (format, output, error) = match structured_output.check(&resp_body) {
+				llm::structured_output::Check::Valid => {
+					self.record_structured_output(log, if attempt == 0 { "valid" } else { "repaired" });
+					return Ok(resp);
+				},
+				llm::structured_output::Check::Skipped => return Ok(resp),
+				llm::structured_output::Check::Invalid {
+					format,
+					output,
+					error,
+				} => (format, output, error),
+			};
+			debug!(attempt, %error, "response did not match structured output schema");
+			let repaired = (attempt < structured_output.max_retries)
+				.then(|| structured_output.repair(&body, &output, &error))
+				.flatten();
+			let Some(repaired) = repaired else {
+				self.record_structured_output(log, "invalid");
+				return Ok(structured_output.error_response(format, &error));
+			};
+			body = repaired;
+			attempt += 1;
+		}
+	}
+
+	fn record_structured_output(&self, log: &RequestLog, result: &str) {
+		self
+			.inputs
+			.metrics
+			.gen_ai_structured_output_validations
+			.get_or_create(&StructuredOutputLabels {
+				route: log.route_name.as_ref().map(|r| r.as_route_name()).into(),
+				result: result.into(),
+			})
+			.inc();
+	}
+
+	/// Send the request to each model in its fallback chain in turn, until one succeeds or the chain
+	/// is exhausted.
+	#[allow(clippy::too_many_arguments)]
+	async fn attempt_model_fallback(
+		&self,
+		log: &mut RequestLog,
+		req_upgrade: &mut Option<RequestUpgrade>,
+		route_policies: Arc<store::LLMRequestPolicies>,
+		selected_backend: &RouteBackend,
+		backend_policies: BackendPolicies,
+		response_policies: &mut ResponsePolicies,
+		fallback: &llm::fallback::Policy,
+		mut req: Request,
+	) -> Result<Response, ProxyResponse> {
+		let body = http::inspect_body(&mut req)
+			.await
+			.map_err(ProxyError::Processing)?;
+		let requested = llm::fallback::requested_model(&body);
+		let chain = fallback.chain(requested.as_deref());
+		if chain.is_empty() {
+			let started = Instant::now();
+			let res = self
+				.attempt_upstream(
+					log,
+					req_upgrade,
+					route_policies,
+					selected_backend,
+					backend_policies,
+					response_policies,
+					req,
+				)
+				.await;
+			record_attempt(log, started, &res);
+			return res;
+		}
+		let (head, _) = req.into_parts();
+		let mut last_res = None;
+		for (i, link) in chain.iter().enumerate() {
+			let body = if requested.as_deref() == Some(link.model.as_str()) {
+				body.clone()
+			} else {
+				llm::fallback::set_model(&body, &link.model).map_err(ProxyError::Processing)?
+			};
+			let mut reason = None;
+			for _ in 0..link.attempts.get() {
+				let req = Request::from_parts(head.clone(), http::Body::from(body.clone()));
+				let started = Instant::now();
+				let call = self.attempt_upstream(
+					log,
+					req_upgrade,
+					route_policies.clone(),
+					selected_backend,
+					backend_policies.clone(),
+					response_policies,
+					req,
+				);
+				let res = match link.timeout {
+					Some(timeout) => tokio::time::timeout(timeout, call)
+						.await
+						.unwrap_or_else(|_| Err(ProxyError::RequestTimeout.into())),
+					None => call.await,
+				};
+				record_attempt(log, started, &res);
+				reason = llm::fallback::Reason::of(&res);
+				if reason.is_none() {
+					return res;
+				}
+				last_res = Some(res);
+			}
+			let (Some(reason), Some(next)) = (reason, chain.get(i + 1)) else {
+				continue;
+			};
+			debug!(
+				from=%link.model,
+				to=%next.model,
+				reason=reason.as_str(),
+				"falling back to next model"
+			);
+			self
+				.inputs
+				.metrics
+				.gen_ai_fallbacks
+				.get_or_create(&FallbackLabels {
+					route: log.route_name.as_ref().map(|r| r.as_route_name()).into(),
+					from_model: link.model.clone().into(),
+					to_model: next.model.clone().into(),
+					reason: reason.as_str().into(),
+				})
+				.inc();
+		}
+		last_res.expect("fallback chain has at least one attempt")
+	}
+
 	fn detect_misdirected(
 		log: &RequestLog,
 		bind: Arc<Bind>,
@@ -866,15 +1307,7 @@ impl HTTPProxy {
 		if needs_body && let Ok(body) = crate::http::inspect_body(req).await {
 			log.cel.ctx().with_request_body(body);
 		}
-		if let Some(claims) = req.extensions().get::<crate::http::jwt::Claims>() {
-			log.cel.ctx().with_jwt(claims);
-		}
-		if let Some(claims) = req.extensions().get::<crate::http::apikey::Claims>() {
-			log.cel.ctx().with_api_key(claims);
-		}
-		if let Some(claims) = req.extensions().get::<crate::http::basicauth::Claims>() {
-			log.cel.ctx().with_basic_auth(claims);
-		}
+		apply_identity_to_cel(log.cel.ctx(), req);
 	}
 
 	#[allow(clippy::too_many_arguments)]
@@ -886,27 +1319,18 @@ impl HTTPProxy {
 		selected_backend: &RouteBackend,
 		backend_policies: BackendPolicies,
 		response_policies: &mut ResponsePolicies,
-		mut req: Request,
+		req: Request,
 	) -> Result<Response, ProxyResponse> {
-		if let Some(backend_timeout) = response_policies
-			.timeout
-			.as_ref()
-			.and_then(|t| t.backend_request_timeout)
-		{
-			req
-				.extensions_mut()
-				.insert(BackendRequestTimeout(backend_timeout));
-		}
-		let call = make_backend_call(
-			self.inputs.clone(),
-			route_policies.clone(),
-			&selected_backend.backend.backend,
-			backend_policies,
-			req,
-			Some(log),
-			response_policies,
-		)
-		.await?;
+		let call = self
+			.start_upstream(
+				Some(&mut *log),
+				route_policies,
+				selected_backend,
+				backend_policies,
+				response_policies,
+				req,
+			)
+			.await?;
 
 		let timeout = response_policies
 			.timeout
@@ -945,6 +1369,139 @@ impl HTTPProxy {
 		Ok(resp)
 	}
 
+	/// Apply the backend policies to the request, returning the call to the backend.
+	async fn start_upstream(
+		&self,
+		log: Option<&mut RequestLog>,
+		route_policies: Arc<store::LLMRequestPolicies>,
+		selected_backend: &RouteBackend,
+		backend_policies: BackendPolicies,
+		response_policies: &mut ResponsePolicies,
+		mut req: Request,
+	) -> Result<Pin<Box<dyn Future<Output = Result<Response, ProxyError>> + Send>>, ProxyResponse> {
+		if let Some(backend_timeout) = response_policies
+			.timeout
+			.as_ref()
+			.and_then(|t| t.backend_request_timeout)
+		{
+			req
+				.extensions_mut()
+				.insert(BackendRequestTimeout(backend_timeout));
+		}
+		make_backend_call(
+			self.inputs.clone(),
+			route_policies,
+			&selected_backend.backend.backend,
+			backend_policies,
+			req,
+			log,
+			response_policies,
+		)
+		.await
+	}
+
+	/// Send the request, and a second attempt if the first has not responded within the hedging
+	/// delay. The first successful response is returned, and the other attempt is cancelled.
+	#[allow(clippy::too_many_arguments)]
+	async fn attempt_hedged(
+		&self,
+		log: &mut RequestLog,
+		route_policies: Arc<store::LLMRequestPolicies>,
+		selected_backend: &RouteBackend,
+		backend_policies: BackendPolicies,
+		response_policies: &mut ResponsePolicies,
+		hedging: &http::hedging::Policy,
+		head: ::http::request::Parts,
+		body: http::retry::ReplayBody<http::Body>,
+	) -> Result<Response, ProxyResponse> {
+		let started = Instant::now();
+		let hedge_body = body.clone();
+		let req = Request::from_parts(head.clone(), http::Body::new(body));
+		let mut first = self
+			.start_upstream(
+				Some(&mut *log),
+				route_policies.clone(),
+				selected_backend,
+				backend_policies.clone(),
+				response_policies,
+				req,
+			)
+			.await?;
+		let delay = hedging.delay();
+		let hedged = async {
+			if let Ok(res) = tokio::time::timeout(delay, &mut first).await {
+				return (res, None);
+			}
+			if hedge_body.is_capped() != Some(false) {
+				// The first attempt has not finished sending the body, so it cannot be replayed.
+				return (first.await, None);
+			}
+			debug!(?delay, "sending hedged attempt");
+			let req = Request::from_parts(head, http::Body::new(hedge_body));
+			// The second attempt applies the same policies, so only the first attempt's response
+			// policies are kept.
+			let mut hedge_response_policies = ResponsePolicies {
+				timeout: response_policies.timeout.clone(),
+				..Default::default()
+			};
+			let second = match self
+				.start_upstream(
+					None,
+					route_policies,
+					selected_backend,
+					backend_policies,
+					&mut hedge_response_policies,
+					req,
+				)
+				.await
+			{
+				Ok(second) => second,
+				Err(e) => {
+					debug!("failed to send hedged attempt: {e}");
+					return (first.await, None);
+				},
+			};
+			// Return the first successful response, or the other attempt's if one fails.
+			let (res, other, hedge_first) = match futures_util::future::select(first, second).await {
+				Either::Left((res, other)) => (res, other, false),
+				Either::Right((res, other)) => (res, other, true),
+			};
+			if res.as_ref().is_ok_and(|r| !r.status().is_server_error()) {
+				(res, Some(hedge_first))
+			} else {
+				(other.await, Some(!hedge_first))
+			}
+		};
+
+		let timeout = response_policies
+			.timeout
+			.as_ref()
+			.and_then(|t| t.request_timeout);
+		let (res, hedge_won) = if let Some(timeout) = timeout {
+			let deadline = tokio::time::Instant::from_std(log.start + timeout);
+			tokio::time::timeout_at(deadline, hedged)
+				.await
+				.map_err(|_| ProxyError::RequestTimeout)?
+		} else {
+			hedged.await
+		};
+		if let Some(hedge_won) = hedge_won {
+			self
+				.inputs
+				.metrics
+				.hedged_requests
+				.get_or_create(&HedgeLabels {
+					route: log.route_name.as_ref().map(|r| r.as_route_name()).into(),
+					winner: if hedge_won { "hedge" } else { "original" }.into(),
+				})
+				.inc();
+		}
+		let resp = res?;
+		hedging.record(started.elapsed());
+		maybe_set_grpc_status(&log.grpc_status, resp.headers());
+		Ok(resp)
+	}
+
 	fn policy_client(&self) -> PolicyClient {
 		PolicyClient {
 			inputs: self.inputs.clone(),
@@ -1001,14 +1558,14 @@ async fn handle_upgrade(
 			let _ = agent_core::copy::copy_bidirectional(
 				&mut TokioIo::new(req),
 				&mut server,
-				&agent_core::copy::ConnectionResult {},
+				&agent_core::copy::ConnectionResult::default(),
 			)
 			.await;
 		} else {
 			let _ = agent_core::copy::copy_bidirectional(
 				&mut TokioIo::new(req),
 				&mut server,
-				&agent_core::copy::ConnectionResult {},
+				&agent_core::copy::ConnectionResult::default(),
 			)
 			.await;
 		}
@@ -1177,7 +1734,14 @@ async fn make_backend_call(
 
 	let backend_call = match backend {
 		Backend::AI(n, ai) => {
-			let (provider, handle) = ai.select_provider().ok_or(ProxyError::NoHealthyEndpoints)?;
+			let model = if ai.routes_by_model() {
+				requested_model(&mut req).await
+			} else {
+				None
+			};
+			let (provider, handle) = ai
+				.select_provider(model.as_deref())
+				.ok_or(ProxyError::NoHealthyEndpoints)?;
 			log.add(move |l| l.request_handle = Some(handle));
 			let sub_backend_name = BackendTargetRef::Backend {
 				name: n.name.as_ref(),
@@ -1266,6 +1830,14 @@ async fn make_backend_call(
 					.await
 			}));
 		},
+		Backend::Bridge(_, bridge) => {
+			let bridge = bridge.clone();
+			return Ok(Box::pin(async move {
+				crate::bridge::call(bridge, req)
+					.await
+					.map_err(ProxyError::Bridge)
+			}));
+		},
 		Backend::Invalid => return Err(ProxyResponse::from(ProxyError::BackendDoesNotExist)),
 	};
 
@@ -1293,6 +1865,21 @@ async fn make_backend_call(
 		route_policies.merge_backend_policies(backend_call.backend_policies.llm.clone());
 
 	set_backend_cel_context(&mut log);
+	if let Some(access) = backend_call
+		.backend_policies
+		.llm
+		.as_ref()
+		.and_then(|l| l.model_access.as_ref())
+	{
+		// Backend model access rules are only known once the backend is selected, so capture the
+		// identity they need now.
+		log.add(|l| {
+			for expr in access.expressions() {
+				l.cel.ctx().register_expression(expr)
+			}
+			apply_identity_to_cel(l.cel.ctx(), &req);
+		});
+	}
 
 	let (mut req, llm_response_policies, llm_request) =
 		if let Some(llm) = &backend_call.backend_policies.llm_provider {
@@ -1310,7 +1897,8 @@ async fn make_backend_call(
 				| RouteType::Messages
 				| RouteType::Responses
 				| RouteType::AnthropicTokenCount
-				| RouteType::Embeddings => {
+				| RouteType::Embeddings
+				| RouteType::Rerank => {
 					let r = match route_type {
 						RouteType::Completions => llm
 							.provider
@@ -1356,6 +1944,17 @@ async fn make_backend_call(
 							)
 							.await
 							.map_err(|e| ProxyError::Processing(e.into()))?,
+						RouteType::Rerank => llm
+							.provider
+							.process_rerank_request(
+								&backend_info,
+								llm_request_policies.llm.as_deref(),
+								req,
+								llm.tokenize,
+								&mut log,
+							)
+							.await
+							.map_err(|e| ProxyError::Processing(e.into()))?,
 						RouteType::AnthropicTokenCount => llm
 							.provider
 							.process_count_tokens_request(
@@ -1453,6 +2052,8 @@ async fn make_backend_call(
 		&mut req,
 	)
 	.await?;
+	// Key pools quarantine keys based on the upstream response
+	let key_lease = req.extensions_mut().remove::<keypool::KeyLease>();
 	let transport = build_transport(
 		&inputs,
 		&backend_call,
@@ -1465,6 +2066,31 @@ async fn make_backend_call(
 			.or(backend_call.http_version_override),
 	)
 	.await?;
+	if let Some(pool) = backend_call.backend_policies.connection_pool {
+		req.extensions_mut().insert(pool);
+	}
+	if let Some(tcp) = backend_call.backend_policies.tcp {
+		req.extensions_mut().insert(tcp);
+	}
+	// Take a slot under the backend's concurrency limit last, so it only covers the upstream call.
+	let concurrency_labels = BackendConcurrencyLabels {
+		backend: Some(backend.name()).into(),
+	};
+	let concurrency = match &backend_call.backend_policies.adaptive_concurrency {
+		Some(policy) => match policy.acquire(&backend.name()) {
+			Ok(permit) => Some(permit),
+			Err(e) => {
+				inputs
+					.metrics
+					.backend_concurrency_rejections
+					.get_or_create(&concurrency_labels)
+					.inc();
+				return Err(ProxyError::AdaptiveConcurrency(e).into());
+			},
+		},
+		None => None,
+	};
+	let metrics = inputs.metrics.clone();
 	let call = client::Call {
 		req,
 		target: backend_call.target,
@@ -1474,11 +2100,25 @@ async fn make_backend_call(
 	let llm_response_log = log.as_ref().map(|l| l.llm_response.clone());
 	let include_completion_in_log = log
 		.as_ref()
-		.map(|l| l.cel.cel_context.needs_llm_completion())
+		.map(|l| {
+			l.cel.cel_context.needs_llm_completion()
+				|| l.llm_capture.as_ref().is_some_and(|c| c.wants_completion())
+		})
 		.unwrap_or_default();
 	let a2a_type = response_policies.a2a_type.clone();
 	Ok(Box::pin(async move {
-		let mut resp = upstream.call(call).await?;
+		let res = upstream.call(call).await;
+		if let Some(permit) = concurrency {
+			let limit = permit.complete(&res);
+			metrics
+				.backend_concurrency_limit
+				.get_or_create(&concurrency_labels)
+				.set(limit as i64);
+		}
+		let mut resp = res?;
+		if let Some(lease) = &key_lease {
+			lease.record(&resp);
+		}
 		a2a::apply_to_response(
 			backend_call.backend_policies.a2a.as_ref(),
 			a2a_type,
@@ -1510,6 +2150,18 @@ async fn make_backend_call(
 	}))
 }
 
+fn apply_identity_to_cel(ctx: &mut cel::ContextBuilder, req: &Request) {
+	if let Some(claims) = req.extensions().get::<crate::http::jwt::Claims>() {
+		ctx.with_jwt(claims);
+	}
+	if let Some(claims) = req.extensions().get::<crate::http::apikey::Claims>() {
+		ctx.with_api_key(claims);
+	}
+	if let Some(claims) = req.extensions().get::<crate::http::basicauth::Claims>() {
+		ctx.with_basic_auth(claims);
+	}
+}
+
 fn set_backend_cel_context(log: &mut Option<&mut RequestLog>) {
 	log.add(|l| {
 		if let Some(bp) = l.backend_protocol
@@ -1626,20 +2278,23 @@ pub fn build_service_call(
 	})
 }
 
-fn should_retry(res: &Result<Response, ProxyResponse>, pol: &retry::Policy) -> bool {
+fn should_retry(
+	res: &Result<Response, ProxyResponse>,
+	pol: &retry::Policy,
+	provider: Option<&str>,
+) -> bool {
 	match res {
-		Ok(resp) => pol.codes.contains(&resp.status()),
+		Ok(resp) => {
+			pol.retryable_status(resp.status(), provider) || pol.retryable_grpc_status(resp.headers())
+		},
 		Err(ProxyResponse::Error(e)) => e.is_retryable(),
 		Err(ProxyResponse::DirectResponse(_)) => false,
 	}
 }
 
 pub fn maybe_set_grpc_status(status: &AsyncLog<u8>, headers: &HeaderMap) {
-	if let Some(s) = headers.get("grpc-status") {
-		let parsed = std::str::from_utf8(s.as_bytes())
-			.ok()
-			.and_then(|s| s.parse::<u8>().ok());
-		status.store(parsed);
+	if headers.contains_key(http::grpc::GRPC_STATUS) {
+		status.store(http::grpc::status(headers));
 	}
 }
 
@@ -1727,6 +2382,44 @@ fn get_upgrade_type(headers: &HeaderMap) -> Option<HeaderValue> {
 	}
 }
 
+/// The model an OpenAI or Anthropic style request asks for, from its JSON body.
+async fn requested_model(req: &mut Request) -> Option<String> {
+	#[derive(serde::Deserialize)]
+	struct ModelRequest {
+		model: Option<String>,
+	}
+	json::inspect_body::<ModelRequest>(req)
+		.await
+		.ok()
+		.and_then(|r| r.model)
+}
+
+/// Record an upstream attempt for the slow request log, if it applies to the request, and publish
+/// an event if it failed.
+fn record_attempt(log: &mut RequestLog, started: Instant, res: &Result<Response, ProxyResponse>) {
+	let endpoint = log.endpoint.as_ref().map(|e| e.to_string());
+	let status = res.as_ref().ok().map(|r| r.status());
+	if res.is_err() || status.is_some_and(|s| s.is_server_error()) {
+		events::publish(EventKind::UpstreamFailed {
+			route: log
+				.route_name
+				.as_ref()
+				.map(|r| r.as_route_name().to_string()),
+			backend: log
+				.backend_info
+				.as_ref()
+				.map(|b| b.backend_name.to_string()),
+			endpoint: endpoint.clone(),
+			status: status.map(|s| s.as_u16()),
+			error: res.as_ref().err().map(|e| e.to_string()),
+		});
+	}
+	let Some(capture) = log.slow_request.as_mut() else {
+		return;
+	};
+	capture.record_attempt(slowlog::Attempt::new(log.start, started, endpoint, res));
+}
+
 fn sensitive_headers(req: &mut Request) {
 	for (name, value) in req.headers_mut() {
 		if name == http::header::AUTHORIZATION {
@@ -1781,6 +2474,7 @@ struct ResponsePolicies {
 	backend_response_header: Option<filters::HeaderModifier>,
 	transformation: Option<Transformation>,
 	gateway_transformation: Option<Transformation>,
+	dlp: Option<http::dlp::Dlp>,
 	response_headers: HeaderMap,
 	ext_proc: Option<ExtProcRequest>,
 	gateway_ext_proc: Option<ExtProcRequest>,
@@ -1830,6 +2524,10 @@ impl ResponsePolicies {
 				PolicyResponse::default()
 			}
 			.apply(&mut self.response_headers)?;
+			// Scan the final response, which includes MCP tool results, for sensitive data.
+			if let Some(dlp) = &self.dlp {
+				dlp.apply_response(resp, log.metrics.clone()).await?;
+			}
 		}
 
 		merge_in_headers(Some(self.response_headers.clone()), resp.headers_mut());
@@ -1844,9 +2542,20 @@ pub struct PolicyClient {
 
 impl PolicyClient {
 	pub async fn call_reference(
+		&self,
+		req: Request,
+		backend_ref: &SimpleBackendReference,
+	) -> Result<Response, ProxyError> {
+		self.call_reference_to(req, backend_ref, None).await
+	}
+
+	/// Like `call_reference`, but for service backends the request is sent to the endpoint at
+	/// `dest` rather than a load balanced one.
+	pub async fn call_reference_to(
 		&self,
 		mut req: Request,
 		backend_ref: &SimpleBackendReference,
+		dest: Option<SocketAddr>,
 	) -> Result<Response, ProxyError> {
 		let backend = resolve_simple_backend(backend_ref, self.inputs.as_ref())?;
 		trace!("resolved {:?} to {:?}", backend_ref, &backend);
@@ -1865,7 +2574,10 @@ impl PolicyClient {
 		.map_err(ProxyError::Processing)?;
 
 		let backend = BackendWithPolicies::from(backend);
-		let pols = get_backend_policies(&self.inputs, &backend, &[], None);
+		let mut pols = get_backend_policies(&self.inputs, &backend, &[], None);
+		if dest.is_some() {
+			pols.override_dest = dest;
+		}
 		self
 			.internal_call_with_policies(req, backend.backend, pols)
 			.await
@@ -1,9 +1,13 @@
 mod gateway;
 pub mod httpproxy;
+pub mod prober;
 pub mod proxy_protocol;
 #[cfg(any(test, feature = "testing"))]
 pub mod request_builder;
+mod shard;
 pub mod tcpproxy;
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+pub mod uring;
 
 pub use gateway::Gateway;
 use hyper_util_fork::client::legacy::Error as HyperError;
@@ -49,18 +53,30 @@ impl ProxyResponse {
 			ProxyError::BasicAuthenticationFailure(_) => ProxyResponseReason::BasicAuth,
 			ProxyError::APIKeyAuthenticationFailure(_) => ProxyResponseReason::APIKeyAuth,
 			ProxyError::ExternalAuthorizationFailed(_) => ProxyResponseReason::ExtAuth,
-			ProxyError::AuthorizationFailed | ProxyError::CsrfValidationFailed => {
-				ProxyResponseReason::Authorization
-			},
+			ProxyError::SignatureVerificationFailure(_) => ProxyResponseReason::Signature,
+			ProxyError::AuthorizationFailed
+			| ProxyError::CsrfValidationFailed
+			| ProxyError::IpAccessDenied => ProxyResponseReason::Authorization,
 			ProxyError::UpstreamCallFailed(_)
 			| ProxyError::UpstreamTCPCallFailed(_)
 			| ProxyError::BackendAuthenticationFailed(_)
 			| ProxyError::UpstreamTCPProxy(_) => ProxyResponseReason::UpstreamFailure,
-			ProxyError::RequestTimeout | ProxyError::UpstreamCallTimeout => ProxyResponseReason::Timeout,
+			ProxyError::RequestTimeout
+			| ProxyError::UpstreamCallTimeout
+			| ProxyError::UpstreamTCPIdleTimeout(_) => ProxyResponseReason::Timeout,
+			ProxyError::Bridge(bridge::Error::Timeout(_)) => ProxyResponseReason::Timeout,
+			ProxyError::Bridge(bridge::Error::NoResponders) => ProxyResponseReason::NoHealthyBackend,
+			ProxyError::Bridge(bridge::Error::PayloadTooLarge) => ProxyResponseReason::Internal,
+			ProxyError::Bridge(bridge::Error::Connection(_)) => ProxyResponseReason::UpstreamFailure,
 			ProxyError::ExtProc(_) => ProxyResponseReason::ExtProc,
 			ProxyError::RateLimitFailed | ProxyError::RateLimitExceeded { .. } => {
 				ProxyResponseReason::RateLimit
 			},
+			ProxyError::Quota(http::quota::Error::MissingIdentity) => ProxyResponseReason::Authorization,
+			ProxyError::Quota(_) => ProxyResponseReason::RateLimit,
+			ProxyError::Queue(_) => ProxyResponseReason::RateLimit,
+			ProxyError::AdaptiveConcurrency(_) => ProxyResponseReason::RateLimit,
+			ProxyError::MemoryPressure | ProxyError::Overloaded => ProxyResponseReason::RateLimit,
 		}
 	}
 	pub fn downcast(self) -> ProxyError {
@@ -91,6 +107,8 @@ pub enum ProxyResponseReason {
 	BasicAuth,
 	/// API Key authentication failed
 	APIKeyAuth,
+	/// Request signature verification failed
+	Signature,
 	/// External Authorization failed
 	ExtAuth,
 	/// Authorization failed
@@ -137,8 +155,12 @@ pub enum ProxyError {
 	BasicAuthenticationFailure(http::basicauth::Error),
 	#[error("api key authentication failure: {0}")]
 	APIKeyAuthenticationFailure(http::apikey::Error),
+	#[error("request signature verification failure: {0}")]
+	SignatureVerificationFailure(http::signature::Error),
 	#[error("CSRF validation failed")]
 	CsrfValidationFailed,
+	#[error("client address is not allowed")]
+	IpAccessDenied,
 	#[error("service not found")]
 	ServiceNotFound,
 	#[error("invalid backend type")]
@@ -159,6 +181,8 @@ pub enum ProxyError {
 	UpstreamTCPCallFailed(http::Error),
 	#[error("upstream tcp proxy failed: {0}")]
 	UpstreamTCPProxy(agent_core::copy::CopyError),
+	#[error("upstream tcp connection idle for {0:?}")]
+	UpstreamTCPIdleTimeout(Duration),
 	#[error("request timeout")]
 	RequestTimeout,
 	#[error("processing failed: {0}")]
@@ -175,6 +199,18 @@ pub enum ProxyError {
 	},
 	#[error("rate limit failed")]
 	RateLimitFailed,
+	#[error("{0}")]
+	Quota(http::quota::Error),
+	#[error("{0}")]
+	Queue(http::queue::Error),
+	#[error("{0}")]
+	Bridge(bridge::Error),
+	#[error("{0}")]
+	AdaptiveConcurrency(http::adaptiveconcurrency::LimitExceeded),
+	#[error("memory limit reached, request rejected")]
+	MemoryPressure,
+	#[error("gateway overloaded, request rejected")]
+	Overloaded,
 	#[error("invalid request")]
 	InvalidRequest,
 	#[error("request upgrade failed, backend tried {1:?} but {0:?} was requested")]
@@ -205,6 +241,7 @@ impl ProxyError {
 			ProxyError::InvalidBackendType => StatusCode::INTERNAL_SERVER_ERROR,
 			ProxyError::ExtProc(_) => StatusCode::INTERNAL_SERVER_ERROR,
 			ProxyError::CsrfValidationFailed => StatusCode::FORBIDDEN,
+			ProxyError::IpAccessDenied => StatusCode::FORBIDDEN,
 
 			ProxyError::UpgradeFailed(_, _) => StatusCode::BAD_GATEWAY,
 
@@ -215,6 +252,7 @@ impl ProxyError {
 			ProxyError::JwtAuthenticationFailure(_) => StatusCode::UNAUTHORIZED,
 			ProxyError::BasicAuthenticationFailure(_) => StatusCode::UNAUTHORIZED,
 			ProxyError::APIKeyAuthenticationFailure(_) => StatusCode::UNAUTHORIZED,
+			ProxyError::SignatureVerificationFailure(_) => StatusCode::UNAUTHORIZED,
 			ProxyError::AuthorizationFailed => StatusCode::FORBIDDEN,
 			ProxyError::ExternalAuthorizationFailed(status) => status.unwrap_or(StatusCode::FORBIDDEN),
 
@@ -222,16 +260,28 @@ impl ProxyError {
 			ProxyError::NoHealthyEndpoints => StatusCode::SERVICE_UNAVAILABLE,
 			ProxyError::UpstreamCallFailed(_) => StatusCode::SERVICE_UNAVAILABLE,
 			ProxyError::UpstreamCallTimeout => StatusCode::GATEWAY_TIMEOUT,
+			ProxyError::Bridge(bridge::Error::Timeout(_)) => StatusCode::GATEWAY_TIMEOUT,
+			ProxyError::Bridge(bridge::Error::NoResponders) => StatusCode::SERVICE_UNAVAILABLE,
+			ProxyError::Bridge(bridge::Error::PayloadTooLarge) => StatusCode::PAYLOAD_TOO_LARGE,
+			ProxyError::Bridge(bridge::Error::Connection(_)) => StatusCode::BAD_GATEWAY,
 
 			ProxyError::RequestTimeout => StatusCode::GATEWAY_TIMEOUT,
 			ProxyError::Processing(_) => StatusCode::SERVICE_UNAVAILABLE,
 			ProxyError::ProcessingString(_) => StatusCode::SERVICE_UNAVAILABLE,
 			ProxyError::RateLimitExceeded { .. } => StatusCode::TOO_MANY_REQUESTS,
 			ProxyError::RateLimitFailed => StatusCode::TOO_MANY_REQUESTS,
+			ProxyError::Quota(http::quota::Error::MissingIdentity) => StatusCode::FORBIDDEN,
+			ProxyError::Quota(_) => StatusCode::TOO_MANY_REQUESTS,
+			ProxyError::Queue(http::queue::Error::Full) => StatusCode::TOO_MANY_REQUESTS,
+			ProxyError::Queue(http::queue::Error::Timeout) => StatusCode::SERVICE_UNAVAILABLE,
+			ProxyError::AdaptiveConcurrency(_) => StatusCode::SERVICE_UNAVAILABLE,
+			ProxyError::MemoryPressure => StatusCode::SERVICE_UNAVAILABLE,
+			ProxyError::Overloaded => StatusCode::SERVICE_UNAVAILABLE,
 
 			// Shouldn't happen on this path
 			ProxyError::UpstreamTCPCallFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
 			ProxyError::UpstreamTCPProxy(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			ProxyError::UpstreamTCPIdleTimeout(_) => StatusCode::INTERNAL_SERVER_ERROR,
 		};
 		let msg = self.to_string();
 		let mut rb = ::http::Response::builder()
@@ -256,6 +306,25 @@ impl ProxyError {
 			}
 		}
 
+		if let ProxyError::Quota(http::quota::Error::Exceeded {
+			limit,
+			reset_seconds,
+			..
+		}) = &self
+		{
+			if let Ok(hv) = HeaderValue::try_from(limit.to_string()) {
+				rb = rb.header(http::x_headers::X_QUOTA_LIMIT, hv)
+			}
+			rb = rb.header(
+				http::x_headers::X_QUOTA_REMAINING,
+				HeaderValue::from_static("0"),
+			);
+			if let Ok(hv) = HeaderValue::try_from(reset_seconds.to_string()) {
+				rb = rb.header(http::x_headers::X_QUOTA_RESET, hv.clone());
+				rb = rb.header(hyper::header::RETRY_AFTER, hv)
+			}
+		}
+
 		// Add WWW-Authenticate header for basic auth failures
 		if let ProxyError::BasicAuthenticationFailure(err) = &self {
 			let realm = match err {
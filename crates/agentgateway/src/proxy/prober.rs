@@ -0,0 +1,418 @@
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::{Mutex, OnceLock};
+
+use ::http::{Method, StatusCode, header};
+use agent_core::version::BuildInfo;
+use chrono::{DateTime, Utc};
+use macro_rules_attribute::apply;
+use rmcp::model::ProtocolVersion;
+use rmcp::transport::common::http_header::{
+	EVENT_STREAM_MIME_TYPE, HEADER_SESSION_ID, JSON_MIME_TYPE,
+};
+
+use crate::ProxyInputs;
+use crate::http::{Body, Request, Response};
+use crate::proxy::httpproxy::PolicyClient;
+use crate::proxy::{ProxyError, resolve_simple_backend};
+use crate::telemetry::metrics::ProbeLabels;
+use crate::types::agent::{SimpleBackend, SimpleBackendReference};
+use crate::types::discovery::Service;
+use crate::*;
+
+#[cfg(test)]
+#[path = "prober_tests.rs"]
+mod tests;
+
+static PROBER: OnceLock<Arc<Prober>> = OnceLock::new();
+
+/// Periodically send synthetic requests to backends, removing failing endpoints from load
+/// balancing.
+#[apply(schema!)]
+pub struct Config {
+	/// How often each backend is probed.
+	#[serde(default = "default_interval", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub interval: Duration,
+	/// How long a probe may take before it is considered failed.
+	#[serde(default = "default_timeout", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub timeout: Duration,
+	/// Number of consecutive failures before an endpoint is removed from load balancing. It is
+	/// returned after one interval, and removed again if the next probe fails.
+	#[serde(default = "default_unhealthy_threshold")]
+	pub unhealthy_threshold: u32,
+	pub probes: Vec<Probe>,
+}
+
+fn default_interval() -> Duration {
+	Duration::from_secs(10)
+}
+
+fn default_timeout() -> Duration {
+	Duration::from_secs(5)
+}
+
+fn default_unhealthy_threshold() -> u32 {
+	3
+}
+
+#[apply(schema!)]
+pub struct Probe {
+	/// Name of the probe, used in metrics and results.
+	pub name: String,
+	/// Backend to probe. For services, each endpoint is probed individually.
+	pub backend: SimpleBackendReference,
+	pub check: Check,
+}
+
+#[apply(schema!)]
+pub enum Check {
+	/// Send a GET request, expecting a successful status.
+	#[serde(rename_all = "camelCase")]
+	Http {
+		#[serde(default = "default_http_path")]
+		path: String,
+		/// Statuses considered healthy. If empty, any 2xx status is.
+		#[serde(default, skip_serializing_if = "Vec::is_empty")]
+		expected_statuses: Vec<u16>,
+	},
+	/// Open an MCP session with `initialize` and send a `ping`.
+	Mcp {
+		#[serde(default = "default_mcp_path")]
+		path: String,
+	},
+}
+
+fn default_http_path() -> String {
+	"/".to_string()
+}
+
+fn default_mcp_path() -> String {
+	"/mcp".to_string()
+}
+
+impl Check {
+	fn is_healthy(&self, status: StatusCode) -> bool {
+		match self {
+			Check::Http {
+				expected_statuses, ..
+			} if !expected_statuses.is_empty() => expected_statuses.contains(&status.as_u16()),
+			_ => status.is_success(),
+		}
+	}
+}
+
+/// The latest result of probing one target.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProbeResult {
+	pub probe: String,
+	/// The endpoint probed, for service backends, or the backend.
+	pub target: String,
+	pub healthy: bool,
+	pub consecutive_failures: u32,
+	#[serde(with = "serde_dur")]
+	pub latency: Duration,
+	pub checked_at: DateTime<Utc>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
+}
+
+/// Something to send a probe to.
+struct Target {
+	name: String,
+	/// Set for service endpoints, to pin the probe to the endpoint and evict it on failure.
+	endpoint: Option<(Arc<Service>, Strng, SocketAddr)>,
+}
+
+pub struct Prober {
+	cfg: Config,
+	inputs: Arc<ProxyInputs>,
+	results: Mutex<BTreeMap<(String, String), ProbeResult>>,
+}
+
+/// Start probing the configured backends.
+pub fn spawn(cfg: Config, inputs: Arc<ProxyInputs>) -> anyhow::Result<()> {
+	anyhow::ensure!(!cfg.interval.is_zero(), "probe interval must be positive");
+	let prober = Arc::new(Prober {
+		cfg,
+		inputs,
+		results: Default::default(),
+	});
+	PROBER
+		.set(prober.clone())
+		.map_err(|_| anyhow::anyhow!("prober already started"))?;
+	tokio::spawn(prober.run());
+	Ok(())
+}
+
+/// The latest result for each probe target, or None if probing is not configured.
+pub fn results() -> Option<Vec<ProbeResult>> {
+	let prober = PROBER.get()?;
+	Some(
+		prober
+			.results
+			.lock()
+			.expect("mutex acquired")
+			.values()
+			.cloned()
+			.collect(),
+	)
+}
+
+impl Prober {
+	async fn run(self: Arc<Self>) {
+		// Give configuration a chance to load before the first round.
+		let mut interval = tokio::time::interval_at(
+			tokio::time::Instant::now() + self.cfg.interval,
+			self.cfg.interval,
+		);
+		interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+		loop {
+			interval.tick().await;
+			futures::future::join_all(self.cfg.probes.iter().map(|p| self.probe(p))).await;
+		}
+	}
+
+	async fn probe(&self, probe: &Probe) {
+		let targets = match self.targets(probe) {
+			Ok(t) => t,
+			Err(e) => {
+				let target = Target {
+					name: reference_name(&probe.backend),
+					endpoint: None,
+				};
+				self.record(probe, &target, Duration::ZERO, Err(e.to_string()));
+				return;
+			},
+		};
+		let checks = targets.iter().map(|t| async move {
+			let start = Instant::now();
+			let dest = t.endpoint.as_ref().map(|(_, _, addr)| *addr);
+			let res = tokio::time::timeout(self.cfg.timeout, self.check(probe, dest))
+				.await
+				.unwrap_or_else(|_| Err("timed out".to_string()));
+			(t, start.elapsed(), res)
+		});
+		for (t, latency, res) in futures::future::join_all(checks).await {
+			self.record(probe, t, latency, res);
+		}
+		self.remove_stale(probe, &targets);
+	}
+
+	fn targets(&self, probe: &Probe) -> Result<Vec<Target>, ProxyError> {
+		let (svc, port) = match resolve_simple_backend(&probe.backend, self.inputs.as_ref())?.backend {
+			SimpleBackend::Service(svc, port) => (svc, port),
+			backend => {
+				return Ok(vec![Target {
+					name: backend.to_string(),
+					endpoint: None,
+				}]);
+			},
+		};
+		let workloads = &self.inputs.stores.read_discovery().workloads;
+		let svc_target_port = svc.ports.get(&port).copied().unwrap_or_default();
+		let targets = svc
+			.endpoints
+			.iter()
+			.iter()
+			.filter_map(|(ep, _)| {
+				let wl = workloads.find_uid(&ep.workload_uid)?;
+				let ip = *wl.workload_ips.first()?;
+				let target_port = ep.port.get(&port).copied().unwrap_or(svc_target_port);
+				Some(Target {
+					name: ep.workload_uid.to_string(),
+					endpoint: Some((
+						svc.clone(),
+						ep.workload_uid.clone(),
+						SocketAddr::new(ip, target_port),
+					)),
+				})
+			})
+			.collect();
+		Ok(targets)
+	}
+
+	async fn check(&self, probe: &Probe, dest: Option<SocketAddr>) -> Result<(), String> {
+		match &probe.check {
+			Check::Http { path, .. } => {
+				let req = ::http::Request::builder()
+					.method(Method::GET)
+					.uri(path)
+					.body(Body::empty())
+					.map_err(|e| e.to_string())?;
+				let resp = self.send(probe, dest, req).await?;
+				expect(&probe.check, &resp)
+			},
+			Check::Mcp { path } => {
+				let init = serde_json::json!({
+					"jsonrpc": "2.0",
+					"id": 1,
+					"method": "initialize",
+					"params": {
+						"protocolVersion": ProtocolVersion::V_2025_06_18,
+						"capabilities": {},
+						"clientInfo": {
+							"name": "agentgateway-prober",
+							"version": BuildInfo::new().version.to_string(),
+						},
+					},
+				});
+				let resp = self
+					.send(probe, dest, mcp_request(path, &init, None)?)
+					.await?;
+				expect(&probe.check, &resp)?;
+				let session = resp.headers().get(HEADER_SESSION_ID).cloned();
+
+				let ping = serde_json::json!({"jsonrpc": "2.0", "id": 2, "method": "ping"});
+				let resp = self
+					.send(probe, dest, mcp_request(path, &ping, session.as_ref())?)
+					.await?;
+				expect(&probe.check, &resp)?;
+
+				if let Some(session) = session {
+					// Best effort; servers also expire idle sessions.
+					let req = ::http::Request::builder()
+						.method(Method::DELETE)
+						.uri(path)
+						.header(HEADER_SESSION_ID, session)
+						.body(Body::empty())
+						.map_err(|e| e.to_string())?;
+					let _ = self.send(probe, dest, req).await;
+				}
+				Ok(())
+			},
+		}
+	}
+
+	async fn send(
+		&self,
+		probe: &Probe,
+		dest: Option<SocketAddr>,
+		req: Request,
+	) -> Result<Response, String> {
+		PolicyClient {
+			inputs: self.inputs.clone(),
+		}
+		.call_reference_to(req, &probe.backend, dest)
+		.await
+		.map_err(|e| e.to_string())
+	}
+
+	fn record(&self, probe: &Probe, target: &Target, latency: Duration, res: Result<(), String>) {
+		let labels = ProbeLabels {
+			probe: probe.name.as_str().into(),
+			target: target.name.as_str().into(),
+		};
+		let metrics = &self.inputs.metrics;
+		metrics
+			.probe_duration
+			.get_or_create(&labels)
+			.observe(latency.as_secs_f64());
+		metrics
+			.probe_healthy
+			.get_or_create(&labels)
+			.set(res.is_ok() as i64);
+		if res.is_err() {
+			metrics.probe_failures.get_or_create(&labels).inc();
+		}
+
+		let mut results = self.results.lock().expect("mutex acquired");
+		let key = (probe.name.clone(), target.name.clone());
+		let prev_failures = results
+			.get(&key)
+			.map(|r| r.consecutive_failures)
+			.unwrap_or_default();
+		let result = next_result(probe, target, prev_failures, latency, res);
+		if let Some((svc, key, _)) = &target.endpoint
+			&& result.consecutive_failures >= self.cfg.unhealthy_threshold
+		{
+			debug!(probe=%probe.name, endpoint=%key, "evicting endpoint after failed probes");
+			svc
+				.endpoints
+				.evict(key.clone(), Instant::now() + self.cfg.interval);
+		}
+		results.insert(key, result);
+	}
+
+	/// Forget targets that are no longer part of the backend, such as removed endpoints.
+	fn remove_stale(&self, probe: &Probe, targets: &[Target]) {
+		let mut results = self.results.lock().expect("mutex acquired");
+		results.retain(|(p, t), _| {
+			if p != &probe.name || targets.iter().any(|x| &x.name == t) {
+				return true;
+			}
+			let labels = ProbeLabels {
+				probe: p.as_str().into(),
+				target: t.as_str().into(),
+			};
+			let metrics = &self.inputs.metrics;
+			metrics.probe_duration.remove(&labels);
+			metrics.probe_failures.remove(&labels);
+			metrics.probe_healthy.remove(&labels);
+			false
+		});
+	}
+}
+
+fn next_result(
+	probe: &Probe,
+	target: &Target,
+	prev_failures: u32,
+	latency: Duration,
+	res: Result<(), String>,
+) -> ProbeResult {
+	ProbeResult {
+		probe: probe.name.clone(),
+		target: target.name.clone(),
+		healthy: res.is_ok(),
+		consecutive_failures: if res.is_ok() {
+			0
+		} else {
+			prev_failures.saturating_add(1)
+		},
+		latency,
+		checked_at: Utc::now(),
+		error: res.err(),
+	}
+}
+
+/// A name for a backend that could not be resolved.
+fn reference_name(backend: &SimpleBackendReference) -> String {
+	match backend {
+		SimpleBackendReference::Service { name, port } => format!("{}:{port}", name.hostname),
+		SimpleBackendReference::Backend(key) => key.to_string(),
+		SimpleBackendReference::InlineBackend(target) => target.hostport(),
+		SimpleBackendReference::Invalid => "invalid".to_string(),
+	}
+}
+
+fn expect(check: &Check, resp: &Response) -> Result<(), String> {
+	if check.is_healthy(resp.status()) {
+		Ok(())
+	} else {
+		Err(format!("unexpected status {}", resp.status()))
+	}
+}
+
+fn mcp_request(
+	path: &str,
+	body: &serde_json::Value,
+	session: Option<&::http::HeaderValue>,
+) -> Result<Request, String> {
+	let mut req = ::http::Request::builder()
+		.method(Method::POST)
+		.uri(path)
+		.header(header::CONTENT_TYPE, JSON_MIME_TYPE)
+		.header(
+			header::ACCEPT,
+			format!("{JSON_MIME_TYPE}, {EVENT_STREAM_MIME_TYPE}"),
+		);
+	if let Some(s) = session {
+		req = req.header(HEADER_SESSION_ID, s);
+	}
+	req
+		.body(Body::from(body.to_string()))
+		.map_err(|e| e.to_string())
+}
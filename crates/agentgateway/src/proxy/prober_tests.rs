@@ -0,0 +1,86 @@
+use super::*;
+
+fn probe(check: serde_json::Value) -> Probe {
+	serde_json::from_value(serde_json::json!({
+		"name": "test",
+		"backend": {"host": "127.0.0.1:8080"},
+		"check": check,
+	}))
+	.unwrap()
+}
+
+#[test]
+fn test_config() {
+	let cfg: Config = serde_json::from_value(serde_json::json!({
+		"probes": [
+			{"name": "web", "backend": {"host": "127.0.0.1:8080"}, "check": {"http": {}}},
+			{"name": "tools", "backend": {"backend": "mcp"}, "check": {"mcp": {}}},
+		],
+	}))
+	.unwrap();
+	assert_eq!(cfg.interval, Duration::from_secs(10));
+	assert_eq!(cfg.timeout, Duration::from_secs(5));
+	assert_eq!(cfg.unhealthy_threshold, 3);
+	assert!(matches!(&cfg.probes[0].check, Check::Http { path, .. } if path == "/"));
+	assert!(matches!(&cfg.probes[1].check, Check::Mcp { path } if path == "/mcp"));
+	assert_eq!(reference_name(&cfg.probes[1].backend), "mcp");
+}
+
+#[test]
+fn test_is_healthy() {
+	let check = probe(serde_json::json!({"http": {"path": "/healthz"}})).check;
+	assert!(check.is_healthy(StatusCode::OK));
+	assert!(check.is_healthy(StatusCode::NO_CONTENT));
+	assert!(!check.is_healthy(StatusCode::SERVICE_UNAVAILABLE));
+
+	let check = probe(serde_json::json!({"http": {"expectedStatuses": [200, 401]}})).check;
+	assert!(check.is_healthy(StatusCode::UNAUTHORIZED));
+	assert!(!check.is_healthy(StatusCode::NO_CONTENT));
+}
+
+#[test]
+fn test_consecutive_failures() {
+	let p = probe(serde_json::json!({"http": {}}));
+	let target = Target {
+		name: "127.0.0.1:8080".to_string(),
+		endpoint: None,
+	};
+	let latency = Duration::from_millis(5);
+
+	let r = next_result(&p, &target, 0, latency, Err("refused".to_string()));
+	assert!(!r.healthy);
+	assert_eq!(r.consecutive_failures, 1);
+	assert_eq!(r.error.as_deref(), Some("refused"));
+
+	let r = next_result(
+		&p,
+		&target,
+		r.consecutive_failures,
+		latency,
+		Err("refused".to_string()),
+	);
+	assert_eq!(r.consecutive_failures, 2);
+
+	let r = next_result(&p, &target, r.consecutive_failures, latency, Ok(()));
+	assert!(r.healthy);
+	assert_eq!(r.consecutive_failures, 0);
+	assert_eq!(r.error, None);
+}
+
+#[test]
+fn test_mcp_request() {
+	let session = ::http::HeaderValue::from_static("abc");
+	let req = mcp_request(
+		"/mcp",
+		&serde_json::json!({"jsonrpc": "2.0", "id": 2, "method": "ping"}),
+		Some(&session),
+	)
+	.unwrap();
+	assert_eq!(req.method(), Method::POST);
+	assert_eq!(req.uri().path(), "/mcp");
+	assert_eq!(req.headers().get(HEADER_SESSION_ID).unwrap(), "abc");
+	assert_eq!(
+		req.headers().get(header::ACCEPT).unwrap(),
+		"application/json, text/event-stream"
+	);
+}
@@ -1,15 +1,16 @@
-//! PROXY protocol v2 parser for Istio sandwich waypoint mode.
+//! PROXY protocol (https://www.haproxy.org/download/2.9/doc/proxy-protocol.txt) support.
 //!
-//! When agentgateway operates as an Istio ambient mesh waypoint in "sandwich" mode,
-//! ztunnel handles mTLS termination and forwards traffic to the waypoint using
-//! PROXY protocol v2. This module parses the PROXY header to extract:
+//! Binds with the `proxy` tunnel protocol expect each connection to start with a PROXY header,
+//! as sent by L4 load balancers, which carries the original client and destination addresses.
+//! Both the v1 (text) and v2 (binary) formats are accepted.
 //!
-//! - Original source/destination addresses (standard PROXY protocol)
-//! - Peer identity from TLV 0xD0 (SPIFFE URI of the source workload)
+//! In Istio sandwich waypoint mode, ztunnel handles mTLS termination and forwards traffic to the
+//! waypoint using PROXY protocol v2, with the peer identity in TLV 0xD0 (SPIFFE URI of the source
+//! workload). The extracted identity flows through to CEL authorization via TLSConnectionInfo.
 //!
-//! The extracted identity flows through to CEL authorization via TLSConnectionInfo.
+//! Headers can also be sent to backends of TCP routes that expect them; see [`encode_header`].
 
-use std::net::SocketAddr;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
 
 use anyhow::bail;
 use ppp::{HeaderResult, v2};
@@ -17,11 +18,18 @@ use tokio::io::{AsyncRead, AsyncReadExt};
 use tracing::trace;
 
 use crate::transport::tls::IstioIdentity;
+use crate::types::backend::ProxyProtocolVersion;
 use crate::types::discovery::Identity;
 
 /// TLV type for peer identity (SPIFFE URI) - matches ztunnel's PROXY_PROTOCOL_AUTHORITY_TLV
 const PROXY_PROTOCOL_AUTHORITY_TLV: u8 = 0xD0;
 
+/// PROXY protocol v1 headers start with this.
+const PROXY_V1_PREFIX: &[u8; 6] = b"PROXY ";
+
+/// Maximum v1 header size, including the trailing CRLF.
+const PROXY_V1_MAX_HEADER: usize = 107;
+
 /// PROXY protocol v2 signature (12 bytes)
 const PROXY_V2_SIGNATURE: [u8; 12] = [
 	0x0D, 0x0A, 0x0D, 0x0A, 0x00, 0x0D, 0x0A, 0x51, 0x55, 0x49, 0x54, 0x0A,
@@ -35,27 +43,90 @@ const PROXY_V2_MIN_HEADER: usize = 16;
 /// (ztunnel only sends identity TLV) while preventing allocation attacks.
 const PROXY_V2_MAX_ADDR_LEN: usize = 512;
 
-/// Information extracted from PROXY protocol v2 header.
+/// Information extracted from a PROXY protocol header.
 #[derive(Debug)]
 pub struct ProxyProtocolInfo {
-	/// Original source address of the client (before ztunnel)
-	pub src_addr: SocketAddr,
-	/// Original destination address (the service VIP)
-	pub dst_addr: SocketAddr,
+	/// Original source (the client) and destination (such as the service VIP) addresses.
+	/// Unset for connections the sender made on its own behalf, such as health checks (v2 `LOCAL`,
+	/// v1 `UNKNOWN`), which keep their actual addresses.
+	pub addresses: Option<(SocketAddr, SocketAddr)>,
 	/// Peer identity extracted from TLV 0xD0, if present
 	pub peer_identity: Option<IstioIdentity>,
 }
 
-/// Parse PROXY protocol v2 header from stream.
+/// Parse a PROXY protocol v1 or v2 header from stream.
 ///
 /// Reads exactly the header bytes (no more) so subsequent reads get the HTTP request.
-/// Uses the length field in the PROXY header to determine exact read size.
+/// For v2, uses the length field in the PROXY header to determine exact read size.
 pub async fn parse_proxy_protocol<S: AsyncRead + Unpin>(
 	stream: &mut S,
 ) -> anyhow::Result<ProxyProtocolInfo> {
-	// Read the fixed 16-byte header prefix
+	// Both formats are at least this long, so this never reads past the header.
+	let mut prefix = [0u8; PROXY_V1_PREFIX.len()];
+	stream.read_exact(&mut prefix).await?;
+	if &prefix == PROXY_V1_PREFIX {
+		parse_v1(stream).await
+	} else {
+		parse_v2(prefix, stream).await
+	}
+}
+
+async fn parse_v1<S: AsyncRead + Unpin>(stream: &mut S) -> anyhow::Result<ProxyProtocolInfo> {
+	// The header is a single line, so read a byte at a time to stop at its end.
+	let mut line = PROXY_V1_PREFIX.to_vec();
+	while !line.ends_with(b"\r\n") {
+		if line.len() >= PROXY_V1_MAX_HEADER {
+			bail!("PROXY v1 header exceeds maximum {PROXY_V1_MAX_HEADER} bytes");
+		}
+		line.push(stream.read_u8().await?);
+	}
+	let line = std::str::from_utf8(&line[..line.len() - 2])?;
+	let addresses = parse_v1_addresses(line)?;
+	trace!(?addresses, "parsed PROXY protocol v1 header");
+	Ok(ProxyProtocolInfo {
+		addresses,
+		peer_identity: None,
+	})
+}
+
+/// Parse the addresses from a v1 header line, such as
+/// `PROXY TCP4 192.168.1.1 10.0.0.1 12345 8080`.
+fn parse_v1_addresses(line: &str) -> anyhow::Result<Option<(SocketAddr, SocketAddr)>> {
+	let parts: Vec<&str> = line.split(' ').collect();
+	let (family, src, dst, src_port, dst_port) = match parts.as_slice() {
+		["PROXY", "UNKNOWN", ..] => return Ok(None),
+		[
+			"PROXY",
+			family @ ("TCP4" | "TCP6"),
+			src,
+			dst,
+			src_port,
+			dst_port,
+		] => (*family, src, dst, src_port, dst_port),
+		_ => bail!("invalid PROXY v1 header {line:?}"),
+	};
+	let src: IpAddr = src.parse()?;
+	let dst: IpAddr = dst.parse()?;
+	let ipv4 = family == "TCP4";
+	if src.is_ipv4() != ipv4 || dst.is_ipv4() != ipv4 {
+		bail!("PROXY v1 addresses do not match family {family}");
+	}
+	Ok(Some((
+		SocketAddr::new(src, src_port.parse()?),
+		SocketAddr::new(dst, dst_port.parse()?),
+	)))
+}
+
+async fn parse_v2<S: AsyncRead + Unpin>(
+	prefix: [u8; PROXY_V1_PREFIX.len()],
+	stream: &mut S,
+) -> anyhow::Result<ProxyProtocolInfo> {
+	// Read the rest of the fixed 16-byte header prefix
 	let mut header_prefix = [0u8; PROXY_V2_MIN_HEADER];
-	stream.read_exact(&mut header_prefix).await?;
+	header_prefix[..prefix.len()].copy_from_slice(&prefix);
+	stream
+		.read_exact(&mut header_prefix[prefix.len()..])
+		.await?;
 
 	// Verify signature
 	if header_prefix[..12] != PROXY_V2_SIGNATURE {
@@ -98,19 +169,21 @@ pub async fn parse_proxy_protocol<S: AsyncRead + Unpin>(
 	let header = match HeaderResult::parse(&full_header) {
 		HeaderResult::V2(Ok(h)) => h,
 		HeaderResult::V2(Err(e)) => bail!("invalid PROXY v2 header: {e:?}"),
-		HeaderResult::V1(_) => bail!("PROXY v1 not supported, expected v2"),
+		HeaderResult::V1(_) => bail!("invalid PROXY v2 header: parsed as v1"),
 	};
 
-	// Extract addresses
-	let (src_addr, dst_addr) = match header.addresses {
-		v2::Addresses::IPv4(ref a) => (
+	// Extract addresses. LOCAL connections carry none that apply.
+	let addresses = match header.addresses {
+		_ if matches!(header.command, v2::Command::Local) => None,
+		v2::Addresses::Unspecified => None,
+		v2::Addresses::IPv4(ref a) => Some((
 			SocketAddr::new(a.source_address.into(), a.source_port),
 			SocketAddr::new(a.destination_address.into(), a.destination_port),
-		),
-		v2::Addresses::IPv6(ref a) => (
+		)),
+		v2::Addresses::IPv6(ref a) => Some((
 			SocketAddr::new(a.source_address.into(), a.source_port),
 			SocketAddr::new(a.destination_address.into(), a.destination_port),
-		),
+		)),
 		_ => bail!("unsupported PROXY protocol address family"),
 	};
 
@@ -122,19 +195,70 @@ pub async fn parse_proxy_protocol<S: AsyncRead + Unpin>(
 		.and_then(|t| parse_spiffe_identity(&t.value));
 
 	trace!(
-		src = %src_addr,
-		dst = %dst_addr,
+		?addresses,
 		identity = ?peer_identity,
 		"parsed PROXY protocol v2 header"
 	);
 
 	Ok(ProxyProtocolInfo {
-		src_addr,
-		dst_addr,
+		addresses,
 		peer_identity,
 	})
 }
 
+/// Encode a PROXY header for a connection from `src` to `dst`, to send ahead of its data.
+/// When one address is IPv4 and the other IPv6, both are sent as IPv6.
+pub fn encode_header(
+	version: ProxyProtocolVersion,
+	src: SocketAddr,
+	dst: SocketAddr,
+) -> anyhow::Result<Vec<u8>> {
+	let addresses = match (src, dst) {
+		(SocketAddr::V4(src), SocketAddr::V4(dst)) => v2::Addresses::IPv4(v2::IPv4 {
+			source_address: *src.ip(),
+			destination_address: *dst.ip(),
+			source_port: src.port(),
+			destination_port: dst.port(),
+		}),
+		(src, dst) => v2::Addresses::IPv6(v2::IPv6 {
+			source_address: to_ipv6(src.ip()),
+			destination_address: to_ipv6(dst.ip()),
+			source_port: src.port(),
+			destination_port: dst.port(),
+		}),
+	};
+	match version {
+		ProxyProtocolVersion::V1 => {
+			let line = match addresses {
+				v2::Addresses::IPv4(a) => format!(
+					"PROXY TCP4 {} {} {} {}\r\n",
+					a.source_address, a.destination_address, a.source_port, a.destination_port
+				),
+				v2::Addresses::IPv6(a) => format!(
+					"PROXY TCP6 {} {} {} {}\r\n",
+					a.source_address, a.destination_address, a.source_port, a.destination_port
+				),
+				_ => "PROXY UNKNOWN\r\n".to_string(),
+			};
+			Ok(line.into_bytes())
+		},
+		ProxyProtocolVersion::V2 => v2::Builder::with_addresses(
+			v2::Version::Two | v2::Command::Proxy,
+			v2::Protocol::Stream,
+			addresses,
+		)
+		.build()
+		.map_err(|e| anyhow::anyhow!("failed to encode PROXY v2 header: {e:?}")),
+	}
+}
+
+fn to_ipv6(ip: IpAddr) -> Ipv6Addr {
+	match ip {
+		IpAddr::V4(ip) => ip.to_ipv6_mapped(),
+		IpAddr::V6(ip) => ip,
+	}
+}
+
 /// Parse a SPIFFE URI into IstioIdentity components.
 ///
 /// Uses the existing `Identity::FromStr` implementation for parsing,
@@ -177,6 +301,10 @@ mod tests {
 		builder.build().unwrap()
 	}
 
+	fn addrs(src: &str, dst: &str) -> Option<(SocketAddr, SocketAddr)> {
+		Some((src.parse().unwrap(), dst.parse().unwrap()))
+	}
+
 	#[test]
 	fn test_parse_spiffe_identity() {
 		let cases = [
@@ -205,8 +333,7 @@ mod tests {
 		let mut cursor = std::io::Cursor::new(data);
 		let info = parse_proxy_protocol(&mut cursor).await.unwrap();
 
-		assert_eq!(info.src_addr.to_string(), "192.168.1.1:12345");
-		assert_eq!(info.dst_addr.to_string(), "10.0.0.1:8080");
+		assert_eq!(info.addresses, addrs("192.168.1.1:12345", "10.0.0.1:8080"));
 		assert!(info.peer_identity.is_none());
 		assert_eq!(cursor.position() as usize, header.len()); // didn't consume HTTP
 	}
@@ -222,11 +349,113 @@ mod tests {
 		let mut cursor = std::io::Cursor::new(header);
 		let info = parse_proxy_protocol(&mut cursor).await.unwrap();
 
-		assert_eq!(info.src_addr.to_string(), "192.168.1.1:12345");
-		assert_eq!(info.dst_addr.to_string(), "10.0.0.1:8080");
+		assert_eq!(info.addresses, addrs("192.168.1.1:12345", "10.0.0.1:8080"));
 		assert_eq!(
 			info.peer_identity.unwrap().to_string(),
 			"spiffe://cluster.local/ns/default/sa/my-service"
 		);
 	}
+
+	#[tokio::test]
+	async fn test_parse_proxy_protocol_local() {
+		// Load balancer health checks
+		let header = Builder::with_addresses(
+			Version::Two | Command::Local,
+			Protocol::Stream,
+			ppp::v2::Addresses::Unspecified,
+		)
+		.build()
+		.unwrap();
+		let mut cursor = std::io::Cursor::new(header);
+		let info = parse_proxy_protocol(&mut cursor).await.unwrap();
+		assert!(info.addresses.is_none());
+	}
+
+	#[tokio::test]
+	async fn test_parse_proxy_protocol_v1() {
+		let cases = [
+			(
+				"PROXY TCP4 192.168.1.1 10.0.0.1 12345 8080\r\n",
+				Some(("192.168.1.1:12345", "10.0.0.1:8080")),
+			),
+			(
+				"PROXY TCP6 2001:db8::1 2001:db8::2 12345 8080\r\n",
+				Some(("[2001:db8::1]:12345", "[2001:db8::2]:8080")),
+			),
+			("PROXY UNKNOWN\r\n", None),
+			("PROXY UNKNOWN 192.168.1.1 10.0.0.1 12345 8080\r\n", None),
+		];
+		for (header, want) in cases {
+			let mut data = header.as_bytes().to_vec();
+			data.extend_from_slice(b"GET / HTTP/1.1\r\n"); // trailing HTTP
+			let mut cursor = std::io::Cursor::new(data);
+			let info = parse_proxy_protocol(&mut cursor).await.unwrap();
+			let want = want.and_then(|(src, dst)| addrs(src, dst));
+			assert_eq!(info.addresses, want, "{header:?}");
+			assert_eq!(cursor.position() as usize, header.len(), "{header:?}"); // didn't consume HTTP
+		}
+
+		let invalid = [
+			"PROXY TCP4 2001:db8::1 10.0.0.1 12345 8080\r\n", // wrong family
+			"PROXY TCP4 192.168.1.1 10.0.0.1 12345\r\n",      // missing port
+			"PROXY UDP4 192.168.1.1 10.0.0.1 12345 8080\r\n", // unknown protocol
+			"PROXY TCP4 192.168.1.1 10.0.0.1 70000 8080\r\n", // invalid port
+		];
+		for header in invalid {
+			let mut cursor = std::io::Cursor::new(header.as_bytes().to_vec());
+			assert!(
+				parse_proxy_protocol(&mut cursor).await.is_err(),
+				"{header:?}"
+			);
+		}
+		// The header must end within 107 bytes
+		let mut cursor = std::io::Cursor::new(format!("PROXY TCP4 {}", "1".repeat(200)).into_bytes());
+		assert!(parse_proxy_protocol(&mut cursor).await.is_err());
+	}
+
+	#[tokio::test]
+	async fn test_encode_header() {
+		let cases = [
+			(
+				"192.168.1.1:12345",
+				"10.0.0.1:8080",
+				"192.168.1.1:12345",
+				"10.0.0.1:8080",
+			),
+			(
+				"[2001:db8::1]:12345",
+				"[2001:db8::2]:8080",
+				"[2001:db8::1]:12345",
+				"[2001:db8::2]:8080",
+			),
+			// Mixed families are sent as IPv6
+			(
+				"192.168.1.1:12345",
+				"[2001:db8::2]:8080",
+				"[::ffff:192.168.1.1]:12345",
+				"[2001:db8::2]:8080",
+			),
+		];
+		for version in [ProxyProtocolVersion::V1, ProxyProtocolVersion::V2] {
+			for (src, dst, want_src, want_dst) in cases {
+				let header = encode_header(version, src.parse().unwrap(), dst.parse().unwrap()).unwrap();
+				let mut cursor = std::io::Cursor::new(header);
+				let info = parse_proxy_protocol(&mut cursor).await.unwrap();
+				assert_eq!(
+					info.addresses,
+					addrs(want_src, want_dst),
+					"{version:?} {src} {dst}"
+				);
+			}
+		}
+		assert_eq!(
+			encode_header(
+				ProxyProtocolVersion::V1,
+				"192.168.1.1:12345".parse().unwrap(),
+				"10.0.0.1:8080".parse().unwrap()
+			)
+			.unwrap(),
+			b"PROXY TCP4 192.168.1.1 10.0.0.1 12345 8080\r\n"
+		);
+	}
 }
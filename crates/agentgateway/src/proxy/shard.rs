@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use tokio::runtime::Handle;
+use tokio::sync::oneshot;
+use tracing::warn;
+
+use crate::{ProxyInputs, client};
+
+/// An independent single threaded runtime, pinned to a core, used in the thread per core threading
+/// mode. Each shard accepts connections on its own socket for every bind, and has its own upstream
+/// client and so its own connection pools, so requests are served without crossing threads. The
+/// config stores are shared by all shards.
+pub(super) struct Shard {
+	pub(super) handle: Handle,
+	pub(super) pi: Arc<ProxyInputs>,
+	/// Stops the runtime when the shard is dropped.
+	_stop: oneshot::Sender<()>,
+}
+
+/// Start `n` shards, pinned to each core in turn.
+pub(super) fn start(pi: &Arc<ProxyInputs>, n: usize) -> anyhow::Result<Vec<Shard>> {
+	let cores = core_affinity::get_core_ids().unwrap_or_default();
+	if cores.is_empty() {
+		warn!("failed to list cores, runtime shards will not be pinned");
+	}
+	(0..n)
+		.map(|i| {
+			let core = (!cores.is_empty()).then(|| cores[i % cores.len()]);
+			let runtime = tokio::runtime::Builder::new_current_thread()
+				.enable_all()
+				.build()?;
+			let handle = runtime.handle().clone();
+			let (stop, stopped) = oneshot::channel::<()>();
+			std::thread::Builder::new()
+				.name(format!("agentgateway-shard-{i}"))
+				.spawn(move || {
+					if let Some(core) = core
+						&& !core_affinity::set_for_current(core)
+					{
+						warn!(shard = i, core = core.id, "failed to pin runtime shard");
+					}
+					// Run the tasks spawned on the shard until it is dropped.
+					runtime.block_on(async {
+						let _ = stopped.await;
+					});
+				})?;
+
+			// The client must be created on the shard, as its resolver and pools run there.
+			let _guard = handle.enter();
+			let mut shard_pi = ProxyInputs::clone(pi);
+			shard_pi.upstream = client::Client::new(
+				&pi.cfg.dns,
+				None,
+				pi.cfg.backend.clone(),
+				Some(pi.metrics.clone()),
+			);
+			Ok(Shard {
+				handle: handle.clone(),
+				pi: Arc::new(shard_pi),
+				_stop: stop,
+			})
+		})
+		.collect()
+}
@@ -146,15 +146,20 @@ impl TCPProxy {
 		let mut connection = connection;
 		connection.set_transport_metrics(self.inputs.metrics.clone(), tcp_labels);
 
-		inputs
+		let stats = Arc::new(agent_core::copy::ConnectionResult::default());
+		let res = inputs
 			.upstream
 			.call_tcp(client::TCPCall {
 				source: connection,
 				target: backend_call.target,
 				transport,
+				tcp: backend_call.backend_policies.tcp,
+				stats: stats.clone(),
+				io_uring: inputs.cfg.io_uring,
 			})
-			.await?;
-		Ok(())
+			.await;
+		log.tcp_bytes = Some(stats.load());
+		res
 	}
 }
 
@@ -0,0 +1,502 @@
+//! Accepting and copying TCP connections with io_uring, enabled by the `io-uring` feature and
+//! `ioUring`.
+//!
+//! Each listener has a single multishot accept submitted to its own ring, so the kernel keeps
+//! accepting connections without a syscall for each one, and every wakeup reaps all connections
+//! accepted since the last. Accepted connections are then served by the tokio runtime as usual.
+//!
+//! Connections of TCP routes are copied to and from their backends on one shared ring, driven by a
+//! single thread: each direction of a copy has a receive or a send in flight, and each wakeup
+//! completes those of every connection that made progress. Connections that are not plain TCP,
+//! such as TLS connections, are copied by the runtime.
+//!
+//! If io_uring is unavailable, such as on kernels older than 5.19 or when it is blocked by seccomp,
+//! connections are accepted with epoll and copied by the runtime instead.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use agent_core::copy::{ConnectionResult, CopyError, TcpStreamSplitter};
+use io_uring::{IoUring, cqueue, opcode, squeue, types};
+use tokio::sync::{Mutex, mpsc, oneshot};
+use tracing::{debug, warn};
+
+use crate::transport::stream::{Metrics, Socket};
+
+/// Size of the submission and completion queues.
+const RING_ENTRIES: u32 = 256;
+/// Accepted connections waiting to be picked up by the runtime.
+const BACKLOG: usize = 1024;
+/// How long to back off when accepting fails for lack of resources, such as file descriptors.
+const ERROR_BACKOFF: Duration = Duration::from_millis(10);
+/// Size of the shared copy ring's submission queue; its completion queue is twice as large.
+const COPY_RING_ENTRIES: u32 = 4096;
+/// Copies waiting to be started by the copy ring. Beyond this, connections are copied by the
+/// runtime.
+const COPY_BACKLOG: usize = 1024;
+/// Size of the buffer for each direction of a copy.
+const COPY_BUFFER_SIZE: usize = 16 * 1024;
+/// The `user_data` of the read that wakes the copy ring to start new copies.
+const WAKE: u64 = u64::MAX;
+
+pub struct Acceptor {
+	listener: Arc<TcpListener>,
+	closed: Arc<AtomicBool>,
+	state: Mutex<State>,
+}
+
+enum State {
+	Uring(mpsc::Receiver<TcpStream>),
+	Epoll(tokio::net::TcpListener),
+}
+
+impl Acceptor {
+	/// Start accepting on `listener`, which must be non-blocking. Must be called from a runtime.
+	pub fn new(listener: TcpListener) -> io::Result<Acceptor> {
+		let listener = Arc::new(listener);
+		let closed = Arc::new(AtomicBool::new(false));
+		let state = match IoUring::new(RING_ENTRIES) {
+			Ok(ring) => {
+				let (tx, rx) = mpsc::channel(BACKLOG);
+				let (l, c) = (listener.clone(), closed.clone());
+				std::thread::Builder::new()
+					.name("uring-acceptor".to_string())
+					.spawn(move || {
+						if let Err(e) = run(ring, &l, &c, tx) {
+							warn!("io_uring acceptor failed, accepting with epoll: {e}");
+						}
+					})?;
+				State::Uring(rx)
+			},
+			Err(e) => {
+				warn!("io_uring is unavailable, accepting with epoll: {e}");
+				State::Epoll(tokio::net::TcpListener::from_std(listener.try_clone()?)?)
+			},
+		};
+		Ok(Acceptor {
+			listener,
+			closed,
+			state: Mutex::new(state),
+		})
+	}
+
+	pub async fn accept(&self) -> io::Result<Socket> {
+		let mut state = self.state.lock().await;
+		loop {
+			let stream = match &mut *state {
+				State::Uring(rx) => match rx.recv().await {
+					Some(stream) => tokio::net::TcpStream::from_std(stream)?,
+					None => {
+						// The ring failed; keep serving the listener without it.
+						*state = State::Epoll(tokio::net::TcpListener::from_std(
+							self.listener.try_clone()?,
+						)?);
+						continue;
+					},
+				},
+				State::Epoll(l) => l.accept().await?.0,
+			};
+			return Socket::from_tcp(stream);
+		}
+	}
+}
+
+impl Drop for Acceptor {
+	fn drop(&mut self) {
+		self.closed.store(true, Ordering::Relaxed);
+		// Shutting down the listener fails the pending accept, so the ring's thread exits.
+		let _ = socket2::SockRef::from(&*self.listener).shutdown(Shutdown::Read);
+	}
+}
+
+fn run(
+	mut ring: IoUring,
+	listener: &TcpListener,
+	closed: &AtomicBool,
+	accepted: mpsc::Sender<TcpStream>,
+) -> io::Result<()> {
+	let accept = opcode::AcceptMulti::new(types::Fd(listener.as_raw_fd()))
+		.flags(libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC)
+		.build();
+	let mut armed = false;
+	loop {
+		if !armed {
+			// SAFETY: the entry references no buffers, and the listener outlives the ring.
+			unsafe { ring.submission().push(&accept) }.map_err(io::Error::other)?;
+			armed = true;
+		}
+		match ring.submit_and_wait(1) {
+			Ok(_) => {},
+			Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+			Err(e) => return Err(e),
+		}
+		let mut backoff = false;
+		for cqe in ring.completion() {
+			// A multishot accept ends after an error, or if the completion queue overflows.
+			if !cqueue::more(cqe.flags()) {
+				armed = false;
+			}
+			let res = cqe.result();
+			if res < 0 {
+				if closed.load(Ordering::Relaxed) {
+					return Ok(());
+				}
+				match -res {
+					// Multishot accept needs Linux 5.19.
+					libc::EINVAL => return Err(io::Error::from_raw_os_error(-res)),
+					libc::EMFILE | libc::ENFILE | libc::ENOBUFS | libc::ENOMEM => backoff = true,
+					// Can fail if they immediately disconnected; not much we can do.
+					_ => {},
+				}
+				debug!(
+					"io_uring accept failed: {}",
+					io::Error::from_raw_os_error(-res)
+				);
+				continue;
+			}
+			// SAFETY: the kernel returned a newly accepted socket, which nothing else owns.
+			let stream = unsafe { TcpStream::from_raw_fd(res) };
+			if accepted.blocking_send(stream).is_err() {
+				return Ok(());
+			}
+		}
+		if backoff {
+			std::thread::sleep(ERROR_BACKOFF);
+		}
+	}
+}
+
+/// Copy between two connections until both directions are closed, with io_uring if both are plain
+/// TCP. Otherwise, or if io_uring is unavailable, they are copied by the runtime.
+pub async fn copy_bidirectional(
+	downstream: Socket,
+	upstream: Socket,
+	stats: Arc<ConnectionResult>,
+) -> Result<(), CopyError> {
+	let ring = match copy_ring() {
+		Some(ring) if downstream.is_tcp() && upstream.is_tcp() => ring,
+		_ => return agent_core::copy::copy_bidirectional(downstream, upstream, &stats).await,
+	};
+	let (Ok((down, down_metrics)), Ok((up, up_metrics))) =
+		(downstream.into_tcp(), upstream.into_tcp())
+	else {
+		unreachable!("both sockets are plain TCP");
+	};
+	let _counted = Counted {
+		metrics: [down_metrics, up_metrics],
+		stats: stats.clone(),
+	};
+	let streams = [Arc::new(down.into_std()?), Arc::new(up.into_std()?)];
+	let (done, result) = oneshot::channel();
+	let copy = Transfer {
+		streams: streams.clone(),
+		stats: stats.clone(),
+		done,
+	};
+	if let Err(mpsc::error::TrySendError::Full(copy) | mpsc::error::TrySendError::Closed(copy)) =
+		ring.copies.try_send(copy)
+	{
+		// The ring is backed up, or failed; copy with the runtime instead.
+		drop(streams);
+		let [down, up] = copy
+			.streams
+			.map(|s| Arc::into_inner(s).expect("streams are not shared"));
+		return agent_core::copy::copy_bidirectional(
+			TcpStreamSplitter(tokio::net::TcpStream::from_std(down)?),
+			TcpStreamSplitter(tokio::net::TcpStream::from_std(up)?),
+			&stats,
+		)
+		.await;
+	}
+	ring.wake();
+	// If the copy is abandoned, such as after an idle timeout, shutting down the connections fails
+	// its operations in flight, which ends it.
+	let _shutdown = ShutdownOnDrop(streams);
+	result
+		.await
+		.unwrap_or_else(|_| Err(io::Error::other("io_uring copy ring failed").into()))
+}
+
+/// Counts the bytes copied in the connections' metrics when dropped, as they do not go through the
+/// sockets.
+struct Counted {
+	metrics: [Metrics; 2],
+	stats: Arc<ConnectionResult>,
+}
+
+impl Drop for Counted {
+	fn drop(&mut self) {
+		let (sent, recv) = self.stats.load();
+		self.metrics[0].count(recv, sent);
+		self.metrics[1].count(sent, recv);
+	}
+}
+
+struct ShutdownOnDrop([Arc<TcpStream>; 2]);
+
+impl Drop for ShutdownOnDrop {
+	fn drop(&mut self) {
+		for stream in &self.0 {
+			let _ = stream.shutdown(Shutdown::Both);
+		}
+	}
+}
+
+struct CopyRing {
+	copies: mpsc::Sender<Transfer>,
+	wake: File,
+}
+
+impl CopyRing {
+	fn start() -> io::Result<CopyRing> {
+		let ring = IoUring::builder()
+			.setup_cqsize(2 * COPY_RING_ENTRIES)
+			.build(COPY_RING_ENTRIES)?;
+		// SAFETY: eventfd has no preconditions, and its result is checked.
+		let fd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC) };
+		if fd < 0 {
+			return Err(io::Error::last_os_error());
+		}
+		// SAFETY: the eventfd was just created, and nothing else owns it.
+		let wake = unsafe { File::from_raw_fd(fd) };
+		let (tx, rx) = mpsc::channel(COPY_BACKLOG);
+		let w = wake.try_clone()?;
+		std::thread::Builder::new()
+			.name("uring-copy".to_string())
+			.spawn(move || {
+				if let Err(e) = run_copies(ring, &w, rx) {
+					warn!("io_uring copy ring failed, copying with epoll: {e}");
+				}
+			})?;
+		Ok(CopyRing { copies: tx, wake })
+	}
+
+	fn wake(&self) {
+		let _ = (&self.wake).write_all(&1u64.to_ne_bytes());
+	}
+}
+
+/// The shared copy ring, started on first use, or `None` if io_uring is unavailable.
+fn copy_ring() -> Option<&'static CopyRing> {
+	static RING: OnceLock<Option<CopyRing>> = OnceLock::new();
+	RING
+		.get_or_init(|| match CopyRing::start() {
+			Ok(ring) => Some(ring),
+			Err(e) => {
+				warn!("io_uring is unavailable, copying with epoll: {e}");
+				None
+			},
+		})
+		.as_ref()
+}
+
+struct Transfer {
+	streams: [Arc<TcpStream>; 2],
+	stats: Arc<ConnectionResult>,
+	done: oneshot::Sender<Result<(), CopyError>>,
+}
+
+/// A copy in progress. Direction 0 copies from the downstream to the upstream, and 1 back.
+struct Active {
+	copy: Transfer,
+	dirs: [Direction; 2],
+}
+
+struct Direction {
+	buf: Box<[u8]>,
+	filled: usize,
+	written: usize,
+	done: bool,
+	error: Option<io::Error>,
+}
+
+impl Active {
+	fn new(copy: Transfer) -> Active {
+		let dir = || Direction {
+			buf: vec![0; COPY_BUFFER_SIZE].into_boxed_slice(),
+			filled: 0,
+			written: 0,
+			done: false,
+			error: None,
+		};
+		Active {
+			copy,
+			dirs: [dir(), dir()],
+		}
+	}
+
+	/// Receives into the buffer of a direction, from the stream it copies from.
+	fn recv(&mut self, id: u64, dir: usize) -> squeue::Entry {
+		let d = &mut self.dirs[dir];
+		let fd = types::Fd(self.copy.streams[dir].as_raw_fd());
+		opcode::Recv::new(fd, d.buf.as_mut_ptr(), d.buf.len() as u32)
+			.build()
+			.user_data(id << 2 | (dir as u64) << 1)
+	}
+
+	/// Sends the rest of the buffer of a direction, to the stream it copies to.
+	fn send(&self, id: u64, dir: usize) -> squeue::Entry {
+		let d = &self.dirs[dir];
+		let rest = &d.buf[d.written..d.filled];
+		let fd = types::Fd(self.copy.streams[1 - dir].as_raw_fd());
+		opcode::Send::new(fd, rest.as_ptr(), rest.len() as u32)
+			// Fail sends to closed connections, rather than raising SIGPIPE.
+			.flags(libc::MSG_NOSIGNAL)
+			.build()
+			.user_data(id << 2 | (dir as u64) << 1 | 1)
+	}
+
+	/// Handles a completed operation, returning the next operation of its direction, if any.
+	fn complete(&mut self, id: u64, dir: usize, send: bool, res: i32) -> Option<squeue::Entry> {
+		if res < 0 {
+			let e = io::Error::from_raw_os_error(-res);
+			// As with the runtime's copy, connections closing ungracefully are not errors.
+			use io::ErrorKind::*;
+			if !matches!(
+				e.kind(),
+				NotConnected | UnexpectedEof | ConnectionReset | BrokenPipe
+			) {
+				self.dirs[dir].error = Some(e);
+			}
+			self.close(dir);
+			return None;
+		}
+		let n = res as usize;
+		let d = &mut self.dirs[dir];
+		if !send {
+			if n == 0 {
+				self.close(dir);
+				return None;
+			}
+			(d.filled, d.written) = (n, 0);
+			return Some(self.send(id, dir));
+		}
+		d.written += n;
+		if dir == 0 {
+			self.copy.stats.increment_send(n as u64);
+		} else {
+			self.copy.stats.increment_recv(n as u64);
+		}
+		if d.written < d.filled {
+			Some(self.send(id, dir))
+		} else {
+			Some(self.recv(id, dir))
+		}
+	}
+
+	/// Ends a direction, shutting down writes to the stream it copies to.
+	fn close(&mut self, dir: usize) {
+		self.dirs[dir].done = true;
+		let _ = self.copy.streams[1 - dir].shutdown(Shutdown::Write);
+	}
+
+	fn finish(self) {
+		let [sent, received] = self.dirs.map(|d| d.error);
+		let res = match (sent, received) {
+			(Some(e), _) => Err(CopyError::SendError(Box::new(e.into()))),
+			(None, Some(e)) => Err(CopyError::ReceiveError(Box::new(e.into()))),
+			(None, None) => Ok(()),
+		};
+		let _ = self.copy.done.send(res);
+	}
+}
+
+fn run_copies(
+	mut ring: IoUring,
+	wake: &File,
+	mut copies: mpsc::Receiver<Transfer>,
+) -> io::Result<()> {
+	let mut active = HashMap::new();
+	let res = drive(&mut ring, wake, &mut copies, &mut active);
+	for (_, a) in active.drain() {
+		for stream in &a.copy.streams {
+			let _ = stream.shutdown(Shutdown::Both);
+		}
+		let _ = a
+			.copy
+			.done
+			.send(Err(io::Error::other("io_uring copy ring failed").into()));
+		// Operations may still be in flight, so their buffers must not be freed.
+		std::mem::forget(a.dirs);
+	}
+	res
+}
+
+fn drive(
+	ring: &mut IoUring,
+	wake: &File,
+	copies: &mut mpsc::Receiver<Transfer>,
+	active: &mut HashMap<u64, Active>,
+) -> io::Result<()> {
+	// Leaked, as a read may still be in flight if the ring fails.
+	let counter: &'static mut [u8; 8] = Box::leak(Box::new([0; 8]));
+	let read_wake = opcode::Read::new(types::Fd(wake.as_raw_fd()), counter.as_mut_ptr(), 8)
+		.build()
+		.user_data(WAKE);
+	// SAFETY: the counter is never freed.
+	unsafe { push(ring, &read_wake) }?;
+	let mut next_id = 0;
+	let mut completed = Vec::new();
+	loop {
+		match ring.submit_and_wait(1) {
+			Ok(_) => {},
+			Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+			Err(e) => return Err(e),
+		}
+		completed.extend(ring.completion().map(|cqe| (cqe.user_data(), cqe.result())));
+		for (user_data, res) in completed.drain(..) {
+			if user_data == WAKE {
+				if res < 0 {
+					return Err(io::Error::from_raw_os_error(-res));
+				}
+				while let Ok(copy) = copies.try_recv() {
+					let id = next_id;
+					next_id += 1;
+					let mut a = Active::new(copy);
+					for dir in 0..2 {
+						// SAFETY: the buffers of a copy are freed only once none of its operations
+						// are in flight.
+						unsafe { push(ring, &a.recv(id, dir)) }?;
+					}
+					active.insert(id, a);
+				}
+				// SAFETY: the counter is never freed.
+				unsafe { push(ring, &read_wake) }?;
+				continue;
+			}
+			let (id, dir, send) = (
+				user_data >> 2,
+				(user_data >> 1 & 1) as usize,
+				user_data & 1 == 1,
+			);
+			let Some(a) = active.get_mut(&id) else {
+				continue;
+			};
+			if let Some(next) = a.complete(id, dir, send, res) {
+				// SAFETY: as above.
+				unsafe { push(ring, &next) }?;
+			} else if a.dirs.iter().all(|d| d.done) {
+				active.remove(&id).expect("copy is active").finish();
+			}
+		}
+	}
+}
+
+/// Push an entry to the submission queue, submitting the queue first if it is full.
+///
+/// # Safety
+///
+/// The buffers of the entry must outlive its operation.
+unsafe fn push(ring: &mut IoUring, entry: &squeue::Entry) -> io::Result<()> {
+	// SAFETY: guaranteed by the caller.
+	while unsafe { ring.submission().push(entry) }.is_err() {
+		ring.submit()?;
+	}
+	Ok(())
+}
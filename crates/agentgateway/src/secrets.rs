@@ -0,0 +1,388 @@
+//! Resolution of secret references in configuration.
+//!
+//! Instead of embedding secrets directly in the configuration file, a string value may be a
+//! reference to a secret held elsewhere, written as `${secret:<provider>:<path>#<key>}`. Only
+//! values that are entirely such a reference are resolved; other strings are left as they are.
+//! * `${secret:env:NAME}` reads the environment variable `NAME`.
+//! * `${secret:vault:<path>#<key>}` reads `key` from the Vault secret at `<path>`, using
+//!   `VAULT_ADDR` and `VAULT_TOKEN`. For KV v2 engines, the path must include the `data/` segment
+//!   (`kv/data/foo`).
+//! * `${secret:k8s:<namespace>/<name>#<key>}` reads `key` from a Kubernetes Secret, using the
+//!   in-cluster service account credentials.
+//!
+//! Resolved secrets are cached, and periodically refreshed so that rotated secrets are picked up.
+
+use std::collections::{HashMap, HashSet};
+
+use base64::Engine;
+use secrecy::{ExposeSecret, SecretString};
+use serde_json::Value;
+
+use crate::client::Client;
+use crate::http::Body;
+use crate::*;
+
+/// How long a resolved secret is served from cache before it is fetched again.
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(300);
+
+/// The start of a secret reference, `${secret:<provider>:<path>#<key>}`.
+const REFERENCE_PREFIX: &str = "${secret:";
+
+#[cfg(test)]
+#[path = "secrets_tests.rs"]
+mod tests;
+
+/// SecretRef is a parsed reference to a secret, in the form `<provider>:<path>#<key>`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SecretRef {
+	pub provider: Strng,
+	pub path: String,
+	pub key: Option<String>,
+}
+
+impl Display for SecretRef {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}:{}", self.provider, self.path)?;
+		if let Some(key) = &self.key {
+			write!(f, "#{key}")?;
+		}
+		Ok(())
+	}
+}
+
+/// A SecretProvider fetches secrets from a backing store.
+#[async_trait::async_trait]
+pub trait SecretProvider: Send + Sync + Debug {
+	async fn fetch(&self, secret: &SecretRef) -> anyhow::Result<SecretString>;
+}
+
+#[derive(Debug)]
+struct CachedSecret {
+	value: SecretString,
+	fetched: Instant,
+}
+
+#[derive(Debug)]
+struct ResolverInner {
+	providers: HashMap<Strng, Arc<dyn SecretProvider>>,
+	cache: Mutex<HashMap<SecretRef, CachedSecret>>,
+	refresh_interval: Duration,
+}
+
+/// Resolver resolves secret references using a set of registered providers.
+#[derive(Debug, Clone)]
+pub struct Resolver {
+	inner: Arc<ResolverInner>,
+}
+
+impl Resolver {
+	/// Create a resolver with the built-in `env`, `vault`, and `k8s` providers.
+	pub fn new(client: Client) -> Self {
+		let mut providers: HashMap<Strng, Arc<dyn SecretProvider>> = HashMap::new();
+		providers.insert(strng::literal!("env"), Arc::new(EnvProvider));
+		providers.insert(
+			strng::literal!("vault"),
+			Arc::new(VaultProvider {
+				client: client.clone(),
+			}),
+		);
+		providers.insert(
+			strng::literal!("k8s"),
+			Arc::new(KubernetesProvider { client }),
+		);
+		Self::with_providers(providers, DEFAULT_REFRESH_INTERVAL)
+	}
+
+	pub fn with_providers(
+		providers: HashMap<Strng, Arc<dyn SecretProvider>>,
+		refresh_interval: Duration,
+	) -> Self {
+		Self {
+			inner: Arc::new(ResolverInner {
+				providers,
+				cache: Default::default(),
+				refresh_interval,
+			}),
+		}
+	}
+
+	pub fn refresh_interval(&self) -> Duration {
+		self.inner.refresh_interval
+	}
+
+	/// Returns true if no secrets have been resolved yet.
+	pub fn is_empty(&self) -> bool {
+		self.inner.cache.lock().unwrap().is_empty()
+	}
+
+	/// Parse a string as a secret reference. Strings that are not entirely `${secret:...}` are not
+	/// references, and return None; references that are malformed, or name an unknown provider, are
+	/// errors.
+	pub fn parse_reference(&self, s: &str) -> anyhow::Result<Option<SecretRef>> {
+		let Some(inner) = s
+			.strip_prefix(REFERENCE_PREFIX)
+			.and_then(|s| s.strip_suffix('}'))
+		else {
+			return Ok(None);
+		};
+		let (provider, rest) = inner
+			.split_once(':')
+			.with_context(|| format!("invalid secret reference {s}: expected <provider>:<path>"))?;
+		let (provider, _) = self
+			.inner
+			.providers
+			.get_key_value(provider)
+			.with_context(|| format!("invalid secret reference {s}: unknown provider {provider}"))?;
+		if rest.is_empty() || rest.contains(char::is_whitespace) {
+			anyhow::bail!("invalid secret reference {s}: the path must be set, without whitespace");
+		}
+		let (path, key) = match rest.split_once('#') {
+			Some((path, key)) => (path, Some(key.to_string())),
+			None => (rest, None),
+		};
+		Ok(Some(SecretRef {
+			provider: provider.clone(),
+			path: path.to_string(),
+			key,
+		}))
+	}
+
+	/// Resolve a single secret, using the cached value if it is still fresh.
+	pub async fn resolve(&self, secret: &SecretRef) -> anyhow::Result<SecretString> {
+		let cached = self
+			.inner
+			.cache
+			.lock()
+			.unwrap()
+			.get(secret)
+			.filter(|c| c.fetched.elapsed() < self.inner.refresh_interval)
+			.map(|c| c.value.clone());
+		if let Some(value) = cached {
+			return Ok(value);
+		}
+		let value = self.fetch(secret).await?;
+		self.inner.cache.lock().unwrap().insert(
+			secret.clone(),
+			CachedSecret {
+				value: value.clone(),
+				fetched: Instant::now(),
+			},
+		);
+		Ok(value)
+	}
+
+	async fn fetch(&self, secret: &SecretRef) -> anyhow::Result<SecretString> {
+		let provider = self
+			.inner
+			.providers
+			.get(&secret.provider)
+			.ok_or_else(|| anyhow::anyhow!("unknown secret provider {}", secret.provider))?;
+		provider
+			.fetch(secret)
+			.await
+			.with_context(|| format!("failed to resolve secret {secret}"))
+	}
+
+	/// Check that every secret reference in `value` is well formed and names a known provider,
+	/// without fetching any of them.
+	pub fn check_references(&self, value: &Value) -> anyhow::Result<()> {
+		self.references(value).map(|_| ())
+	}
+
+	/// The secret references in `value`, without fetching any of them.
+	pub fn references(&self, value: &Value) -> anyhow::Result<HashSet<SecretRef>> {
+		let mut refs = HashSet::new();
+		collect_references(self, value, &mut refs)?;
+		Ok(refs)
+	}
+
+	/// Replace all string values in `value` that are secret references with the secret they refer to.
+	pub async fn resolve_references(&self, value: &mut Value) -> anyhow::Result<()> {
+		let mut refs = HashSet::new();
+		collect_references(self, value, &mut refs)?;
+		if refs.is_empty() {
+			return Ok(());
+		}
+		let mut resolved = HashMap::with_capacity(refs.len());
+		for r in refs {
+			let secret = self.resolve(&r).await?;
+			resolved.insert(r, secret);
+		}
+		replace_references(self, value, &resolved);
+		Ok(())
+	}
+
+	/// Fetch all previously resolved secrets again, returning true if any of them changed.
+	/// If a secret cannot be fetched, the previous value is kept.
+	pub async fn refresh(&self) -> bool {
+		let refs: Vec<SecretRef> = self.inner.cache.lock().unwrap().keys().cloned().collect();
+		let mut changed = false;
+		for r in refs {
+			let value = match self.fetch(&r).await {
+				Ok(v) => v,
+				Err(e) => {
+					warn!(secret=%r, "failed to refresh secret, keeping previous value: {e:#}");
+					continue;
+				},
+			};
+			let prev = self.inner.cache.lock().unwrap().insert(
+				r.clone(),
+				CachedSecret {
+					value: value.clone(),
+					fetched: Instant::now(),
+				},
+			);
+			if prev.is_none_or(|p| p.value.expose_secret() != value.expose_secret()) {
+				info!(secret=%r, "secret rotated");
+				changed = true;
+			}
+		}
+		changed
+	}
+}
+
+fn collect_references(
+	resolver: &Resolver,
+	value: &Value,
+	refs: &mut HashSet<SecretRef>,
+) -> anyhow::Result<()> {
+	match value {
+		Value::String(s) => {
+			if let Some(r) = resolver.parse_reference(s)? {
+				refs.insert(r);
+			}
+		},
+		Value::Array(a) => {
+			for v in a {
+				collect_references(resolver, v, refs)?;
+			}
+		},
+		Value::Object(o) => {
+			for v in o.values() {
+				collect_references(resolver, v, refs)?;
+			}
+		},
+		_ => {},
+	}
+	Ok(())
+}
+
+fn replace_references(
+	resolver: &Resolver,
+	value: &mut Value,
+	resolved: &HashMap<SecretRef, SecretString>,
+) {
+	match value {
+		Value::String(s) => {
+			if let Some(secret) = resolver
+				.parse_reference(s)
+				.ok()
+				.flatten()
+				.and_then(|r| resolved.get(&r))
+			{
+				*s = secret.expose_secret().to_string();
+			}
+		},
+		Value::Array(a) => a
+			.iter_mut()
+			.for_each(|v| replace_references(resolver, v, resolved)),
+		Value::Object(o) => o
+			.values_mut()
+			.for_each(|v| replace_references(resolver, v, resolved)),
+		_ => {},
+	}
+}
+
+/// EnvProvider reads secrets from environment variables: `${secret:env:NAME}`.
+#[derive(Debug)]
+struct EnvProvider;
+
+#[async_trait::async_trait]
+impl SecretProvider for EnvProvider {
+	async fn fetch(&self, secret: &SecretRef) -> anyhow::Result<SecretString> {
+		if secret.key.is_some() {
+			anyhow::bail!("env secrets do not support a key");
+		}
+		let v = std::env::var(&secret.path)
+			.with_context(|| format!("environment variable {} not set", secret.path))?;
+		Ok(SecretString::from(v))
+	}
+}
+
+/// VaultProvider reads secrets from HashiCorp Vault: `${secret:vault:<path>#<key>}`.
+#[derive(Debug)]
+struct VaultProvider {
+	client: Client,
+}
+
+#[async_trait::async_trait]
+impl SecretProvider for VaultProvider {
+	async fn fetch(&self, secret: &SecretRef) -> anyhow::Result<SecretString> {
+		let key = secret
+			.key
+			.as_deref()
+			.context("vault secrets require a key (${secret:vault:<path>#<key>})")?;
+		let addr = std::env::var("VAULT_ADDR").context("VAULT_ADDR is not set")?;
+		let token = std::env::var("VAULT_TOKEN").context("VAULT_TOKEN is not set")?;
+		let mut req = ::http::Request::builder()
+			.uri(format!(
+				"{}/v1/{}",
+				addr.trim_end_matches('/'),
+				secret.path.trim_start_matches('/')
+			))
+			.header("X-Vault-Token", token);
+		if let Ok(ns) = std::env::var("VAULT_NAMESPACE") {
+			req = req.header("X-Vault-Namespace", ns);
+		}
+		let resp = self.client.simple_call(req.body(Body::empty())?).await?;
+		if !resp.status().is_success() {
+			anyhow::bail!("vault returned status {}", resp.status());
+		}
+		let body: Value = crate::json::from_response_body(resp).await?;
+		// KV v2 nests the secret under data.data, while KV v1 and most other engines use data.
+		let data = body
+			.pointer("/data/data")
+			.filter(|d| d.is_object())
+			.or_else(|| body.get("data"))
+			.context("vault response has no data")?;
+		let value = data
+			.get(key)
+			.and_then(Value::as_str)
+			.with_context(|| format!("key {key} not found"))?;
+		Ok(SecretString::from(value.to_string()))
+	}
+}
+
+/// KubernetesProvider reads secrets from the Kubernetes API:
+/// `${secret:k8s:<namespace>/<name>#<key>}`.
+#[derive(Debug)]
+struct KubernetesProvider {
+	client: Client,
+}
+
+#[async_trait::async_trait]
+impl SecretProvider for KubernetesProvider {
+	async fn fetch(&self, secret: &SecretRef) -> anyhow::Result<SecretString> {
+		let key = secret
+			.key
+			.as_deref()
+			.context("k8s secrets require a key (${secret:k8s:<namespace>/<name>#<key>})")?;
+		let (namespace, name) = secret
+			.path
+			.split_once('/')
+			.context("k8s secrets must be in the form ${secret:k8s:<namespace>/<name>#<key>}")?;
+		let body = crate::control::kubernetes::get(
+			&self.client,
+			&format!("/api/v1/namespaces/{namespace}/secrets/{name}"),
+		)
+		.await?
+		.context("secret not found")?;
+		let value = body
+			.get("data")
+			.and_then(|d| d.get(key))
+			.and_then(Value::as_str)
+			.with_context(|| format!("key {key} not found"))?;
+		let decoded = base64::engine::general_purpose::STANDARD.decode(value)?;
+		Ok(SecretString::from(String::from_utf8(decoded)?))
+	}
+}
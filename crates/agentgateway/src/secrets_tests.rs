@@ -0,0 +1,103 @@
+use super::*;
+
+#[derive(Debug, Default)]
+struct StaticProvider {
+	value: Mutex<String>,
+}
+
+#[async_trait::async_trait]
+impl SecretProvider for StaticProvider {
+	async fn fetch(&self, secret: &SecretRef) -> anyhow::Result<SecretString> {
+		Ok(SecretString::from(format!(
+			"{}-{}-{}",
+			self.value.lock().unwrap(),
+			secret.path,
+			secret.key.clone().unwrap_or_default()
+		)))
+	}
+}
+
+fn resolver(p: Arc<StaticProvider>, refresh: Duration) -> Resolver {
+	Resolver::with_providers(
+		HashMap::from([(strng::literal!("test"), p as Arc<dyn SecretProvider>)]),
+		refresh,
+	)
+}
+
+#[test]
+fn test_parse_reference() {
+	let r = resolver(Default::default(), DEFAULT_REFRESH_INTERVAL);
+	assert_eq!(
+		r.parse_reference("${secret:test:a/b#c}").unwrap(),
+		Some(SecretRef {
+			provider: strng::literal!("test"),
+			path: "a/b".to_string(),
+			key: Some("c".to_string()),
+		})
+	);
+	assert_eq!(
+		r.parse_reference("${secret:test:a}")
+			.unwrap()
+			.map(|r| r.key),
+		Some(None::<String>)
+	);
+	// Only the explicit syntax is a reference
+	assert_eq!(r.parse_reference("test:a#b").unwrap(), None);
+	assert_eq!(r.parse_reference("env:HOME").unwrap(), None);
+	assert_eq!(
+		r.parse_reference("${secret:test:a#b} suffix").unwrap(),
+		None
+	);
+	assert_eq!(r.parse_reference("plain").unwrap(), None);
+	// Explicit references must be valid
+	assert!(r.parse_reference("${secret:other:a#b}").is_err());
+	assert!(r.parse_reference("${secret:test:}").is_err());
+	assert!(
+		r.parse_reference("${secret:test: not a reference}")
+			.is_err()
+	);
+	assert!(r.parse_reference("${secret:test}").is_err());
+}
+
+#[tokio::test]
+async fn test_resolve_references() {
+	let r = resolver(Default::default(), DEFAULT_REFRESH_INTERVAL);
+	let mut v = serde_json::json!({
+		"key": "${secret:test:a#b}",
+		"nested": [{"key": "${secret:test:c}"}],
+		"plain": "test:a#b",
+		"num": 1,
+	});
+	r.resolve_references(&mut v).await.unwrap();
+	assert_eq!(
+		v,
+		serde_json::json!({
+			"key": "-a-b",
+			"nested": [{"key": "-c-"}],
+			"plain": "test:a#b",
+			"num": 1,
+		})
+	);
+}
+
+#[tokio::test]
+async fn test_refresh() {
+	let p = Arc::new(StaticProvider::default());
+	let r = resolver(p.clone(), DEFAULT_REFRESH_INTERVAL);
+	let sr = r.parse_reference("${secret:test:a#b}").unwrap().unwrap();
+	assert_eq!(r.resolve(&sr).await.unwrap().expose_secret(), "-a-b");
+	assert!(!r.refresh().await);
+
+	*p.value.lock().unwrap() = "rotated".to_string();
+	// Still cached
+	assert_eq!(r.resolve(&sr).await.unwrap().expose_secret(), "-a-b");
+	assert!(r.refresh().await);
+	assert_eq!(r.resolve(&sr).await.unwrap().expose_secret(), "rotated-a-b");
+}
+
+#[tokio::test]
+async fn test_resolve_invalid_reference() {
+	let r = resolver(Default::default(), DEFAULT_REFRESH_INTERVAL);
+	let mut v = serde_json::json!({"key": "${secret:unknown:a#b}"});
+	assert!(r.resolve_references(&mut v).await.is_err());
+}
@@ -53,6 +53,14 @@ attribute_alias! {
 		#[apply(schema!)] = #[serde_with::serde_as] #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)] #[serde(rename_all = "camelCase", deny_unknown_fields)] #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))];
 }
 
+/// Generate the JSON Schema of `T`, with subschemas inlined so it reads as one document.
+#[cfg(feature = "schema")]
+pub fn json_schema<T: JsonSchema>() -> anyhow::Result<String> {
+	let settings = schemars::generate::SchemaSettings::default().with(|s| s.inline_subschemas = true);
+	let schema = schemars::SchemaGenerator::new(settings).into_root_schema_for::<T>();
+	Ok(serde_json::to_string_pretty(&schema)?)
+}
+
 pub fn is_default<T: Default + PartialEq>(t: &T) -> bool {
 	*t == Default::default()
 }
@@ -128,6 +136,35 @@ pub mod serde_dur_option {
 	}
 }
 
+/// File permissions, as an octal string such as `0660`.
+pub mod serde_file_mode_option {
+	use serde::{Deserialize, Deserializer, Serializer};
+
+	pub fn serialize<S: Serializer>(t: &Option<u32>, serializer: S) -> Result<S::Ok, S::Error> {
+		match t {
+			None => serializer.serialize_none(),
+			Some(t) => serializer.serialize_str(&format!("{t:04o}")),
+		}
+	}
+
+	pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<u32>, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		let input: Option<String> = Option::deserialize(deserializer)?;
+		let Some(input) = input else {
+			return Ok(None);
+		};
+		let digits = input.strip_prefix("0o").unwrap_or(&input);
+		match u32::from_str_radix(digits, 8) {
+			Ok(mode) if mode <= 0o7777 => Ok(Some(mode)),
+			_ => Err(serde::de::Error::custom(format!(
+				"invalid file mode {input:?}, expected octal such as \"0660\""
+			))),
+		}
+	}
+}
+
 pub fn ser_display_option<S: Serializer, T: Display>(
 	t: &Option<T>,
 	serializer: S,
@@ -0,0 +1,160 @@
+//! Phased graceful shutdown.
+//!
+//! Once a shutdown is requested, the gateway moves through each [`Phase`] in order. Each phase
+//! has its own deadline, after which the next one starts regardless, and the progress through the
+//! phases is reported on the readiness server's `/healthz` endpoint.
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use tokio::time::Instant;
+
+use crate::*;
+
+#[cfg(test)]
+#[path = "shutdown_tests.rs"]
+mod tests;
+
+/// Deadlines of each shutdown phase. The phases accepting and serving connections default to the
+/// connection termination deadlines, so configurations that only set those keep their behavior.
+#[apply(schema_de!)]
+#[derive(Default)]
+pub struct RawConfig {
+	/// How long to keep accepting connections once the gateway reports it is not ready, so load
+	/// balancers stop sending new ones. New connections are asked not to be reused. Defaults to
+	/// `connectionMinTerminationDeadline`.
+	#[serde(default, with = "serde_dur_option")]
+	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+	stop_accepting: Option<Duration>,
+	/// How long to wait for mesh nodes and event stream subscribers to disconnect after they are
+	/// told the gateway is shutting down. Defaults to 1s.
+	#[serde(default, with = "serde_dur_option")]
+	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+	notify: Option<Duration>,
+	/// How long to wait for in-flight HTTP requests to complete. Defaults to
+	/// `connectionTerminationDeadline`.
+	#[serde(default, with = "serde_dur_option")]
+	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+	http: Option<Duration>,
+	/// How long to wait, once MCP sessions still open after the HTTP phase are closed, for their
+	/// connections to complete. Connections still open after this are closed forcefully. Defaults
+	/// to 5s.
+	#[serde(default, with = "serde_dur_option")]
+	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+	mcp: Option<Duration>,
+	/// How long to wait for quota usage, the mesh ledger, logs, and traces to be flushed. Defaults
+	/// to 5s.
+	#[serde(default, with = "serde_dur_option")]
+	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+	flush: Option<Duration>,
+}
+
+impl RawConfig {
+	pub fn resolve(self, min_deadline: Duration, max_deadline: Duration) -> Config {
+		Config {
+			stop_accepting: self.stop_accepting.unwrap_or(min_deadline),
+			notify: self.notify.unwrap_or(Duration::from_secs(1)),
+			http: self.http.unwrap_or(max_deadline),
+			mcp: self.mcp.unwrap_or(Duration::from_secs(5)),
+			flush: self.flush.unwrap_or(Duration::from_secs(5)),
+		}
+	}
+}
+
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Config {
+	#[serde(with = "serde_dur")]
+	pub stop_accepting: Duration,
+	#[serde(with = "serde_dur")]
+	pub notify: Duration,
+	#[serde(with = "serde_dur")]
+	pub http: Duration,
+	#[serde(with = "serde_dur")]
+	pub mcp: Duration,
+	#[serde(with = "serde_dur")]
+	pub flush: Duration,
+}
+
+impl Config {
+	/// How long connections may stay open once the gateway stops accepting new ones, before they
+	/// are closed forcefully.
+	pub fn connection_deadline(&self) -> Duration {
+		self.notify + self.http + self.mcp
+	}
+}
+
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Phase {
+	/// Readiness fails, and new connections are accepted but discouraged.
+	StopAccepting,
+	/// Mesh nodes and event stream subscribers are told the gateway is shutting down.
+	Notify,
+	/// In-flight HTTP requests complete.
+	DrainHttp,
+	/// Open MCP sessions are closed, and their connections complete.
+	DrainMcp,
+	/// Quota usage, the mesh ledger, logs, and traces are flushed.
+	Flush,
+	/// The process exits.
+	Exit,
+}
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PhaseProgress {
+	pub phase: Phase,
+	pub started_at: DateTime<Utc>,
+	/// How long the phase took, once it has ended.
+	#[serde(skip_serializing_if = "Option::is_none", with = "serde_dur_option")]
+	pub duration: Option<Duration>,
+	/// Whether the phase ended because its deadline passed, rather than because it completed.
+	pub timed_out: bool,
+}
+
+/// The shutdown phases started so far, shared between the shutdown sequence and the readiness
+/// server.
+#[derive(Clone, Debug, Default)]
+pub struct Progress(Arc<Mutex<Vec<(PhaseProgress, Instant)>>>);
+
+impl Progress {
+	/// End the current phase, and start `phase`.
+	pub fn enter(&self, phase: Phase) {
+		let mut phases = self.0.lock();
+		if let Some((current, started)) = phases.last_mut() {
+			current.duration.get_or_insert_with(|| started.elapsed());
+		}
+		info!(?phase, "shutdown phase started");
+		phases.push((
+			PhaseProgress {
+				phase,
+				started_at: Utc::now(),
+				duration: None,
+				timed_out: false,
+			},
+			Instant::now(),
+		));
+	}
+
+	/// Run `phase` until `fut` completes or `deadline` passes. Returns whether `fut` completed.
+	pub async fn run(&self, phase: Phase, deadline: Duration, fut: impl Future<Output = ()>) -> bool {
+		self.enter(phase);
+		if tokio::time::timeout(deadline, fut).await.is_ok() {
+			return true;
+		}
+		warn!(?phase, ?deadline, "shutdown phase deadline passed");
+		if let Some((current, _)) = self.0.lock().last_mut() {
+			current.timed_out = true;
+		}
+		false
+	}
+
+	/// The current phase, or `None` if the gateway is not shutting down.
+	pub fn phase(&self) -> Option<Phase> {
+		self.0.lock().last().map(|(p, _)| p.phase)
+	}
+
+	pub fn phases(&self) -> Vec<PhaseProgress> {
+		self.0.lock().iter().map(|(p, _)| p.clone()).collect()
+	}
+}
@@ -0,0 +1,73 @@
+use super::*;
+
+fn raw(cfg: serde_json::Value) -> RawConfig {
+	serde_json::from_value(cfg).unwrap()
+}
+
+#[test]
+fn test_config() {
+	let min = Duration::from_secs(2);
+	let max = Duration::from_secs(25);
+	let cfg = RawConfig::default().resolve(min, max);
+	assert_eq!(cfg.stop_accepting, min);
+	assert_eq!(cfg.notify, Duration::from_secs(1));
+	assert_eq!(cfg.http, max);
+	assert_eq!(cfg.mcp, Duration::from_secs(5));
+	assert_eq!(cfg.flush, Duration::from_secs(5));
+	assert_eq!(cfg.connection_deadline(), Duration::from_secs(31));
+
+	let cfg = raw(serde_json::json!({
+		"stopAccepting": "0s",
+		"notify": "500ms",
+		"http": "10s",
+		"mcp": "1s",
+		"flush": "2s",
+	}))
+	.resolve(min, max);
+	assert_eq!(cfg.stop_accepting, Duration::ZERO);
+	assert_eq!(cfg.notify, Duration::from_millis(500));
+	assert_eq!(cfg.http, Duration::from_secs(10));
+	assert_eq!(cfg.mcp, Duration::from_secs(1));
+	assert_eq!(cfg.flush, Duration::from_secs(2));
+	assert_eq!(cfg.connection_deadline(), Duration::from_millis(11500));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_progress() {
+	let progress = Progress::default();
+	assert_eq!(progress.phase(), None);
+
+	progress.enter(Phase::StopAccepting);
+	tokio::time::advance(Duration::from_secs(1)).await;
+	assert!(
+		progress
+			.run(Phase::DrainHttp, Duration::from_secs(5), async {})
+			.await
+	);
+	assert!(
+		!progress
+			.run(
+				Phase::DrainMcp,
+				Duration::from_secs(2),
+				std::future::pending()
+			)
+			.await
+	);
+	progress.enter(Phase::Exit);
+	assert_eq!(progress.phase(), Some(Phase::Exit));
+
+	let phases = progress.phases();
+	let summary: Vec<_> = phases
+		.iter()
+		.map(|p| (p.phase, p.duration, p.timed_out))
+		.collect();
+	assert_eq!(
+		summary,
+		vec![
+			(Phase::StopAccepting, Some(Duration::from_secs(1)), false),
+			(Phase::DrainHttp, Some(Duration::ZERO), false),
+			(Phase::DrainMcp, Some(Duration::from_secs(2)), true),
+			(Phase::Exit, None, false),
+		]
+	);
+}
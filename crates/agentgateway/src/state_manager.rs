@@ -1,16 +1,27 @@
+use std::collections::VecDeque;
 use std::path::{Path, PathBuf, absolute};
+use std::str::FromStr;
+use std::sync::OnceLock;
 use std::time::Duration;
 
 use agent_core::prelude::*;
+use aws_lc_rs::digest;
+use itertools::Itertools;
 use notify::{EventKind, RecursiveMode};
+use parking_lot::Mutex;
 use tokio::fs;
 
 use crate::client::Client;
 use crate::store::Stores;
+use crate::telemetry::events;
 use crate::types::agent::ListenerTarget;
 use crate::types::proto::agent::Resource as ADPResource;
 use crate::types::proto::workload::Address as XdsAddress;
-use crate::{ConfigSource, client, control, store};
+use crate::{ConfigSource, client, control, secrets, store};
+
+#[cfg(test)]
+#[path = "state_manager_tests.rs"]
+mod tests;
 
 #[derive(serde::Serialize)]
 pub struct StateManager {
@@ -27,6 +38,213 @@ pub const AUTHORIZATION_TYPE: Strng =
 pub const ADP_TYPE: Strng =
 	strng::literal!("type.googleapis.com/agentgateway.dev.resource.Resource");
 
+static CONFIG_STATUS: Mutex<Option<ConfigStatus>> = Mutex::new(None);
+static CONTROL_PLANES: OnceLock<agent_xds::ControlPlanes> = OnceLock::new();
+static RELOADS: OnceLock<tokio::sync::mpsc::Sender<ReloadRequest>> = OnceLock::new();
+static CONFIG_HISTORY: Mutex<VecDeque<ConfigGeneration>> = Mutex::new(VecDeque::new());
+
+/// The number of applied local configs kept for rollback.
+const MAX_GENERATIONS: usize = 10;
+
+type ReloadRequest = (Reload, tokio::sync::oneshot::Sender<anyhow::Result<()>>);
+
+#[derive(Debug)]
+enum Reload {
+	/// Reload these parts of the config from its source.
+	Scopes(Vec<ConfigScope>),
+	/// Re-apply a previous generation of the config.
+	Rollback(u64),
+}
+
+/// A local config that was applied in full. Each applied config that differs from the one before it
+/// is a new generation; reloads of only part of the config are not recorded.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigGeneration {
+	pub generation: u64,
+	/// The SHA-256 of the config, hex encoded.
+	pub hash: String,
+	pub applied: String,
+	/// The generation this config was rolled back to, if it was applied by a rollback.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub rollback_of: Option<u64>,
+	#[serde(skip)]
+	content: Arc<str>,
+}
+
+/// A part of the local config that can be reloaded without touching the rest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigScope {
+	/// Binds, and the listeners and routes within them.
+	Listeners,
+	Backends,
+	/// Policies, other than telemetry policies.
+	Policies,
+	/// Policies that only configure telemetry, such as access logging and tracing.
+	Telemetry,
+	/// Services and workloads.
+	Services,
+}
+
+impl ConfigScope {
+	pub const ALL: [ConfigScope; 5] = [
+		ConfigScope::Listeners,
+		ConfigScope::Backends,
+		ConfigScope::Policies,
+		ConfigScope::Telemetry,
+		ConfigScope::Services,
+	];
+
+	/// Whether `scopes` covers the whole config.
+	pub fn all(scopes: &[ConfigScope]) -> bool {
+		ConfigScope::ALL.iter().all(|s| scopes.contains(s))
+	}
+}
+
+impl FromStr for ConfigScope {
+	type Err = anyhow::Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"listeners" => Ok(ConfigScope::Listeners),
+			"backends" => Ok(ConfigScope::Backends),
+			"policies" => Ok(ConfigScope::Policies),
+			"telemetry" => Ok(ConfigScope::Telemetry),
+			"services" => Ok(ConfigScope::Services),
+			_ => anyhow::bail!(
+				"unknown config scope {s}; expected one of listeners, backends, policies, telemetry, services"
+			),
+		}
+	}
+}
+
+impl std::fmt::Display for ConfigScope {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(match self {
+			ConfigScope::Listeners => "listeners",
+			ConfigScope::Backends => "backends",
+			ConfigScope::Policies => "policies",
+			ConfigScope::Telemetry => "telemetry",
+			ConfigScope::Services => "services",
+		})
+	}
+}
+
+/// The outcome of loading the local config, served on the admin server so a rejected reload is
+/// visible without reading the logs.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigStatus {
+	source: ConfigSource,
+	/// The number of times the config was loaded and applied.
+	loads: u64,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	last_loaded: Option<String>,
+	/// The error of the last load, if it failed. The previously applied config is still served.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	error: Option<ConfigError>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ConfigError {
+	timestamp: String,
+	message: String,
+}
+
+/// The status of each XDS control plane, if XDS is used.
+pub fn control_plane_status() -> Option<Vec<agent_xds::ControlPlaneStatus>> {
+	CONTROL_PLANES.get().map(|c| c.status())
+}
+
+/// The XDS resources most recently rejected, if XDS is used.
+pub fn xds_rejections() -> Option<Vec<agent_xds::Rejection>> {
+	CONTROL_PLANES.get().map(|c| c.rejections())
+}
+
+/// Whether the config served is the last-known-good XDS snapshot, as no control plane has been
+/// reached since startup.
+pub fn xds_config_stale() -> bool {
+	CONTROL_PLANES.get().is_some_and(|c| c.stale())
+}
+
+/// Reload the given parts of the local config now, leaving the others as they are. Returns None if
+/// there is no local config.
+pub async fn reload_local_config(scopes: Vec<ConfigScope>) -> Option<anyhow::Result<()>> {
+	request_reload(Reload::Scopes(scopes)).await
+}
+
+/// Re-apply a previous generation of the local config. Returns None if there is no local config.
+pub async fn rollback_local_config(generation: u64) -> Option<anyhow::Result<()>> {
+	request_reload(Reload::Rollback(generation)).await
+}
+
+async fn request_reload(reload: Reload) -> Option<anyhow::Result<()>> {
+	let (tx, rx) = tokio::sync::oneshot::channel();
+	RELOADS.get()?.send((reload, tx)).await.ok()?;
+	rx.await.ok()
+}
+
+/// The generations of the local config that can be rolled back to, oldest first.
+pub fn config_history() -> Vec<ConfigGeneration> {
+	CONFIG_HISTORY.lock().iter().cloned().collect()
+}
+
+fn record_config_generation(content: &str, rollback_of: Option<u64>) {
+	push_config_generation(&mut CONFIG_HISTORY.lock(), content, rollback_of);
+}
+
+fn push_config_generation(
+	history: &mut VecDeque<ConfigGeneration>,
+	content: &str,
+	rollback_of: Option<u64>,
+) {
+	let hash = hex::encode(digest::digest(&digest::SHA256, content.as_bytes()).as_ref());
+	let last = history.back();
+	if last.is_some_and(|g| g.hash == hash) {
+		return;
+	}
+	let generation = last.map(|g| g.generation + 1).unwrap_or(1);
+	if history.len() >= MAX_GENERATIONS {
+		history.pop_front();
+	}
+	history.push_back(ConfigGeneration {
+		generation,
+		hash,
+		applied: agent_core::telemetry::render_current_time(),
+		rollback_of,
+		content: content.into(),
+	});
+}
+
+/// The status of the local config, if there is one.
+pub fn config_status() -> Option<ConfigStatus> {
+	CONFIG_STATUS.lock().clone()
+}
+
+fn record_config_status(source: &ConfigSource, error: Option<&anyhow::Error>) {
+	let now = agent_core::telemetry::render_current_time();
+	let mut status = CONFIG_STATUS.lock();
+	let status = status.get_or_insert_with(|| ConfigStatus {
+		source: source.clone(),
+		loads: 0,
+		last_loaded: None,
+		error: None,
+	});
+	match error {
+		Some(e) => {
+			status.error = Some(ConfigError {
+				timestamp: now,
+				message: format!("{e:#}"),
+			})
+		},
+		None => {
+			status.loads += 1;
+			status.last_loaded = Some(now);
+			status.error = None;
+		},
+	}
+}
+
 impl StateManager {
 	pub async fn new(
 		config: &crate::XDSConfig,
@@ -36,33 +254,57 @@ impl StateManager {
 	) -> anyhow::Result<Self> {
 		let stores = Stores::new();
 
-		let xds_client = if config.address.is_some() {
-			let connector = control::grpc_connector(
-				client.clone(),
-				config.address.as_ref().unwrap().clone(),
-				config.auth.clone(),
-				config.ca_cert.clone(),
-			)
-			.await?;
-			Some(
-				agent_xds::Config::new(
-					agent_xds::GrpcClient::new(connector),
-					config.gateway.clone(),
-					config.namespace.clone(),
+		let xds_client = if let Some(address) = &config.address {
+			let connect = |address: &String| {
+				control::grpc_connector(
+					client.clone(),
+					address.clone(),
+					config.auth.clone(),
+					config.ca_cert.clone(),
 				)
-				.with_watched_handler::<XdsAddress>(ADDRESS_TYPE, stores.clone().discovery.clone())
-				.with_watched_handler::<ADPResource>(ADP_TYPE, stores.clone().binds.clone())
-				// .with_watched_handler::<XdsAuthorization>(AUTHORIZATION_TYPE, state)
-				.build(xds_metrics, awaiting_ready),
+			};
+			let mut xds = agent_xds::Config::new(
+				agent_xds::ControlPlane {
+					address: strng::new(address),
+					client: agent_xds::GrpcClient::new(connect(address).await?),
+				},
+				config.gateway.clone(),
+				config.namespace.clone(),
+			);
+			for address in &config.fallback_addresses {
+				xds = xds.with_fallback(agent_xds::ControlPlane {
+					address: strng::new(address),
+					client: agent_xds::GrpcClient::new(connect(address).await?),
+				});
+			}
+			if let Some(path) = &config.snapshot_path {
+				xds = xds.with_snapshot(path.clone());
+			}
+			Some(
+				xds
+					.with_watched_handler::<XdsAddress>(ADDRESS_TYPE, stores.clone().discovery.clone())
+					.with_watched_handler::<ADPResource>(ADP_TYPE, stores.clone().binds.clone())
+					.with_nack_handler(|type_url, error| {
+						events::publish(events::EventKind::ConfigRejected {
+							source: type_url.to_string(),
+							error: error.to_string(),
+						})
+					})
+					// .with_watched_handler::<XdsAuthorization>(AUTHORIZATION_TYPE, state)
+					.build(xds_metrics, awaiting_ready),
 			)
 		} else {
 			None
 		};
+		if let Some(xds) = &xds_client {
+			let _ = CONTROL_PLANES.set(xds.control_planes());
+		}
 		if let Some(cfg) = &config.local_config {
 			let local_client = LocalClient {
 				stores: stores.clone(),
 				cfg: cfg.clone(),
-				client,
+				secrets: secrets::Resolver::new(client.clone()),
+				client: client.clone(),
 				gateway: ListenerTarget {
 					gateway_name: config.gateway.clone(),
 					gateway_namespace: config.namespace.clone(),
@@ -71,6 +313,48 @@ impl StateManager {
 			};
 			local_client.run().await?;
 		}
+		if let Some(cfg) = &config.remote_config {
+			control::remote::Controller {
+				cfg: cfg.clone(),
+				stores: stores.clone(),
+				secrets: secrets::Resolver::new(client.clone()),
+				client: client.clone(),
+				gateway: ListenerTarget {
+					gateway_name: config.gateway.clone(),
+					gateway_namespace: config.namespace.clone(),
+					listener_name: None,
+				},
+			}
+			.run()?;
+		}
+		if let Some(cfg) = &config.consul {
+			control::consul::Controller {
+				cfg: cfg.clone(),
+				stores: stores.clone(),
+				secrets: secrets::Resolver::new(client.clone()),
+				client: client.clone(),
+				gateway: ListenerTarget {
+					gateway_name: config.gateway.clone(),
+					gateway_namespace: config.namespace.clone(),
+					listener_name: None,
+				},
+			}
+			.run();
+		}
+		if let Some(cfg) = &config.kubernetes {
+			control::kubernetes::Controller {
+				cfg: cfg.clone(),
+				stores: stores.clone(),
+				secrets: secrets::Resolver::new(client.clone()),
+				client,
+				gateway: ListenerTarget {
+					gateway_name: config.gateway.clone(),
+					gateway_namespace: config.namespace.clone(),
+					listener_name: None,
+				},
+			}
+			.run();
+		}
 		Ok(Self { stores, xds_client })
 	}
 
@@ -92,6 +376,7 @@ pub struct LocalClient {
 	pub cfg: ConfigSource,
 	pub stores: Stores,
 	pub client: Client,
+	pub secrets: secrets::Resolver,
 	pub gateway: ListenerTarget,
 }
 
@@ -101,13 +386,96 @@ impl LocalClient {
 			// Load initial state then watch
 			self.watch_config_file(path).await?;
 		} else {
-			// Load it once
-			self.reload_config(PreviousState::default()).await?;
+			// Load it once, then only reload if a referenced secret rotates or a reload is requested
+			let mut next_state = self.reload_config(PreviousState::default()).await?;
+			let mut reloads = Self::reload_requests();
+			let lc = self.clone();
+			tokio::task::spawn(async move {
+				let mut rotation = tokio::time::interval(lc.secrets.refresh_interval());
+				rotation.tick().await;
+				loop {
+					tokio::select! {
+						Some((reload, done)) = reloads.recv() => {
+							let _ = done.send(lc.reload_requested(&mut next_state, reload).await);
+						},
+						_ = rotation.tick(), if !lc.secrets.is_empty() => {
+							lc.reload_on_secret_rotation(&mut next_state).await;
+						},
+						else => break,
+					}
+				}
+			});
 		}
 
 		Ok(())
 	}
 
+	fn publish_rejected(e: &anyhow::Error) {
+		events::publish(events::EventKind::ConfigRejected {
+			source: "file".to_string(),
+			error: e.to_string(),
+		});
+	}
+
+	fn reload_requests() -> tokio::sync::mpsc::Receiver<ReloadRequest> {
+		let (tx, rx) = tokio::sync::mpsc::channel(1);
+		if RELOADS.set(tx).is_err() {
+			warn!("config reloads are already served by another local config");
+		}
+		rx
+	}
+
+	/// Reloads the config as requested from the admin server.
+	async fn reload_requested(
+		&self,
+		next_state: &mut PreviousState,
+		reload: Reload,
+	) -> anyhow::Result<()> {
+		let res = match reload {
+			Reload::Scopes(scopes) => {
+				info!(
+					"Reload of {} requested, reloading config...",
+					scopes.iter().join(", ")
+				);
+				self.reload_config_scoped(next_state.clone(), &scopes).await
+			},
+			Reload::Rollback(generation) => {
+				info!("Rollback to generation {generation} requested, reloading config...");
+				self.rollback_config(next_state.clone(), generation).await
+			},
+		};
+		match res {
+			Ok(nxt) => {
+				*next_state = nxt;
+				info!("Config reloaded successfully");
+				Ok(())
+			},
+			Err(e) => {
+				error!("Failed to reload config: {}", e);
+				Self::publish_rejected(&e);
+				Err(e)
+			},
+		}
+	}
+
+	/// Re-fetches all referenced secrets and reloads the config if any of them changed.
+	async fn reload_on_secret_rotation(&self, next_state: &mut PreviousState) {
+		if !self.secrets.refresh().await {
+			return;
+		}
+		info!("Secret rotated, reloading config...");
+		match self.reload_config(next_state.clone()).await {
+			Ok(nxt) => {
+				*next_state = nxt;
+				info!("Config reloaded successfully")
+			},
+			Err(e) => {
+				error!("Failed to reload config: {}", e);
+				Self::publish_rejected(&e);
+			},
+		}
+	}
+
 	async fn watch_config_file(&self, path: &Path) -> anyhow::Result<()> {
 		let (tx, mut rx) = tokio::sync::mpsc::channel(1);
 
@@ -133,12 +501,32 @@ impl LocalClient {
 
 		let lc: LocalClient = self.to_owned();
 		let mut next_state = lc.reload_config(PreviousState::default()).await?;
+		let mut reloads = Self::reload_requests();
 		tokio::task::spawn(async move {
 			// Resolve initial target (symlink or not)
 			let mut real_config_path = lc.resolve_symlink(&abspath).await.ok();
 
-			// Handle file change events
-			while let Some(Ok(events)) = rx.recv().await {
+			let mut rotation = tokio::time::interval(lc.secrets.refresh_interval());
+			rotation.tick().await;
+
+			// Handle file change events, secret rotation, and requested reloads
+			loop {
+				let events = tokio::select! {
+					res = rx.recv() => match res {
+						Some(Ok(events)) => events,
+						_ => break,
+					},
+					Some((reload, done)) = reloads.recv() => {
+						let _ = done.send(lc.reload_requested(&mut next_state, reload).await);
+						continue;
+					},
+					_ = rotation.tick() => {
+						if !lc.secrets.is_empty() {
+							lc.reload_on_secret_rotation(&mut next_state).await;
+						}
+						continue;
+					},
+				};
 				let current_config_path = lc.resolve_symlink(&abspath).await.ok();
 
 				// Only process if we have actual content changes
@@ -156,7 +544,8 @@ impl LocalClient {
 							info!("Config reloaded successfully")
 						},
 						Err(e) => {
-							error!("Failed to reload config: {}", e)
+							error!("Failed to reload config: {}", e);
+							Self::publish_rejected(&e);
 						},
 					}
 				}
@@ -183,26 +572,89 @@ impl LocalClient {
 	}
 
 	async fn reload_config(&self, prev: PreviousState) -> anyhow::Result<PreviousState> {
-		let config_content = self.cfg.read_to_string().await?;
-		let config = crate::types::local::NormalizedLocalConfig::from(
+		self.reload_config_scoped(prev, &ConfigScope::ALL).await
+	}
+
+	async fn reload_config_scoped(
+		&self,
+		prev: PreviousState,
+		scopes: &[ConfigScope],
+	) -> anyhow::Result<PreviousState> {
+		let res = match self.cfg.read_to_string().await {
+			Ok(content) => {
+				let res = self.load_config(prev, &content, scopes).await;
+				// A partial reload leaves the running config a mix of this content and the last, so only
+				// full reloads are a generation that can be rolled back to.
+				if res.is_ok() && ConfigScope::all(scopes) {
+					record_config_generation(&content, None);
+				}
+				res
+			},
+			Err(e) => Err(e),
+		};
+		record_config_status(&self.cfg, res.as_ref().err());
+		res
+	}
+
+	/// Re-apply a previous generation of the config, through the same path as a reload.
+	async fn rollback_config(
+		&self,
+		prev: PreviousState,
+		generation: u64,
+	) -> anyhow::Result<PreviousState> {
+		let content = CONFIG_HISTORY
+			.lock()
+			.iter()
+			.find(|g| g.generation == generation)
+			.map(|g| g.content.clone())
+			.ok_or_else(|| anyhow::anyhow!("config generation {generation} is not in the history"))?;
+		let res = self.load_config(prev, &content, &ConfigScope::ALL).await;
+		if res.is_ok() {
+			record_config_generation(&content, Some(generation));
+		}
+		record_config_status(&self.cfg, res.as_ref().err());
+		res
+	}
+
+	/// Load the config and apply the parts of it in `scopes` to the stores. The whole config is
+	/// validated first, so a rejected config leaves the stores untouched.
+	async fn load_config(
+		&self,
+		prev: PreviousState,
+		config_content: &str,
+		scopes: &[ConfigScope],
+	) -> anyhow::Result<PreviousState> {
+		let config = crate::types::local::NormalizedLocalConfig::from_with_secrets(
 			self.client.clone(),
+			&self.secrets,
 			self.gateway.clone(),
-			config_content.as_str(),
+			config_content,
 		)
 		.await?;
+		config.check_services()?;
 		info!("loaded config from {:?}", self.cfg);
 
 		// Sync the state
-		let next_binds =
-			self
-				.stores
-				.binds
-				.sync_local(config.binds, config.policies, config.backends, prev.binds);
-		let next_discovery =
+		let scoped = |scope| scopes.contains(&scope);
+		let (telemetry, policies): (Vec<_>, Vec<_>) = config
+			.policies
+			.into_iter()
+			.partition(|p| p.policy.is_telemetry());
+		let next_binds = self.stores.binds.sync_local_scoped(
+			scoped(ConfigScope::Listeners).then_some(config.binds),
+			scoped(ConfigScope::Policies).then_some(policies),
+			scoped(ConfigScope::Telemetry).then_some(telemetry),
+			scoped(ConfigScope::Backends).then_some(config.backends),
+			prev.binds,
+		);
+		let next_discovery = if scoped(ConfigScope::Services) {
 			self
 				.stores
 				.discovery
-				.sync_local(config.services, config.workloads, prev.discovery)?;
+				.sync_local(config.services, config.workloads, prev.discovery)?
+		} else {
+			prev.discovery
+		};
 
 		Ok(PreviousState {
 			binds: next_binds,
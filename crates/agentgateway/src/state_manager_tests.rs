@@ -0,0 +1,48 @@
+use super::*;
+
+#[test]
+fn test_config_scope_all() {
+	assert!(ConfigScope::all(&ConfigScope::ALL));
+	assert!(!ConfigScope::all(&[ConfigScope::Backends]));
+	assert!(!ConfigScope::all(&ConfigScope::ALL[1..]));
+	for scope in ConfigScope::ALL {
+		assert_eq!(scope.to_string().parse::<ConfigScope>().unwrap(), scope);
+	}
+	assert!("routes".parse::<ConfigScope>().is_err());
+}
+
+#[test]
+fn test_config_generations() {
+	let mut history = VecDeque::new();
+	push_config_generation(&mut history, "a", None);
+	push_config_generation(&mut history, "b", None);
+	// Applying the same config again is not a new generation
+	push_config_generation(&mut history, "b", None);
+	assert_eq!(
+		history.iter().map(|g| g.generation).collect::<Vec<_>>(),
+		vec![1, 2]
+	);
+	assert_eq!(&*history[0].content, "a");
+	assert_ne!(history[0].hash, history[1].hash);
+
+	// Rolling back is a new generation, recording where it came from
+	push_config_generation(&mut history, "a", Some(1));
+	let last = history.back().unwrap();
+	assert_eq!(last.generation, 3);
+	assert_eq!(last.rollback_of, Some(1));
+	assert_eq!(last.hash, history[0].hash);
+}
+
+#[test]
+fn test_config_generations_bounded() {
+	let mut history = VecDeque::new();
+	for i in 0..MAX_GENERATIONS + 2 {
+		push_config_generation(&mut history, &i.to_string(), None);
+	}
+	assert_eq!(history.len(), MAX_GENERATIONS);
+	assert_eq!(history.front().unwrap().generation, 3);
+	assert_eq!(
+		history.back().unwrap().generation,
+		MAX_GENERATIONS as u64 + 2
+	);
+}
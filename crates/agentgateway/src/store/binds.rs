@@ -1,7 +1,8 @@
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
-use agent_xds::{RejectedConfig, XdsUpdate};
+use agent_xds::{InvalidField, RejectedConfig, XdsUpdate};
+use anyhow::Context;
 use futures_core::Stream;
 use itertools::Itertools;
 use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
@@ -17,6 +18,7 @@ use crate::llm::policy::ResponseGuard;
 use crate::mcp::McpAuthorizationSet;
 use crate::proxy::httpproxy::PolicyClient;
 use crate::store::Event;
+use crate::telemetry::audit;
 use crate::types::agent::{
 	A2aPolicy, Backend, BackendKey, BackendPolicy, BackendTargetRef, BackendWithPolicies, Bind,
 	BindKey, FrontendPolicy, Listener, ListenerKey, ListenerName, McpAuthentication, PolicyKey,
@@ -65,6 +67,7 @@ pub struct FrontendPolices {
 	pub tcp: Option<frontend::TCP>,
 	pub access_log: Option<frontend::LoggingPolicy>,
 	pub tracing: Option<Arc<crate::types::agent::TracingPolicy>>,
+	pub request_id: Option<crate::http::requestid::RequestIdPolicy>,
 }
 
 impl FrontendPolices {
@@ -100,6 +103,8 @@ pub struct BackendPolicies {
 
 	pub http: Option<types::backend::HTTP>,
 	pub tcp: Option<types::backend::TCP>,
+	pub connection_pool: Option<types::backend::ConnectionPool>,
+	pub adaptive_concurrency: Option<http::adaptiveconcurrency::Policy>,
 
 	pub request_header_modifier: Option<filters::HeaderModifier>,
 	pub response_header_modifier: Option<filters::HeaderModifier>,
@@ -128,6 +133,8 @@ impl BackendPolicies {
 			inference_routing: other.inference_routing.or(self.inference_routing),
 			http: other.http.or(self.http),
 			tcp: other.tcp.or(self.tcp),
+			connection_pool: other.connection_pool.or(self.connection_pool),
+			adaptive_concurrency: other.adaptive_concurrency.or(self.adaptive_concurrency),
 			request_header_modifier: other
 				.request_header_modifier
 				.or(self.request_header_modifier),
@@ -167,9 +174,24 @@ pub struct RoutePolicies {
 	pub transformation: Option<http::transformation_cel::Transformation>,
 	pub llm: Option<Arc<llm::Policy>>,
 	pub csrf: Option<http::csrf::Csrf>,
+	pub ip_filter: Option<http::ipfilter::IpFilter>,
+	pub request_signature: Option<http::signature::RequestSignature>,
+	pub dlp: Option<http::dlp::Dlp>,
+	pub quota: Option<http::quota::Quota>,
+	pub opa: Option<http::opa::Opa>,
+	pub session_token: Option<http::sessiontoken::SessionToken>,
+	pub access_log: Option<crate::telemetry::accesslog::Policy>,
+	pub slow_request_log: Option<crate::telemetry::slowlog::Policy>,
+	pub llm_log: Option<crate::telemetry::llmlog::Policy>,
 
 	pub timeout: Option<timeout::Policy>,
 	pub retry: Option<retry::Policy>,
+	pub hedging: Option<http::hedging::Policy>,
+	pub model_fallback: Option<llm::fallback::Policy>,
+	pub model_experiment: Option<llm::experiment::Policy>,
+	pub llm_batch: Option<llm::batch::Policy>,
+	pub structured_output: Option<llm::structured_output::Policy>,
+	pub request_queue: Option<http::queue::Policy>,
 	pub request_header_modifier: Option<filters::HeaderModifier>,
 	pub response_header_modifier: Option<filters::HeaderModifier>,
 	pub request_redirect: Option<filters::RequestRedirect>,
@@ -188,6 +210,7 @@ pub struct GatewayPolicies {
 	pub transformation: Option<http::transformation_cel::Transformation>,
 	pub basic_auth: Option<http::basicauth::BasicAuthentication>,
 	pub api_key: Option<http::apikey::APIKeyAuthentication>,
+	pub ip_filter: Option<http::ipfilter::IpFilter>,
 }
 
 impl GatewayPolicies {
@@ -221,11 +244,30 @@ impl RoutePolicies {
 		if let Some(rrl) = &self.authorization {
 			rrl.register(ctx)
 		};
+		if let Some(expr) = self
+			.request_queue
+			.as_ref()
+			.and_then(|q| q.priority.as_deref())
+		{
+			ctx.register_expression(expr)
+		};
+		if let Some(expr) = self
+			.model_experiment
+			.as_ref()
+			.and_then(|e| e.key.as_deref())
+		{
+			ctx.register_expression(expr)
+		};
 		if let Some(extauthz) = &self.ext_authz {
 			for expr in extauthz.expressions() {
 				ctx.register_expression(expr)
 			}
 		}
+		if let Some(access) = self.llm.as_ref().and_then(|l| l.model_access.as_ref()) {
+			for expr in access.expressions() {
+				ctx.register_expression(expr)
+			}
+		}
 	}
 }
 
@@ -286,6 +328,32 @@ impl LLMRequestPolicies {
 			} else {
 				be.routes.clone()
 			},
+			semantic_cache: be
+				.semantic_cache
+				.clone()
+				.or_else(|| re.semantic_cache.clone()),
+			response_cache: be
+				.response_cache
+				.clone()
+				.or_else(|| re.response_cache.clone()),
+			token_limits: be.token_limits.clone().or_else(|| re.token_limits.clone()),
+			context_window: be
+				.context_window
+				.clone()
+				.or_else(|| re.context_window.clone()),
+			stream_rewrite: be
+				.stream_rewrite
+				.clone()
+				.or_else(|| re.stream_rewrite.clone()),
+			system_prompt: be
+				.system_prompt
+				.clone()
+				.or_else(|| re.system_prompt.clone()),
+			pii_redaction: be
+				.pii_redaction
+				.clone()
+				.or_else(|| re.pii_redaction.clone()),
+			model_access: be.model_access.clone().or_else(|| re.model_access.clone()),
 		}));
 		Arc::new(route_policies)
 	}
@@ -295,7 +363,12 @@ impl LLMRequestPolicies {
 pub struct LLMResponsePolicies {
 	pub local_rate_limit: Vec<http::localratelimit::RateLimit>,
 	pub remote_rate_limit: Option<http::remoteratelimit::LLMResponseAmend>,
+	pub quota: Option<http::quota::Account>,
 	pub prompt_guard: Vec<ResponseGuard>,
+	pub semantic_cache: Option<llm::policy::semantic_cache::Pending>,
+	pub response_cache: Option<llm::policy::response_cache::Pending>,
+	pub stream_rewrite: Option<llm::policy::stream_rewrite::StreamRewrite>,
+	pub pii_restore: Option<llm::policy::pii_redaction::Redacted>,
 }
 
 impl Default for Store {
@@ -396,6 +469,33 @@ impl Store {
 				TrafficPolicy::Csrf(p) => {
 					pol.csrf.get_or_insert_with(|| p.clone());
 				},
+				TrafficPolicy::RequestSignature(p) => {
+					pol.request_signature.get_or_insert_with(|| p.clone());
+				},
+				TrafficPolicy::IpFilter(p) => {
+					pol.ip_filter.get_or_insert_with(|| p.clone());
+				},
+				TrafficPolicy::Dlp(p) => {
+					pol.dlp.get_or_insert_with(|| p.clone());
+				},
+				TrafficPolicy::Quota(p) => {
+					pol.quota.get_or_insert_with(|| p.clone());
+				},
+				TrafficPolicy::Opa(p) => {
+					pol.opa.get_or_insert_with(|| p.clone());
+				},
+				TrafficPolicy::SessionToken(p) => {
+					pol.session_token.get_or_insert_with(|| p.clone());
+				},
+				TrafficPolicy::AccessLog(p) => {
+					pol.access_log.get_or_insert_with(|| p.clone());
+				},
+				TrafficPolicy::SlowRequestLog(p) => {
+					pol.slow_request_log.get_or_insert_with(|| p.clone());
+				},
+				TrafficPolicy::LlmLog(p) => {
+					pol.llm_log.get_or_insert_with(|| p.clone());
+				},
 
 				TrafficPolicy::Timeout(p) => {
 					pol.timeout.get_or_insert_with(|| p.clone());
@@ -403,6 +503,24 @@ impl Store {
 				TrafficPolicy::Retry(p) => {
 					pol.retry.get_or_insert_with(|| p.clone());
 				},
+				TrafficPolicy::Hedging(p) => {
+					pol.hedging.get_or_insert_with(|| p.clone());
+				},
+				TrafficPolicy::ModelFallback(p) => {
+					pol.model_fallback.get_or_insert_with(|| p.clone());
+				},
+				TrafficPolicy::ModelExperiment(p) => {
+					pol.model_experiment.get_or_insert_with(|| p.clone());
+				},
+				TrafficPolicy::LlmBatch(p) => {
+					pol.llm_batch.get_or_insert_with(|| p.clone());
+				},
+				TrafficPolicy::StructuredOutput(p) => {
+					pol.structured_output.get_or_insert_with(|| p.clone());
+				},
+				TrafficPolicy::RequestQueue(p) => {
+					pol.request_queue.get_or_insert_with(|| p.clone());
+				},
 				TrafficPolicy::RequestHeaderModifier(p) => {
 					pol.request_header_modifier.get_or_insert_with(|| p.clone());
 				},
@@ -472,6 +590,9 @@ impl Store {
 				TrafficPolicy::Transformation(p) => {
 					pol.transformation.get_or_insert_with(|| p.clone());
 				},
+				TrafficPolicy::IpFilter(p) => {
+					pol.ip_filter.get_or_insert_with(|| p.clone());
+				},
 				other => {
 					warn!("unexpected gateway policy: {:?}", other);
 				},
@@ -596,7 +717,13 @@ impl Store {
 					pol.http.get_or_insert_with(|| p.clone());
 				},
 				BackendPolicy::TCP(p) => {
-					pol.tcp.get_or_insert_with(|| p.clone());
+					pol.tcp.get_or_insert(*p);
+				},
+				BackendPolicy::ConnectionPool(p) => {
+					pol.connection_pool.get_or_insert(*p);
+				},
+				BackendPolicy::AdaptiveConcurrency(p) => {
+					pol.adaptive_concurrency.get_or_insert_with(|| p.clone());
 				},
 
 				BackendPolicy::RequestHeaderModifier(p) => {
@@ -660,6 +787,9 @@ impl Store {
 				FrontendPolicy::Tracing(p) => {
 					pol.tracing.get_or_insert_with(|| p.clone());
 				},
+				FrontendPolicy::RequestId(p) => {
+					pol.request_id.get_or_insert_with(|| p.clone());
+				},
 			}
 		}
 		pol
@@ -712,10 +842,11 @@ impl Store {
         fields(bind),
     )]
 	pub fn remove_policy(&mut self, pol: PolicyKey) {
-		if let Some(old) = self.policies_by_key.remove(&pol)
-			&& let Some(o) = self.policies_by_target.get_mut(&old.target)
-		{
-			o.remove(&pol);
+		if let Some(old) = self.policies_by_key.remove(&pol) {
+			audit::record_policy_change(audit::PolicyAction::Removed, &pol, Some(&old));
+			if let Some(o) = self.policies_by_target.get_mut(&old.target) {
+				o.remove(&pol);
+			}
 		}
 	}
 	#[instrument(
@@ -817,6 +948,13 @@ impl Store {
 			}
 			bind.listeners.insert(v)
 		}
+		watch_opa_bundles(
+			bind
+				.listeners
+				.iter()
+				.flat_map(|l| l.routes.iter())
+				.flat_map(|r| r.inline_policies.iter()),
+		);
 		let arc = Arc::new(bind);
 		self.binds.insert(arc.key.clone(), arc.clone());
 		// ok to have no subs
@@ -834,8 +972,25 @@ impl Store {
 	}
 
 	pub fn insert_policy(&mut self, pol: TargetedPolicy) {
+		if let agent::PolicyType::Traffic(p) = &pol.policy {
+			watch_opa_bundles([&p.policy]);
+		}
 		let pol = Arc::new(pol);
-		if let Some(old) = self.policies_by_key.insert(pol.key.clone(), pol.clone()) {
+		let old = self.policies_by_key.insert(pol.key.clone(), pol.clone());
+		if audit::enabled(audit::EventKind::PolicyChange) {
+			// Local config is re-applied in full on each reload, so skip policies that did not change.
+			let action = match &old {
+				None => Some(audit::PolicyAction::Added),
+				Some(old) if serde_json::to_value(old).ok() != serde_json::to_value(&pol).ok() => {
+					Some(audit::PolicyAction::Updated)
+				},
+				Some(_) => None,
+			};
+			if let Some(action) = action {
+				audit::record_policy_change(action, &pol.key, Some(&pol));
+			}
+		}
+		if let Some(old) = old {
 			// Remove the old target. We may add it back, though.
 			if let Some(o) = self.policies_by_target.get_mut(&old.target) {
 				o.remove(&pol.key);
@@ -964,37 +1119,45 @@ impl Store {
 				self
 					.resources
 					.insert(name, ResourceKind::Bind(strng::new(&w.key)));
-				self.insert_xds_bind(w)
+				self.insert_xds_bind(w).context(InvalidField::new("bind"))
 			},
 			Some(XdsKind::Listener(w)) => {
 				self
 					.resources
 					.insert(name, ResourceKind::Listener(strng::new(&w.key)));
-				self.insert_xds_listener(w)
+				self
+					.insert_xds_listener(w)
+					.context(InvalidField::new("listener"))
 			},
 			Some(XdsKind::Route(w)) => {
 				self
 					.resources
 					.insert(name, ResourceKind::Route(strng::new(&w.key)));
-				self.insert_xds_route(w)
+				self.insert_xds_route(w).context(InvalidField::new("route"))
 			},
 			Some(XdsKind::TcpRoute(w)) => {
 				self
 					.resources
 					.insert(name, ResourceKind::TcpRoute(strng::new(&w.key)));
-				self.insert_xds_tcp_route(w)
+				self
+					.insert_xds_tcp_route(w)
+					.context(InvalidField::new("tcp_route"))
 			},
 			Some(XdsKind::Backend(w)) => {
 				self
 					.resources
 					.insert(name, ResourceKind::Backend(strng::new(&w.key)));
-				self.insert_xds_backend(w)
+				self
+					.insert_xds_backend(w)
+					.context(InvalidField::new("backend"))
 			},
 			Some(XdsKind::Policy(w)) => {
 				self
 					.resources
 					.insert(name, ResourceKind::Policy(strng::new(&w.key)));
-				self.insert_xds_policy(w)
+				self
+					.insert_xds_policy(w)
+					.context(InvalidField::new("policy"))
 			},
 			_ => Err(anyhow::anyhow!("unknown resource type")),
 		}
@@ -1094,49 +1257,103 @@ impl StoreUpdater {
 		policies: Vec<TargetedPolicy>,
 		backends: Vec<BackendWithPolicies>,
 		prev: PreviousState,
+	) -> PreviousState {
+		let (telemetry, policies) = policies.into_iter().partition(|p| p.policy.is_telemetry());
+		self.sync_local_scoped(
+			Some(binds),
+			Some(policies),
+			Some(telemetry),
+			Some(backends),
+			prev,
+		)
+	}
+
+	/// Like sync_local, but only resources of the kinds passed are synced. Resources of the other
+	/// kinds are left as they are. Telemetry policies are passed in `telemetry`, apart from the other
+	/// `policies`, so either can be synced alone.
+	pub fn sync_local_scoped(
+		&self,
+		binds: Option<Vec<Bind>>,
+		policies: Option<Vec<TargetedPolicy>>,
+		telemetry: Option<Vec<TargetedPolicy>>,
+		backends: Option<Vec<BackendWithPolicies>>,
+		prev: PreviousState,
 	) -> PreviousState {
 		let mut s = self.state.write().expect("mutex acquired");
-		let mut old_binds = prev.binds;
-		let mut old_pols = prev.policies;
-		let mut old_backends = prev.backends;
-		let mut next_state = PreviousState {
-			binds: Default::default(),
-			policies: Default::default(),
-			backends: Default::default(),
-		};
-		for b in binds {
-			old_binds.remove(&b.key);
-			next_state.binds.insert(b.key.clone());
-			s.insert_bind(b);
+		let mut next_state = prev.clone();
+		if let Some(binds) = binds {
+			let mut old_binds = prev.binds;
+			next_state.binds = Default::default();
+			for b in binds {
+				old_binds.remove(&b.key);
+				next_state.binds.insert(b.key.clone());
+				s.insert_bind(b);
+			}
+			for remaining_bind in old_binds {
+				s.remove_bind(remaining_bind);
+			}
 		}
-		for b in backends {
-			// Here we use the 'name' as the key. This is appropriate for local case only
-			old_backends.remove(&b.backend.name());
-			next_state.backends.insert(b.backend.name());
-			s.insert_backend(b.backend.name(), b);
+		if let Some(backends) = backends {
+			let mut old_backends = prev.backends;
+			next_state.backends = Default::default();
+			for b in backends {
+				// Here we use the 'name' as the key. This is appropriate for local case only
+				old_backends.remove(&b.backend.name());
+				next_state.backends.insert(b.backend.name());
+				s.insert_backend(b.backend.name(), b);
+			}
+			for remaining_backend in old_backends {
+				s.remove_backend(remaining_backend);
+			}
 		}
-		for p in policies {
-			old_pols.remove(&p.key);
-			next_state.policies.insert(p.key.clone());
-			s.insert_policy(p);
+		if let Some(policies) = policies {
+			next_state.policies = sync_local_policies(
+				&mut s,
+				policies,
+				prev.policies,
+				&next_state.telemetry_policies,
+			);
 		}
-		for remaining_bind in old_binds {
-			s.remove_bind(remaining_bind);
+		if let Some(policies) = telemetry {
+			next_state.telemetry_policies = sync_local_policies(
+				&mut s,
+				policies,
+				prev.telemetry_policies,
+				&next_state.policies,
+			);
 		}
-		for remaining_policy in old_pols {
+		next_state
+	}
+}
+
+/// Sync one kind of local policy, returning the keys now held. Policies in `old` that are not in
+/// `policies` are removed, unless their key is now held by a policy of the other kind, in `others`.
+fn sync_local_policies(
+	s: &mut Store,
+	policies: Vec<TargetedPolicy>,
+	mut old: HashSet<PolicyKey>,
+	others: &HashSet<PolicyKey>,
+) -> HashSet<PolicyKey> {
+	let mut next = HashSet::new();
+	for p in policies {
+		old.remove(&p.key);
+		next.insert(p.key.clone());
+		s.insert_policy(p);
+	}
+	for remaining_policy in old {
+		if !others.contains(&remaining_policy) {
 			s.remove_policy(remaining_policy);
 		}
-		for remaining_backend in old_backends {
-			s.remove_backend(remaining_backend);
-		}
-		next_state
 	}
+	next
 }
 
 #[derive(Clone, Debug, Default)]
 pub struct PreviousState {
 	pub binds: HashSet<BindKey>,
 	pub policies: HashSet<PolicyKey>,
+	/// Policies that only configure telemetry, which are synced apart from the other policies.
+	pub telemetry_policies: HashSet<PolicyKey>,
 	pub backends: HashSet<BackendKey>,
 }
 
@@ -1172,6 +1389,18 @@ fn preload_tokenizers() {
 	});
 }
 
+/// Embedded OPA bundles are loaded while parsing config, which may happen outside the runtime, so
+/// their reload watchers are started once they are in use.
+fn watch_opa_bundles<'a>(policies: impl IntoIterator<Item = &'a TrafficPolicy>) {
+	for p in policies {
+		if let TrafficPolicy::Opa(opa) = p
+			&& let http::opa::Source::Embedded(b) = &opa.source
+		{
+			b.start_watching();
+		}
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -1254,4 +1483,111 @@ mod tests {
 		);
 		assert_eq!(grpc_pols.timeout, Some(grpc_timeout));
 	}
+
+	fn backend(name: &str) -> BackendWithPolicies {
+		BackendWithPolicies {
+			backend: Backend::Opaque(
+				agent::ResourceName::new(strng::new(name), strng::literal!("")),
+				agent::Target::Address("127.0.0.1:8080".parse().unwrap()),
+			),
+			inline_policies: vec![],
+		}
+	}
+
+	fn access_log_policy(key: &str) -> TargetedPolicy {
+		TargetedPolicy {
+			key: strng::new(key),
+			name: None,
+			target: PolicyTarget::Route(route("r", "ns", None)),
+			policy: FrontendPolicy::AccessLog(crate::types::frontend::LoggingPolicy {
+				filter: None,
+				add: Default::default(),
+				remove: Default::default(),
+			})
+			.into(),
+		}
+	}
+
+	fn timeout_policy(key: &str) -> TargetedPolicy {
+		TargetedPolicy {
+			key: strng::new(key),
+			name: None,
+			target: PolicyTarget::Route(route("r", "ns", None)),
+			policy: TrafficPolicy::Timeout(timeout::Policy {
+				request_timeout: Some(Duration::from_secs(1)),
+				backend_request_timeout: None,
+			})
+			.into(),
+		}
+	}
+
+	#[test]
+	fn sync_local_scoped_leaves_other_kinds() {
+		let updater = StoreUpdater::new(Arc::new(RwLock::new(Store::new())));
+		let prev = updater.sync_local(
+			vec![],
+			vec![timeout_policy("p1")],
+			vec![backend("b1")],
+			PreviousState::default(),
+		);
+
+		// Only policies are synced, so b1 stays even though no backends are passed
+		let next = updater.sync_local_scoped(None, Some(vec![timeout_policy("p2")]), None, None, prev);
+		let store = updater.read();
+		assert!(store.backend(&strng::new("b1")).is_some());
+		assert!(!store.policies_by_key.contains_key(&strng::new("p1")));
+		assert!(store.policies_by_key.contains_key(&strng::new("p2")));
+		drop(store);
+		assert_eq!(next.backends, HashSet::from([strng::new("b1")]));
+		assert_eq!(next.policies, HashSet::from([strng::new("p2")]));
+
+		updater.sync_local_scoped(None, None, None, Some(vec![]), next);
+		assert!(updater.read().backend(&strng::new("b1")).is_none());
+	}
+
+	#[test]
+	fn sync_local_scoped_telemetry() {
+		let updater = StoreUpdater::new(Arc::new(RwLock::new(Store::new())));
+		let prev = updater.sync_local(
+			vec![],
+			vec![timeout_policy("p1"), access_log_policy("t1")],
+			vec![backend("b1")],
+			PreviousState::default(),
+		);
+		assert_eq!(prev.policies, HashSet::from([strng::new("p1")]));
+		assert_eq!(prev.telemetry_policies, HashSet::from([strng::new("t1")]));
+
+		// Syncing the other policies leaves telemetry policies alone
+		let next = updater.sync_local_scoped(None, Some(vec![timeout_policy("p2")]), None, None, prev);
+		let store = updater.read();
+		assert!(!store.policies_by_key.contains_key(&strng::new("p1")));
+		assert!(store.policies_by_key.contains_key(&strng::new("p2")));
+		assert!(store.policies_by_key.contains_key(&strng::new("t1")));
+		drop(store);
+
+		// And syncing telemetry leaves everything else alone
+		let next =
+			updater.sync_local_scoped(None, None, Some(vec![access_log_policy("t2")]), None, next);
+		let store = updater.read();
+		assert!(!store.policies_by_key.contains_key(&strng::new("t1")));
+		assert!(store.policies_by_key.contains_key(&strng::new("t2")));
+		assert!(store.policies_by_key.contains_key(&strng::new("p2")));
+		assert!(store.backend(&strng::new("b1")).is_some());
+		drop(store);
+		assert_eq!(next.telemetry_policies, HashSet::from([strng::new("t2")]));
+		assert_eq!(next.backends, HashSet::from([strng::new("b1")]));
+
+		// A policy that becomes a telemetry policy is not removed with the other policies
+		let next =
+			updater.sync_local_scoped(None, None, Some(vec![access_log_policy("p2")]), None, next);
+		let next = updater.sync_local_scoped(None, Some(vec![]), None, None, next);
+		assert!(
+			updater
+				.read()
+				.policies_by_key
+				.contains_key(&strng::new("p2"))
+		);
+		assert!(next.policies.is_empty());
+		assert_eq!(next.telemetry_policies, HashSet::from([strng::new("p2")]));
+	}
 }
@@ -0,0 +1,486 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::OnceLock;
+
+use agent_core::telemetry::ValueBag;
+use chrono::{DateTime, Utc};
+use indexmap::IndexMap;
+use macro_rules_attribute::apply;
+use tokio::sync::mpsc;
+
+use crate::telemetry::log::{CelLoggingExecutor, RequestLog};
+use crate::telemetry::sink::BoundedSink;
+use crate::telemetry::{als, audit, syslog};
+use crate::*;
+
+#[cfg(test)]
+#[path = "accesslog_tests.rs"]
+mod tests;
+
+static ACCESS_LOG: OnceLock<AccessLog> = OnceLock::new();
+
+#[apply(schema!)]
+#[derive(Default, Copy, PartialEq, Eq)]
+pub enum Format {
+	/// One JSON object per line.
+	#[default]
+	Json,
+	/// Apache combined log format. Custom fields are not included.
+	Combined,
+}
+
+/// Configuration for the access log. When set, each request is recorded here instead of as a
+/// `request` line in the application logs.
+#[apply(schema!)]
+pub struct Config {
+	/// Destinations for access log entries. Each entry is sent to every sink. If unset, entries are
+	/// written to stdout.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub sinks: Vec<Sink>,
+	/// Default format for sinks that do not set their own.
+	#[serde(default)]
+	pub format: Format,
+	/// Built-in fields to include in JSON entries, such as `http.status` or `duration`. If unset,
+	/// all fields are included.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub fields: Vec<String>,
+	/// Additional fields to include in JSON entries, as CEL expressions.
+	#[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+	#[cfg_attr(
+		feature = "schema",
+		schemars(with = "std::collections::HashMap<String, String>")
+	)]
+	pub add: IndexMap<String, String>,
+	/// Only record requests matching these conditions. Routes may override this.
+	#[serde(default)]
+	pub conditions: Conditions,
+}
+
+#[apply(schema!)]
+pub struct Sink {
+	#[serde(flatten)]
+	pub destination: Destination,
+	/// Format of entries written to this sink. Defaults to the access log format. gRPC sinks always
+	/// send ALS entries.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub format: Option<Format>,
+	/// Number of entries buffered for this sink. Once full, new entries are dropped rather than
+	/// delaying requests.
+	#[serde(default = "default_buffer_size")]
+	pub buffer_size: usize,
+}
+
+fn default_buffer_size() -> usize {
+	8192
+}
+
+#[apply(schema!)]
+pub enum Destination {
+	Stdout {},
+	Stderr {},
+	File(audit::FileSink),
+	Syslog(syslog::SyslogSink),
+	Grpc(als::GrpcSink),
+}
+
+/// Conditions for recording a request. If none are set, all requests are recorded; otherwise, a
+/// request is recorded if it matches any of them.
+#[apply(schema!)]
+#[derive(Default, PartialEq, Eq)]
+pub struct Conditions {
+	/// Record requests that failed, with a 4xx or 5xx status or an error.
+	#[serde(default, skip_serializing_if = "is_default")]
+	pub errors: bool,
+	/// Record requests that took at least this long.
+	#[serde(
+		default,
+		with = "serde_dur_option",
+		skip_serializing_if = "Option::is_none"
+	)]
+	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+	pub slow_threshold: Option<Duration>,
+}
+
+impl Conditions {
+	fn matches(&self, failed: bool, duration: Duration) -> bool {
+		if !self.errors && self.slow_threshold.is_none() {
+			return true;
+		}
+		(self.errors && failed) || self.slow_threshold.is_some_and(|t| duration >= t)
+	}
+}
+
+/// Per-route access log settings.
+#[apply(schema!)]
+#[derive(Default)]
+pub struct Policy {
+	/// Do not record requests to this route.
+	#[serde(default, skip_serializing_if = "is_default")]
+	pub disabled: bool,
+	/// Replace the global conditions for this route.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub conditions: Option<Conditions>,
+}
+
+pub struct AccessLog {
+	fields: Vec<String>,
+	add: Vec<(String, Arc<cel::Expression>)>,
+	conditions: Conditions,
+	referer: cel::Expression,
+	user_agent: cel::Expression,
+	request_id: cel::Expression,
+	// The representations needed by at least one sink
+	json: bool,
+	combined: bool,
+	als: bool,
+	sinks: Vec<SinkHandle>,
+}
+
+/// A recorded request, in each line format needed by the sinks. Lines include the trailing newline.
+struct Entry {
+	failed: bool,
+	json: Option<Vec<u8>>,
+	combined: Option<Vec<u8>>,
+}
+
+impl Entry {
+	fn line(&self, format: Format) -> &[u8] {
+		let line = match format {
+			Format::Json => &self.json,
+			Format::Combined => &self.combined,
+		};
+		line.as_deref().unwrap_or_default()
+	}
+}
+
+enum SinkHandle {
+	Line(Format, BoundedSink<Arc<Entry>>),
+	Grpc(BoundedSink<als::proto::HttpAccessLogEntry>),
+}
+
+enum LineWriter {
+	Local(audit::Writer),
+	Syslog(syslog::Writer),
+}
+
+/// Initialize the global access log. `node` identifies this gateway to gRPC sinks.
+pub fn init(cfg: &Config, node: als::proto::Node) -> anyhow::Result<()> {
+	let default_sink = Sink {
+		destination: Destination::Stdout {},
+		format: None,
+		buffer_size: default_buffer_size(),
+	};
+	let sinks = if cfg.sinks.is_empty() {
+		std::slice::from_ref(&default_sink)
+	} else {
+		cfg.sinks.as_slice()
+	};
+	let sinks = sinks
+		.iter()
+		.map(|s| open_sink(s, cfg.format, &node))
+		.collect::<anyhow::Result<_>>()?;
+	let access_log = AccessLog::new(cfg, sinks)?;
+	ACCESS_LOG
+		.set(access_log)
+		.map_err(|_| anyhow::anyhow!("access log already initialized"))
+}
+
+fn open_sink(
+	sink: &Sink,
+	default_format: Format,
+	node: &als::proto::Node,
+) -> anyhow::Result<SinkHandle> {
+	anyhow::ensure!(
+		sink.buffer_size > 0,
+		"access log sink bufferSize must be positive"
+	);
+	let writer = match &sink.destination {
+		Destination::Grpc(g) => {
+			let client = als::connect(g)?;
+			let (tx, rx) = BoundedSink::new("access log sink", sink.buffer_size);
+			tokio::spawn(als::run(g.clone(), node.clone(), client, rx));
+			return Ok(SinkHandle::Grpc(tx));
+		},
+		Destination::Stdout {} => LineWriter::Local(audit::Writer::Stdout),
+		Destination::Stderr {} => LineWriter::Local(audit::Writer::Stderr),
+		Destination::File(f) => {
+			LineWriter::Local(audit::Writer::File(audit::RotatingFile::open(f.clone())?))
+		},
+		Destination::Syslog(s) => LineWriter::Syslog(syslog::Writer::new(s.clone())?),
+	};
+	let format = sink.format.unwrap_or(default_format);
+	let (tx, rx) = BoundedSink::new("access log sink", sink.buffer_size);
+	std::thread::Builder::new()
+		.name("access-log".to_string())
+		.spawn(move || write_loop(rx, format, writer))?;
+	Ok(SinkHandle::Line(format, tx))
+}
+
+/// Returns true if the access log is configured.
+pub fn enabled() -> bool {
+	ACCESS_LOG.get().is_some()
+}
+
+/// Register the attributes the access log needs, so they are captured for each request.
+pub fn register(ctx: &mut cel::ContextBuilder) {
+	let Some(a) = ACCESS_LOG.get() else {
+		return;
+	};
+	for (_, v) in &a.add {
+		ctx.register_expression(v);
+	}
+	if a.combined || a.als {
+		ctx.register_expression(&a.referer);
+		ctx.register_expression(&a.user_agent);
+	}
+	if a.als {
+		ctx.register_expression(&a.request_id);
+	}
+}
+
+/// Record a completed request, if it matches the configured conditions. `fields` are the built-in
+/// fields, as emitted for the application log.
+pub fn record(
+	log: &RequestLog,
+	cel_exec: &CelLoggingExecutor,
+	duration: Duration,
+	fields: &[(&str, Option<ValueBag>)],
+) {
+	let Some(a) = ACCESS_LOG.get() else {
+		return;
+	};
+	let policy = log.access_log.as_ref();
+	if policy.is_some_and(|p| p.disabled) {
+		return;
+	}
+	let conditions = policy
+		.and_then(|p| p.conditions.as_ref())
+		.unwrap_or(&a.conditions);
+	let failed = log.error.is_some()
+		|| log
+			.status
+			.is_some_and(|s| s.is_client_error() || s.is_server_error());
+	if !conditions.matches(failed, duration) {
+		return;
+	}
+	let header = |expr: &cel::Expression| match cel_exec.executor.eval(expr) {
+		Ok(cel::Value::String(s)) => Some(s.to_string()),
+		_ => None,
+	};
+	// Custom fields are evaluated once, and shared by JSON and ALS entries.
+	let custom = if a.json || a.als {
+		a.add
+			.iter()
+			.filter_map(|(k, expr)| {
+				let v = cel_exec.executor.eval(expr).ok()?.json().ok()?;
+				Some((k.as_str(), v))
+			})
+			.collect()
+	} else {
+		vec![]
+	};
+	let entry = Arc::new(Entry {
+		failed,
+		json: a.json.then(|| {
+			let mut line = a.json_line(fields, custom.iter().cloned());
+			line.push(b'\n');
+			line
+		}),
+		combined: a.combined.then(|| {
+			let mut line = a
+				.combined_line(log, duration, header(&a.referer), header(&a.user_agent))
+				.into_bytes();
+			line.push(b'\n');
+			line
+		}),
+	});
+	let als_entry = a.als.then(|| {
+		let headers = als::Headers {
+			user_agent: header(&a.user_agent),
+			referer: header(&a.referer),
+			request_id: log.request_id.clone().or_else(|| header(&a.request_id)),
+		};
+		let tags = custom
+			.into_iter()
+			.filter(|(_, v)| !v.is_null())
+			.map(|(k, v)| {
+				let v = match v {
+					serde_json::Value::String(s) => s,
+					v => v.to_string(),
+				};
+				(k.to_string(), v)
+			})
+			.collect::<HashMap<_, _>>();
+		als::entry(log, duration, headers, tags)
+	});
+	for s in &a.sinks {
+		s.send(&entry, als_entry.as_ref());
+	}
+}
+
+impl AccessLog {
+	fn new(cfg: &Config, sinks: Vec<SinkHandle>) -> anyhow::Result<Self> {
+		let add = cfg
+			.add
+			.iter()
+			.map(|(k, v)| Ok((k.clone(), Arc::new(cel::Expression::new_strict(v)?))))
+			.collect::<anyhow::Result<_>>()?;
+		let has = |f: Format| {
+			sinks
+				.iter()
+				.any(|s| matches!(s, SinkHandle::Line(format, _) if *format == f))
+		};
+		Ok(AccessLog {
+			fields: cfg.fields.clone(),
+			add,
+			conditions: cfg.conditions.clone(),
+			referer: cel::Expression::new_strict("request.headers[\"referer\"]")?,
+			user_agent: cel::Expression::new_strict("request.headers[\"user-agent\"]")?,
+			request_id: cel::Expression::new_strict("request.headers[\"x-request-id\"]")?,
+			json: has(Format::Json),
+			combined: has(Format::Combined),
+			als: sinks.iter().any(|s| matches!(s, SinkHandle::Grpc(_))),
+			sinks,
+		})
+	}
+
+	fn json_line<'a>(
+		&self,
+		fields: &[(&str, Option<ValueBag>)],
+		custom: impl Iterator<Item = (&'a str, serde_json::Value)>,
+	) -> Vec<u8> {
+		let mut entry = serde_json::Map::new();
+		entry.insert(
+			"timestamp".to_string(),
+			agent_core::telemetry::render_current_time().into(),
+		);
+		for (k, v) in fields {
+			let Some(v) = v else {
+				continue;
+			};
+			if !self.fields.is_empty() && !self.fields.iter().any(|f| f == k) {
+				continue;
+			}
+			if let Ok(v) = serde_json::to_value(v) {
+				entry.insert(k.to_string(), v);
+			}
+		}
+		for (k, v) in custom {
+			if !v.is_null() {
+				entry.insert(k.to_string(), v);
+			}
+		}
+		serde_json::to_vec(&entry).unwrap_or_default()
+	}
+
+	fn combined_line(
+		&self,
+		log: &RequestLog,
+		duration: Duration,
+		referer: Option<String>,
+		user_agent: Option<String>,
+	) -> String {
+		CombinedEntry {
+			src: log.tcp_info.peer_addr.ip(),
+			user: log.jwt_sub.as_deref(),
+			time: Utc::now() - duration,
+			method: log.method.as_ref().map(|m| m.as_str()),
+			path: log.path.as_deref(),
+			version: log.version,
+			status: log.status.map(|s| s.as_u16()),
+			bytes: log.response_bytes,
+			referer,
+			user_agent,
+		}
+		.to_string()
+	}
+}
+
+impl SinkHandle {
+	fn send(&self, entry: &Arc<Entry>, als_entry: Option<&als::proto::HttpAccessLogEntry>) {
+		match (self, als_entry) {
+			(SinkHandle::Line(_, tx), _) => tx.send(entry.clone()),
+			(SinkHandle::Grpc(tx), Some(e)) => tx.send(e.clone()),
+			(SinkHandle::Grpc(_), None) => {},
+		}
+	}
+}
+
+/// A request in the Apache combined log format:
+/// `%h - %u [%t] "%r" %>s %b "%{Referer}i" "%{User-Agent}i"`.
+struct CombinedEntry<'a> {
+	src: IpAddr,
+	user: Option<&'a str>,
+	time: DateTime<Utc>,
+	method: Option<&'a str>,
+	path: Option<&'a str>,
+	version: Option<::http::Version>,
+	status: Option<u16>,
+	bytes: u64,
+	referer: Option<String>,
+	user_agent: Option<String>,
+}
+
+impl fmt::Display for CombinedEntry<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"{} - {} [{}] \"{} {} {:?}\" ",
+			self.src,
+			self.user.map(escape).unwrap_or("-".into()),
+			self.time.format("%d/%b/%Y:%H:%M:%S %z"),
+			self.method.unwrap_or("-"),
+			self.path.map(escape).unwrap_or("-".into()),
+			self.version.unwrap_or_default(),
+		)?;
+		match self.status {
+			Some(s) => write!(f, "{s} ")?,
+			None => write!(f, "- ")?,
+		}
+		match self.bytes {
+			0 => write!(f, "- ")?,
+			b => write!(f, "{b} ")?,
+		}
+		write!(
+			f,
+			"\"{}\" \"{}\"",
+			self.referer.as_deref().map(escape).unwrap_or("-".into()),
+			self.user_agent.as_deref().map(escape).unwrap_or("-".into()),
+		)
+	}
+}
+
+/// Escape quotes, backslashes, and control characters, so client supplied values cannot break the
+/// line format.
+fn escape(s: &str) -> String {
+	let mut out = String::with_capacity(s.len());
+	for c in s.chars() {
+		match c {
+			'"' => out.push_str("\\\""),
+			'\\' => out.push_str("\\\\"),
+			c if c.is_control() => out.push_str(&format!("\\x{:02x}", c as u32)),
+			c => out.push(c),
+		}
+	}
+	out
+}
+
+fn write_loop(mut rx: mpsc::Receiver<Arc<Entry>>, format: Format, mut writer: LineWriter) {
+	while let Some(entry) = rx.blocking_recv() {
+		let line = entry.line(format);
+		let res = match &mut writer {
+			LineWriter::Local(w) => w.write_line(line),
+			LineWriter::Syslog(w) => {
+				let severity = if entry.failed {
+					syslog::Severity::Warning
+				} else {
+					syslog::Severity::Informational
+				};
+				w.write(severity, line.strip_suffix(b"\n").unwrap_or(line))
+			},
+		};
+		if let Err(e) = res {
+			warn!("failed to write access log entry: {e}");
+		}
+	}
+}
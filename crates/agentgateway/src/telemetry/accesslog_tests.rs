@@ -0,0 +1,162 @@
+use chrono::TimeZone;
+
+use super::*;
+
+fn access_log(cfg: serde_json::Value) -> AccessLog {
+	let cfg: Config = serde_json::from_value(cfg).unwrap();
+	AccessLog::new(&cfg, vec![]).unwrap()
+}
+
+#[test]
+fn test_conditions() {
+	let all = Conditions::default();
+	assert!(all.matches(false, Duration::from_millis(1)));
+
+	let errors = Conditions {
+		errors: true,
+		slow_threshold: None,
+	};
+	assert!(errors.matches(true, Duration::from_millis(1)));
+	assert!(!errors.matches(false, Duration::from_secs(10)));
+
+	let errors_or_slow = Conditions {
+		errors: true,
+		slow_threshold: Some(Duration::from_secs(1)),
+	};
+	assert!(errors_or_slow.matches(false, Duration::from_secs(1)));
+	assert!(errors_or_slow.matches(true, Duration::from_millis(1)));
+	assert!(!errors_or_slow.matches(false, Duration::from_millis(999)));
+}
+
+#[test]
+fn test_config() {
+	let cfg: Config = serde_json::from_value(serde_json::json!({
+		"format": "combined",
+		"conditions": {"errors": true, "slowThreshold": "500ms"},
+	}))
+	.unwrap();
+	assert_eq!(cfg.format, Format::Combined);
+	assert_eq!(
+		cfg.conditions.slow_threshold,
+		Some(Duration::from_millis(500))
+	);
+	// Invalid expressions are rejected up front
+	let cfg: Config = serde_json::from_value(serde_json::json!({
+		"add": {"bad": "request.("},
+	}))
+	.unwrap();
+	assert!(AccessLog::new(&cfg, vec![]).is_err());
+}
+
+#[test]
+fn test_sinks_config() {
+	let cfg: Config = serde_json::from_value(serde_json::json!({
+		"sinks": [
+			{"stdout": {}},
+			{"file": {"path": "/var/log/access.log", "rotateInterval": "24h"}, "format": "combined"},
+			{"syslog": {"address": "syslog.example.com:6514", "transport": "tls"}, "bufferSize": 100},
+			{"grpc": {"address": "http://als.example.com:9001"}},
+		],
+	}))
+	.unwrap();
+	assert!(matches!(cfg.sinks[0].destination, Destination::Stdout {}));
+	assert_eq!(cfg.sinks[0].buffer_size, 8192);
+	let Destination::File(f) = &cfg.sinks[1].destination else {
+		panic!("expected file sink");
+	};
+	assert_eq!(f.rotate_interval, Some(Duration::from_secs(24 * 60 * 60)));
+	assert_eq!(cfg.sinks[1].format, Some(Format::Combined));
+	let Destination::Syslog(s) = &cfg.sinks[2].destination else {
+		panic!("expected syslog sink");
+	};
+	assert_eq!(s.transport, syslog::Transport::Tls);
+	assert_eq!(s.facility, 16);
+	assert_eq!(cfg.sinks[2].buffer_size, 100);
+	let Destination::Grpc(g) = &cfg.sinks[3].destination else {
+		panic!("expected grpc sink");
+	};
+	assert_eq!(g.log_name, "agentgateway");
+	assert_eq!(g.batch_size, 100);
+
+	// Exactly one destination is required
+	assert!(
+		serde_json::from_value::<Config>(serde_json::json!({
+			"sinks": [{"format": "json"}],
+		}))
+		.is_err()
+	);
+}
+
+#[test]
+fn test_entry_line() {
+	let entry = Entry {
+		failed: false,
+		json: Some(b"{}\n".to_vec()),
+		combined: None,
+	};
+	assert_eq!(entry.line(Format::Json), b"{}\n");
+	assert_eq!(entry.line(Format::Combined), b"");
+}
+
+#[test]
+fn test_json_fields() {
+	let a = access_log(serde_json::json!({
+		"fields": ["http.method", "http.status"],
+	}));
+	let fields = [
+		("http.method", Some(ValueBag::from("GET"))),
+		("http.status", Some(ValueBag::from(404u16))),
+		("http.path", Some(ValueBag::from("/foo"))),
+		("jwt.sub", None),
+	];
+	let custom = [
+		("tenant", serde_json::json!("acme")),
+		("empty", serde_json::Value::Null),
+	];
+	let line = a.json_line(&fields, custom.into_iter());
+	let entry: serde_json::Map<String, serde_json::Value> = serde_json::from_slice(&line).unwrap();
+	assert_eq!(entry["http.method"], "GET");
+	assert_eq!(entry["http.status"], 404);
+	assert_eq!(entry["tenant"], "acme");
+	assert!(entry.contains_key("timestamp"));
+	assert!(!entry.contains_key("http.path"));
+	assert!(!entry.contains_key("jwt.sub"));
+	assert!(!entry.contains_key("empty"));
+}
+
+#[test]
+fn test_combined() {
+	let entry = CombinedEntry {
+		src: "10.0.0.1".parse().unwrap(),
+		user: Some("alice"),
+		time: Utc.with_ymd_and_hms(2025, 10, 3, 13, 55, 36).unwrap(),
+		method: Some("GET"),
+		path: Some("/a\"b"),
+		version: Some(::http::Version::HTTP_11),
+		status: Some(200),
+		bytes: 2326,
+		referer: None,
+		user_agent: Some("curl/8.0".to_string()),
+	};
+	assert_eq!(
+		entry.to_string(),
+		r#"10.0.0.1 - alice [03/Oct/2025:13:55:36 +0000] "GET /a\"b HTTP/1.1" 200 2326 "-" "curl/8.0""#
+	);
+
+	let rejected = CombinedEntry {
+		user: None,
+		status: None,
+		bytes: 0,
+		user_agent: None,
+		..entry
+	};
+	assert_eq!(
+		rejected.to_string(),
+		r#"10.0.0.1 - - [03/Oct/2025:13:55:36 +0000] "GET /a\"b HTTP/1.1" - - "-" "-""#
+	);
+}
+
+#[test]
+fn test_escape() {
+	assert_eq!(escape("a\"b\\c\nd"), "a\\\"b\\\\c\\x0ad");
+}
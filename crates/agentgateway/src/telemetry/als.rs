@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::SystemTime;
+
+use macro_rules_attribute::apply;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::transport::{Channel, Endpoint};
+
+use crate::telemetry::log::RequestLog;
+use crate::types::agent::Target;
+use crate::*;
+
+pub mod proto {
+	tonic::include_proto!("envoy.service.accesslog.v3");
+}
+
+use proto::access_log_service_client::AccessLogServiceClient;
+use proto::http_access_log_entry::HttpVersion;
+use proto::stream_access_logs_message::{HttpAccessLogEntries, Identifier, LogEntries};
+use proto::{
+	AccessLogCommon, Address, HttpAccessLogEntry, HttpRequestProperties, HttpResponseProperties,
+	RequestMethod, SocketAddress, StreamAccessLogsMessage,
+};
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Stream entries to an Envoy access log service (ALS) server, as HTTP access log entries.
+#[apply(schema!)]
+pub struct GrpcSink {
+	/// Address of the ALS server, such as `http://als.example.com:9001`.
+	pub address: String,
+	/// Log name sent to the server to identify this stream.
+	#[serde(default = "defaults::log_name")]
+	pub log_name: String,
+	/// Maximum number of entries sent in one message.
+	#[serde(default = "defaults::batch_size")]
+	pub batch_size: usize,
+	/// Maximum time an entry waits before its batch is sent.
+	#[serde(default = "defaults::flush_interval", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub flush_interval: Duration,
+}
+
+mod defaults {
+	use std::time::Duration;
+
+	pub fn log_name() -> String {
+		"agentgateway".to_string()
+	}
+	pub fn batch_size() -> usize {
+		100
+	}
+	pub fn flush_interval() -> Duration {
+		Duration::from_secs(1)
+	}
+}
+
+/// Request headers reported in ALS entries, which are not otherwise part of the request log.
+#[derive(Debug, Default)]
+pub(super) struct Headers {
+	pub user_agent: Option<String>,
+	pub referer: Option<String>,
+	pub request_id: Option<String>,
+}
+
+pub(super) fn connect(cfg: &GrpcSink) -> anyhow::Result<AccessLogServiceClient<Channel>> {
+	let channel = Endpoint::from_shared(cfg.address.clone())?.connect_lazy();
+	Ok(AccessLogServiceClient::new(channel))
+}
+
+/// Stream entries from `rx` to the server until the channel is closed, reconnecting whenever the
+/// stream fails. Entries in flight when a stream fails are lost.
+pub(super) async fn run(
+	cfg: GrpcSink,
+	node: proto::Node,
+	mut client: AccessLogServiceClient<Channel>,
+	mut rx: mpsc::Receiver<HttpAccessLogEntry>,
+) {
+	loop {
+		let (tx, stream_rx) = mpsc::channel(1);
+		// The identifier is only sent in the first message of each stream.
+		let mut identifier = Some(Identifier {
+			node: Some(node.clone()),
+			log_name: cfg.log_name.clone(),
+		});
+		let send = async {
+			while let Some(batch) = next_batch(&mut rx, cfg.batch_size, cfg.flush_interval).await {
+				let msg = StreamAccessLogsMessage {
+					identifier: identifier.take(),
+					log_entries: Some(LogEntries::HttpLogs(HttpAccessLogEntries {
+						log_entry: batch,
+					})),
+				};
+				if tx.send(msg).await.is_err() {
+					return false;
+				}
+			}
+			true
+		};
+		tokio::select! {
+			res = client.stream_access_logs(ReceiverStream::new(stream_rx)) => {
+				match res {
+					Ok(_) => debug!("access log stream to {} closed, reconnecting", cfg.address),
+					Err(e) => warn!("access log stream to {} failed: {e}", cfg.address),
+				}
+			},
+			done = send => {
+				if done {
+					return;
+				}
+			},
+		}
+		tokio::time::sleep(RECONNECT_DELAY).await;
+	}
+}
+
+/// Wait for the next entry, then collect more until the batch is full or `flush_interval` passes.
+/// Returns None once the channel is closed and drained.
+async fn next_batch(
+	rx: &mut mpsc::Receiver<HttpAccessLogEntry>,
+	batch_size: usize,
+	flush_interval: Duration,
+) -> Option<Vec<HttpAccessLogEntry>> {
+	let mut batch = vec![rx.recv().await?];
+	let deadline = tokio::time::sleep(flush_interval);
+	tokio::pin!(deadline);
+	while batch.len() < batch_size {
+		tokio::select! {
+			e = rx.recv() => match e {
+				Some(e) => batch.push(e),
+				None => break,
+			},
+			_ = &mut deadline => break,
+		}
+	}
+	Some(batch)
+}
+
+pub(super) fn entry(
+	log: &RequestLog,
+	duration: Duration,
+	headers: Headers,
+	custom_tags: HashMap<String, String>,
+) -> HttpAccessLogEntry {
+	let upstream_remote_address = match &log.endpoint {
+		Some(Target::Address(a)) => Some(address(*a)),
+		_ => None,
+	};
+	HttpAccessLogEntry {
+		common_properties: Some(AccessLogCommon {
+			sample_rate: 1.0,
+			downstream_remote_address: Some(address(log.tcp_info.peer_addr)),
+			downstream_local_address: Some(address(log.tcp_info.local_addr)),
+			start_time: Some((SystemTime::now() - duration).into()),
+			time_to_last_downstream_tx_byte: duration.try_into().ok(),
+			upstream_remote_address,
+			upstream_cluster: log
+				.backend_info
+				.as_ref()
+				.map(|b| b.backend_name.to_string())
+				.unwrap_or_default(),
+			route_name: log
+				.route_name
+				.as_ref()
+				.map(|r| r.as_route_name().to_string())
+				.unwrap_or_default(),
+			custom_tags,
+		}),
+		protocol_version: log.version.map(http_version).unwrap_or_default() as i32,
+		request: Some(HttpRequestProperties {
+			request_method: log.method.as_ref().map(request_method).unwrap_or_default() as i32,
+			scheme: if log.tls_info.is_some() {
+				"https"
+			} else {
+				"http"
+			}
+			.to_string(),
+			authority: log.host.clone().unwrap_or_default(),
+			path: log.path.clone().unwrap_or_default(),
+			user_agent: headers.user_agent.unwrap_or_default(),
+			referer: headers.referer.unwrap_or_default(),
+			request_id: headers.request_id.unwrap_or_default(),
+		}),
+		response: Some(HttpResponseProperties {
+			response_code: log.status.map(|s| s.as_u16() as u32),
+			response_body_bytes: log.response_bytes,
+			response_code_details: log.reason.map(|r| r.to_string()).unwrap_or_default(),
+		}),
+	}
+}
+
+fn address(addr: SocketAddr) -> Address {
+	Address {
+		address: Some(proto::address::Address::SocketAddress(SocketAddress {
+			address: addr.ip().to_string(),
+			port_specifier: Some(proto::socket_address::PortSpecifier::PortValue(
+				addr.port() as u32
+			)),
+		})),
+	}
+}
+
+fn http_version(v: ::http::Version) -> HttpVersion {
+	match v {
+		::http::Version::HTTP_10 => HttpVersion::Http10,
+		::http::Version::HTTP_11 => HttpVersion::Http11,
+		::http::Version::HTTP_2 => HttpVersion::Http2,
+		::http::Version::HTTP_3 => HttpVersion::Http3,
+		_ => HttpVersion::ProtocolUnspecified,
+	}
+}
+
+fn request_method(m: &::http::Method) -> RequestMethod {
+	match *m {
+		::http::Method::GET => RequestMethod::Get,
+		::http::Method::HEAD => RequestMethod::Head,
+		::http::Method::POST => RequestMethod::Post,
+		::http::Method::PUT => RequestMethod::Put,
+		::http::Method::DELETE => RequestMethod::Delete,
+		::http::Method::CONNECT => RequestMethod::Connect,
+		::http::Method::OPTIONS => RequestMethod::Options,
+		::http::Method::TRACE => RequestMethod::Trace,
+		::http::Method::PATCH => RequestMethod::Patch,
+		_ => RequestMethod::MethodUnspecified,
+	}
+}
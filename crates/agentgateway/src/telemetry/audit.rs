@@ -0,0 +1,439 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+use macro_rules_attribute::apply;
+use tokio::sync::mpsc;
+
+use crate::proxy::ProxyResponseReason;
+use crate::telemetry::log::RequestLog;
+use crate::telemetry::metrics::{GuardrailAction, GuardrailPhase};
+use crate::telemetry::sink::BoundedSink;
+use crate::types::agent::{PolicyKey, PolicyTarget, TargetedPolicy};
+use crate::*;
+
+#[cfg(test)]
+#[path = "audit_tests.rs"]
+mod tests;
+
+static AUDIT: OnceLock<AuditLog> = OnceLock::new();
+
+const CHANNEL_SIZE: usize = 4096;
+
+/// The kinds of security events recorded in the audit log.
+#[apply(schema!)]
+#[derive(Copy, PartialEq, Eq, Hash)]
+pub enum EventKind {
+	AuthenticationFailure,
+	AuthorizationDenied,
+	RateLimited,
+	PolicyChange,
+	GuardrailVerdict,
+}
+
+/// Configuration for the security audit log. Audit events are written separately from access logs,
+/// as one JSON object per line.
+#[apply(schema!)]
+pub struct Config {
+	/// Destinations for audit events. If unset, events are written to stdout.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub sinks: Vec<Sink>,
+	/// Event kinds to record. If unset, all events are recorded.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub events: Vec<EventKind>,
+}
+
+#[apply(schema!)]
+pub enum Sink {
+	Stdout,
+	Stderr,
+	File(FileSink),
+}
+
+#[apply(schema!)]
+pub struct FileSink {
+	pub path: PathBuf,
+	/// Rotate the file once it reaches this size, in bytes.
+	#[serde(default = "defaults::max_size")]
+	pub max_size: u64,
+	/// Number of rotated files to keep.
+	#[serde(default = "defaults::max_files")]
+	pub max_files: usize,
+	/// Also rotate the file once it has been open for this long, regardless of its size.
+	#[serde(
+		default,
+		with = "serde_dur_option",
+		skip_serializing_if = "Option::is_none"
+	)]
+	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+	pub rotate_interval: Option<Duration>,
+	/// Remove rotated files older than this duration.
+	#[serde(
+		default,
+		with = "serde_dur_option",
+		skip_serializing_if = "Option::is_none"
+	)]
+	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+	pub max_age: Option<Duration>,
+}
+
+mod defaults {
+	pub fn max_size() -> u64 {
+		100 * 1024 * 1024
+	}
+	pub fn max_files() -> usize {
+		10
+	}
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Event {
+	pub timestamp: String,
+	pub kind: EventKind,
+	#[serde(flatten)]
+	pub details: Details,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(untagged)]
+pub enum Details {
+	Request(RequestDetails),
+	Policy(PolicyDetails),
+	Guardrail(GuardrailDetails),
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestDetails {
+	pub reason: String,
+	pub src_addr: SocketAddr,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub method: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub host: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub path: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub status: Option<u16>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub gateway: Option<Strng>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub listener: Option<Strng>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub route: Option<Strng>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub jwt_sub: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
+}
+
+#[apply(schema_ser!)]
+#[derive(Copy, PartialEq, Eq)]
+pub enum PolicyAction {
+	Added,
+	Updated,
+	Removed,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyDetails {
+	pub action: PolicyAction,
+	pub policy: PolicyKey,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub name: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub target: Option<PolicyTarget>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GuardrailDetails {
+	pub phase: GuardrailPhase,
+	pub action: GuardrailAction,
+	/// The kind of guard that reached the verdict, such as `regex` or `classifier`.
+	pub guard: &'static str,
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub categories: Vec<Strng>,
+}
+
+pub struct AuditLog {
+	events: Vec<EventKind>,
+	sink: BoundedSink<Event>,
+}
+
+/// Initialize the global audit log. Events recorded before this is called, or when it is never called,
+/// are discarded.
+pub fn init(cfg: &Config) -> anyhow::Result<()> {
+	let writers = open_writers(&cfg.sinks)?;
+	let (sink, rx) = BoundedSink::new("audit log", CHANNEL_SIZE);
+	std::thread::Builder::new()
+		.name("audit-log".to_string())
+		.spawn(move || write_loop(rx, writers))?;
+	AUDIT
+		.set(AuditLog {
+			events: cfg.events.clone(),
+			sink,
+		})
+		.map_err(|_| anyhow::anyhow!("audit log already initialized"))
+}
+
+/// Returns true if events of the given kind are recorded.
+pub fn enabled(kind: EventKind) -> bool {
+	AUDIT.get().is_some_and(|a| a.enabled(kind))
+}
+
+/// Record the outcome of a request, if it was rejected for a security reason.
+pub fn record_request(log: &RequestLog) {
+	let Some(kind) = log.reason.and_then(kind_for_reason) else {
+		return;
+	};
+	let Some(audit) = AUDIT.get().filter(|a| a.enabled(kind)) else {
+		return;
+	};
+	audit.sink.send(Event {
+		timestamp: agent_core::telemetry::render_current_time(),
+		kind,
+		details: Details::Request(request_details(log)),
+	});
+}
+
+/// Record a policy being added, updated, or removed.
+pub fn record_policy_change(action: PolicyAction, key: &PolicyKey, pol: Option<&TargetedPolicy>) {
+	let Some(audit) = AUDIT.get().filter(|a| a.enabled(EventKind::PolicyChange)) else {
+		return;
+	};
+	audit.sink.send(Event {
+		timestamp: agent_core::telemetry::render_current_time(),
+		kind: EventKind::PolicyChange,
+		details: Details::Policy(PolicyDetails {
+			action,
+			policy: key.clone(),
+			name: pol.and_then(|p| p.name.as_ref()).map(|n| n.to_string()),
+			target: pol.map(|p| p.target.clone()),
+		}),
+	});
+}
+
+/// Record a prompt guard masking, annotating, or rejecting an LLM request or response.
+pub fn record_guardrail(details: GuardrailDetails) {
+	let Some(audit) = AUDIT
+		.get()
+		.filter(|a| a.enabled(EventKind::GuardrailVerdict))
+	else {
+		return;
+	};
+	audit.sink.send(Event {
+		timestamp: agent_core::telemetry::render_current_time(),
+		kind: EventKind::GuardrailVerdict,
+		details: Details::Guardrail(details),
+	});
+}
+
+/// Record an LLM request rejected for a model its identity may not use.
+pub fn record_model_denied(log: &RequestLog, error: String) {
+	let Some(audit) = AUDIT
+		.get()
+		.filter(|a| a.enabled(EventKind::AuthorizationDenied))
+	else {
+		return;
+	};
+	audit.sink.send(Event {
+		timestamp: agent_core::telemetry::render_current_time(),
+		kind: EventKind::AuthorizationDenied,
+		details: Details::Request(RequestDetails {
+			reason: ProxyResponseReason::Authorization.to_string(),
+			status: Some(::http::StatusCode::FORBIDDEN.as_u16()),
+			error: Some(error),
+			..request_details(log)
+		}),
+	});
+}
+
+impl AuditLog {
+	fn enabled(&self, kind: EventKind) -> bool {
+		self.events.is_empty() || self.events.contains(&kind)
+	}
+}
+
+fn kind_for_reason(reason: ProxyResponseReason) -> Option<EventKind> {
+	match reason {
+		ProxyResponseReason::JwtAuth
+		| ProxyResponseReason::BasicAuth
+		| ProxyResponseReason::APIKeyAuth
+		| ProxyResponseReason::Signature => Some(EventKind::AuthenticationFailure),
+		ProxyResponseReason::ExtAuth | ProxyResponseReason::Authorization => {
+			Some(EventKind::AuthorizationDenied)
+		},
+		ProxyResponseReason::RateLimit => Some(EventKind::RateLimited),
+		_ => None,
+	}
+}
+
+fn request_details(log: &RequestLog) -> RequestDetails {
+	RequestDetails {
+		reason: log.reason.map(|r| r.to_string()).unwrap_or_default(),
+		src_addr: log.tcp_info.peer_addr,
+		method: log.method.as_ref().map(|m| m.to_string()),
+		host: log.host.clone(),
+		path: log.path.clone(),
+		status: log.status.map(|s| s.as_u16()),
+		gateway: log.listener_name.as_ref().map(|l| l.as_gateway_name()),
+		listener: log.listener_name.as_ref().map(|l| l.listener_name.clone()),
+		route: log.route_name.as_ref().map(|r| r.as_route_name()),
+		jwt_sub: log.jwt_sub.clone(),
+		error: log.error.clone(),
+	}
+}
+
+pub(super) enum Writer {
+	Stdout,
+	Stderr,
+	File(RotatingFile),
+}
+
+/// Open the given sinks for writing, defaulting to stdout if there are none.
+pub(super) fn open_writers(sinks: &[Sink]) -> anyhow::Result<Vec<Writer>> {
+	if sinks.is_empty() {
+		return Ok(vec![Writer::Stdout]);
+	}
+	sinks
+		.iter()
+		.map(|s| {
+			Ok(match s {
+				Sink::Stdout => Writer::Stdout,
+				Sink::Stderr => Writer::Stderr,
+				Sink::File(f) => Writer::File(RotatingFile::open(f.clone())?),
+			})
+		})
+		.collect()
+}
+
+impl Writer {
+	pub(super) fn write_line(&mut self, line: &[u8]) -> std::io::Result<()> {
+		match self {
+			Writer::Stdout => std::io::stdout().lock().write_all(line),
+			Writer::Stderr => std::io::stderr().lock().write_all(line),
+			Writer::File(f) => f.write_line(line),
+		}
+	}
+}
+
+fn write_loop(mut rx: mpsc::Receiver<Event>, mut writers: Vec<Writer>) {
+	while let Some(event) = rx.blocking_recv() {
+		let mut line = match serde_json::to_vec(&event) {
+			Ok(l) => l,
+			Err(e) => {
+				warn!("failed to serialize audit event: {e}");
+				continue;
+			},
+		};
+		line.push(b'\n');
+		for w in writers.iter_mut() {
+			if let Err(e) = w.write_line(&line) {
+				warn!("failed to write audit event: {e}");
+			}
+		}
+	}
+}
+
+/// A file that is rotated once it exceeds a maximum size or age. Rotated files are named `<path>.1`
+/// (newest) through `<path>.<maxFiles>` (oldest).
+pub(super) struct RotatingFile {
+	cfg: FileSink,
+	file: File,
+	size: u64,
+	opened: SystemTime,
+}
+
+impl RotatingFile {
+	pub(super) fn open(cfg: FileSink) -> anyhow::Result<Self> {
+		if let Some(parent) = cfg.path.parent()
+			&& !parent.as_os_str().is_empty()
+		{
+			std::fs::create_dir_all(parent)?;
+		}
+		let file = OpenOptions::new()
+			.create(true)
+			.append(true)
+			.open(&cfg.path)?;
+		let size = file.metadata()?.len();
+		let f = RotatingFile {
+			cfg,
+			file,
+			size,
+			opened: SystemTime::now(),
+		};
+		f.prune();
+		Ok(f)
+	}
+
+	pub(super) fn write_line(&mut self, line: &[u8]) -> std::io::Result<()> {
+		if self.size > 0 && (self.size + line.len() as u64 > self.cfg.max_size || self.expired()) {
+			self.rotate()?;
+		}
+		self.file.write_all(line)?;
+		self.size += line.len() as u64;
+		Ok(())
+	}
+
+	fn rotate(&mut self) -> std::io::Result<()> {
+		self.file.flush()?;
+		let max = self.cfg.max_files;
+		if max == 0 {
+			self.file.set_len(0)?;
+		} else {
+			let _ = std::fs::remove_file(rotated_path(&self.cfg.path, max));
+			for i in (1..max).rev() {
+				let from = rotated_path(&self.cfg.path, i);
+				if from.exists() {
+					std::fs::rename(&from, rotated_path(&self.cfg.path, i + 1))?;
+				}
+			}
+			std::fs::rename(&self.cfg.path, rotated_path(&self.cfg.path, 1))?;
+			self.file = OpenOptions::new()
+				.create(true)
+				.append(true)
+				.open(&self.cfg.path)?;
+		}
+		self.size = 0;
+		self.opened = SystemTime::now();
+		self.prune();
+		Ok(())
+	}
+
+	fn expired(&self) -> bool {
+		self.cfg.rotate_interval.is_some_and(|interval| {
+			SystemTime::now()
+				.duration_since(self.opened)
+				.is_ok_and(|age| age >= interval)
+		})
+	}
+
+	/// Remove rotated files older than the configured maximum age.
+	fn prune(&self) {
+		let Some(max_age) = self.cfg.max_age else {
+			return;
+		};
+		let now = SystemTime::now();
+		for i in 1..=self.cfg.max_files {
+			let p = rotated_path(&self.cfg.path, i);
+			let expired = std::fs::metadata(&p)
+				.and_then(|m| m.modified())
+				.is_ok_and(|m| now.duration_since(m).is_ok_and(|age| age > max_age));
+			if expired && let Err(e) = std::fs::remove_file(&p) {
+				warn!("failed to remove expired audit log {}: {e}", p.display());
+			}
+		}
+	}
+}
+
+fn rotated_path(path: &Path, n: usize) -> PathBuf {
+	let mut p = path.as_os_str().to_owned();
+	p.push(format!(".{n}"));
+	PathBuf::from(p)
+}
@@ -0,0 +1,82 @@
+use super::*;
+
+fn file_sink(dir: &Path, max_size: u64, max_files: usize) -> FileSink {
+	FileSink {
+		path: dir.join("audit.log"),
+		max_size,
+		max_files,
+		max_age: None,
+		rotate_interval: None,
+	}
+}
+
+#[test]
+fn test_rotation() {
+	let dir = tempfile::tempdir().unwrap();
+	let cfg = file_sink(dir.path(), 10, 2);
+	let mut f = RotatingFile::open(cfg.clone()).unwrap();
+	for line in ["first-1\n", "second\n", "third-\n", "fourth\n"] {
+		f.write_line(line.as_bytes()).unwrap();
+	}
+	let read = |n: usize| {
+		let p = if n == 0 {
+			cfg.path.clone()
+		} else {
+			rotated_path(&cfg.path, n)
+		};
+		std::fs::read_to_string(p).unwrap()
+	};
+	assert_eq!(read(0), "fourth\n");
+	assert_eq!(read(1), "third-\n");
+	assert_eq!(read(2), "second\n");
+	// Only maxFiles rotated files are kept
+	assert!(!rotated_path(&cfg.path, 3).exists());
+}
+
+#[test]
+fn test_interval_rotation() {
+	let dir = tempfile::tempdir().unwrap();
+	let cfg = FileSink {
+		rotate_interval: Some(Duration::ZERO),
+		..file_sink(dir.path(), 1024, 2)
+	};
+	let mut f = RotatingFile::open(cfg.clone()).unwrap();
+	f.write_line(b"first\n").unwrap();
+	f.write_line(b"second\n").unwrap();
+	assert_eq!(std::fs::read_to_string(&cfg.path).unwrap(), "second\n");
+	assert_eq!(
+		std::fs::read_to_string(rotated_path(&cfg.path, 1)).unwrap(),
+		"first\n"
+	);
+}
+
+#[test]
+fn test_reason_mapping() {
+	assert_eq!(
+		kind_for_reason(ProxyResponseReason::JwtAuth),
+		Some(EventKind::AuthenticationFailure)
+	);
+	assert_eq!(
+		kind_for_reason(ProxyResponseReason::Authorization),
+		Some(EventKind::AuthorizationDenied)
+	);
+	assert_eq!(
+		kind_for_reason(ProxyResponseReason::RateLimit),
+		Some(EventKind::RateLimited)
+	);
+	assert_eq!(kind_for_reason(ProxyResponseReason::Upstream), None);
+}
+
+#[test]
+fn test_config() {
+	let cfg: Config = serde_json::from_value(serde_json::json!({
+		"sinks": ["stdout", {"file": {"path": "/var/log/audit.log", "maxAge": "720h"}}],
+		"events": ["authenticationFailure", "policyChange"],
+	}))
+	.unwrap();
+	let Sink::File(f) = &cfg.sinks[1] else {
+		panic!("expected file sink");
+	};
+	assert_eq!(f.max_files, 10);
+	assert_eq!(f.max_age, Some(Duration::from_secs(720 * 3600)));
+}
@@ -0,0 +1,155 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Mutex;
+
+use agent_core::metrics::DefaultedUnknown;
+use agent_core::strng::RichStrng;
+use macro_rules_attribute::apply;
+
+use crate::*;
+
+#[cfg(test)]
+#[path = "cardinality_tests.rs"]
+mod tests;
+
+/// Recorded in place of values that are not in a label's allowlist.
+pub const OTHER: &str = "other";
+/// Recorded in place of new values once a label has reached its maximum number of values.
+pub const OVERFLOW: &str = "overflow";
+
+/// Limits the distinct values of metric labels derived from requests, such as `route`, `backend`,
+/// `tool`, and `model`, so that they cannot grow the number of series without bound.
+#[apply(schema!)]
+#[derive(Default)]
+pub struct Config {
+	/// Maximum number of distinct values recorded for each label. Further values are recorded as
+	/// `overflow`.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub max_values: Option<usize>,
+	/// Settings for individual labels, by label name, overriding `maxValues`.
+	#[serde(default, skip_serializing_if = "HashMap::is_empty")]
+	pub labels: HashMap<String, LabelConfig>,
+}
+
+#[apply(schema!)]
+#[derive(Default)]
+pub struct LabelConfig {
+	/// Only record these values. Other values are recorded as `other`.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub allow: Option<Vec<String>>,
+	/// Maximum number of distinct values recorded for this label.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub max_values: Option<usize>,
+}
+
+#[derive(Debug, Default)]
+struct LabelState {
+	values: HashSet<RichStrng>,
+	overflowed: u64,
+}
+
+/// Applies a cardinality `Config` to label values, tracking the values seen for each label.
+#[derive(Debug, Default)]
+pub struct Limiter {
+	cfg: Config,
+	allow: HashMap<String, HashSet<String>>,
+	seen: Mutex<HashMap<&'static str, LabelState>>,
+}
+
+impl Limiter {
+	pub fn new(cfg: Config) -> Self {
+		let allow = cfg
+			.labels
+			.iter()
+			.filter_map(|(k, v)| {
+				v.allow
+					.as_ref()
+					.map(|a| (k.clone(), a.iter().cloned().collect()))
+			})
+			.collect();
+		Limiter {
+			cfg,
+			allow,
+			seen: Default::default(),
+		}
+	}
+
+	fn max_values(&self, label: &str) -> Option<usize> {
+		self
+			.cfg
+			.labels
+			.get(label)
+			.and_then(|l| l.max_values)
+			.or(self.cfg.max_values)
+	}
+
+	/// Returns the value to record for `label`: the value itself, `other` if it is not allowed, or
+	/// `overflow` if the label already has its maximum number of distinct values.
+	pub fn limit(
+		&self,
+		label: &'static str,
+		value: DefaultedUnknown<RichStrng>,
+	) -> DefaultedUnknown<RichStrng> {
+		let Some(v) = value.as_ref() else {
+			return value;
+		};
+		if let Some(allow) = self.allow.get(label)
+			&& !allow.contains(v.as_str())
+		{
+			return RichStrng::from(OTHER).into();
+		}
+		let Some(max) = self.max_values(label) else {
+			return value;
+		};
+		let mut seen = self.seen.lock().expect("mutex acquired");
+		let state = seen.entry(label).or_default();
+		if state.values.contains(v) {
+			return value;
+		}
+		if state.values.len() >= max {
+			state.overflowed += 1;
+			return RichStrng::from(OVERFLOW).into();
+		}
+		state.values.insert(v.clone());
+		value
+	}
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LabelDump {
+	values: usize,
+	overflowed: u64,
+}
+
+impl serde::Serialize for Limiter {
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		#[derive(serde::Serialize)]
+		#[serde(rename_all = "camelCase")]
+		struct Dump<'a> {
+			#[serde(flatten)]
+			cfg: &'a Config,
+			/// The current number of distinct values of each limited label.
+			current: BTreeMap<&'static str, LabelDump>,
+		}
+		let current = self
+			.seen
+			.lock()
+			.expect("mutex acquired")
+			.iter()
+			.map(|(k, s)| {
+				(
+					*k,
+					LabelDump {
+						values: s.values.len(),
+						overflowed: s.overflowed,
+					},
+				)
+			})
+			.collect();
+		Dump {
+			cfg: &self.cfg,
+			current,
+		}
+		.serialize(serializer)
+	}
+}
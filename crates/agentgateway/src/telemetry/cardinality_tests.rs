@@ -0,0 +1,79 @@
+use super::*;
+
+fn value(v: &str) -> DefaultedUnknown<RichStrng> {
+	RichStrng::from(v).into()
+}
+
+fn limited(l: &Limiter, label: &'static str, v: &str) -> String {
+	l.limit(label, value(v))
+		.as_deref()
+		.map(|v| v.to_string())
+		.unwrap_or_default()
+}
+
+#[test]
+fn test_unlimited() {
+	let l = Limiter::new(Config::default());
+	for i in 0..100 {
+		assert_eq!(limited(&l, "backend", &format!("b{i}")), format!("b{i}"));
+	}
+	// Nothing is tracked without limits.
+	assert!(l.seen.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_max_values() {
+	let l = Limiter::new(Config {
+		max_values: Some(2),
+		labels: HashMap::from([(
+			"route".to_string(),
+			LabelConfig {
+				allow: None,
+				max_values: Some(1),
+			},
+		)]),
+	});
+	assert_eq!(limited(&l, "backend", "a"), "a");
+	assert_eq!(limited(&l, "backend", "b"), "b");
+	assert_eq!(limited(&l, "backend", "c"), OVERFLOW);
+	// Values already seen keep being recorded.
+	assert_eq!(limited(&l, "backend", "a"), "a");
+
+	assert_eq!(limited(&l, "route", "r1"), "r1");
+	assert_eq!(limited(&l, "route", "r2"), OVERFLOW);
+
+	// Missing values are left as unknown, and do not count towards the limit.
+	assert!(
+		l.limit("model", DefaultedUnknown::default())
+			.as_ref()
+			.is_none()
+	);
+
+	let dump = serde_json::to_value(&l).unwrap();
+	assert_eq!(
+		dump["current"],
+		serde_json::json!({
+			"backend": {"values": 2, "overflowed": 1},
+			"route": {"values": 1, "overflowed": 1},
+		})
+	);
+	assert_eq!(dump["maxValues"], 2);
+}
+
+#[test]
+fn test_allow() {
+	let l = Limiter::new(Config {
+		max_values: None,
+		labels: HashMap::from([(
+			"model".to_string(),
+			LabelConfig {
+				allow: Some(vec!["gpt-4o".to_string(), "claude".to_string()]),
+				max_values: None,
+			},
+		)]),
+	});
+	assert_eq!(limited(&l, "model", "gpt-4o"), "gpt-4o");
+	assert_eq!(limited(&l, "model", "llama"), OTHER);
+	// Other labels are not affected.
+	assert_eq!(limited(&l, "backend", "llama"), "llama");
+}
@@ -0,0 +1,408 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, OnceLock};
+
+use agent_core::strng::RichStrng;
+use chrono::{DateTime, TimeDelta, Utc};
+use macro_rules_attribute::apply;
+
+use crate::llm::LLMInfo;
+use crate::telemetry::log::{CelLoggingExecutor, RequestLog};
+use crate::telemetry::metrics::CostLabels;
+use crate::*;
+
+#[cfg(test)]
+#[path = "costs_tests.rs"]
+mod tests;
+
+static COSTS: OnceLock<CostStore> = OnceLock::new();
+
+/// Spend is aggregated into buckets of this size, so report windows have hour granularity.
+pub const BUCKET_SECONDS: i64 = 3600;
+
+/// Model name matching any model without its own price.
+const ANY_MODEL: &str = "*";
+
+/// Configuration for attributing the cost of LLM requests, from the tokens they use.
+#[apply(schema!)]
+pub struct Config {
+	/// Token prices by model name. The model reported by the provider is matched first, then the
+	/// requested model. A `*` entry applies to any other model.
+	pub models: HashMap<String, ModelPrice>,
+	/// CEL expression for the team a request is attributed to, such as `jwt.team`.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub team: Option<String>,
+	/// How long spend is kept for the `/costs` report.
+	#[serde(default = "default_retention", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub retention: Duration,
+	/// Periodically export usage records, for billing systems.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub export: Option<super::usage_export::Config>,
+}
+
+/// Prices in USD per million tokens.
+#[apply(schema!)]
+#[derive(Default, Copy, PartialEq)]
+pub struct ModelPrice {
+	#[serde(default)]
+	pub input: f64,
+	#[serde(default)]
+	pub output: f64,
+}
+
+impl ModelPrice {
+	fn cost(&self, input: u64, output: u64) -> f64 {
+		(input as f64 * self.input + output as f64 * self.output) / 1_000_000.0
+	}
+}
+
+fn default_retention() -> Duration {
+	Duration::from_secs(7 * 24 * 3600)
+}
+
+/// What a request's spend is attributed to.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize)]
+pub struct Attribution {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub consumer: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub team: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub route: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub model: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Spend {
+	pub requests: u64,
+	pub input_tokens: u64,
+	pub output_tokens: u64,
+	/// Cost in USD.
+	pub cost: f64,
+}
+
+impl Spend {
+	fn add(&mut self, other: &Spend) {
+		self.requests += other.requests;
+		self.input_tokens += other.input_tokens;
+		self.output_tokens += other.output_tokens;
+		self.cost += other.cost;
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+	Consumer,
+	Team,
+	Route,
+	Model,
+}
+
+/// A report over the spend recorded between `start` and `end`, grouped by some of the
+/// attribution fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+	pub start: DateTime<Utc>,
+	pub end: DateTime<Utc>,
+	pub group_by: Vec<GroupBy>,
+}
+
+impl Query {
+	/// Parse a report query from the query parameters `window` (a duration ending now, default
+	/// `24h`), or `start` and `end` (RFC 3339 timestamps), and `groupBy` (comma separated from
+	/// `consumer`, `team`, `route`, and `model`, default `consumer`).
+	pub fn parse(query: Option<&str>, now: DateTime<Utc>) -> Result<Query, String> {
+		let mut window = None;
+		let mut start = None;
+		let mut end = None;
+		let mut group_by = vec![];
+		for (k, v) in url::form_urlencoded::parse(query.unwrap_or_default().as_bytes()) {
+			match k.as_ref() {
+				"window" => {
+					let d = agent_core::durfmt::parse(&v)
+						.map_err(|_| format!("invalid window {v:?}, expected a duration"))?;
+					let d = TimeDelta::from_std(d).map_err(|_| format!("window {v:?} is too large"))?;
+					window = Some(d);
+				},
+				"start" | "end" => {
+					let t = DateTime::parse_from_rfc3339(&v)
+						.map_err(|_| format!("invalid {k} {v:?}, expected an RFC 3339 timestamp"))?
+						.with_timezone(&Utc);
+					if k == "start" {
+						start = Some(t);
+					} else {
+						end = Some(t);
+					}
+				},
+				"groupBy" => {
+					for g in v.split(',').map(str::trim).filter(|g| !g.is_empty()) {
+						group_by.push(match g {
+							"consumer" => GroupBy::Consumer,
+							"team" => GroupBy::Team,
+							"route" => GroupBy::Route,
+							"model" => GroupBy::Model,
+							_ => {
+								return Err(format!(
+									"invalid groupBy {g:?}, expected one of consumer, team, route, model"
+								));
+							},
+						});
+					}
+				},
+				_ => {},
+			}
+		}
+		if window.is_some() && start.is_some() {
+			return Err("window and start cannot both be set".to_string());
+		}
+		let end = end.unwrap_or(now);
+		let start = start.unwrap_or_else(|| end - window.unwrap_or(TimeDelta::hours(24)));
+		if start > end {
+			return Err("start must be before end".to_string());
+		}
+		if group_by.is_empty() {
+			group_by.push(GroupBy::Consumer);
+		}
+		Ok(Query {
+			start,
+			end,
+			group_by,
+		})
+	}
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportRow {
+	#[serde(flatten)]
+	pub attribution: Attribution,
+	#[serde(flatten)]
+	pub spend: Spend,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Report {
+	pub start: DateTime<Utc>,
+	pub end: DateTime<Utc>,
+	pub total: Spend,
+	/// Spend per group, highest cost first.
+	pub groups: Vec<ReportRow>,
+}
+
+/// Spend per attribution, in time buckets.
+#[derive(Debug)]
+pub struct CostStore {
+	models: HashMap<String, ModelPrice>,
+	team: Option<cel::Expression>,
+	retention: Duration,
+	buckets: Mutex<BTreeMap<i64, HashMap<Attribution, Spend>>>,
+}
+
+impl CostStore {
+	pub fn new(cfg: &Config) -> anyhow::Result<Self> {
+		Ok(CostStore {
+			models: cfg.models.clone(),
+			team: cfg
+				.team
+				.as_ref()
+				.map(|t| cel::Expression::new_strict(t))
+				.transpose()?,
+			retention: cfg.retention,
+			buckets: Default::default(),
+		})
+	}
+
+	/// The price of the first of `models` with one, falling back to the `*` price.
+	fn price<'a>(&self, models: impl IntoIterator<Item = &'a str>) -> Option<&ModelPrice> {
+		models
+			.into_iter()
+			.find_map(|m| self.models.get(m))
+			.or_else(|| self.models.get(ANY_MODEL))
+	}
+
+	/// Record the spend of a request at `now`, returning its cost.
+	pub fn record(
+		&self,
+		attribution: Attribution,
+		price: &ModelPrice,
+		input: u64,
+		output: u64,
+		now: DateTime<Utc>,
+	) -> f64 {
+		let cost = price.cost(input, output);
+		let bucket = now.timestamp().div_euclid(BUCKET_SECONDS) * BUCKET_SECONDS;
+		let mut buckets = self.buckets.lock().expect("mutex acquired");
+		buckets
+			.entry(bucket)
+			.or_default()
+			.entry(attribution)
+			.or_default()
+			.add(&Spend {
+				requests: 1,
+				input_tokens: input,
+				output_tokens: output,
+				cost,
+			});
+		// Expire buckets that are entirely past the retention period.
+		if let Some(oldest) = TimeDelta::from_std(self.retention)
+			.ok()
+			.and_then(|r| now.checked_sub_signed(r))
+		{
+			let oldest = oldest.timestamp().div_euclid(BUCKET_SECONDS) * BUCKET_SECONDS;
+			*buckets = buckets.split_off(&oldest);
+		}
+		cost
+	}
+
+	/// The spend of each attribution in the bucket starting at `start`, a multiple of the bucket
+	/// size.
+	pub fn bucket(&self, start: i64) -> Vec<ReportRow> {
+		let buckets = self.buckets.lock().expect("mutex acquired");
+		let mut rows: Vec<_> = buckets
+			.get(&start)
+			.into_iter()
+			.flatten()
+			.map(|(a, s)| ReportRow {
+				attribution: a.clone(),
+				spend: s.clone(),
+			})
+			.collect();
+		rows.sort_by(|a, b| a.attribution.cmp(&b.attribution));
+		rows
+	}
+
+	/// Sum the spend in buckets overlapping the query window.
+	pub fn report(&self, q: &Query) -> Report {
+		let first = q.start.timestamp().div_euclid(BUCKET_SECONDS) * BUCKET_SECONDS;
+		let last = q.end.timestamp();
+		let mut groups: HashMap<Attribution, Spend> = HashMap::new();
+		// Fields that are not grouped by are dropped, so spend is summed across their values.
+		let group = |g: GroupBy, v: &Option<String>| v.clone().filter(|_| q.group_by.contains(&g));
+		let buckets = self.buckets.lock().expect("mutex acquired");
+		for (_, bucket) in buckets.range(first..=last) {
+			for (a, s) in bucket {
+				let key = Attribution {
+					consumer: group(GroupBy::Consumer, &a.consumer),
+					team: group(GroupBy::Team, &a.team),
+					route: group(GroupBy::Route, &a.route),
+					model: group(GroupBy::Model, &a.model),
+				};
+				groups.entry(key).or_default().add(s);
+			}
+		}
+		let mut total = Spend::default();
+		let mut groups: Vec<_> = groups
+			.into_iter()
+			.map(|(attribution, spend)| {
+				total.add(&spend);
+				ReportRow { attribution, spend }
+			})
+			.collect();
+		groups.sort_by(|a, b| {
+			b.spend
+				.cost
+				.total_cmp(&a.spend.cost)
+				.then_with(|| a.attribution.cmp(&b.attribution))
+		});
+		Report {
+			start: q.start,
+			end: q.end,
+			total,
+			groups,
+		}
+	}
+}
+
+/// Initialize the global cost store.
+pub fn init(cfg: &Config) -> anyhow::Result<()> {
+	let store = CostStore::new(cfg)?;
+	COSTS
+		.set(store)
+		.map_err(|_| anyhow::anyhow!("costs already initialized"))
+}
+
+/// Returns true if costs are configured.
+pub fn enabled() -> bool {
+	COSTS.get().is_some()
+}
+
+/// Register the attributes needed to attribute costs, so they are captured for each request.
+pub fn register(ctx: &mut cel::ContextBuilder) {
+	if let Some(team) = COSTS.get().and_then(|c| c.team.as_ref()) {
+		ctx.register_expression(team);
+	}
+}
+
+/// Report the spend in the query window, if costs are configured.
+pub fn report(q: &Query) -> Option<Report> {
+	COSTS.get().map(|c| c.report(q))
+}
+
+/// The spend of each attribution in the bucket starting at `start`, if costs are configured.
+pub fn bucket(start: i64) -> Option<Vec<ReportRow>> {
+	COSTS.get().map(|c| c.bucket(start))
+}
+
+/// The cost of an LLM request in USD, if costs are configured with a price for its model.
+pub fn cost(llm: &LLMInfo) -> Option<f64> {
+	let store = COSTS.get()?;
+	let response_model = llm.response.provider_model.as_deref();
+	let price = store.price(
+		response_model
+			.into_iter()
+			.chain([llm.request.request_model.as_str()]),
+	)?;
+	Some(price.cost(
+		llm.input_tokens().unwrap_or_default(),
+		llm.response.output_tokens.unwrap_or_default(),
+	))
+}
+
+/// Record the cost of an LLM request, both in the cost store and as the `gen_ai_cost` metric.
+pub fn record(log: &RequestLog, cel_exec: &CelLoggingExecutor, route: Option<&str>, llm: &LLMInfo) {
+	let Some(store) = COSTS.get() else {
+		return;
+	};
+	let response_model = llm.response.provider_model.as_deref();
+	let request_model = llm.request.request_model.as_str();
+	let Some(price) = store.price(response_model.into_iter().chain([request_model])) else {
+		return;
+	};
+	let input = llm.input_tokens().unwrap_or_default();
+	let output = llm.response.output_tokens.unwrap_or_default();
+	let team = store
+		.team
+		.as_ref()
+		.and_then(|t| match cel_exec.executor.eval(t) {
+			Ok(cel::Value::String(s)) => Some(s.to_string()),
+			_ => None,
+		});
+	let consumer = log.consumer();
+	let model = response_model.unwrap_or(request_model);
+	let attribution = Attribution {
+		consumer: consumer.clone(),
+		team: team.clone(),
+		route: route.map(str::to_string),
+		model: Some(model.to_string()),
+	};
+	let cost = store.record(attribution, price, input, output, Utc::now());
+
+	let limiter = &log.cel.metric_fields.cardinality;
+	log
+		.metrics
+		.gen_ai_cost
+		.get_or_create(&CostLabels {
+			gen_ai_system: llm.request.provider.clone().into(),
+			gen_ai_request_model: limiter.limit(
+				"gen_ai_request_model",
+				llm.request.request_model.clone().into(),
+			),
+			consumer: limiter.limit("consumer", consumer.map(RichStrng::from).into()),
+			team: limiter.limit("team", team.map(RichStrng::from).into()),
+			route: limiter.limit("route", route.map(RichStrng::from).into()),
+		})
+		.inc_by(cost);
+}
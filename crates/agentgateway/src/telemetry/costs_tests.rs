@@ -0,0 +1,138 @@
+use super::*;
+
+fn store() -> CostStore {
+	let cfg: Config = serde_json::from_value(serde_json::json!({
+		"models": {
+			"gpt-4o": {"input": 2.5, "output": 10},
+			"*": {"input": 1, "output": 1},
+		},
+		"retention": "48h",
+	}))
+	.unwrap();
+	CostStore::new(&cfg).unwrap()
+}
+
+fn at(s: &str) -> DateTime<Utc> {
+	DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+}
+
+fn attribution(consumer: &str, team: &str, model: &str) -> Attribution {
+	Attribution {
+		consumer: Some(consumer.to_string()),
+		team: Some(team.to_string()),
+		route: Some("default/llm".to_string()),
+		model: Some(model.to_string()),
+	}
+}
+
+#[test]
+fn test_price() {
+	let s = store();
+	assert_eq!(s.price(["gpt-4o-2024-08-06", "gpt-4o"]).unwrap().input, 2.5);
+	assert_eq!(s.price(["llama"]).unwrap().input, 1.0);
+	assert_eq!(
+		ModelPrice {
+			input: 2.5,
+			output: 10.0
+		}
+		.cost(1_000_000, 500_000),
+		7.5
+	);
+
+	let s = CostStore::new(&Config {
+		models: HashMap::from([("gpt-4o".to_string(), ModelPrice::default())]),
+		team: None,
+		retention: default_retention(),
+		export: None,
+	})
+	.unwrap();
+	assert!(s.price(["llama"]).is_none());
+}
+
+#[test]
+fn test_report() {
+	let s = store();
+	let gpt = *s.price(["gpt-4o"]).unwrap();
+	let other = *s.price(["llama"]).unwrap();
+	s.record(
+		attribution("apikey:a", "search", "gpt-4o"),
+		&gpt,
+		1_000_000,
+		0,
+		at("2026-01-01T10:15:00Z"),
+	);
+	s.record(
+		attribution("apikey:b", "search", "llama"),
+		&other,
+		500_000,
+		500_000,
+		at("2026-01-01T10:30:00Z"),
+	);
+	s.record(
+		attribution("apikey:a", "search", "gpt-4o"),
+		&gpt,
+		0,
+		100_000,
+		at("2026-01-01T12:00:00Z"),
+	);
+
+	let q = Query::parse(
+		Some("start=2026-01-01T10:00:00Z&end=2026-01-01T11:00:00Z"),
+		at("2026-01-01T12:30:00Z"),
+	)
+	.unwrap();
+	let r = s.report(&q);
+	assert_eq!(r.total.requests, 2);
+	assert_eq!(r.total.cost, 3.5);
+	let consumers: Vec<_> = r
+		.groups
+		.iter()
+		.map(|g| (g.attribution.consumer.as_deref().unwrap(), g.spend.cost))
+		.collect();
+	assert_eq!(consumers, vec![("apikey:a", 2.5), ("apikey:b", 1.0)]);
+
+	let q = Query::parse(Some("window=24h&groupBy=team"), at("2026-01-01T12:30:00Z")).unwrap();
+	let r = s.report(&q);
+	assert_eq!(r.groups.len(), 1);
+	assert_eq!(r.groups[0].attribution.team.as_deref(), Some("search"));
+	assert_eq!(r.groups[0].attribution.consumer, None);
+	assert_eq!(r.groups[0].spend.requests, 3);
+	assert_eq!(r.groups[0].spend.cost, 4.5);
+
+	// Spend past the retention period is dropped.
+	s.record(
+		attribution("apikey:a", "search", "gpt-4o"),
+		&gpt,
+		0,
+		0,
+		at("2026-01-03T11:00:00Z"),
+	);
+	let q = Query::parse(Some("window=720h"), at("2026-01-03T11:00:00Z")).unwrap();
+	assert_eq!(s.report(&q).total.requests, 2);
+}
+
+#[test]
+fn test_parse_query() {
+	let now = at("2026-01-01T12:00:00Z");
+	assert_eq!(
+		Query::parse(None, now).unwrap(),
+		Query {
+			start: at("2025-12-31T12:00:00Z"),
+			end: now,
+			group_by: vec![GroupBy::Consumer],
+		}
+	);
+	assert_eq!(
+		Query::parse(Some("window=1h&groupBy=team,model"), now).unwrap(),
+		Query {
+			start: at("2026-01-01T11:00:00Z"),
+			end: now,
+			group_by: vec![GroupBy::Team, GroupBy::Model],
+		}
+	);
+	assert!(Query::parse(Some("window=abc"), now).is_err());
+	assert!(Query::parse(Some("start=yesterday"), now).is_err());
+	assert!(Query::parse(Some("groupBy=region"), now).is_err());
+	assert!(Query::parse(Some("window=1h&start=2026-01-01T10:00:00Z"), now).is_err());
+	assert!(Query::parse(Some("start=2026-01-02T00:00:00Z"), now).is_err());
+}
@@ -0,0 +1,369 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex, RwLock};
+
+use chrono::{DateTime, Utc};
+use prometheus_client::collector::Collector;
+use prometheus_client::encoding::{DescriptorEncoder, EncodeMetric};
+use prometheus_client::metrics::MetricType;
+use prometheus_client::metrics::counter::ConstCounter;
+use prometheus_client::metrics::gauge::ConstGauge;
+use prometheus_client::registry::Registry;
+use tokio::sync::mpsc;
+
+use crate::management::mesh::MeshEvent;
+use crate::proxy::ProxyResponseReason;
+use crate::telemetry::log::RequestLog;
+use crate::*;
+
+#[cfg(test)]
+#[path = "events_tests.rs"]
+mod tests;
+
+static BUS: LazyLock<Bus> = LazyLock::new(Bus::default);
+
+/// Number of events queued for each streaming subscriber before it is disconnected.
+const QUEUE_SIZE: usize = 256;
+
+/// Number of recent events kept for `/debug/events`.
+const HISTORY_SIZE: usize = 1024;
+
+/// A change in the health of part of the gateway, or a notable failure while serving a request.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EventKind {
+	/// An endpoint was removed from load balancing after failing requests.
+	#[serde(rename_all = "camelCase")]
+	EndpointEvicted {
+		endpoint: String,
+		/// How long until the endpoint is considered again.
+		#[serde(with = "serde_dur")]
+		duration: Duration,
+	},
+	/// A previously evicted endpoint was returned to load balancing.
+	#[serde(rename_all = "camelCase")]
+	EndpointRestored { endpoint: String },
+	/// An endpoint's health score fell below its degraded threshold, so it is avoided by load
+	/// balancing.
+	#[serde(rename_all = "camelCase")]
+	EndpointDegraded { endpoint: String, score: f64 },
+	/// A degraded endpoint's health score recovered.
+	#[serde(rename_all = "camelCase")]
+	EndpointRecovered { endpoint: String, score: f64 },
+	/// Configuration was rejected. `source` is the xDS type URL, or `file` for local configuration.
+	#[serde(rename_all = "camelCase")]
+	ConfigRejected { source: String, error: String },
+	/// The workload certificate could not be renewed, and will expire at `expires_at`.
+	#[serde(rename_all = "camelCase")]
+	CertificateExpiring {
+		identity: String,
+		expires_at: DateTime<Utc>,
+	},
+	/// A mesh node registered, refreshed its registration, or was evicted.
+	Mesh(MeshEvent),
+	/// A request to a backend failed, or returned a 5xx status.
+	#[serde(rename_all = "camelCase")]
+	UpstreamFailed {
+		#[serde(skip_serializing_if = "Option::is_none")]
+		route: Option<String>,
+		#[serde(skip_serializing_if = "Option::is_none")]
+		backend: Option<String>,
+		#[serde(skip_serializing_if = "Option::is_none")]
+		endpoint: Option<String>,
+		#[serde(skip_serializing_if = "Option::is_none")]
+		status: Option<u16>,
+		#[serde(skip_serializing_if = "Option::is_none")]
+		error: Option<String>,
+	},
+	/// A request is being retried after a failed attempt. `attempt` is the retry number, starting
+	/// at 1.
+	#[serde(rename_all = "camelCase")]
+	RequestRetried {
+		#[serde(skip_serializing_if = "Option::is_none")]
+		route: Option<String>,
+		#[serde(skip_serializing_if = "Option::is_none")]
+		backend: Option<String>,
+		attempt: u8,
+	},
+	/// A request was rejected by an authentication, authorization, or rate limit policy.
+	#[serde(rename_all = "camelCase")]
+	PolicyDenied {
+		reason: String,
+		src_addr: SocketAddr,
+		#[serde(skip_serializing_if = "Option::is_none")]
+		route: Option<String>,
+		#[serde(skip_serializing_if = "Option::is_none")]
+		path: Option<String>,
+	},
+	/// Memory usage crossed a memory watchdog threshold, changing how much load is shed.
+	#[serde(rename_all = "camelCase")]
+	MemoryPressure {
+		pressure: crate::memory::Pressure,
+		usage: u64,
+		limit: u64,
+	},
+	/// The gateway is shutting down. Event streams end after this event. Connections still open
+	/// after `deadline` are closed forcefully.
+	#[serde(rename_all = "camelCase")]
+	ShuttingDown {
+		#[serde(with = "serde_dur")]
+		deadline: Duration,
+	},
+}
+
+impl EventKind {
+	/// The name of the kind, as it appears in the serialized event.
+	pub fn name(&self) -> &'static str {
+		match self {
+			EventKind::EndpointEvicted { .. } => "endpointEvicted",
+			EventKind::EndpointRestored { .. } => "endpointRestored",
+			EventKind::EndpointDegraded { .. } => "endpointDegraded",
+			EventKind::EndpointRecovered { .. } => "endpointRecovered",
+			EventKind::ConfigRejected { .. } => "configRejected",
+			EventKind::CertificateExpiring { .. } => "certificateExpiring",
+			EventKind::Mesh(_) => "mesh",
+			EventKind::UpstreamFailed { .. } => "upstreamFailed",
+			EventKind::RequestRetried { .. } => "requestRetried",
+			EventKind::PolicyDenied { .. } => "policyDenied",
+			EventKind::MemoryPressure { .. } => "memoryPressure",
+			EventKind::ShuttingDown { .. } => "shuttingDown",
+		}
+	}
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Event {
+	pub timestamp: DateTime<Utc>,
+	#[serde(flatten)]
+	pub kind: EventKind,
+}
+
+/// Receives every event published on the bus. Subscribers are called inline by the publisher, so
+/// they must not block.
+pub trait Subscriber: Send + Sync {
+	fn on_event(&self, event: &Event);
+}
+
+struct Bus {
+	streams: Mutex<Vec<mpsc::Sender<Event>>>,
+	subscribers: RwLock<Vec<Arc<dyn Subscriber>>>,
+	history: Mutex<History>,
+	/// Events not delivered to a stream because its queue was full. The stream is disconnected.
+	dropped: AtomicU64,
+}
+
+impl Default for Bus {
+	fn default() -> Self {
+		Bus {
+			streams: Default::default(),
+			subscribers: Default::default(),
+			history: Mutex::new(History::new(HISTORY_SIZE)),
+			dropped: AtomicU64::new(0),
+		}
+	}
+}
+
+/// Returned by [`Subscription::recv`] once the subscription fell too far behind and was
+/// disconnected. Events published since were not delivered, so the subscriber must resync.
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("subscriber fell behind and missed events")]
+pub struct Overflowed;
+
+/// Events published after [`subscribe`] was called.
+#[derive(Debug)]
+pub struct Subscription {
+	rx: mpsc::Receiver<Event>,
+}
+
+impl Subscription {
+	/// The next event. Events queued before the subscription fell behind are still delivered, and
+	/// then `Overflowed` is returned.
+	pub async fn recv(&mut self) -> Result<Event, Overflowed> {
+		self.rx.recv().await.ok_or(Overflowed)
+	}
+}
+
+/// The most recent events, oldest first.
+struct History {
+	events: VecDeque<Event>,
+	capacity: usize,
+}
+
+impl History {
+	fn new(capacity: usize) -> Self {
+		History {
+			events: VecDeque::with_capacity(capacity),
+			capacity,
+		}
+	}
+
+	fn push(&mut self, event: Event) {
+		if self.events.len() == self.capacity {
+			self.events.pop_front();
+		}
+		self.events.push_back(event);
+	}
+
+	/// Events published after `since`, or all events if unset.
+	fn since(&self, since: Option<DateTime<Utc>>) -> Vec<Event> {
+		self
+			.events
+			.iter()
+			.filter(|e| since.is_none_or(|since| e.timestamp > since))
+			.cloned()
+			.collect()
+	}
+}
+
+impl Bus {
+	fn publish(&self, kind: EventKind) {
+		let event = Event {
+			timestamp: Utc::now(),
+			kind,
+		};
+		debug!(?event, "publishing event");
+		for s in self.subscribers.read().expect("lock acquired").iter() {
+			s.on_event(&event);
+		}
+		self
+			.history
+			.lock()
+			.expect("lock acquired")
+			.push(event.clone());
+		// Streams that cannot keep up are disconnected, rather than silently missing events.
+		self
+			.streams
+			.lock()
+			.expect("lock acquired")
+			.retain(|tx| match tx.try_send(event.clone()) {
+				Ok(()) => true,
+				Err(mpsc::error::TrySendError::Full(_)) => {
+					warn!("event subscriber fell behind, disconnecting it");
+					self.dropped.fetch_add(1, Ordering::Relaxed);
+					false
+				},
+				Err(mpsc::error::TrySendError::Closed(_)) => false,
+			});
+	}
+
+	fn subscribe(&self, queue_size: usize) -> Subscription {
+		let (tx, rx) = mpsc::channel(queue_size);
+		self.streams.lock().expect("lock acquired").push(tx);
+		Subscription { rx }
+	}
+
+	fn subscriptions(&self) -> usize {
+		let mut streams = self.streams.lock().expect("lock acquired");
+		streams.retain(|tx| !tx.is_closed());
+		streams.len()
+	}
+}
+
+/// Publish an event to all subscribers.
+pub fn publish(kind: EventKind) {
+	BUS.publish(kind)
+}
+
+/// Recent events published after `since`, oldest first. Only the last 1024 events are kept.
+pub fn recent(since: Option<DateTime<Utc>>) -> Vec<Event> {
+	BUS.history.lock().expect("lock acquired").since(since)
+}
+
+/// Parse the `since` parameter of `/debug/events`: either an RFC 3339 timestamp, or a duration
+/// before `now`, such as `15m`.
+pub fn parse_since(v: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+	if let Ok(d) = agent_core::durfmt::parse(v) {
+		let d = chrono::TimeDelta::from_std(d).map_err(|_| format!("since {v:?} is too large"))?;
+		return Ok(now - d);
+	}
+	DateTime::parse_from_rfc3339(v)
+		.map(|t| t.with_timezone(&Utc))
+		.map_err(|_| format!("invalid since {v:?}, expected a duration or an RFC 3339 timestamp"))
+}
+
+/// Publish a `PolicyDenied` event if the request was rejected by a security policy.
+pub fn record_request(log: &RequestLog) {
+	let Some(reason) = log.reason.filter(|r| {
+		matches!(
+			r,
+			ProxyResponseReason::JwtAuth
+				| ProxyResponseReason::BasicAuth
+				| ProxyResponseReason::APIKeyAuth
+				| ProxyResponseReason::Signature
+				| ProxyResponseReason::ExtAuth
+				| ProxyResponseReason::Authorization
+				| ProxyResponseReason::RateLimit
+		)
+	}) else {
+		return;
+	};
+	publish(EventKind::PolicyDenied {
+		reason: reason.to_string(),
+		src_addr: log.tcp_info.peer_addr,
+		route: log
+			.route_name
+			.as_ref()
+			.map(|r| r.as_route_name().to_string()),
+		path: log.path.clone(),
+	});
+}
+
+/// Receive events published from now on. Up to 256 events are queued; a subscription that falls
+/// further behind is disconnected.
+pub fn subscribe() -> Subscription {
+	BUS.subscribe(QUEUE_SIZE)
+}
+
+/// The number of subscriptions that are still open.
+pub fn subscriptions() -> usize {
+	BUS.subscriptions()
+}
+
+/// Register a subscriber to be called for every event published from now on.
+pub fn add_subscriber(subscriber: Arc<dyn Subscriber>) {
+	BUS
+		.subscribers
+		.write()
+		.expect("lock acquired")
+		.push(subscriber);
+}
+
+/// Exposes the streaming subscribers of the event bus, and how many were disconnected for falling
+/// behind, at scrape time.
+#[derive(Debug)]
+pub struct EventsCollector;
+
+impl EventsCollector {
+	pub fn register(registry: &mut Registry) {
+		registry.register_collector(Box::new(EventsCollector));
+	}
+}
+
+impl Collector for EventsCollector {
+	fn encode(&self, mut encoder: DescriptorEncoder) -> Result<(), std::fmt::Error> {
+		let streams = BUS
+			.streams
+			.lock()
+			.expect("lock acquired")
+			.iter()
+			.filter(|tx| !tx.is_closed())
+			.count();
+		let metric_encoder = encoder.encode_descriptor(
+			"event_subscribers",
+			"Clients streaming events from the event bus",
+			None,
+			MetricType::Gauge,
+		)?;
+		ConstGauge::new(streams as i64).encode(metric_encoder)?;
+
+		let metric_encoder = encoder.encode_descriptor(
+			"events_dropped",
+			"Events dropped for streaming subscribers that fell behind. Each such subscriber is disconnected, and told to resync",
+			None,
+			MetricType::Counter,
+		)?;
+		ConstCounter::new(BUS.dropped.load(Ordering::Relaxed)).encode(metric_encoder)?;
+		Ok(())
+	}
+}
@@ -0,0 +1,179 @@
+use std::sync::Mutex;
+
+use super::*;
+
+#[derive(Default)]
+struct Recorder(Mutex<Vec<Event>>);
+
+impl Subscriber for Recorder {
+	fn on_event(&self, event: &Event) {
+		self.0.lock().unwrap().push(event.clone());
+	}
+}
+
+#[tokio::test]
+async fn test_publish() {
+	// The bus is global, so only look at events published by this test.
+	let is_ours =
+		|e: &Event| matches!(&e.kind, EventKind::ConfigRejected { source, .. } if source == "test");
+	let recorder = Arc::new(Recorder::default());
+	add_subscriber(recorder.clone());
+	let mut rx = subscribe();
+
+	publish(EventKind::ConfigRejected {
+		source: "test".to_string(),
+		error: "invalid route".to_string(),
+	});
+
+	let received = loop {
+		let e = rx.recv().await.unwrap();
+		if is_ours(&e) {
+			break e;
+		}
+	};
+	let json = serde_json::to_value(&received).unwrap();
+	assert_eq!(
+		json["configRejected"],
+		serde_json::json!({"source": "test", "error": "invalid route"})
+	);
+	assert!(json["timestamp"].is_string());
+
+	let recorded = recorder.0.lock().unwrap();
+	assert_eq!(recorded.iter().filter(|e| is_ours(e)).count(), 1);
+}
+
+#[tokio::test]
+async fn test_subscriber_overflow() {
+	let bus = Bus::default();
+	let mut slow = bus.subscribe(2);
+	let mut fast = bus.subscribe(2);
+	for source in ["a", "b"] {
+		bus.publish(EventKind::ConfigRejected {
+			source: source.to_string(),
+			error: "invalid".to_string(),
+		});
+	}
+	assert!(fast.recv().await.is_ok());
+	assert!(fast.recv().await.is_ok());
+	bus.publish(EventKind::ConfigRejected {
+		source: "c".to_string(),
+		error: "invalid".to_string(),
+	});
+
+	// The slow subscriber receives the events queued before it fell behind, and is then told it
+	// missed some.
+	let queued = [slow.recv().await.unwrap(), slow.recv().await.unwrap()];
+	assert_eq!(sources(&queued), vec!["a", "b"]);
+	assert_eq!(slow.recv().await.unwrap_err(), Overflowed);
+	assert_eq!(bus.dropped.load(Ordering::Relaxed), 1);
+	assert_eq!(sources(&[fast.recv().await.unwrap()]), vec!["c"]);
+	assert_eq!(bus.streams.lock().unwrap().len(), 1);
+
+	// Subscribers that go away are removed on the next publish.
+	drop(fast);
+	bus.publish(EventKind::ConfigRejected {
+		source: "d".to_string(),
+		error: "invalid".to_string(),
+	});
+	assert!(bus.streams.lock().unwrap().is_empty());
+	assert_eq!(bus.dropped.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn test_serialize_mesh() {
+	let event = Event {
+		timestamp: Utc::now(),
+		kind: EventKind::Mesh(MeshEvent::NodeRemoved("svc".to_string())),
+	};
+	let json = serde_json::to_value(&event).unwrap();
+	assert_eq!(json["mesh"], serde_json::json!({"nodeRemoved": "svc"}));
+}
+
+fn rejected(source: &str, timestamp: DateTime<Utc>) -> Event {
+	Event {
+		timestamp,
+		kind: EventKind::ConfigRejected {
+			source: source.to_string(),
+			error: "invalid".to_string(),
+		},
+	}
+}
+
+fn sources(events: &[Event]) -> Vec<&str> {
+	events
+		.iter()
+		.map(|e| match &e.kind {
+			EventKind::ConfigRejected { source, .. } => source.as_str(),
+			_ => unreachable!(),
+		})
+		.collect()
+}
+
+#[test]
+fn test_history() {
+	let t0 = Utc::now();
+	let mut h = History::new(3);
+	for (i, source) in ["a", "b", "c", "d"].into_iter().enumerate() {
+		h.push(rejected(source, t0 + chrono::TimeDelta::seconds(i as i64)));
+	}
+	// The oldest event was dropped to make room.
+	assert_eq!(sources(&h.since(None)), vec!["b", "c", "d"]);
+	assert_eq!(
+		sources(&h.since(Some(t0 + chrono::TimeDelta::seconds(2)))),
+		vec!["d"]
+	);
+	assert!(h.since(Some(t0 + chrono::TimeDelta::seconds(3))).is_empty());
+}
+
+#[test]
+fn test_parse_since() {
+	let now = DateTime::parse_from_rfc3339("2025-01-01T12:00:00Z")
+		.unwrap()
+		.with_timezone(&Utc);
+	assert_eq!(
+		parse_since("15m", now).unwrap(),
+		now - chrono::TimeDelta::minutes(15)
+	);
+	assert_eq!(
+		parse_since("2025-01-01T11:00:00Z", now).unwrap(),
+		now - chrono::TimeDelta::hours(1)
+	);
+	assert!(parse_since("yesterday", now).is_err());
+}
+
+#[test]
+fn test_kind_name() {
+	let kind = EventKind::RequestRetried {
+		route: Some("default/route".to_string()),
+		backend: None,
+		attempt: 1,
+	};
+	let json = serde_json::to_value(&kind).unwrap();
+	assert_eq!(
+		json.as_object().unwrap().keys().next().unwrap(),
+		kind.name()
+	);
+	assert_eq!(
+		json["requestRetried"],
+		serde_json::json!({"route": "default/route", "attempt": 1})
+	);
+}
+
+#[test]
+fn test_subscriptions() {
+	let bus = Bus::default();
+	let a = bus.subscribe(2);
+	let _b = bus.subscribe(2);
+	assert_eq!(bus.subscriptions(), 2);
+	drop(a);
+	assert_eq!(bus.subscriptions(), 1);
+
+	let kind = EventKind::ShuttingDown {
+		deadline: Duration::from_secs(30),
+	};
+	let json = serde_json::to_value(&kind).unwrap();
+	assert_eq!(
+		json,
+		serde_json::json!({"shuttingDown": {"deadline": "30s"}})
+	);
+}
@@ -0,0 +1,126 @@
+use opentelemetry::trace::SpanKind;
+use opentelemetry::{KeyValue, Value};
+
+use crate::llm::{InputFormat, LLMRequest, LLMResponse};
+use crate::mcp::{MCPInfo, MCPOperation};
+
+#[cfg(test)]
+#[path = "genai_tests.rs"]
+mod tests;
+
+/// A GenAI operation performed while serving a request, such as an LLM completion or an MCP tool
+/// call. Each is recorded as a child of the request span, following the OpenTelemetry GenAI
+/// semantic conventions (v1.37.0), so GenAI dashboards work against gateway traces.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operation {
+	pub name: String,
+	pub kind: SpanKind,
+	pub attributes: Vec<KeyValue>,
+}
+
+/// The `gen_ai.operation.name` of an LLM request.
+pub fn operation_name(format: InputFormat) -> &'static str {
+	match format {
+		InputFormat::Embeddings => "embeddings",
+		InputFormat::Rerank => "rerank",
+		_ => "chat",
+	}
+}
+
+/// The `gen_ai.operation.name` of an MCP method, for methods that have one.
+fn mcp_operation_name(method: &str) -> Option<&'static str> {
+	match method {
+		"tools/call" => Some("execute_tool"),
+		"sampling/createMessage" => Some("chat"),
+		_ => None,
+	}
+}
+
+/// The span for a call to an LLM provider, named `{operation} {model}`.
+pub fn llm(req: &LLMRequest, resp: Option<&LLMResponse>) -> Operation {
+	let operation = operation_name(req.input_format);
+	let mut attributes = vec![
+		KeyValue::new("gen_ai.operation.name", operation),
+		KeyValue::new("gen_ai.provider.name", req.provider.to_string()),
+		KeyValue::new("gen_ai.request.model", req.request_model.to_string()),
+	];
+	let p = &req.params;
+	let params: [(&'static str, Option<Value>); 7] = [
+		("gen_ai.request.temperature", p.temperature.map(Into::into)),
+		("gen_ai.request.top_p", p.top_p.map(Into::into)),
+		(
+			"gen_ai.request.frequency_penalty",
+			p.frequency_penalty.map(Into::into),
+		),
+		(
+			"gen_ai.request.presence_penalty",
+			p.presence_penalty.map(Into::into),
+		),
+		("gen_ai.request.seed", p.seed.map(Into::into)),
+		(
+			"gen_ai.request.max_tokens",
+			p.max_tokens.map(|v| (v as i64).into()),
+		),
+		(
+			"gen_ai.embeddings.dimension.count",
+			p.dimensions.map(|v| (v as i64).into()),
+		),
+	];
+	attributes.extend(
+		params
+			.into_iter()
+			.filter_map(|(k, v)| v.map(|v| KeyValue::new(k, v))),
+	);
+	if let Some(resp) = resp {
+		if let Some(model) = &resp.provider_model {
+			attributes.push(KeyValue::new("gen_ai.response.model", model.to_string()));
+		}
+		if let Some(input) = resp.input_tokens.or(req.input_tokens) {
+			attributes.push(KeyValue::new("gen_ai.usage.input_tokens", input as i64));
+		}
+		if let Some(output) = resp.output_tokens {
+			attributes.push(KeyValue::new("gen_ai.usage.output_tokens", output as i64));
+		}
+	}
+	Operation {
+		name: format!("{operation} {}", req.request_model),
+		kind: SpanKind::Client,
+		attributes,
+	}
+}
+
+/// The span for an MCP request, named `{method} {target}` where the target is the tool, prompt, or
+/// resource the request refers to. Tool calls are `execute_tool` operations.
+pub fn mcp(info: &MCPInfo) -> Option<Operation> {
+	let method = info.method_name.as_deref()?;
+	let mut attributes = vec![KeyValue::new("mcp.method.name", method.to_string())];
+	if let Some(operation) = mcp_operation_name(method) {
+		attributes.push(KeyValue::new("gen_ai.operation.name", operation));
+	}
+	if let Some(session) = &info.session_id {
+		attributes.push(KeyValue::new("mcp.session.id", session.clone()));
+	}
+	if let Some(target) = &info.target_name {
+		attributes.push(KeyValue::new("mcp.target", target.clone()));
+	}
+	if let Some(resource) = &info.resource_name {
+		let key = match info.resource {
+			Some(MCPOperation::Tool) => Some("gen_ai.tool.name"),
+			Some(MCPOperation::Prompt) => Some("gen_ai.prompt.name"),
+			Some(MCPOperation::Resource) => Some("mcp.resource.uri"),
+			Some(MCPOperation::ResourceTemplates) | None => None,
+		};
+		if let Some(key) = key {
+			attributes.push(KeyValue::new(key, resource.clone()));
+		}
+	}
+	let name = match &info.resource_name {
+		Some(target) => format!("{method} {target}"),
+		None => method.to_string(),
+	};
+	Some(Operation {
+		name,
+		kind: SpanKind::Internal,
+		attributes,
+	})
+}
@@ -0,0 +1,97 @@
+use super::*;
+use crate::llm::LLMRequestParams;
+use agent_core::strng;
+
+fn attr<'a>(op: &'a Operation, key: &str) -> Option<&'a Value> {
+	op.attributes
+		.iter()
+		.find(|kv| kv.key.as_str() == key)
+		.map(|kv| &kv.value)
+}
+
+fn request(input_format: InputFormat) -> LLMRequest {
+	LLMRequest {
+		input_tokens: Some(10),
+		input_format,
+		request_model: strng::literal!("gpt-4o"),
+		provider: strng::literal!("openai"),
+		streaming: false,
+		params: LLMRequestParams {
+			temperature: Some(0.5),
+			max_tokens: Some(100),
+			..Default::default()
+		},
+	}
+}
+
+#[test]
+fn test_llm() {
+	let resp = LLMResponse {
+		input_tokens: Some(12),
+		output_tokens: Some(34),
+		provider_model: Some(strng::literal!("gpt-4o-2024-08-06")),
+		..Default::default()
+	};
+	let op = llm(&request(InputFormat::Completions), Some(&resp));
+	assert_eq!(op.name, "chat gpt-4o");
+	assert_eq!(op.kind, SpanKind::Client);
+	assert_eq!(attr(&op, "gen_ai.operation.name"), Some(&"chat".into()));
+	assert_eq!(attr(&op, "gen_ai.provider.name"), Some(&"openai".into()));
+	assert_eq!(
+		attr(&op, "gen_ai.response.model"),
+		Some(&"gpt-4o-2024-08-06".into())
+	);
+	assert_eq!(attr(&op, "gen_ai.usage.input_tokens"), Some(&12i64.into()));
+	assert_eq!(attr(&op, "gen_ai.usage.output_tokens"), Some(&34i64.into()));
+	assert_eq!(attr(&op, "gen_ai.request.temperature"), Some(&0.5.into()));
+	assert_eq!(attr(&op, "gen_ai.request.max_tokens"), Some(&100i64.into()));
+	assert_eq!(attr(&op, "gen_ai.request.top_p"), None);
+
+	// Without a response, such as a rejected request, only the request is described.
+	let op = llm(&request(InputFormat::Embeddings), None);
+	assert_eq!(op.name, "embeddings gpt-4o");
+	assert_eq!(attr(&op, "gen_ai.usage.input_tokens"), None);
+}
+
+#[test]
+fn test_mcp_tool_call() {
+	let info = MCPInfo {
+		method_name: Some("tools/call".to_string()),
+		resource_name: Some("get_weather".to_string()),
+		target_name: Some("weather".to_string()),
+		resource: Some(MCPOperation::Tool),
+		session_id: Some("abc".to_string()),
+	};
+	let op = mcp(&info).unwrap();
+	assert_eq!(op.name, "tools/call get_weather");
+	assert_eq!(op.kind, SpanKind::Internal);
+	assert_eq!(
+		attr(&op, "gen_ai.operation.name"),
+		Some(&"execute_tool".into())
+	);
+	assert_eq!(attr(&op, "gen_ai.tool.name"), Some(&"get_weather".into()));
+	assert_eq!(attr(&op, "mcp.method.name"), Some(&"tools/call".into()));
+	assert_eq!(attr(&op, "mcp.session.id"), Some(&"abc".into()));
+}
+
+#[test]
+fn test_mcp_other_methods() {
+	let info = MCPInfo {
+		method_name: Some("sampling/createMessage".to_string()),
+		..Default::default()
+	};
+	let op = mcp(&info).unwrap();
+	assert_eq!(op.name, "sampling/createMessage");
+	assert_eq!(attr(&op, "gen_ai.operation.name"), Some(&"chat".into()));
+
+	let info = MCPInfo {
+		method_name: Some("tools/list".to_string()),
+		..Default::default()
+	};
+	let op = mcp(&info).unwrap();
+	assert_eq!(op.name, "tools/list");
+	assert_eq!(attr(&op, "gen_ai.operation.name"), None);
+
+	// GET and DELETE requests have no method, and are not GenAI operations.
+	assert_eq!(mcp(&MCPInfo::default()), None);
+}
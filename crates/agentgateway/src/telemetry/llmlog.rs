@@ -0,0 +1,248 @@
+use std::sync::OnceLock;
+
+use aws_lc_rs::digest;
+use macro_rules_attribute::apply;
+use tokio::sync::mpsc;
+
+use crate::llm::SimpleChatCompletionMessage;
+use crate::llm::policy::pii_redaction::PiiRedaction;
+use crate::telemetry::audit;
+use crate::telemetry::log::RequestLog;
+use crate::telemetry::sink::BoundedSink;
+use crate::*;
+
+#[cfg(test)]
+#[path = "llmlog_tests.rs"]
+mod tests;
+
+static LLM_LOG: OnceLock<LlmLog> = OnceLock::new();
+
+const CHANNEL_SIZE: usize = 1024;
+
+/// Configuration for the LLM log. Prompts and completions of requests to routes with an `llmLog`
+/// policy are recorded here, for debugging and for collecting evaluation datasets, as one JSON
+/// object per line.
+#[apply(schema!)]
+pub struct Config {
+	/// Destinations for LLM log entries. If unset, entries are written to stdout.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub sinks: Vec<audit::Sink>,
+}
+
+/// Per-route LLM logging. Only routes with this policy are recorded.
+#[apply(schema!)]
+pub struct Policy {
+	/// Fraction of requests to record, from 0 to 1. Defaults to 1.
+	#[serde(default = "default_sample_rate")]
+	pub sample_rate: f64,
+	/// How prompts are recorded. Defaults to `full`.
+	#[serde(default)]
+	pub prompts: Content,
+	/// How completions are recorded. Defaults to `full`.
+	#[serde(default)]
+	pub completions: Content,
+	/// Personal data to redact from recorded prompts and completions. This only affects the log;
+	/// use a `piiRedaction` policy to redact data sent to the provider. `restore` has no effect.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub redact: Option<PiiRedaction>,
+}
+
+#[apply(schema!)]
+#[derive(Default, Copy, PartialEq, Eq)]
+pub enum Content {
+	/// Record the full text.
+	#[default]
+	Full,
+	/// Record only a SHA-256 hash of the text, to group identical requests without storing them.
+	Hash,
+	/// Do not record the text.
+	Drop,
+}
+
+fn default_sample_rate() -> f64 {
+	1.0
+}
+
+/// Details of a sampled request, collected while it is proxied.
+#[derive(Debug)]
+pub struct Capture {
+	policy: Policy,
+	prompt: Option<Vec<SimpleChatCompletionMessage>>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry<'a> {
+	timestamp: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	request_id: Option<&'a str>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	trace_id: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	route: Option<Strng>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	consumer: Option<String>,
+	provider: Strng,
+	request_model: Strng,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	response_model: Option<Strng>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	status: Option<u16>,
+	#[serde(with = "serde_dur")]
+	duration: Duration,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	input_tokens: Option<u64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	output_tokens: Option<u64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	prompt: Option<Vec<SimpleChatCompletionMessage>>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	prompt_hash: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	completion: Option<Vec<String>>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	completion_hash: Option<String>,
+}
+
+pub struct LlmLog {
+	sink: BoundedSink<Vec<u8>>,
+}
+
+/// Initialize the global LLM log.
+pub fn init(cfg: &Config) -> anyhow::Result<()> {
+	let writers = audit::open_writers(&cfg.sinks)?;
+	let (sink, rx) = BoundedSink::new("llm log", CHANNEL_SIZE);
+	std::thread::Builder::new()
+		.name("llm-log".to_string())
+		.spawn(move || write_loop(rx, writers))?;
+	LLM_LOG
+		.set(LlmLog { sink })
+		.map_err(|_| anyhow::anyhow!("llm log already initialized"))
+}
+
+/// Start capturing a request, if its route has a policy and it is sampled.
+pub fn capture(policy: Option<&Policy>) -> Option<Box<Capture>> {
+	LLM_LOG.get()?;
+	let policy = policy?;
+	if !rand::random_bool(policy.sample_rate.clamp(0.0, 1.0)) {
+		return None;
+	}
+	Some(Box::new(Capture {
+		policy: policy.clone(),
+		prompt: None,
+	}))
+}
+
+impl Capture {
+	pub fn wants_prompt(&self) -> bool {
+		self.policy.prompts != Content::Drop
+	}
+
+	pub fn wants_completion(&self) -> bool {
+		self.policy.completions != Content::Drop
+	}
+
+	pub fn record_prompt(&mut self, messages: Vec<SimpleChatCompletionMessage>) {
+		self.prompt = Some(messages);
+	}
+}
+
+/// Record a completed LLM request, if it was captured.
+pub fn record(log: &RequestLog, duration: Duration) {
+	let Some(capture) = log.llm_capture.as_deref() else {
+		return;
+	};
+	let Some(s) = LLM_LOG.get() else {
+		return;
+	};
+	let Some(entry) = entry(log, capture, duration) else {
+		return;
+	};
+	let mut line = match serde_json::to_vec(&entry) {
+		Ok(l) => l,
+		Err(e) => {
+			warn!("failed to serialize llm log entry: {e}");
+			return;
+		},
+	};
+	line.push(b'\n');
+	s.sink.send(line);
+}
+
+fn entry<'a>(log: &'a RequestLog, capture: &Capture, duration: Duration) -> Option<Entry<'a>> {
+	let req = log.llm_request.as_ref()?;
+	let mut response = None;
+	log
+		.llm_response
+		.non_atomic_mutate(|r| response = Some(r.clone()));
+	let mut prompt = capture.prompt.clone();
+	let mut completion = response
+		.as_ref()
+		.and_then(|r| r.response.completion.clone());
+	if let Some(r) = &capture.policy.redact {
+		redact(r, &mut prompt, &mut completion);
+	}
+	let (prompt, prompt_hash) = content(capture.policy.prompts, prompt);
+	let (completion, completion_hash) = content(capture.policy.completions, completion);
+	Some(Entry {
+		timestamp: agent_core::telemetry::render_current_time(),
+		request_id: log.request_id.as_deref(),
+		trace_id: log.outgoing_span.as_ref().map(|s| s.trace_id()),
+		route: log.route_name.as_ref().map(|r| r.as_route_name()),
+		consumer: log.consumer(),
+		provider: req.provider.clone(),
+		request_model: req.request_model.clone(),
+		response_model: response
+			.as_ref()
+			.and_then(|r| r.response.provider_model.clone()),
+		status: log.status.map(|s| s.as_u16()),
+		duration,
+		input_tokens: response.as_ref().and_then(|r| r.input_tokens()),
+		output_tokens: response.as_ref().and_then(|r| r.response.output_tokens),
+		prompt,
+		prompt_hash,
+		completion,
+		completion_hash,
+	})
+}
+
+/// Redact prompts and completions together, so each value has one placeholder in the entry.
+fn redact(
+	redaction: &PiiRedaction,
+	prompt: &mut Option<Vec<SimpleChatCompletionMessage>>,
+	completion: &mut Option<Vec<String>>,
+) {
+	let mut texts: Vec<String> = prompt
+		.iter()
+		.flatten()
+		.map(|m| m.content.to_string())
+		.collect();
+	redaction.redact_texts(texts.iter_mut().chain(completion.iter_mut().flatten()));
+	for (m, t) in prompt.iter_mut().flatten().zip(texts) {
+		m.content = t.into();
+	}
+}
+
+/// Apply a content mode to captured text, returning the text or its hash.
+fn content<T: serde::Serialize>(mode: Content, v: Option<T>) -> (Option<T>, Option<String>) {
+	match mode {
+		Content::Full => (v, None),
+		Content::Hash => (None, v.and_then(|v| hash(&v))),
+		Content::Drop => (None, None),
+	}
+}
+
+fn hash<T: serde::Serialize>(v: &T) -> Option<String> {
+	let b = serde_json::to_vec(v).ok()?;
+	Some(hex::encode(digest::digest(&digest::SHA256, &b).as_ref()))
+}
+
+fn write_loop(mut rx: mpsc::Receiver<Vec<u8>>, mut writers: Vec<audit::Writer>) {
+	while let Some(line) = rx.blocking_recv() {
+		for w in writers.iter_mut() {
+			if let Err(e) = w.write_line(&line) {
+				warn!("failed to write llm log entry: {e}");
+			}
+		}
+	}
+}
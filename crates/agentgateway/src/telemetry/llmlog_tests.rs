@@ -0,0 +1,70 @@
+use super::*;
+
+fn message(role: &str, content: &str) -> SimpleChatCompletionMessage {
+	SimpleChatCompletionMessage {
+		role: role.into(),
+		content: content.into(),
+	}
+}
+
+#[test]
+fn test_config() {
+	let cfg: Config = serde_json::from_value(serde_json::json!({
+		"sinks": [{"file": {"path": "/var/log/llm.log"}}],
+	}))
+	.unwrap();
+	assert_eq!(cfg.sinks.len(), 1);
+
+	let p: Policy = serde_json::from_value(serde_json::json!({})).unwrap();
+	assert_eq!(p.sample_rate, 1.0);
+	assert_eq!(p.prompts, Content::Full);
+	assert_eq!(p.completions, Content::Full);
+
+	let p: Policy = serde_json::from_value(serde_json::json!({
+		"sampleRate": 0.1,
+		"prompts": "hash",
+		"completions": "drop",
+		"redact": {"rules": [{"builtin": "email"}]},
+	}))
+	.unwrap();
+	assert_eq!(p.sample_rate, 0.1);
+	assert_eq!(p.prompts, Content::Hash);
+	assert_eq!(p.completions, Content::Drop);
+	assert!(p.redact.is_some());
+}
+
+#[test]
+fn test_content() {
+	let completion = Some(vec!["Hi there".to_string()]);
+	assert_eq!(
+		content(Content::Full, completion.clone()),
+		(completion.clone(), None)
+	);
+	assert_eq!(content(Content::Drop, completion.clone()), (None, None));
+	let (text, hash) = content(Content::Hash, completion.clone());
+	assert_eq!(text, None);
+	// Hashes are stable, so identical requests can be grouped
+	assert_eq!(hash, content(Content::Hash, completion).1);
+	assert_eq!(hash.unwrap().len(), 64);
+	assert_eq!(content::<Vec<String>>(Content::Hash, None), (None, None));
+}
+
+#[test]
+fn test_redact() {
+	let redaction: PiiRedaction = serde_json::from_value(serde_json::json!({
+		"rules": [{"builtin": "email"}],
+	}))
+	.unwrap();
+	let mut prompt = Some(vec![
+		message("system", "Be helpful"),
+		message("user", "Email jane@example.com and bob@example.com"),
+	]);
+	let mut completion = Some(vec!["Sent to jane@example.com".to_string()]);
+	redact(&redaction, &mut prompt, &mut completion);
+	assert_eq!(
+		prompt.unwrap()[1].content.as_str(),
+		"Email <EMAIL_ADDRESS_1> and <EMAIL_ADDRESS_2>"
+	);
+	// The same value has the same placeholder in prompts and completions
+	assert_eq!(completion.unwrap()[0], "Sent to <EMAIL_ADDRESS_1>");
+}
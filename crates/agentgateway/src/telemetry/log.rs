@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::net::SocketAddr;
 use std::pin::Pin;
@@ -23,13 +24,16 @@ use serde_json::Value;
 use tracing::{Level, trace};
 
 use crate::cel::{ContextBuilder, Expression};
-use crate::llm::{InputFormat, LLMInfo};
+use crate::llm::LLMInfo;
 use crate::proxy::ProxyResponseReason;
 use crate::telemetry::metrics::{
-	GenAILabels, GenAILabelsTokenUsage, HTTPLabels, MCPCall, Metrics, RouteIdentifier,
+	GenAILabels, GenAILabelsTokenUsage, GrpcLabels, HTTPLabels, MCPCall, Metrics,
+	ProviderHealthLabels, RequestLabel, RouteIdentifier, TCPRouteLabels, TokenLabels, TraceExemplar,
 };
-use crate::telemetry::trc;
 use crate::telemetry::trc::TraceParent;
+use crate::telemetry::{
+	accesslog, audit, cardinality, costs, events, genai, llmlog, slo, slowlog, trc,
+};
 use crate::transport::stream::{TCPConnectionInfo, TLSConnectionInfo};
 use crate::types::agent::{BackendInfo, BindKey, ListenerName, RouteName, Target};
 use crate::types::loadbalancer::ActiveHandle;
@@ -88,8 +92,11 @@ pub struct Config {
 	pub fields: LoggingFields,
 	pub metric_fields: Arc<MetricFields>,
 	pub excluded_metrics: FzHashSet<String>,
+	/// Bucket boundaries overriding the defaults, per histogram metric name.
+	pub histogram_buckets: HashMap<String, Vec<f64>>,
 	pub level: String,
 	pub format: crate::LoggingFormat,
+	pub otlp: Option<crate::telemetry::logexport::Config>,
 }
 
 #[derive(serde::Serialize, Default, Clone, Debug)]
@@ -101,6 +108,8 @@ pub struct LoggingFields {
 #[derive(serde::Serialize, Default, Clone, Debug)]
 pub struct MetricFields {
 	pub add: OrderedStringMap<Arc<cel::Expression>>,
+	pub request_labels: Vec<RequestLabel>,
+	pub cardinality: Arc<cardinality::Limiter>,
 }
 
 #[derive(Clone, Debug)]
@@ -353,6 +362,8 @@ impl CelLogging {
 		for v in cfg.metric_fields.add.values_unordered() {
 			cel_context.register_expression(v.as_ref());
 		}
+		accesslog::register(&mut cel_context);
+		costs::register(&mut cel_context);
 
 		Self {
 			cel_context,
@@ -423,13 +434,21 @@ impl DropOnLog {
 		duration: Duration,
 		llm_response: &Option<LLMInfo>,
 		custom_metric_fields: &CustomField,
+		exemplar: &Option<TraceExemplar>,
 	) {
 		if let Some(llm_response) = &llm_response {
+			let limiter = &log.cel.metric_fields.cardinality;
 			let gen_ai_labels = Arc::new(GenAILabels {
 				gen_ai_operation_name: strng::literal!("chat").into(),
 				gen_ai_system: llm_response.request.provider.clone().into(),
-				gen_ai_request_model: llm_response.request.request_model.clone().into(),
-				gen_ai_response_model: llm_response.response.provider_model.clone().into(),
+				gen_ai_request_model: limiter.limit(
+					"gen_ai_request_model",
+					llm_response.request.request_model.clone().into(),
+				),
+				gen_ai_response_model: limiter.limit(
+					"gen_ai_response_model",
+					llm_response.response.provider_model.clone().into(),
+				),
 				custom: custom_metric_fields.clone(),
 				route: route_identifier.clone(),
 			});
@@ -443,6 +462,28 @@ impl DropOnLog {
 					})
 					.observe(it as f64)
 			}
+			let consumer = limiter.limit("consumer", log.consumer().map(RichStrng::from).into());
+			let token_labels = |token_type: &'static str| TokenLabels {
+				gen_ai_token_type: strng::new(token_type).into(),
+				gen_ai_system: llm_response.request.provider.clone().into(),
+				gen_ai_request_model: gen_ai_labels.gen_ai_request_model.clone(),
+				consumer: consumer.clone(),
+			};
+			let input = llm_response.input_tokens();
+			let output = llm_response.response.output_tokens;
+			let total = llm_response
+				.response
+				.total_tokens
+				.or_else(|| Some(input? + output?));
+			for (token_type, count) in [("prompt", input), ("completion", output), ("total", total)] {
+				if let Some(count) = count {
+					log
+						.metrics
+						.gen_ai_tokens
+						.get_or_create(&token_labels(token_type))
+						.inc_by(count);
+				}
+			}
 			if let Some(ot) = llm_response.response.output_tokens {
 				log
 					.metrics
@@ -457,7 +498,7 @@ impl DropOnLog {
 				.metrics
 				.gen_ai_request_duration
 				.get_or_create(&gen_ai_labels)
-				.observe(duration.as_secs_f64());
+				.observe(duration.as_secs_f64(), exemplar.clone(), None);
 			if let Some(ft) = llm_response.response.first_token {
 				let ttft = ft - log.start;
 				// Duration from start of request to first token
@@ -519,7 +560,10 @@ impl RequestLog {
 			reason: None,
 			retry_after: None,
 			jwt_sub: None,
+			request_id: None,
+			api_key: None,
 			retry_attempt: None,
+			experiment: None,
 			error: None,
 			grpc_status: Default::default(),
 			mcp_status: Default::default(),
@@ -531,6 +575,10 @@ impl RequestLog {
 			inference_pool: None,
 			request_handle: None,
 			response_bytes: 0,
+			tcp_bytes: None,
+			access_log: None,
+			slow_request: None,
+			llm_capture: None,
 		}
 	}
 }
@@ -566,8 +614,15 @@ pub struct RequestLog {
 	pub retry_after: Option<Duration>,
 
 	pub jwt_sub: Option<String>,
+	/// Fingerprint of the API key the request authenticated with.
+	pub api_key: Option<String>,
+
+	/// Set only if the gateway has a request ID policy
+	pub request_id: Option<String>,
 
 	pub retry_attempt: Option<u8>,
+	/// Set only if the request was assigned to a model experiment variant
+	pub experiment: Option<llm::experiment::Assignment>,
 	pub error: Option<String>,
 
 	pub grpc_status: AsyncLog<u8>,
@@ -586,9 +641,36 @@ pub struct RequestLog {
 	pub request_handle: Option<ActiveHandle>,
 
 	pub response_bytes: u64,
+
+	// Set only for TCP routes: the bytes sent to and received from the client
+	pub tcp_bytes: Option<(u64, u64)>,
+
+	// Set only if the route has an access log policy
+	pub access_log: Option<accesslog::Policy>,
+
+	// Set only if the slow request log applies to the route
+	pub slow_request: Option<Box<slowlog::Capture>>,
+
+	// Set only if the route has an LLM log policy and the request was sampled
+	pub llm_capture: Option<Box<llmlog::Capture>>,
 }
 
 impl RequestLog {
+	/// The caller to attribute usage to: an API key, JWT subject, or mTLS identity.
+	pub fn consumer(&self) -> Option<String> {
+		if let Some(key) = &self.api_key {
+			return Some(format!("apikey:{key}"));
+		}
+		if let Some(sub) = &self.jwt_sub {
+			return Some(format!("jwt:{sub}"));
+		}
+		let tls = self.tls_info.as_ref()?.src_identity.as_ref()?;
+		match &tls.identity {
+			Some(id) => Some(format!("mtls:{id}")),
+			None => tls.subject_alt_names.first().map(|s| format!("mtls:{s}")),
+		}
+	}
+
 	pub fn trace_sampled(&self, tp: Option<&TraceParent>) -> bool {
 		let TraceSampler {
 			random_sampling,
@@ -619,6 +701,15 @@ impl Drop for DropOnLog {
 		let Some(mut log) = self.log.take() else {
 			return;
 		};
+		audit::record_request(&log);
+		events::record_request(&log);
+		let elapsed = log.start.elapsed();
+		slowlog::record(&log, elapsed);
+		llmlog::record(&log, elapsed);
+		slo::record(&log, elapsed);
+		if let Some(a) = &log.experiment {
+			a.record(&log.metrics, log.status, elapsed);
+		}
 
 		let route_identifier = RouteIdentifier {
 			bind: (&log.bind_name).into(),
@@ -635,29 +726,66 @@ impl Drop for DropOnLog {
 				.and_then(|l| l.rule_name.as_ref())
 				.into(),
 		};
+		// Labels derived from the request are limited for metrics, but logged as is.
+		let limiter = log.cel.metric_fields.cardinality.clone();
+		let metric_route = RouteIdentifier {
+			route: limiter.limit("route", route_identifier.route.clone()),
+			route_rule: limiter.limit("route_rule", route_identifier.route_rule.clone()),
+			..route_identifier.clone()
+		};
 
 		let is_tcp = matches!(&log.backend_protocol, &Some(cel::BackendProtocol::tcp));
 
 		let mut http_labels = HTTPLabels {
-			backend: log
-				.backend_info
-				.as_ref()
-				.map(|info| info.backend_name.clone())
-				.into(),
+			backend: limiter.limit(
+				"backend",
+				log
+					.backend_info
+					.as_ref()
+					.map(|info| info.backend_name.clone())
+					.into(),
+			),
 			protocol: log.backend_protocol.into(),
-			route: route_identifier.clone(),
+			route: metric_route.clone(),
 			method: log.method.clone().into(),
 			status: log.status.as_ref().map(|s| s.as_u16()).into(),
 			reason: log.reason.into(),
 			custom: CustomField::default(),
 		};
 
-		let enable_custom_metrics = !log.cel.metric_fields.add.is_empty();
+		if let Some((sent, recv)) = log.tcp_bytes {
+			let labels = TCPRouteLabels {
+				backend: http_labels.backend.clone(),
+				route: metric_route.clone(),
+			};
+			log
+				.metrics
+				.tcp_route_tx_bytes
+				.get_or_create(&labels)
+				.inc_by(sent);
+			log
+				.metrics
+				.tcp_route_rx_bytes
+				.get_or_create(&labels)
+				.inc_by(recv);
+		}
+
+		let enable_custom_metrics =
+			!log.cel.metric_fields.add.is_empty() || !log.cel.metric_fields.request_labels.is_empty();
 
 		let enable_trace = log.tracer.is_some();
 		// We will later check it also matches a filter, but filter is slower
-		let maybe_enable_log = agent_core::telemetry::enabled("request", &Level::INFO);
-		if !maybe_enable_log && !enable_trace && !enable_custom_metrics {
+		// The access log, when configured, replaces the `request` application log line.
+		let enable_access_log = accesslog::enabled();
+		let maybe_enable_log =
+			!enable_access_log && agent_core::telemetry::enabled("request", &Level::INFO);
+		let enable_costs = costs::enabled();
+		if !maybe_enable_log
+			&& !enable_trace
+			&& !enable_custom_metrics
+			&& !enable_access_log
+			&& !enable_costs
+		{
 			// Report our non-customized metrics
 			if !is_tcp {
 				log.metrics.requests.get_or_create(&http_labels).inc();
@@ -671,15 +799,43 @@ impl Drop for DropOnLog {
 			.cel_context
 			.with_request_completion(agent_core::telemetry::render_current_time());
 		let duration = end_time - log.start;
+		let llm_response = log.llm_response.take();
 		if let Some(rh) = log.request_handle.take() {
 			let status = log
 				.status
 				.unwrap_or(crate::http::StatusCode::INTERNAL_SERVER_ERROR);
 			let health = !status.is_server_error() && !status.is_client_error();
+			let tokens = llm_response.as_ref().and_then(|l| {
+				let r = &l.response;
+				r.total_tokens
+					.or_else(|| Some(r.input_tokens.unwrap_or_default() + r.output_tokens?))
+			});
+			if health && let Some(tokens) = tokens {
+				rh.record_tokens(tokens, duration);
+			}
+			if let Some((score, degraded)) = rh.record_health(status) {
+				let labels = ProviderHealthLabels {
+					backend: log
+						.backend_info
+						.as_ref()
+						.map(|info| info.backend_name.clone())
+						.into(),
+					provider: rh.key().clone().into(),
+				};
+				log
+					.metrics
+					.gen_ai_provider_health_score
+					.get_or_create(&labels)
+					.set(score);
+				log
+					.metrics
+					.gen_ai_provider_degraded
+					.get_or_create(&labels)
+					.set(i64::from(degraded));
+			}
 			rh.finish_request(health, duration, log.retry_after);
 		}
 
-		let llm_response = log.llm_response.take();
 		if let Some(llm_response) = &llm_response {
 			// Since this is async, we add it to the context here. A bit awkward but gets the job done.
 			log.cel.cel_context.with_llm_response(llm_response);
@@ -690,22 +846,44 @@ impl Drop for DropOnLog {
 			return;
 		};
 
-		let custom_metric_fields = CustomField::new(
-			// For metrics, keep empty values which will become 'unknown'
-			cel_exec
-				.eval_keep_empty(&cel_exec.metric_fields.add, true)
-				.into_iter()
-				.map(|(k, v)| {
-					(
-						strng::new(k),
-						v.and_then(|v| match v {
-							Value::String(s) => Some(strng::new(s)),
-							_ => None,
-						}),
-					)
-				}),
-		);
-		http_labels.custom = custom_metric_fields.clone();
+		// For metrics, keep empty values which will become 'unknown'
+		let custom_fields: Vec<(Strng, Option<Strng>)> = cel_exec
+			.eval_keep_empty(&cel_exec.metric_fields.add, true)
+			.into_iter()
+			.map(|(k, v)| {
+				(
+					strng::new(k),
+					v.and_then(|v| match v {
+						Value::String(s) => Some(strng::new(s)),
+						_ => None,
+					}),
+				)
+			})
+			.collect();
+		let custom_metric_fields = CustomField::new(custom_fields.iter().cloned());
+		let mcp = log.mcp_status.take();
+		http_labels.custom = if cel_exec.metric_fields.request_labels.is_empty() {
+			custom_metric_fields.clone()
+		} else {
+			let request_labels = cel_exec.metric_fields.request_labels.iter().map(|l| {
+				let value = match l {
+					RequestLabel::Tool => mcp
+						.as_ref()
+						.filter(|m| m.resource == Some(mcp::MCPOperation::Tool))
+						.and_then(|m| m.resource_name.as_ref())
+						.map(strng::new),
+					RequestLabel::Model => llm_response
+						.as_ref()
+						.map(|l| l.request.request_model.clone()),
+				};
+				let value = limiter.limit(l.name(), value.map(RichStrng::from).into());
+				(
+					strng::new(l.name()),
+					value.inner().map(|v| Strng::clone(&v)),
+				)
+			});
+			CustomField::new(custom_fields.into_iter().chain(request_labels))
+		};
 		if !is_tcp {
 			log.metrics.requests.get_or_create(&http_labels).inc();
 		}
@@ -716,22 +894,39 @@ impl Drop for DropOnLog {
 				.get_or_create(&http_labels)
 				.inc_by(log.response_bytes);
 		}
+		// Link latency observations to the trace of this request, if it is recorded.
+		let exemplar = log
+			.outgoing_span
+			.as_ref()
+			.filter(|s| enable_trace && s.is_sampled())
+			.map(|s| TraceExemplar {
+				trace_id: s.trace_id(),
+				span_id: s.span_id(),
+			});
 		// Record HTTP request duration for all requests
 		log
 			.metrics
 			.request_duration
 			.get_or_create(&http_labels)
-			.observe(duration.as_secs_f64());
+			.observe(duration.as_secs_f64(), exemplar.clone(), None);
 
 		Self::add_llm_metrics(
 			&log,
-			&route_identifier,
+			&metric_route,
 			end_time,
 			duration,
 			&llm_response,
 			&custom_metric_fields,
+			&exemplar,
 		);
-		let mcp = log.mcp_status.take();
+		if let Some(llm_response) = &llm_response {
+			costs::record(
+				&log,
+				&cel_exec,
+				route_identifier.route.as_deref().map(|r| r.as_str()),
+				llm_response,
+			);
+		}
 		if let Some(mcp) = &mcp
 			&& mcp.method_name.is_some()
 		{
@@ -742,17 +937,42 @@ impl Drop for DropOnLog {
 				.get_or_create(&MCPCall {
 					method: mcp.method_name.as_ref().map(RichStrng::from).into(),
 					resource_type: mcp.resource.into(),
-					server: mcp.target_name.as_ref().map(RichStrng::from).into(),
-					resource: mcp.resource_name.as_ref().map(RichStrng::from).into(),
-
-					route: route_identifier.clone(),
+					server: limiter.limit(
+						"server",
+						mcp.target_name.as_ref().map(RichStrng::from).into(),
+					),
+					resource: limiter.limit(
+						"resource",
+						mcp.resource_name.as_ref().map(RichStrng::from).into(),
+					),
+
+					route: metric_route.clone(),
 					custom: custom_metric_fields.clone(),
 				})
 				.inc();
 		}
+		// Only gRPC responses have a gRPC status, in their headers or trailers.
+		if let Some(status) = log.grpc_status.load() {
+			let (service, method) = log
+				.path
+				.as_deref()
+				.and_then(crate::http::grpc::service_method)
+				.unzip();
+			log
+				.metrics
+				.grpc_requests
+				.get_or_create(&GrpcLabels {
+					backend: http_labels.backend.clone(),
+					service: limiter.limit("service", service.map(RichStrng::from).into()),
+					method: limiter.limit("method", method.map(RichStrng::from).into()),
+					status: Some(status).into(),
+					route: metric_route.clone(),
+				})
+				.inc();
+		}
 
 		let enable_logs = maybe_enable_log && cel_exec.eval_filter();
-		if !enable_logs && !enable_trace {
+		if !enable_logs && !enable_trace && !enable_access_log {
 			return;
 		}
 
@@ -797,10 +1017,13 @@ impl Drop for DropOnLog {
 					None
 				},
 			),
+			("request.id", log.request_id.display()),
 			("trace.id", trace_id.display()),
 			("span.id", span_id.display()),
 			("jwt.sub", log.jwt_sub.display()),
 			("protocol", log.backend_protocol.as_ref().map(debug)),
+			("tcp.sent", log.tcp_bytes.map(|(sent, _)| sent.into())),
+			("tcp.received", log.tcp_bytes.map(|(_, recv)| recv.into())),
 			("a2a.method", log.a2a_method.display()),
 			(
 				"mcp.method",
@@ -841,13 +1064,10 @@ impl Drop for DropOnLog {
 			// OpenTelemetry Gen AI Semantic Conventions v1.37.0
 			(
 				"gen_ai.operation.name",
-				log.llm_request.as_ref().map(|r| {
-					if r.input_format == InputFormat::Embeddings {
-						"embeddings".into()
-					} else {
-						"chat".into()
-					}
-				}),
+				log
+					.llm_request
+					.as_ref()
+					.map(|r| genai::operation_name(r.input_format).into()),
 			),
 			(
 				"gen_ai.provider.name",
@@ -934,13 +1154,30 @@ impl Drop for DropOnLog {
 					.and_then(|l| l.params.seed)
 					.map(Into::into),
 			),
+			(
+				"experiment.name",
+				log.experiment.as_ref().map(|e| display(&e.experiment)),
+			),
+			(
+				"experiment.variant",
+				log.experiment.as_ref().map(|e| display(&e.variant)),
+			),
 			("retry.attempt", log.retry_attempt.display()),
 			("error", log.error.quoted()),
 			("duration", Some(dur.as_str().into())),
 		];
 		if enable_trace && let Some(t) = &log.tracer {
-			t.send(&log, &cel_exec, kv.as_slice())
+			let genai = log
+				.llm_request
+				.iter()
+				.map(|r| genai::llm(r, llm_response.as_ref().map(|l| &l.response)))
+				.chain(mcp.as_ref().and_then(genai::mcp))
+				.collect();
+			t.send(&log, &cel_exec, kv.as_slice(), genai)
 		};
+		if enable_access_log {
+			accesslog::record(&log, &cel_exec, duration, kv.as_slice());
+		}
 		if enable_logs {
 			kv.reserve(fields.add.len());
 			for (k, v) in &mut kv {
@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+
+use macro_rules_attribute::apply;
+use opentelemetry_otlp::{WithExportConfig, WithHttpConfig};
+use opentelemetry_sdk::logs::SdkLoggerProvider;
+
+use crate::telemetry::trc;
+use crate::*;
+
+/// Export the gateway's own logs to an OTLP logs endpoint, in addition to stdout. Logs are batched,
+/// and carry the same resource attributes as traces.
+#[apply(schema!)]
+pub struct Config {
+	pub endpoint: String,
+	#[serde(default, skip_serializing_if = "HashMap::is_empty")]
+	pub headers: HashMap<String, String>,
+	#[serde(default)]
+	pub protocol: trc::Protocol,
+	/// OTLP path, for the HTTP protocol. Default is /v1/logs
+	#[serde(default = "default_path")]
+	pub path: String,
+	/// Log level for exported logs, in the same format as the log level. Defaults to the log level.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub level: Option<String>,
+}
+
+fn default_path() -> String {
+	"/v1/logs".to_string()
+}
+
+/// Start exporting logs. `default_level` is used unless the config sets its own level.
+pub fn init(cfg: &Config, default_level: &str) -> anyhow::Result<()> {
+	let exporter = if cfg.protocol == trc::Protocol::Grpc {
+		opentelemetry_otlp::LogExporter::builder()
+			.with_tonic()
+			.with_endpoint(&cfg.endpoint)
+			.build()?
+	} else {
+		opentelemetry_otlp::LogExporter::builder()
+			.with_http()
+			.with_endpoint(format!(
+				"{}/{}",
+				cfg.endpoint.trim_end_matches('/'),
+				cfg.path.trim_start_matches('/')
+			))
+			.with_headers(cfg.headers.clone())
+			.build()?
+	};
+	let provider = SdkLoggerProvider::builder()
+		.with_resource(trc::default_resource())
+		.with_batch_exporter(exporter)
+		.build();
+	agent_core::telemetry::logexport::init(provider, cfg.level.as_deref().unwrap_or(default_level))?;
+	Ok(())
+}
@@ -1,13 +1,21 @@
+use std::collections::HashMap;
 use std::fmt::Debug;
+use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
 
 use agent_core::metrics::{CustomField, DefaultedUnknown, EncodeArc, EncodeDebug, EncodeDisplay};
 use agent_core::strng::RichStrng;
 use agent_core::version;
 use frozen_collections::FzHashSet;
+use macro_rules_attribute::apply;
 use prometheus_client::encoding::EncodeLabelSet;
 use prometheus_client::metrics::counter;
-use prometheus_client::metrics::family::Family;
-use prometheus_client::metrics::histogram::Histogram as PromHistogram;
+use prometheus_client::metrics::exemplar::HistogramWithExemplars;
+use prometheus_client::metrics::family::{Family, MetricConstructor};
+use prometheus_client::metrics::gauge::Gauge;
+use prometheus_client::metrics::histogram::{
+	Histogram as PromHistogram, exponential_buckets, linear_buckets,
+};
 use prometheus_client::metrics::info::Info;
 use prometheus_client::registry::{Metric, Registry, Unit};
 use tracing::{debug, trace};
@@ -15,6 +23,11 @@ use tracing::{debug, trace};
 use crate::mcp::MCPOperation;
 use crate::proxy::ProxyResponseReason;
 use crate::types::agent::TransportProtocol;
+use crate::*;
+
+#[cfg(test)]
+#[path = "metrics_tests.rs"]
+mod tests;
 
 #[derive(Clone, Hash, Default, Debug, PartialEq, Eq, EncodeLabelSet)]
 pub struct RouteIdentifier {
@@ -26,8 +39,17 @@ pub struct RouteIdentifier {
 }
 
 #[derive(
-	Copy, Clone, Hash, Debug, PartialEq, Eq, prometheus_client::encoding::EncodeLabelValue, Default,
+	Copy,
+	Clone,
+	Hash,
+	Debug,
+	PartialEq,
+	Eq,
+	prometheus_client::encoding::EncodeLabelValue,
+	Default,
+	serde::Serialize,
 )]
+#[serde(rename_all = "camelCase")]
 pub enum GuardrailPhase {
 	#[default]
 	Request,
@@ -35,13 +57,23 @@ pub enum GuardrailPhase {
 }
 
 #[derive(
-	Copy, Clone, Hash, Debug, PartialEq, Eq, prometheus_client::encoding::EncodeLabelValue, Default,
+	Copy,
+	Clone,
+	Hash,
+	Debug,
+	PartialEq,
+	Eq,
+	prometheus_client::encoding::EncodeLabelValue,
+	Default,
+	serde::Serialize,
 )]
+#[serde(rename_all = "camelCase")]
 pub enum GuardrailAction {
 	#[default]
 	Allow,
 	Mask,
 	Reject,
+	Annotate,
 }
 
 #[derive(Clone, Hash, Default, Debug, PartialEq, Eq, EncodeLabelSet)]
@@ -50,6 +82,21 @@ pub struct GuardrailLabels {
 	pub action: GuardrailAction,
 }
 
+#[derive(Clone, Hash, Default, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct GuardrailFailureLabels {
+	pub phase: GuardrailPhase,
+	pub guard: RichStrng,
+	/// Whether the content was allowed through (failing open) or rejected (failing closed).
+	pub action: GuardrailAction,
+}
+
+#[derive(Clone, Hash, Default, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct DlpLabels {
+	pub phase: GuardrailPhase,
+	pub action: GuardrailAction,
+	pub entity: DefaultedUnknown<RichStrng>,
+}
+
 #[derive(Clone, Hash, Default, Debug, PartialEq, Eq, EncodeLabelSet)]
 pub struct HTTPLabels {
 	pub backend: DefaultedUnknown<RichStrng>,
@@ -80,6 +127,23 @@ pub struct GenAILabels {
 	pub custom: CustomField,
 }
 
+#[derive(Clone, Hash, Default, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct TokenLabels {
+	pub gen_ai_token_type: DefaultedUnknown<RichStrng>,
+	pub gen_ai_system: DefaultedUnknown<RichStrng>,
+	pub gen_ai_request_model: DefaultedUnknown<RichStrng>,
+	pub consumer: DefaultedUnknown<RichStrng>,
+}
+
+#[derive(Clone, Hash, Default, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct CostLabels {
+	pub gen_ai_system: DefaultedUnknown<RichStrng>,
+	pub gen_ai_request_model: DefaultedUnknown<RichStrng>,
+	pub consumer: DefaultedUnknown<RichStrng>,
+	pub team: DefaultedUnknown<RichStrng>,
+	pub route: DefaultedUnknown<RichStrng>,
+}
+
 #[derive(Clone, Hash, Default, Debug, PartialEq, Eq, EncodeLabelSet)]
 pub struct GenAILabelsTokenUsage {
 	pub gen_ai_token_type: DefaultedUnknown<RichStrng>,
@@ -103,6 +167,18 @@ pub struct MCPCall {
 	pub custom: CustomField,
 }
 
+#[derive(Clone, Hash, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct GrpcLabels {
+	pub backend: DefaultedUnknown<RichStrng>,
+	pub service: DefaultedUnknown<RichStrng>,
+	pub method: DefaultedUnknown<RichStrng>,
+	/// The gRPC status code, from the response headers or trailers.
+	pub status: DefaultedUnknown<EncodeDisplay<u8>>,
+
+	#[prometheus(flatten)]
+	pub route: RouteIdentifier,
+}
+
 #[derive(Clone, Hash, Debug, PartialEq, Eq, EncodeLabelSet)]
 pub struct TCPLabels {
 	pub bind: DefaultedUnknown<RichStrng>,
@@ -111,15 +187,174 @@ pub struct TCPLabels {
 	pub protocol: TransportProtocol,
 }
 
+#[derive(Clone, Hash, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct TCPRouteLabels {
+	pub backend: DefaultedUnknown<RichStrng>,
+
+	#[prometheus(flatten)]
+	pub route: RouteIdentifier,
+}
+
 #[derive(Clone, Hash, Debug, PartialEq, Eq, EncodeLabelSet)]
 pub struct ConnectLabels {
 	pub transport: DefaultedUnknown<RichStrng>,
 }
 
+#[derive(Clone, Hash, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct PoolLabels {
+	pub backend: DefaultedUnknown<RichStrng>,
+	pub transport: DefaultedUnknown<RichStrng>,
+}
+
+#[derive(Clone, Hash, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct ProbeLabels {
+	pub probe: RichStrng,
+	pub target: RichStrng,
+}
+
+#[derive(Clone, Hash, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct FallbackLabels {
+	pub route: DefaultedUnknown<RichStrng>,
+	pub from_model: RichStrng,
+	pub to_model: RichStrng,
+	pub reason: RichStrng,
+}
+
+#[derive(Clone, Hash, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct ExperimentLabels {
+	pub experiment: RichStrng,
+	pub variant: RichStrng,
+}
+
+#[derive(Clone, Hash, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct ExperimentOutcomeLabels {
+	#[prometheus(flatten)]
+	pub experiment: ExperimentLabels,
+	pub outcome: RichStrng,
+}
+
+#[derive(Clone, Hash, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct StructuredOutputLabels {
+	pub route: DefaultedUnknown<RichStrng>,
+	pub result: RichStrng,
+}
+
+#[derive(Clone, Hash, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct ProviderHealthLabels {
+	pub backend: DefaultedUnknown<RichStrng>,
+	pub provider: RichStrng,
+}
+
+#[derive(Clone, Hash, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct QueueRejectionLabels {
+	pub route: DefaultedUnknown<RichStrng>,
+	pub reason: RichStrng,
+}
+
+#[derive(Clone, Hash, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct HedgeLabels {
+	pub route: DefaultedUnknown<RichStrng>,
+	/// Which attempt's response was returned: `original` or `hedge`.
+	pub winner: RichStrng,
+}
+
+#[derive(Clone, Hash, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct BackendConcurrencyLabels {
+	pub backend: DefaultedUnknown<RichStrng>,
+}
+
+#[derive(Clone, Hash, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct MemoryShedLabels {
+	/// What was shed: `connection` or `request`.
+	pub kind: RichStrng,
+}
+
+#[derive(Clone, Hash, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct BufferPoolLabels {
+	pub size: RichStrng,
+}
+
+#[derive(Clone, Hash, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct BufferPoolRequestLabels {
+	pub size: RichStrng,
+	pub result: RichStrng,
+}
+
+#[derive(
+	Copy, Clone, Hash, Debug, PartialEq, Eq, prometheus_client::encoding::EncodeLabelValue, Default,
+)]
+pub enum SemanticCacheResult {
+	#[default]
+	Miss,
+	Hit,
+}
+
+#[derive(Clone, Hash, Default, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct SemanticCacheLabels {
+	pub model: RichStrng,
+	pub result: SemanticCacheResult,
+}
+
+#[derive(
+	Copy, Clone, Hash, Debug, PartialEq, Eq, prometheus_client::encoding::EncodeLabelValue, Default,
+)]
+pub enum ResponseCacheResult {
+	#[default]
+	Miss,
+	Hit,
+}
+
+#[derive(Clone, Hash, Default, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct ResponseCacheLabels {
+	pub model: RichStrng,
+	pub result: ResponseCacheResult,
+}
+
+#[derive(Clone, Hash, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct SloLabels {
+	pub slo: RichStrng,
+	pub target: RichStrng,
+}
+
+#[derive(Clone, Hash, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct SloWindowLabels {
+	#[prometheus(flatten)]
+	pub slo: SloLabels,
+	pub window: RichStrng,
+}
+
 type Counter = Family<HTTPLabels, counter::Counter>;
-type Histogram<T> = Family<T, prometheus_client::metrics::histogram::Histogram>;
+type Histogram<T> = Family<T, PromHistogram, Buckets>;
+type ExemplarHistogram<T> = Family<T, HistogramWithExemplars<TraceExemplar>, Buckets>;
 type TCPCounter = Family<TCPLabels, counter::Counter>;
 
+/// Optional labels added to HTTP request metrics.
+#[apply(schema!)]
+#[derive(Copy, PartialEq, Eq)]
+pub enum RequestLabel {
+	/// The MCP tool called by the request.
+	Tool,
+	/// The model requested from the LLM provider.
+	Model,
+}
+
+impl RequestLabel {
+	pub fn name(&self) -> &'static str {
+		match self {
+			RequestLabel::Tool => "tool",
+			RequestLabel::Model => "model",
+		}
+	}
+}
+
+/// Links an observation to the trace of the request it came from, so a spike in a histogram can
+/// be followed to representative traces.
+#[derive(Clone, Hash, Debug, PartialEq, Eq, EncodeLabelSet)]
+pub struct TraceExemplar {
+	pub trace_id: String,
+	pub span_id: String,
+}
+
 #[derive(Clone, Hash, Debug, PartialEq, Eq, EncodeLabelSet)]
 pub struct BuildLabel {
 	tag: &'static str,
@@ -128,26 +363,135 @@ pub struct BuildLabel {
 #[derive(Debug)]
 pub struct Metrics {
 	pub requests: Counter,
-	pub request_duration: Histogram<HTTPLabels>,
+	pub request_duration: ExemplarHistogram<HTTPLabels>,
 	pub response_bytes: Family<HTTPLabels, counter::Counter>,
 
 	pub mcp_requests: Family<MCPCall, counter::Counter>,
+	// gRPC calls, by service, method, and gRPC status
+	pub grpc_requests: Family<GrpcLabels, counter::Counter>,
 
 	pub gen_ai_token_usage: Histogram<GenAILabelsTokenUsage>,
-	pub gen_ai_request_duration: Histogram<GenAILabels>,
+	// Total tokens used per provider, model, and consumer, by type (prompt, completion, total)
+	pub gen_ai_tokens: Family<TokenLabels, counter::Counter>,
+	// Cost in USD of LLM requests, from the configured token prices
+	pub gen_ai_cost: Family<CostLabels, counter::Counter<f64, AtomicU64>>,
+	pub gen_ai_request_duration: ExemplarHistogram<GenAILabels>,
 	pub gen_ai_time_per_output_token: Histogram<GenAILabels>,
 	pub gen_ai_time_to_first_token: Histogram<GenAILabels>,
+	// Requests that fell back from one model to the next in a fallback chain
+	pub gen_ai_fallbacks: Family<FallbackLabels, counter::Counter>,
+	// LLM requests looked up in a semantic cache, by whether they were served from it
+	pub gen_ai_semantic_cache_lookups: Family<SemanticCacheLabels, counter::Counter>,
+	// LLM requests looked up in an exact-match response cache, by whether they were served from it
+	pub gen_ai_response_cache_lookups: Family<ResponseCacheLabels, counter::Counter>,
+	// Requests assigned to each variant of a model experiment, by outcome
+	pub gen_ai_experiment_requests: Family<ExperimentOutcomeLabels, counter::Counter>,
+	pub gen_ai_experiment_request_duration: Histogram<ExperimentLabels>,
+	// LLM responses validated against a structured output schema, by whether they matched
+	pub gen_ai_structured_output_validations: Family<StructuredOutputLabels, counter::Counter>,
+	// The health score of each LLM provider, and whether it is degraded
+	pub gen_ai_provider_health_score: Family<ProviderHealthLabels, Gauge<f64, AtomicU64>>,
+	pub gen_ai_provider_degraded: Family<ProviderHealthLabels, Gauge>,
+	// Requests rejected by a request queue, because it was full or they waited too long
+	pub request_queue_rejections: Family<QueueRejectionLabels, counter::Counter>,
+	// Requests that were sent a second time by a hedging policy
+	pub hedged_requests: Family<HedgeLabels, counter::Counter>,
+	// The adaptive concurrency limit of each backend, and the requests rejected for exceeding it
+	pub backend_concurrency_limit: Family<BackendConcurrencyLabels, Gauge>,
+	pub backend_concurrency_rejections: Family<BackendConcurrencyLabels, counter::Counter>,
 
 	pub tls_handshake_duration: Histogram<TCPLabels>,
 
 	pub downstream_connection: TCPCounter,
 	pub tcp_downstream_rx_bytes: Family<TCPLabels, counter::Counter>,
 	pub tcp_downstream_tx_bytes: Family<TCPLabels, counter::Counter>,
+	// bytes proxied by TCP routes, per route and backend, counted when the connection closes
+	pub tcp_route_rx_bytes: Family<TCPRouteLabels, counter::Counter>,
+	pub tcp_route_tx_bytes: Family<TCPRouteLabels, counter::Counter>,
 
 	pub upstream_connect_duration: Histogram<ConnectLabels>,
 
+	// metrics for the upstream connection pool, per backend. Pool gauges are exposed by
+	// client::PoolCollector.
+	pub upstream_connections_created: Family<PoolLabels, counter::Counter>,
+	pub upstream_connections_reused: Family<PoolLabels, counter::Counter>,
+	pub upstream_connections_evicted: Family<PoolLabels, counter::Counter>,
+	pub upstream_connect_failures: Family<PoolLabels, counter::Counter>,
+
 	// metrics for guardrail checks (allow/mask/reject) for request/response
 	pub guardrail_checks: Family<GuardrailLabels, counter::Counter>,
+	// metrics for external guardrails services that failed or timed out, by how the guard failed
+	pub guardrail_failures: Family<GuardrailFailureLabels, counter::Counter>,
+	// metrics for sensitive data found by DLP policies, per entity type
+	pub dlp_redactions: Family<DlpLabels, counter::Counter>,
+
+	// metrics for synthetic probes, per probe and endpoint
+	pub probe_duration: Histogram<ProbeLabels>,
+	pub probe_failures: Family<ProbeLabels, counter::Counter>,
+	pub probe_healthy: Family<ProbeLabels, Gauge>,
+}
+
+/// Bucket boundaries for a histogram metric, overriding its defaults.
+#[apply(schema!)]
+pub enum HistogramBuckets {
+	/// Explicit bucket upper bounds, in increasing order.
+	Explicit(Vec<f64>),
+	/// `count` buckets, the first with an upper bound of `start`, each `factor` times the previous.
+	/// This covers a wide range with constant relative error, similar to native histograms.
+	Exponential { start: f64, factor: f64, count: u16 },
+	/// `count` buckets, the first with an upper bound of `start`, each `width` above the previous.
+	Linear { start: f64, width: f64, count: u16 },
+}
+
+impl HistogramBuckets {
+	pub fn bounds(&self) -> anyhow::Result<Vec<f64>> {
+		let bounds: Vec<f64> = match *self {
+			HistogramBuckets::Explicit(ref b) => b.clone(),
+			HistogramBuckets::Exponential {
+				start,
+				factor,
+				count,
+			} => {
+				if start <= 0.0 || factor <= 1.0 {
+					anyhow::bail!("exponential buckets require start > 0 and factor > 1");
+				}
+				exponential_buckets(start, factor, count).collect()
+			},
+			HistogramBuckets::Linear {
+				start,
+				width,
+				count,
+			} => {
+				if width <= 0.0 {
+					anyhow::bail!("linear buckets require width > 0");
+				}
+				linear_buckets(start, width, count).collect()
+			},
+		};
+		if bounds.is_empty() {
+			anyhow::bail!("histogram must have at least one bucket");
+		}
+		if bounds.iter().any(|b| !b.is_finite()) || bounds.windows(2).any(|w| w[0] >= w[1]) {
+			anyhow::bail!("histogram buckets must be finite and strictly increasing");
+		}
+		Ok(bounds)
+	}
+}
+
+/// Constructs histograms with a fixed set of buckets.
+#[derive(Clone, Debug)]
+pub struct Buckets(Arc<[f64]>);
+
+impl MetricConstructor<PromHistogram> for Buckets {
+	fn new_metric(&self) -> PromHistogram {
+		PromHistogram::new(self.0.iter().copied())
+	}
+}
+
+impl MetricConstructor<HistogramWithExemplars<TraceExemplar>> for Buckets {
+	fn new_metric(&self) -> HistogramWithExemplars<TraceExemplar> {
+		HistogramWithExemplars::new(self.0.iter().copied())
+	}
 }
 
 // FilteredRegistry is a wrapper around Registry that allows to filter out certain metrics.
@@ -159,31 +503,70 @@ pub struct Metrics {
 struct FilteredRegistry<'a> {
 	registry: &'a mut Registry,
 	removes: FzHashSet<String>,
+	histograms: HashMap<String, Vec<f64>>,
 }
 
-impl<'a> FilteredRegistry<'a> {
-	fn should_skip(&self, name: &str, unit: Option<&Unit>) -> bool {
-		let mut names = vec![
-			name.to_string(),
-			format!("{}_total", name),
-			format!("{}_{}_total", agent_core::metrics::PREFIX, name),
-			format!("{}_{}", agent_core::metrics::PREFIX, name),
-		];
-		if let Some(unit) = unit {
-			names.extend_from_slice(&[
-				format!("{}_{}", name, unit.as_str()),
-				format!("{}_{}_total", name, unit.as_str()),
-				format!(
-					"{}_{}_{}_total",
-					agent_core::metrics::PREFIX,
-					name,
-					unit.as_str()
-				),
-				format!("{}_{}_{}", agent_core::metrics::PREFIX, name, unit.as_str()),
-			])
+/// The histograms whose buckets can be configured in `metrics.histograms`.
+const HISTOGRAMS: &[(&str, Option<Unit>)] = &[
+	("gen_ai_client_token_usage", None),
+	("gen_ai_server_request_duration", None),
+	("gen_ai_server_time_per_output_token", None),
+	("gen_ai_server_time_to_first_token", None),
+	("gen_ai_experiment_request_duration", Some(Unit::Seconds)),
+	("request_duration", Some(Unit::Seconds)),
+	("upstream_connect_duration", Some(Unit::Seconds)),
+	("tls_handshake_duration", Some(Unit::Seconds)),
+	("probe_duration", Some(Unit::Seconds)),
+];
+
+/// Check that each name configured in `metrics.histograms` refers to a histogram, so typos are not
+/// silently ignored.
+pub fn validate_histogram_names<'a>(
+	names: impl IntoIterator<Item = &'a String>,
+) -> anyhow::Result<()> {
+	for name in names {
+		let known = HISTOGRAMS
+			.iter()
+			.any(|(h, unit)| metric_names(h, unit.as_ref()).contains(name));
+		if !known {
+			let histograms: Vec<_> = HISTOGRAMS.iter().map(|(h, _)| *h).collect();
+			anyhow::bail!(
+				"unknown histogram {name}, expected one of: {}",
+				histograms.join(", ")
+			);
 		}
+	}
+	Ok(())
+}
+
+/// The names a metric may be referred to by in config: with or without the prefix, unit, and
+/// `_total` suffix.
+fn metric_names(name: &str, unit: Option<&Unit>) -> Vec<String> {
+	let mut names = vec![
+		name.to_string(),
+		format!("{}_total", name),
+		format!("{}_{}_total", agent_core::metrics::PREFIX, name),
+		format!("{}_{}", agent_core::metrics::PREFIX, name),
+	];
+	if let Some(unit) = unit {
+		names.extend_from_slice(&[
+			format!("{}_{}", name, unit.as_str()),
+			format!("{}_{}_total", name, unit.as_str()),
+			format!(
+				"{}_{}_{}_total",
+				agent_core::metrics::PREFIX,
+				name,
+				unit.as_str()
+			),
+			format!("{}_{}_{}", agent_core::metrics::PREFIX, name, unit.as_str()),
+		])
+	}
+	names
+}
 
-		for n in names.into_iter() {
+impl<'a> FilteredRegistry<'a> {
+	fn should_skip(&self, name: &str, unit: Option<&Unit>) -> bool {
+		for n in metric_names(name, unit).into_iter() {
 			let exclude = self.removes.contains(&n);
 			trace!(name = n, exclude, "check metric for exclusion");
 			if exclude {
@@ -215,11 +598,51 @@ impl<'a> FilteredRegistry<'a> {
 		}
 		self.registry.register_with_unit(name, help, unit, metric);
 	}
+
+	/// Build and register a histogram, with the buckets configured for it or `default`.
+	fn histogram<T, M>(
+		&mut self,
+		name: &str,
+		help: &str,
+		unit: Option<Unit>,
+		default: &[f64],
+	) -> Family<T, M, Buckets>
+	where
+		T: Clone + std::hash::Hash + Eq,
+		Family<T, M, Buckets>: Metric + Clone,
+	{
+		let unit_name = unit.as_ref().map(Unit::as_str);
+		debug_assert!(
+			HISTOGRAMS
+				.iter()
+				.any(|(h, u)| *h == name && u.as_ref().map(Unit::as_str) == unit_name),
+			"histogram {name} must be listed in HISTOGRAMS"
+		);
+		let bounds = metric_names(name, unit.as_ref())
+			.iter()
+			.find_map(|n| self.histograms.get(n))
+			.map(Vec::as_slice)
+			.unwrap_or(default);
+		let m = Family::new_with_constructor(Buckets(bounds.into()));
+		match unit {
+			Some(unit) => self.register_with_unit(name, help, unit, m.clone()),
+			None => self.register(name, help, m.clone()),
+		}
+		m
+	}
 }
 
 impl Metrics {
-	pub fn new(registry: &mut Registry, removes: FzHashSet<String>) -> Self {
-		let mut registry = FilteredRegistry { registry, removes };
+	pub fn new(
+		registry: &mut Registry,
+		removes: FzHashSet<String>,
+		histograms: HashMap<String, Vec<f64>>,
+	) -> Self {
+		let mut registry = FilteredRegistry {
+			registry,
+			removes,
+			histograms,
+		};
 		registry.register(
 			"build",
 			"Agentgateway build information",
@@ -228,41 +651,33 @@ impl Metrics {
 			}),
 		);
 
-		let gen_ai_token_usage = Family::<GenAILabelsTokenUsage, _>::new_with_constructor(move || {
-			PromHistogram::new(TOKEN_USAGE_BUCKET)
-		});
-		registry.register(
+		let gen_ai_token_usage = registry.histogram(
 			"gen_ai_client_token_usage",
 			"Number of tokens used per request",
-			gen_ai_token_usage.clone(),
+			None,
+			&TOKEN_USAGE_BUCKET,
 		);
 
 		// TODO: add error attribute if it ends with an error
-		let gen_ai_request_duration = Family::<GenAILabels, _>::new_with_constructor(move || {
-			PromHistogram::new(REQUEST_DURATION_BUCKET)
-		});
-		registry.register(
+		let gen_ai_request_duration = registry.histogram(
 			"gen_ai_server_request_duration",
 			"Duration of generative AI request",
-			gen_ai_request_duration.clone(),
+			None,
+			&REQUEST_DURATION_BUCKET,
 		);
 
-		let gen_ai_time_per_output_token = Family::<GenAILabels, _>::new_with_constructor(move || {
-			PromHistogram::new(OUTPUT_TOKEN_BUCKET)
-		});
-		registry.register(
+		let gen_ai_time_per_output_token = registry.histogram(
 			"gen_ai_server_time_per_output_token",
 			"Time to generate each output token for a given request",
-			gen_ai_time_per_output_token.clone(),
+			None,
+			&OUTPUT_TOKEN_BUCKET,
 		);
 
-		let gen_ai_time_to_first_token = Family::<GenAILabels, _>::new_with_constructor(move || {
-			PromHistogram::new(FIRST_TOKEN_BUCKET)
-		});
-		registry.register(
+		let gen_ai_time_to_first_token = registry.histogram(
 			"gen_ai_server_time_to_first_token",
 			"Time to generate the first token for a given request",
-			gen_ai_time_to_first_token.clone(),
+			None,
+			&FIRST_TOKEN_BUCKET,
 		);
 
 		Metrics {
@@ -280,6 +695,24 @@ impl Metrics {
 				);
 				m
 			},
+			guardrail_failures: {
+				let m = Family::<GuardrailFailureLabels, _>::default();
+				registry.register(
+					"guardrail_failures",
+					"Total number of external guardrails calls that failed or timed out",
+					m.clone(),
+				);
+				m
+			},
+			dlp_redactions: {
+				let m = Family::<DlpLabels, _>::default();
+				registry.register(
+					"dlp_redactions",
+					"Total number of sensitive data matches masked or rejected by DLP policies",
+					m.clone(),
+				);
+				m
+			},
 			downstream_connection: build(
 				&mut registry,
 				"downstream_connections",
@@ -291,11 +724,103 @@ impl Metrics {
 				"mcp_requests",
 				"Total number of MCP tool calls",
 			),
+			grpc_requests: build(
+				&mut registry,
+				"grpc_requests",
+				"Total number of gRPC calls, by service, method, and gRPC status",
+			),
 
 			gen_ai_token_usage,
+			gen_ai_tokens: build(
+				&mut registry,
+				"gen_ai_tokens",
+				"Total number of tokens used, by provider, model, consumer, and token type",
+			),
+			gen_ai_cost: {
+				let m = Family::<CostLabels, _>::default();
+				registry.register(
+					"gen_ai_cost",
+					"Total cost in USD of LLM requests, by provider, model, consumer, team, and route",
+					m.clone(),
+				);
+				m
+			},
 			gen_ai_request_duration,
 			gen_ai_time_per_output_token,
 			gen_ai_time_to_first_token,
+			gen_ai_fallbacks: build(
+				&mut registry,
+				"gen_ai_fallbacks",
+				"Total number of LLM requests that fell back to the next model, by route, models, and reason",
+			),
+			gen_ai_semantic_cache_lookups: build(
+				&mut registry,
+				"gen_ai_semantic_cache_lookups",
+				"Total number of LLM requests looked up in a semantic cache, by model and result",
+			),
+			gen_ai_response_cache_lookups: build(
+				&mut registry,
+				"gen_ai_response_cache_lookups",
+				"Total number of LLM requests looked up in a response cache, by model and result",
+			),
+			gen_ai_experiment_requests: build(
+				&mut registry,
+				"gen_ai_experiment_requests",
+				"Total number of LLM requests in a model experiment, by experiment, variant, and outcome",
+			),
+			gen_ai_experiment_request_duration: registry.histogram(
+				"gen_ai_experiment_request_duration",
+				"Duration of LLM requests in a model experiment, by experiment and variant (seconds)",
+				Some(Unit::Seconds),
+				&REQUEST_DURATION_BUCKET,
+			),
+			gen_ai_structured_output_validations: build(
+				&mut registry,
+				"gen_ai_structured_output_validations",
+				"Total number of LLM responses validated against a structured output schema, by route and result",
+			),
+			gen_ai_provider_health_score: {
+				let m = Family::<ProviderHealthLabels, Gauge<f64, AtomicU64>>::default();
+				registry.register(
+					"gen_ai_provider_health_score",
+					"The health score of an LLM provider, from 0 to 1, by backend and provider",
+					m.clone(),
+				);
+				m
+			},
+			gen_ai_provider_degraded: {
+				let m = Family::<ProviderHealthLabels, Gauge>::default();
+				registry.register(
+					"gen_ai_provider_degraded",
+					"Whether an LLM provider is degraded (1) or not (0), by backend and provider",
+					m.clone(),
+				);
+				m
+			},
+			request_queue_rejections: build(
+				&mut registry,
+				"request_queue_rejections",
+				"Total number of requests rejected by a request queue, by route and reason",
+			),
+			hedged_requests: build(
+				&mut registry,
+				"hedged_requests",
+				"Total number of requests sent a second time by a hedging policy, by route and which attempt's response was returned",
+			),
+			backend_concurrency_limit: {
+				let m = Family::<BackendConcurrencyLabels, Gauge>::default();
+				registry.register(
+					"backend_concurrency_limit",
+					"The adaptive limit on requests in flight to a backend, by backend",
+					m.clone(),
+				);
+				m
+			},
+			backend_concurrency_rejections: build(
+				&mut registry,
+				"backend_concurrency_rejections",
+				"Total number of requests rejected for exceeding a backend's adaptive concurrency limit, by backend",
+			),
 
 			response_bytes: {
 				let m = Family::<HTTPLabels, _>::default();
@@ -307,18 +832,12 @@ impl Metrics {
 				);
 				m
 			},
-			request_duration: {
-				let m = Family::<HTTPLabels, _>::new_with_constructor(move || {
-					PromHistogram::new(HTTP_REQUEST_DURATION_BUCKET)
-				});
-				registry.register_with_unit(
-					"request_duration",
-					"Duration of HTTP requests (seconds)",
-					Unit::Seconds,
-					m.clone(),
-				);
-				m
-			},
+			request_duration: registry.histogram(
+				"request_duration",
+				"Duration of HTTP requests (seconds)",
+				Some(Unit::Seconds),
+				&HTTP_REQUEST_DURATION_BUCKET,
+			),
 			tcp_downstream_rx_bytes: {
 				let m = Family::<TCPLabels, _>::default();
 				registry.register_with_unit(
@@ -339,26 +858,74 @@ impl Metrics {
 				);
 				m
 			},
-			upstream_connect_duration: {
-				let m = Family::<ConnectLabels, _>::new_with_constructor(move || {
-					PromHistogram::new(CONNECT_DURATION_BUCKET)
-				});
+			tcp_route_rx_bytes: {
+				let m = Family::<TCPRouteLabels, _>::default();
 				registry.register_with_unit(
-					"upstream_connect_duration",
-					"Duration to establish upstream connection (seconds)",
-					Unit::Seconds,
+					"tcp_route_received",
+					"Total bytes received from clients of TCP routes, per route and backend",
+					Unit::Bytes,
 					m.clone(),
 				);
 				m
 			},
-			tls_handshake_duration: {
-				let m = Family::<TCPLabels, _>::new_with_constructor(move || {
-					PromHistogram::new(CONNECT_DURATION_BUCKET)
-				});
+			tcp_route_tx_bytes: {
+				let m = Family::<TCPRouteLabels, _>::default();
 				registry.register_with_unit(
-					"tls_handshake_duration",
-					"Duration to complete inbound TLS/HTTPS handshake (seconds)",
-					Unit::Seconds,
+					"tcp_route_sent",
+					"Total bytes sent to clients of TCP routes, per route and backend",
+					Unit::Bytes,
+					m.clone(),
+				);
+				m
+			},
+			upstream_connect_duration: registry.histogram(
+				"upstream_connect_duration",
+				"Duration to establish upstream connection (seconds)",
+				Some(Unit::Seconds),
+				&CONNECT_DURATION_BUCKET,
+			),
+			upstream_connections_created: build(
+				&mut registry,
+				"upstream_connections_created",
+				"The total number of upstream connections established",
+			),
+			upstream_connections_reused: build(
+				&mut registry,
+				"upstream_connections_reused",
+				"The total number of requests sent on a pooled upstream connection",
+			),
+			upstream_connections_evicted: build(
+				&mut registry,
+				"upstream_connections_evicted",
+				"The total number of idle upstream connections removed from the pool",
+			),
+			upstream_connect_failures: build(
+				&mut registry,
+				"upstream_connect_failures",
+				"The total number of failed attempts to establish an upstream connection",
+			),
+			tls_handshake_duration: registry.histogram(
+				"tls_handshake_duration",
+				"Duration to complete inbound TLS/HTTPS handshake (seconds)",
+				Some(Unit::Seconds),
+				&CONNECT_DURATION_BUCKET,
+			),
+			probe_duration: registry.histogram(
+				"probe_duration",
+				"Duration of synthetic backend probes (seconds)",
+				Some(Unit::Seconds),
+				&HTTP_REQUEST_DURATION_BUCKET,
+			),
+			probe_failures: build(
+				&mut registry,
+				"probe_failures",
+				"The total number of failed synthetic backend probes",
+			),
+			probe_healthy: {
+				let m = Family::<ProbeLabels, Gauge>::default();
+				registry.register(
+					"probe_healthy",
+					"Whether the last synthetic probe of a backend succeeded (1) or not (0)",
 					m.clone(),
 				);
 				m
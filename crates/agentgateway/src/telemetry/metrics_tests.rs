@@ -0,0 +1,121 @@
+use prometheus_client::encoding::text::encode;
+
+use super::*;
+
+#[test]
+fn test_histogram_bounds() {
+	let b: HistogramBuckets = serde_json::from_str(r#"{"explicit": [0.1, 1, 10]}"#).unwrap();
+	assert_eq!(b.bounds().unwrap(), vec![0.1, 1.0, 10.0]);
+
+	let b: HistogramBuckets =
+		serde_json::from_str(r#"{"exponential": {"start": 0.001, "factor": 10, "count": 4}}"#).unwrap();
+	let bounds = b.bounds().unwrap();
+	assert_eq!(bounds.len(), 4);
+	assert!((bounds[3] - 1.0).abs() < 1e-9);
+
+	let b: HistogramBuckets =
+		serde_json::from_str(r#"{"linear": {"start": 30, "width": 30, "count": 3}}"#).unwrap();
+	assert_eq!(b.bounds().unwrap(), vec![30.0, 60.0, 90.0]);
+
+	for invalid in [
+		r#"{"explicit": []}"#,
+		r#"{"explicit": [1, 1]}"#,
+		r#"{"explicit": [2, 1]}"#,
+		r#"{"exponential": {"start": 0, "factor": 2, "count": 4}}"#,
+		r#"{"exponential": {"start": 1, "factor": 1, "count": 4}}"#,
+		r#"{"linear": {"start": 1, "width": 0, "count": 4}}"#,
+		r#"{"linear": {"start": 1, "width": 1, "count": 0}}"#,
+	] {
+		let b: HistogramBuckets = serde_json::from_str(invalid).unwrap();
+		assert!(b.bounds().is_err(), "{invalid} should be rejected");
+	}
+}
+
+#[test]
+fn test_histogram_override() {
+	let mut registry = Registry::default();
+	let histograms = HashMap::from([(
+		// Any of the names accepted for `remove` can be used.
+		"upstream_connect_duration_seconds".to_string(),
+		vec![0.5, 60.0],
+	)]);
+	let metrics = Metrics::new(&mut registry, Default::default(), histograms);
+	let labels = ConnectLabels {
+		transport: RichStrng::from("plaintext").into(),
+	};
+	metrics
+		.upstream_connect_duration
+		.get_or_create(&labels)
+		.observe(0.3);
+	metrics
+		.tls_handshake_duration
+		.get_or_create(&TCPLabels {
+			bind: Default::default(),
+			gateway: Default::default(),
+			listener: Default::default(),
+			protocol: TransportProtocol::tls,
+		})
+		.observe(0.3);
+
+	let mut out = String::new();
+	encode(&mut out, &registry).unwrap();
+	let buckets = |metric: &str| {
+		out
+			.lines()
+			.filter(|l| l.starts_with(&format!("{metric}_bucket")))
+			.count()
+	};
+	// The configured buckets, plus +Inf.
+	assert_eq!(buckets("upstream_connect_duration_seconds"), 3);
+	// Other histograms keep their defaults.
+	assert_eq!(
+		buckets("tls_handshake_duration_seconds"),
+		CONNECT_DURATION_BUCKET.len() + 1
+	);
+}
+
+#[test]
+fn test_validate_histogram_names() {
+	let names = |n: &[&str]| n.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+	validate_histogram_names(&names(&[
+		"request_duration",
+		"agentgateway_request_duration_seconds",
+		"gen_ai_server_time_to_first_token",
+	]))
+	.unwrap();
+	let err = validate_histogram_names(&names(&["request_duraton"])).unwrap_err();
+	assert!(
+		err
+			.to_string()
+			.contains("unknown histogram request_duraton")
+	);
+	// Only histograms can have their buckets configured
+	assert!(validate_histogram_names(&names(&["requests"])).is_err());
+}
+
+#[test]
+fn test_request_duration_exemplar() {
+	let mut registry = Registry::default();
+	let metrics = Metrics::new(&mut registry, Default::default(), Default::default());
+	let exemplar = TraceExemplar {
+		trace_id: "4bf92f3577b34da6a3ce929d0e0e4736".to_string(),
+		span_id: "00f067aa0ba902b7".to_string(),
+	};
+	metrics
+		.request_duration
+		.get_or_create(&HTTPLabels::default())
+		.observe(0.3, Some(exemplar), None);
+
+	let mut out = String::new();
+	encode(&mut out, &registry).unwrap();
+	let bucket = out
+		.lines()
+		.find(|l| l.starts_with("request_duration_seconds_bucket") && l.contains(r#"le="0.5""#))
+		.unwrap();
+	assert!(
+		bucket.ends_with(
+			r#"# {trace_id="4bf92f3577b34da6a3ce929d0e0e4736",span_id="00f067aa0ba902b7"} 0.3"#
+		),
+		"{bucket}"
+	);
+}
@@ -1,3 +1,19 @@
+pub mod accesslog;
+pub mod als;
+pub mod audit;
+pub mod cardinality;
+pub mod costs;
+pub mod events;
+pub mod genai;
+pub mod llmlog;
 pub mod log;
+pub mod logexport;
 pub mod metrics;
+pub mod sink;
+pub mod slo;
+pub mod slowlog;
+pub mod statsd;
+pub mod syslog;
+pub mod tailsampling;
 pub mod trc;
+pub mod usage_export;
@@ -0,0 +1,57 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+
+use crate::*;
+
+#[cfg(test)]
+#[path = "sink_tests.rs"]
+mod tests;
+
+/// The sending half of a bounded queue from the proxy to a telemetry writer. Once the writer falls
+/// behind and the queue fills up, records are dropped rather than blocking the proxy.
+pub struct BoundedSink<T> {
+	name: &'static str,
+	tx: mpsc::Sender<T>,
+	dropped: AtomicU64,
+}
+
+impl<T> BoundedSink<T> {
+	/// A sink holding up to `capacity` records, and the receiver its writer reads them from. `name`
+	/// identifies the sink in warnings.
+	pub fn new(name: &'static str, capacity: usize) -> (Self, mpsc::Receiver<T>) {
+		let (tx, rx) = mpsc::channel(capacity);
+		let sink = BoundedSink {
+			name,
+			tx,
+			dropped: AtomicU64::new(0),
+		};
+		(sink, rx)
+	}
+
+	/// Queue a record for the writer, dropping it if the queue is full or the writer has stopped.
+	pub fn send(&self, record: T) {
+		match self.tx.try_send(record) {
+			Ok(()) => {},
+			Err(TrySendError::Full(_)) => {
+				// Warn on the first drop and every 1000 after, rather than flooding the log.
+				let dropped = self.dropped.fetch_add(1, Ordering::Relaxed);
+				if dropped % 1000 == 0 {
+					warn!(
+						dropped = dropped + 1,
+						"{} is full, dropping entries", self.name
+					);
+				}
+			},
+			Err(TrySendError::Closed(_)) => {
+				warn!("{} stopped, dropping entry", self.name);
+			},
+		}
+	}
+
+	/// The number of records dropped because the queue was full.
+	pub fn dropped(&self) -> u64 {
+		self.dropped.load(Ordering::Relaxed)
+	}
+}
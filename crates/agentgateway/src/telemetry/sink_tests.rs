@@ -0,0 +1,28 @@
+use super::*;
+
+#[test]
+fn test_drops_when_full() {
+	let (sink, mut rx) = BoundedSink::new("test log", 2);
+	for i in 0..5 {
+		sink.send(i);
+	}
+	assert_eq!(sink.dropped(), 3);
+	// The records queued before it filled up are kept, in order.
+	assert_eq!(rx.try_recv(), Ok(0));
+	assert_eq!(rx.try_recv(), Ok(1));
+	assert!(rx.try_recv().is_err());
+
+	// Once the writer catches up, records are queued again.
+	sink.send(5);
+	assert_eq!(rx.try_recv(), Ok(5));
+	assert_eq!(sink.dropped(), 3);
+}
+
+#[test]
+fn test_writer_stopped() {
+	let (sink, rx) = BoundedSink::new("test log", 2);
+	drop(rx);
+	sink.send(0);
+	// Records sent after the writer stops are not counted as dropped for a full queue.
+	assert_eq!(sink.dropped(), 0);
+}
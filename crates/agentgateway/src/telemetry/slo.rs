@@ -0,0 +1,403 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+
+use agent_core::strng::RichStrng;
+use chrono::{DateTime, Utc};
+use macro_rules_attribute::apply;
+use prometheus_client::collector::Collector;
+use prometheus_client::encoding::{DescriptorEncoder, EncodeMetric};
+use prometheus_client::metrics::MetricType;
+use prometheus_client::metrics::gauge::ConstGauge;
+use prometheus_client::registry::Registry;
+
+use crate::telemetry::log::RequestLog;
+use crate::telemetry::metrics::{SloLabels, SloWindowLabels};
+use crate::*;
+
+#[cfg(test)]
+#[path = "slo_tests.rs"]
+mod tests;
+
+static SLOS: OnceLock<SloStore> = OnceLock::new();
+
+/// Requests are counted into buckets of this size, so burn rates have minute granularity.
+const BUCKET_SECONDS: i64 = 60;
+
+/// Windows over which burn rates are reported, in addition to the full SLO window. A short and a
+/// long window are typically alerted on together, to catch both fast and slow budget burns.
+const BURN_RATE_WINDOWS: &[(&str, Duration)] = &[
+	("5m", Duration::from_secs(5 * 60)),
+	("1h", Duration::from_secs(3600)),
+	("6h", Duration::from_secs(6 * 3600)),
+];
+
+/// Service level objectives, tracked over a rolling window and reported as metrics and by the
+/// `/slo` admin endpoint.
+#[apply(schema!)]
+pub struct Config {
+	pub objectives: Vec<Objective>,
+}
+
+#[apply(schema!)]
+pub struct Objective {
+	/// Name of the objective, used in metrics and the `/slo` report.
+	pub name: String,
+	/// Route the objective applies to, as `namespace/name`. If unset, all requests are counted.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub route: Option<String>,
+	/// Length of the rolling window the error budget is computed over.
+	#[serde(default = "default_window", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub window: Duration,
+	/// Percentage of requests that must not fail, such as `99.9`. Requests fail if they return a
+	/// 5xx status or no response.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub availability: Option<f64>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub latency: Option<LatencyObjective>,
+}
+
+/// A latency percentile target, such as 99% of requests completing within 300ms.
+#[apply(schema!)]
+pub struct LatencyObjective {
+	/// Percentage of requests that must complete within the threshold, such as `99` for p99.
+	pub percentile: f64,
+	#[serde(with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub threshold: Duration,
+}
+
+fn default_window() -> Duration {
+	Duration::from_secs(30 * 24 * 3600)
+}
+
+/// Which target of an objective a status refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Target {
+	Availability,
+	Latency,
+}
+
+impl Target {
+	fn as_str(&self) -> &'static str {
+		match self {
+			Target::Availability => "availability",
+			Target::Latency => "latency",
+		}
+	}
+}
+
+/// The state of one objective, as served by `/slo`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Status {
+	pub name: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub route: Option<String>,
+	#[serde(with = "serde_dur")]
+	pub window: Duration,
+	/// Requests counted in the window.
+	pub requests: u64,
+	pub targets: Vec<TargetStatus>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetStatus {
+	pub target: Target,
+	/// Required percentage of good requests.
+	pub objective: f64,
+	/// Percentage of good requests in the window, or 100 if there were none.
+	pub actual: f64,
+	/// Requests in the window that did not meet the target.
+	pub bad_requests: u64,
+	/// Fraction of the error budget left in the window. Negative once the budget is exhausted.
+	pub error_budget_remaining: f64,
+	/// Rate the error budget is being spent at, by window. A burn rate of 1 uses up exactly the
+	/// budget over the SLO window.
+	pub burn_rates: BTreeMap<String, f64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Bucket {
+	start: i64,
+	total: u64,
+	errors: u64,
+	slow: u64,
+}
+
+impl Bucket {
+	fn add(&mut self, other: &Bucket) {
+		self.total += other.total;
+		self.errors += other.errors;
+		self.slow += other.slow;
+	}
+
+	fn bad(&self, target: Target) -> u64 {
+		match target {
+			Target::Availability => self.errors,
+			Target::Latency => self.slow,
+		}
+	}
+}
+
+struct Tracker {
+	objective: Objective,
+	buckets: Mutex<VecDeque<Bucket>>,
+}
+
+impl Tracker {
+	fn record(&self, now: DateTime<Utc>, failed: bool, duration: Duration) {
+		let start = now.timestamp() - now.timestamp().rem_euclid(BUCKET_SECONDS);
+		let slow = self
+			.objective
+			.latency
+			.as_ref()
+			.is_some_and(|l| duration > l.threshold);
+		let mut buckets = self.buckets.lock().unwrap();
+		if buckets.back().is_none_or(|b| b.start != start) {
+			buckets.push_back(Bucket {
+				start,
+				..Default::default()
+			});
+		}
+		let b = buckets.back_mut().expect("bucket was just added");
+		b.total += 1;
+		b.errors += failed as u64;
+		b.slow += slow as u64;
+		let cutoff = start - self.objective.window.as_secs() as i64;
+		while buckets.front().is_some_and(|b| b.start <= cutoff) {
+			buckets.pop_front();
+		}
+	}
+
+	/// Sum the buckets that started within `window` of `now`.
+	fn sum(&self, now: DateTime<Utc>, window: Duration) -> Bucket {
+		let cutoff = now.timestamp() - window.as_secs() as i64;
+		let mut sum = Bucket::default();
+		for b in self.buckets.lock().unwrap().iter().rev() {
+			if b.start <= cutoff {
+				break;
+			}
+			sum.add(b);
+		}
+		sum
+	}
+
+	fn status(&self, now: DateTime<Utc>) -> Status {
+		let o = &self.objective;
+		let total = self.sum(now, o.window);
+		let windows: Vec<(&str, Bucket)> = BURN_RATE_WINDOWS
+			.iter()
+			.filter(|(_, w)| *w < o.window)
+			.map(|(name, w)| (*name, self.sum(now, *w)))
+			.collect();
+		let targets = [
+			(Target::Availability, o.availability),
+			(Target::Latency, o.latency.as_ref().map(|l| l.percentile)),
+		]
+		.into_iter()
+		.filter_map(|(target, objective)| {
+			let objective = objective?;
+			let mut burn_rates: BTreeMap<String, f64> = windows
+				.iter()
+				.map(|(name, b)| (name.to_string(), burn_rate(b, target, objective)))
+				.collect();
+			burn_rates.insert("window".to_string(), burn_rate(&total, target, objective));
+			let bad = total.bad(target);
+			Some(TargetStatus {
+				target,
+				objective,
+				actual: if total.total == 0 {
+					100.0
+				} else {
+					100.0 * (total.total - bad) as f64 / total.total as f64
+				},
+				bad_requests: bad,
+				error_budget_remaining: budget_remaining(&total, target, objective),
+				burn_rates,
+			})
+		})
+		.collect();
+		Status {
+			name: o.name.clone(),
+			route: o.route.clone(),
+			window: o.window,
+			requests: total.total,
+			targets,
+		}
+	}
+}
+
+/// The rate of bad requests relative to the rate the objective allows.
+fn burn_rate(b: &Bucket, target: Target, objective: f64) -> f64 {
+	if b.total == 0 {
+		return 0.0;
+	}
+	let allowed = 1.0 - objective / 100.0;
+	(b.bad(target) as f64 / b.total as f64) / allowed
+}
+
+fn budget_remaining(b: &Bucket, target: Target, objective: f64) -> f64 {
+	let budget = b.total as f64 * (1.0 - objective / 100.0);
+	if budget == 0.0 {
+		return 1.0;
+	}
+	1.0 - b.bad(target) as f64 / budget
+}
+
+struct SloStore {
+	trackers: Vec<Tracker>,
+}
+
+impl SloStore {
+	fn new(cfg: &Config) -> anyhow::Result<Self> {
+		let mut names = std::collections::HashSet::new();
+		for o in &cfg.objectives {
+			if !names.insert(o.name.as_str()) {
+				anyhow::bail!("duplicate objective {:?}", o.name);
+			}
+			if o.availability.is_none() && o.latency.is_none() {
+				anyhow::bail!(
+					"objective {:?} has no availability or latency target",
+					o.name
+				);
+			}
+			let percents = o
+				.availability
+				.into_iter()
+				.chain(o.latency.as_ref().map(|l| l.percentile));
+			for p in percents {
+				if !(p > 0.0 && p < 100.0) {
+					anyhow::bail!(
+						"objective {:?}: target {p} must be a percentage between 0 and 100",
+						o.name
+					);
+				}
+			}
+			if o.window.as_secs() < BUCKET_SECONDS as u64 {
+				anyhow::bail!("objective {:?}: window must be at least 1m", o.name);
+			}
+		}
+		Ok(SloStore {
+			trackers: cfg
+				.objectives
+				.iter()
+				.map(|o| Tracker {
+					objective: o.clone(),
+					buckets: Mutex::new(VecDeque::new()),
+				})
+				.collect(),
+		})
+	}
+
+	fn record(&self, now: DateTime<Utc>, route: Option<&str>, failed: bool, duration: Duration) {
+		for t in &self.trackers {
+			let applies = match &t.objective.route {
+				Some(r) => route == Some(r.as_str()),
+				None => true,
+			};
+			if applies {
+				t.record(now, failed, duration);
+			}
+		}
+	}
+
+	fn report(&self, now: DateTime<Utc>) -> Vec<Status> {
+		self.trackers.iter().map(|t| t.status(now)).collect()
+	}
+}
+
+/// Initialize the global SLO store.
+pub fn init(cfg: &Config) -> anyhow::Result<()> {
+	let store = SloStore::new(cfg)?;
+	SLOS
+		.set(store)
+		.map_err(|_| anyhow::anyhow!("slos already initialized"))
+}
+
+/// Returns true if SLOs are configured.
+pub fn enabled() -> bool {
+	SLOS.get().is_some()
+}
+
+/// Count a completed request towards the objectives for its route.
+pub fn record(log: &RequestLog, duration: Duration) {
+	let Some(store) = SLOS.get() else {
+		return;
+	};
+	let route = log.route_name.as_ref().map(|r| r.as_route_name());
+	let failed = log.error.is_some() || log.status.is_none_or(|s| s.is_server_error());
+	store.record(Utc::now(), route.as_deref(), failed, duration);
+}
+
+/// Report the state of each objective, if SLOs are configured.
+pub fn report() -> Option<Vec<Status>> {
+	SLOS.get().map(|s| s.report(Utc::now()))
+}
+
+/// Exposes compliance, error budget, and burn rate gauges for each objective at scrape time.
+#[derive(Debug)]
+pub struct SloCollector;
+
+impl SloCollector {
+	pub fn register(registry: &mut Registry) {
+		registry.register_collector(Box::new(SloCollector));
+	}
+}
+
+impl Collector for SloCollector {
+	fn encode(&self, mut encoder: DescriptorEncoder) -> Result<(), std::fmt::Error> {
+		let Some(report) = report() else {
+			return Ok(());
+		};
+		let gauges: [(&str, &str, fn(&TargetStatus) -> f64); 2] = [
+			(
+				"slo_compliance",
+				"Percentage of requests meeting the objective over the SLO window",
+				|t| t.actual,
+			),
+			(
+				"slo_error_budget_remaining",
+				"Fraction of the error budget left in the SLO window",
+				|t| t.error_budget_remaining,
+			),
+		];
+		for (name, help, value) in gauges {
+			let mut metric_encoder = encoder.encode_descriptor(name, help, None, MetricType::Gauge)?;
+			for s in &report {
+				for t in &s.targets {
+					let family_encoder = metric_encoder.encode_family(&labels(s, t))?;
+					ConstGauge::new(value(t)).encode(family_encoder)?;
+				}
+			}
+		}
+		let mut metric_encoder = encoder.encode_descriptor(
+			"slo_burn_rate",
+			"Rate the error budget is being spent at, where 1 spends exactly the budget over the SLO window",
+			None,
+			MetricType::Gauge,
+		)?;
+		for s in &report {
+			for t in &s.targets {
+				for (window, rate) in &t.burn_rates {
+					let labels = SloWindowLabels {
+						slo: labels(s, t),
+						window: RichStrng::from(window.as_str()),
+					};
+					let family_encoder = metric_encoder.encode_family(&labels)?;
+					ConstGauge::new(*rate).encode(family_encoder)?;
+				}
+			}
+		}
+		Ok(())
+	}
+}
+
+fn labels(s: &Status, t: &TargetStatus) -> SloLabels {
+	SloLabels {
+		slo: RichStrng::from(s.name.as_str()),
+		target: RichStrng::from(t.target.as_str()),
+	}
+}
@@ -0,0 +1,128 @@
+use chrono::TimeZone;
+
+use super::*;
+
+fn store(objective: serde_json::Value) -> SloStore {
+	let cfg: Config = serde_json::from_value(serde_json::json!({"objectives": [objective]})).unwrap();
+	SloStore::new(&cfg).unwrap()
+}
+
+fn at(secs: i64) -> DateTime<Utc> {
+	Utc.timestamp_opt(1_700_000_000 + secs, 0).unwrap()
+}
+
+#[test]
+fn test_config() {
+	let cfg: Config = serde_json::from_value(serde_json::json!({
+		"objectives": [{
+			"name": "checkout",
+			"route": "default/checkout",
+			"availability": 99.9,
+			"latency": {"percentile": 99, "threshold": "300ms"},
+		}],
+	}))
+	.unwrap();
+	let o = &cfg.objectives[0];
+	assert_eq!(o.window, Duration::from_secs(30 * 24 * 3600));
+	assert_eq!(o.availability, Some(99.9));
+	assert_eq!(
+		o.latency.as_ref().unwrap().threshold,
+		Duration::from_millis(300)
+	);
+}
+
+#[test]
+fn test_validation() {
+	for invalid in [
+		serde_json::json!([{"name": "a"}]),
+		serde_json::json!([{"name": "a", "availability": 100}]),
+		serde_json::json!([{"name": "a", "latency": {"percentile": 0, "threshold": "1s"}}]),
+		serde_json::json!([{"name": "a", "availability": 99, "window": "30s"}]),
+		serde_json::json!([{"name": "a", "availability": 99}, {"name": "a", "availability": 99}]),
+	] {
+		let cfg: Config = serde_json::from_value(serde_json::json!({"objectives": invalid})).unwrap();
+		assert!(SloStore::new(&cfg).is_err(), "{invalid} was accepted");
+	}
+}
+
+#[test]
+fn test_route_match() {
+	let s = store(serde_json::json!({"name": "a", "route": "default/a", "availability": 99}));
+	s.record(at(0), Some("default/a"), false, Duration::ZERO);
+	s.record(at(0), Some("default/b"), false, Duration::ZERO);
+	s.record(at(0), None, false, Duration::ZERO);
+	assert_eq!(s.report(at(0))[0].requests, 1);
+}
+
+#[test]
+fn test_error_budget() {
+	let s = store(serde_json::json!({"name": "a", "availability": 99, "window": "1h"}));
+	// 1000 requests with 5 errors spends half of the 1% budget.
+	for i in 0..1000 {
+		s.record(at(i), None, i % 200 == 0, Duration::ZERO);
+	}
+	let status = &s.report(at(1000))[0];
+	assert_eq!(status.requests, 1000);
+	let t = &status.targets[0];
+	assert_eq!(t.target, Target::Availability);
+	assert_eq!(t.bad_requests, 5);
+	assert!((t.actual - 99.5).abs() < 1e-9);
+	assert!((t.error_budget_remaining - 0.5).abs() < 1e-9);
+	assert!((t.burn_rates["window"] - 0.5).abs() < 1e-9);
+	// Only the windows shorter than the SLO window are reported.
+	assert_eq!(
+		t.burn_rates.keys().collect::<Vec<_>>(),
+		vec!["5m", "window"]
+	);
+}
+
+#[test]
+fn test_burn_rate_windows() {
+	let s = store(serde_json::json!({"name": "a", "availability": 99, "window": "24h"}));
+	for i in 0..100 {
+		s.record(at(0), None, false, Duration::ZERO);
+		s.record(at(3 * 3600), None, i < 10, Duration::ZERO);
+	}
+	let t = &s.report(at(3 * 3600))[0].targets[0];
+	// All errors happened in the last 5 minutes, at 10x the allowed rate.
+	assert!((t.burn_rates["5m"] - 10.0).abs() < 1e-9);
+	assert!((t.burn_rates["1h"] - 10.0).abs() < 1e-9);
+	assert!((t.burn_rates["6h"] - 5.0).abs() < 1e-9);
+	assert!((t.error_budget_remaining + 4.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_latency() {
+	let s = store(serde_json::json!({
+		"name": "a",
+		"latency": {"percentile": 90, "threshold": "100ms"},
+	}));
+	for i in 0..10 {
+		let d = Duration::from_millis(if i < 2 { 500 } else { 50 });
+		// Failures only count against the availability target.
+		s.record(at(0), None, true, d);
+	}
+	let status = &s.report(at(0))[0];
+	assert_eq!(status.targets.len(), 1);
+	let t = &status.targets[0];
+	assert_eq!(t.target, Target::Latency);
+	assert_eq!(t.bad_requests, 2);
+	assert!((t.actual - 80.0).abs() < 1e-9);
+	assert!((t.error_budget_remaining + 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_window_expiry() {
+	let s = store(serde_json::json!({"name": "a", "availability": 99, "window": "1h"}));
+	s.record(at(0), None, true, Duration::ZERO);
+	s.record(at(2 * 3600), None, false, Duration::ZERO);
+	let status = &s.report(at(2 * 3600))[0];
+	assert_eq!(status.requests, 1);
+	assert_eq!(status.targets[0].error_budget_remaining, 1.0);
+	assert_eq!(s.trackers[0].buckets.lock().unwrap().len(), 1);
+
+	let empty = store(serde_json::json!({"name": "a", "availability": 99}));
+	let t = &empty.report(at(0))[0].targets[0];
+	assert_eq!(t.actual, 100.0);
+	assert_eq!(t.burn_rates["window"], 0.0);
+}
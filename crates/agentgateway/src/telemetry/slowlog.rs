@@ -0,0 +1,295 @@
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::OnceLock;
+
+use ::http::HeaderMap;
+use macro_rules_attribute::apply;
+use tokio::sync::mpsc;
+
+use crate::http::Response;
+use crate::proxy::ProxyResponse;
+use crate::telemetry::audit;
+use crate::telemetry::log::RequestLog;
+use crate::telemetry::sink::BoundedSink;
+use crate::*;
+
+#[cfg(test)]
+#[path = "slowlog_tests.rs"]
+mod tests;
+
+static SLOW_LOG: OnceLock<SlowLog> = OnceLock::new();
+
+const CHANNEL_SIZE: usize = 1024;
+
+/// Headers whose values are never written to the slow request log.
+const SENSITIVE_HEADERS: &[&str] = &[
+	"authorization",
+	"proxy-authorization",
+	"cookie",
+	"set-cookie",
+	"x-api-key",
+];
+
+const REDACTED: &str = "[redacted]";
+
+/// Configuration for the slow request log. Requests that exceed their route's latency threshold are
+/// recorded here in detail, including headers, timings, and upstream attempts, as one JSON object
+/// per line.
+#[apply(schema!)]
+pub struct Config {
+	/// Destinations for slow request entries. If unset, entries are written to stdout.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub sinks: Vec<audit::Sink>,
+	/// Threshold for routes without a `slowRequestLog` policy. If unset, only routes with a policy
+	/// are recorded.
+	#[serde(
+		default,
+		with = "serde_dur_option",
+		skip_serializing_if = "Option::is_none"
+	)]
+	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+	pub threshold: Option<Duration>,
+	/// Additional headers whose values are redacted. `authorization`, `cookie`, and similar headers
+	/// are always redacted.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub redact_headers: Vec<String>,
+}
+
+/// Per-route slow request logging.
+#[apply(schema!)]
+pub struct Policy {
+	/// Record requests to this route that take at least this long.
+	#[serde(with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub threshold: Duration,
+}
+
+/// Details of a request collected while it is proxied, in case it turns out to be slow.
+#[derive(Debug)]
+pub struct Capture {
+	threshold: Duration,
+	request_headers: HeaderMap,
+	response_headers: Option<HeaderMap>,
+	/// Time from the start of the request until the response headers were sent.
+	response_start: Option<Duration>,
+	attempts: Vec<Attempt>,
+}
+
+/// One request sent to a backend. Retries result in multiple attempts.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Attempt {
+	/// Time from the start of the request until the attempt was sent.
+	#[serde(with = "serde_dur")]
+	pub start: Duration,
+	/// Time until the backend returned response headers, or the attempt failed.
+	#[serde(with = "serde_dur")]
+	pub duration: Duration,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub endpoint: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub status: Option<u16>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Timings {
+	/// Time spent on routing and request policies, before the first upstream attempt.
+	#[serde(with = "serde_dur_option", skip_serializing_if = "Option::is_none")]
+	before_upstream: Option<Duration>,
+	/// Time spent waiting on upstream attempts, including retries.
+	#[serde(with = "serde_dur")]
+	upstream: Duration,
+	/// Time until the response headers were sent to the client.
+	#[serde(with = "serde_dur_option", skip_serializing_if = "Option::is_none")]
+	response_start: Option<Duration>,
+	#[serde(with = "serde_dur")]
+	total: Duration,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry<'a> {
+	timestamp: String,
+	#[serde(with = "serde_dur")]
+	threshold: Duration,
+	src_addr: SocketAddr,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	request_id: Option<&'a str>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	trace_id: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	method: Option<&'a str>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	host: Option<&'a str>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	path: Option<&'a str>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	status: Option<u16>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	route: Option<Strng>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	backend: Option<Strng>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	error: Option<&'a str>,
+	timings: Timings,
+	attempts: &'a [Attempt],
+	request_headers: BTreeMap<&'a str, String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	response_headers: Option<BTreeMap<&'a str, String>>,
+}
+
+pub struct SlowLog {
+	threshold: Option<Duration>,
+	redact_headers: Vec<String>,
+	sink: BoundedSink<Vec<u8>>,
+}
+
+/// Initialize the global slow request log.
+pub fn init(cfg: &Config) -> anyhow::Result<()> {
+	let writers = audit::open_writers(&cfg.sinks)?;
+	let (sink, rx) = BoundedSink::new("slow request log", CHANNEL_SIZE);
+	std::thread::Builder::new()
+		.name("slow-request-log".to_string())
+		.spawn(move || write_loop(rx, writers))?;
+	SLOW_LOG
+		.set(SlowLog {
+			threshold: cfg.threshold,
+			redact_headers: cfg.redact_headers.clone(),
+			sink,
+		})
+		.map_err(|_| anyhow::anyhow!("slow request log already initialized"))
+}
+
+/// Start capturing details of a request, if the slow request log applies to its route.
+pub fn capture(policy: Option<&Policy>, headers: &HeaderMap) -> Option<Box<Capture>> {
+	let s = SLOW_LOG.get()?;
+	let threshold = policy.map(|p| p.threshold).or(s.threshold)?;
+	Some(Box::new(Capture::new(threshold, headers)))
+}
+
+impl Capture {
+	fn new(threshold: Duration, request_headers: &HeaderMap) -> Self {
+		Capture {
+			threshold,
+			request_headers: request_headers.clone(),
+			response_headers: None,
+			response_start: None,
+			attempts: vec![],
+		}
+	}
+
+	pub fn record_attempt(&mut self, attempt: Attempt) {
+		self.attempts.push(attempt);
+	}
+
+	pub fn record_response(&mut self, elapsed: Duration, headers: &HeaderMap) {
+		self.response_start = Some(elapsed);
+		self.response_headers = Some(headers.clone());
+	}
+}
+
+impl Attempt {
+	pub fn new(
+		request_start: Instant,
+		attempt_start: Instant,
+		endpoint: Option<String>,
+		res: &Result<Response, ProxyResponse>,
+	) -> Self {
+		Attempt {
+			start: attempt_start.saturating_duration_since(request_start),
+			duration: attempt_start.elapsed(),
+			endpoint,
+			status: res.as_ref().ok().map(|r| r.status().as_u16()),
+			error: res.as_ref().err().map(|e| e.to_string()),
+		}
+	}
+}
+
+/// Record a completed request, if it was captured and exceeded its threshold.
+pub fn record(log: &RequestLog, duration: Duration) {
+	let Some(capture) = log.slow_request.as_deref() else {
+		return;
+	};
+	if duration < capture.threshold {
+		return;
+	}
+	let Some(s) = SLOW_LOG.get() else {
+		return;
+	};
+	let mut line = match serde_json::to_vec(&s.entry(log, capture, duration)) {
+		Ok(l) => l,
+		Err(e) => {
+			warn!("failed to serialize slow request: {e}");
+			return;
+		},
+	};
+	line.push(b'\n');
+	s.sink.send(line);
+}
+
+impl SlowLog {
+	fn entry<'a>(&self, log: &'a RequestLog, capture: &'a Capture, total: Duration) -> Entry<'a> {
+		Entry {
+			timestamp: agent_core::telemetry::render_current_time(),
+			threshold: capture.threshold,
+			src_addr: log.tcp_info.peer_addr,
+			request_id: log.request_id.as_deref(),
+			trace_id: log.outgoing_span.as_ref().map(|s| s.trace_id()),
+			method: log.method.as_ref().map(|m| m.as_str()),
+			host: log.host.as_deref(),
+			path: log.path.as_deref(),
+			status: log.status.map(|s| s.as_u16()),
+			route: log.route_name.as_ref().map(|r| r.as_route_name()),
+			backend: log.backend_info.as_ref().map(|b| b.backend_name.clone()),
+			error: log.error.as_deref(),
+			timings: Timings {
+				before_upstream: capture.attempts.first().map(|a| a.start),
+				upstream: capture.attempts.iter().map(|a| a.duration).sum(),
+				response_start: capture.response_start,
+				total,
+			},
+			attempts: &capture.attempts,
+			request_headers: self.headers(&capture.request_headers),
+			response_headers: capture.response_headers.as_ref().map(|h| self.headers(h)),
+		}
+	}
+
+	/// Flatten headers for logging, joining repeated headers and redacting sensitive values.
+	fn headers<'a>(&self, headers: &'a HeaderMap) -> BTreeMap<&'a str, String> {
+		let mut out: BTreeMap<&str, String> = BTreeMap::new();
+		for (k, v) in headers {
+			let redact = v.is_sensitive()
+				|| SENSITIVE_HEADERS.contains(&k.as_str())
+				|| self
+					.redact_headers
+					.iter()
+					.any(|h| h.eq_ignore_ascii_case(k.as_str()));
+			let v = if redact {
+				REDACTED
+			} else {
+				v.to_str().unwrap_or("[binary]")
+			};
+			out
+				.entry(k.as_str())
+				.and_modify(|e| {
+					e.push_str(", ");
+					e.push_str(v);
+				})
+				.or_insert_with(|| v.to_string());
+		}
+		out
+	}
+}
+
+fn write_loop(mut rx: mpsc::Receiver<Vec<u8>>, mut writers: Vec<audit::Writer>) {
+	while let Some(line) = rx.blocking_recv() {
+		for w in writers.iter_mut() {
+			if let Err(e) = w.write_line(&line) {
+				warn!("failed to write slow request: {e}");
+			}
+		}
+	}
+}
@@ -0,0 +1,88 @@
+use ::http::HeaderValue;
+
+use super::*;
+use crate::proxy::ProxyError;
+
+fn slow_log(redact_headers: Vec<String>) -> SlowLog {
+	let (sink, _rx) = BoundedSink::new("slow request log", 1);
+	SlowLog {
+		threshold: None,
+		redact_headers,
+		sink,
+	}
+}
+
+#[test]
+fn test_config() {
+	let cfg: Config = serde_json::from_value(serde_json::json!({
+		"sinks": [{"file": {"path": "/var/log/slow.log"}}],
+		"threshold": "2s",
+		"redactHeaders": ["x-session"],
+	}))
+	.unwrap();
+	assert_eq!(cfg.threshold, Some(Duration::from_secs(2)));
+	assert_eq!(cfg.redact_headers, vec!["x-session".to_string()]);
+
+	let p: Policy = serde_json::from_value(serde_json::json!({"threshold": "500ms"})).unwrap();
+	assert_eq!(p.threshold, Duration::from_millis(500));
+}
+
+#[test]
+fn test_headers() {
+	let mut h = HeaderMap::new();
+	h.insert("authorization", HeaderValue::from_static("Bearer secret"));
+	h.insert("x-session", HeaderValue::from_static("abc"));
+	h.append("accept", HeaderValue::from_static("text/html"));
+	h.append("accept", HeaderValue::from_static("application/json"));
+	let mut sensitive = HeaderValue::from_static("hidden");
+	sensitive.set_sensitive(true);
+	h.insert("x-token", sensitive);
+
+	let out = slow_log(vec!["X-Session".to_string()]).headers(&h);
+	assert_eq!(
+		out,
+		BTreeMap::from([
+			("accept", "text/html, application/json".to_string()),
+			("authorization", REDACTED.to_string()),
+			("x-session", REDACTED.to_string()),
+			("x-token", REDACTED.to_string()),
+		])
+	);
+}
+
+#[test]
+fn test_attempt() {
+	let request_start = Instant::now();
+	let attempt_start = request_start + Duration::from_millis(10);
+
+	let resp = ::http::Response::builder()
+		.status(503)
+		.body(crate::http::Body::empty())
+		.unwrap();
+	let a = Attempt::new(
+		request_start,
+		attempt_start,
+		Some("10.0.0.1:8080".to_string()),
+		&Ok(resp),
+	);
+	assert_eq!(a.start, Duration::from_millis(10));
+	assert_eq!(a.status, Some(503));
+	assert_eq!(a.error, None);
+
+	let a = Attempt::new(
+		request_start,
+		attempt_start,
+		None,
+		&Err(ProxyError::RequestTimeout.into()),
+	);
+	assert_eq!(a.status, None);
+	assert!(a.error.is_some());
+}
+
+#[test]
+fn test_capture() {
+	let mut c = Capture::new(Duration::from_secs(1), &HeaderMap::new());
+	c.record_response(Duration::from_millis(1200), &HeaderMap::new());
+	assert_eq!(c.response_start, Some(Duration::from_millis(1200)));
+	assert!(c.response_headers.is_some());
+}
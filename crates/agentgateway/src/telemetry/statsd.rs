@@ -0,0 +1,207 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use macro_rules_attribute::apply;
+use prometheus_client::registry::Registry;
+use tokio::net::UdpSocket;
+
+use crate::management::metrics_stream::{Sample, deltas, sample};
+use crate::*;
+
+#[cfg(test)]
+#[path = "statsd_tests.rs"]
+mod tests;
+
+/// Maximum payload of a single datagram, small enough to avoid fragmentation on common networks.
+const MAX_PACKET_SIZE: usize = 1432;
+
+#[apply(schema!)]
+#[derive(Default, Copy, PartialEq, Eq)]
+pub enum Format {
+	/// Plain StatsD. Labels are not supported, so series of the same metric are summed.
+	#[default]
+	Statsd,
+	/// DogStatsD, with labels sent as tags.
+	DogStatsd,
+}
+
+/// Push metrics to a StatsD or DogStatsD server, in addition to serving them for Prometheus.
+#[apply(schema!)]
+pub struct Config {
+	/// Address of the server, such as `127.0.0.1:8125`.
+	pub address: String,
+	#[serde(default)]
+	pub format: Format,
+	/// Prefix for all metric names, such as `agentgateway.`.
+	#[serde(default, skip_serializing_if = "String::is_empty")]
+	pub prefix: String,
+	/// Tags added to all metrics. Only sent with DogStatsD.
+	#[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+	pub tags: BTreeMap<String, String>,
+	/// How often metrics are sent. Counters are sent as their increase over the interval.
+	#[serde(default = "default_flush_interval", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub flush_interval: Duration,
+}
+
+fn default_flush_interval() -> Duration {
+	Duration::from_secs(10)
+}
+
+/// Start sending metrics from `registry` every flush interval.
+pub fn spawn(cfg: Config, registry: Arc<Mutex<Registry>>) -> anyhow::Result<()> {
+	anyhow::ensure!(
+		!cfg.flush_interval.is_zero(),
+		"statsd flushInterval must be positive"
+	);
+	tokio::spawn(run(cfg, registry));
+	Ok(())
+}
+
+async fn run(cfg: Config, registry: Arc<Mutex<Registry>>) {
+	let mut interval = tokio::time::interval_at(
+		tokio::time::Instant::now() + cfg.flush_interval,
+		cfg.flush_interval,
+	);
+	interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+	let mut prev = sample(&registry, &[]);
+	let mut socket = None;
+	loop {
+		interval.tick().await;
+		let cur = sample(&registry, &[]);
+		let lines = lines(&cfg, &prev, &cur);
+		prev = cur;
+		if socket.is_none() {
+			// Resolve the address again after failures, in case it changed.
+			socket = connect(&cfg.address)
+				.await
+				.inspect_err(|e| warn!("failed to connect to statsd server {}: {e}", cfg.address))
+				.ok();
+		}
+		let Some(s) = &socket else {
+			continue;
+		};
+		let mut failed = false;
+		for packet in packets(&lines) {
+			if let Err(e) = s.send(packet.as_bytes()).await {
+				debug!("failed to send statsd metrics: {e}");
+				failed = true;
+				break;
+			}
+		}
+		if failed {
+			socket = None;
+		}
+	}
+}
+
+async fn connect(address: &str) -> std::io::Result<UdpSocket> {
+	let addr = tokio::net::lookup_host(address)
+		.await?
+		.next()
+		.ok_or_else(|| std::io::Error::other("address did not resolve"))?;
+	let bind = if addr.is_ipv4() {
+		"0.0.0.0:0"
+	} else {
+		"[::]:0"
+	};
+	let socket = UdpSocket::bind(bind).await?;
+	socket.connect(addr).await?;
+	Ok(socket)
+}
+
+/// The StatsD lines for an interval: the increase of each counter, and the value of each gauge.
+fn lines(cfg: &Config, prev: &Sample, cur: &Sample) -> Vec<String> {
+	let increases = deltas(prev, cur);
+	let series = increases
+		.iter()
+		.map(|(k, v)| (k, *v, "c"))
+		.chain(cur.gauges.iter().map(|(k, v)| (k, *v, "g")));
+	match cfg.format {
+		Format::Statsd => {
+			let mut summed: BTreeMap<(&str, &str), f64> = BTreeMap::new();
+			for (k, v, kind) in series {
+				let (name, _) = split_series(k);
+				*summed.entry((name, kind)).or_default() += v;
+			}
+			summed
+				.into_iter()
+				.map(|((name, kind), v)| format!("{}{name}:{v}|{kind}", cfg.prefix))
+				.collect()
+		},
+		Format::DogStatsd => series
+			.map(|(k, v, kind)| {
+				let (name, labels) = split_series(k);
+				let tags = cfg
+					.tags
+					.iter()
+					.map(|(k, v)| (k.as_str(), v.clone()))
+					.chain(labels.iter().map(|(k, v)| (*k, v.clone())))
+					.map(|(k, v)| format!("{}:{}", sanitize(k), sanitize(&v)))
+					.collect::<Vec<_>>();
+				if tags.is_empty() {
+					format!("{}{name}:{v}|{kind}", cfg.prefix)
+				} else {
+					format!("{}{name}:{v}|{kind}|#{}", cfg.prefix, tags.join(","))
+				}
+			})
+			.collect(),
+	}
+}
+
+/// Split a series from the text exposition, such as `requests_total{a="b"}`, into its name and
+/// labels.
+fn split_series(series: &str) -> (&str, Vec<(&str, String)>) {
+	let Some((name, rest)) = series.split_once('{') else {
+		return (series, vec![]);
+	};
+	let mut labels = vec![];
+	let mut rest = rest.strip_suffix('}').unwrap_or(rest);
+	while let Some((key, value)) = rest.split_once("=\"") {
+		let mut v = String::new();
+		let mut chars = value.char_indices();
+		let mut end = value.len();
+		while let Some((i, c)) = chars.next() {
+			match c {
+				'\\' => match chars.next() {
+					Some((_, 'n')) => v.push('\n'),
+					Some((_, c)) => v.push(c),
+					None => {},
+				},
+				'"' => {
+					end = i + 1;
+					break;
+				},
+				c => v.push(c),
+			}
+		}
+		labels.push((key.trim_start_matches(','), v));
+		rest = &value[end..];
+	}
+	(name, labels)
+}
+
+/// Replace characters that are reserved in DogStatsD tags.
+fn sanitize(s: &str) -> String {
+	s.replace(['|', ',', '#', '\n'], "_")
+}
+
+/// Join lines into as few packets as possible, each at most `MAX_PACKET_SIZE` unless a single
+/// line is larger.
+fn packets(lines: &[String]) -> Vec<String> {
+	let mut packets = vec![];
+	let mut cur = String::new();
+	for l in lines {
+		if !cur.is_empty() && cur.len() + 1 + l.len() > MAX_PACKET_SIZE {
+			packets.push(std::mem::take(&mut cur));
+		}
+		if !cur.is_empty() {
+			cur.push('\n');
+		}
+		cur.push_str(l);
+	}
+	if !cur.is_empty() {
+		packets.push(cur);
+	}
+	packets
+}
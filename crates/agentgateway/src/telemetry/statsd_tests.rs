@@ -0,0 +1,81 @@
+use super::*;
+
+const PREV: &str = r#"# TYPE requests counter
+requests_total{backend="a",status="200"} 10
+requests_total{backend="b",status="200"} 1
+# TYPE pool_idle gauge
+pool_idle{backend="a"} 3
+# EOF
+"#;
+
+const CUR: &str = r#"# TYPE requests counter
+requests_total{backend="a",status="200"} 15
+requests_total{backend="b",status="200"} 3
+# TYPE pool_idle gauge
+pool_idle{backend="a"} 2
+pool_idle{backend="b|c"} 1
+# EOF
+"#;
+
+fn config(format: Format) -> Config {
+	Config {
+		address: "127.0.0.1:8125".to_string(),
+		format,
+		prefix: "agentgateway.".to_string(),
+		tags: BTreeMap::from([("env".to_string(), "prod".to_string())]),
+		flush_interval: default_flush_interval(),
+	}
+}
+
+#[test]
+fn test_lines_statsd() {
+	let prev = Sample::parse(PREV, &[]);
+	let cur = Sample::parse(CUR, &[]);
+	assert_eq!(
+		lines(&config(Format::Statsd), &prev, &cur),
+		vec![
+			"agentgateway.pool_idle:3|g",
+			"agentgateway.requests_total:7|c",
+		]
+	);
+}
+
+#[test]
+fn test_lines_dogstatsd() {
+	let prev = Sample::parse(PREV, &[]);
+	let cur = Sample::parse(CUR, &[]);
+	assert_eq!(
+		lines(&config(Format::DogStatsd), &prev, &cur),
+		vec![
+			"agentgateway.requests_total:5|c|#env:prod,backend:a,status:200",
+			"agentgateway.requests_total:2|c|#env:prod,backend:b,status:200",
+			"agentgateway.pool_idle:2|g|#env:prod,backend:a",
+			"agentgateway.pool_idle:1|g|#env:prod,backend:b_c",
+		]
+	);
+}
+
+#[test]
+fn test_split_series() {
+	assert_eq!(split_series("build_info"), ("build_info", vec![]));
+	assert_eq!(
+		split_series(r#"requests_total{a="b",path="/x\"y\\",c=""}"#),
+		(
+			"requests_total",
+			vec![
+				("a", "b".to_string()),
+				("path", r#"/x"y\"#.to_string()),
+				("c", "".to_string())
+			]
+		)
+	);
+}
+
+#[test]
+fn test_packets() {
+	let lines: Vec<String> = (0..100).map(|i| format!("metric_{i:03}:1|c")).collect();
+	let packets = packets(&lines);
+	assert!(packets.len() > 1);
+	assert!(packets.iter().all(|p| p.len() <= MAX_PACKET_SIZE));
+	assert_eq!(packets.join("\n").lines().count(), 100);
+}
@@ -0,0 +1,205 @@
+use std::io::{self, Write};
+use std::net::{TcpStream, ToSocketAddrs, UdpSocket};
+
+use chrono::{SecondsFormat, Utc};
+use macro_rules_attribute::apply;
+use rustls::pki_types::ServerName;
+use rustls::{ClientConfig, ClientConnection, StreamOwned};
+
+use crate::transport;
+use crate::*;
+
+#[cfg(test)]
+#[path = "syslog_tests.rs"]
+mod tests;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[apply(schema!)]
+#[derive(Default, Copy, PartialEq, Eq)]
+pub enum Transport {
+	#[default]
+	Udp,
+	Tcp,
+	Tls,
+}
+
+/// Send entries to a syslog server, as RFC 5424 messages. TCP and TLS use octet-counting framing
+/// (RFC 6587, RFC 5425).
+#[apply(schema!)]
+pub struct SyslogSink {
+	/// Address of the syslog server, as `host:port`.
+	pub address: String,
+	#[serde(default)]
+	pub transport: Transport,
+	/// Syslog facility code (default: 16, local0).
+	#[serde(default = "defaults::facility")]
+	pub facility: u8,
+	/// Application name in the message header.
+	#[serde(default = "defaults::app_name")]
+	pub app_name: String,
+	/// PEM encoded CA certificate used to verify the server, for the TLS transport. Defaults to the
+	/// system roots.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub ca_cert: Option<FileOrInline>,
+}
+
+mod defaults {
+	pub fn facility() -> u8 {
+		16
+	}
+	pub fn app_name() -> String {
+		"agentgateway".to_string()
+	}
+}
+
+/// Syslog severity of a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+	Warning = 4,
+	Informational = 6,
+}
+
+enum Conn {
+	Udp(UdpSocket),
+	Tcp(TcpStream),
+	Tls(Box<StreamOwned<ClientConnection, TcpStream>>),
+}
+
+/// Writes messages to a syslog server, reconnecting as needed. Writes block, so this is used from a
+/// dedicated thread.
+pub(super) struct Writer {
+	cfg: SyslogSink,
+	hostname: String,
+	tls: Option<(Arc<ClientConfig>, ServerName<'static>)>,
+	conn: Option<Conn>,
+}
+
+impl Writer {
+	pub(super) fn new(cfg: SyslogSink) -> anyhow::Result<Self> {
+		if cfg.facility > 23 {
+			anyhow::bail!("invalid syslog facility {}", cfg.facility);
+		}
+		let (host, _) = cfg
+			.address
+			.rsplit_once(':')
+			.ok_or_else(|| anyhow::anyhow!("syslog address must be host:port"))?;
+		let tls = match cfg.transport {
+			Transport::Tls => {
+				let server_name = ServerName::try_from(host.trim_matches(['[', ']']).to_string())?;
+				Some((Arc::new(client_config(cfg.ca_cert.as_ref())?), server_name))
+			},
+			_ => None,
+		};
+		Ok(Writer {
+			hostname: std::env::var("HOSTNAME")
+				.ok()
+				.filter(|h| !h.is_empty())
+				.unwrap_or_else(|| "-".to_string()),
+			cfg,
+			tls,
+			conn: None,
+		})
+	}
+
+	pub(super) fn write(&mut self, severity: Severity, msg: &[u8]) -> io::Result<()> {
+		let message = self.message(severity, msg);
+		// A broken stream is only noticed on write; reconnect and retry once.
+		for attempt in 0..2 {
+			match self.send(&message) {
+				Ok(()) => return Ok(()),
+				Err(e) if attempt == 0 && self.cfg.transport != Transport::Udp => {
+					debug!("syslog connection failed, reconnecting: {e}");
+					self.conn = None;
+				},
+				Err(e) => {
+					self.conn = None;
+					return Err(e);
+				},
+			}
+		}
+		Ok(())
+	}
+
+	fn message(&self, severity: Severity, msg: &[u8]) -> Vec<u8> {
+		let pri = self.cfg.facility as u32 * 8 + severity as u32;
+		let header = format!(
+			"<{pri}>1 {} {} {} {} access - ",
+			Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+			self.hostname,
+			self.cfg.app_name,
+			std::process::id(),
+		);
+		let mut message = header.into_bytes();
+		message.extend_from_slice(msg);
+		message
+	}
+
+	fn send(&mut self, message: &[u8]) -> io::Result<()> {
+		if self.conn.is_none() {
+			self.conn = Some(self.connect()?);
+		}
+		let framed = |w: &mut dyn Write| {
+			write!(w, "{} ", message.len())?;
+			w.write_all(message)?;
+			w.flush()
+		};
+		match self.conn.as_mut().expect("connection was just set") {
+			Conn::Udp(s) => s.send(message).map(|_| ()),
+			Conn::Tcp(s) => framed(s),
+			Conn::Tls(s) => framed(s.as_mut()),
+		}
+	}
+
+	fn connect(&self) -> io::Result<Conn> {
+		let addr = self
+			.cfg
+			.address
+			.to_socket_addrs()?
+			.next()
+			.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no addresses for syslog server"))?;
+		if self.cfg.transport == Transport::Udp {
+			let bind = if addr.is_ipv4() {
+				"0.0.0.0:0"
+			} else {
+				"[::]:0"
+			};
+			let s = UdpSocket::bind(bind)?;
+			s.connect(addr)?;
+			return Ok(Conn::Udp(s));
+		}
+		let s = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+		s.set_write_timeout(Some(WRITE_TIMEOUT))?;
+		s.set_nodelay(true)?;
+		match &self.tls {
+			Some((cc, server_name)) => {
+				let conn =
+					ClientConnection::new(cc.clone(), server_name.clone()).map_err(io::Error::other)?;
+				Ok(Conn::Tls(Box::new(StreamOwned::new(conn, s))))
+			},
+			None => Ok(Conn::Tcp(s)),
+		}
+	}
+}
+
+fn client_config(ca_cert: Option<&FileOrInline>) -> anyhow::Result<ClientConfig> {
+	let mut roots = rustls::RootCertStore::empty();
+	match ca_cert {
+		Some(ca) => {
+			let pem = ca.load()?;
+			let certs = rustls_pemfile::certs(&mut io::BufReader::new(pem.as_bytes()))
+				.collect::<Result<Vec<_>, _>>()?;
+			roots.add_parsable_certificates(certs);
+		},
+		None => {
+			roots.add_parsable_certificates(crate::http::backendtls::SYSTEM_ROOT.certs.iter().cloned());
+		},
+	}
+	Ok(
+		ClientConfig::builder_with_provider(transport::tls::provider())
+			.with_protocol_versions(transport::tls::ALL_TLS_VERSIONS)?
+			.with_root_certificates(roots)
+			.with_no_client_auth(),
+	)
+}
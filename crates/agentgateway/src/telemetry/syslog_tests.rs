@@ -0,0 +1,68 @@
+use std::io::Read;
+use std::net::{TcpListener, UdpSocket};
+
+use super::*;
+
+fn sink(address: String, transport: Transport) -> SyslogSink {
+	SyslogSink {
+		address,
+		transport,
+		facility: 16,
+		app_name: "agw".to_string(),
+		ca_cert: None,
+	}
+}
+
+#[test]
+fn test_udp() {
+	let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+	let mut w = Writer::new(sink(
+		server.local_addr().unwrap().to_string(),
+		Transport::Udp,
+	))
+	.unwrap();
+	w.write(Severity::Warning, b"hello").unwrap();
+
+	let mut buf = [0u8; 512];
+	let n = server.recv(&mut buf).unwrap();
+	let msg = std::str::from_utf8(&buf[..n]).unwrap();
+	// local0 (16) * 8 + warning (4)
+	assert!(msg.starts_with("<132>1 "), "{msg}");
+	assert!(msg.ends_with(" access - hello"), "{msg}");
+	assert!(msg.contains(" agw "), "{msg}");
+}
+
+#[test]
+fn test_tcp_octet_counting() {
+	let server = TcpListener::bind("127.0.0.1:0").unwrap();
+	let mut w = Writer::new(sink(
+		server.local_addr().unwrap().to_string(),
+		Transport::Tcp,
+	))
+	.unwrap();
+	w.write(Severity::Informational, b"one").unwrap();
+	w.write(Severity::Informational, b"two").unwrap();
+	drop(w);
+
+	let (mut conn, _) = server.accept().unwrap();
+	let mut received = String::new();
+	conn.read_to_string(&mut received).unwrap();
+	let mut rest = received.as_str();
+	for expected in ["one", "two"] {
+		let (len, after) = rest.split_once(' ').unwrap();
+		let len: usize = len.parse().unwrap();
+		let (msg, next) = after.split_at(len);
+		assert!(msg.starts_with("<134>1 "), "{msg}");
+		assert!(msg.ends_with(expected), "{msg}");
+		rest = next;
+	}
+	assert!(rest.is_empty());
+}
+
+#[test]
+fn test_invalid_config() {
+	let mut s = sink("127.0.0.1:514".to_string(), Transport::Udp);
+	s.facility = 24;
+	assert!(Writer::new(s).is_err());
+	assert!(Writer::new(sink("localhost".to_string(), Transport::Udp)).is_err());
+}
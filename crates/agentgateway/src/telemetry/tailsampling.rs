@@ -0,0 +1,265 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+use macro_rules_attribute::apply;
+use opentelemetry::trace::{SpanKind, Status, TraceId};
+use opentelemetry::{Context, KeyValue, Value};
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::trace::{Span, SpanData, SpanProcessor};
+
+use crate::*;
+
+#[cfg(test)]
+#[path = "tailsampling_tests.rs"]
+mod tests;
+
+/// Tail sampling buffers the spans of each trace until its server span ends, then exports the trace
+/// only if it matches one of the policies. This is applied after head sampling
+/// (`randomSampling`/`clientSampling`), so those should be set high enough to see the traces of
+/// interest.
+#[apply(schema!)]
+#[derive(PartialEq, Eq)]
+pub struct Config {
+	/// Keep traces with an error: a 5xx status, an `error` attribute, or an error span status.
+	#[serde(default, skip_serializing_if = "is_default")]
+	pub errors: bool,
+	/// Keep traces whose server span took at least this long.
+	#[serde(
+		default,
+		with = "serde_dur_option",
+		skip_serializing_if = "Option::is_none"
+	)]
+	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+	pub latency_threshold: Option<Duration>,
+	/// Keep traces for these routes, as `namespace/name`.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub routes: Vec<String>,
+	/// Keep traces that call these MCP tools.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub tools: Vec<String>,
+	/// How long to buffer the spans of a trace waiting for its server span. Default is 30s.
+	#[serde(default = "defaults::decision_wait", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub decision_wait: Duration,
+	/// Maximum number of traces buffered at once. Spans of additional traces are dropped.
+	#[serde(default = "defaults::max_traces")]
+	pub max_traces: usize,
+}
+
+mod defaults {
+	use std::time::Duration;
+
+	pub fn decision_wait() -> Duration {
+		Duration::from_secs(30)
+	}
+	pub fn max_traces() -> usize {
+		10_000
+	}
+}
+
+impl Config {
+	fn keep(&self, t: &Summary) -> bool {
+		(self.errors && t.error)
+			|| self.latency_threshold.is_some_and(|l| t.duration >= l)
+			|| t.route.as_ref().is_some_and(|r| self.routes.contains(r))
+			|| t.tools.iter().any(|tool| self.tools.contains(tool))
+	}
+}
+
+/// The properties of a trace that policies are evaluated against.
+#[derive(Debug, Default)]
+struct Summary {
+	error: bool,
+	duration: Duration,
+	route: Option<String>,
+	tools: Vec<String>,
+}
+
+impl Summary {
+	fn add(&mut self, attributes: &[KeyValue], status: &Status) {
+		if matches!(status, Status::Error { .. }) {
+			self.error = true;
+		}
+		let mut resource_type = None;
+		let mut resource_name = None;
+		for kv in attributes {
+			match (kv.key.as_str(), &kv.value) {
+				("error", _) => self.error = true,
+				("http.status", Value::I64(s)) if *s >= 500 => self.error = true,
+				("route", v) => self.route = Some(v.as_str().into_owned()),
+				("mcp.resource.type", v) => resource_type = Some(v.as_str()),
+				("mcp.resource.name", v) => resource_name = Some(v.as_str()),
+				_ => {},
+			}
+		}
+		if let (Some(t), Some(n)) = (resource_type, resource_name)
+			&& t == "tool"
+		{
+			self.tools.push(n.into_owned());
+		}
+	}
+
+	fn from_spans(spans: &[SpanData]) -> Summary {
+		let mut summary = Summary::default();
+		for s in spans {
+			summary.add(&s.attributes, &s.status);
+			if s.span_kind == SpanKind::Server {
+				summary.duration = s.end_time.duration_since(s.start_time).unwrap_or_default();
+			}
+		}
+		summary
+	}
+}
+
+struct Pending {
+	created: Instant,
+	spans: Vec<SpanData>,
+}
+
+impl std::fmt::Debug for Pending {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("Pending")
+			.field("spans", &self.spans.len())
+			.finish()
+	}
+}
+
+/// The traces waiting for their server span.
+#[derive(Debug, Default)]
+struct Buffer {
+	traces: HashMap<TraceId, Pending>,
+	/// When each trace was first buffered, oldest first. Entries for traces that already completed
+	/// are skipped when they expire.
+	created: VecDeque<(Instant, TraceId)>,
+}
+
+impl Buffer {
+	/// Discard traces whose server span did not arrive within `decision_wait`.
+	fn expire(&mut self, now: Instant, decision_wait: Duration) {
+		while let Some(&(created, trace_id)) = self.created.front() {
+			if now.saturating_duration_since(created) < decision_wait {
+				break;
+			}
+			self.created.pop_front();
+			if self
+				.traces
+				.get(&trace_id)
+				.is_some_and(|p| p.created == created)
+			{
+				self.traces.remove(&trace_id);
+			}
+		}
+	}
+
+	/// Get the pending trace, if there is room to buffer it.
+	fn get_or_insert(
+		&mut self,
+		trace_id: TraceId,
+		now: Instant,
+		max_traces: usize,
+	) -> Option<&mut Pending> {
+		if !self.traces.contains_key(&trace_id) {
+			if self.traces.len() >= max_traces {
+				return None;
+			}
+			self.created.push_back((now, trace_id));
+		}
+		Some(self.traces.entry(trace_id).or_insert_with(|| Pending {
+			created: now,
+			spans: Vec::new(),
+		}))
+	}
+
+	fn remove(&mut self, trace_id: &TraceId) -> Option<Pending> {
+		self.traces.remove(trace_id)
+	}
+
+	fn clear(&mut self) {
+		self.traces.clear();
+		self.created.clear();
+	}
+}
+
+/// A span processor that applies tail sampling before handing complete traces to `inner`.
+#[derive(Debug)]
+pub struct TailSamplingProcessor<P> {
+	cfg: Config,
+	inner: P,
+	pending: Mutex<Buffer>,
+	dropped: AtomicU64,
+}
+
+impl<P: SpanProcessor> TailSamplingProcessor<P> {
+	pub fn new(cfg: Config, inner: P) -> Self {
+		Self {
+			cfg,
+			inner,
+			pending: Mutex::new(Buffer::default()),
+			dropped: AtomicU64::new(0),
+		}
+	}
+
+	fn buffer(&self, span: SpanData) {
+		let mut pending = self.pending.lock().expect("mutex acquired");
+		let now = Instant::now();
+		// Discard traces whose server span never arrived, so they do not hold memory until the buffer
+		// fills up.
+		pending.expire(now, self.cfg.decision_wait);
+		let trace_id = span.span_context.trace_id();
+		let Some(trace) = pending.get_or_insert(trace_id, now, self.cfg.max_traces) else {
+			let dropped = self.dropped.fetch_add(1, Ordering::Relaxed);
+			if dropped % 1000 == 0 {
+				warn!(
+					dropped = dropped + 1,
+					"tail sampling buffer is full, dropping spans"
+				);
+			}
+			return;
+		};
+		trace.spans.push(span);
+	}
+}
+
+impl<P: SpanProcessor> SpanProcessor for TailSamplingProcessor<P> {
+	fn on_start(&self, span: &mut Span, cx: &Context) {
+		self.inner.on_start(span, cx)
+	}
+
+	fn on_end(&self, span: SpanData) {
+		// The server span is the last span of a request to end, so the trace is complete.
+		if span.span_kind != SpanKind::Server {
+			self.buffer(span);
+			return;
+		}
+		let mut spans = self
+			.pending
+			.lock()
+			.expect("mutex acquired")
+			.remove(&span.span_context.trace_id())
+			.map(|p| p.spans)
+			.unwrap_or_default();
+		spans.push(span);
+		if !self.cfg.keep(&Summary::from_spans(&spans)) {
+			return;
+		}
+		for s in spans {
+			self.inner.on_end(s);
+		}
+	}
+
+	fn force_flush(&self) -> OTelSdkResult {
+		self.inner.force_flush()
+	}
+
+	fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+		self.pending.lock().expect("mutex acquired").clear();
+		self.inner.shutdown_with_timeout(timeout)
+	}
+
+	fn set_resource(&mut self, resource: &Resource) {
+		self.inner.set_resource(resource)
+	}
+}
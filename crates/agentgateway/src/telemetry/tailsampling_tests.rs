@@ -0,0 +1,130 @@
+use opentelemetry::trace::Status;
+
+use super::*;
+
+fn config(cfg: serde_json::Value) -> Config {
+	serde_json::from_value(cfg).unwrap()
+}
+
+fn summary(attributes: &[KeyValue]) -> Summary {
+	let mut s = Summary::default();
+	s.add(attributes, &Status::Unset);
+	s
+}
+
+#[test]
+fn test_config_defaults() {
+	let cfg = config(serde_json::json!({"errors": true}));
+	assert_eq!(cfg.decision_wait, Duration::from_secs(30));
+	assert_eq!(cfg.max_traces, 10_000);
+	assert!(cfg.routes.is_empty());
+}
+
+#[test]
+fn test_summary() {
+	let s = summary(&[
+		KeyValue::new("route", "default/chat"),
+		KeyValue::new("http.status", 503i64),
+		KeyValue::new("mcp.resource.type", "tool"),
+		KeyValue::new("mcp.resource.name", "search"),
+	]);
+	assert!(s.error);
+	assert_eq!(s.route.as_deref(), Some("default/chat"));
+	assert_eq!(s.tools, vec!["search".to_string()]);
+
+	let s = summary(&[
+		KeyValue::new("http.status", 404i64),
+		KeyValue::new("mcp.resource.type", "prompt"),
+		KeyValue::new("mcp.resource.name", "search"),
+	]);
+	assert!(!s.error);
+	assert!(s.tools.is_empty());
+
+	assert!(summary(&[KeyValue::new("error", "connection refused")]).error);
+
+	let mut s = Summary::default();
+	s.add(&[], &Status::error("failed"));
+	assert!(s.error);
+}
+
+#[test]
+fn test_keep() {
+	let cfg = config(serde_json::json!({
+		"errors": true,
+		"latencyThreshold": "1s",
+		"routes": ["default/debug"],
+		"tools": ["deploy"],
+	}));
+	assert!(!cfg.keep(&Summary::default()));
+	assert!(cfg.keep(&Summary {
+		error: true,
+		..Default::default()
+	}));
+	assert!(cfg.keep(&Summary {
+		duration: Duration::from_secs(1),
+		..Default::default()
+	}));
+	assert!(!cfg.keep(&Summary {
+		duration: Duration::from_millis(999),
+		route: Some("default/other".to_string()),
+		..Default::default()
+	}));
+	assert!(cfg.keep(&Summary {
+		route: Some("default/debug".to_string()),
+		..Default::default()
+	}));
+	assert!(cfg.keep(&Summary {
+		tools: vec!["list".to_string(), "deploy".to_string()],
+		..Default::default()
+	}));
+
+	// Nothing is kept without a policy
+	let none = config(serde_json::json!({}));
+	assert!(!none.keep(&Summary {
+		error: true,
+		duration: Duration::from_secs(60),
+		..Default::default()
+	}));
+}
+
+#[test]
+fn test_buffer_expire() {
+	let wait = Duration::from_secs(30);
+	let start = Instant::now();
+	let id = |i: u8| TraceId::from_bytes([i; 16]);
+	let mut b = Buffer::default();
+	assert!(b.get_or_insert(id(1), start, 2).is_some());
+	assert!(
+		b.get_or_insert(id(2), start + Duration::from_secs(10), 2)
+			.is_some()
+	);
+	// Full
+	assert!(
+		b.get_or_insert(id(3), start + Duration::from_secs(10), 2)
+			.is_none()
+	);
+	// Existing traces can still be added to
+	assert!(
+		b.get_or_insert(id(1), start + Duration::from_secs(10), 2)
+			.is_some()
+	);
+
+	b.expire(start + Duration::from_secs(29), wait);
+	assert_eq!(b.traces.len(), 2);
+	// Only the trace that exceeded the decision wait is expired, without the buffer being full
+	b.expire(start + Duration::from_secs(30), wait);
+	assert!(!b.traces.contains_key(&id(1)));
+	assert!(b.traces.contains_key(&id(2)));
+
+	// A completed trace that is buffered again is not expired by its earlier entry
+	assert!(b.remove(&id(2)).is_some());
+	assert!(
+		b.get_or_insert(id(2), start + Duration::from_secs(35), 2)
+			.is_some()
+	);
+	b.expire(start + Duration::from_secs(40), wait);
+	assert!(b.traces.contains_key(&id(2)));
+	b.expire(start + Duration::from_secs(65), wait);
+	assert!(b.traces.is_empty());
+	assert!(b.created.is_empty());
+}
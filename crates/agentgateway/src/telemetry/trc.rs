@@ -7,17 +7,27 @@ use agent_core::telemetry::ValueBag;
 use http::Version;
 use itertools::Itertools;
 use once_cell::sync::OnceCell;
-use opentelemetry::trace::{Span, SpanContext, SpanKind, TraceState, Tracer as _, TracerProvider};
-use opentelemetry::{Key, KeyValue, TraceFlags};
+use opentelemetry::trace::{
+	Span, SpanContext, SpanKind, TraceContextExt, TraceState, Tracer as _, TracerProvider,
+};
+use opentelemetry::{Context, Key, KeyValue, TraceFlags};
 use opentelemetry_otlp::{WithExportConfig, WithHttpConfig};
 use opentelemetry_sdk::Resource;
-use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::trace::{
+	BatchSpanProcessor, SdkTracerProvider, SpanExporter, TracerProviderBuilder,
+};
 pub use traceparent::TraceParent;
 
 use crate::cel;
+use crate::telemetry::genai;
 use crate::telemetry::log::{CelLoggingExecutor, LoggingFields, RequestLog};
+use crate::telemetry::tailsampling::{self, TailSamplingProcessor};
 use crate::types::agent::{SimpleBackendReference, TracingConfig};
 
+#[cfg(test)]
+#[path = "trc_tests.rs"]
+mod tests;
+
 #[derive(Clone, Debug)]
 pub struct Tracer {
 	pub tracer: Arc<opentelemetry_sdk::trace::SdkTracer>,
@@ -34,6 +44,24 @@ pub enum Protocol {
 	Http,
 }
 
+/// Header formats used to propagate trace context to backends. Incoming requests are accepted in
+/// any of these formats.
+#[derive(serde::Serialize, serde::Deserialize, Copy, Eq, PartialEq, Clone, Debug)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schema", derive(crate::JsonSchema))]
+pub enum Propagation {
+	/// W3C `traceparent` header.
+	TraceContext,
+	/// Single `b3` header.
+	B3,
+	/// Multiple `X-B3-*` headers.
+	B3Multi,
+}
+
+pub fn default_propagation() -> Vec<Propagation> {
+	vec![Propagation::TraceContext]
+}
+
 #[derive(serde::Serialize, Clone, Debug)]
 pub struct Config {
 	pub endpoint: Option<String>,
@@ -43,6 +71,8 @@ pub struct Config {
 	pub random_sampling: Option<Arc<cel::Expression>>,
 	pub client_sampling: Option<Arc<cel::Expression>>,
 	pub path: String,
+	pub propagation: Vec<Propagation>,
+	pub tail_sampling: Option<tailsampling::Config>,
 }
 
 mod semconv {
@@ -126,10 +156,12 @@ impl Tracer {
 				Arc::new(config.provider_backend.clone()),
 				exporter_runtime.clone(),
 			);
-			opentelemetry_sdk::trace::SdkTracerProvider::builder()
-				.with_resource(resource.clone())
-				.with_batch_exporter(exporter)
-				.build()
+			with_exporter(
+				SdkTracerProvider::builder().with_resource(resource.clone()),
+				exporter,
+				config.tail_sampling.as_ref(),
+			)
+			.build()
 		} else {
 			// Use HTTP exporter via PolicyClient by default.
 			// Resolve the OTLP/HTTP path from global defaults; if not set, use the per-policy path (default "/v1/traces").
@@ -149,16 +181,16 @@ impl Tracer {
 				backend_ref: config.provider_backend.clone(),
 				runtime: exporter_runtime,
 			};
-			opentelemetry_sdk::trace::SdkTracerProvider::builder()
-				.with_resource(resource.clone())
-				.with_batch_exporter(
-					opentelemetry_otlp::SpanExporter::builder()
-						.with_http()
-						.with_http_client(http_client)
-						.with_endpoint(endpoint_path)
-						.build()?,
-				)
-				.build()
+			with_exporter(
+				SdkTracerProvider::builder().with_resource(resource.clone()),
+				opentelemetry_otlp::SpanExporter::builder()
+					.with_http()
+					.with_http_client(http_client)
+					.with_endpoint(endpoint_path)
+					.build()?,
+				config.tail_sampling.as_ref(),
+			)
+			.build()
 		};
 		let tracer = provider.tracer(tracer_name);
 		Ok(Tracer {
@@ -172,57 +204,41 @@ impl Tracer {
 		let Some(ep) = &cfg.endpoint else {
 			return Ok(None);
 		};
-		// Apply global defaults (gateway-derived if initialized)
-		let defaults = GLOBAL_RESOURCE_DEFAULTS.get();
-		let result = opentelemetry_sdk::trace::SdkTracerProvider::builder()
-			.with_resource({
-				let mut rb = Resource::builder()
-					.with_service_name(
-						defaults
-							.and_then(|d| d.service_name.clone())
-							.unwrap_or_else(|| "agentgateway".to_string()),
-					)
-					.with_attribute(KeyValue::new(
-						"service.version",
-						agent_core::version::BuildInfo::new().version,
-					));
-				if let Some(d) = defaults {
-					for kv in &d.attrs {
-						rb = rb.with_attribute(kv.clone());
-					}
-				}
-				rb.build()
-			})
-			// TODO: this should be integrated with PolicyClient
-			.with_batch_exporter(if cfg.protocol == Protocol::Grpc {
-				// TODO: otel is using an old tonic version that mismatches with the one we have
-				// let metadata = MetadataMap::from_headers(HeaderMap::from_iter(
-				// 	cfg
-				// 		.headers
-				// 		.clone()
-				// 		.into_iter()
-				// 		.map(|(k, v)| Ok((HeaderName::try_from(k)?, HeaderValue::try_from(v)?)))
-				// 		.collect::<Result<_, _>>()?
-				// 		.iter(),
-				// ));
-				opentelemetry_otlp::SpanExporter::builder()
-					.with_tonic()
-					.with_endpoint(ep)
-					// .with_metadata(metadata)
-					.build()?
-			} else {
-				opentelemetry_otlp::SpanExporter::builder()
-					.with_http()
-					// For HTTP, we add the suffix ourselves
-					.with_endpoint(format!(
-						"{}/{}",
-						ep.strip_suffix("/").unwrap_or(ep),
-						cfg.path.clone()
-					))
-					.with_headers(cfg.headers.clone())
-					.build()?
-			})
-			.build();
+		// TODO: this should be integrated with PolicyClient
+		let exporter = if cfg.protocol == Protocol::Grpc {
+			// TODO: otel is using an old tonic version that mismatches with the one we have
+			// let metadata = MetadataMap::from_headers(HeaderMap::from_iter(
+			// 	cfg
+			// 		.headers
+			// 		.clone()
+			// 		.into_iter()
+			// 		.map(|(k, v)| Ok((HeaderName::try_from(k)?, HeaderValue::try_from(v)?)))
+			// 		.collect::<Result<_, _>>()?
+			// 		.iter(),
+			// ));
+			opentelemetry_otlp::SpanExporter::builder()
+				.with_tonic()
+				.with_endpoint(ep)
+				// .with_metadata(metadata)
+				.build()?
+		} else {
+			opentelemetry_otlp::SpanExporter::builder()
+				.with_http()
+				// For HTTP, we add the suffix ourselves
+				.with_endpoint(format!(
+					"{}/{}",
+					ep.strip_suffix("/").unwrap_or(ep),
+					cfg.path.clone()
+				))
+				.with_headers(cfg.headers.clone())
+				.build()?
+		};
+		let result = with_exporter(
+			SdkTracerProvider::builder().with_resource(default_resource()),
+			exporter,
+			cfg.tail_sampling.as_ref(),
+		)
+		.build();
 		let tracer = result.tracer("agentgateway");
 		Ok(Some(Tracer {
 			tracer: Arc::new(tracer),
@@ -240,6 +256,7 @@ impl Tracer {
 		request: &RequestLog,
 		cel_exec: &CelLoggingExecutor,
 		attrs: &[(&str, Option<ValueBag<'v>>)],
+		genai: Vec<genai::Operation>,
 	) {
 		let mut attributes = attrs
 			.iter()
@@ -253,6 +270,7 @@ impl Tracer {
 		}
 		let end = SystemTime::now();
 		let elapsed = request.tcp_info.start.elapsed();
+		let start = end.sub(elapsed);
 
 		// For now we only accept HTTP(?)
 		attributes.push(KeyValue::new(semconv::URL_SCHEME.clone(), "http"));
@@ -291,31 +309,62 @@ impl Tracer {
 		});
 
 		let out_span = request.outgoing_span.as_ref().unwrap();
-		let mut sb = self
+		let sb = self
 			.tracer
 			.span_builder(span_name)
-			.with_start_time(end.sub(elapsed))
+			.with_start_time(start)
 			.with_end_time(SystemTime::now())
 			.with_kind(SpanKind::Server)
 			.with_attributes(attributes)
 			.with_trace_id(out_span.trace_id.into())
 			.with_span_id(out_span.span_id.into());
 
+		// Parent the span to the caller's span, so the client, gateway, and backend spans show up as a
+		// single trace.
+		let mut cx = Context::new();
 		if let Some(in_span) = &request.incoming_span {
-			let parent = SpanContext::new(
+			cx = cx.with_remote_span_context(SpanContext::new(
 				in_span.trace_id.into(),
 				in_span.span_id.into(),
 				TraceFlags::new(in_span.flags),
 				true,
 				TraceState::default(),
-			);
-			sb = sb.with_links(vec![opentelemetry::trace::Link::new(
-				parent.clone(),
-				vec![],
-				0,
-			)]);
+			));
+		}
+		sb.start_with_context(self.tracer.as_ref(), &cx).end();
+
+		// GenAI operations are recorded as children of the request span.
+		let cx = Context::new().with_remote_span_context(SpanContext::new(
+			out_span.trace_id.into(),
+			out_span.span_id.into(),
+			TraceFlags::new(out_span.flags),
+			false,
+			TraceState::default(),
+		));
+		for op in genai {
+			self
+				.tracer
+				.span_builder(op.name)
+				.with_start_time(start)
+				.with_end_time(SystemTime::now())
+				.with_kind(op.kind)
+				.with_attributes(op.attributes)
+				.start_with_context(self.tracer.as_ref(), &cx)
+				.end()
 		}
-		sb.start(self.tracer.as_ref()).end()
+	}
+}
+
+/// Export spans through a batch processor, applying tail sampling first if configured.
+fn with_exporter<E: SpanExporter + 'static>(
+	builder: TracerProviderBuilder,
+	exporter: E,
+	tail_sampling: Option<&tailsampling::Config>,
+) -> TracerProviderBuilder {
+	let batch = BatchSpanProcessor::builder(exporter).build();
+	match tail_sampling {
+		Some(cfg) => builder.with_span_processor(TailSamplingProcessor::new(cfg.clone(), batch)),
+		None => builder.with_span_processor(batch),
 	}
 }
 
@@ -519,6 +568,27 @@ fn from_span_data(
 	}]
 }
 
+/// The resource describing this gateway, with global defaults applied (gateway-derived if initialized).
+pub fn default_resource() -> Resource {
+	let defaults = GLOBAL_RESOURCE_DEFAULTS.get();
+	let mut rb = Resource::builder()
+		.with_service_name(
+			defaults
+				.and_then(|d| d.service_name.clone())
+				.unwrap_or_else(|| "agentgateway".to_string()),
+		)
+		.with_attribute(KeyValue::new(
+			"service.version",
+			agent_core::version::BuildInfo::new().version,
+		));
+	if let Some(d) = defaults {
+		for kv in &d.attrs {
+			rb = rb.with_attribute(kv.clone());
+		}
+	}
+	rb.build()
+}
+
 /// Initialize defaults using gateway name/namespace from config
 pub fn set_resource_defaults_from_config(cfg: &crate::Config) {
 	let pm = &cfg.proxy_metadata;
@@ -589,6 +659,7 @@ mod traceparent {
 
 	use rand::Rng;
 
+	use super::Propagation;
 	use crate::http::Request;
 
 	/// Represents a traceparent, as defined by https://www.w3.org/TR/trace-context/
@@ -601,6 +672,22 @@ mod traceparent {
 	}
 
 	pub const TRACEPARENT_HEADER: &str = "traceparent";
+	pub const B3_HEADER: &str = "b3";
+	pub const B3_TRACE_ID_HEADER: &str = "x-b3-traceid";
+	pub const B3_SPAN_ID_HEADER: &str = "x-b3-spanid";
+	pub const B3_PARENT_SPAN_ID_HEADER: &str = "x-b3-parentspanid";
+	pub const B3_SAMPLED_HEADER: &str = "x-b3-sampled";
+	pub const B3_FLAGS_HEADER: &str = "x-b3-flags";
+
+	const PROPAGATION_HEADERS: [&str; 7] = [
+		TRACEPARENT_HEADER,
+		B3_HEADER,
+		B3_TRACE_ID_HEADER,
+		B3_SPAN_ID_HEADER,
+		B3_PARENT_SPAN_ID_HEADER,
+		B3_SAMPLED_HEADER,
+		B3_FLAGS_HEADER,
+	];
 
 	impl Default for TraceParent {
 		fn default() -> Self {
@@ -618,16 +705,81 @@ mod traceparent {
 				flags: 0,
 			}
 		}
-		pub fn insert_header(&self, req: &mut Request) {
-			let hv = hyper::header::HeaderValue::from_bytes(format!("{self:?}").as_bytes()).unwrap();
-			req.headers_mut().insert(TRACEPARENT_HEADER, hv);
+		/// Replace any trace context headers on the request with this span, in each of the
+		/// `propagation` formats.
+		pub fn insert_headers(&self, req: &mut Request, propagation: &[Propagation]) {
+			let headers = req.headers_mut();
+			for h in PROPAGATION_HEADERS {
+				headers.remove(h);
+			}
+			let sampled = if self.is_sampled() { "1" } else { "0" };
+			for p in propagation {
+				match p {
+					Propagation::TraceContext => {
+						headers.insert(TRACEPARENT_HEADER, header_value(format!("{self:?}")));
+					},
+					Propagation::B3 => {
+						let v = format!("{}-{}-{sampled}", self.trace_id(), self.span_id());
+						headers.insert(B3_HEADER, header_value(v));
+					},
+					Propagation::B3Multi => {
+						headers.insert(B3_TRACE_ID_HEADER, header_value(self.trace_id()));
+						headers.insert(B3_SPAN_ID_HEADER, header_value(self.span_id()));
+						headers.insert(B3_SAMPLED_HEADER, header_value(sampled.to_string()));
+					},
+				}
+			}
 		}
+		/// Extract the caller's span from the request. The W3C `traceparent` header takes precedence,
+		/// followed by the single `b3` header, and then the `X-B3-*` headers.
 		pub fn from_request(req: &Request) -> Option<Self> {
-			req
-				.headers()
-				.get(TRACEPARENT_HEADER)
-				.and_then(|b| b.to_str().ok())
-				.and_then(|b| TraceParent::try_from(b).ok())
+			let header = |name: &str| req.headers().get(name).and_then(|b| b.to_str().ok());
+			if let Some(tp) = header(TRACEPARENT_HEADER).and_then(|b| TraceParent::try_from(b).ok()) {
+				return Some(tp);
+			}
+			if let Some(tp) = header(B3_HEADER).and_then(Self::from_b3) {
+				return Some(tp);
+			}
+			Self::from_b3_multi(
+				header(B3_TRACE_ID_HEADER)?,
+				header(B3_SPAN_ID_HEADER)?,
+				header(B3_SAMPLED_HEADER),
+				header(B3_FLAGS_HEADER),
+			)
+		}
+		/// Parse a single `b3` header: `{trace id}-{span id}[-{sampling state}[-{parent span id}]]`.
+		/// A header carrying only a sampling decision has no span to continue, so is ignored.
+		fn from_b3(value: &str) -> Option<Self> {
+			let mut segs = value.split('-');
+			let trace_id = parse_b3_trace_id(segs.next()?)?;
+			let span_id = parse_b3_span_id(segs.next()?)?;
+			let flags = match segs.next() {
+				None => 1,
+				Some("1") | Some("d") => 1,
+				Some("0") => 0,
+				Some(_) => return None,
+			};
+			Some(Self {
+				version: 0,
+				trace_id,
+				span_id,
+				flags,
+			})
+		}
+		fn from_b3_multi(
+			trace_id: &str,
+			span_id: &str,
+			sampled: Option<&str>,
+			flags: Option<&str>,
+		) -> Option<Self> {
+			// Debug (`X-B3-Flags: 1`) implies sampled. A missing decision is treated as sampled.
+			let sampled = flags == Some("1") || !matches!(sampled, Some("0") | Some("false"));
+			Some(Self {
+				version: 0,
+				trace_id: parse_b3_trace_id(trace_id)?,
+				span_id: parse_b3_span_id(span_id)?,
+				flags: u8::from(sampled),
+			})
 		}
 		pub fn new_span(&self) -> Self {
 			let mut rng = rand::rng();
@@ -646,6 +798,25 @@ mod traceparent {
 		}
 	}
 
+	fn header_value(v: String) -> hyper::header::HeaderValue {
+		hyper::header::HeaderValue::try_from(v).expect("hex encoded trace context is a valid header")
+	}
+
+	/// B3 trace ids are 64 or 128 bits. 64 bit ids are left padded with zeros.
+	fn parse_b3_trace_id(s: &str) -> Option<u128> {
+		if s.len() != 16 && s.len() != 32 {
+			return None;
+		}
+		u128::from_str_radix(s, 16).ok().filter(|id| *id != 0)
+	}
+
+	fn parse_b3_span_id(s: &str) -> Option<u64> {
+		if s.len() != 16 {
+			return None;
+		}
+		u64::from_str_radix(s, 16).ok().filter(|id| *id != 0)
+	}
+
 	impl fmt::Debug for TraceParent {
 		fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 			write!(
@@ -0,0 +1,101 @@
+use super::*;
+
+fn request(headers: &[(&str, &str)]) -> crate::http::Request {
+	let mut req = crate::http::Request::new(crate::http::Body::empty());
+	for (k, v) in headers {
+		req
+			.headers_mut()
+			.insert(*k, http::HeaderValue::from_str(v).unwrap());
+	}
+	req
+}
+
+#[test]
+fn test_extract_traceparent() {
+	let req = request(&[
+		(
+			"traceparent",
+			"00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+		),
+		("b3", "80f198ee56343ba864fe8b2a57d3eff7-e457b5a2e4d86bd1-1"),
+	]);
+	let tp = TraceParent::from_request(&req).unwrap();
+	assert_eq!(tp.trace_id(), "4bf92f3577b34da6a3ce929d0e0e4736");
+	assert_eq!(tp.span_id(), "00f067aa0ba902b7");
+	assert!(tp.is_sampled());
+}
+
+#[test]
+fn test_extract_b3() {
+	let req = request(&[("b3", "80f198ee56343ba864fe8b2a57d3eff7-e457b5a2e4d86bd1-0")]);
+	let tp = TraceParent::from_request(&req).unwrap();
+	assert_eq!(tp.trace_id(), "80f198ee56343ba864fe8b2a57d3eff7");
+	assert_eq!(tp.span_id(), "e457b5a2e4d86bd1");
+	assert!(!tp.is_sampled());
+
+	// 64 bit trace ids are padded
+	let req = request(&[("b3", "64fe8b2a57d3eff7-e457b5a2e4d86bd1-d-05e3ac9a4f6e3b90")]);
+	let tp = TraceParent::from_request(&req).unwrap();
+	assert_eq!(tp.trace_id(), "000000000000000064fe8b2a57d3eff7");
+	assert!(tp.is_sampled());
+
+	// A sampling decision alone has no span to continue
+	assert!(TraceParent::from_request(&request(&[("b3", "1")])).is_none());
+}
+
+#[test]
+fn test_extract_b3_multi() {
+	let req = request(&[
+		("x-b3-traceid", "80f198ee56343ba864fe8b2a57d3eff7"),
+		("x-b3-spanid", "e457b5a2e4d86bd1"),
+		("x-b3-sampled", "0"),
+	]);
+	let tp = TraceParent::from_request(&req).unwrap();
+	assert_eq!(tp.span_id(), "e457b5a2e4d86bd1");
+	assert!(!tp.is_sampled());
+
+	let req = request(&[
+		("x-b3-traceid", "80f198ee56343ba864fe8b2a57d3eff7"),
+		("x-b3-spanid", "e457b5a2e4d86bd1"),
+		("x-b3-sampled", "0"),
+		("x-b3-flags", "1"),
+	]);
+	assert!(TraceParent::from_request(&req).unwrap().is_sampled());
+
+	// Both ids are required
+	let req = request(&[("x-b3-traceid", "80f198ee56343ba864fe8b2a57d3eff7")]);
+	assert!(TraceParent::from_request(&req).is_none());
+}
+
+#[test]
+fn test_inject() {
+	let mut req = request(&[
+		("b3", "80f198ee56343ba864fe8b2a57d3eff7-e457b5a2e4d86bd1-1"),
+		("x-b3-parentspanid", "05e3ac9a4f6e3b90"),
+	]);
+	let tp = TraceParent::from_request(&req).unwrap().new_span();
+	tp.insert_headers(
+		&mut req,
+		&[
+			Propagation::TraceContext,
+			Propagation::B3,
+			Propagation::B3Multi,
+		],
+	);
+	let h = req.headers();
+	assert_eq!(h["traceparent"], format!("{tp:?}"));
+	assert_eq!(
+		h["b3"],
+		format!("80f198ee56343ba864fe8b2a57d3eff7-{}-1", tp.span_id())
+	);
+	assert_eq!(h["x-b3-traceid"], "80f198ee56343ba864fe8b2a57d3eff7");
+	assert_eq!(h["x-b3-spanid"], tp.span_id());
+	assert_eq!(h["x-b3-sampled"], "1");
+	// Stale context from the caller is not forwarded
+	assert!(h.get("x-b3-parentspanid").is_none());
+
+	tp.insert_headers(&mut req, &default_propagation());
+	assert!(req.headers().get("b3").is_none());
+	assert!(req.headers().get("x-b3-traceid").is_none());
+	assert_eq!(TraceParent::from_request(&req), Some(tp));
+}
@@ -0,0 +1,326 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use macro_rules_attribute::apply;
+
+use crate::ProxyInputs;
+use crate::http::auth::{AwsAuth, AwsService, BackendAuth};
+use crate::llm::bedrock::AwsRegion;
+use crate::llm::policy::de_from_local_backend_policy;
+use crate::proxy::httpproxy::PolicyClient;
+use crate::telemetry::costs::{self, BUCKET_SECONDS, ReportRow};
+use crate::types::agent::{BackendPolicy, ResourceName, SimpleBackend, Target};
+use crate::types::local::LocalBackendPolicies;
+use crate::*;
+
+#[cfg(test)]
+#[path = "usage_export_tests.rs"]
+mod tests;
+
+/// How long to wait before retrying a failed export.
+const RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+const CSV_HEADER: &str =
+	"start,end,consumer,team,route,model,requests,inputTokens,outputTokens,cost";
+
+/// Export usage records for billing systems. Once each hour ends, the requests, tokens, and cost
+/// of every consumer, team, route, and model with usage in it are exported as one record each.
+/// Hours without usage are skipped, and failed exports are retried until the hour passes the
+/// cost retention period.
+#[apply(schema!)]
+pub struct Config {
+	#[serde(default)]
+	pub format: Format,
+	pub sink: Sink,
+}
+
+#[apply(schema!)]
+#[derive(Default, Copy, PartialEq, Eq)]
+pub enum Format {
+	/// One JSON object per line.
+	#[default]
+	Json,
+	/// Comma separated values, with a header row.
+	Csv,
+}
+
+impl Format {
+	fn extension(&self) -> &'static str {
+		match self {
+			Format::Json => "jsonl",
+			Format::Csv => "csv",
+		}
+	}
+
+	fn content_type(&self) -> &'static str {
+		match self {
+			Format::Json => "application/x-ndjson",
+			Format::Csv => "text/csv",
+		}
+	}
+}
+
+#[apply(schema!)]
+pub enum Sink {
+	/// Append records to a local file.
+	File(FileSink),
+	/// Upload each hour's records to an S3 bucket, as an object named after the hour.
+	S3(S3Sink),
+	/// POST each hour's records to an HTTP endpoint.
+	Http(HttpSink),
+}
+
+#[apply(schema!)]
+pub struct FileSink {
+	pub path: PathBuf,
+}
+
+#[apply(schema!)]
+pub struct S3Sink {
+	pub bucket: String,
+	pub region: String,
+	/// Prefix for object keys, such as `usage/`. Keys end with the hour, such as
+	/// `20260101T100000Z.jsonl`.
+	#[serde(default, skip_serializing_if = "String::is_empty")]
+	pub prefix: String,
+	/// Policies for calling S3. Unless `backendAuth` is set, requests are signed with AWS
+	/// credentials from the environment.
+	#[serde(default, deserialize_with = "de_from_local_backend_policy")]
+	#[cfg_attr(feature = "schema", schemars(with = "LocalBackendPolicies"))]
+	pub policies: Vec<BackendPolicy>,
+}
+
+#[apply(schema!)]
+pub struct HttpSink {
+	/// The URL to send records to, such as `https://billing.example.com/usage`.
+	pub url: String,
+	/// Policies for calling the endpoint, such as `backendAuth`.
+	#[serde(default, deserialize_with = "de_from_local_backend_policy")]
+	#[cfg_attr(feature = "schema", schemars(with = "LocalBackendPolicies"))]
+	pub policies: Vec<BackendPolicy>,
+}
+
+/// The usage of one attribution over one hour.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageRecord {
+	pub start: DateTime<Utc>,
+	pub end: DateTime<Utc>,
+	#[serde(flatten)]
+	pub usage: ReportRow,
+}
+
+/// Start exporting usage once each hour ends.
+pub fn spawn(cfg: Config, inputs: Arc<ProxyInputs>) -> anyhow::Result<()> {
+	if let Sink::Http(h) = &cfg.sink {
+		h.target()?;
+	}
+	tokio::spawn(run(cfg, PolicyClient { inputs }));
+	Ok(())
+}
+
+async fn run(cfg: Config, client: PolicyClient) {
+	// Usage from before the gateway started is not recorded, so start with the current hour.
+	let mut next = Utc::now().timestamp().div_euclid(BUCKET_SECONDS) * BUCKET_SECONDS;
+	loop {
+		let end = next + BUCKET_SECONDS;
+		let now = Utc::now().timestamp();
+		if now < end {
+			tokio::time::sleep(Duration::from_secs((end - now) as u64)).await;
+			continue;
+		}
+		if let Err(e) = export(&cfg, &client, next).await {
+			warn!("failed to export usage for the hour starting {next}: {e}");
+			tokio::time::sleep(RETRY_INTERVAL).await;
+			continue;
+		}
+		next = end;
+	}
+}
+
+async fn export(cfg: &Config, client: &PolicyClient, start: i64) -> anyhow::Result<()> {
+	let records = records(start, costs::bucket(start).unwrap_or_default());
+	if records.is_empty() {
+		return Ok(());
+	}
+	debug!(start, records = records.len(), "exporting usage");
+	match &cfg.sink {
+		Sink::File(f) => f.write(cfg.format, &records),
+		Sink::S3(s) => s.upload(client, cfg.format, start, &records).await,
+		Sink::Http(h) => h.post(client, cfg.format, &records).await,
+	}
+}
+
+fn records(start: i64, rows: Vec<ReportRow>) -> Vec<UsageRecord> {
+	let (Some(start), Some(end)) = (
+		DateTime::from_timestamp(start, 0),
+		DateTime::from_timestamp(start + BUCKET_SECONDS, 0),
+	) else {
+		return vec![];
+	};
+	rows
+		.into_iter()
+		.map(|usage| UsageRecord { start, end, usage })
+		.collect()
+}
+
+/// Encode records, with a header row for CSV if `header` is set.
+fn encode(format: Format, records: &[UsageRecord], header: bool) -> anyhow::Result<Vec<u8>> {
+	let mut out = Vec::new();
+	match format {
+		Format::Json => {
+			for r in records {
+				serde_json::to_writer(&mut out, r)?;
+				out.push(b'\n');
+			}
+		},
+		Format::Csv => {
+			if header {
+				writeln!(out, "{CSV_HEADER}")?;
+			}
+			for r in records {
+				let a = &r.usage.attribution;
+				let s = &r.usage.spend;
+				writeln!(
+					out,
+					"{},{},{},{},{},{},{},{},{},{}",
+					r.start.to_rfc3339_opts(SecondsFormat::Secs, true),
+					r.end.to_rfc3339_opts(SecondsFormat::Secs, true),
+					csv_field(a.consumer.as_deref()),
+					csv_field(a.team.as_deref()),
+					csv_field(a.route.as_deref()),
+					csv_field(a.model.as_deref()),
+					s.requests,
+					s.input_tokens,
+					s.output_tokens,
+					s.cost,
+				)?;
+			}
+		},
+	}
+	Ok(out)
+}
+
+/// Quote a CSV field if it contains a separator, quote, or line break.
+fn csv_field(v: Option<&str>) -> String {
+	let v = v.unwrap_or_default();
+	if v.contains([',', '"', '\n', '\r']) {
+		format!("\"{}\"", v.replace('"', "\"\""))
+	} else {
+		v.to_string()
+	}
+}
+
+impl FileSink {
+	fn write(&self, format: Format, records: &[UsageRecord]) -> anyhow::Result<()> {
+		let mut file = OpenOptions::new()
+			.create(true)
+			.append(true)
+			.open(&self.path)?;
+		let empty = file.metadata()?.len() == 0;
+		file.write_all(&encode(format, records, empty)?)?;
+		Ok(())
+	}
+}
+
+impl S3Sink {
+	async fn upload(
+		&self,
+		client: &PolicyClient,
+		format: Format,
+		start: i64,
+		records: &[UsageRecord],
+	) -> anyhow::Result<()> {
+		let host = format!("{}.s3.{}.amazonaws.com", self.bucket, self.region);
+		let key = format!(
+			"{}{}.{}",
+			self.prefix,
+			records[0].start.format("%Y%m%dT%H%M%SZ"),
+			format.extension()
+		);
+		let mut pols = vec![BackendPolicy::BackendTLS(
+			crate::http::backendtls::SYSTEM_TRUST.clone(),
+		)];
+		if !self
+			.policies
+			.iter()
+			.any(|p| matches!(p, BackendPolicy::BackendAuth(_)))
+		{
+			pols.push(BackendPolicy::BackendAuth(BackendAuth::Aws(
+				AwsAuth::Implicit {},
+			)));
+		}
+		pols.extend(self.policies.iter().cloned());
+		let req = ::http::Request::builder()
+			.uri(format!("https://{host}/{key}"))
+			.method(::http::Method::PUT)
+			.header(::http::header::CONTENT_TYPE, format.content_type())
+			.extension(AwsRegion {
+				region: self.region.clone(),
+			})
+			.extension(AwsService("s3"))
+			.body(crate::http::Body::from(encode(format, records, true)?))?;
+		let be = SimpleBackend::Opaque(
+			ResourceName::new(strng::literal!("_usage-export"), strng::literal!("")),
+			Target::Hostname(host.into(), 443),
+		);
+		let resp = client.call_with_explicit_policies(req, be, pols).await?;
+		anyhow::ensure!(
+			resp.status().is_success(),
+			"S3 returned {} for {key} (hour {start})",
+			resp.status()
+		);
+		Ok(())
+	}
+}
+
+impl HttpSink {
+	/// The target of the URL, and whether it uses TLS.
+	fn target(&self) -> anyhow::Result<(Target, bool)> {
+		let uri: ::http::Uri = self.url.parse()?;
+		let tls = match uri.scheme_str() {
+			Some("https") => true,
+			Some("http") => false,
+			_ => anyhow::bail!("usage export url must be http or https: {}", self.url),
+		};
+		let host = uri
+			.host()
+			.ok_or_else(|| anyhow::anyhow!("usage export url has no host: {}", self.url))?;
+		let port = uri.port_u16().unwrap_or(if tls { 443 } else { 80 });
+		Ok(((host, port).try_into()?, tls))
+	}
+
+	async fn post(
+		&self,
+		client: &PolicyClient,
+		format: Format,
+		records: &[UsageRecord],
+	) -> anyhow::Result<()> {
+		let (target, tls) = self.target()?;
+		let mut pols = Vec::new();
+		if tls {
+			pols.push(BackendPolicy::BackendTLS(
+				crate::http::backendtls::SYSTEM_TRUST.clone(),
+			));
+		}
+		pols.extend(self.policies.iter().cloned());
+		let req = ::http::Request::builder()
+			.uri(&self.url)
+			.method(::http::Method::POST)
+			.header(::http::header::CONTENT_TYPE, format.content_type())
+			.body(crate::http::Body::from(encode(format, records, true)?))?;
+		let be = SimpleBackend::Opaque(
+			ResourceName::new(strng::literal!("_usage-export"), strng::literal!("")),
+			target,
+		);
+		let resp = client.call_with_explicit_policies(req, be, pols).await?;
+		anyhow::ensure!(
+			resp.status().is_success(),
+			"usage endpoint returned {}",
+			resp.status()
+		);
+		Ok(())
+	}
+}
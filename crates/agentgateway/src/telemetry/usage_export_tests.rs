@@ -0,0 +1,104 @@
+use super::*;
+use crate::telemetry::costs::{Attribution, Spend};
+
+fn row(consumer: &str, model: &str, requests: u64, cost: f64) -> ReportRow {
+	ReportRow {
+		attribution: Attribution {
+			consumer: Some(consumer.to_string()),
+			team: None,
+			route: Some("default/llm".to_string()),
+			model: Some(model.to_string()),
+		},
+		spend: Spend {
+			requests,
+			input_tokens: 1000,
+			output_tokens: 200,
+			cost,
+		},
+	}
+}
+
+// 2026-01-01T10:00:00Z
+const START: i64 = 1767261600;
+
+#[test]
+fn test_config() {
+	let cfg: Config = serde_json::from_value(serde_json::json!({
+		"format": "csv",
+		"sink": {"s3": {"bucket": "billing", "region": "us-east-1", "prefix": "usage/"}},
+	}))
+	.unwrap();
+	assert_eq!(cfg.format, Format::Csv);
+	assert!(matches!(cfg.sink, Sink::S3(S3Sink { ref prefix, .. }) if prefix == "usage/"));
+
+	let http = |url: &str| HttpSink {
+		url: url.to_string(),
+		policies: vec![],
+	};
+	let (target, tls) = http("https://billing.example.com/usage").target().unwrap();
+	assert_eq!(target.to_string(), "billing.example.com:443");
+	assert!(tls);
+	let (target, tls) = http("http://127.0.0.1:9000/usage").target().unwrap();
+	assert_eq!(target.to_string(), "127.0.0.1:9000");
+	assert!(!tls);
+	assert!(http("ftp://billing.example.com").target().is_err());
+}
+
+#[test]
+fn test_json() {
+	let records = records(START, vec![row("apikey:a", "gpt-4o", 3, 0.5)]);
+	let out = String::from_utf8(encode(Format::Json, &records, true).unwrap()).unwrap();
+	let lines: Vec<serde_json::Value> = out
+		.lines()
+		.map(|l| serde_json::from_str(l).unwrap())
+		.collect();
+	assert_eq!(
+		lines,
+		vec![serde_json::json!({
+			"start": "2026-01-01T10:00:00Z",
+			"end": "2026-01-01T11:00:00Z",
+			"consumer": "apikey:a",
+			"route": "default/llm",
+			"model": "gpt-4o",
+			"requests": 3,
+			"inputTokens": 1000,
+			"outputTokens": 200,
+			"cost": 0.5,
+		})]
+	);
+}
+
+#[test]
+fn test_csv() {
+	let records = records(
+		START,
+		vec![
+			row("apikey:a", "gpt-4o", 3, 0.5),
+			row("user \"b\", ops", "llama", 1, 0.0),
+		],
+	);
+	let out = String::from_utf8(encode(Format::Csv, &records, true).unwrap()).unwrap();
+	assert_eq!(
+		out,
+		"start,end,consumer,team,route,model,requests,inputTokens,outputTokens,cost\n\
+		2026-01-01T10:00:00Z,2026-01-01T11:00:00Z,apikey:a,,default/llm,gpt-4o,3,1000,200,0.5\n\
+		2026-01-01T10:00:00Z,2026-01-01T11:00:00Z,\"user \"\"b\"\", ops\",,default/llm,llama,1,1000,200,0\n"
+	);
+	let out = String::from_utf8(encode(Format::Csv, &records[..1], false).unwrap()).unwrap();
+	assert!(out.starts_with("2026-01-01T10:00:00Z"));
+}
+
+#[test]
+fn test_file_sink() {
+	let dir = tempfile::tempdir().unwrap();
+	let sink = FileSink {
+		path: dir.path().join("usage.csv"),
+	};
+	let records = records(START, vec![row("apikey:a", "gpt-4o", 3, 0.5)]);
+	sink.write(Format::Csv, &records).unwrap();
+	sink.write(Format::Csv, &records).unwrap();
+	let out = std::fs::read_to_string(&sink.path).unwrap();
+	// The header is only written to a new file
+	assert_eq!(out.lines().count(), 3);
+	assert!(out.starts_with("start,"));
+}
@@ -143,6 +143,7 @@ pub fn setup_llm_mock(
 		path_override: None,
 		tokenize,
 		policies: None,
+		models: vec![],
 	})
 	.translate()
 	.unwrap();
@@ -221,6 +222,7 @@ pub fn simple_bind(route: Route) -> Bind {
 		}]),
 		protocol: BindProtocol::http,
 		tunnel_protocol: Default::default(),
+		unix_socket: None,
 	}
 }
 
@@ -239,6 +241,7 @@ pub fn simple_tcp_bind(route: TCPRoute) -> Bind {
 		}]),
 		protocol: BindProtocol::tcp,
 		tunnel_protocol: Default::default(),
+		unix_socket: None,
 	}
 }
 
@@ -514,6 +517,7 @@ impl TestBind {
 			insecure_host: true,
 			alpn: None,
 			subject_alt_names: None,
+			..Default::default()
 		}
 		.try_into()
 		.unwrap();
@@ -612,9 +616,11 @@ pub fn setup_proxy_test(cfg: &str) -> anyhow::Result<TestBind> {
 		metrics: Arc::new(crate::metrics::Metrics::new(
 			metrics::sub_registry(&mut Registry::default()),
 			Default::default(),
+			Default::default(),
 		)),
 		upstream: client.clone(),
 		ca: None,
+		quotas: Default::default(),
 
 		mcp_state: mcp::App::new(stores.clone(), encoder),
 	});
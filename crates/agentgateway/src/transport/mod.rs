@@ -1,6 +1,7 @@
 pub mod hbone;
 mod rewind;
 pub mod stream;
+pub mod tickets;
 pub mod tls;
 
 #[derive(Debug, Clone)]
@@ -1,7 +1,7 @@
 use std::fmt::Display;
 use std::io;
 use std::io::{Error, IoSlice};
-use std::net::SocketAddr;
+use std::net::{Ipv4Addr, SocketAddr};
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
@@ -78,6 +78,14 @@ impl Metrics {
 			ctx: None,
 		}
 	}
+
+	/// Counts bytes that were copied without going through the socket.
+	pub fn count(&mut self, sent: u64, recv: u64) {
+		if let Some(counter) = &mut self.counter {
+			counter.sent(sent as usize);
+			counter.recv(recv as usize);
+		}
+	}
 }
 
 #[derive(Debug, Clone)]
@@ -140,6 +148,22 @@ impl Socket {
 		(self.ext, self.metrics, self.inner)
 	}
 
+	pub fn is_tcp(&self) -> bool {
+		matches!(self.inner, SocketType::Tcp(_))
+	}
+
+	/// The stream of a plain TCP socket, with its metrics, or the socket unchanged otherwise.
+	pub fn into_tcp(self) -> Result<(TcpStream, Metrics), Socket> {
+		match self.inner {
+			SocketType::Tcp(stream) => Ok((stream, self.metrics)),
+			inner => Err(Socket {
+				ext: self.ext,
+				inner,
+				metrics: self.metrics,
+			}),
+		}
+	}
+
 	pub fn from_rewind(ext: Extension, metrics: Metrics, socket: RewindSocket) -> Socket {
 		Self {
 			ext,
@@ -261,7 +285,7 @@ impl Socket {
 		}
 	}
 
-	pub async fn dial(target: SocketAddr, cfg: &crate::BackendConfig) -> io::Result<Socket> {
+	pub async fn dial(target: SocketAddr, cfg: &crate::types::backend::TCP) -> io::Result<Socket> {
 		let res = tokio::time::timeout(cfg.connect_timeout, TcpStream::connect(target))
 			.await
 			.map_err(|to| io::Error::new(io::ErrorKind::TimedOut, to))??;
@@ -287,9 +311,31 @@ impl Socket {
 		})
 	}
 
+	/// Create a Socket from a connection accepted on a Unix domain socket listener. Unix sockets have
+	/// no addresses, so the connection is from and to loopback.
+	#[cfg(unix)]
+	pub fn from_unix_downstream(stream: UnixStream) -> Self {
+		let mut ext = Extension::new();
+		let loopback = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0);
+		ext.insert(TCPConnectionInfo {
+			peer_addr: loopback,
+			local_addr: loopback,
+			start: Instant::now(),
+			raw_peer_addr: None,
+		});
+		Socket {
+			ext,
+			inner: SocketType::Unix(stream),
+			metrics: Metrics::with_counter(),
+		}
+	}
+
 	/// Dial a Unix domain socket
 	#[cfg(unix)]
-	pub async fn dial_unix(path: &std::path::Path, cfg: &crate::BackendConfig) -> io::Result<Socket> {
+	pub async fn dial_unix(
+		path: &std::path::Path,
+		cfg: &crate::types::backend::TCP,
+	) -> io::Result<Socket> {
 		let res = tokio::time::timeout(cfg.connect_timeout, UnixStream::connect(path))
 			.await
 			.map_err(|to| io::Error::new(io::ErrorKind::TimedOut, to))??;
@@ -298,7 +344,7 @@ impl Socket {
 	#[cfg(not(unix))]
 	pub async fn dial_unix(
 		_path: &std::path::Path,
-		_cfg: &crate::BackendConfig,
+		_cfg: &crate::types::backend::TCP,
 	) -> io::Result<Socket> {
 		Err(io::Error::new(
 			io::ErrorKind::Unsupported,
@@ -0,0 +1,187 @@
+//! Session ticket keys for TLS listeners.
+//!
+//! By default, each listener encrypts session tickets with random keys of its own, so tickets are
+//! only accepted by the replica that issued them, and not after the listener is reloaded.
+//! Configuring the keys lets every replica resume sessions started on any other, as long as they
+//! share the keys (for example, from the same secret reference).
+//!
+//! Tickets are encrypted with AES-256-GCM as `<key name> || <nonce> || <ciphertext>`, where the key
+//! name is derived from the key, so tickets issued under a previous key are still accepted after
+//! the key is rotated.
+
+use anyhow::Context;
+use aws_lc_rs::aead::{AES_256_GCM, Aad, NONCE_LEN, Nonce, RandomizedNonceKey};
+use secrecy::{ExposeSecret, SecretString};
+
+use crate::*;
+
+#[cfg(test)]
+#[path = "tickets_tests.rs"]
+mod tests;
+
+const KEY_NAME_LEN: usize = 16;
+
+#[apply(schema_de!)]
+pub struct Config {
+	/// Key used to encrypt new session tickets, as 64 hex characters (for example, from
+	/// `openssl rand -hex 32`). May be a secret reference, such as
+	/// `${secret:vault:secret/data/tls#ticketKey}`, to share it across replicas.
+	#[serde(deserialize_with = "deser_key")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub key: SecretString,
+	/// Previous keys, still accepted to resume sessions. Keep a key here for at least `lifetime`
+	/// after rotating it.
+	#[serde(default, deserialize_with = "deser_keys")]
+	#[cfg_attr(feature = "schema", schemars(with = "Vec<String>"))]
+	pub previous_keys: Vec<SecretString>,
+	/// How long clients may resume a session with a ticket. Defaults to 6h.
+	#[serde(default = "default_lifetime", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub lifetime: Duration,
+}
+
+impl serde::Serialize for Config {
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		use serde::ser::SerializeStruct;
+		let mut s = serializer.serialize_struct("Config", 3)?;
+		s.serialize_field("key", "<redacted>")?;
+		s.serialize_field("previousKeys", &self.previous_keys.len())?;
+		s.serialize_field("lifetime", &agent_core::durfmt::format(self.lifetime))?;
+		s.end()
+	}
+}
+
+fn default_lifetime() -> Duration {
+	Duration::from_secs(6 * 60 * 60)
+}
+
+fn deser_key<'de, D>(deserializer: D) -> Result<SecretString, D::Error>
+where
+	D: serde::Deserializer<'de>,
+{
+	<String as serde::Deserialize>::deserialize(deserializer).map(SecretString::from)
+}
+
+fn deser_keys<'de, D>(deserializer: D) -> Result<Vec<SecretString>, D::Error>
+where
+	D: serde::Deserializer<'de>,
+{
+	let keys = <Vec<String> as serde::Deserialize>::deserialize(deserializer)?;
+	Ok(keys.into_iter().map(SecretString::from).collect())
+}
+
+impl Config {
+	pub fn build(&self) -> anyhow::Result<Ticketer> {
+		let decode = |k: &SecretString| {
+			hex::decode(k.expose_secret().trim())
+				.map_err(|_| anyhow::anyhow!("expected 32 bytes (64 hex characters)"))
+		};
+		let current = decode(&self.key).context("session ticket key")?;
+		let previous = self
+			.previous_keys
+			.iter()
+			.enumerate()
+			.map(|(i, k)| decode(k).with_context(|| format!("previous session ticket key {i}")))
+			.collect::<anyhow::Result<Vec<_>>>()?;
+		Ticketer::new(&current, &previous, self.lifetime)
+	}
+}
+
+struct TicketKey {
+	name: [u8; KEY_NAME_LEN],
+	key: RandomizedNonceKey,
+}
+
+impl TicketKey {
+	fn new(raw: &[u8]) -> anyhow::Result<Self> {
+		if raw.len() != 32 {
+			anyhow::bail!("expected 32 bytes (64 hex characters)");
+		}
+		let key = RandomizedNonceKey::new(&AES_256_GCM, raw)
+			.map_err(|_| anyhow::anyhow!("invalid session ticket key"))?;
+		// Identify keys by a hash prefix, so the key itself is never sent.
+		let digest = aws_lc_rs::digest::digest(&aws_lc_rs::digest::SHA256, raw);
+		let mut name = [0; KEY_NAME_LEN];
+		name.copy_from_slice(&digest.as_ref()[..KEY_NAME_LEN]);
+		Ok(TicketKey { name, key })
+	}
+}
+
+/// Encrypts session tickets with the current key, and decrypts tickets encrypted with the current
+/// or any previous key.
+pub struct Ticketer {
+	current: TicketKey,
+	previous: Vec<TicketKey>,
+	lifetime: u32,
+}
+
+impl Debug for Ticketer {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Ticketer")
+			.field("key_id", &self.key_id())
+			.field("previous_keys", &self.previous.len())
+			.field("lifetime", &self.lifetime)
+			.finish()
+	}
+}
+
+impl Ticketer {
+	pub fn new(current: &[u8], previous: &[Vec<u8>], lifetime: Duration) -> anyhow::Result<Self> {
+		Ok(Ticketer {
+			current: TicketKey::new(current)?,
+			previous: previous
+				.iter()
+				.map(|k| TicketKey::new(k))
+				.collect::<anyhow::Result<_>>()?,
+			lifetime: lifetime.as_secs().try_into().unwrap_or(u32::MAX),
+		})
+	}
+
+	/// An identifier of the current key, reported in the config dump so operators can check that
+	/// replicas share it.
+	pub fn key_id(&self) -> String {
+		hex::encode(&self.current.name[..4])
+	}
+}
+
+impl rustls::server::ProducesTickets for Ticketer {
+	fn enabled(&self) -> bool {
+		true
+	}
+
+	fn lifetime(&self) -> u32 {
+		self.lifetime
+	}
+
+	fn encrypt(&self, plain: &[u8]) -> Option<Vec<u8>> {
+		let mut in_out = plain.to_vec();
+		let nonce = self
+			.current
+			.key
+			.seal_in_place_append_tag(Aad::from(&self.current.name), &mut in_out)
+			.ok()?;
+		let mut ticket = Vec::with_capacity(KEY_NAME_LEN + NONCE_LEN + in_out.len());
+		ticket.extend_from_slice(&self.current.name);
+		ticket.extend_from_slice(nonce.as_ref());
+		ticket.extend_from_slice(&in_out);
+		Some(ticket)
+	}
+
+	fn decrypt(&self, ticket: &[u8]) -> Option<Vec<u8>> {
+		if ticket.len() < KEY_NAME_LEN + NONCE_LEN {
+			return None;
+		}
+		let (name, rest) = ticket.split_at(KEY_NAME_LEN);
+		let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+		let key = std::iter::once(&self.current)
+			.chain(&self.previous)
+			.find(|k| k.name == name)?;
+		let nonce = Nonce::try_assume_unique_for_key(nonce).ok()?;
+		let mut in_out = ciphertext.to_vec();
+		let plain = key
+			.key
+			.open_in_place(nonce, Aad::from(&key.name), &mut in_out)
+			.ok()?;
+		Some(plain.to_vec())
+	}
+}
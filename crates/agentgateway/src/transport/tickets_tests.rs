@@ -0,0 +1,60 @@
+use rustls::server::ProducesTickets;
+
+use super::*;
+
+fn key(b: u8) -> Vec<u8> {
+	vec![b; 32]
+}
+
+#[test]
+fn test_round_trip() {
+	let t = Ticketer::new(&key(1), &[], Duration::from_secs(60)).unwrap();
+	assert!(t.enabled());
+	assert_eq!(t.lifetime(), 60);
+	let ticket = t.encrypt(b"session").unwrap();
+	assert_eq!(t.decrypt(&ticket).unwrap(), b"session");
+	// Nonces are random, so the same session encrypts differently
+	assert_ne!(ticket, t.encrypt(b"session").unwrap());
+
+	// Tampered or truncated tickets are rejected
+	let mut tampered = ticket.clone();
+	*tampered.last_mut().unwrap() ^= 1;
+	assert!(t.decrypt(&tampered).is_none());
+	assert!(t.decrypt(&ticket[..KEY_NAME_LEN]).is_none());
+}
+
+#[test]
+fn test_shared_and_rotated_keys() {
+	let a = Ticketer::new(&key(1), &[], Duration::from_secs(60)).unwrap();
+	let ticket = a.encrypt(b"session").unwrap();
+
+	// Another replica with the same key resumes the session
+	let b = Ticketer::new(&key(1), &[], Duration::from_secs(60)).unwrap();
+	assert_eq!(b.key_id(), a.key_id());
+	assert_eq!(b.decrypt(&ticket).unwrap(), b"session");
+
+	// After rotation, tickets under the previous key are accepted until it is dropped
+	let rotated = Ticketer::new(&key(2), &[key(1)], Duration::from_secs(60)).unwrap();
+	assert_ne!(rotated.key_id(), a.key_id());
+	assert_eq!(rotated.decrypt(&ticket).unwrap(), b"session");
+	let dropped = Ticketer::new(&key(2), &[], Duration::from_secs(60)).unwrap();
+	assert!(dropped.decrypt(&ticket).is_none());
+}
+
+#[test]
+fn test_config() {
+	let cfg: Config = serde_json::from_value(serde_json::json!({
+		"key": hex::encode(key(1)),
+		"previousKeys": [hex::encode(key(2))],
+	}))
+	.unwrap();
+	assert_eq!(cfg.lifetime, Duration::from_secs(6 * 60 * 60));
+	let t = cfg.build().unwrap();
+	assert_eq!(t.previous.len(), 1);
+	let dump = serde_json::to_value(&cfg).unwrap();
+	assert_eq!(dump["key"], "<redacted>");
+	assert_eq!(dump["previousKeys"], 1);
+
+	let short: Config = serde_json::from_value(serde_json::json!({"key": "abcd"})).unwrap();
+	assert!(short.build().is_err());
+}
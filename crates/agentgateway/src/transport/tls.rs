@@ -158,6 +158,70 @@ pub fn provider_with_cipher_suites(
 	}))
 }
 
+/// Ensure at least one of the cipher suites in the provider can be negotiated with the enabled
+/// protocol versions.
+pub fn validate_cipher_suites(
+	versions: &[&'static rustls::SupportedProtocolVersion],
+	provider: &CryptoProvider,
+) -> anyhow::Result<()> {
+	let usable = provider
+		.cipher_suites
+		.iter()
+		.any(|s| versions.iter().any(|v| v.version == s.version().version));
+	if !usable {
+		anyhow::bail!("none of the configured cipher suites are usable with the enabled TLS versions");
+	}
+	Ok(())
+}
+
+/// The effective settings of a TLS configuration, as reported in the config dump.
+#[apply(schema_ser!)]
+pub struct TLSSettings {
+	pub versions: Vec<String>,
+	pub cipher_suites: Vec<String>,
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub alpn: Vec<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub session_tickets: Option<bool>,
+	/// Identifies the configured session ticket key, so replicas can be checked to share it.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub session_ticket_key: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub client_auth: Option<bool>,
+}
+
+impl TLSSettings {
+	pub fn new(
+		versions: &[&'static rustls::SupportedProtocolVersion],
+		provider: &CryptoProvider,
+		alpn: &[Vec<u8>],
+	) -> Self {
+		Self {
+			versions: versions
+				.iter()
+				.map(|v| match v.version {
+					rustls::ProtocolVersion::TLSv1_2 => "TLS_V1_2".to_string(),
+					rustls::ProtocolVersion::TLSv1_3 => "TLS_V1_3".to_string(),
+					other => format!("{other:?}"),
+				})
+				.collect(),
+			cipher_suites: provider
+				.cipher_suites
+				.iter()
+				.filter(|s| versions.iter().any(|v| v.version == s.version().version))
+				.map(|s| format!("{:?}", s.suite()))
+				.collect(),
+			alpn: alpn
+				.iter()
+				.map(|a| String::from_utf8_lossy(a).to_string())
+				.collect(),
+			session_tickets: None,
+			session_ticket_key: None,
+			client_auth: None,
+		}
+	}
+}
+
 // pub fn provider() -> Arc<CryptoProvider> {
 // 	Arc::new(CryptoProvider {
 // 		// Limit to only the subset of ciphers that are FIPS compatible
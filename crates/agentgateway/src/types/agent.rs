@@ -39,10 +39,30 @@ pub struct Bind {
 	pub protocol: BindProtocol,
 	pub tunnel_protocol: TunnelProtocol,
 	pub listeners: ListenerSet,
+	/// Listen on a Unix domain socket instead of `address`.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub unix_socket: Option<UnixSocket>,
 }
 
 pub type BindKey = Strng;
 
+/// A Unix domain socket to listen on. Connections over it have a loopback address as their source
+/// and destination.
+#[apply(schema!)]
+pub struct UnixSocket {
+	/// The path of the socket. A socket left at the path by a previous run is replaced.
+	pub path: PathBuf,
+	/// The permissions of the socket, as an octal string such as `0660`, controlling who may
+	/// connect. Defaults to the permissions from the process umask.
+	#[serde(
+		default,
+		with = "serde_file_mode_option",
+		skip_serializing_if = "Option::is_none"
+	)]
+	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+	pub mode: Option<u32>,
+}
+
 #[derive(Debug, Clone, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Listener {
@@ -76,6 +96,23 @@ struct ServerTlsInputs {
 	root_pem: Option<Vec<u8>>,
 	// Default ALPNs configured at creation time.
 	default_alpns: Alpns,
+	// If set, explicitly enable or disable session resumption.
+	session_tickets: Option<bool>,
+	// If set, encrypt session tickets with these keys rather than random ones.
+	session_ticket_keys: Option<Arc<crate::transport::tickets::Ticketer>>,
+}
+
+/// Listener level TLS settings, applied when building the base config.
+#[derive(Debug, Clone, Default)]
+pub struct ServerTLSOptions {
+	pub min_version: Option<TLSVersion>,
+	pub max_version: Option<TLSVersion>,
+	pub cipher_suites: Option<Vec<crate::transport::tls::CipherSuite>>,
+	/// If true, issue stateless session tickets. If false, disable session resumption entirely.
+	pub session_tickets: Option<bool>,
+	/// Keys to encrypt session tickets with, so they can be shared across replicas and reloads.
+	/// Implies `session_tickets`.
+	pub session_ticket_keys: Option<Arc<crate::transport::tickets::Ticketer>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -124,6 +161,8 @@ pub struct ServerTLSConfig {
 	/// Original inputs required to rebuild a fresh `ServerConfig` for a given profile.
 	inputs: Option<Arc<ServerTlsInputs>>,
 	per_profile_config: Arc<RwLock<HashMap<ServerTlsProfileKey, Arc<ServerConfig>>>>,
+	/// Effective settings of the base config, reported in the config dump.
+	settings: Option<Arc<crate::transport::tls::TLSSettings>>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EncodeLabelValue)]
@@ -141,6 +180,7 @@ impl ServerTLSConfig {
 			base_config: Some(config),
 			inputs: None,
 			per_profile_config: Arc::new(Default::default()),
+			settings: None,
 		}
 	}
 
@@ -150,7 +190,13 @@ impl ServerTLSConfig {
 		root_pem: Option<Vec<u8>>,
 		default_alpns: Alpns,
 	) -> anyhow::Result<Self> {
-		Self::from_pem_with_profile(cert_pem, key_pem, root_pem, default_alpns, None, None, None)
+		Self::from_pem_with_profile(
+			cert_pem,
+			key_pem,
+			root_pem,
+			default_alpns,
+			ServerTLSOptions::default(),
+		)
 	}
 
 	pub fn from_pem_with_profile(
@@ -158,28 +204,32 @@ impl ServerTLSConfig {
 		key_pem: Vec<u8>,
 		root_pem: Option<Vec<u8>>,
 		default_alpns: Alpns,
-		min_version: Option<TLSVersion>,
-		max_version: Option<TLSVersion>,
-		cipher_suites: Option<Vec<crate::transport::tls::CipherSuite>>,
+		options: ServerTLSOptions,
 	) -> anyhow::Result<Self> {
+		if options.session_tickets == Some(false) && options.session_ticket_keys.is_some() {
+			anyhow::bail!("session ticket keys cannot be set when session tickets are disabled");
+		}
 		let inputs = Arc::new(ServerTlsInputs {
 			cert_pem,
 			key_pem,
 			root_pem,
 			default_alpns,
+			session_tickets: options.session_tickets,
+			session_ticket_keys: options.session_ticket_keys,
 		});
-		let suites = cipher_suites.as_deref().filter(|s| !s.is_empty());
-		let base = Arc::new(Self::build_server_config(
+		let suites = options.cipher_suites.as_deref().filter(|s| !s.is_empty());
+		let (base, settings) = Self::build_server_config(
 			&inputs,
 			None,
-			min_version,
-			max_version,
+			options.min_version,
+			options.max_version,
 			suites.unwrap_or(&[]),
-		)?);
+		)?;
 		Ok(Self {
-			base_config: Some(base),
+			base_config: Some(Arc::new(base)),
 			inputs: Some(inputs),
 			per_profile_config: Arc::new(Default::default()),
+			settings: Some(Arc::new(settings)),
 		})
 	}
 
@@ -189,6 +239,7 @@ impl ServerTLSConfig {
 			base_config: None,
 			inputs: None,
 			per_profile_config: Arc::new(Default::default()),
+			settings: None,
 		}
 	}
 	/// config_for returns the appropriate config for the requested ALPN
@@ -232,13 +283,14 @@ impl ServerTLSConfig {
 			return Ok(Arc::clone(cached_config));
 		}
 
-		let built = Arc::new(Self::build_server_config(
+		let (built, _) = Self::build_server_config(
 			&inputs,
 			Some(&key.alpns),
 			key.min_version,
 			key.max_version,
 			&key.cipher_suites,
-		)?);
+		)?;
+		let built = Arc::new(built);
 		writer.insert(key, Arc::clone(&built));
 		Ok(built)
 	}
@@ -249,7 +301,7 @@ impl ServerTLSConfig {
 		min_version: Option<TLSVersion>,
 		max_version: Option<TLSVersion>,
 		cipher_suites: &[crate::transport::tls::CipherSuite],
-	) -> anyhow::Result<ServerConfig> {
+	) -> anyhow::Result<(ServerConfig, crate::transport::tls::TLSSettings)> {
 		let provider = if cipher_suites.is_empty() {
 			crate::transport::tls::provider()
 		} else {
@@ -257,6 +309,17 @@ impl ServerTLSConfig {
 		};
 
 		let versions = tls_versions_for_range(min_version, max_version)?;
+		crate::transport::tls::validate_cipher_suites(&versions, &provider)?;
+		let mut settings = crate::transport::tls::TLSSettings::new(
+			&versions,
+			&provider,
+			alpns.unwrap_or(&inputs.default_alpns),
+		);
+		settings.client_auth = Some(inputs.root_pem.is_some());
+		settings.session_tickets = inputs
+			.session_tickets
+			.or(inputs.session_ticket_keys.as_ref().map(|_| true));
+		settings.session_ticket_key = inputs.session_ticket_keys.as_ref().map(|t| t.key_id());
 		let scb = ServerConfig::builder_with_provider(provider.clone())
 			.with_protocol_versions(&versions)
 			.expect("server config must be valid");
@@ -282,11 +345,24 @@ impl ServerTLSConfig {
 		sc.alpn_protocols = alpns
 			.map(|a| a.to_vec())
 			.unwrap_or_else(|| inputs.default_alpns.clone());
-		Ok(sc)
+		match (inputs.session_tickets, &inputs.session_ticket_keys) {
+			(Some(false), _) => {
+				sc.session_storage = Arc::new(rustls::server::NoServerSessionStorage {});
+				sc.send_tls13_tickets = 0;
+			},
+			(_, Some(keys)) => {
+				sc.ticketer = keys.clone();
+			},
+			(Some(true), None) => {
+				sc.ticketer = rustls::crypto::aws_lc_rs::Ticketer::new()?;
+			},
+			(None, None) => {},
+		}
+		Ok((sc, settings))
 	}
 }
 
-fn tls_versions_for_range(
+pub(crate) fn tls_versions_for_range(
 	min_version: Option<TLSVersion>,
 	max_version: Option<TLSVersion>,
 ) -> anyhow::Result<Vec<&'static rustls::SupportedProtocolVersion>> {
@@ -330,7 +406,10 @@ impl serde::Serialize for ServerTLSConfig {
 		S: Serializer,
 	{
 		// TODO: store raw pem
-		serializer.serialize_none()
+		match &self.settings {
+			Some(settings) => settings.serialize(serializer),
+			None => serializer.serialize_none(),
+		}
 	}
 }
 
@@ -835,6 +914,8 @@ pub enum Backend {
 	AI(ResourceName, crate::llm::AIBackend),
 	#[serde(serialize_with = "serialize_backend_tuple")]
 	Dynamic(ResourceName, ()),
+	#[serde(serialize_with = "serialize_backend_tuple")]
+	Bridge(ResourceName, crate::bridge::Bridge),
 	Invalid,
 }
 
@@ -1011,7 +1092,8 @@ impl Backend {
 			Backend::Opaque(name, _)
 			| Backend::MCP(name, _)
 			| Backend::AI(name, _)
-			| Backend::Dynamic(name, _) => BackendTarget::Backend {
+			| Backend::Dynamic(name, _)
+			| Backend::Bridge(name, _) => BackendTarget::Backend {
 				name: name.name.clone(),
 				namespace: name.namespace.clone(),
 				section: None,
@@ -1030,7 +1112,8 @@ impl Backend {
 			Backend::Opaque(name, _)
 			| Backend::MCP(name, _)
 			| Backend::AI(name, _)
-			| Backend::Dynamic(name, _) => BackendTargetRef::Backend {
+			| Backend::Dynamic(name, _)
+			| Backend::Bridge(name, _) => BackendTargetRef::Backend {
 				name: name.name.as_ref(),
 				namespace: name.namespace.as_ref(),
 				section: None,
@@ -1045,7 +1128,8 @@ impl Backend {
 			Backend::Opaque(name, _)
 			| Backend::MCP(name, _)
 			| Backend::AI(name, _)
-			| Backend::Dynamic(name, _) => strng::format!("{}", name),
+			| Backend::Dynamic(name, _)
+			| Backend::Bridge(name, _) => strng::format!("{}", name),
 			Backend::Invalid => strng::literal!("invalid"),
 		}
 	}
@@ -1057,6 +1141,7 @@ impl Backend {
 			Backend::MCP(_, _) => cel::BackendType::MCP,
 			Backend::AI(_, _) => cel::BackendType::AI,
 			Backend::Dynamic { .. } => cel::BackendType::Dynamic,
+			Backend::Bridge(_, _) => cel::BackendType::Bridge,
 			Backend::Invalid => cel::BackendType::Unknown,
 		}
 	}
@@ -1190,11 +1275,13 @@ where
 		},
 		Serde::Inline(s) => s,
 	};
-	// OpenAPI can be huge, so grow our stack
-	let schema: OpenAPI = stacker::grow(2 * 1024 * 1024, || {
-		yamlviajson::from_str(s.as_str()).map_err(serde::de::Error::custom)
-	})?;
+	parse_openapi(&s).map_err(serde::de::Error::custom)
+}
 
+/// Parse an OpenAPI spec, in either JSON or YAML.
+pub fn parse_openapi(s: &str) -> anyhow::Result<Arc<OpenAPI>> {
+	// OpenAPI can be huge, so grow our stack
+	let schema: OpenAPI = stacker::grow(2 * 1024 * 1024, || yamlviajson::from_str(s))?;
 	Ok(Arc::new(schema))
 }
 
@@ -1462,6 +1549,10 @@ impl RouteSet {
 		self.all.contains_key(key)
 	}
 
+	pub fn iter(&self) -> impl Iterator<Item = &Route> {
+		self.all.values().map(Arc::as_ref)
+	}
+
 	pub fn remove(&mut self, key: &RouteKey) {
 		let Some(old_route) = self.all.remove(key) else {
 			return;
@@ -1661,6 +1752,9 @@ pub struct TracingConfig {
 	// protocol specifies the OTLP protocol variant to use. Default is HTTP
 	#[serde(default)]
 	pub protocol: TracingProtocol,
+	/// Only export traces matching these policies. If unset, all sampled traces are exported.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub tail_sampling: Option<crate::telemetry::tailsampling::Config>,
 }
 
 fn default_otlp_path() -> String {
@@ -1799,6 +1893,13 @@ impl PolicyType {
 			_ => None,
 		}
 	}
+	/// Whether the policy only configures telemetry, such as access logging or tracing.
+	pub fn is_telemetry(&self) -> bool {
+		matches!(
+			self,
+			PolicyType::Frontend(FrontendPolicy::AccessLog(_) | FrontendPolicy::Tracing(_))
+		)
+	}
 }
 
 pub type RouteTarget = RouteName;
@@ -1860,6 +1961,7 @@ pub enum FrontendPolicy {
 	TCP(frontend::TCP),
 	AccessLog(frontend::LoggingPolicy),
 	Tracing(Arc<TracingPolicy>),
+	RequestId(crate::http::requestid::RequestIdPolicy),
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -1867,6 +1969,12 @@ pub enum FrontendPolicy {
 pub enum TrafficPolicy {
 	Timeout(timeout::Policy),
 	Retry(retry::Policy),
+	Hedging(crate::http::hedging::Policy),
+	ModelFallback(llm::fallback::Policy),
+	ModelExperiment(llm::experiment::Policy),
+	LlmBatch(llm::batch::Policy),
+	StructuredOutput(llm::structured_output::Policy),
+	RequestQueue(crate::http::queue::Policy),
 	#[serde(rename = "ai")]
 	AI(Arc<llm::Policy>),
 	Authorization(Authorization),
@@ -1879,6 +1987,15 @@ pub enum TrafficPolicy {
 	APIKey(crate::http::apikey::APIKeyAuthentication),
 	Transformation(crate::http::transformation_cel::Transformation),
 	Csrf(crate::http::csrf::Csrf),
+	IpFilter(crate::http::ipfilter::IpFilter),
+	RequestSignature(crate::http::signature::RequestSignature),
+	Dlp(crate::http::dlp::Dlp),
+	Quota(crate::http::quota::Quota),
+	Opa(crate::http::opa::Opa),
+	SessionToken(crate::http::sessiontoken::SessionToken),
+	AccessLog(crate::telemetry::accesslog::Policy),
+	SlowRequestLog(crate::telemetry::slowlog::Policy),
+	LlmLog(crate::telemetry::llmlog::Policy),
 
 	RequestHeaderModifier(filters::HeaderModifier),
 	ResponseHeaderModifier(filters::HeaderModifier),
@@ -1901,6 +2018,8 @@ pub enum BackendPolicy {
 	HTTP(backend::HTTP),
 	#[serde(rename = "tcp")]
 	TCP(backend::TCP),
+	ConnectionPool(backend::ConnectionPool),
+	AdaptiveConcurrency(http::adaptiveconcurrency::Policy),
 	#[serde(rename = "backendTLS")]
 	BackendTLS(http::backendtls::BackendTLS),
 	BackendAuth(BackendAuth),
@@ -2117,17 +2236,21 @@ impl Target {
 }
 
 #[apply(schema!)]
+#[derive(Copy, PartialEq, Eq, Hash)]
 pub struct KeepaliveConfig {
 	#[serde(default = "defaults::always_true")]
 	pub enabled: bool,
+	/// How long a connection is idle before the first probe is sent.
 	#[serde(with = "serde_dur")]
 	#[cfg_attr(feature = "schema", schemars(with = "String"))]
 	#[serde(default = "defaults::keepalive_time")]
 	pub time: Duration,
+	/// How long to wait between unacknowledged probes.
 	#[serde(with = "serde_dur")]
 	#[cfg_attr(feature = "schema", schemars(with = "String"))]
 	#[serde(default = "defaults::keepalive_interval")]
 	pub interval: Duration,
+	/// How many unacknowledged probes are sent before the connection is closed.
 	#[serde(default = "defaults::keepalive_retries")]
 	pub retries: u32,
 }
@@ -2164,6 +2287,76 @@ pub mod defaults {
 mod tests {
 	use super::*;
 
+	fn test_server_tls(options: ServerTLSOptions) -> anyhow::Result<ServerTLSConfig> {
+		ServerTLSConfig::from_pem_with_profile(
+			include_bytes!("../../../../examples/tls/certs/cert.pem").to_vec(),
+			include_bytes!("../../../../examples/tls/certs/key.pem").to_vec(),
+			None,
+			vec![b"h2".to_vec()],
+			options,
+		)
+	}
+
+	#[test]
+	fn test_server_tls_options() {
+		let cfg = test_server_tls(ServerTLSOptions {
+			min_version: Some(TLSVersion::TLS_V1_3),
+			session_tickets: Some(true),
+			..Default::default()
+		})
+		.unwrap();
+		let dump = serde_json::to_value(&cfg).unwrap();
+		assert_eq!(dump["versions"], serde_json::json!(["TLS_V1_3"]));
+		assert_eq!(dump["alpn"], serde_json::json!(["h2"]));
+		assert_eq!(dump["sessionTickets"], serde_json::json!(true));
+		assert_eq!(dump["clientAuth"], serde_json::json!(false));
+		let base = cfg.config_for(None).unwrap();
+		assert!(rustls::server::ProducesTickets::enabled(
+			base.ticketer.as_ref()
+		));
+
+		// Configured ticket keys imply session tickets
+		let keys = Arc::new(
+			crate::transport::tickets::Ticketer::new(&[1; 32], &[], Duration::from_secs(60)).unwrap(),
+		);
+		let cfg = test_server_tls(ServerTLSOptions {
+			session_ticket_keys: Some(keys.clone()),
+			..Default::default()
+		})
+		.unwrap();
+		let dump = serde_json::to_value(&cfg).unwrap();
+		assert_eq!(dump["sessionTickets"], serde_json::json!(true));
+		assert_eq!(dump["sessionTicketKey"], serde_json::json!(keys.key_id()));
+		let base = cfg.config_for(None).unwrap();
+		assert_eq!(
+			rustls::server::ProducesTickets::lifetime(base.ticketer.as_ref()),
+			60
+		);
+		let err = test_server_tls(ServerTLSOptions {
+			session_tickets: Some(false),
+			session_ticket_keys: Some(keys),
+			..Default::default()
+		});
+		assert!(err.is_err());
+
+		// TLS 1.3 cipher suites cannot be used with a TLS 1.2 maximum
+		let err = test_server_tls(ServerTLSOptions {
+			max_version: Some(TLSVersion::TLS_V1_2),
+			cipher_suites: Some(vec![
+				crate::transport::tls::CipherSuite::TLS_AES_128_GCM_SHA256,
+			]),
+			..Default::default()
+		});
+		assert!(err.is_err());
+
+		let err = test_server_tls(ServerTLSOptions {
+			min_version: Some(TLSVersion::TLS_V1_3),
+			max_version: Some(TLSVersion::TLS_V1_2),
+			..Default::default()
+		});
+		assert!(err.is_err());
+	}
+
 	#[test]
 	fn test_backend_type_categorization() {
 		let opaque_backend = Backend::Opaque(
@@ -19,6 +19,7 @@ use crate::types::proto::ProtoError;
 use crate::types::proto::agent::backend_policy_spec::ai::request_guard::Kind;
 use crate::types::proto::agent::backend_policy_spec::ai::{ActionKind, response_guard};
 use crate::types::proto::agent::backend_policy_spec::backend_http::HttpVersion;
+use crate::types::proto::agent::backend_policy_spec::backend_tcp::ProxyProtocolVersion;
 use crate::types::proto::agent::mcp_target::Protocol;
 use crate::types::proto::agent::traffic_policy_spec::host_rewrite::Mode;
 use crate::types::{agent, backend, proto};
@@ -107,9 +108,13 @@ impl From<&proto::agent::TlsConfig> for ServerTLSConfig {
 			value.private_key.clone(),
 			value.root.clone(),
 			default_alpns,
-			min_version,
-			max_version,
-			cipher_suites,
+			ServerTLSOptions {
+				min_version,
+				max_version,
+				cipher_suites,
+				session_tickets: None,
+				session_ticket_keys: None,
+			},
 		) {
 			Ok(sc) => sc,
 			Err(e) => {
@@ -372,6 +377,14 @@ fn convert_backend_ai_policy(
 			.iter()
 			.map(|(k, v)| (strng::new(k), convert_route_type(*v)))
 			.collect(),
+		semantic_cache: None,
+		response_cache: None,
+		token_limits: None,
+		context_window: None,
+		stream_rewrite: None,
+		system_prompt: None,
+		pii_redaction: None,
+		model_access: None,
 	};
 
 	// Compile wildcard patterns from model_aliases
@@ -515,6 +528,7 @@ impl TryFrom<&proto::agent::Bind> for Bind {
 				proto::agent::bind::TunnelProtocol::HboneWaypoint => TunnelProtocol::HboneWaypoint,
 				proto::agent::bind::TunnelProtocol::Proxy => TunnelProtocol::Proxy,
 			},
+			unix_socket: None,
 		})
 	}
 }
@@ -702,6 +716,7 @@ impl TryFrom<&proto::agent::Backend> for BackendWithPolicies {
 								})
 								.transpose()?,
 							inline_policies: pols,
+							models: vec![],
 						};
 						local_provider_group.push((provider_name, np));
 					}
@@ -718,7 +733,14 @@ impl TryFrom<&proto::agent::Backend> for BackendWithPolicies {
 				}
 
 				let es = crate::types::loadbalancer::EndpointSet::new(provider_groups);
-				Backend::AI(name.into(), AIBackend { providers: es })
+				Backend::AI(
+					name.into(),
+					AIBackend {
+						providers: es,
+						load_balancing: Default::default(),
+						health_scoring: Default::default(),
+					},
+				)
 			},
 			Some(proto::agent::backend::Kind::Mcp(m)) => Backend::MCP(
 				name.into(),
@@ -937,13 +959,24 @@ impl TryFrom<&proto::agent::BackendPolicySpec> for BackendPolicy {
 						HttpVersion::Http2 => Some(::http::Version::HTTP_2),
 					},
 					request_timeout: bhttp.request_timeout.map(convert_duration),
+					http2_window_size: bhttp.http2_window_size,
+					http2_connection_window_size: bhttp.http2_connection_window_size,
+					http2_frame_size: bhttp.http2_frame_size,
+					http2_keepalive_interval: bhttp.http2_keepalive_interval.map(convert_duration),
+					http2_keepalive_timeout: bhttp.http2_keepalive_timeout.map(convert_duration),
 				})
 			},
 			Some(bps::Kind::BackendTcp(btcp)) => BackendPolicy::TCP(backend::TCP {
+				proxy_protocol: match ProxyProtocolVersion::try_from(btcp.proxy_protocol)? {
+					ProxyProtocolVersion::None => None,
+					ProxyProtocolVersion::V1 => Some(backend::ProxyProtocolVersion::V1),
+					ProxyProtocolVersion::V2 => Some(backend::ProxyProtocolVersion::V2),
+				},
 				connect_timeout: btcp
 					.connect_timeout
 					.map(convert_duration)
 					.unwrap_or(backend::defaults::connect_timeout()),
+				idle_timeout: btcp.idle_timeout.map(convert_duration),
 				keepalives: btcp
 					.keepalive
 					.as_ref()
@@ -966,6 +999,7 @@ impl TryFrom<&proto::agent::BackendPolicySpec> for BackendPolicy {
 					} else {
 						Some(btls.verify_subject_alt_names.clone())
 					},
+					..Default::default()
 				}
 				.try_into()
 				.map_err(|e| ProtoError::Generic(e.to_string()))?;
@@ -1097,6 +1131,10 @@ impl TryFrom<&proto::agent::TrafficPolicySpec> for TrafficPolicy {
 					attempts,
 					backoff,
 					codes: codes.into_boxed_slice(),
+					grpc_codes: Box::default(),
+					honor_retry_after: false,
+					jitter: false,
+					budget: None,
 				})
 			},
 			Some(tps::Kind::LocalRateLimit(lrl)) => {
@@ -1509,6 +1547,7 @@ impl TryFrom<&proto::agent::FrontendPolicySpec> for FrontendPolicy {
 				http2_window_size: h.http2_window_size,
 				http2_connection_window_size: h.http2_connection_window_size,
 				http2_frame_size: h.http2_frame_size,
+				http2_max_concurrent_streams: h.http2_max_concurrent_streams,
 				http2_keepalive_interval: h.http2_keepalive_interval.map(convert_duration),
 				http2_keepalive_timeout: h.http2_keepalive_timeout.map(convert_duration),
 			}),
@@ -1657,6 +1696,7 @@ impl TryFrom<&proto::agent::frontend_policy_spec::Tracing> for types::agent::Tra
 			client_sampling,
 			path,
 			protocol,
+			tail_sampling: None,
 		})
 	}
 }
@@ -1846,6 +1886,8 @@ fn convert_webhook(
 	Ok(llm::policy::Webhook {
 		target,
 		forward_header_matches,
+		timeout: None,
+		failure_mode: Default::default(),
 	})
 }
 
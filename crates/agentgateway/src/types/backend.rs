@@ -1,3 +1,7 @@
+use std::num::NonZeroUsize;
+
+use hyper_util_fork::client::legacy::Http2Settings;
+
 use crate::http::filters::BackendRequestTimeout;
 use crate::transport::stream::TLSConnectionInfo;
 use crate::{apply, *};
@@ -15,6 +19,32 @@ pub struct HTTP {
 	)]
 	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
 	pub request_timeout: Option<Duration>,
+
+	/// The initial flow control window of each HTTP/2 stream to the backend.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub http2_window_size: Option<u32>,
+	/// The initial flow control window of each HTTP/2 connection to the backend.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub http2_connection_window_size: Option<u32>,
+	/// The largest HTTP/2 frame the backend may send.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub http2_frame_size: Option<u32>,
+	/// How often to send HTTP/2 pings on connections to the backend, to detect dead connections.
+	#[serde(
+		default,
+		skip_serializing_if = "Option::is_none",
+		with = "serde_dur_option"
+	)]
+	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+	pub http2_keepalive_interval: Option<Duration>,
+	/// How long to wait for a ping to be acknowledged before closing the connection.
+	#[serde(
+		default,
+		skip_serializing_if = "Option::is_none",
+		with = "serde_dur_option"
+	)]
+	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+	pub http2_keepalive_timeout: Option<Duration>,
 }
 
 impl HTTP {
@@ -22,6 +52,10 @@ impl HTTP {
 		if let Some(timeout) = self.request_timeout {
 			req.extensions_mut().insert(BackendRequestTimeout(timeout));
 		};
+		let http2 = self.http2_settings();
+		if http2 != Http2Settings::default() {
+			req.extensions_mut().insert(http2);
+		}
 		// Version override comes from a Service having a version specified. A policy is more specific
 		// so we use the policy first.
 		let set_version = match self.version.or(version_override) {
@@ -37,7 +71,7 @@ impl HTTP {
 				let tls = req.extensions().get::<TLSConnectionInfo>();
 				if tls.is_some() {
 					// Do not trust the downstream, use HTTP/1.1
-					if is_grpc(req) {
+					if http::grpc::is_grpc(req.headers()) {
 						Some(::http::Version::HTTP_2)
 					} else {
 						Some(::http::Version::HTTP_11)
@@ -58,19 +92,43 @@ impl HTTP {
 			_ => {},
 		};
 	}
-}
 
-fn is_grpc(req: &http::Request) -> bool {
-	req
-		.headers()
-		.get(http::header::CONTENT_TYPE)
-		.is_some_and(|value| value.as_bytes().starts_with("application/grpc".as_bytes()))
+	fn http2_settings(&self) -> Http2Settings {
+		Http2Settings {
+			initial_stream_window_size: self.http2_window_size,
+			initial_connection_window_size: self.http2_connection_window_size,
+			max_frame_size: self.http2_frame_size,
+			keep_alive_interval: self.http2_keepalive_interval,
+			keep_alive_timeout: self.http2_keepalive_timeout,
+		}
+	}
 }
 
+/// Settings for TCP connections to the backend, in place of the global `backend` settings.
 #[apply(schema!)]
+#[derive(Copy, PartialEq, Eq, Hash)]
 pub struct TCP {
+	/// Probe idle connections, so connections the backend or a NAT dropped silently are detected
+	/// and closed, rather than reused.
+	#[serde(default)]
 	pub keepalives: super::agent::KeepaliveConfig,
+	/// How long to wait for a connection to be established. Defaults to 11s.
+	#[serde(default = "defaults::connect_timeout", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
 	pub connect_timeout: Duration,
+	/// How long a connection proxied by a TCP route may go without bytes sent in either direction
+	/// before it is closed. By default, idle connections are kept open.
+	#[serde(
+		default,
+		skip_serializing_if = "Option::is_none",
+		with = "serde_dur_option"
+	)]
+	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+	pub idle_timeout: Option<Duration>,
+	/// Send a PROXY protocol header with the client's address ahead of the data of each connection
+	/// proxied by a TCP route, for backends behind the gateway that need it.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub proxy_protocol: Option<ProxyProtocolVersion>,
 }
 
 impl Default for TCP {
@@ -78,9 +136,52 @@ impl Default for TCP {
 		Self {
 			keepalives: Default::default(),
 			connect_timeout: defaults::connect_timeout(),
+			idle_timeout: None,
+			proxy_protocol: None,
 		}
 	}
 }
+
+#[apply(schema!)]
+#[derive(Copy, PartialEq, Eq, Hash)]
+pub enum ProxyProtocolVersion {
+	/// The human-readable text format.
+	V1,
+	/// The binary format.
+	V2,
+}
+
+/// Settings for the pool of connections to each endpoint of the backend. Whether HTTP/1.1 or HTTP/2
+/// is used is set by the `http` policy.
+#[apply(schema!)]
+#[derive(Default, Copy, PartialEq, Eq, Hash)]
+pub struct ConnectionPool {
+	/// The maximum number of connections to each endpoint. Once reached, requests wait for a
+	/// connection to become available.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub max_connections: Option<NonZeroUsize>,
+	/// The maximum number of idle connections kept to each endpoint. Defaults to the global
+	/// `poolMaxSize`.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub max_idle: Option<usize>,
+	/// How long a connection may be idle before it is closed. Defaults to the global
+	/// `poolIdleTimeout`.
+	#[serde(
+		default,
+		skip_serializing_if = "Option::is_none",
+		with = "serde_dur_option"
+	)]
+	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+	pub idle_timeout: Option<Duration>,
+	/// The maximum number of requests sent on a connection before it is closed.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub max_requests_per_connection: Option<NonZeroUsize>,
+	/// The number of connections opened to an endpoint with no connections when a request is sent
+	/// to it, so a burst of requests does not wait on new connections one at a time.
+	#[serde(default)]
+	pub warm_connections: usize,
+}
+
 pub mod defaults {
 	use std::time::Duration;
 
@@ -47,6 +47,9 @@ pub struct HTTP {
 	pub http2_connection_window_size: Option<u32>,
 	#[serde(default)]
 	pub http2_frame_size: Option<u32>,
+	/// The maximum number of concurrent streams a client may open on a connection. Defaults to 200.
+	#[serde(default)]
+	pub http2_max_concurrent_streams: Option<u32>,
 	#[serde(with = "serde_dur_option")]
 	#[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
 	#[serde(default)]
@@ -68,6 +71,7 @@ impl Default for HTTP {
 			http2_window_size: None,
 			http2_connection_window_size: None,
 			http2_frame_size: None,
+			http2_max_concurrent_streams: None,
 
 			http2_keepalive_interval: None,
 			http2_keepalive_timeout: None,
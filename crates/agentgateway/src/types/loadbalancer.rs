@@ -1,14 +1,17 @@
 use std::collections::BinaryHeap;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
+use ::http::StatusCode;
 use arc_swap::ArcSwap;
 use indexmap::IndexMap;
 use itertools::Itertools;
+use macro_rules_attribute::apply;
 use rand::Rng;
 use serde::ser::SerializeSeq;
 use tokio::sync::mpsc;
 use tokio::time::sleep_until;
 
+use crate::telemetry::events::{self, EventKind};
 use crate::types::discovery::{Endpoint, Service, Workload};
 use crate::*;
 
@@ -266,6 +269,28 @@ impl<T: Clone + Sync + Send + 'static> EndpointSet<T> {
 		ActiveEndpointsIter(self.best_bucket())
 	}
 
+	/// The endpoints matching `f` in the first bucket with a healthy match. If no bucket has one,
+	/// rejected endpoints matching `f` are returned instead.
+	pub fn select_matching<F>(&self, f: F) -> Vec<(Arc<T>, Arc<EndpointInfo>)>
+	where
+		F: Fn(&T) -> bool,
+	{
+		let buckets = self.buckets.iter().map(|b| b.load_full()).collect_vec();
+		let matching = |eps: &IndexMap<EndpointKey, EndpointWithInfo<T>>| {
+			eps
+				.values()
+				.filter(|e| f(e.endpoint.as_ref()))
+				.map(|e| (e.endpoint.clone(), e.info.clone()))
+				.collect_vec()
+		};
+		buckets
+			.iter()
+			.map(|b| matching(&b.active))
+			.chain(buckets.iter().map(|b| matching(&b.rejected)))
+			.find(|m| !m.is_empty())
+			.unwrap_or_default()
+	}
+
 	pub fn insert_key(&self, key: EndpointKey, ep: T, bucket: usize) {
 		self.event(EndpointEvent::Add(key, EndpointWithInfo::new(ep), bucket))
 	}
@@ -309,6 +334,9 @@ impl<T: Clone + Sync + Send + 'static> EndpointSet<T> {
 				let mut eps = Arc::unwrap_or_clone(bucket.load_full());
 				if let Some(ep) = eps.rejected.swap_remove(&key) {
 					ep.info.evicted_until.store(None);
+					events::publish(EventKind::EndpointRestored {
+						endpoint: key.to_string(),
+					});
 					eps.active.insert(key, ep);
 				}
 				bucket.store(Arc::new(eps));
@@ -327,6 +355,10 @@ impl<T: Clone + Sync + Send + 'static> EndpointSet<T> {
 				let mut eps = Arc::unwrap_or_clone(bucket.load_full());
 				uneviction_heap.push((timer, key.clone()));
 				if let Some(ep) = eps.active.swap_remove(&key) {
+					events::publish(EventKind::EndpointEvicted {
+						endpoint: key.to_string(),
+						duration: timer.saturating_duration_since(Instant::now()),
+					});
 					eps.rejected.insert(key, ep);
 				}
 				bucket.store(Arc::new(eps));
@@ -343,7 +375,7 @@ impl<T: Clone + Sync + Send + 'static> EndpointSet<T> {
 			}
 		});
 	}
-	pub async fn evict(&mut self, key: EndpointKey, time: Instant) {
+	pub fn evict(&self, key: EndpointKey, time: Instant) {
 		let Some(bucket) = self.find_bucket(&key) else {
 			return;
 		};
@@ -366,12 +398,69 @@ impl<T: Clone + Sync + Send + 'static> EndpointSet<T> {
 
 const ALPHA: f64 = 0.3;
 
+/// The factor the load balancing score of a degraded endpoint is scaled by.
+pub const DEGRADED_PENALTY: f64 = 0.1;
+
+/// Score endpoints from 0 to 1 on their rate of server errors, their rate of throttled (`429`)
+/// requests, and their latency. Endpoints scoring below `degradedBelow` are marked degraded, and
+/// are only chosen over other degraded endpoints, until they score at least `recoveredAbove` again.
+/// Degraded endpoints still receive some requests, so their recovery is noticed.
+#[apply(schema!)]
+#[derive(Copy, PartialEq)]
+pub struct HealthScoring {
+	/// Endpoints are marked degraded once their score falls below this. Defaults to 0.5.
+	#[serde(default = "default_degraded_below")]
+	pub degraded_below: f64,
+	/// Degraded endpoints recover once their score reaches this. Defaults to 0.8.
+	#[serde(default = "default_recovered_above")]
+	pub recovered_above: f64,
+	/// Endpoints with a latency above this score lower, in proportion to how much slower they are.
+	/// Defaults to 60s.
+	#[serde(default = "default_latency_threshold", with = "serde_dur")]
+	#[cfg_attr(feature = "schema", schemars(with = "String"))]
+	pub latency_threshold: Duration,
+}
+
+impl Default for HealthScoring {
+	fn default() -> Self {
+		Self {
+			degraded_below: default_degraded_below(),
+			recovered_above: default_recovered_above(),
+			latency_threshold: default_latency_threshold(),
+		}
+	}
+}
+
+fn default_degraded_below() -> f64 {
+	0.5
+}
+
+fn default_recovered_above() -> f64 {
+	0.8
+}
+
+fn default_latency_threshold() -> Duration {
+	Duration::from_secs(60)
+}
+
 #[derive(Debug, Serialize)]
 pub struct EndpointInfo {
 	/// health keeps track of the success rate for the endpoint.
 	health: Ewma,
+	/// availability tracks the share of requests that did not fail with a server error.
+	availability: Ewma,
+	/// admission tracks the share of requests that were not throttled.
+	admission: Ewma,
+	/// health_score is the score from the latest request, per the endpoint's HealthScoring.
+	health_score: Ewma,
+	/// degraded is set while the health score is too low, per the endpoint's HealthScoring.
+	degraded: AtomicBool,
 	/// request latency tracks the latency of requests
 	request_latency: Ewma,
+	/// tokens_per_request tracks the tokens used by LLM requests, from their reported usage.
+	tokens_per_request: Ewma,
+	/// token_throughput tracks the rate, in tokens per second, that LLM requests are processed at.
+	token_throughput: Ewma,
 	/// pending_requests keeps track of the total number of pending requests.
 	pending_requests: ActiveCounter,
 	/// total_requests keeps track of the total number of requests.
@@ -385,8 +474,14 @@ impl Default for EndpointInfo {
 	fn default() -> Self {
 		Self {
 			health: Ewma::new(1.0),
+			availability: Ewma::new(1.0),
+			admission: Ewma::new(1.0),
+			health_score: Ewma::new(1.0),
+			degraded: AtomicBool::new(false),
 			// TODO: this will overload them on the first request
 			request_latency: Default::default(),
+			tokens_per_request: Default::default(),
+			token_throughput: Default::default(),
 			pending_requests: Default::default(),
 			total_requests: Default::default(),
 			evicted_until: Arc::new(Default::default()),
@@ -404,6 +499,62 @@ impl EndpointInfo {
 			self.request_latency.load() * (1.0 + self.pending_requests.countf() * 0.1);
 		self.health.load() / (1.0 + latency_penalty)
 	}
+	/// Score by estimated queue depth: the time, in seconds, to work through the tokens of the
+	/// pending requests and one more. Endpoints that have not reported token usage are scored by
+	/// `score` instead.
+	pub fn token_score(&self) -> f64 {
+		let per_request = self.tokens_per_request.load();
+		let throughput = self.token_throughput.load();
+		if per_request <= 0.0 || throughput <= 0.0 {
+			return self.score();
+		}
+		let queued_tokens = (self.pending_requests.countf() + 1.0) * per_request;
+		self.health.load() / (1.0 + queued_tokens / throughput)
+	}
+	/// The health score of the endpoint, from 0 to 1. Only tracked for endpoints with a
+	/// HealthScoring.
+	pub fn health_score(&self) -> f64 {
+		self.health_score.load()
+	}
+	pub fn is_degraded(&self) -> bool {
+		self.degraded.load(Ordering::Relaxed)
+	}
+	/// Update the health score with the outcome of a request. Returns true if this changed whether
+	/// the endpoint is degraded.
+	fn record_health(&self, status: StatusCode, scoring: &HealthScoring) -> bool {
+		self
+			.availability
+			.record(if status.is_server_error() { 0.0 } else { 1.0 });
+		self
+			.admission
+			.record(if status == StatusCode::TOO_MANY_REQUESTS {
+				0.0
+			} else {
+				1.0
+			});
+		let latency = self.request_latency.load();
+		let threshold = scoring.latency_threshold.as_secs_f64();
+		let latency_factor = if latency > threshold {
+			threshold / latency
+		} else {
+			1.0
+		};
+		let score = self.availability.load() * self.admission.load() * latency_factor;
+		self.health_score.0.store(score, Ordering::Relaxed);
+		if self.is_degraded() {
+			score >= scoring.recovered_above
+				&& self
+					.degraded
+					.compare_exchange(true, false, Ordering::Relaxed, Ordering::Relaxed)
+					.is_ok()
+		} else {
+			score < scoring.degraded_below
+				&& self
+					.degraded
+					.compare_exchange(false, true, Ordering::Relaxed, Ordering::Relaxed)
+					.is_ok()
+		}
+	}
 	pub fn start_request(
 		self: &Arc<Self>,
 		key: Strng,
@@ -414,6 +565,7 @@ impl EndpointInfo {
 			info: self.clone(),
 			key,
 			tx: tx_sender,
+			health_scoring: None,
 			counter: self.pending_requests.0.clone(),
 		}
 	}
@@ -459,11 +611,44 @@ pub struct ActiveHandle {
 	info: Arc<EndpointInfo>,
 	key: Strng,
 	tx: mpsc::Sender<EvictionEvent>,
+	health_scoring: Option<HealthScoring>,
 	#[allow(dead_code)]
 	counter: Arc<()>,
 }
 
 impl ActiveHandle {
+	/// Track the health score of the endpoint from the outcome of this request.
+	pub fn with_health_scoring(mut self, scoring: HealthScoring) -> Self {
+		self.health_scoring = Some(scoring);
+		self
+	}
+	pub fn key(&self) -> &Strng {
+		&self.key
+	}
+	/// Record the status of a completed request in the endpoint's health score, if it is tracked.
+	/// Returns the updated score, and whether the endpoint is degraded.
+	pub fn record_health(&self, status: StatusCode) -> Option<(f64, bool)> {
+		let scoring = self.health_scoring.as_ref()?;
+		if self.info.record_health(status, scoring) {
+			let endpoint = self.key.to_string();
+			let score = self.info.health_score();
+			events::publish(if self.info.is_degraded() {
+				EventKind::EndpointDegraded { endpoint, score }
+			} else {
+				EventKind::EndpointRecovered { endpoint, score }
+			});
+		}
+		Some((self.info.health_score(), self.info.is_degraded()))
+	}
+	/// Record the tokens a completed LLM request used, as feedback for token-based balancing.
+	pub fn record_tokens(&self, tokens: u64, latency: Duration) {
+		let secs = latency.as_secs_f64();
+		if tokens == 0 || secs <= 0.0 {
+			return;
+		}
+		self.info.tokens_per_request.record(tokens as f64);
+		self.info.token_throughput.record(tokens as f64 / secs);
+	}
 	pub fn finish_request(self, success: bool, latency: Duration, eviction_time: Option<Duration>) {
 		if success {
 			self.info.request_latency.record(latency.as_secs_f64());
@@ -1,10 +1,10 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
 use std::path::PathBuf;
 
 use ::http::Uri;
 use agent_core::prelude::Strng;
-use anyhow::{Error, anyhow, bail};
+use anyhow::{Context, Error, anyhow, bail};
 use itertools::Itertools;
 use macro_rules_attribute::apply;
 use openapiv3::OpenAPI;
@@ -23,30 +23,353 @@ use crate::types::agent::{
 	ListenerProtocol, ListenerSet, ListenerTarget, LocalMcpAuthentication, McpAuthentication,
 	McpBackend, McpTarget, McpTargetName, McpTargetSpec, OpenAPITarget, PathMatch, PolicyPhase,
 	PolicyTarget, PolicyType, ResourceName, Route, RouteBackendReference, RouteMatch, RouteName,
-	RouteSet, ServerTLSConfig, SimpleBackend, SimpleBackendReference, SimpleBackendWithPolicies,
-	SseTargetSpec, StreamableHTTPTargetSpec, TCPRoute, TCPRouteBackendReference, TCPRouteSet, Target,
-	TargetedPolicy, TracingConfig, TrafficPolicy, TunnelProtocol, TypedResourceName,
+	RouteSet, ServerTLSConfig, ServerTLSOptions, SimpleBackend, SimpleBackendReference,
+	SimpleBackendWithPolicies, SseTargetSpec, StreamableHTTPTargetSpec, TCPRoute,
+	TCPRouteBackendReference, TCPRouteSet, Target, TargetedPolicy, TracingConfig, TrafficPolicy,
+	TunnelProtocol, TypedResourceName, UnixSocket,
 };
 use crate::types::discovery::{NamespacedHostname, Service};
 use crate::types::{backend, frontend};
 use crate::*;
 
+#[cfg(test)]
+#[path = "local_tests.rs"]
+mod tests;
+
 impl NormalizedLocalConfig {
 	pub async fn from(
 		client: client::Client,
 		gateway_name: ListenerTarget,
 		s: &str,
 	) -> anyhow::Result<NormalizedLocalConfig> {
-		// Avoid shell expanding the comment for schema. Probably there are better ways to do this!
-		let s = s.replace("# yaml-language-server: $schema", "#");
-		let s = shellexpand::full(&s)?;
-		let config: LocalConfig = serdes::yamlviajson::from_str(&s)?;
+		let secrets = secrets::Resolver::new(client.clone());
+		Self::from_with_secrets(client, &secrets, gateway_name, s).await
+	}
+
+	/// Like `from`, but resolves secret references with the provided resolver, so resolved secrets
+	/// can be cached and refreshed across reloads.
+	pub async fn from_with_secrets(
+		client: client::Client,
+		secrets: &secrets::Resolver,
+		gateway_name: ListenerTarget,
+		s: &str,
+	) -> anyhow::Result<NormalizedLocalConfig> {
+		let raw = parse_raw(s)?;
+		Self::from_value(client, secrets, gateway_name, raw).await
+	}
+
+	/// Like `from_with_secrets`, but for a config that was already parsed, or built from another
+	/// source. Environment variables are not expanded.
+	pub async fn from_value(
+		client: client::Client,
+		secrets: &secrets::Resolver,
+		gateway_name: ListenerTarget,
+		mut raw: serde_json::Value,
+	) -> anyhow::Result<NormalizedLocalConfig> {
+		secrets.resolve_references(&mut raw).await?;
+		let config: LocalConfig = serde_json_path_to_error::from_slice(&serde_json::to_vec(&raw)?)?;
 		let t = convert(client, gateway_name, config).await?;
 		Ok(t)
 	}
+
+	/// Syncing discovery fails on invalid service names; check them up front so a bad config cannot
+	/// leave the stores partially updated.
+	pub fn check_services(&self) -> anyhow::Result<()> {
+		for wl in &self.workloads {
+			for svc in wl.services.keys() {
+				svc.parse::<NamespacedHostname>().map_err(|e| {
+					anyhow::anyhow!("workload {}: invalid service {svc}: {e}", wl.workload.uid)
+				})?;
+			}
+		}
+		Ok(())
+	}
+
+	/// Check a config as `from` would load it, reporting every problem found rather than only the
+	/// first. This also reports mistakes that loading tolerates: duplicate names, references to
+	/// backends or services that are not defined, and missing TLS files.
+	///
+	/// Secret references are checked, but not resolved, so validating does not need access to the
+	/// secret stores.
+	pub async fn validate(
+		client: client::Client,
+		gateway_name: ListenerTarget,
+		s: &str,
+		opts: ValidateOptions,
+	) -> Result<NormalizedLocalConfig, Vec<ValidationError>> {
+		let fail = |kind, e: anyhow::Error| vec![ValidationError::new(kind, None, format!("{e:#}"))];
+		let raw = if opts.interpolate {
+			parse_raw(s)
+		} else {
+			serdes::yamlviajson::from_str(s)
+		}
+		.map_err(|e| fail(ValidationErrorKind::Syntax, e))?;
+		secrets::Resolver::new(client.clone())
+			.check_references(&raw)
+			.map_err(|e| fail(ValidationErrorKind::InvalidField, e))?;
+		let config: LocalConfig = serde_json::to_vec(&raw)
+			.map_err(anyhow::Error::from)
+			.and_then(|b| Ok(serde_json_path_to_error::from_slice(&b)?))
+			.map_err(|e| fail(ValidationErrorKind::InvalidField, e))?;
+
+		let mut errors = check_files(&config);
+		// Loading fails on the first missing file, which was already reported.
+		let check_load = errors.is_empty();
+		errors.extend(check_names(&config));
+		errors.extend(check_references(&config, &raw, !opts.services_from_xds));
+		if !check_load {
+			return Err(errors);
+		}
+		match convert(client, gateway_name, config).await {
+			Ok(c) if errors.is_empty() => Ok(c),
+			Ok(_) => Err(errors),
+			Err(e) => {
+				errors.push(ValidationError::new(
+					ValidationErrorKind::Invalid,
+					None,
+					format!("{e:#}"),
+				));
+				Err(errors)
+			},
+		}
+	}
 }
 
-#[derive(Debug, Clone)]
+/// How `NormalizedLocalConfig::validate` checks a config.
+#[derive(Debug, Clone, Copy)]
+pub struct ValidateOptions {
+	/// Services are not checked, as they may be defined by the control plane.
+	pub services_from_xds: bool,
+	/// Expand `${VAR}` and `${file:...}` as loading does. Configs from untrusted callers must not be
+	/// interpolated, as the result would reveal the gateway's environment and files.
+	pub interpolate: bool,
+}
+
+/// Parse a local config, in YAML or JSON, after interpolating its variables.
+pub(crate) fn parse_raw(s: &str) -> anyhow::Result<serde_json::Value> {
+	let s = crate::config::interpolate(s)?;
+	serdes::yamlviajson::from_str(&s)
+}
+
+/// A problem found by `NormalizedLocalConfig::validate`.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationError {
+	pub kind: ValidationErrorKind,
+	/// Where the problem is, such as `binds[0].listeners[1].name`.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub location: Option<String>,
+	pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ValidationErrorKind {
+	/// The config is not valid YAML, or references an unset environment variable.
+	Syntax,
+	/// A field is unknown, missing, or has an invalid value, such as a CEL expression that does not
+	/// compile.
+	InvalidField,
+	/// A name is used by more than one bind, listener, route, backend, or policy.
+	DuplicateName,
+	/// A backend or service is referenced but not defined.
+	DanglingReference,
+	/// A file the config refers to does not exist.
+	MissingFile,
+	/// The config could not be loaded for another reason.
+	Invalid,
+}
+
+impl ValidationError {
+	fn new(kind: ValidationErrorKind, location: Option<String>, message: String) -> Self {
+		Self {
+			kind,
+			location,
+			message,
+		}
+	}
+}
+
+impl std::fmt::Display for ValidationError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match &self.location {
+			Some(l) => write!(f, "{l}: {}", self.message),
+			None => write!(f, "{}", self.message),
+		}
+	}
+}
+
+/// Names seen so far, and where they were first defined.
+#[derive(Default)]
+struct Names(HashMap<String, String>);
+
+impl Names {
+	fn check(&mut self, what: &str, name: String, location: String) -> Option<ValidationError> {
+		if let Some(first) = self.0.get(&name) {
+			return Some(ValidationError::new(
+				ValidationErrorKind::DuplicateName,
+				Some(location),
+				format!("{what} {name} is already defined at {first}"),
+			));
+		}
+		self.0.insert(name, location);
+		None
+	}
+}
+
+/// Report names defined more than once. Only one of them would be served, so the others are
+/// silently ignored when the config is loaded.
+fn check_names(config: &LocalConfig) -> Vec<ValidationError> {
+	let route_name = |n: &LocalRouteName| {
+		let name = n.name.as_ref()?;
+		let ns = n.namespace.as_deref().unwrap_or("default");
+		Some(match &n.rule_name {
+			Some(rule) => format!("{ns}/{name}/{rule}"),
+			None => format!("{ns}/{name}"),
+		})
+	};
+	let mut errors = Vec::new();
+	let mut ports = Names::default();
+	let mut listeners = Names::default();
+	for (i, b) in config.binds.iter().enumerate() {
+		if let Some((what, name, field)) = b.listen_name() {
+			errors.extend(ports.check(what, name, format!("binds[{i}].{field}")));
+		}
+		for (j, l) in b.listeners.iter().enumerate() {
+			let loc = format!("binds[{i}].listeners[{j}]");
+			if let Some(name) = &l.name.name {
+				errors.extend(listeners.check("listener", name.to_string(), format!("{loc}.name")));
+			}
+			let mut routes = Names::default();
+			for (k, r) in l.routes.iter().flatten().enumerate() {
+				if let Some(name) = route_name(&r.name) {
+					errors.extend(routes.check("route", name, format!("{loc}.routes[{k}].name")));
+				}
+			}
+			for (k, r) in l.tcp_routes.iter().flatten().enumerate() {
+				if let Some(name) = route_name(&r.name) {
+					errors.extend(routes.check("route", name, format!("{loc}.tcpRoutes[{k}].name")));
+				}
+			}
+		}
+	}
+	let mut backends = Names::default();
+	for (i, b) in config.backends.iter().enumerate() {
+		errors.extend(backends.check("backend", b.name.to_string(), format!("backends[{i}].name")));
+	}
+	let mut policies = Names::default();
+	for (i, p) in config.policies.iter().enumerate() {
+		errors.extend(policies.check("policy", p.name.to_string(), format!("policies[{i}].name")));
+	}
+	errors
+}
+
+/// Report listener TLS files that do not exist.
+fn check_files(config: &LocalConfig) -> Vec<ValidationError> {
+	let mut errors = Vec::new();
+	for (i, b) in config.binds.iter().enumerate() {
+		for (j, l) in b.listeners.iter().enumerate() {
+			let Some(tls) = &l.tls else {
+				continue;
+			};
+			let files = [
+				("cert", Some(&tls.cert)),
+				("key", Some(&tls.key)),
+				("root", tls.root.as_ref()),
+			];
+			for (field, path) in files {
+				if let Some(path) = path
+					&& !path.exists()
+				{
+					errors.push(ValidationError::new(
+						ValidationErrorKind::MissingFile,
+						Some(format!("binds[{i}].listeners[{j}].tls.{field}")),
+						format!("{} does not exist", path.display()),
+					));
+				}
+			}
+		}
+	}
+	errors
+}
+
+/// Report references to backends, and to services if `check_services`, that are not defined.
+/// Backends can be referenced from many policies, so references are found in the raw config.
+fn check_references(
+	config: &LocalConfig,
+	raw: &serde_json::Value,
+	check_services: bool,
+) -> Vec<ValidationError> {
+	let backends: HashSet<String> = config
+		.backends
+		.iter()
+		.map(|b| local_name(b.name.clone()).to_string())
+		.collect();
+	let services: HashSet<String> = config
+		.services
+		.iter()
+		.map(|s| format!("{}/{}", s.namespace, s.hostname))
+		.collect();
+	let mut errors = Vec::new();
+	let mut check = |location: &str, key: &str, v: &serde_json::Value| match (key, v) {
+		("backend", serde_json::Value::String(name)) if !backends.contains(name) => {
+			let hint = if backends.contains(&format!("/{name}")) {
+				format!("; did you mean /{name}?")
+			} else {
+				String::new()
+			};
+			errors.push(ValidationError::new(
+				ValidationErrorKind::DanglingReference,
+				Some(location.to_string()),
+				format!("backend {name} is not defined{hint}"),
+			));
+		},
+		("service", serde_json::Value::Object(svc)) if check_services => {
+			if let Some(serde_json::Value::String(name)) = svc.get("name")
+				&& !services.contains(name)
+			{
+				errors.push(ValidationError::new(
+					ValidationErrorKind::DanglingReference,
+					Some(location.to_string()),
+					format!("service {name} is not defined"),
+				));
+			}
+		},
+		_ => {},
+	};
+	for field in ["binds", "frontendPolicies", "policies", "backends"] {
+		if let Some(v) = raw.get(field) {
+			walk(v, field.to_string(), &mut check);
+		}
+	}
+	errors
+}
+
+/// Call `f` with the location, key, and value of every field under `v`.
+fn walk(
+	v: &serde_json::Value,
+	location: String,
+	f: &mut impl FnMut(&str, &str, &serde_json::Value),
+) {
+	match v {
+		serde_json::Value::Object(m) => {
+			for (k, v) in m {
+				let location = format!("{location}.{k}");
+				f(&location, k, v);
+				walk(v, location, f);
+			}
+		},
+		serde_json::Value::Array(a) => {
+			for (i, v) in a.iter().enumerate() {
+				walk(v, format!("{location}[{i}]"), f);
+			}
+		},
+		_ => {},
+	}
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct NormalizedLocalConfig {
 	pub binds: Vec<Bind>,
 	pub policies: Vec<TargetedPolicy>,
@@ -55,6 +378,7 @@ pub struct NormalizedLocalConfig {
 	// for now
 	pub workloads: Vec<LocalWorkload>,
 	pub services: Vec<Service>,
+	pub serial: Option<u64>,
 }
 
 #[apply(schema_de!)]
@@ -79,16 +403,37 @@ pub struct LocalConfig {
 	services: Vec<Service>,
 	#[serde(default)]
 	backends: Vec<FullLocalBackend>,
+	/// A version number for this config, which must increase with each version published. Remote
+	/// config requires it, and rejects a config whose serial is not newer than the one applied, so
+	/// an older signed config cannot be replayed to roll the gateway back.
+	#[serde(default)]
+	serial: Option<u64>,
 }
 
 #[apply(schema_de!)]
 struct LocalBind {
-	port: u16,
+	/// The TCP port to listen on. Required unless `unix` is set.
+	#[serde(default)]
+	port: Option<u16>,
+	/// Listen on a Unix domain socket, instead of a TCP port.
+	#[serde(default)]
+	unix: Option<UnixSocket>,
 	listeners: Vec<LocalListener>,
 	#[serde(default)]
 	tunnel_protocol: TunnelProtocol,
 }
 
+impl LocalBind {
+	/// What the bind listens on, and the field it is set by, for reporting duplicates.
+	fn listen_name(&self) -> Option<(&'static str, String, &'static str)> {
+		match (&self.unix, self.port) {
+			(Some(unix), _) => Some(("socket", unix.path.display().to_string(), "unix.path")),
+			(None, Some(port)) => Some(("port", port.to_string(), "port")),
+			(None, None) => None,
+		}
+	}
+}
+
 #[apply(schema_de!)]
 pub struct LocalListenerName {
 	// User facing name
@@ -153,6 +498,17 @@ pub struct LocalTLSServerConfig {
 		alias = "maxTlsVersion"
 	)]
 	pub max_tls_version: Option<frontend::TLSVersion>,
+	/// ALPN protocols to advertise, in order of preference. Defaults to `h2` and `http/1.1`.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub alpn: Option<Vec<String>>,
+	/// If true, issue stateless session tickets. If false, disable session resumption.
+	/// If unset, stateful session resumption is used.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub session_tickets: Option<bool>,
+	/// Keys to encrypt session tickets with, instead of random keys per listener, so sessions can
+	/// be resumed on any replica sharing the keys and across reloads. Implies `sessionTickets`.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub session_ticket_keys: Option<crate::transport::tickets::Config>,
 }
 
 #[apply(schema_de!)]
@@ -174,12 +530,85 @@ struct LocalRoute {
 	hostnames: Vec<Strng>,
 	#[serde(default = "default_matches")]
 	matches: Vec<RouteMatch>,
+	/// Match only the methods of these gRPC services, in place of the paths of `matches`.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	grpc: Option<LocalGrpcMatch>,
 	#[serde(default, skip_serializing_if = "Option::is_none")]
 	policies: Option<FilterOrPolicy>,
 	#[serde(default, skip_serializing_if = "Vec::is_empty")]
 	backends: Vec<LocalRouteBackend>,
 }
 
+/// The gRPC services a route serves, from any of the sources below.
+#[apply(schema_de!)]
+pub struct LocalGrpcMatch {
+	/// Fully-qualified services, such as `helloworld.Greeter`, all of whose methods are matched.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	services: Vec<Strng>,
+	/// A serialized `FileDescriptorSet`, as produced by `protoc --descriptor_set_out`, whose
+	/// services' methods are matched.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	descriptors: Option<PathBuf>,
+	/// Learn the services, and their methods, from the route's first `host` backend with gRPC server
+	/// reflection over plaintext, when the config is loaded.
+	#[serde(default)]
+	reflection: bool,
+}
+
+impl LocalGrpcMatch {
+	/// The paths of the methods to match. Services with unknown methods match any of their methods.
+	async fn paths(
+		&self,
+		client: &Client,
+		backends: &[LocalRouteBackend],
+	) -> anyhow::Result<Vec<PathMatch>> {
+		let mut services: http::grpc::Services = self
+			.services
+			.iter()
+			.map(|s| (s.to_string(), vec![]))
+			.collect();
+		if let Some(file) = &self.descriptors {
+			let descriptors = fs_err::tokio::read(file).await?;
+			services.extend(
+				http::grpc::services_from_descriptors(&descriptors)
+					.with_context(|| format!("descriptors {}", file.display()))?,
+			);
+		}
+		if self.reflection {
+			let Some(target) = backends.iter().find_map(|b| match &b.backend {
+				LocalBackend::Opaque(t) => Some(t),
+				_ => None,
+			}) else {
+				bail!("gRPC reflection requires a host backend");
+			};
+			let uri = Uri::try_from(format!("http://{target}"))?;
+			services.extend(
+				http::grpc::reflect(client.clone(), &uri)
+					.await
+					.with_context(|| format!("gRPC reflection of {target}"))?,
+			);
+		}
+		if services.is_empty() {
+			bail!("no gRPC services to match");
+		}
+		Ok(
+			services
+				.into_iter()
+				.flat_map(|(service, methods)| {
+					if methods.is_empty() {
+						vec![PathMatch::PathPrefix(strng::format!("/{service}"))]
+					} else {
+						methods
+							.into_iter()
+							.map(|m| PathMatch::Exact(strng::format!("/{service}/{m}")))
+							.collect()
+					}
+				})
+				.collect(),
+		)
+	}
+}
+
 #[apply(schema_de!)]
 pub struct LocalRouteBackend {
 	#[serde(default = "default_weight")]
@@ -218,6 +647,7 @@ pub enum LocalBackend {
 	MCP(LocalMcpBackend),
 	#[serde(rename = "ai")]
 	AI(LocalAIBackend),
+	Bridge(crate::bridge::Bridge),
 	Invalid,
 }
 
@@ -227,7 +657,15 @@ pub enum LocalBackend {
 #[allow(clippy::large_enum_variant)] // Size is not sensitive for local config
 pub enum LocalAIBackend {
 	Provider(LocalNamedAIProvider),
-	Groups { groups: Vec<LocalAIProviders> },
+	Groups {
+		groups: Vec<LocalAIProviders>,
+		/// How requests are balanced across the providers of a group. Defaults to `requests`.
+		#[cfg_attr(feature = "schema", schemars(rename = "loadBalancing", default))]
+		load_balancing: llm::LoadBalancing,
+		/// How providers are scored on their health, so traffic moves away from degraded ones.
+		#[cfg_attr(feature = "schema", schemars(rename = "healthScoring", default))]
+		health_scoring: types::loadbalancer::HealthScoring,
+	},
 }
 
 // Custom impl to avoid terrible 'not match any variant of untagged' errors.
@@ -241,11 +679,25 @@ impl<'de> Deserialize<'de> for LocalAIBackend {
 				let v: serde_json::Value = map.deserialize()?;
 
 				if let serde_json::Value::Object(m) = &v
-					&& m.len() == 1
 					&& let Some(g) = m.get("groups")
+					&& m
+						.keys()
+						.all(|k| k == "groups" || k == "loadBalancing" || k == "healthScoring")
 				{
 					Ok(LocalAIBackend::Groups {
 						groups: Vec::<LocalAIProviders>::deserialize(g).map_err(serde::de::Error::custom)?,
+						load_balancing: m
+							.get("loadBalancing")
+							.map(llm::LoadBalancing::deserialize)
+							.transpose()
+							.map_err(serde::de::Error::custom)?
+							.unwrap_or_default(),
+						health_scoring: m
+							.get("healthScoring")
+							.map(types::loadbalancer::HealthScoring::deserialize)
+							.transpose()
+							.map_err(serde::de::Error::custom)?
+							.unwrap_or_default(),
 					})
 				} else {
 					Ok(LocalAIBackend::Provider(
@@ -275,15 +727,26 @@ pub struct LocalNamedAIProvider {
 	pub tokenize: bool,
 	#[serde(default, skip_serializing_if = "Option::is_none")]
 	pub policies: Option<LocalBackendPolicies>,
+	/// Models served by this provider, such as `gpt-4o` or `claude-*` (a trailing `*` matches any
+	/// suffix). When any provider in the backend sets this, requests are routed to a provider
+	/// serving their requested model, falling back to providers without `models`.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub models: Vec<Strng>,
 }
 
 impl LocalAIBackend {
 	pub fn translate(self) -> anyhow::Result<AIBackend> {
-		let providers = match self {
-			LocalAIBackend::Provider(p) => {
-				vec![vec![p]]
-			},
-			LocalAIBackend::Groups { groups } => groups.into_iter().map(|g| g.providers).collect_vec(),
+		let (providers, load_balancing, health_scoring) = match self {
+			LocalAIBackend::Provider(p) => (vec![vec![p]], Default::default(), Default::default()),
+			LocalAIBackend::Groups {
+				groups,
+				load_balancing,
+				health_scoring,
+			} => (
+				groups.into_iter().map(|g| g.providers).collect_vec(),
+				load_balancing,
+				health_scoring,
+			),
 		};
 		let mut ep_groups = vec![];
 		for g in providers {
@@ -303,18 +766,27 @@ impl LocalAIBackend {
 						path_override: p.path_override,
 						tokenize: p.tokenize,
 						inline_policies: policies,
+						models: p.models,
 					},
 				));
 			}
 			ep_groups.push(group);
 		}
 		let es = types::loadbalancer::EndpointSet::new(ep_groups);
-		Ok(AIBackend { providers: es })
+		Ok(AIBackend {
+			providers: es,
+			load_balancing,
+			health_scoring,
+		})
 	}
 }
 
 impl LocalBackend {
-	pub fn as_backends(&self, name: ResourceName) -> anyhow::Result<Vec<BackendWithPolicies>> {
+	pub async fn as_backends(
+		&self,
+		client: &Client,
+		name: ResourceName,
+	) -> anyhow::Result<Vec<BackendWithPolicies>> {
 		Ok(match self {
 			LocalBackend::Service { .. } => vec![], // These stay as references
 			LocalBackend::Opaque(tgt) => vec![Backend::Opaque(name, tgt.clone()).into()],
@@ -363,6 +835,10 @@ impl LocalBackend {
 						},
 						LocalMcpTargetSpec::Stdio { cmd, args, env } => McpTargetSpec::Stdio { cmd, args, env },
 						LocalMcpTargetSpec::OpenAPI { backend, schema } => {
+							let schema = schema
+								.load(client.clone())
+								.await
+								.with_context(|| format!("openapi schema of target {}", t.name))?;
 							let (backend, _, tls) = backend.process()?;
 							let (bref, be) = mcp_to_simple_backend_and_ref(local_name(name.clone()), backend);
 							if let Some(b) = be {
@@ -399,6 +875,7 @@ impl LocalBackend {
 				let be = tgt.clone().translate()?;
 				vec![Backend::AI(name, be).into()]
 			},
+			LocalBackend::Bridge(b) => vec![Backend::Bridge(name, b.clone()).into()],
 			LocalBackend::Invalid => vec![Backend::Invalid.into()],
 		})
 	}
@@ -521,12 +998,50 @@ pub enum LocalMcpTargetSpec {
 	OpenAPI {
 		#[serde(flatten)]
 		backend: McpBackendHost,
-		#[serde(deserialize_with = "types::agent::de_openapi")]
-		#[cfg_attr(feature = "schema", schemars(with = "serde_json::value::RawValue"))]
-		schema: Arc<OpenAPI>,
+		schema: LocalOpenAPISchema,
 	},
 }
 
+/// Where to load an OpenAPI spec, in either JSON or YAML, from.
+#[apply(schema_de!)]
+pub enum LocalOpenAPISchema {
+	File(PathBuf),
+	Inline(String),
+	/// Fetched once, when the config is loaded.
+	Url(
+		#[serde(deserialize_with = "de_parse")]
+		#[cfg_attr(feature = "schema", schemars(with = "String"))]
+		Uri,
+	),
+}
+
+impl LocalOpenAPISchema {
+	async fn load(self, client: Client) -> anyhow::Result<Arc<OpenAPI>> {
+		let s = match self {
+			LocalOpenAPISchema::File(file) => fs_err::tokio::read_to_string(file).await?,
+			LocalOpenAPISchema::Inline(s) => s,
+			LocalOpenAPISchema::Url(url) => {
+				let resp = client
+					.simple_call(
+						::http::Request::builder()
+							.uri(&url)
+							.body(crate::http::Body::empty())
+							.expect("builder should succeed"),
+					)
+					.await
+					.context(format!("fetch {url}"))?;
+				if !resp.status().is_success() {
+					bail!("fetch {url}: status {}", resp.status());
+				}
+				let limit = crate::http::response_buffer_limit(&resp);
+				let body = crate::http::read_body_with_limit(resp.into_body(), limit).await?;
+				String::from_utf8(body.to_vec())?
+			},
+		};
+		types::agent::parse_openapi(&s)
+	}
+}
+
 fn default_matches() -> Vec<RouteMatch> {
 	vec![RouteMatch {
 		headers: vec![],
@@ -643,6 +1158,9 @@ struct LocalGatewayPolicy {
 	/// Authenticate incoming requests using API Keys
 	#[serde(default)]
 	api_key: Option<crate::http::apikey::LocalAPIKeys>,
+	/// Allow or deny requests based on the client IP address.
+	#[serde(default)]
+	ip_filter: Option<http::ipfilter::IpFilter>,
 }
 
 impl From<LocalGatewayPolicy> for FilterOrPolicy {
@@ -654,6 +1172,7 @@ impl From<LocalGatewayPolicy> for FilterOrPolicy {
 			transformations,
 			basic_auth,
 			api_key,
+			ip_filter,
 		} = val;
 		FilterOrPolicy {
 			jwt_auth,
@@ -662,6 +1181,7 @@ impl From<LocalGatewayPolicy> for FilterOrPolicy {
 			transformations,
 			basic_auth,
 			api_key,
+			ip_filter,
 			..Default::default()
 		}
 	}
@@ -705,6 +1225,12 @@ pub struct LocalBackendPolicies {
 	/// Specify TCP settings for the backend
 	#[serde(default)]
 	pub tcp: Option<backend::TCP>,
+	/// Specify connection pool settings for the backend
+	#[serde(default)]
+	pub connection_pool: Option<backend::ConnectionPool>,
+	/// Limit the requests in flight to the backend, adjusting the limit to its latency
+	#[serde(default)]
+	pub adaptive_concurrency: Option<http::adaptiveconcurrency::Policy>,
 }
 
 impl LocalBackendPolicies {
@@ -720,11 +1246,20 @@ impl LocalBackendPolicies {
 			backend_auth,
 			http,
 			tcp,
+			connection_pool,
+			adaptive_concurrency,
 		} = self;
 		let mut pols = vec![];
 		if let Some(p) = tcp {
 			pols.push(BackendPolicy::TCP(p));
 		}
+		if let Some(p) = connection_pool {
+			pols.push(BackendPolicy::ConnectionPool(p));
+		}
+		if let Some(p) = adaptive_concurrency {
+			p.validate()?;
+			pols.push(BackendPolicy::AdaptiveConcurrency(p));
+		}
 		if let Some(p) = http {
 			pols.push(BackendPolicy::HTTP(p));
 		}
@@ -763,15 +1298,21 @@ pub struct LocalTCPBackendPolicies {
 	/// Send TLS to the backend.
 	#[serde(rename = "backendTLS", default)]
 	pub backend_tls: Option<http::backendtls::LocalBackendTLS>,
+	/// Specify TCP settings for the backend
+	#[serde(default)]
+	pub tcp: Option<backend::TCP>,
 }
 
 impl LocalTCPBackendPolicies {
 	pub fn translate(self) -> anyhow::Result<Vec<BackendPolicy>> {
-		let LocalTCPBackendPolicies { backend_tls } = self;
+		let LocalTCPBackendPolicies { backend_tls, tcp } = self;
 		let mut pols = vec![];
 		if let Some(p) = backend_tls {
 			pols.push(BackendPolicy::BackendTLS(p.try_into()?))
 		}
+		if let Some(p) = tcp {
+			pols.push(BackendPolicy::TCP(p));
+		}
 		Ok(pols)
 	}
 }
@@ -793,6 +1334,9 @@ struct LocalFrontendPolicies {
 	pub access_log: Option<frontend::LoggingPolicy>,
 	#[serde(default)]
 	pub tracing: Option<TracingConfig>,
+	/// Settings for generating and propagating request IDs.
+	#[serde(default)]
+	pub request_id: Option<crate::http::requestid::RequestIdPolicy>,
 }
 
 #[apply(schema_de!)]
@@ -883,6 +1427,45 @@ struct FilterOrPolicy {
 	#[serde(default)]
 	csrf: Option<http::csrf::Csrf>,
 
+	/// Verify HMAC signatures on incoming requests, rejecting unsigned or tampered requests.
+	#[serde(default)]
+	request_signature: Option<http::signature::RequestSignature>,
+
+	/// Allow or deny requests based on the client IP address.
+	#[serde(default)]
+	ip_filter: Option<http::ipfilter::IpFilter>,
+
+	/// Scan request and response bodies for sensitive data, masking or rejecting matches.
+	#[serde(default)]
+	dlp: Option<http::dlp::LocalDlp>,
+
+	/// Enforce daily or monthly request and LLM token budgets per authenticated identity.
+	#[serde(default)]
+	quota: Option<http::quota::Quota>,
+
+	/// Authorize requests with an Open Policy Agent decision, from a remote server or an embedded
+	/// Rego bundle.
+	#[serde(default)]
+	opa: Option<http::opa::LocalOpa>,
+
+	/// Send upstreams a short-lived token, signed by the gateway's token issuer, describing the
+	/// authenticated identity.
+	#[serde(default)]
+	session_token: Option<http::sessiontoken::SessionToken>,
+
+	/// Disable the access log for this route, or override when requests are recorded.
+	#[serde(default)]
+	access_log: Option<crate::telemetry::accesslog::Policy>,
+
+	/// Record full details of requests to this route that exceed a latency threshold, in the slow
+	/// request log.
+	#[serde(default)]
+	slow_request_log: Option<crate::telemetry::slowlog::Policy>,
+
+	/// Record prompts and completions of LLM requests to this route in the LLM log.
+	#[serde(default)]
+	llm_log: Option<crate::telemetry::llmlog::Policy>,
+
 	// TrafficPolicy
 	/// Timeout requests that exceed the configured duration.
 	#[serde(default)]
@@ -890,6 +1473,24 @@ struct FilterOrPolicy {
 	/// Retry matching requests.
 	#[serde(default)]
 	retry: Option<retry::Policy>,
+	/// Send a second attempt of slow idempotent requests, and return whichever responds first.
+	#[serde(default)]
+	hedging: Option<http::hedging::Policy>,
+	/// Fall back to other models when requests to an LLM fail, time out, or are rate limited.
+	#[serde(default)]
+	model_fallback: Option<crate::llm::fallback::Policy>,
+	/// Split requests to an LLM between model variants, to compare them.
+	#[serde(default)]
+	model_experiment: Option<crate::llm::experiment::Policy>,
+	/// Collect requests marked as batchable and submit them to an LLM provider's batch API.
+	#[serde(default)]
+	llm_batch: Option<crate::llm::batch::Policy>,
+	/// Validate that LLM responses are JSON matching a schema, retrying with a repair prompt.
+	#[serde(default)]
+	structured_output: Option<crate::llm::structured_output::Policy>,
+	/// Queue requests by priority while the backend is at its concurrency limit or rate limiting.
+	#[serde(default)]
+	request_queue: Option<http::queue::Policy>,
 }
 
 #[apply(schema_de!)]
@@ -897,6 +1498,9 @@ struct TCPFilterOrPolicy {
 	#[serde(default, skip_serializing_if = "Option::is_none")]
 	#[serde(rename = "backendTLS")]
 	backend_tls: Option<LocalBackendTLS>,
+	/// TCP settings for connections to the route's backends, such as an idle timeout.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	tcp: Option<backend::TCP>,
 }
 
 async fn convert(
@@ -912,12 +1516,29 @@ async fn convert(
 		workloads,
 		services,
 		backends,
+		serial,
 	} = i;
 	let mut all_policies = vec![];
 	let mut all_backends = vec![];
 	let mut all_binds = vec![];
 	for b in binds {
-		let bind_name = strng::format!("bind/{}", b.port);
+		let (bind_name, sockaddr) = match (&b.unix, b.port) {
+			(Some(unix), None) => (
+				strng::format!("bind/unix:{}", unix.path.display()),
+				// Not listened on, but connections over the socket have this address.
+				SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0),
+			),
+			(None, Some(port)) => {
+				let sockaddr = if cfg!(target_family = "unix") {
+					SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), port)
+				} else {
+					// Windows and IPv6 don't mix well apparently?
+					SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), port)
+				};
+				(strng::format!("bind/{port}"), sockaddr)
+			},
+			_ => anyhow::bail!("a bind must set exactly one of port and unix"),
+		};
 		let mut ls = ListenerSet::default();
 		for (idx, l) in b.listeners.into_iter().enumerate() {
 			let (l, pol, backends) = convert_listener(
@@ -932,18 +1553,13 @@ async fn convert(
 			all_backends.extend_from_slice(&backends);
 			ls.insert(l)
 		}
-		let sockaddr = if cfg!(target_family = "unix") {
-			SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), b.port)
-		} else {
-			// Windows and IPv6 don't mix well apparently?
-			SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), b.port)
-		};
 		let b = Bind {
 			key: bind_name,
 			address: sockaddr,
 			protocol: detect_bind_protocol(&ls),
 			listeners: ls,
 			tunnel_protocol: b.tunnel_protocol,
+			unix_socket: b.unix,
 		};
 		all_binds.push(b)
 	}
@@ -989,6 +1605,7 @@ async fn convert(
 		backends: all_backends.into_iter().collect(),
 		workloads,
 		services,
+		serial,
 	})
 }
 
@@ -1144,13 +1761,33 @@ async fn convert_route(
 		name,
 		hostnames,
 		matches,
+		grpc,
 		policies,
 		backends,
 	} = lr;
 
 	let route_name = name.name.unwrap_or_else(|| strng::format!("route{}", idx));
 	let namespace = name.namespace.unwrap_or_else(|| strng::new("default"));
-	let key = strng::format!("{listener_key}/{namespace}/{route_name}");
+	let key = match &name.rule_name {
+		Some(rule) => strng::format!("{listener_key}/{namespace}/{route_name}/{rule}"),
+		None => strng::format!("{listener_key}/{namespace}/{route_name}"),
+	};
+
+	let matches = match &grpc {
+		Some(grpc) => {
+			let paths = grpc.paths(&client, &backends).await.context("grpc")?;
+			matches
+				.iter()
+				.flat_map(|m| {
+					paths.iter().map(|p| RouteMatch {
+						path: p.clone(),
+						..m.clone()
+					})
+				})
+				.collect()
+		},
+		None => matches,
+	};
 
 	let mut backend_refs = Vec::new();
 	let mut external_backends = Vec::new();
@@ -1172,7 +1809,7 @@ async fn convert_route(
 			LocalBackend::Dynamic {} => BackendReference::Backend("dynamic".into()),
 			_ => BackendReference::Backend(strng::format!("/{}", backend_key)),
 		};
-		let backends = b.backend.as_backends(be_name.clone())?;
+		let backends = b.backend.as_backends(&client, be_name.clone()).await?;
 		let bref = RouteBackendReference {
 			weight: b.weight,
 			backend: bref,
@@ -1195,7 +1832,7 @@ async fn convert_route(
 		name: RouteName {
 			name: route_name,
 			namespace,
-			rule_name: None,
+			rule_name: name.rule_name,
 			kind: None,
 		},
 		hostnames,
@@ -1233,6 +1870,7 @@ async fn split_frontend_policies(
 		tcp,
 		access_log,
 		tracing,
+		request_id,
 	} = pol;
 	if let Some(p) = http {
 		add(FrontendPolicy::HTTP(p), "http");
@@ -1246,6 +1884,9 @@ async fn split_frontend_policies(
 	if let Some(p) = access_log {
 		add(FrontendPolicy::AccessLog(p), "accessLog");
 	}
+	if let Some(p) = request_id {
+		add(FrontendPolicy::RequestId(p), "requestId");
+	}
 	if let Some(tracing_config) = tracing {
 		// Build logging fields from attributes for lazy tracer creation
 		let logging_fields = Arc::new(crate::telemetry::log::LoggingFields {
@@ -1292,10 +1933,25 @@ async fn split_policies(client: Client, pol: FilterOrPolicy) -> Result<ResolvedP
 		api_key,
 		transformations,
 		csrf,
+		request_signature,
+		ip_filter,
+		dlp,
+		quota,
+		opa,
+		session_token,
+		access_log,
+		slow_request_log,
+		llm_log,
 		ext_authz,
 		ext_proc,
 		timeout,
 		retry,
+		hedging,
+		model_fallback,
+		model_experiment,
+		llm_batch,
+		structured_output,
+		request_queue,
 	} = pol;
 	if let Some(p) = request_header_modifier {
 		route_policies.push(TrafficPolicy::RequestHeaderModifier(p));
@@ -1361,6 +2017,33 @@ async fn split_policies(client: Client, pol: FilterOrPolicy) -> Result<ResolvedP
 	if let Some(p) = csrf {
 		route_policies.push(TrafficPolicy::Csrf(p))
 	}
+	if let Some(p) = request_signature {
+		route_policies.push(TrafficPolicy::RequestSignature(p))
+	}
+	if let Some(p) = ip_filter {
+		route_policies.push(TrafficPolicy::IpFilter(p))
+	}
+	if let Some(p) = dlp {
+		route_policies.push(TrafficPolicy::Dlp(p.try_into()?))
+	}
+	if let Some(p) = quota {
+		route_policies.push(TrafficPolicy::Quota(p))
+	}
+	if let Some(p) = opa {
+		route_policies.push(TrafficPolicy::Opa(p.try_into()?))
+	}
+	if let Some(p) = session_token {
+		route_policies.push(TrafficPolicy::SessionToken(p))
+	}
+	if let Some(p) = access_log {
+		route_policies.push(TrafficPolicy::AccessLog(p))
+	}
+	if let Some(p) = slow_request_log {
+		route_policies.push(TrafficPolicy::SlowRequestLog(p))
+	}
+	if let Some(p) = llm_log {
+		route_policies.push(TrafficPolicy::LlmLog(p))
+	}
 	if let Some(p) = authorization {
 		route_policies.push(TrafficPolicy::Authorization(p))
 	}
@@ -1384,6 +2067,25 @@ async fn split_policies(client: Client, pol: FilterOrPolicy) -> Result<ResolvedP
 	if let Some(p) = retry {
 		route_policies.push(TrafficPolicy::Retry(p));
 	}
+	if let Some(p) = hedging {
+		p.validate()?;
+		route_policies.push(TrafficPolicy::Hedging(p));
+	}
+	if let Some(p) = model_fallback {
+		route_policies.push(TrafficPolicy::ModelFallback(p));
+	}
+	if let Some(p) = model_experiment {
+		route_policies.push(TrafficPolicy::ModelExperiment(p));
+	}
+	if let Some(p) = llm_batch {
+		route_policies.push(TrafficPolicy::LlmBatch(p));
+	}
+	if let Some(p) = structured_output {
+		route_policies.push(TrafficPolicy::StructuredOutput(p));
+	}
+	if let Some(p) = request_queue {
+		route_policies.push(TrafficPolicy::RequestQueue(p));
+	}
 	Ok(resolved)
 }
 
@@ -1439,13 +2141,18 @@ async fn convert_tcp_route(
 	}
 
 	if let Some(pol) = policies {
-		let TCPFilterOrPolicy { backend_tls } = pol;
+		let TCPFilterOrPolicy { backend_tls, tcp } = pol;
 		if let Some(p) = backend_tls {
 			for br in backend_refs.iter_mut() {
 				br.inline_policies
 					.push(BackendPolicy::BackendTLS(p.clone().try_into()?));
 			}
 		}
+		if let Some(p) = tcp {
+			for br in backend_refs.iter_mut() {
+				br.inline_policies.push(BackendPolicy::TCP(p));
+			}
+		}
 	}
 	let route = TCPRoute {
 		key,
@@ -1479,14 +2186,31 @@ impl TryInto<ServerTLSConfig> for LocalTLSServerConfig {
 		let cert_pem = fs_err::read(self.cert)?;
 		let key_pem = fs_err::read(self.key)?;
 		let root_pem = self.root.map(fs_err::read).transpose()?;
+		let alpns = match self.alpn {
+			Some(alpn) => {
+				if alpn.is_empty() || alpn.iter().any(|a| a.is_empty()) {
+					bail!("alpn must contain at least one non-empty protocol");
+				}
+				alpn.into_iter().map(String::into_bytes).collect()
+			},
+			None => vec![b"h2".to_vec(), b"http/1.1".to_vec()],
+		};
 		ServerTLSConfig::from_pem_with_profile(
 			cert_pem,
 			key_pem,
 			root_pem,
-			vec![b"h2".to_vec(), b"http/1.1".to_vec()],
-			self.min_tls_version.map(Into::into),
-			self.max_tls_version.map(Into::into),
-			self.cipher_suites,
+			alpns,
+			ServerTLSOptions {
+				min_version: self.min_tls_version.map(Into::into),
+				max_version: self.max_tls_version.map(Into::into),
+				cipher_suites: self.cipher_suites,
+				session_tickets: self.session_tickets,
+				session_ticket_keys: self
+					.session_ticket_keys
+					.map(|k| k.build().map(Arc::new))
+					.transpose()
+					.map_err(|e| e.context("sessionTicketKeys"))?,
+			},
 		)
 	}
 }
@@ -0,0 +1,378 @@
+use serde_json::json;
+
+use super::*;
+use crate::test_helpers::proxymock::setup_proxy_test;
+
+fn gateway() -> ListenerTarget {
+	ListenerTarget {
+		gateway_name: "default".into(),
+		gateway_namespace: "default".into(),
+		listener_name: None,
+	}
+}
+
+async fn validate(
+	config: serde_json::Value,
+) -> Result<NormalizedLocalConfig, Vec<ValidationError>> {
+	let t = setup_proxy_test("{}").expect("setup proxy inputs");
+	NormalizedLocalConfig::validate(
+		t.inputs().upstream.clone(),
+		gateway(),
+		&config.to_string(),
+		ValidateOptions {
+			services_from_xds: false,
+			interpolate: true,
+		},
+	)
+	.await
+}
+
+#[tokio::test]
+async fn test_validate() {
+	let config = json!({
+		"binds": [
+			{"port": 8080, "listeners": [{
+				"name": "http",
+				"routes": [{"name": "api", "backends": [{"host": "127.0.0.1:9000"}]}],
+			}]},
+			{"port": 9000, "listeners": [{
+				"name": "tcp",
+				"protocol": "TCP",
+				"tcpRoutes": [{"backends": [{"backend": "/tools"}]}],
+			}]},
+		],
+		"backends": [{"name": "tools", "host": "127.0.0.1:9001"}],
+	});
+	let normalized = validate(config).await.unwrap();
+	assert_eq!(normalized.binds.len(), 2);
+}
+
+#[tokio::test]
+async fn test_validate_reports_every_error() {
+	let config = json!({
+		"binds": [
+			{"port": 8080, "listeners": [
+				{"name": "http", "routes": [
+					{"name": "api", "backends": [{"service": {"name": "default/missing.svc", "port": 80}}]},
+					{"name": "api", "backends": [{"host": "127.0.0.1:9000"}]},
+				]},
+				{"name": "http", "protocol": "HTTPS", "routes": [],
+					"tls": {"cert": "/missing/cert.pem", "key": "/missing/key.pem"}},
+			]},
+			{"port": 9000, "listeners": [{
+				"name": "tcp",
+				"protocol": "TCP",
+				"tcpRoutes": [{"backends": [{"backend": "tools"}]}],
+			}]},
+		],
+		"backends": [
+			{"name": "tools", "host": "127.0.0.1:9001"},
+			{"name": "tools", "host": "127.0.0.1:9002"},
+		],
+	});
+	let errors = validate(config).await.unwrap_err();
+	let found = errors
+		.iter()
+		.map(|e| (e.kind, e.location.as_deref().unwrap_or_default()))
+		.collect::<Vec<_>>();
+	use ValidationErrorKind::*;
+	assert_eq!(
+		found,
+		vec![
+			(MissingFile, "binds[0].listeners[1].tls.cert"),
+			(MissingFile, "binds[0].listeners[1].tls.key"),
+			(DuplicateName, "binds[0].listeners[0].routes[1].name"),
+			(DuplicateName, "binds[0].listeners[1].name"),
+			(DuplicateName, "backends[1].name"),
+			(
+				DanglingReference,
+				"binds[0].listeners[0].routes[0].backends[0].service"
+			),
+			(
+				DanglingReference,
+				"binds[1].listeners[0].tcpRoutes[0].backends[0].backend"
+			),
+		]
+	);
+	assert_eq!(
+		errors[4].message,
+		"backend tools is already defined at backends[0].name"
+	);
+	assert_eq!(
+		errors[6].message,
+		"backend tools is not defined; did you mean /tools?"
+	);
+}
+
+#[tokio::test]
+async fn test_validate_invalid_cel() {
+	let config = json!({
+		"binds": [{"port": 8080, "listeners": [{"routes": [{
+			"policies": {"authorization": {"rules": ["1 +"]}},
+			"backends": [{"host": "127.0.0.1:9000"}],
+		}]}]}],
+	});
+	let errors = validate(config).await.unwrap_err();
+	assert_eq!(errors.len(), 1);
+	assert_eq!(errors[0].kind, ValidationErrorKind::InvalidField);
+}
+
+#[tokio::test]
+async fn test_validate_secret_references() {
+	let config = |body: &str| {
+		json!({
+			"binds": [{"port": 8080, "listeners": [{"routes": [{
+				"policies": {"directResponse": {"body": body, "status": 200}},
+			}]}]}],
+		})
+	};
+	// References are not resolved, so no secret store is needed
+	validate(config("${secret:vault:kv/data/agw#token}"))
+		.await
+		.unwrap();
+	let errors = validate(config("${secret:unknown:kv/data/agw#token}"))
+		.await
+		.unwrap_err();
+	assert_eq!(errors.len(), 1);
+	assert_eq!(errors[0].kind, ValidationErrorKind::InvalidField);
+	assert!(errors[0].message.contains("unknown provider"));
+}
+
+#[tokio::test]
+async fn test_validate_without_interpolation() {
+	let t = setup_proxy_test("{}").expect("setup proxy inputs");
+	let config = json!({
+		"binds": [{"port": 8080, "listeners": [{"routes": [{
+			"policies": {"directResponse": {"body": "${file:/nonexistent/agw}", "status": 200}},
+		}]}]}],
+	})
+	.to_string();
+	let validate = |interpolate| {
+		NormalizedLocalConfig::validate(
+			t.inputs().upstream.clone(),
+			gateway(),
+			&config,
+			ValidateOptions {
+				services_from_xds: false,
+				interpolate,
+			},
+		)
+	};
+	let errors = validate(true).await.unwrap_err();
+	assert_eq!(errors[0].kind, ValidationErrorKind::Syntax);
+	// The file is never read, so the reference is kept as written
+	validate(false).await.unwrap();
+}
+
+#[test]
+fn test_backend_tcp_policy() {
+	let policies: LocalBackendPolicies = serde_json::from_value(json!({
+		"tcp": {
+			"keepalives": {"time": "30s", "interval": "5s", "retries": 3},
+			"connectTimeout": "2s",
+		},
+	}))
+	.unwrap();
+	let tcp = policies.tcp.unwrap();
+	assert_eq!(
+		tcp.keepalives,
+		types::agent::KeepaliveConfig {
+			enabled: true,
+			time: Duration::from_secs(30),
+			interval: Duration::from_secs(5),
+			retries: 3,
+		}
+	);
+	assert_eq!(tcp.connect_timeout, Duration::from_secs(2));
+	assert_eq!(tcp.proxy_protocol, None);
+
+	let policies: LocalBackendPolicies =
+		serde_json::from_value(json!({"tcp": {"proxyProtocol": "v2"}})).unwrap();
+	assert_eq!(
+		policies.tcp.unwrap().proxy_protocol,
+		Some(backend::ProxyProtocolVersion::V2)
+	);
+
+	let policies: LocalBackendPolicies = serde_json::from_value(json!({"tcp": {}})).unwrap();
+	assert_eq!(policies.tcp, Some(backend::TCP::default()));
+}
+
+#[tokio::test]
+async fn test_openapi_schema_url() {
+	use wiremock::matchers::{method, path};
+	use wiremock::{Mock, MockServer, ResponseTemplate};
+
+	let server = MockServer::start().await;
+	let spec = "openapi: 3.0.0
+info: {title: pets, version: '1'}
+paths:
+  /pets:
+    get:
+      operationId: listPets
+      responses: {'200': {description: ok}}
+";
+	Mock::given(method("GET"))
+		.and(path("/openapi.yaml"))
+		.respond_with(ResponseTemplate::new(200).set_body_string(spec))
+		.mount(&server)
+		.await;
+	let config = |url: String| {
+		json!({
+			"binds": [{"port": 8080, "listeners": [{"routes": [{"backends": [{"mcp": {"targets": [{
+				"name": "pets",
+				"openapi": {"host": "127.0.0.1:9000", "schema": {"url": url}},
+			}]}}]}]}]}],
+		})
+	};
+
+	let normalized = validate(config(format!("{}/openapi.yaml", server.uri())))
+		.await
+		.unwrap();
+	let schema = normalized
+		.backends
+		.iter()
+		.find_map(|b| match &b.backend {
+			Backend::MCP(_, m) => match &m.targets[0].spec {
+				McpTargetSpec::OpenAPI(o) => Some(o.schema.clone()),
+				_ => None,
+			},
+			_ => None,
+		})
+		.unwrap();
+	assert!(schema.paths.paths.contains_key("/pets"));
+
+	let errors = validate(config(format!("{}/missing.yaml", server.uri())))
+		.await
+		.unwrap_err();
+	assert_eq!(errors.len(), 1);
+	assert!(
+		errors[0].message.contains("status 404"),
+		"{}",
+		errors[0].message
+	);
+}
+
+#[tokio::test]
+async fn test_grpc_route() {
+	use prost::Message;
+	use prost_types::{
+		FileDescriptorProto, FileDescriptorSet, MethodDescriptorProto, ServiceDescriptorProto,
+	};
+
+	let set = FileDescriptorSet {
+		file: vec![FileDescriptorProto {
+			package: Some("helloworld".to_string()),
+			service: vec![ServiceDescriptorProto {
+				name: Some("Greeter".to_string()),
+				method: vec![MethodDescriptorProto {
+					name: Some("SayHello".to_string()),
+					..Default::default()
+				}],
+				..Default::default()
+			}],
+			..Default::default()
+		}],
+	};
+	let dir = tempfile::tempdir().unwrap();
+	let descriptors = dir.path().join("descriptors.pb");
+	fs_err::write(&descriptors, set.encode_to_vec()).unwrap();
+
+	let config = json!({
+		"binds": [{"port": 8080, "listeners": [{"routes": [{
+			"matches": [{"path": {"pathPrefix": "/"}, "headers": [{"name": "x-env", "value": {"exact": "prod"}}]}],
+			"grpc": {"services": ["echo.Echo"], "descriptors": descriptors},
+			"backends": [{"host": "127.0.0.1:9000"}],
+		}]}]}],
+	});
+	let normalized = validate(config).await.unwrap();
+	let listener = normalized.binds[0].listeners.get_exactly_one().unwrap();
+	let matches: Vec<_> = listener
+		.routes
+		.get_hostname(&crate::types::agent::HostnameMatchRef::None)
+		.map(|(_, m)| {
+			// Other conditions of the match are kept.
+			assert_eq!(m.headers.len(), 1);
+			match &m.path {
+				PathMatch::Exact(p) => format!("exact {p}"),
+				PathMatch::PathPrefix(p) => format!("prefix {p}"),
+				PathMatch::Regex(..) => panic!("unexpected regex"),
+			}
+		})
+		.collect();
+	assert_eq!(matches.len(), 2);
+	assert!(matches.contains(&"prefix /echo.Echo".to_string()));
+	assert!(matches.contains(&"exact /helloworld.Greeter/SayHello".to_string()));
+
+	let config = json!({
+		"binds": [{"port": 8080, "listeners": [{"routes": [{
+			"grpc": {},
+			"backends": [{"host": "127.0.0.1:9000"}],
+		}]}]}],
+	});
+	let errors = validate(config).await.unwrap_err();
+	assert!(
+		errors[0].message.contains("no gRPC services"),
+		"{}",
+		errors[0].message
+	);
+}
+
+#[tokio::test]
+async fn test_unix_bind() {
+	let bind = |b: serde_json::Value| {
+		let mut b = b;
+		b["listeners"] = json!([{"routes": [{"backends": [{"host": "127.0.0.1:9000"}]}]}]);
+		json!({"binds": [b]})
+	};
+	let normalized = validate(bind(
+		json!({"unix": {"path": "/run/agentgateway.sock", "mode": "0660"}}),
+	))
+	.await
+	.unwrap();
+	let b = &normalized.binds[0];
+	assert_eq!(b.key, "bind/unix:/run/agentgateway.sock");
+	let unix = b.unix_socket.as_ref().unwrap();
+	assert_eq!(unix.path, PathBuf::from("/run/agentgateway.sock"));
+	assert_eq!(unix.mode, Some(0o660));
+
+	let errors = validate(bind(
+		json!({"port": 8080, "unix": {"path": "/run/agentgateway.sock"}}),
+	))
+	.await
+	.unwrap_err();
+	assert!(
+		errors[0].message.contains("exactly one of port and unix"),
+		"{}",
+		errors[0].message
+	);
+	assert!(
+		validate(bind(
+			json!({"unix": {"path": "/run/agentgateway.sock", "mode": "0999"}})
+		))
+		.await
+		.is_err()
+	);
+}
+
+#[tokio::test]
+async fn test_tcp_route_idle_timeout() {
+	let config = json!({
+		"binds": [{"port": 5432, "listeners": [{
+			"protocol": "TCP",
+			"tcpRoutes": [{
+				"policies": {"tcp": {"idleTimeout": "5m"}},
+				"backends": [{"host": "127.0.0.1:5433"}],
+			}],
+		}]}],
+	});
+	let normalized = validate(config).await.unwrap();
+	let listener = normalized.binds[0].listeners.get_exactly_one().unwrap();
+	let route = listener
+		.tcp_routes
+		.get_hostname(&crate::types::agent::HostnameMatchRef::None)
+		.unwrap();
+	assert_matches::assert_matches!(
+		route.backends[0].inline_policies.as_slice(),
+		[BackendPolicy::TCP(tcp)] if tcp.idle_timeout == Some(Duration::from_secs(300))
+	);
+}
@@ -4,7 +4,9 @@ use std::marker::PhantomPinned;
 use std::mem::MaybeUninit;
 use std::net::SocketAddr;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::task::{Context, Poll, ready};
+use std::time::{Duration, Instant};
 
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 use pin_project_lite::pin_project;
@@ -164,14 +166,51 @@ const RESIZE_THRESHOLD_LARGE: u64 = 128 * 1024;
 // After 10Mb of data we will trigger a resize from LARGE to JUMBO
 const RESIZE_THRESHOLD_JUMBO: u64 = 10 * 1024 * 1024;
 
-pub struct ConnectionResult {}
+// ConnectionResult counts the bytes copied in each direction, and tracks when bytes were last
+// copied so idle connections can be closed.
+pub struct ConnectionResult {
+	start: Instant,
+	sent: AtomicU64,
+	received: AtomicU64,
+	// When bytes were last copied in either direction, in milliseconds since `start`.
+	last_active: AtomicU64,
+}
+
+impl Default for ConnectionResult {
+	fn default() -> Self {
+		Self {
+			start: Instant::now(),
+			sent: AtomicU64::new(0),
+			received: AtomicU64::new(0),
+			last_active: AtomicU64::new(0),
+		}
+	}
+}
 
 impl ConnectionResult {
-	pub fn increment_recv(&self, _: u64) {
-		// TODO
+	pub fn increment_recv(&self, amt: u64) {
+		self.received.fetch_add(amt, Ordering::Relaxed);
+		self.touch();
+	}
+	pub fn increment_send(&self, amt: u64) {
+		self.sent.fetch_add(amt, Ordering::Relaxed);
+		self.touch();
+	}
+	fn touch(&self) {
+		let now = self.start.elapsed().as_millis() as u64;
+		self.last_active.store(now, Ordering::Relaxed);
+	}
+	// load returns the bytes sent to and received from the downstream.
+	pub fn load(&self) -> (u64, u64) {
+		(
+			self.sent.load(Ordering::Relaxed),
+			self.received.load(Ordering::Relaxed),
+		)
 	}
-	pub fn increment_send(&self, _: u64) {
-		// TODO
+	// idle returns how long it has been since bytes were copied in either direction.
+	pub fn idle(&self) -> Duration {
+		let last_active = Duration::from_millis(self.last_active.load(Ordering::Relaxed));
+		self.start.elapsed().saturating_sub(last_active)
 	}
 }
 
@@ -9,6 +9,8 @@ mod msg;
 mod nonblocking;
 mod worker;
 
+pub mod logexport;
+
 use std::cell::RefCell;
 use std::fmt::{Debug, Display, Write as FmtWrite};
 use std::str::FromStr;
@@ -170,6 +172,7 @@ pub fn setup_logging(default_level: &str, json: bool) -> nonblocking::WorkerGuar
 	let _ = NON_BLOCKING.set((non_blocking.clone(), use_json));
 	tracing_subscriber::registry()
 		.with(fmt_layer(non_blocking, default_level, use_json))
+		.with(logexport::layer())
 		.init();
 	_guard
 }
@@ -277,6 +280,8 @@ pub enum Error {
 	Reload(#[from] reload::Error),
 	#[error("logging is not initialized")]
 	Uninitialized,
+	#[error("log export is already initialized")]
+	AlreadyInitialized,
 }
 
 // IstioFormat encodes logs in the "standard" Istio JSON formatting used in the rest of the code
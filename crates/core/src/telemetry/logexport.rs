@@ -0,0 +1,149 @@
+// Export of our own log events to an OpenTelemetry logs endpoint, alongside stdout.
+// The layer is always installed, but is disabled until `init` is called with an exporter, since the
+// exporter is only known once configuration is loaded.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use once_cell::sync::OnceCell;
+use opentelemetry::logs::{AnyValue, LogRecord, Logger, LoggerProvider, Severity};
+use opentelemetry_sdk::logs::{SdkLogger, SdkLoggerProvider};
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_core::Field;
+use tracing_core::field::Visit;
+use tracing_core::subscriber::Interest;
+use tracing_log::NormalizeEvent;
+use tracing_subscriber::filter;
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+use super::Error;
+
+static EXPORTER: OnceCell<Exporter> = OnceCell::new();
+
+// The exporter logs through these targets itself; exporting them could feed back indefinitely.
+const EXCLUDED_TARGETS: &str = "opentelemetry=off,opentelemetry_sdk=off,opentelemetry_otlp=off,opentelemetry_http=off,hyper=off,hyper_util=off,h2=off,tonic=off,tower=off,reqwest=off";
+
+struct Exporter {
+	provider: SdkLoggerProvider,
+	logger: SdkLogger,
+	filter: filter::Targets,
+}
+
+/// Start exporting log events through `provider`. Events are filtered by `level` (in the same
+/// syntax as the stdout log level), independently of stdout.
+pub fn init(provider: SdkLoggerProvider, level: &str) -> Result<(), Error> {
+	let filter = filter::Targets::from_str(&format!(
+		"{},{EXCLUDED_TARGETS}",
+		super::default_filter(level)
+	))?;
+	let logger = provider.logger("agentgateway");
+	EXPORTER
+		.set(Exporter {
+			provider,
+			logger,
+			filter,
+		})
+		.map_err(|_| Error::AlreadyInitialized)?;
+	// Callsites were registered while export was disabled; re-evaluate them.
+	tracing_core::callsite::rebuild_interest_cache();
+	Ok(())
+}
+
+/// Flush any buffered events and stop exporting.
+pub fn shutdown() {
+	if let Some(e) = EXPORTER.get()
+		&& let Err(err) = e.provider.shutdown()
+	{
+		tracing::warn!("failed to shutdown log exporter: {err}");
+	}
+}
+
+fn enabled(meta: &Metadata<'_>) -> bool {
+	EXPORTER
+		.get()
+		.is_some_and(|e| e.filter.would_enable(meta.target(), meta.level()))
+}
+
+pub(super) fn layer<S>() -> impl Layer<S>
+where
+	S: Subscriber + for<'a> LookupSpan<'a>,
+{
+	let filter = filter::dynamic_filter_fn(|meta, _| enabled(meta)).with_callsite(|meta| {
+		if enabled(meta) {
+			Interest::always()
+		} else {
+			Interest::never()
+		}
+	});
+	ExportLayer.with_filter(filter)
+}
+
+struct ExportLayer;
+
+impl<S: Subscriber> Layer<S> for ExportLayer {
+	fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+		let Some(e) = EXPORTER.get() else {
+			return;
+		};
+		let normalized = event.normalized_metadata();
+		let meta = normalized.as_ref().unwrap_or_else(|| event.metadata());
+		let now = SystemTime::now();
+		let mut record = e.logger.create_log_record();
+		record.set_timestamp(now);
+		record.set_observed_timestamp(now);
+		record.set_target(meta.target().to_string());
+		record.set_severity_number(severity(meta.level()));
+		record.set_severity_text(meta.level().as_str());
+		event.record(&mut RecordVisitor(&mut record));
+		e.logger.emit(record);
+	}
+}
+
+fn severity(level: &Level) -> Severity {
+	match *level {
+		Level::TRACE => Severity::Trace,
+		Level::DEBUG => Severity::Debug,
+		Level::INFO => Severity::Info,
+		Level::WARN => Severity::Warn,
+		Level::ERROR => Severity::Error,
+	}
+}
+
+struct RecordVisitor<'a, R>(&'a mut R);
+
+impl<R: LogRecord> RecordVisitor<'_, R> {
+	fn record(&mut self, field: &Field, value: AnyValue) {
+		match field.name() {
+			"message" => self.0.set_body(value),
+			// Added by the `log` bridge, and already reflected in the target
+			n if n.starts_with("log.") => {},
+			n => self.0.add_attribute(n, value),
+		}
+	}
+}
+
+impl<R: LogRecord> Visit for RecordVisitor<'_, R> {
+	fn record_f64(&mut self, field: &Field, value: f64) {
+		self.record(field, value.into())
+	}
+	fn record_i64(&mut self, field: &Field, value: i64) {
+		self.record(field, value.into())
+	}
+	fn record_u64(&mut self, field: &Field, value: u64) {
+		match i64::try_from(value) {
+			Ok(v) => self.record(field, v.into()),
+			Err(_) => self.record(field, value.to_string().into()),
+		}
+	}
+	fn record_bool(&mut self, field: &Field, value: bool) {
+		self.record(field, value.into())
+	}
+	fn record_str(&mut self, field: &Field, value: &str) {
+		self.record(field, value.to_string().into())
+	}
+	fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+		self.record(field, format!("{value:?}").into())
+	}
+}
@@ -4,10 +4,13 @@
 //! For now, to enable people to use hyper 1.0 quicker, this `Client` exists
 //! in much the same way it did in hyper 0.14.
 
+use std::collections::HashMap;
 use std::error::Error as StdError;
 use std::fmt;
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
 use std::task::{self, Poll};
 use std::time::Duration;
 
@@ -28,6 +31,9 @@ use crate::common::{lazy as hyper_lazy, timer, Exec, Lazy, SyncWrapper};
 
 type BoxSendFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
 
+/// How often a request waiting at the connection limit checks whether a connection has closed.
+const CONNECTION_LIMIT_RECHECK: Duration = Duration::from_millis(100);
+
 /// A Client to make outgoing HTTP requests.
 ///
 /// `Client` is cheap to clone and cloning is the recommended way to share a `Client`. The
@@ -41,9 +47,49 @@ pub struct Client<C, B, PK: pool::Key = DefaultPoolKey> {
 	h1_builder: hyper::client::conn::http1::Builder,
 	#[cfg(feature = "http2")]
 	h2_builder: hyper::client::conn::http2::Builder<Exec>,
+	#[cfg(feature = "http2")]
+	http2_settings: Arc<OnceLock<Arc<Http2SettingsFn<PK>>>>,
 	pool: pool::Pool<PoolClient<B>, PK>,
 }
 
+/// HTTP/2 settings for the connections of a pool key, overriding those set on the `Builder`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Http2Settings {
+	/// See `Builder::http2_initial_stream_window_size`.
+	pub initial_stream_window_size: Option<u32>,
+	/// See `Builder::http2_initial_connection_window_size`.
+	pub initial_connection_window_size: Option<u32>,
+	/// See `Builder::http2_max_frame_size`.
+	pub max_frame_size: Option<u32>,
+	/// See `Builder::http2_keep_alive_interval`.
+	pub keep_alive_interval: Option<Duration>,
+	/// See `Builder::http2_keep_alive_timeout`.
+	pub keep_alive_timeout: Option<Duration>,
+}
+
+type Http2SettingsFn<K> = dyn Fn(&K) -> Http2Settings + Send + Sync;
+
+#[cfg(feature = "http2")]
+impl Http2Settings {
+	fn apply(&self, b: &mut hyper::client::conn::http2::Builder<Exec>) {
+		if let Some(sz) = self.initial_stream_window_size {
+			b.initial_stream_window_size(sz);
+		}
+		if let Some(sz) = self.initial_connection_window_size {
+			b.initial_connection_window_size(sz);
+		}
+		if let Some(sz) = self.max_frame_size {
+			b.max_frame_size(sz);
+		}
+		if let Some(interval) = self.keep_alive_interval {
+			b.keep_alive_interval(interval);
+		}
+		if let Some(timeout) = self.keep_alive_timeout {
+			b.keep_alive_timeout(timeout);
+		}
+	}
+}
+
 #[derive(Clone, Copy, Debug)]
 struct Config {
 	retry_canceled_requests: bool,
@@ -217,6 +263,27 @@ where
 		ResponseFuture::new(self.clone().send_request(req))
 	}
 
+	/// Set the observer for connection lifecycle events of the pool.
+	pub fn set_pool_observer(&self, observer: Arc<dyn pool::Observer<PK>>) {
+		self.pool.set_observer(observer);
+	}
+
+	/// Returns the current state of the pooled connections for each pool key.
+	pub fn pool_snapshot(&self) -> HashMap<PK, pool::Snapshot> {
+		self.pool.snapshot()
+	}
+
+	/// Set the limits for the pooled connections of each pool key.
+	pub fn set_pool_limits(&self, limits: Arc<dyn Fn(&PK) -> pool::Limits + Send + Sync>) {
+		self.pool.set_limits(limits);
+	}
+
+	/// Set the HTTP/2 settings for the connections of each pool key.
+	#[cfg(feature = "http2")]
+	pub fn set_http2_settings(&self, settings: Arc<Http2SettingsFn<PK>>) {
+		let _ = self.http2_settings.set(settings);
+	}
+
 	async fn send_request(
 		self,
 		mut req: Request<B>,
@@ -394,6 +461,33 @@ where
 				.map_err(ClientConnectError::Normal);
 		}
 
+		let limits = self.pool.limits(pool_key);
+		if let Some(max) = limits.max_connections {
+			while self.pool.connections(pool_key) >= max {
+				// At the connection limit, so wait for a connection to be returned to the pool. A
+				// connection may close instead, so check the limit again periodically.
+				trace!("connection limit reached for {:?}", pool_key);
+				let checkout = self.pool.checkout(pool_key.clone());
+				let checked_out = match self.pool.sleep(CONNECTION_LIMIT_RECHECK) {
+					Some(recheck) => match future::select(checkout, recheck).await {
+						Either::Left((checked_out, _)) => checked_out,
+						Either::Right(_) => continue,
+					},
+					None => checkout.await,
+				};
+				return checked_out.map_err(|err| {
+					if err.is_canceled() {
+						ClientConnectError::CheckoutIsClosed(err)
+					} else {
+						ClientConnectError::Normal(e!(Connect, err))
+					}
+				});
+			}
+		}
+		if limits.warm_connections > 0 && dst.version != Version::HTTP_2 {
+			self.warm(dst, pool_key, &limits);
+		}
+
 		// This actually races 2 different futures to try to get a ready
 		// connection the fastest, and to reduce connection churn.
 		//
@@ -474,6 +568,31 @@ where
 		}
 	}
 
+	/// Open connections in the background, so the pool holds `warm_connections` once the request
+	/// that triggered this has its own connection.
+	fn warm(&self, dst: &http::request::Parts, pk: &PK, limits: &pool::Limits) {
+		if self.pool.connections(pk) > 0 {
+			return;
+		}
+		let mut warm = limits.warm_connections.saturating_sub(1);
+		if let Some(max) = limits.max_connections {
+			warm = warm.min(max.saturating_sub(1));
+		}
+		if warm == 0 {
+			return;
+		}
+		trace!("warming {} connections for {:?}", warm, pk);
+		for _ in 0..warm {
+			let connect = self
+				.connect_to(dst, pk)
+				.map_err(|err| trace!("warm connect error: {}", err))
+				.map(|_pooled| {
+					// dropping here places the connection in the pool
+				});
+			self.exec.execute(connect);
+		}
+	}
+
 	#[cfg(any(feature = "http1", feature = "http2"))]
 	fn connect_to(
 		&self,
@@ -486,7 +605,13 @@ where
 		#[cfg(feature = "http1")]
 		let h1_builder = self.h1_builder.clone();
 		#[cfg(feature = "http2")]
-		let h2_builder = self.h2_builder.clone();
+		let h2_builder = {
+			let mut b = self.h2_builder.clone();
+			if let Some(settings) = self.http2_settings.get() {
+				settings(pk).apply(&mut b);
+			}
+			b
+		};
 		// let ver = self.config.ver;
 		let ver = if dst.version == Version::HTTP_2 {
 			Ver::Http2
@@ -501,6 +626,8 @@ where
 		let pkc = pk.clone();
 		ext.insert(pkc);
 		let pkc = pk.clone();
+		let stats_key = pk.clone();
+		let max_requests = pool.limits(pk).max_requests;
 		hyper_lazy(move || {
 			// Try to take a "connecting lock".
 			//
@@ -516,10 +643,16 @@ where
 					return Either::Right(future::err(canceled));
 				},
 			};
+			let establishing = pool.establishing(stats_key.clone());
+			let failed_pool = pool.clone();
+			let failed_key = stats_key.clone();
 			Either::Left(
 				connector
 					.connect(super::connect::sealed::Internal, ext)
-					.map_err(|src| e!(Connect, src))
+					.map_err(move |src| {
+						failed_pool.notify(&failed_key, pool::Event::ConnectFailed);
+						e!(Connect, src)
+					})
 					.and_then(move |io| {
 						let connected = io.connected();
 						// If ALPN is h2 and we aren't http2_only already,
@@ -552,10 +685,13 @@ where
 									let (mut tx, conn) = h2_builder.handshake(io).await.map_err(Error::tx)?;
 
 									trace!("http2 handshake complete, spawning background dispatcher task");
+									// The connection is open until its background task completes.
+									let open = pool.opened(stats_key);
+									drop(establishing);
 									executor.execute(
 										conn
 											.map_err(|e| debug!("client connection error: {}", e))
-											.map(|_| ()),
+											.map(move |_| drop(open)),
 									);
 
 									// Wait for 'conn' to ready up before we
@@ -583,6 +719,9 @@ where
 									// err_tx sends errors from the connection task, and err_rx receives them
 									// to correlate connection failures with request readiness errors.
 									let (err_tx, err_rx) = tokio::sync::oneshot::channel();
+									// The connection is open until its background task completes.
+									let open = pool.opened(stats_key);
+									drop(establishing);
 									// Spawn the connection task in the background using the executor.
 									// The task manages the HTTP/1.1 connection, including upgrades (e.g., WebSocket).
 									// Errors are sent via err_tx to ensure they can be checked if the sender (tx) fails.
@@ -598,7 +737,7 @@ where
 												// (e.g., if the receiver is dropped, which is handled later).
 												let _ = err_tx.send(e);
 											})
-											.map(|_| ()),
+											.map(move |_| drop(open)),
 									);
 									// Log that the client is waiting for the connection to be ready.
 									// Readiness indicates the sender (tx) can accept a request without blocking.
@@ -664,6 +803,8 @@ where
 								PoolClient {
 									conn_info: connected,
 									tx,
+									sent: Arc::new(AtomicUsize::new(0)),
+									max_requests,
 								},
 							))
 						}))
@@ -722,6 +863,8 @@ impl<C: Clone, B, PK: pool::Key> Clone for Client<C, B, PK> {
 			h1_builder: self.h1_builder.clone(),
 			#[cfg(feature = "http2")]
 			h2_builder: self.h2_builder.clone(),
+			#[cfg(feature = "http2")]
+			http2_settings: self.http2_settings.clone(),
 			connector: self.connector.clone(),
 			pool: self.pool.clone(),
 		}
@@ -768,6 +911,9 @@ impl Future for ResponseFuture {
 struct PoolClient<B> {
 	conn_info: Connected,
 	tx: PoolTx<B>,
+	// Requests sent on the connection, shared by all reservations of an HTTP/2 connection.
+	sent: Arc<AtomicUsize>,
+	max_requests: Option<usize>,
 }
 
 enum PoolTx<B> {
@@ -807,6 +953,13 @@ impl<B> PoolClient<B> {
 		self.conn_info.poisoned.poisoned()
 	}
 
+	/// Whether the connection has sent as many requests as it is allowed to.
+	fn is_exhausted(&self) -> bool {
+		self
+			.max_requests
+			.is_some_and(|max| self.sent.load(Ordering::Relaxed) >= max)
+	}
+
 	fn is_ready(&self) -> bool {
 		match self.tx {
 			#[cfg(feature = "http1")]
@@ -825,6 +978,7 @@ impl<B: Body + 'static> PoolClient<B> {
 	where
 		B: Send,
 	{
+		self.sent.fetch_add(1, Ordering::Relaxed);
 		#[cfg(all(feature = "http1", feature = "http2"))]
 		return match self.tx {
 			#[cfg(feature = "http1")]
@@ -854,7 +1008,7 @@ where
 	B: Send + 'static,
 {
 	fn is_open(&self) -> bool {
-		!self.is_poisoned() && self.is_ready()
+		!self.is_poisoned() && !self.is_exhausted() && self.is_ready()
 	}
 
 	fn reserve(self) -> pool::Reservation<Self> {
@@ -863,16 +1017,22 @@ where
 			PoolTx::Http1(tx) => pool::Reservation::Unique(PoolClient {
 				conn_info: self.conn_info,
 				tx: PoolTx::Http1(tx),
+				sent: self.sent,
+				max_requests: self.max_requests,
 			}),
 			#[cfg(feature = "http2")]
 			PoolTx::Http2(tx) => {
 				let b = PoolClient {
 					conn_info: self.conn_info.clone(),
 					tx: PoolTx::Http2(tx.clone()),
+					sent: self.sent.clone(),
+					max_requests: self.max_requests,
 				};
 				let a = PoolClient {
 					conn_info: self.conn_info,
 					tx: PoolTx::Http2(tx),
+					sent: self.sent,
+					max_requests: self.max_requests,
 				};
 				pool::Reservation::Shared(a, b)
 			},
@@ -1552,6 +1712,8 @@ impl Builder {
 			h1_builder: self.h1_builder.clone(),
 			#[cfg(feature = "http2")]
 			h2_builder: self.h2_builder.clone(),
+			#[cfg(feature = "http2")]
+			http2_settings: Default::default(),
 			connector,
 			pool: pool::Pool::<_, PK>::new(self.pool_config, exec, timer),
 		}
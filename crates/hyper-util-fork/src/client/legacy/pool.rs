@@ -8,7 +8,7 @@ use std::future::Future;
 use std::hash::Hash;
 use std::ops::{Deref, DerefMut};
 use std::pin::Pin;
-use std::sync::{Arc, Mutex, Weak};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
 use std::task::{self, Poll};
 use std::time::{Duration, Instant};
 
@@ -26,6 +26,114 @@ use crate::common::timer::Timer;
 pub struct Pool<T, K: Key> {
 	// If the pool is disabled, this is None.
 	inner: Option<Arc<Mutex<PoolInner<T, K>>>>,
+	// Tracked even if the pool is disabled.
+	stats: Arc<Stats<K>>,
+}
+
+/// Connection lifecycle events reported to an [`Observer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+	/// A new connection was established.
+	Created,
+	/// An idle connection was used for a request.
+	Reused,
+	/// An idle connection was discarded, because it expired, was closed, or the pool was full.
+	Evicted,
+	/// Establishing a new connection failed.
+	ConnectFailed,
+}
+
+/// Receives connection lifecycle events from the pool. Events may be reported while the pool is
+/// locked, so implementations must not call back into the pool.
+pub trait Observer<K>: Send + Sync {
+	fn on_event(&self, key: &K, event: Event);
+}
+
+/// A point in time view of the connections for one key.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Snapshot {
+	/// Connections that are open, whether idle or in use.
+	pub open: usize,
+	/// Connections waiting in the pool to be reused.
+	pub idle: usize,
+	/// Requests waiting for a connection, and connections being established.
+	pub pending: usize,
+}
+
+/// Limits for the connections of one key, overriding the pool config.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Limits {
+	/// The maximum number of connections, including those being established. Once reached, requests
+	/// wait for a connection to be returned to the pool.
+	pub max_connections: Option<usize>,
+	/// The maximum number of idle connections kept in the pool.
+	pub max_idle: Option<usize>,
+	/// How long a connection may stay idle before it is closed.
+	pub idle_timeout: Option<Duration>,
+	/// The maximum number of requests sent on a connection before it is closed.
+	pub max_requests: Option<usize>,
+	/// The number of connections opened ahead of demand when a request finds none open.
+	pub warm_connections: usize,
+}
+
+type LimitsFn<K> = dyn Fn(&K) -> Limits + Send + Sync;
+
+struct Stats<K> {
+	observer: OnceLock<Arc<dyn Observer<K>>>,
+	limits: OnceLock<Arc<LimitsFn<K>>>,
+	open: Mutex<HashMap<K, usize>>,
+	establishing: Mutex<HashMap<K, usize>>,
+}
+
+impl<K: Key> Stats<K> {
+	fn notify(&self, key: &K, event: Event) {
+		if let Some(o) = self.observer.get() {
+			o.on_event(key, event);
+		}
+	}
+
+	fn limits(&self, key: &K) -> Limits {
+		self.limits.get().map(|l| l(key)).unwrap_or_default()
+	}
+}
+
+fn increment<K: Key>(counts: &Mutex<HashMap<K, usize>>, key: &K) {
+	*counts.lock().unwrap().entry(key.clone()).or_default() += 1;
+}
+
+fn decrement<K: Key>(counts: &Mutex<HashMap<K, usize>>, key: &K) {
+	if let Ok(mut counts) = counts.lock() {
+		if let Some(n) = counts.get_mut(key) {
+			*n -= 1;
+			if *n == 0 {
+				counts.remove(key);
+			}
+		}
+	}
+}
+
+/// Tracks an open connection, until it is dropped.
+pub(super) struct OpenConnection<K: Key> {
+	key: K,
+	stats: Arc<Stats<K>>,
+}
+
+impl<K: Key> Drop for OpenConnection<K> {
+	fn drop(&mut self) {
+		decrement(&self.stats.open, &self.key);
+	}
+}
+
+/// Tracks a connection being established, until it is dropped.
+pub(super) struct EstablishingConnection<K: Key> {
+	key: K,
+	stats: Arc<Stats<K>>,
+}
+
+impl<K: Key> Drop for EstablishingConnection<K> {
+	fn drop(&mut self) {
+		decrement(&self.stats.establishing, &self.key);
+	}
 }
 
 // Before using a pooled connection, make sure the sender is not dead.
@@ -81,6 +189,9 @@ struct PoolInner<T, K: Eq + Hash> {
 	// state, waiting to receive a new Request to send on the socket.
 	idle: HashMap<K, Vec<Idle<T>>>,
 	max_idle_per_host: usize,
+	// How often idle connections are checked for expiry. This is the shortest idle timeout of the
+	// pool and any key.
+	reap_interval: Option<Duration>,
 	// These are outstanding Checkouts that are waiting for a socket to be
 	// able to send a Request one. This is used when "racing" for a new
 	// connection.
@@ -97,6 +208,7 @@ struct PoolInner<T, K: Eq + Hash> {
 	exec: Exec,
 	timer: Option<Timer>,
 	timeout: Option<Duration>,
+	stats: Arc<Stats<K>>,
 }
 
 // This is because `Weak::new()` *allocates* space for `T`, even if it
@@ -123,28 +235,120 @@ impl<T, K: Key> Pool<T, K> {
 	{
 		let exec = Exec::new(executor);
 		let timer = timer.map(|t| Timer::new(t));
+		let stats = Arc::new(Stats {
+			observer: OnceLock::new(),
+			limits: OnceLock::new(),
+			open: Mutex::new(HashMap::new()),
+			establishing: Mutex::new(HashMap::new()),
+		});
 		let inner = if config.is_enabled() {
 			Some(Arc::new(Mutex::new(PoolInner {
 				connecting: HashSet::new(),
 				idle: HashMap::new(),
 				idle_interval_ref: None,
 				max_idle_per_host: config.max_idle_per_host,
+				reap_interval: config.idle_timeout,
 				waiters: HashMap::new(),
 				exec,
 				timer,
 				timeout: config.idle_timeout,
+				stats: stats.clone(),
 			})))
 		} else {
 			None
 		};
 
-		Pool { inner }
+		Pool { inner, stats }
 	}
 
 	pub(crate) fn is_enabled(&self) -> bool {
 		self.inner.is_some()
 	}
 
+	/// Set the observer for connection lifecycle events. Only the first observer set is used.
+	pub fn set_observer(&self, observer: Arc<dyn Observer<K>>) {
+		let _ = self.stats.observer.set(observer);
+	}
+
+	/// Set the limits for the connections of each key. Only the first function set is used.
+	pub fn set_limits(&self, limits: Arc<LimitsFn<K>>) {
+		let _ = self.stats.limits.set(limits);
+	}
+
+	pub(super) fn limits(&self, key: &K) -> Limits {
+		self.stats.limits(key)
+	}
+
+	pub(super) fn notify(&self, key: &K, event: Event) {
+		self.stats.notify(key, event);
+	}
+
+	/// Record a connection being established, which is counted until the returned value is dropped.
+	pub(super) fn establishing(&self, key: K) -> EstablishingConnection<K> {
+		increment(&self.stats.establishing, &key);
+		EstablishingConnection {
+			key,
+			stats: self.stats.clone(),
+		}
+	}
+
+	/// Record a newly established connection, which is counted as open until the returned value is
+	/// dropped.
+	pub(super) fn opened(&self, key: K) -> OpenConnection<K> {
+		increment(&self.stats.open, &key);
+		self.stats.notify(&key, Event::Created);
+		OpenConnection {
+			key,
+			stats: self.stats.clone(),
+		}
+	}
+
+	/// Returns the number of connections for a key that are open or being established.
+	pub(super) fn connections(&self, key: &K) -> usize {
+		let open = self.stats.open.lock().unwrap().get(key).copied();
+		let establishing = self.stats.establishing.lock().unwrap().get(key).copied();
+		open.unwrap_or_default() + establishing.unwrap_or_default()
+	}
+
+	/// Returns a future that completes after `duration`, if the pool has a timer.
+	pub(super) fn sleep(&self, duration: Duration) -> Option<Pin<Box<dyn Sleep>>> {
+		let inner = self.inner.as_ref()?.lock().unwrap();
+		inner.timer.as_ref().map(|t| t.sleep(duration))
+	}
+
+	/// Returns the current state of the connections for each key.
+	pub fn snapshot(&self) -> HashMap<K, Snapshot> {
+		let mut snapshot: HashMap<K, Snapshot> = self
+			.stats
+			.open
+			.lock()
+			.unwrap()
+			.iter()
+			.map(|(k, open)| {
+				(
+					k.clone(),
+					Snapshot {
+						open: *open,
+						..Default::default()
+					},
+				)
+			})
+			.collect();
+		if let Some(inner) = &self.inner {
+			let inner = inner.lock().unwrap();
+			for (k, idle) in &inner.idle {
+				snapshot.entry(k.clone()).or_default().idle = idle.len();
+			}
+			for (k, waiters) in &inner.waiters {
+				snapshot.entry(k.clone()).or_default().pending += waiters.len();
+			}
+			for k in &inner.connecting {
+				snapshot.entry(k.clone()).or_default().pending += 1;
+			}
+		}
+		snapshot
+	}
+
 	#[cfg(test)]
 	pub(super) fn no_timer(&self) {
 		// Prevent an actual interval from being created for this pool...
@@ -263,6 +467,7 @@ impl<T: Poolable, K: Key> Pool<T, K> {
 
 	fn reuse(&self, key: &K, value: T) -> Pooled<T, K> {
 		debug!("reuse idle connection for {:?}", key);
+		self.stats.notify(key, Event::Reused);
 		// TODO: unhack this
 		// In Pool::pooled(), which is used for inserting brand new connections,
 		// there's some code that adjusts the pool reference taken depending
@@ -288,18 +493,20 @@ impl<T: Poolable, K: Key> Pool<T, K> {
 }
 
 /// Pop off this list, looking for a usable connection that hasn't expired.
-struct IdlePopper<'a, T, K> {
+struct IdlePopper<'a, T, K: Key> {
 	key: &'a K,
 	list: &'a mut Vec<Idle<T>>,
+	stats: &'a Stats<K>,
 }
 
-impl<'a, T: Poolable + 'a, K: Debug> IdlePopper<'a, T, K> {
+impl<'a, T: Poolable + 'a, K: Key> IdlePopper<'a, T, K> {
 	fn pop(self, expiration: &Expiration) -> Option<Idle<T>> {
 		while let Some(entry) = self.list.pop() {
 			// If the connection has been closed, or is older than our idle
 			// timeout, simply drop it and keep looking...
 			if !entry.value.is_open() {
 				trace!("removing closed connection for {:?}", self.key);
+				self.stats.notify(self.key, Event::Evicted);
 				continue;
 			}
 			// TODO: Actually, since the `idle` list is pushed to the end always,
@@ -310,6 +517,7 @@ impl<'a, T: Poolable + 'a, K: Debug> IdlePopper<'a, T, K> {
 			// whole list...
 			if expiration.expires(entry.idle_at) {
 				trace!("removing expired connection for {:?}", self.key);
+				self.stats.notify(self.key, Event::Evicted);
 				continue;
 			}
 
@@ -380,11 +588,16 @@ impl<T: Poolable, K: Key> PoolInner<T, K> {
 
 		match value {
 			Some(value) => {
+				let limits = self.stats.limits(&key);
+				if let Some(timeout) = limits.idle_timeout {
+					self.reap_interval = Some(self.reap_interval.map_or(timeout, |i| i.min(timeout)));
+				}
 				// borrow-check scope...
 				{
 					let idle_list = self.idle.entry(key.clone()).or_default();
-					if self.max_idle_per_host <= idle_list.len() {
+					if limits.max_idle.unwrap_or(self.max_idle_per_host) <= idle_list.len() {
 						trace!("max idle per host for {:?}, dropping connection", key);
+						self.stats.notify(&key, Event::Evicted);
 						return;
 					}
 
@@ -416,7 +629,7 @@ impl<T: Poolable, K: Key> PoolInner<T, K> {
 		if self.idle_interval_ref.is_some() {
 			return;
 		}
-		let dur = if let Some(dur) = self.timeout {
+		let dur = if let Some(dur) = self.reap_interval {
 			dur
 		} else {
 			return;
@@ -462,21 +675,24 @@ impl<T, K: Eq + Hash> PoolInner<T, K> {
 impl<T: Poolable, K: Key> PoolInner<T, K> {
 	/// This should *only* be called by the IdleTask
 	fn clear_expired(&mut self) {
-		let dur = self.timeout.expect("interval assumes timeout");
-
 		let now = Instant::now();
 		// self.last_idle_check_at = now;
 
+		let stats = &self.stats;
+		let timeout = self.timeout;
 		self.idle.retain(|key, values| {
+			let dur = stats.limits(key).idle_timeout.or(timeout);
 			values.retain(|entry| {
 				if !entry.value.is_open() {
 					trace!("idle interval evicting closed for {:?}", key);
+					stats.notify(key, Event::Evicted);
 					return false;
 				}
 
 				// Avoid `Instant::sub` to avoid issues like rust-lang/rust#86470.
-				if now.saturating_duration_since(entry.idle_at) > dur {
+				if dur.is_some_and(|dur| now.saturating_duration_since(entry.idle_at) > dur) {
 					trace!("idle interval evicting expired for {:?}", key);
+					stats.notify(key, Event::Evicted);
 					return false;
 				}
 
@@ -494,6 +710,7 @@ impl<T, K: Key> Clone for Pool<T, K> {
 	fn clone(&self) -> Pool<T, K> {
 		Pool {
 			inner: self.inner.clone(),
+			stats: self.stats.clone(),
 		}
 	}
 }
@@ -633,7 +850,8 @@ impl<T: Poolable, K: Key> Checkout<T, K> {
 	fn checkout(&mut self, cx: &mut task::Context<'_>) -> Option<Pooled<T, K>> {
 		let entry = {
 			let mut inner = self.pool.inner.as_ref()?.lock().unwrap();
-			let expiration = Expiration::new(inner.timeout);
+			let stats = &self.pool.stats;
+			let expiration = Expiration::new(stats.limits(&self.key).idle_timeout.or(inner.timeout));
 			let maybe_entry = inner.idle.get_mut(&self.key).and_then(|list| {
 				trace!("take? {:?}: expiration = {:?}", self.key, expiration.0);
 				// A block to end the mutable borrow on list,
@@ -642,6 +860,7 @@ impl<T: Poolable, K: Key> Checkout<T, K> {
 					let popper = IdlePopper {
 						key: &self.key,
 						list,
+						stats,
 					};
 					popper.pop(&expiration)
 				}
@@ -787,6 +1006,19 @@ impl<T: Poolable + 'static, K: Key> Future for IdleTask<T, K> {
 			}
 
 			ready!(Pin::new(&mut this.fut).poll(cx));
+			let Some(inner) = this.pool.upgrade() else {
+				return Poll::Ready(());
+			};
+			let Ok(mut inner) = inner.lock() else {
+				return Poll::Ready(());
+			};
+			trace!("idle interval checking for expired");
+			inner.clear_expired();
+			// A key with a shorter idle timeout may have been added since the last check.
+			if let Some(interval) = inner.reap_interval {
+				*this.duration = interval;
+			}
+
 			// Set this task to run after the next deadline
 			// If the poll missed the deadline by a lot, set the deadline
 			// from the current time instead
@@ -795,15 +1027,6 @@ impl<T: Poolable + 'static, K: Key> Future for IdleTask<T, K> {
 				*this.deadline = Instant::now() + *this.duration;
 			}
 			*this.fut = this.timer.sleep_until(*this.deadline);
-
-			if let Some(inner) = this.pool.upgrade() {
-				if let Ok(mut inner) = inner.lock() {
-					trace!("idle interval checking for expired");
-					inner.clear_expired();
-					continue;
-				}
-			}
-			return Poll::Ready(());
 		}
 	}
 }
@@ -972,6 +1195,90 @@ mod tests {
 		);
 	}
 
+	#[tokio::test]
+	async fn test_pool_key_limits() {
+		let pool = pool_no_timer();
+		let limited = host_key("foo");
+		let other = host_key("bar");
+		let key = limited.clone();
+		pool.set_limits(Arc::new(move |k: &KeyImpl| {
+			if *k == key {
+				super::Limits {
+					max_idle: Some(1),
+					idle_timeout: Some(Duration::from_millis(10)),
+					..Default::default()
+				}
+			} else {
+				Default::default()
+			}
+		}));
+
+		pool.pooled(c(limited.clone()), Uniq(41));
+		pool.pooled(c(limited.clone()), Uniq(5));
+		pool.pooled(c(other.clone()), Uniq(41));
+		pool.pooled(c(other.clone()), Uniq(5));
+		assert_eq!(
+			pool
+				.locked()
+				.idle
+				.get(&limited)
+				.map(|entries| entries.len()),
+			Some(1)
+		);
+		assert_eq!(
+			pool.locked().idle.get(&other).map(|entries| entries.len()),
+			Some(2)
+		);
+
+		// Only the connections of the limited key have expired
+		tokio::time::sleep(Duration::from_millis(20)).await;
+		let mut checkout = pool.checkout(limited);
+		assert!(PollOnce(&mut checkout).await.is_none());
+		assert!(pool.checkout(other).await.is_ok());
+	}
+
+	#[derive(Default)]
+	struct Events(std::sync::Mutex<Vec<super::Event>>);
+
+	impl<K> super::Observer<K> for Events {
+		fn on_event(&self, _key: &K, event: super::Event) {
+			self.0.lock().unwrap().push(event);
+		}
+	}
+
+	#[tokio::test]
+	async fn test_pool_stats() {
+		let pool = pool_max_idle_no_timer(1);
+		let events = std::sync::Arc::new(Events::default());
+		pool.set_observer(events.clone());
+		let key = host_key("foo");
+
+		let first = pool.opened(key.clone());
+		let second = pool.opened(key.clone());
+		pool.pooled(c(key.clone()), Uniq(41));
+		pool.pooled(c(key.clone()), Uniq(5));
+		assert_eq!(
+			pool.snapshot().get(&key),
+			Some(&super::Snapshot {
+				open: 2,
+				idle: 1,
+				pending: 0,
+			})
+		);
+
+		let pooled = pool.checkout(key.clone()).await.unwrap();
+		assert!(pooled.is_reused());
+		drop(first);
+		drop(second);
+		assert_eq!(pool.snapshot().get(&key).map(|s| s.open), None);
+
+		use super::Event::*;
+		assert_eq!(
+			*events.0.lock().unwrap(),
+			vec![Created, Created, Evicted, Reused]
+		);
+	}
+
 	#[tokio::test]
 	async fn test_pool_timer_removes_expired() {
 		let pool = Pool::new(
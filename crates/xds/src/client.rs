@@ -1,6 +1,9 @@
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::fmt::{Debug, Display, Formatter};
+use std::path::PathBuf;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
 use std::time::Duration;
 use std::{fmt, mem};
@@ -8,8 +11,9 @@ use std::{fmt, mem};
 use agent_core::metrics::{IncrementRecorder, Recorder};
 use agent_core::strng;
 use agent_core::strng::Strng;
+use aws_lc_rs::digest;
 use http::Request;
-use prost::{DecodeError, EncodeError};
+use prost::{DecodeError, EncodeError, Message};
 use prost_types::value::Kind;
 use prost_types::{Struct, Value};
 use split_iter::Splittable;
@@ -22,6 +26,11 @@ use super::Error;
 use crate::metrics::{ConnectionTerminationReason, Metrics};
 use crate::service::discovery::v3::aggregated_discovery_service_client::AggregatedDiscoveryServiceClient;
 use crate::service::discovery::v3::{Resource as ProtoResource, *};
+use crate::snapshot::{self, Snapshot};
+
+#[cfg(test)]
+#[path = "client_tests.rs"]
+mod tests;
 
 const INSTANCE_IP: &str = "INSTANCE_IP";
 const INSTANCE_IPS: &str = "INSTANCE_IPS";
@@ -49,21 +58,72 @@ impl Display for ResourceKey {
 #[derive(Debug)]
 pub struct RejectedConfig {
 	name: Strng,
+	field: Option<Strng>,
 	reason: anyhow::Error,
 }
 
 impl RejectedConfig {
+	/// Reject the resource `name`. If `reason` has an InvalidField context, the field is reported.
 	pub fn new(name: Strng, reason: anyhow::Error) -> Self {
-		Self { name, reason }
+		let field = reason.downcast_ref::<InvalidField>().map(|f| f.0.clone());
+		Self {
+			name,
+			field,
+			reason,
+		}
+	}
+
+	fn violation(&self) -> bad_request::FieldViolation {
+		let field = match &self.field {
+			Some(field) => format!("resources[{}].{field}", self.name),
+			None => format!("resources[{}]", self.name),
+		};
+		bad_request::FieldViolation {
+			field,
+			description: format!("{:#}", self.reason),
+		}
 	}
 }
 
 impl Display for RejectedConfig {
 	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-		write!(f, "{}: {}", self.name, self.reason)
+		write!(f, "{}: {:#}", self.name, self.reason)
 	}
 }
 
+/// InvalidField marks the field of a resource that caused it to be rejected, so it can be reported
+/// in the NACK. Attach it as context: `res.context(InvalidField::new("route"))`.
+#[derive(Debug, Clone)]
+pub struct InvalidField(Strng);
+
+impl InvalidField {
+	pub fn new(field: impl Into<Strng>) -> Self {
+		Self(field.into())
+	}
+}
+
+impl Display for InvalidField {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "invalid {}", self.0)
+	}
+}
+
+/// The number of rejected resources kept for display.
+const MAX_REJECTIONS: usize = 50;
+
+/// A resource rejected by the most recent NACKs.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Rejection {
+	pub timestamp: String,
+	pub control_plane: Strng,
+	pub type_url: Strng,
+	pub name: Strng,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub field: Option<Strng>,
+	pub reason: String,
+}
+
 /// handle_single_resource is a helper to process a set of updates with a closure that processes items one-by-one.
 /// It handles aggregating errors as NACKS.
 pub fn handle_single_resource<T: prost::Message, F: FnMut(XdsUpdate<T>) -> anyhow::Result<()>>(
@@ -123,35 +183,57 @@ impl<T: 'static + prost::Message + Default + Debug> RawHandler for HandlerWrappe
 	) -> Result<(), Vec<RejectedConfig>> {
 		let type_url = strng::new(res.type_url);
 		let removes = &res.removed_resources;
+		// The handler must be done with the state before we update it below.
+		let (result, decode_failures) = {
+			let known = state.known_resources.get(&type_url);
+			let accepted = state.accepted.get(&type_url);
+
+			// Resources sent again at the version we already have carry no changes (for example, TTL
+			// heartbeats, which may omit the resource entirely), so they are not passed to the handler.
+			// Nor are resources with the same contents as the ones we accepted, as when a control plane
+			// pushes its full state again under new versions, so they are not decoded and validated
+			// again.
+			let changed = |raw: &&ProtoResource| {
+				let same_version = !raw.version.is_empty()
+					&& known
+						.and_then(|k| k.get(raw.name.as_str()))
+						.is_some_and(|v| v.as_str() == raw.version);
+				if same_version {
+					return false;
+				}
+				let hash = content_hash(raw);
+				hash.is_none() || accepted.and_then(|a| a.get(raw.name.as_str())).copied() != hash
+			};
 
-		// Keep track of any failures but keep going
-		let (decode_failures, updates) = res
-			.resources
-			.iter()
-			.map(|raw| {
-				decode_proto::<T>(raw).map_err(|err| RejectedConfig {
-					name: raw.name.as_str().into(),
-					reason: err.into(),
+			// Keep track of any failures but keep going
+			let (decode_failures, updates) = res
+				.resources
+				.iter()
+				.filter(changed)
+				.map(|raw| {
+					decode_proto::<T>(raw)
+						.map_err(|err| RejectedConfig::new(raw.name.as_str().into(), err.into()))
 				})
-			})
-			.split(|i| i.is_ok());
-
-		let mut updates = updates
-			// We already filtered to ok
-			.map(|r| r.expect("must be ok"))
-			.map(XdsUpdate::Update)
-			.chain(removes.iter().cloned().map(|s| XdsUpdate::Remove(s.into())));
-
-		// First, call handlers that update the agentgateway state.
-		// other wise on-demand notifications might observe a cache without their resource
-		let updates: Box<&mut dyn Iterator<Item = XdsUpdate<T>>> = Box::new(&mut updates);
-		let result = self.h.handle(updates);
-
-		// Collecting after handle() is important, as the split() will cache the side we use last.
-		// Updates >>> Errors (hopefully), so we want this one to do the allocations.
-		let decode_failures: Vec<_> = decode_failures
-			.map(|r| r.expect_err("must be err"))
-			.collect();
+				.split(|i| i.is_ok());
+
+			let mut updates = updates
+				// We already filtered to ok
+				.map(|r| r.expect("must be ok"))
+				.map(XdsUpdate::Update)
+				.chain(removes.iter().cloned().map(|s| XdsUpdate::Remove(s.into())));
+
+			// First, call handlers that update the agentgateway state.
+			// other wise on-demand notifications might observe a cache without their resource
+			let updates: Box<&mut dyn Iterator<Item = XdsUpdate<T>>> = Box::new(&mut updates);
+			let result = self.h.handle(updates);
+
+			// Collecting after handle() is important, as the split() will cache the side we use last.
+			// Updates >>> Errors (hopefully), so we want this one to do the allocations.
+			let decode_failures: Vec<_> = decode_failures
+				.map(|r| r.expect_err("must be err"))
+				.collect();
+			(result, decode_failures)
+		};
 
 		// after we update the agentgateway cache, we can update our xds cache. it's important that we do this after
 		// as we make on demand notifications here, so the agentgateway cache must be updated first.
@@ -161,19 +243,42 @@ impl<T: 'static + prost::Message + Default + Debug> RawHandler for HandlerWrappe
 				type_url: type_url.clone(),
 			};
 			debug!("received delete resource {k}");
-			if let Some(rm) = state.known_resources.get_mut(&k.type_url) {
-				rm.remove(&k.name);
+			state.remove_resource(&k);
+			if let Some(s) = &mut state.snapshot {
+				s.remove(&k);
 			}
 			state.notify_on_demand(&k);
 		}
 
-		for r in res.resources {
+		// Rejected resources are tracked without a version, so the server sends them again on reconnect
+		// rather than assuming we hold them.
+		let rejected: HashSet<&Strng> = result
+			.as_ref()
+			.err()
+			.into_iter()
+			.flatten()
+			.chain(decode_failures.iter())
+			.map(|r| &r.name)
+			.collect();
+		for r in &res.resources {
 			let key = ResourceKey {
-				name: r.name.into(),
+				name: r.name.as_str().into(),
 				type_url: type_url.clone(),
 			};
 			state.notify_on_demand(&key);
-			state.add_resource(key.type_url, key.name);
+			let version = if rejected.contains(&key.name) {
+				state.remove_accepted(&key);
+				strng::EMPTY
+			} else {
+				if let Some(s) = &mut state.snapshot {
+					s.insert(key.type_url.clone(), r);
+				}
+				if let Some(hash) = content_hash(r) {
+					state.add_accepted(&key, hash);
+				}
+				r.version.as_str().into()
+			};
+			state.add_resource(key.type_url, key.name, version);
 		}
 
 		// Either can fail. Merge the results
@@ -232,12 +337,22 @@ impl tower::Service<Request<Body>> for GrpcClient {
 		self.client.box_clone().make_call(req)
 	}
 }
+/// A control plane serving XDS.
+pub struct ControlPlane {
+	pub address: Strng,
+	pub client: GrpcClient,
+}
+
 pub struct Config {
-	client: GrpcClient,
+	/// The control planes to connect to. The first is the primary; the rest are fallbacks, used in
+	/// order while the primary is unreachable.
+	control_planes: Vec<ControlPlane>,
 	proxy_metadata: HashMap<String, String>,
 	handlers: HashMap<Strng, Box<dyn RawHandler>>,
+	on_nack: Option<Box<dyn Fn(&str, &str) + Send + Sync>>,
 	initial_requests: Vec<DeltaDiscoveryRequest>,
 	on_demand: bool,
+	snapshot: Option<PathBuf>,
 	// Environment variables
 	instance_ip: String,
 	pod_name: String,
@@ -246,12 +361,14 @@ pub struct Config {
 }
 
 impl Config {
-	pub fn new(client: GrpcClient, gateway_name: Strng, namespace: Strng) -> Self {
+	pub fn new(primary: ControlPlane, gateway_name: Strng, namespace: Strng) -> Self {
 		Self {
-			client,
+			control_planes: vec![primary],
 			handlers: HashMap::new(),
+			on_nack: None,
 			initial_requests: Vec::new(),
 			on_demand: false,
+			snapshot: None,
 			proxy_metadata: HashMap::from([
 				("GATEWAY_NAME".to_string(), gateway_name.to_string()),
 				("NAMESPACE".to_string(), namespace.to_string()),
@@ -265,14 +382,23 @@ impl Config {
 }
 
 pub struct State {
-	/// Stores all known workload resources. Map from type_url to name
-	known_resources: HashMap<Strng, HashSet<Strng>>,
+	/// Stores all known workload resources. Map from type_url to name to the resource version, which
+	/// is sent as the initial resource versions on reconnect so unchanged resources are not resent.
+	known_resources: HashMap<Strng, HashMap<Strng, Strng>>,
+
+	/// A hash of the contents of each accepted resource. Map from type_url to name to hash, so
+	/// resources sent again with the same contents can be skipped whatever their version.
+	accepted: HashMap<Strng, HashMap<Strng, [u8; 32]>>,
 
 	/// pending stores a list of all resources that are pending and XDS push
 	pending: HashMap<ResourceKey, oneshot::Sender<()>>,
 
 	demand: mpsc::Receiver<(oneshot::Sender<()>, ResourceKey)>,
 	demand_tx: mpsc::Sender<(oneshot::Sender<()>, ResourceKey)>,
+
+	/// The accepted resources, persisted so they can be served if no control plane is reachable on
+	/// a later startup.
+	snapshot: Option<Snapshot>,
 }
 
 impl State {
@@ -284,12 +410,43 @@ impl State {
 			}
 		}
 	}
-	fn add_resource(&mut self, type_url: Strng, name: Strng) {
+	fn add_resource(&mut self, type_url: Strng, name: Strng, version: Strng) {
 		self
 			.known_resources
 			.entry(type_url)
 			.or_default()
-			.insert(name.clone());
+			.insert(name, version);
+	}
+	fn remove_resource(&mut self, key: &ResourceKey) {
+		if let Some(rm) = self.known_resources.get_mut(&key.type_url) {
+			rm.remove(&key.name);
+		}
+		self.remove_accepted(key);
+	}
+	fn add_accepted(&mut self, key: &ResourceKey, hash: [u8; 32]) {
+		self
+			.accepted
+			.entry(key.type_url.clone())
+			.or_default()
+			.insert(key.name.clone(), hash);
+	}
+	fn remove_accepted(&mut self, key: &ResourceKey) {
+		if let Some(rm) = self.accepted.get_mut(&key.type_url) {
+			rm.remove(&key.name);
+		}
+	}
+	/// The versions of the resources of `type_url` we hold, sent on reconnect so the server only
+	/// sends the ones that changed.
+	fn initial_resource_versions(&self, type_url: &str) -> HashMap<String, String> {
+		self
+			.known_resources
+			.get(&strng::new(type_url))
+			.map(|hs| {
+				hs.iter()
+					.map(|(n, v)| (n.to_string(), v.to_string()))
+					.collect()
+			})
+			.unwrap_or_default()
 	}
 }
 
@@ -304,6 +461,25 @@ impl Config {
 			.watch(type_url, no_on_demand)
 	}
 
+	/// Add a fallback control plane, connected to when the control planes before it are unreachable.
+	pub fn with_fallback(mut self, fallback: ControlPlane) -> Config {
+		self.control_planes.push(fallback);
+		self
+	}
+
+	/// Persist accepted resources to `path`. If no control plane can be reached on startup, the
+	/// resources in the snapshot are served until one can.
+	pub fn with_snapshot(mut self, path: PathBuf) -> Config {
+		self.snapshot = Some(path);
+		self
+	}
+
+	/// Call `f` with the type URL and error of each rejected response.
+	pub fn with_nack_handler(mut self, f: impl Fn(&str, &str) + Send + Sync + 'static) -> Config {
+		self.on_nack = Some(Box::new(f));
+		self
+	}
+
 	fn with_handler<F>(mut self, type_url: Strng, f: impl Handler<F>) -> Config
 	where
 		F: 'static + prost::Message + Default + Debug,
@@ -409,6 +585,83 @@ pub struct AdsClient {
 
 	connection_id: u32,
 	types_to_expect: HashSet<String>,
+
+	/// The index of the control plane in use.
+	active: usize,
+	/// Consecutive failures to connect to the active control plane.
+	failures: u32,
+	control_planes: ControlPlanes,
+	/// Whether the snapshot should be loaded if the control plane cannot be reached. Only the first
+	/// failure before the initial sync loads it.
+	bootstrap_pending: bool,
+}
+
+/// The status of a control plane.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ControlPlaneStatus {
+	pub address: Strng,
+	/// Whether config is currently being received from this control plane.
+	pub active: bool,
+	pub connected: bool,
+	pub consecutive_failures: u32,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub last_error: Option<String>,
+}
+
+/// ControlPlanes reports the status of each configured control plane.
+#[derive(Debug, Clone, Default)]
+pub struct ControlPlanes {
+	planes: Arc<Mutex<Vec<ControlPlaneStatus>>>,
+	stale: Arc<AtomicBool>,
+	rejections: Arc<Mutex<VecDeque<Rejection>>>,
+}
+
+impl ControlPlanes {
+	pub fn status(&self) -> Vec<ControlPlaneStatus> {
+		self.planes.lock().expect("mutex acquired").clone()
+	}
+
+	/// Whether the config served was loaded from the snapshot, because no control plane has been
+	/// reached since startup.
+	pub fn stale(&self) -> bool {
+		self.stale.load(Ordering::Relaxed)
+	}
+
+	/// The most recently rejected resources, oldest first.
+	pub fn rejections(&self) -> Vec<Rejection> {
+		self
+			.rejections
+			.lock()
+			.expect("mutex acquired")
+			.iter()
+			.cloned()
+			.collect()
+	}
+
+	fn record_rejections(&self, control_plane: &Strng, type_url: &str, rejects: &[RejectedConfig]) {
+		let timestamp = agent_core::telemetry::render_current_time();
+		let mut rejections = self.rejections.lock().expect("mutex acquired");
+		for reject in rejects {
+			if rejections.len() >= MAX_REJECTIONS {
+				rejections.pop_front();
+			}
+			rejections.push_back(Rejection {
+				timestamp: timestamp.clone(),
+				control_plane: control_plane.clone(),
+				type_url: strng::new(type_url),
+				name: reject.name.clone(),
+				field: reject.field.clone(),
+				reason: format!("{:#}", reject.reason),
+			});
+		}
+	}
+
+	fn update(&self, idx: usize, f: impl FnOnce(&mut ControlPlaneStatus)) {
+		if let Some(s) = self.planes.lock().expect("mutex acquired").get_mut(idx) {
+			f(s)
+		}
+	}
 }
 
 /// Demanded allows awaiting for an on-demand XDS resource
@@ -463,6 +716,14 @@ impl Demander {
 const INITIAL_BACKOFF: Duration = Duration::from_millis(10);
 const MAX_BACKOFF: Duration = Duration::from_secs(15);
 
+/// Consecutive connection failures after which the next control plane is tried.
+const FAILOVER_AFTER: u32 = 3;
+/// While connected to a fallback, how often the primary is checked, to return to it once healthy.
+const PRIMARY_PROBE_INTERVAL: Duration = Duration::from_secs(30);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+/// How often changed resources are written to the snapshot.
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(5);
+
 impl AdsClient {
 	fn is_initial_request_on_demand(r: &DeltaDiscoveryRequest) -> bool {
 		!r.resource_names_subscribe.is_empty()
@@ -472,9 +733,11 @@ impl AdsClient {
 		let (tx, rx) = mpsc::channel(100);
 		let state = State {
 			known_resources: Default::default(),
+			accepted: Default::default(),
 			pending: Default::default(),
 			demand: rx,
 			demand_tx: tx,
+			snapshot: config.snapshot.clone().map(Snapshot::new),
 		};
 		let types_to_expect: HashSet<String> = config
 			.initial_requests
@@ -482,16 +745,43 @@ impl AdsClient {
 			.filter(|e| !Self::is_initial_request_on_demand(e)) // is_empty implies not ondemand
 			.map(|e| e.type_url.clone())
 			.collect();
+		let control_planes = ControlPlanes {
+			planes: Arc::new(Mutex::new(
+				config
+					.control_planes
+					.iter()
+					.enumerate()
+					.map(|(i, cp)| ControlPlaneStatus {
+						address: cp.address.clone(),
+						active: i == 0,
+						connected: false,
+						consecutive_failures: 0,
+						last_error: None,
+					})
+					.collect(),
+			)),
+			stale: Default::default(),
+			rejections: Default::default(),
+		};
 		AdsClient {
-			config,
 			state,
 			metrics,
 			block_ready: Some(block_ready),
 			connection_id: 0,
 			types_to_expect,
+			active: 0,
+			failures: 0,
+			bootstrap_pending: config.snapshot.is_some(),
+			config,
+			control_planes,
 		}
 	}
 
+	/// Get a handle reporting the status of each control plane.
+	pub fn control_planes(&self) -> ControlPlanes {
+		self.control_planes.clone()
+	}
+
 	/// Get a reference to the XDS configuration
 	pub fn config(&self) -> &Config {
 		&self.config
@@ -509,7 +799,39 @@ impl AdsClient {
 	}
 
 	async fn run_loop(&mut self, backoff: Duration) -> Duration {
-		match self.run_internal().await {
+		let res = self.run_internal().await;
+		let active = self.active;
+		if !matches!(res, Err(Error::Failback)) {
+			self.control_planes.update(active, |s| {
+				s.connected = false;
+				s.last_error = res.as_ref().err().map(|e| e.to_string());
+			});
+		}
+		if matches!(res, Err(Error::Connection(_) | Error::Transport(_))) {
+			if self.bootstrap_pending {
+				self.bootstrap_pending = false;
+				self.load_snapshot();
+			}
+			self.failures += 1;
+			let failures = self.failures;
+			self
+				.control_planes
+				.update(active, |s| s.consecutive_failures = failures);
+			if self.failures >= FAILOVER_AFTER && self.config.control_planes.len() > 1 {
+				let next = (self.active + 1) % self.config.control_planes.len();
+				warn!(
+					from = %self.config.control_planes[active].address,
+					to = %self.config.control_planes[next].address,
+					"XDS control plane unreachable, failing over"
+				);
+				self
+					.metrics
+					.increment(&ConnectionTerminationReason::ConnectionError);
+				self.switch_to(next);
+				return INITIAL_BACKOFF;
+			}
+		}
+		match res {
 			Err(e @ Error::Connection(_)) => {
 				// For connection errors, we add backoff
 				let backoff = std::cmp::min(MAX_BACKOFF, backoff * 2);
@@ -565,6 +887,12 @@ impl AdsClient {
 				tokio::time::sleep(backoff).await;
 				backoff
 			},
+			Err(Error::Failback) => {
+				self
+					.metrics
+					.increment(&ConnectionTerminationReason::Reconnect);
+				INITIAL_BACKOFF
+			},
 			Err(e) => {
 				// For other errors, we connect immediately
 				// TODO: we may need more nuance here; if we fail due to invalid initial request we may overload
@@ -597,6 +925,68 @@ impl AdsClient {
 		}
 	}
 
+	/// Serve the resources in the snapshot, as the control plane could not be reached before the
+	/// initial sync. Once it is, the control plane sends only the resources that changed.
+	fn load_snapshot(&mut self) {
+		let Some(snapshot) = &self.state.snapshot else {
+			return;
+		};
+		let path = snapshot.path().to_path_buf();
+		let responses = match snapshot.load() {
+			Ok(Some(r)) => r,
+			Ok(None) => {
+				debug!(path=%path.display(), "no XDS snapshot to load");
+				return;
+			},
+			Err(e) => {
+				warn!(path=%path.display(), "failed to load XDS snapshot: {e:#}");
+				return;
+			},
+		};
+		warn!(
+			path=%path.display(),
+			"XDS control plane unreachable, serving the last-known-good snapshot"
+		);
+		for response in responses {
+			let type_url = response.type_url.clone();
+			let Some(h) = self.config.handlers.get(&strng::new(&type_url)) else {
+				continue;
+			};
+			if let Err(rejects) = h.handle(&mut self.state, response) {
+				for reject in rejects {
+					warn!(%type_url, "snapshot resource rejected: {reject}");
+				}
+			}
+		}
+		self.control_planes.stale.store(true, Ordering::Relaxed);
+		// The snapshot is the best config available, so stop waiting for the control plane.
+		mem::drop(mem::take(&mut self.block_ready));
+	}
+
+	fn switch_to(&mut self, idx: usize) {
+		let prev = self.active;
+		self.control_planes.update(prev, |s| {
+			s.active = false;
+			s.connected = false;
+		});
+		self.control_planes.update(idx, |s| s.active = true);
+		self.active = idx;
+		self.failures = 0;
+	}
+
+	/// Check whether the primary control plane accepts connections.
+	async fn probe_primary(&self) -> bool {
+		let client = self.config.control_planes[0].client.clone();
+		let outbound = async_stream::stream! {
+			for r in Vec::<DeltaDiscoveryRequest>::new() {
+				yield r;
+			}
+		};
+		let probe = AggregatedDiscoveryServiceClient::new(client)
+			.delta_aggregated_resources(tonic::Request::new(outbound));
+		matches!(tokio::time::timeout(PROBE_TIMEOUT, probe).await, Ok(Ok(_)))
+	}
+
 	async fn run_internal(&mut self) -> Result<(), Error> {
 		let (discovery_req_tx, mut discovery_req_rx) = mpsc::channel::<DeltaDiscoveryRequest>(100);
 		// For each type in initial_watches we will send a request on connection to subscribe
@@ -606,16 +996,7 @@ impl AdsClient {
 			.iter()
 			.map(|e| {
 				let mut req = e.clone();
-				req.initial_resource_versions = self
-					.state
-					.known_resources
-					.get(&strng::new(&req.type_url))
-					.map(|hs| {
-						hs.iter()
-							.map(|n| (n.to_string(), "".to_string())) // Proto expects Name -> Version. We don't care about version
-							.collect()
-					})
-					.unwrap_or_default();
+				req.initial_resource_versions = self.state.initial_resource_versions(&req.type_url);
 				req
 			})
 			.collect();
@@ -641,17 +1022,38 @@ impl AdsClient {
 		};
 
 		let req = tonic::Request::new(outbound);
-		let ads_connection = AggregatedDiscoveryServiceClient::new(self.config.client.clone())
-			.max_decoding_message_size(200 * 1024 * 1024)
-			.delta_aggregated_resources(req)
-			.await;
+		let address = self.config.control_planes[self.active].address.clone();
+		let ads_connection =
+			AggregatedDiscoveryServiceClient::new(self.config.control_planes[self.active].client.clone())
+				.max_decoding_message_size(200 * 1024 * 1024)
+				.delta_aggregated_resources(req)
+				.await;
 
 		let mut response_stream = ads_connection.map_err(Error::Connection)?.into_inner();
 		debug!("connected established");
 
-		info!("Stream established");
+		info!(%address, "Stream established");
+		self.failures = 0;
+		self.control_planes.update(self.active, |s| {
+			s.connected = true;
+			s.consecutive_failures = 0;
+			s.last_error = None;
+		});
+		let mut primary_probe = tokio::time::interval(PRIMARY_PROBE_INTERVAL);
+		primary_probe.tick().await;
+		let mut snapshot_write = tokio::time::interval(SNAPSHOT_INTERVAL);
 		loop {
 			tokio::select! {
+				_ = snapshot_write.tick(), if self.state.snapshot.is_some() => {
+					self.write_snapshot().await;
+				}
+				_ = primary_probe.tick(), if self.active != 0 => {
+					if self.probe_primary().await {
+						info!("primary XDS control plane is reachable again, switching back");
+						self.switch_to(0);
+						return Err(Error::Failback);
+					}
+				}
 				_demand_event = self.state.demand.recv() => {
 					self.handle_demand_event(_demand_event, &discovery_req_tx).await?;
 				}
@@ -667,10 +1069,7 @@ impl AdsClient {
 					}
 					if let XdsSignal::Ack = self.handle_stream_event(msg, &discovery_req_tx).await? {
 						if let Some(received_type) = received_type {
-							self.types_to_expect.remove(&received_type);
-							if self.types_to_expect.is_empty() {
-								mem::drop(mem::take(&mut self.block_ready));
-							}
+							self.initial_type_received(&received_type);
 						}
 					};
 				}
@@ -678,6 +1077,36 @@ impl AdsClient {
 		}
 	}
 
+	/// Record that the initial response for a type was accepted. Once every type has been, the
+	/// initial sync is complete.
+	fn initial_type_received(&mut self, type_url: &str) {
+		self.types_to_expect.remove(type_url);
+		if self.types_to_expect.is_empty() {
+			mem::drop(mem::take(&mut self.block_ready));
+			self.control_planes.stale.store(false, Ordering::Relaxed);
+			// The control plane's config is newer than the snapshot, so it must not be loaded if the
+			// connection fails later.
+			self.bootstrap_pending = false;
+		}
+	}
+
+	async fn write_snapshot(&mut self) {
+		// Only complete config is persisted, so wait for the initial sync.
+		if !self.types_to_expect.is_empty() {
+			return;
+		}
+		let Some(snapshot) = &mut self.state.snapshot else {
+			return;
+		};
+		let Some(data) = snapshot.encode() else {
+			return;
+		};
+		let path = snapshot.path().to_path_buf();
+		if let Err(e) = snapshot::write(path.clone(), data).await {
+			warn!(path=%path.display(), "failed to write XDS snapshot: {e}");
+		}
+	}
+
 	async fn handle_stream_event(
 		&mut self,
 		response: DeltaDiscoveryResponse,
@@ -705,24 +1134,29 @@ impl AdsClient {
 
 		let (response_type, error) = match handler_response {
 			Err(rejects) => {
-				let error = rejects
-					.into_iter()
-					.map(|reject| reject.to_string())
-					.collect::<Vec<String>>()
-					.join("; ");
-				(XdsSignal::Nack, Some(error))
+				let address = &self.config.control_planes[self.active].address;
+				self
+					.control_planes
+					.record_rejections(address, &type_url, &rejects);
+				(XdsSignal::Nack, Some(nack_status(&rejects)))
 			},
 			_ => (XdsSignal::Ack, None),
 		};
 
+		let message = error.as_ref().map(|e| e.message.as_str());
 		match response_type {
-			XdsSignal::Nack => error!(
-				type_url=type_url,
-				nonce,
-				"type"=?response_type,
-				error=error,
-				"sending response",
-			),
+			XdsSignal::Nack => {
+				error!(
+					type_url=type_url,
+					nonce,
+					"type"=?response_type,
+					error=message,
+					"sending response",
+				);
+				if let Some(f) = &self.config.on_nack {
+					f(&type_url, message.unwrap_or_default());
+				}
+			},
 			_ => debug!(
 				type_url=type_url,
 				nonce,
@@ -735,10 +1169,7 @@ impl AdsClient {
 			.send(DeltaDiscoveryRequest {
 				type_url,              // this is owned, OK to move
 				response_nonce: nonce, // this is owned, OK to move
-				error_detail: error.map(|msg| Status {
-					message: msg,
-					..Default::default()
-				}),
+				error_detail: error,
 				..Default::default()
 			})
 			.await
@@ -757,7 +1188,10 @@ impl AdsClient {
 		info!("received on demand request {demand_event}");
 		let ResourceKey { type_url, name } = demand_event.clone();
 		self.state.pending.insert(demand_event, tx);
-		self.state.add_resource(type_url.clone(), name.clone());
+		// Not received yet, so there is no version
+		self
+			.state
+			.add_resource(type_url.clone(), name.clone(), strng::EMPTY);
 		send
 			.send(DeltaDiscoveryRequest {
 				type_url: type_url.to_string(),
@@ -791,6 +1225,41 @@ impl<T: prost::Message> XdsUpdate<T> {
 	}
 }
 
+/// The error detail of a NACK: a summary message, and a BadRequest detail with each rejected
+/// resource so control planes can report exactly what was rejected.
+fn nack_status(rejects: &[RejectedConfig]) -> Status {
+	// google.rpc.Code.INVALID_ARGUMENT
+	const INVALID_ARGUMENT: i32 = 3;
+	let message = rejects
+		.iter()
+		.map(|reject| reject.to_string())
+		.collect::<Vec<String>>()
+		.join("; ");
+	let detail = BadRequest {
+		field_violations: rejects.iter().map(RejectedConfig::violation).collect(),
+	};
+	Status {
+		code: INVALID_ARGUMENT,
+		message,
+		details: vec![prost_types::Any {
+			type_url: "type.googleapis.com/google.rpc.BadRequest".to_string(),
+			value: detail.encode_to_vec(),
+		}],
+	}
+}
+
+/// The SHA-256 of the encoded contents of a resource, or None if it was sent without them. A
+/// cryptographic hash is used so no two resources' contents can be crafted to collide.
+fn content_hash(resource: &ProtoResource) -> Option<[u8; 32]> {
+	let any = resource.resource.as_ref()?;
+	let mut ctx = digest::Context::new(&digest::SHA256);
+	ctx.update(any.type_url.as_bytes());
+	// Type URLs never contain NUL, so this separates them from the value unambiguously.
+	ctx.update(&[0]);
+	ctx.update(&any.value);
+	ctx.finish().as_ref().try_into().ok()
+}
+
 fn decode_proto<T: prost::Message + Default>(
 	resource: &ProtoResource,
 ) -> Result<XdsResource<T>, AdsError> {
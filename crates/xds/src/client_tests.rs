@@ -0,0 +1,208 @@
+use prometheus_client::registry::Registry;
+
+use super::*;
+
+const TYPE_URL: &str = "type.googleapis.com/google.protobuf.Duration";
+
+#[derive(Debug, Clone)]
+struct Unreachable;
+
+impl ClientTrait for Unreachable {
+	fn make_call(
+		&mut self,
+		_: Request<Body>,
+	) -> Pin<
+		Box<dyn Future<Output = Result<http::Response<axum_core::body::Body>, anyhow::Error>> + Send>,
+	> {
+		Box::pin(async { Err(anyhow::anyhow!("unreachable")) })
+	}
+	fn box_clone(&self) -> Box<dyn ClientTrait> {
+		Box::new(self.clone())
+	}
+}
+
+struct Noop;
+
+impl Handler<prost_types::Duration> for Noop {
+	fn handle(
+		&self,
+		_: Box<&mut dyn Iterator<Item = XdsUpdate<prost_types::Duration>>>,
+	) -> Result<(), Vec<RejectedConfig>> {
+		Ok(())
+	}
+}
+
+fn client(snapshot: Option<PathBuf>) -> (AdsClient, tokio::sync::watch::Receiver<()>) {
+	let primary = ControlPlane {
+		address: strng::new("localhost:15010"),
+		client: GrpcClient::new(Unreachable),
+	};
+	let mut config = Config::new(primary, strng::new("gateway"), strng::new("default"))
+		.with_watched_handler(strng::new(TYPE_URL), Noop);
+	if let Some(path) = snapshot {
+		config = config.with_snapshot(path);
+	}
+	let (tx, rx) = tokio::sync::watch::channel(());
+	(config.build(Metrics::new(&mut Registry::default()), tx), rx)
+}
+
+#[test]
+fn test_initial_sync_ends_bootstrap() {
+	let dir = tempfile::tempdir().unwrap();
+	let (mut client, ready) = client(Some(dir.path().join("snapshot")));
+	assert!(client.bootstrap_pending);
+
+	client.initial_type_received(TYPE_URL);
+	// Ready, and a later connection failure serves the control plane's config, not the snapshot
+	assert!(ready.has_changed().is_err());
+	assert!(!client.bootstrap_pending);
+}
+
+/// Records the names of the updates it is passed, prefixing removals with `-`. Resources named
+/// `bad` are rejected.
+#[derive(Clone, Default)]
+struct Updates(Arc<Mutex<Vec<String>>>);
+
+impl Updates {
+	fn take(&self) -> Vec<String> {
+		mem::take(&mut self.0.lock().unwrap())
+	}
+}
+
+impl Handler<prost_types::Duration> for Updates {
+	fn handle(
+		&self,
+		updates: Box<&mut dyn Iterator<Item = XdsUpdate<prost_types::Duration>>>,
+	) -> Result<(), Vec<RejectedConfig>> {
+		let mut rejects = vec![];
+		for update in updates {
+			let name = update.name();
+			self.0.lock().unwrap().push(match update {
+				XdsUpdate::Update(_) => name.to_string(),
+				XdsUpdate::Remove(_) => format!("-{name}"),
+			});
+			if name == "bad" {
+				rejects.push(RejectedConfig::new(name, anyhow::anyhow!("rejected")));
+			}
+		}
+		if rejects.is_empty() {
+			Ok(())
+		} else {
+			Err(rejects)
+		}
+	}
+}
+
+fn resource(name: &str, version: &str, seconds: i64) -> ProtoResource {
+	ProtoResource {
+		name: name.to_string(),
+		version: version.to_string(),
+		resource: Some(prost_types::Any {
+			type_url: TYPE_URL.to_string(),
+			value: prost_types::Duration { seconds, nanos: 0 }.encode_to_vec(),
+		}),
+		..Default::default()
+	}
+}
+
+fn response(resources: Vec<ProtoResource>, removed: &[&str]) -> DeltaDiscoveryResponse {
+	DeltaDiscoveryResponse {
+		type_url: TYPE_URL.to_string(),
+		resources,
+		removed_resources: removed.iter().map(|s| s.to_string()).collect(),
+		..Default::default()
+	}
+}
+
+fn wrapper(updates: &Updates) -> HandlerWrapper<prost_types::Duration> {
+	HandlerWrapper {
+		h: Box::new(updates.clone()),
+	}
+}
+
+fn versions(state: &State) -> HashMap<String, String> {
+	state.initial_resource_versions(TYPE_URL)
+}
+
+#[test]
+fn test_skip_unchanged() {
+	let (mut client, _ready) = client(None);
+	let updates = Updates::default();
+	let h = wrapper(&updates);
+	let state = &mut client.state;
+
+	h.handle(state, response(vec![resource("a", "1", 1)], &[]))
+		.unwrap();
+	assert_eq!(updates.take(), vec!["a"]);
+
+	// Sent again at the same version
+	h.handle(state, response(vec![resource("a", "1", 1)], &[]))
+		.unwrap();
+	assert!(updates.take().is_empty());
+
+	// Sent again at a new version, but with the same contents
+	h.handle(state, response(vec![resource("a", "2", 1)], &[]))
+		.unwrap();
+	assert!(updates.take().is_empty());
+	assert_eq!(
+		versions(state),
+		HashMap::from([("a".to_string(), "2".to_string())])
+	);
+
+	// Changed
+	h.handle(state, response(vec![resource("a", "3", 2)], &[]))
+		.unwrap();
+	assert_eq!(updates.take(), vec!["a"]);
+}
+
+#[test]
+fn test_rejected_versions() {
+	let (mut client, _ready) = client(None);
+	let updates = Updates::default();
+	let h = wrapper(&updates);
+	let state = &mut client.state;
+
+	let rejects = h
+		.handle(
+			state,
+			response(vec![resource("good", "1", 1), resource("bad", "1", 1)], &[]),
+		)
+		.unwrap_err();
+	assert_eq!(
+		rejects.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(),
+		vec!["bad"]
+	);
+	assert_eq!(updates.take(), vec!["good", "bad"]);
+	// The rejected resource is held without a version, so the server sends it again on reconnect
+	assert_eq!(
+		versions(state),
+		HashMap::from([
+			("good".to_string(), "1".to_string()),
+			("bad".to_string(), String::new()),
+		])
+	);
+
+	// And it is not skipped when it is
+	h.handle(state, response(vec![resource("bad", "1", 1)], &[]))
+		.unwrap_err();
+	assert_eq!(updates.take(), vec!["bad"]);
+}
+
+#[test]
+fn test_remove() {
+	let (mut client, _ready) = client(None);
+	let updates = Updates::default();
+	let h = wrapper(&updates);
+	let state = &mut client.state;
+
+	h.handle(state, response(vec![resource("a", "1", 1)], &[]))
+		.unwrap();
+	h.handle(state, response(vec![], &["a"])).unwrap();
+	assert_eq!(updates.take(), vec!["a", "-a"]);
+	assert!(versions(state).is_empty());
+
+	// Once removed, the same resource is no longer skipped
+	h.handle(state, response(vec![resource("a", "1", 1)], &[]))
+		.unwrap();
+	assert_eq!(updates.take(), vec!["a"]);
+}
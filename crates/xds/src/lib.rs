@@ -11,6 +11,7 @@ use self::service::discovery::v3::DeltaDiscoveryRequest;
 
 mod client;
 pub mod metrics;
+mod snapshot;
 mod types;
 
 #[derive(thiserror::Error, Debug)]
@@ -26,6 +27,9 @@ pub enum Error {
 	RequestFailure(#[from] Box<mpsc::error::SendError<DeltaDiscoveryRequest>>),
 	#[error("failed to send on demand resource")]
 	OnDemandSend(),
+	/// The primary control plane is reachable again, so the connection to a fallback is closed.
+	#[error("switching back to the primary control plane")]
+	Failback,
 }
 
 struct DisplayStatus<'a>(&'a tonic::Status);
@@ -0,0 +1,107 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use agent_core::strng::Strng;
+use prost::Message;
+use prost::bytes::Buf;
+
+use crate::ResourceKey;
+use crate::service::discovery::v3::{DeltaDiscoveryResponse, Resource as ProtoResource};
+
+#[cfg(test)]
+#[path = "snapshot_tests.rs"]
+mod tests;
+
+/// Snapshot keeps the last accepted version of every resource, so they can be written to disk and
+/// served on a later startup when no control plane is reachable.
+///
+/// The file holds one length-delimited DeltaDiscoveryResponse per type, as if the control plane had
+/// sent every resource in a single response.
+pub(crate) struct Snapshot {
+	path: PathBuf,
+	resources: HashMap<Strng, BTreeMap<Strng, ProtoResource>>,
+	dirty: bool,
+}
+
+impl Snapshot {
+	pub(crate) fn new(path: PathBuf) -> Self {
+		Self {
+			path,
+			resources: Default::default(),
+			dirty: false,
+		}
+	}
+
+	pub(crate) fn path(&self) -> &Path {
+		&self.path
+	}
+
+	pub(crate) fn insert(&mut self, type_url: Strng, resource: &ProtoResource) {
+		self
+			.resources
+			.entry(type_url)
+			.or_default()
+			.insert(resource.name.as_str().into(), resource.clone());
+		self.dirty = true;
+	}
+
+	pub(crate) fn remove(&mut self, key: &ResourceKey) {
+		if let Some(rs) = self.resources.get_mut(&key.type_url) {
+			self.dirty |= rs.remove(&key.name).is_some();
+		}
+	}
+
+	/// Read the snapshot on disk. Returns None if there is none.
+	pub(crate) fn load(&self) -> anyhow::Result<Option<Vec<DeltaDiscoveryResponse>>> {
+		let data = match std::fs::read(&self.path) {
+			Ok(d) => d,
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+			Err(e) => return Err(e.into()),
+		};
+		let mut buf = &data[..];
+		let mut responses = Vec::new();
+		while buf.has_remaining() {
+			responses.push(DeltaDiscoveryResponse::decode_length_delimited(&mut buf)?);
+		}
+		Ok(Some(responses))
+	}
+
+	/// Encode the snapshot, if it changed since it was last encoded.
+	pub(crate) fn encode(&mut self) -> Option<Vec<u8>> {
+		if !self.dirty {
+			return None;
+		}
+		self.dirty = false;
+		let mut buf = Vec::new();
+		for (type_url, resources) in &self.resources {
+			DeltaDiscoveryResponse {
+				type_url: type_url.to_string(),
+				resources: resources.values().cloned().collect(),
+				..Default::default()
+			}
+			.encode_length_delimited(&mut buf)
+			.expect("vec has sufficient capacity");
+		}
+		Some(buf)
+	}
+}
+
+/// Write the snapshot to `path`, replacing the previous one atomically so a crash while writing
+/// cannot leave a truncated snapshot. Resources may hold credentials, so the file is only readable
+/// by its owner.
+pub(crate) async fn write(path: PathBuf, data: Vec<u8>) -> std::io::Result<()> {
+	tokio::task::spawn_blocking(move || {
+		let tmp = path.with_extension("tmp");
+		let mut opts = std::fs::OpenOptions::new();
+		opts.write(true).create(true).truncate(true);
+		#[cfg(unix)]
+		std::os::unix::fs::OpenOptionsExt::mode(&mut opts, 0o600);
+		let mut f = opts.open(&tmp)?;
+		f.write_all(&data)?;
+		f.sync_all()?;
+		std::fs::rename(&tmp, &path)
+	})
+	.await
+	.map_err(std::io::Error::other)?
+}
@@ -0,0 +1,57 @@
+use prost_types::Any;
+
+use super::*;
+
+fn resource(name: &str, version: &str, value: &[u8]) -> ProtoResource {
+	ProtoResource {
+		name: name.to_string(),
+		version: version.to_string(),
+		resource: Some(Any {
+			type_url: "type.googleapis.com/test".to_string(),
+			value: value.to_vec(),
+		}),
+		..Default::default()
+	}
+}
+
+#[tokio::test]
+async fn test_round_trip() {
+	let dir = tempfile::tempdir().unwrap();
+	let path = dir.path().join("snapshot");
+	let mut snapshot = Snapshot::new(path.clone());
+	// Nothing written yet
+	assert!(snapshot.load().unwrap().is_none());
+	assert!(snapshot.encode().is_none());
+
+	let (a, b, c) = (
+		resource("a", "1", b"first"),
+		resource("b", "2", b"second"),
+		resource("c", "3", b"third"),
+	);
+	snapshot.insert("type/one".into(), &a);
+	snapshot.insert("type/one".into(), &b);
+	snapshot.insert("type/two".into(), &c);
+	snapshot.remove(&ResourceKey {
+		name: "b".into(),
+		type_url: "type/one".into(),
+	});
+	write(path, snapshot.encode().unwrap()).await.unwrap();
+	// Unchanged since it was encoded
+	assert!(snapshot.encode().is_none());
+
+	let mut loaded = snapshot.load().unwrap().unwrap();
+	loaded.sort_by(|x, y| x.type_url.cmp(&y.type_url));
+	assert_eq!(loaded.len(), 2);
+	assert_eq!(loaded[0].type_url, "type/one");
+	assert_eq!(loaded[0].resources, vec![a]);
+	assert_eq!(loaded[1].type_url, "type/two");
+	assert_eq!(loaded[1].resources, vec![c]);
+}
+
+#[test]
+fn test_load_corrupt() {
+	let dir = tempfile::tempdir().unwrap();
+	let path = dir.path().join("snapshot");
+	std::fs::write(&path, b"\xff\xff\xff").unwrap();
+	assert!(Snapshot::new(path).load().is_err());
+}
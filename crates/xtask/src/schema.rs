@@ -57,8 +57,5 @@ pub fn generate_schema() -> Result<()> {
 }
 
 pub fn make<T: JsonSchema>() -> anyhow::Result<String> {
-	let settings = schemars::generate::SchemaSettings::default().with(|s| s.inline_subschemas = true);
-	let gens = schemars::SchemaGenerator::new(settings);
-	let schema = gens.into_root_schema_for::<T>();
-	Ok(serde_json::to_string_pretty(&schema)?)
+	agentgateway::serdes::json_schema::<T>()
 }
@@ -27,6 +27,20 @@ export const MeshHUD: React.FC = () => {
 
     useEffect(() => {
         const sse = new EventSource("http://localhost:15000/mesh/events");
+        // Load the current nodes whenever the stream (re)connects, since events published while
+        // disconnected are not replayed.
+        sse.onopen = () => {
+            fetch("http://localhost:15000/mesh/nodes")
+                .then((res) => res.json())
+                .then((list: MeshHeartbeat[]) => {
+                    setNodes(Object.fromEntries(list.map((n) => [n.serviceName, n])));
+                })
+                .catch((e) => console.error("Failed to load mesh nodes", e));
+        };
+        // Sent before the stream is closed because we fell behind. The browser reconnects on its own.
+        sse.addEventListener("resync", () => {
+            addLog("Event stream fell behind, resyncing", "warn");
+        });
         sse.onmessage = (ev) => {
             try {
                 const event: MeshEvent = JSON.parse(ev.data);